@@ -0,0 +1,38 @@
+/*
+ * C interface to hal-simplicity, built with
+ *
+ *     cargo rustc --release --lib --features ffi --crate-type cdylib
+ *
+ * Each function takes its params as a JSON object, with the same fields as
+ * the daemon method of the same name, and returns either
+ * {"result": ...} or {"error": {"code": ..., "message": ..., "data": ...}}.
+ * Returned strings must be released with hal_simplicity_free.
+ */
+
+#ifndef HAL_SIMPLICITY_H
+#define HAL_SIMPLICITY_H
+
+#ifdef __cplusplus
+extern "C" {
+#endif
+
+/* simplicity_info: {"program", "witness"?, "state"?} */
+char *hal_simplicity_info(const char *params);
+
+/* simplicity_sighash: {"tx", "input_index", "cmr", "control_block"?,
+ * "genesis_hash"?, "secret_key"?, "public_key"?, "signature"?, "input_utxos"?} */
+char *hal_simplicity_sighash(const char *params);
+
+/* pset_finalize: {"pset", "input_index", "program", "witness", "genesis_hash"?} */
+char *hal_simplicity_pset_finalize(const char *params);
+
+/* pset_run: {"pset", "input_index", "program", "witness", "genesis_hash"?, "max_jets"?} */
+char *hal_simplicity_pset_run(const char *params);
+
+void hal_simplicity_free(char *s);
+
+#ifdef __cplusplus
+}
+#endif
+
+#endif /* HAL_SIMPLICITY_H */
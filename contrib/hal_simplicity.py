@@ -0,0 +1,54 @@
+"""Python bindings to the hal-simplicity C interface (see hal_simplicity.h).
+
+    lib = HalSimplicity("target/release/libhal_simplicity.so")
+    info = lib.info(program="...", witness="...")
+
+Each method takes the params of the daemon method of the same name as
+keyword arguments, and returns its result or raises HalSimplicityError.
+"""
+
+import ctypes
+import json
+
+
+class HalSimplicityError(Exception):
+    def __init__(self, code, message, data=None):
+        super().__init__(message)
+        self.code = code
+        self.data = data
+
+
+class HalSimplicity:
+    def __init__(self, path):
+        self._lib = ctypes.CDLL(path)
+        for name in ("info", "sighash", "pset_finalize", "pset_run"):
+            f = getattr(self._lib, "hal_simplicity_" + name)
+            f.argtypes = [ctypes.c_char_p]
+            # A void pointer, so that ctypes does not copy and lose the string.
+            f.restype = ctypes.c_void_p
+        self._lib.hal_simplicity_free.argtypes = [ctypes.c_void_p]
+        self._lib.hal_simplicity_free.restype = None
+
+    def _call(self, name, params):
+        f = getattr(self._lib, "hal_simplicity_" + name)
+        out = f(json.dumps(params).encode())
+        try:
+            response = json.loads(ctypes.string_at(out).decode())
+        finally:
+            self._lib.hal_simplicity_free(out)
+        if "error" in response:
+            error = response["error"]
+            raise HalSimplicityError(error["code"], error["message"], error.get("data"))
+        return response["result"]
+
+    def info(self, **params):
+        return self._call("info", params)
+
+    def sighash(self, **params):
+        return self._call("sighash", params)
+
+    def pset_finalize(self, **params):
+        return self._call("pset_finalize", params)
+
+    def pset_run(self, **params):
+        return self._call("pset_run", params)
@@ -1,7 +1,14 @@
-use elements::bitcoin::{secp256k1, PublicKey};
-use elements::{Address, Script};
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::sync::mpsc;
+use std::thread;
+use std::time::Duration;
 
-use crate::address::{AddressInfo, Addresses};
+use elements::bitcoin::amount::{Denomination, ParseAmountError};
+use elements::bitcoin::{secp256k1, Amount, PublicKey};
+use elements::{Address, AssetId, Script};
+use simplicity::hex::parse::FromHex as _;
+
+use crate::address::{AddressInfo, Addresses, PaymentUri, SimplicityCommitment, VanityAddress};
 use crate::Network;
 
 #[derive(Debug, thiserror::Error)]
@@ -29,8 +36,68 @@ pub enum AddressError {
 
 	#[error("addresses always have params")]
 	AddressesAlwaysHaveParams,
+
+	#[error("give either an address or a CMR, not both")]
+	AddressAndCmr,
+
+	#[error("invalid CMR: {0}")]
+	CmrParse(elements::hashes::hex::HexToArrayError),
+
+	#[error("invalid state commitment: {0}")]
+	StateParse(elements::hashes::hex::HexToArrayError),
+
+	#[error("invalid internal key: {0}")]
+	InternalKeyParse(secp256k1::Error),
+
+	#[error("an internal key or state must come with the CMR of the program they belong to")]
+	ProgramHintWithoutCmr,
+
+	#[error("invalid amount: {0}")]
+	AmountParse(ParseAmountError),
+
+	#[error("invalid asset ID: {0}")]
+	AssetIdParse(elements::hashes::hex::HexToArrayError),
+
+	#[error("an amount must come with the asset ID it is an amount of")]
+	AmountWithoutAsset,
+
+	#[error("unknown payment URI scheme {0} (expected liquidnetwork or liquidtestnet)")]
+	UriScheme(String),
+
+	#[error("a {scheme}: URI cannot pay the {} address {address}", network.as_str())]
+	UriNetworkMismatch {
+		scheme: String,
+		network: Network,
+		address: String,
+	},
+
+	#[error("invalid payment URI parameter: {0}")]
+	UriParam(String),
+
+	#[error("unsupported required payment URI parameter {0}")]
+	UriRequiredParam(String),
+
+	#[error("vanity prefix {0} is not bech32: it may only use the characters {BECH32_CHARSET}")]
+	VanityPrefix(String),
+
+	#[error(
+		"vanity prefix {prefix} is too long: a P2WPKH address has only {max} characters after its \
+		witness version"
+	)]
+	VanityPrefixLength {
+		prefix: String,
+		max: usize,
+	},
 }
 
+/// The characters of the data part of a bech32 address.
+const BECH32_CHARSET: &str = "qpzry9x8gf2tvdw0s3jn54khce6mua7l";
+/// The number of characters in the data part of a P2WPKH address after the
+/// witness version: 32 for the 20-byte program and 6 for the checksum.
+const VANITY_MAX_PREFIX: usize = 38;
+/// How often [`address_vanity`] reports its progress.
+const VANITY_PROGRESS_INTERVAL: Duration = Duration::from_secs(1);
+
 /// Create addresses from a public key or script.
 pub fn address_create(
 	pubkey_hex: Option<&str>,
@@ -59,9 +126,97 @@ pub fn address_create(
 	Ok(created)
 }
 
+/// Grind keypairs on `threads` threads until the unconfidential P2WPKH address
+/// of one has `prefix` right after its witness version, as in `ex1q<prefix>`.
+///
+/// Each character of the prefix makes the search 32 times longer. The number of
+/// keys tried so far is passed to `on_progress` every second.
+pub fn address_vanity(
+	prefix: &str,
+	network: Network,
+	threads: usize,
+	mut on_progress: impl FnMut(u64),
+) -> Result<VanityAddress, AddressError> {
+	let prefix = prefix.to_lowercase();
+	if !prefix.chars().all(|c| BECH32_CHARSET.contains(c)) {
+		return Err(AddressError::VanityPrefix(prefix));
+	}
+	if prefix.len() > VANITY_MAX_PREFIX {
+		return Err(AddressError::VanityPrefixLength {
+			prefix,
+			max: VANITY_MAX_PREFIX,
+		});
+	}
+
+	let params = network.address_params();
+	let found = AtomicBool::new(false);
+	let attempts = AtomicU64::new(0);
+	let (sender, receiver) = mpsc::channel();
+	let (secret, pubkey, address) = thread::scope(|scope| {
+		for _ in 0..threads.max(1) {
+			let (found, attempts, prefix, sender) = (&found, &attempts, &prefix, sender.clone());
+			scope.spawn(move || {
+				let mut rng = secp256k1::rand::thread_rng();
+				while !found.load(Ordering::Relaxed) {
+					let (secret, pubkey) = secp256k1::generate_keypair(&mut rng);
+					let pubkey = PublicKey::new(pubkey);
+					let address = Address::p2wpkh(&pubkey, None, params);
+					attempts.fetch_add(1, Ordering::Relaxed);
+					// The data part follows the last 1, starting with the witness version.
+					let address_str = address.to_string();
+					let (_, data) = address_str.rsplit_once('1').expect("bech32 separator");
+					if data[1..].starts_with(prefix.as_str())
+						&& !found.swap(true, Ordering::Relaxed)
+					{
+						let _ = sender.send((secret, pubkey, address));
+					}
+				}
+			});
+		}
+		drop(sender);
+		loop {
+			match receiver.recv_timeout(VANITY_PROGRESS_INTERVAL) {
+				Ok(found) => return found,
+				Err(mpsc::RecvTimeoutError::Timeout) => {
+					on_progress(attempts.load(Ordering::Relaxed))
+				}
+				Err(mpsc::RecvTimeoutError::Disconnected) => {
+					unreachable!("searchers run until one finds an address")
+				}
+			}
+		}
+	});
+
+	Ok(VanityAddress {
+		address,
+		secret,
+		pubkey,
+		attempts: attempts.load(Ordering::Relaxed),
+	})
+}
+
 /// Inspect an address and return detailed information.
 pub fn address_inspect(address_str: &str) -> Result<AddressInfo, AddressError> {
+	address_inspect_with_program(address_str, None, None, None)
+}
+
+/// Inspect an address, and if a CMR is given, check whether the address
+/// commits to the Simplicity program with that CMR, under `internal_key`
+/// (default: the unspendable key) and alongside `state`.
+pub fn address_inspect_with_program(
+	address_str: &str,
+	cmr: Option<&str>,
+	internal_key: Option<&str>,
+	state: Option<&str>,
+) -> Result<AddressInfo, AddressError> {
 	let address: Address = address_str.parse().map_err(AddressError::AddressParse)?;
+	let simplicity = match cmr {
+		Some(cmr) => Some(simplicity_commitment(&address, cmr, internal_key, state)?),
+		None if internal_key.is_some() || state.is_some() => {
+			return Err(AddressError::ProgramHintWithoutCmr)
+		}
+		None => None,
+	};
 	let script_pk = address.script_pubkey();
 
 	let mut info = AddressInfo {
@@ -89,6 +244,7 @@ pub fn address_inspect(address_str: &str) -> Result<AddressInfo, AddressError> {
 		} else {
 			None
 		},
+		simplicity,
 	};
 
 	use elements::address::Payload;
@@ -131,3 +287,202 @@ pub fn address_inspect(address_str: &str) -> Result<AddressInfo, AddressError> {
 
 	Ok(info)
 }
+
+/// Reconstruct the taproot output of a Simplicity program and compare it with
+/// the one `address` pays to.
+fn simplicity_commitment(
+	address: &Address,
+	cmr: &str,
+	internal_key: Option<&str>,
+	state: Option<&str>,
+) -> Result<SimplicityCommitment, AddressError> {
+	let cmr = cmr.parse().map_err(AddressError::CmrParse)?;
+	let internal_key = internal_key
+		.map(str::parse)
+		.transpose()
+		.map_err(AddressError::InternalKeyParse)?
+		.unwrap_or_else(crate::hal_simplicity::unspendable_internal_key);
+	let state_bytes =
+		state.map(<[u8; 32]>::from_hex).transpose().map_err(AddressError::StateParse)?;
+
+	let spend_info = crate::hal_simplicity::taproot_spend_info(internal_key, state_bytes, cmr);
+	let output_key = spend_info.output_key().into_inner();
+	let matches = match &address.payload {
+		elements::address::Payload::WitnessProgram {
+			version,
+			program,
+		} => version.to_u8() == 1 && program[..] == output_key.serialize(),
+		_ => false,
+	};
+	Ok(SimplicityCommitment {
+		cmr,
+		internal_key,
+		state: state_bytes.map(hex::encode),
+		merkle_root: spend_info.merkle_root().expect("the program is a leaf"),
+		output_key,
+		matches,
+	})
+}
+
+/// Percent-encode a URI parameter value, keeping only unreserved characters.
+fn percent_encode(value: &str) -> String {
+	let mut encoded = String::new();
+	for byte in value.bytes() {
+		if byte.is_ascii_alphanumeric() || b"-._~".contains(&byte) {
+			encoded.push(byte as char);
+		} else {
+			encoded.push_str(&format!("%{:02X}", byte));
+		}
+	}
+	encoded
+}
+
+fn percent_decode(value: &str) -> Result<String, AddressError> {
+	let invalid = || AddressError::UriParam(format!("bad percent-encoding in {}", value));
+	let mut bytes = vec![];
+	let mut rest = value.as_bytes();
+	while let Some((&byte, tail)) = rest.split_first() {
+		if byte == b'%' {
+			let hex =
+				tail.get(..2).and_then(|hex| std::str::from_utf8(hex).ok()).ok_or_else(invalid)?;
+			bytes.push(u8::from_str_radix(hex, 16).map_err(|_| invalid())?);
+			rest = &tail[2..];
+		} else {
+			bytes.push(byte);
+			rest = tail;
+		}
+	}
+	String::from_utf8(bytes).map_err(|_| invalid())
+}
+
+/// Create a payment URI for an address, or for the Simplicity program with
+/// the given CMR and state.
+#[allow(clippy::too_many_arguments)]
+pub fn address_uri_encode(
+	address: Option<&str>,
+	cmr: Option<&str>,
+	state: Option<&str>,
+	amount: Option<&str>,
+	assetid: Option<&str>,
+	label: Option<&str>,
+	message: Option<&str>,
+	network: Network,
+) -> Result<PaymentUri, AddressError> {
+	let address = match (address, cmr) {
+		(Some(_), Some(_)) => return Err(AddressError::AddressAndCmr),
+		(Some(address), None) => address.parse().map_err(AddressError::AddressParse)?,
+		(None, Some(cmr)) => {
+			let cmr = cmr.parse().map_err(AddressError::CmrParse)?;
+			let state =
+				state.map(<[u8; 32]>::from_hex).transpose().map_err(AddressError::StateParse)?;
+			crate::hal_simplicity::elements_address(cmr, state, network.address_params())
+		}
+		(None, None) => return Err(AddressError::NoAddressProvided),
+	};
+	let network =
+		Network::from_params(address.params).ok_or(AddressError::AddressesAlwaysHaveParams)?;
+	let amount = amount
+		.map(|amount| Amount::from_str_in(amount, Denomination::Bitcoin))
+		.transpose()
+		.map_err(AddressError::AmountParse)?;
+	let assetid =
+		assetid.map(str::parse::<AssetId>).transpose().map_err(AddressError::AssetIdParse)?;
+	if amount.is_some() && assetid.is_none() {
+		return Err(AddressError::AmountWithoutAsset);
+	}
+
+	let mut params = vec![];
+	if let Some(amount) = amount {
+		params.push(format!("amount={}", amount.to_string_in(Denomination::Bitcoin)));
+	}
+	if let Some(assetid) = assetid {
+		params.push(format!("assetid={}", assetid));
+	}
+	if let Some(label) = label {
+		params.push(format!("label={}", percent_encode(label)));
+	}
+	if let Some(message) = message {
+		params.push(format!("message={}", percent_encode(message)));
+	}
+	let mut uri = format!("{}:{}", network.uri_scheme(), address);
+	if !params.is_empty() {
+		uri.push('?');
+		uri.push_str(&params.join("&"));
+	}
+
+	Ok(PaymentUri {
+		uri,
+		network,
+		address,
+		amount,
+		assetid,
+		label: label.map(str::to_owned),
+		message: message.map(str::to_owned),
+	})
+}
+
+/// Parse a payment URI into its address and parameters.
+pub fn address_uri_decode(uri: &str) -> Result<PaymentUri, AddressError> {
+	let (scheme, rest) =
+		uri.split_once(':').ok_or_else(|| AddressError::UriScheme(uri.to_owned()))?;
+	let scheme = scheme.to_ascii_lowercase();
+	if scheme != "liquidnetwork" && scheme != "liquidtestnet" {
+		return Err(AddressError::UriScheme(scheme));
+	}
+	let (address, query) = rest.split_once('?').unwrap_or((rest, ""));
+	let address: Address = address.parse().map_err(AddressError::AddressParse)?;
+	let network =
+		Network::from_params(address.params).ok_or(AddressError::AddressesAlwaysHaveParams)?;
+	if (network == Network::LiquidTestnet) != (scheme == "liquidtestnet") {
+		return Err(AddressError::UriNetworkMismatch {
+			scheme,
+			network,
+			address: address.to_string(),
+		});
+	}
+
+	let mut payment = PaymentUri {
+		uri: uri.to_owned(),
+		network,
+		address,
+		amount: None,
+		assetid: None,
+		label: None,
+		message: None,
+	};
+	for param in query.split('&').filter(|param| !param.is_empty()) {
+		let (key, value) = param
+			.split_once('=')
+			.ok_or_else(|| AddressError::UriParam(format!("{} has no value", param)))?;
+		let duplicate = || AddressError::UriParam(format!("{} is given twice", key));
+		match key {
+			"amount" => {
+				let amount = Amount::from_str_in(value, Denomination::Bitcoin)
+					.map_err(AddressError::AmountParse)?;
+				payment.amount.replace(amount).map_or(Ok(()), |_| Err(duplicate()))?;
+			}
+			"assetid" => {
+				let assetid = value.parse().map_err(AddressError::AssetIdParse)?;
+				payment.assetid.replace(assetid).map_or(Ok(()), |_| Err(duplicate()))?;
+			}
+			"label" => {
+				let label = percent_decode(value)?;
+				payment.label.replace(label).map_or(Ok(()), |_| Err(duplicate()))?;
+			}
+			"message" => {
+				let message = percent_decode(value)?;
+				payment.message.replace(message).map_or(Ok(()), |_| Err(duplicate()))?;
+			}
+			// Parameters a wallet must understand to pay, per BIP-0021.
+			key if key.starts_with("req-") => {
+				return Err(AddressError::UriRequiredParam(key.to_owned()))
+			}
+			_ => {}
+		}
+	}
+	if payment.amount.is_some() && payment.assetid.is_none() {
+		return Err(AddressError::AmountWithoutAsset);
+	}
+
+	Ok(payment)
+}
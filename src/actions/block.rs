@@ -1,16 +1,118 @@
-use elements::encode::deserialize;
-use elements::{dynafed, Block, BlockExtData, BlockHeader};
+use std::collections::{BTreeMap, BTreeSet};
+use std::io;
+
+use elements::bitcoin::secp256k1;
+use elements::encode::{deserialize, Decodable as _, VarInt};
+use elements::hashes::{sha256d, Hash as _, HashEngine as _};
+use elements::opcodes::all::{OP_CHECKMULTISIG, OP_PUSHNUM_1, OP_PUSHNUM_16};
+use elements::script::Instruction;
+use elements::{
+	dynafed, Block, BlockExtData, BlockHash, BlockHeader, Script, Transaction, TxMerkleNode,
+};
+use simplicity::dag::{DagLike as _, InternalSharing};
+use simplicity::jet;
+use simplicity::node::Inner;
 
 use crate::block::{BlockHeaderInfo, BlockInfo, ParamsInfo, ParamsType};
-use crate::Network;
+use crate::hal_simplicity::Program;
+use crate::{HexBytes, HexReader, Network};
 
-#[derive(Debug, serde::Serialize)]
+#[derive(Debug, serde::Serialize, serde::Deserialize)]
+#[cfg_attr(feature = "daemon", derive(schemars::JsonSchema))]
 #[serde(untagged)]
 pub enum BlockDecodeOutput {
 	Info(BlockInfo),
 	Header(BlockHeaderInfo),
 }
 
+/// Statistics about the Simplicity spends in a block.
+#[derive(Debug, serde::Serialize, serde::Deserialize)]
+#[cfg_attr(feature = "daemon", derive(schemars::JsonSchema))]
+pub struct BlockStats {
+	#[cfg_attr(feature = "daemon", schemars(with = "String"))]
+	pub block_hash: BlockHash,
+	pub transactions: usize,
+	/// Number of transactions with at least one Simplicity spend.
+	pub simplicity_transactions: usize,
+	/// Number of inputs spending a Simplicity tapleaf.
+	pub simplicity_spends: usize,
+	/// Number of Simplicity spends whose program or witness could not be decoded.
+	pub undecodable_spends: usize,
+	pub total_program_bytes: usize,
+	pub total_witness_bytes: usize,
+	pub max_program_bytes: usize,
+	pub max_witness_bytes: usize,
+	/// For each jet, the number of times it appears across all decoded programs.
+	pub jets: BTreeMap<String, usize>,
+	#[cfg_attr(feature = "daemon", schemars(with = "std::collections::BTreeSet<String>"))]
+	pub cmrs: BTreeSet<simplicity::Cmr>,
+}
+
+/// The result of checking a block's signblock witness (dynafed) or solution (legacy).
+#[derive(Debug, serde::Serialize, serde::Deserialize)]
+#[cfg_attr(feature = "daemon", derive(schemars::JsonSchema))]
+pub struct SignblockVerification {
+	#[cfg_attr(feature = "daemon", schemars(with = "String"))]
+	pub block_hash: BlockHash,
+	pub dynafed: bool,
+	/// The multisig script whose signatures were checked. For dynafed blocks this is
+	/// the witness script, which should hash to the P2WSH signblockscript.
+	#[cfg_attr(feature = "daemon", schemars(with = "String"))]
+	pub multisig_script: HexBytes,
+	pub threshold: usize,
+	#[cfg_attr(feature = "daemon", schemars(with = "Vec<String>"))]
+	pub pubkeys: Vec<HexBytes>,
+	pub signatures: usize,
+	pub valid_signatures: usize,
+	/// Dynafed only: whether the witness script matches the signblockscript.
+	#[serde(skip_serializing_if = "Option::is_none")]
+	pub witness_script_matches: Option<bool>,
+	/// Dynafed only: the serialized size of the signblock witness.
+	#[serde(skip_serializing_if = "Option::is_none")]
+	pub witness_size: Option<usize>,
+	/// Dynafed only: the maximum signblock witness size allowed by the current params.
+	#[serde(skip_serializing_if = "Option::is_none")]
+	pub witness_limit: Option<u32>,
+	pub valid: bool,
+}
+
+/// A header of a chain checked by [`block_verify_chain`].
+#[derive(Debug, serde::Serialize, serde::Deserialize)]
+#[cfg_attr(feature = "daemon", derive(schemars::JsonSchema))]
+pub struct ChainHeader {
+	/// The position of the header in the given sequence.
+	pub index: usize,
+	pub height: u32,
+	#[cfg_attr(feature = "daemon", schemars(with = "String"))]
+	pub block_hash: BlockHash,
+}
+
+/// The first header of a chain which does not follow from the ones before it.
+#[derive(Debug, serde::Serialize, serde::Deserialize)]
+#[cfg_attr(feature = "daemon", derive(schemars::JsonSchema))]
+pub struct InvalidChainHeader {
+	#[serde(flatten)]
+	pub header: ChainHeader,
+	pub reason: String,
+}
+
+/// The result of checking that a sequence of block headers forms a chain.
+#[derive(Debug, serde::Serialize, serde::Deserialize)]
+#[cfg_attr(feature = "daemon", derive(schemars::JsonSchema))]
+pub struct ChainVerification {
+	/// The number of headers given.
+	pub headers: usize,
+	/// The last header up to which the chain is valid.
+	#[serde(skip_serializing_if = "Option::is_none")]
+	pub tip: Option<ChainHeader>,
+	/// The headers at which the dynafed params in force changed, including the
+	/// activation of dynafed.
+	pub params_transitions: Vec<ChainHeader>,
+	#[serde(skip_serializing_if = "Option::is_none")]
+	pub first_invalid: Option<InvalidChainHeader>,
+	pub valid: bool,
+}
+
 #[derive(Debug, thiserror::Error)]
 pub enum BlockError {
 	#[error("can't provide transactions both in JSON and raw.")]
@@ -34,6 +136,26 @@ pub enum BlockError {
 	#[error("invalid json JSON input: {0}")]
 	InvalidJsonInput(serde_json::Error),
 
+	#[error(
+		"merkle root {given} does not match the transactions, which have merkle root {computed}"
+	)]
+	MerkleRootMismatch {
+		given: TxMerkleNode,
+		computed: TxMerkleNode,
+	},
+
+	#[error("unsupported signblock script {0}: only (P2WSH-wrapped) multisig is supported")]
+	UnsupportedSignblockScript(Script),
+
+	#[error("malformed signblock {0}")]
+	MalformedSignblock(&'static str),
+
+	#[error("invalid block header {index}: {error}")]
+	HeaderDeserialize {
+		index: usize,
+		error: elements::encode::Error,
+	},
+
 	#[error("{field} missing in {context}")]
 	MissingField {
 		field: String,
@@ -101,11 +223,37 @@ fn create_params(info: ParamsInfo) -> Result<dynafed::Params, BlockError> {
 	}
 }
 
+/// Compute the merkle root of a block's transactions, as committed to in its header.
+fn merkle_root(txdata: &[Transaction]) -> TxMerkleNode {
+	let mut hashes: Vec<sha256d::Hash> = txdata.iter().map(|tx| tx.txid().to_raw_hash()).collect();
+	if hashes.is_empty() {
+		return TxMerkleNode::all_zeros();
+	}
+	while hashes.len() > 1 {
+		if hashes.len() % 2 == 1 {
+			hashes.push(hashes[hashes.len() - 1]);
+		}
+		hashes = hashes
+			.chunks(2)
+			.map(|pair| {
+				let mut engine = sha256d::Hash::engine();
+				engine.input(pair[0].as_byte_array());
+				engine.input(pair[1].as_byte_array());
+				sha256d::Hash::from_engine(engine)
+			})
+			.collect();
+	}
+	TxMerkleNode::from_raw_hash(hashes[0])
+}
+
+/// Create a block header from header info.
+///
+/// If no merkle root is given, it is set to all-zeros for the caller to fill in.
 fn create_block_header(info: BlockHeaderInfo) -> Result<BlockHeader, BlockError> {
 	Ok(BlockHeader {
 		version: info.version,
 		prev_blockhash: info.previous_block_hash,
-		merkle_root: info.merkle_root,
+		merkle_root: info.merkle_root.unwrap_or_else(TxMerkleNode::all_zeros),
 		time: info.time,
 		height: info.height,
 		ext: if info.dynafed {
@@ -156,8 +304,11 @@ fn create_block_header(info: BlockHeaderInfo) -> Result<BlockHeader, BlockError>
 }
 
 /// Create a block from block info.
+///
+/// If the header has no merkle root, it is computed from the transactions.
 pub fn block_create(info: BlockInfo) -> Result<Block, BlockError> {
-	let header = create_block_header(info.header)?;
+	let given_merkle_root = info.header.merkle_root;
+	let mut header = create_block_header(info.header)?;
 	let txdata = match (info.transactions, info.raw_transactions) {
 		(Some(_), Some(_)) => return Err(BlockError::ConflictingTransactions),
 		(None, None) => return Err(BlockError::NoTransactions),
@@ -171,6 +322,15 @@ pub fn block_create(info: BlockInfo) -> Result<Block, BlockError> {
 			.map(|r| deserialize(&r.0).map_err(BlockError::InvalidRawTransaction))
 			.collect::<Result<Vec<_>, _>>()?,
 	};
+	header.merkle_root = merkle_root(&txdata);
+	if let Some(given) = given_merkle_root {
+		if given != header.merkle_root {
+			return Err(BlockError::MerkleRootMismatch {
+				given,
+				computed: header.merkle_root,
+			});
+		}
+	}
 	Ok(Block {
 		header,
 		txdata,
@@ -178,33 +338,449 @@ pub fn block_create(info: BlockInfo) -> Result<Block, BlockError> {
 }
 
 /// Decode a raw block and return block info or header info.
+///
+/// If neither `txids` nor `full` is set, only the header is decoded. Otherwise the
+/// whole block is decoded and its transaction IDs and/or fully-decoded transactions
+/// are included in the output.
 pub fn block_decode(
 	raw_block_hex: &str,
 	network: Network,
-	txids_only: bool,
+	txids: bool,
+	full: bool,
+) -> Result<BlockDecodeOutput, BlockError> {
+	block_decode_reader(raw_block_hex.as_bytes(), network, txids, full)
+}
+
+/// Map an error decoding hex read through a [`HexReader`] to the hex error.
+fn hex_read_error(e: elements::encode::Error) -> BlockError {
+	match e {
+		elements::encode::Error::Io(ref io) => {
+			match io.get_ref().and_then(|e| e.downcast_ref::<hex::FromHexError>()) {
+				Some(hex) => BlockError::CouldNotDecodeRawBlockHex(*hex),
+				None => BlockError::BlockDeserialize(e),
+			}
+		}
+		e => BlockError::BlockDeserialize(e),
+	}
+}
+
+/// Decode a hex-encoded raw block as it is read from `reader`, like [`block_decode`].
+///
+/// The transactions are decoded one at a time, so that neither the hex nor the
+/// raw block is ever held in memory in full.
+pub fn block_decode_reader<R: io::BufRead>(
+	reader: R,
+	network: Network,
+	txids: bool,
+	full: bool,
 ) -> Result<BlockDecodeOutput, BlockError> {
 	use crate::GetInfo;
 
-	let raw_block = hex::decode(raw_block_hex).map_err(BlockError::CouldNotDecodeRawBlockHex)?;
+	let mut reader = HexReader::new(reader);
+	let at_end = |reader: &mut HexReader<R>| {
+		reader.at_end().map_err(|e| hex_read_error(elements::encode::Error::Io(e)))
+	};
+	let header = BlockHeader::consensus_decode(&mut reader).map_err(hex_read_error)?;
 
-	if txids_only {
-		let block: Block = deserialize(&raw_block).map_err(BlockError::BlockDeserialize)?;
-		let info = BlockInfo {
-			header: block.header.get_info(network),
-			txids: Some(block.txdata.iter().map(|t| t.txid()).collect()),
-			transactions: None,
+	// A lone header is only accepted when no transactions are asked for, but the
+	// transactions of a whole block are always checked to decode.
+	let mut transactions = Vec::new();
+	let mut block_txids = Vec::new();
+	if txids || full || !at_end(&mut reader)? {
+		let count = VarInt::consensus_decode(&mut reader).map_err(hex_read_error)?.0;
+		for _ in 0..count {
+			let tx = Transaction::consensus_decode(&mut reader).map_err(hex_read_error)?;
+			if txids {
+				block_txids.push(tx.txid());
+			}
+			if full {
+				transactions.push(tx.get_info(network));
+			}
+		}
+		if !at_end(&mut reader)? {
+			return Err(BlockError::BlockDeserialize(elements::encode::Error::ParseFailed(
+				"data not consumed entirely when explicitly deserializing",
+			)));
+		}
+	}
+
+	if txids || full {
+		Ok(BlockDecodeOutput::Info(BlockInfo {
+			header: header.get_info(network),
+			transactions: full.then_some(transactions),
+			txids: txids.then_some(block_txids),
 			raw_transactions: None,
-		};
-		Ok(BlockDecodeOutput::Info(info))
+		}))
 	} else {
-		let header: BlockHeader = match deserialize(&raw_block) {
-			Ok(header) => header,
-			Err(_) => {
-				let block: Block = deserialize(&raw_block).map_err(BlockError::BlockDeserialize)?;
-				block.header
+		Ok(BlockDecodeOutput::Header(header.get_info(network)))
+	}
+}
+
+/// If the given input witness is a Simplicity script-path spend, return its
+/// encoded program and witness.
+///
+/// Such a witness stack looks like `[witness, program, cmr, control block]`,
+/// optionally followed by an annex.
+fn simplicity_spend(witness: &[Vec<u8>]) -> Option<(&[u8], &[u8])> {
+	let stack = match witness {
+		[rest @ .., annex] if rest.len() >= 2 && annex.first() == Some(&0x50) => rest,
+		stack => stack,
+	};
+	match stack {
+		[.., wit, prog, _cmr, control_block]
+			if control_block.first().map(|b| b & 0xfe)
+				== Some(simplicity::leaf_version().as_u8()) =>
+		{
+			Some((prog, wit))
+		}
+		_ => None,
+	}
+}
+
+/// Scan a raw block and collect statistics about its Simplicity spends.
+pub fn block_stats(raw_block_hex: &str) -> Result<BlockStats, BlockError> {
+	let raw_block = hex::decode(raw_block_hex).map_err(BlockError::CouldNotDecodeRawBlockHex)?;
+	let block: Block = deserialize(&raw_block).map_err(BlockError::BlockDeserialize)?;
+
+	let mut stats = BlockStats {
+		block_hash: block.block_hash(),
+		transactions: block.txdata.len(),
+		simplicity_transactions: 0,
+		simplicity_spends: 0,
+		undecodable_spends: 0,
+		total_program_bytes: 0,
+		total_witness_bytes: 0,
+		max_program_bytes: 0,
+		max_witness_bytes: 0,
+		jets: BTreeMap::new(),
+		cmrs: BTreeSet::new(),
+	};
+
+	for tx in &block.txdata {
+		let mut has_simplicity = false;
+		for input in &tx.input {
+			let (prog, wit) = match simplicity_spend(&input.witness.script_witness) {
+				Some(spend) => spend,
+				None => continue,
+			};
+			has_simplicity = true;
+			stats.simplicity_spends += 1;
+			stats.total_program_bytes += prog.len();
+			stats.total_witness_bytes += wit.len();
+			stats.max_program_bytes = stats.max_program_bytes.max(prog.len());
+			stats.max_witness_bytes = stats.max_witness_bytes.max(wit.len());
+
+			let program = match Program::<jet::Elements>::from_bytes(prog, Some(wit)) {
+				Ok(program) => program,
+				Err(_) => {
+					stats.undecodable_spends += 1;
+					continue;
+				}
+			};
+			stats.cmrs.insert(program.cmr());
+			let redeem = program.redeem_node().expect("parsed with witness");
+			for data in (&**redeem).post_order_iter::<InternalSharing>() {
+				if let Inner::Jet(jet) = data.node.inner() {
+					*stats.jets.entry(jet.to_string()).or_insert(0) += 1;
+				}
+			}
+		}
+		if has_simplicity {
+			stats.simplicity_transactions += 1;
+		}
+	}
+
+	Ok(stats)
+}
+
+/// Parse an `m <pubkey>... n OP_CHECKMULTISIG` script into its threshold and keys.
+fn parse_multisig(script: &Script) -> Option<(usize, Vec<&[u8]>)> {
+	let pushnum = |ins: &Instruction| match ins {
+		Instruction::Op(op)
+			if (OP_PUSHNUM_1.into_u8()..=OP_PUSHNUM_16.into_u8()).contains(&op.into_u8()) =>
+		{
+			Some(usize::from(op.into_u8() - OP_PUSHNUM_1.into_u8() + 1))
+		}
+		_ => None,
+	};
+
+	let instructions = script.instructions().collect::<Result<Vec<_>, _>>().ok()?;
+	match &instructions[..] {
+		[m, keys @ .., n, Instruction::Op(op)] if *op == OP_CHECKMULTISIG => {
+			let (m, n) = (pushnum(m)?, pushnum(n)?);
+			let keys = keys
+				.iter()
+				.map(|ins| match ins {
+					Instruction::PushBytes(key) => Some(*key),
+					Instruction::Op(_) => None,
+				})
+				.collect::<Option<Vec<_>>>()?;
+			if keys.len() != n || m > n {
+				return None;
+			}
+			Some((m, keys))
+		}
+		_ => None,
+	}
+}
+
+/// Count the signatures which are valid, in order, for the given keys.
+///
+/// Like Elements' block signature checker, signatures commit directly to the
+/// block hash and carry a trailing sighash byte which is ignored.
+fn count_valid_signatures(block_hash: BlockHash, sigs: &[&[u8]], keys: &[&[u8]]) -> usize {
+	let msg = secp256k1::Message::from_digest(block_hash.to_byte_array());
+	let mut keys = keys.iter();
+	let mut valid = 0;
+	for sig in sigs {
+		let sig =
+			match sig.split_last().map(|(_, sig)| secp256k1::ecdsa::Signature::from_der_lax(sig)) {
+				Some(Ok(mut sig)) => {
+					sig.normalize_s();
+					sig
+				}
+				_ => continue,
+			};
+		// Unlike OP_CHECKMULTISIG, an invalid signature does not use up the
+		// remaining keys, so that the count is meaningful for partially-signed blocks.
+		let mut remaining = keys.clone();
+		for key in remaining.by_ref() {
+			let verified = secp256k1::PublicKey::from_slice(key)
+				.map(|key| secp256k1::SECP256K1.verify_ecdsa(&msg, &sig, &key).is_ok())
+				.unwrap_or(false);
+			if verified {
+				valid += 1;
+				keys = remaining;
+				break;
+			}
+		}
+	}
+	valid
+}
+
+/// Check the signblock witness (or, for pre-dynafed blocks, the solution) of a raw
+/// block or block header against its signblockscript (or challenge).
+pub fn block_verify_signblock(raw_block_hex: &str) -> Result<SignblockVerification, BlockError> {
+	let raw_block = hex::decode(raw_block_hex).map_err(BlockError::CouldNotDecodeRawBlockHex)?;
+	let header: BlockHeader = match deserialize(&raw_block) {
+		Ok(header) => header,
+		Err(_) => {
+			let block: Block = deserialize(&raw_block).map_err(BlockError::BlockDeserialize)?;
+			block.header
+		}
+	};
+	verify_signblock(&header)
+}
+
+/// Check the signblock witness or solution of a block header.
+fn verify_signblock(header: &BlockHeader) -> Result<SignblockVerification, BlockError> {
+	let block_hash = header.block_hash();
+
+	match header.ext {
+		BlockExtData::Proof {
+			ref challenge,
+			ref solution,
+		} => {
+			let (threshold, keys) = parse_multisig(challenge)
+				.ok_or_else(|| BlockError::UnsupportedSignblockScript(challenge.clone()))?;
+			let pushes = solution
+				.instructions()
+				.map(|ins| match ins {
+					Ok(Instruction::PushBytes(push)) => Ok(push),
+					_ => Err(BlockError::MalformedSignblock("solution is not push-only")),
+				})
+				.collect::<Result<Vec<_>, _>>()?;
+			// The first push is the dummy element consumed by OP_CHECKMULTISIG.
+			let sigs = pushes.get(1..).unwrap_or_default();
+			let valid_signatures = count_valid_signatures(block_hash, sigs, &keys);
+			Ok(SignblockVerification {
+				block_hash,
+				dynafed: false,
+				multisig_script: challenge.to_bytes().into(),
+				threshold,
+				pubkeys: keys.iter().map(|k| k[..].into()).collect(),
+				signatures: sigs.len(),
+				valid_signatures,
+				witness_script_matches: None,
+				witness_size: None,
+				witness_limit: None,
+				valid: sigs.len() == threshold && valid_signatures == threshold,
+			})
+		}
+		BlockExtData::Dynafed {
+			ref current,
+			ref signblock_witness,
+			..
+		} => {
+			let signblockscript = current
+				.signblockscript()
+				.ok_or(BlockError::MalformedSignblock("current params are null"))?;
+			if !signblockscript.is_v0_p2wsh() {
+				return Err(BlockError::UnsupportedSignblockScript(signblockscript.clone()));
+			}
+			let (witness_script, sigs) = match &signblock_witness[..] {
+				[_dummy, sigs @ .., witness_script] => (Script::from(witness_script.clone()), sigs),
+				_ => return Err(BlockError::MalformedSignblock("witness is too short")),
+			};
+			let (threshold, keys) = parse_multisig(&witness_script)
+				.ok_or_else(|| BlockError::UnsupportedSignblockScript(witness_script.clone()))?;
+			let sigs = sigs.iter().map(|sig| &sig[..]).collect::<Vec<_>>();
+			let valid_signatures = count_valid_signatures(block_hash, &sigs, &keys);
+
+			let witness_script_matches =
+				Script::new_v0_wsh(&witness_script.wscript_hash()) == *signblockscript;
+			let witness_size = elements::encode::serialize(signblock_witness).len();
+			let witness_limit = current.signblock_witness_limit().unwrap_or(0);
+			Ok(SignblockVerification {
+				block_hash,
+				dynafed: true,
+				multisig_script: witness_script.to_bytes().into(),
+				threshold,
+				pubkeys: keys.iter().map(|k| k[..].into()).collect(),
+				signatures: sigs.len(),
+				valid_signatures,
+				witness_script_matches: Some(witness_script_matches),
+				witness_size: Some(witness_size),
+				witness_limit: Some(witness_limit),
+				valid: witness_script_matches
+					&& witness_size <= witness_limit as usize
+					&& sigs.len() == threshold
+					&& valid_signatures == threshold,
+			})
+		}
+	}
+}
+
+/// Why `header` does not follow `prev`, if it does not.
+///
+/// `proposed` holds the roots of the dynafed params proposed by the headers
+/// before `header`, which may come into force at an epoch boundary.
+fn chain_link_error(
+	prev: &BlockHeader,
+	header: &BlockHeader,
+	proposed: &BTreeSet<[u8; 32]>,
+) -> Option<String> {
+	if header.prev_blockhash != prev.block_hash() {
+		return Some(format!(
+			"previous block hash {} is not the hash {} of the header before it",
+			header.prev_blockhash,
+			prev.block_hash(),
+		));
+	}
+	if Some(header.height) != prev.height.checked_add(1) {
+		return Some(format!(
+			"height {} does not follow height {} of the header before it",
+			header.height, prev.height,
+		));
+	}
+	match (&prev.ext, &header.ext) {
+		(
+			BlockExtData::Dynafed {
+				current: prev_current,
+				..
+			},
+			BlockExtData::Dynafed {
+				current,
+				..
+			},
+		) => {
+			let root = current.calculate_root().to_byte_array();
+			if root != prev_current.calculate_root().to_byte_array() && !proposed.contains(&root) {
+				return Some("changes to dynafed params which no earlier header proposed".into());
+			}
+		}
+		(
+			BlockExtData::Dynafed {
+				..
+			},
+			BlockExtData::Proof {
+				..
+			},
+		) => return Some("goes back to a pre-dynafed header after dynafed activated".into()),
+		_ => {}
+	}
+	None
+}
+
+/// Check that a sequence of raw block headers forms a chain: each must commit
+/// to the hash of the one before it, be one higher, and carry a valid
+/// signblock witness (or solution). Dynafed params may only change to ones
+/// proposed by an earlier header of the sequence, so a sequence crossing a
+/// params transition should start before the epoch in which they were voted.
+///
+/// Checking stops at the first header which is invalid.
+pub fn block_verify_chain(raw_headers_hex: &[&str]) -> Result<ChainVerification, BlockError> {
+	let headers = raw_headers_hex
+		.iter()
+		.enumerate()
+		.map(|(index, hex)| {
+			let raw = hex::decode(hex).map_err(BlockError::CouldNotDecodeRawBlockHex)?;
+			deserialize::<BlockHeader>(&raw).map_err(|error| BlockError::HeaderDeserialize {
+				index,
+				error,
+			})
+		})
+		.collect::<Result<Vec<_>, _>>()?;
+
+	let chain_header = |index: usize, header: &BlockHeader| ChainHeader {
+		index,
+		height: header.height,
+		block_hash: header.block_hash(),
+	};
+	let mut verification = ChainVerification {
+		headers: headers.len(),
+		tip: None,
+		params_transitions: vec![],
+		first_invalid: None,
+		valid: true,
+	};
+	let mut proposed = BTreeSet::new();
+	for (index, header) in headers.iter().enumerate() {
+		let prev = index.checked_sub(1).map(|i| &headers[i]);
+		let reason =
+			prev.and_then(|prev| chain_link_error(prev, header, &proposed)).or_else(|| {
+				match verify_signblock(header) {
+					Ok(signblock) if signblock.valid => None,
+					Ok(signblock) => Some(format!(
+						"signblock has {} valid signatures of {} needed",
+						signblock.valid_signatures, signblock.threshold,
+					)),
+					Err(e) => Some(e.to_string()),
+				}
+			});
+		if let Some(reason) = reason {
+			verification.first_invalid = Some(InvalidChainHeader {
+				header: chain_header(index, header),
+				reason,
+			});
+			verification.valid = false;
+			break;
+		}
+
+		if let BlockExtData::Dynafed {
+			ref current,
+			proposed: ref proposal,
+			..
+		} = header.ext
+		{
+			let changed = match prev.map(|prev| &prev.ext) {
+				Some(BlockExtData::Dynafed {
+					current: prev_current,
+					..
+				}) => prev_current.calculate_root() != current.calculate_root(),
+				Some(BlockExtData::Proof {
+					..
+				}) => true,
+				None => false,
+			};
+			if changed {
+				verification.params_transitions.push(chain_header(index, header));
+			}
+			if !proposal.is_null() {
+				proposed.insert(proposal.calculate_root().to_byte_array());
 			}
-		};
-		let info = header.get_info(network);
-		Ok(BlockDecodeOutput::Header(info))
+		}
+		verification.tip = Some(chain_header(index, header));
 	}
+	Ok(verification)
 }
@@ -0,0 +1,699 @@
+//! Error codes for failed actions
+//!
+//! Each subsystem has a range of 100 error codes, and the offset within the
+//! range gives the kind of failure, so that callers can branch on either. For
+//! example, a PSET input without a `witness_utxo` fails with code 1401
+//! (`pset` + `missing`). The daemon reports these codes as JSON-RPC error
+//! codes, and the command line with `--json-errors`.
+//!
+//! | Subsystem    | Codes     |   | Offset | Failure        |
+//! |--------------|-----------|---|--------|----------------|
+//! | `address`    | 1000-1099 |   | 0      | `invalid`      |
+//! | `block`      | 1100-1199 |   | 1      | `missing`      |
+//! | `tx`         | 1200-1299 |   | 2      | `conflict`     |
+//! | `simplicity` | 1300-1399 |   | 3      | `out_of_range` |
+//! | `pset`       | 1400-1499 |   | 4      | `mismatch`     |
+//! | `backend`    | 1500-1599 |   | 5      | `not_found`    |
+//! |              |           |   | 6      | `unsupported`  |
+//! |              |           |   | 7      | `execution`    |
+//! |              |           |   | 8      | `unavailable`  |
+//! |              |           |   | 9      | `rejected`     |
+//!
+//! The error data names both and adds whatever details are known, such as
+//! `{"subsystem": "pset", "failure": "missing", "missing_field": "witness_utxo", "input": 2}`.
+
+use serde_json::{json, Map, Value};
+
+use super::address::AddressError;
+use super::block::BlockError;
+use super::keypair::KeypairError;
+#[cfg(feature = "miniscript")]
+use super::miniscript::MiniscriptError;
+use super::psbt::PsbtError;
+use super::simplicity::pset::{
+	PsetAirgapError, PsetBlindError, PsetCreateError, PsetError, PsetExtractError,
+	PsetFinalizeError, PsetRunError, PsetSignaturesError, PsetUpdateInputError,
+};
+use super::simplicity::{
+	SimplicityBenchError, SimplicityHideError, SimplicityInfoError, SimplicitySighashError,
+	WebIdeError,
+};
+use super::tx::TxError;
+
+/// The part of the library an error comes from.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Subsystem {
+	Address,
+	Block,
+	Tx,
+	Simplicity,
+	Pset,
+	Backend,
+}
+
+impl Subsystem {
+	/// The first code of the subsystem's range.
+	pub fn base(self) -> i64 {
+		match self {
+			Self::Address => 1000,
+			Self::Block => 1100,
+			Self::Tx => 1200,
+			Self::Simplicity => 1300,
+			Self::Pset => 1400,
+			Self::Backend => 1500,
+		}
+	}
+
+	pub fn as_str(self) -> &'static str {
+		match self {
+			Self::Address => "address",
+			Self::Block => "block",
+			Self::Tx => "tx",
+			Self::Simplicity => "simplicity",
+			Self::Pset => "pset",
+			Self::Backend => "backend",
+		}
+	}
+}
+
+/// What kind of failure an error is.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Failure {
+	/// Some input could not be parsed or decoded.
+	Invalid = 0,
+	/// A required input, or a field of one, was not given.
+	Missing = 1,
+	/// Inputs contradict each other.
+	Conflict = 2,
+	/// An index is out of range.
+	OutOfRange = 3,
+	/// Data does not match what it commits to.
+	Mismatch = 4,
+	/// Something referred to could not be found.
+	NotFound = 5,
+	/// The input is valid but not supported.
+	Unsupported = 6,
+	/// A program failed to run.
+	Execution = 7,
+	/// A chain backend could not be reached.
+	Unavailable = 8,
+	/// A chain backend refused the request.
+	Rejected = 9,
+}
+
+impl Failure {
+	pub fn as_str(self) -> &'static str {
+		match self {
+			Self::Invalid => "invalid",
+			Self::Missing => "missing",
+			Self::Conflict => "conflict",
+			Self::OutOfRange => "out_of_range",
+			Self::Mismatch => "mismatch",
+			Self::NotFound => "not_found",
+			Self::Unsupported => "unsupported",
+			Self::Execution => "execution",
+			Self::Unavailable => "unavailable",
+			Self::Rejected => "rejected",
+		}
+	}
+
+	const ALL: [Self; 10] = [
+		Self::Invalid,
+		Self::Missing,
+		Self::Conflict,
+		Self::OutOfRange,
+		Self::Mismatch,
+		Self::NotFound,
+		Self::Unsupported,
+		Self::Execution,
+		Self::Unavailable,
+		Self::Rejected,
+	];
+
+	/// The kind of failure of an error code, if it is that of a failed action.
+	pub fn of_code(code: i64) -> Option<Self> {
+		if !(Subsystem::Address.base()..Subsystem::Backend.base() + 100).contains(&code) {
+			return None;
+		}
+		Self::ALL.iter().copied().find(|failure| *failure as i64 == code % 100)
+	}
+
+	/// The failure with no details.
+	pub(crate) fn only(self) -> (Self, Value) {
+		(self, Value::Null)
+	}
+
+	/// A missing field, optionally of a given input.
+	pub(crate) fn missing(field: &str, input: Option<usize>) -> (Self, Value) {
+		let mut details = json!({ "missing_field": field });
+		if let Some(input) = input {
+			details["input"] = json!(input);
+		}
+		(Self::Missing, details)
+	}
+
+	/// An input index out of range.
+	pub(crate) fn out_of_range(input: usize, n_inputs: usize) -> (Self, Value) {
+		(Self::OutOfRange, json!({ "input": input, "n_inputs": n_inputs }))
+	}
+}
+
+/// An error which a failed action can be reported as.
+pub trait ActionError: std::fmt::Display {
+	fn subsystem(&self) -> Subsystem;
+
+	/// The kind of failure, and a JSON object of details or null.
+	fn failure(&self) -> (Failure, Value);
+
+	/// The error code: the subsystem's base plus the failure's offset.
+	fn code(&self) -> i64 {
+		self.subsystem().base() + self.failure().0 as i64
+	}
+
+	/// The subsystem, the failure and any details, as a JSON object.
+	fn data(&self) -> Value {
+		let (failure, details) = self.failure();
+		let mut data = Map::new();
+		data.insert("subsystem".to_owned(), json!(self.subsystem().as_str()));
+		data.insert("failure".to_owned(), json!(failure.as_str()));
+		if let Value::Object(details) = details {
+			data.extend(details);
+		}
+		Value::Object(data)
+	}
+}
+
+impl ActionError for AddressError {
+	fn subsystem(&self) -> Subsystem {
+		Subsystem::Address
+	}
+
+	fn failure(&self) -> (Failure, Value) {
+		match self {
+			Self::MissingInput => Failure::missing("pubkey", None),
+			Self::NoAddressProvided => Failure::missing("address", None),
+			Self::AmountWithoutAsset => Failure::missing("assetid", None),
+			Self::ProgramHintWithoutCmr => Failure::missing("cmr", None),
+			Self::AddressAndCmr => Failure::Conflict.only(),
+			Self::UriNetworkMismatch {
+				scheme,
+				network,
+				..
+			} => (Failure::Mismatch, json!({ "scheme": scheme, "network": network })),
+			Self::UriRequiredParam(param) => (Failure::Unsupported, json!({ "param": param })),
+			Self::VanityPrefixLength {
+				max,
+				..
+			} => (Failure::OutOfRange, json!({ "max_length": max })),
+			Self::BlinderHex(_)
+			| Self::BlinderInvalid(_)
+			| Self::PubkeyInvalid(_)
+			| Self::ScriptHex(_)
+			| Self::AddressParse(_)
+			| Self::AddressesAlwaysHaveParams
+			| Self::CmrParse(_)
+			| Self::StateParse(_)
+			| Self::InternalKeyParse(_)
+			| Self::AmountParse(_)
+			| Self::AssetIdParse(_)
+			| Self::UriScheme(_)
+			| Self::UriParam(_)
+			| Self::VanityPrefix(_) => Failure::Invalid.only(),
+		}
+	}
+}
+
+impl ActionError for BlockError {
+	fn subsystem(&self) -> Subsystem {
+		Subsystem::Block
+	}
+
+	fn failure(&self) -> (Failure, Value) {
+		match self {
+			Self::ConflictingTransactions => Failure::Conflict.only(),
+			Self::NoTransactions => Failure::missing("transactions", None),
+			Self::TransactionDeserialize(e) => e.failure(),
+			Self::MerkleRootMismatch {
+				given,
+				computed,
+			} => (
+				Failure::Mismatch,
+				json!({ "given": given.to_string(), "computed": computed.to_string() }),
+			),
+			Self::UnsupportedSignblockScript(_) => Failure::Unsupported.only(),
+			Self::MissingField {
+				field,
+				context,
+			} => (Failure::Missing, json!({ "missing_field": field, "context": context })),
+			Self::InvalidRawTransaction(_)
+			| Self::BlockDeserialize(_)
+			| Self::CouldNotDecodeRawBlockHex(_)
+			| Self::InvalidJsonInput(_)
+			| Self::MalformedSignblock(_)
+			| Self::HeaderDeserialize {
+				..
+			} => Failure::Invalid.only(),
+		}
+	}
+}
+
+impl ActionError for KeypairError {
+	fn subsystem(&self) -> Subsystem {
+		Subsystem::Address
+	}
+
+	fn failure(&self) -> (Failure, Value) {
+		match self {
+			Self::NoPublicKeys => Failure::missing("pubkeys", None),
+			Self::TweakOutOfRange => Failure::OutOfRange.only(),
+			Self::PublicKeyParse {
+				..
+			}
+			| Self::TweakHex(_)
+			| Self::TweakSize(_)
+			| Self::TweakedInfinity
+			| Self::SumInfinity => Failure::Invalid.only(),
+		}
+	}
+}
+
+#[cfg(feature = "miniscript")]
+impl ActionError for MiniscriptError {
+	fn subsystem(&self) -> Subsystem {
+		Subsystem::Tx
+	}
+
+	fn failure(&self) -> (Failure, Value) {
+		match self {
+			Self::MissingIndex => Failure::missing("index", None),
+			Self::Lift(_) | Self::LiftHex(_) => Failure::Unsupported.only(),
+			Self::PolicyParse(_)
+			| Self::Compile(_)
+			| Self::ScriptHex(_)
+			| Self::MiniscriptParse(_)
+			| Self::DescriptorParse(_)
+			| Self::Derivation {
+				..
+			}
+			| Self::UnknownContext(_) => Failure::Invalid.only(),
+		}
+	}
+}
+
+impl ActionError for PsbtError {
+	fn subsystem(&self) -> Subsystem {
+		Subsystem::Tx
+	}
+
+	fn failure(&self) -> (Failure, Value) {
+		match self {
+			Self::Hex(_) | Self::Base64(_) | Self::Deserialize(_) => Failure::Invalid.only(),
+		}
+	}
+}
+
+impl ActionError for TxError {
+	fn subsystem(&self) -> Subsystem {
+		Subsystem::Tx
+	}
+
+	fn failure(&self) -> (Failure, Value) {
+		match self {
+			Self::MissingField {
+				field,
+			} => Failure::missing(field, None),
+			Self::MissingVout => Failure::missing("vout", None),
+			Self::NoPrevout => Failure::missing("prevout", None),
+			Self::NoScriptSig => Failure::missing("script_sig", None),
+			Self::NoScriptPubKey => Failure::missing("script_pub_key", None),
+			Self::SimpleCreate(e) => e.failure(),
+			Self::OutputIndexOutOfRange {
+				index,
+				total,
+			} => (Failure::OutOfRange, json!({ "output": index, "n_outputs": total })),
+			Self::OutputNotBlinded {
+				..
+			} => Failure::Unsupported.only(),
+			Self::RangeproofMissing {
+				..
+			} => Failure::missing("rangeproof", None),
+			Self::Rewind(_) | Self::RewindMessage => Failure::Mismatch.only(),
+			Self::InputUtxosRequired => Failure::missing("input_utxos", None),
+			Self::WitnessUtxoMissing {
+				input,
+			} => Failure::missing("witness_utxo", Some(*input)),
+			Self::InputUtxoCountMismatch {
+				expected,
+				actual,
+			} => (Failure::Mismatch, json!({ "expected": expected, "actual": actual })),
+			Self::ConflictingPrevout
+			| Self::MixedNetworks
+			| Self::ContractAndHash
+			| Self::EntropyAndIssuance => Failure::Conflict.only(),
+			Self::PeginOutpointMismatch | Self::PegoutValueMismatch | Self::PegoutAssetMismatch => {
+				Failure::Mismatch.only()
+			}
+			Self::JsonParse(_)
+			| Self::TxHex(_)
+			| Self::TxDeserialize(_)
+			| Self::PrevoutParse(_)
+			| Self::ConfidentialCommitment(_)
+			| Self::ConfidentialCommitmentPublicKey(_)
+			| Self::NonceSize
+			| Self::AssetEntropySize
+			| Self::AssetBlindingNonce(_)
+			| Self::PeginOutpoint(_)
+			| Self::PeginAssetNotExplicit
+			| Self::RangeProof(_)
+			| Self::AsmParse(_)
+			| Self::SurjectionProof(_)
+			| Self::PegoutValueNotExplicit
+			| Self::ContractHashParse(_)
+			| Self::ContractJson(_)
+			| Self::AssetEntropyParse(_)
+			| Self::AssetIdParse(_)
+			| Self::SecretKeyParse(_)
+			| Self::PublicKeyParse(_)
+			| Self::NonceParse(_)
+			| Self::OutputIndexParse(_)
+			| Self::MainchainHeaderHex(_)
+			| Self::MainchainHeader(_)
+			| Self::PsetExtract(_)
+			| Self::InputUtxoParse(_)
+			| Self::GenesisHashParse(_) => Failure::Invalid.only(),
+		}
+	}
+}
+
+impl ActionError for SimplicityInfoError {
+	fn subsystem(&self) -> Subsystem {
+		Subsystem::Simplicity
+	}
+
+	fn failure(&self) -> (Failure, Value) {
+		match self {
+			Self::ProgramParse(_) | Self::StateParse(_) => Failure::Invalid.only(),
+			Self::SourceMap {
+				bit,
+				..
+			} => (Failure::Invalid, json!({ "bit": bit })),
+		}
+	}
+}
+
+impl ActionError for SimplicityHideError {
+	fn subsystem(&self) -> Subsystem {
+		Subsystem::Simplicity
+	}
+
+	fn failure(&self) -> (Failure, Value) {
+		match self {
+			Self::SourceMap(e) => e.failure(),
+			Self::ProgramParse(_) | Self::BranchParse(_) => Failure::Invalid.only(),
+			Self::NotACase {
+				index,
+			} => (Failure::NotFound, json!({ "node": index })),
+			Self::ConflictingBranches {
+				index,
+			} => (Failure::Conflict, json!({ "node": index })),
+		}
+	}
+}
+
+impl ActionError for SimplicityBenchError {
+	fn subsystem(&self) -> Subsystem {
+		Subsystem::Simplicity
+	}
+
+	fn failure(&self) -> (Failure, Value) {
+		match self {
+			Self::SharedError(e) => e.failure(),
+			Self::BitMachineConstruction(_) | Self::Execution(_) => Failure::Execution.only(),
+			Self::PsetDecode(_)
+			| Self::InputIndexParse(_)
+			| Self::ProgramParse(_)
+			| Self::NoRedeemNode
+			| Self::NoIterations => Failure::Invalid.only(),
+		}
+	}
+}
+
+impl ActionError for WebIdeError {
+	fn subsystem(&self) -> Subsystem {
+		Subsystem::Simplicity
+	}
+
+	fn failure(&self) -> (Failure, Value) {
+		match self {
+			Self::BundleParse(_) => Failure::Invalid.only(),
+			Self::Program(e) => e.failure(),
+			Self::FeeExceedsValue {
+				fee,
+				value,
+			} => (Failure::Conflict, json!({ "fee": fee, "value": value })),
+		}
+	}
+}
+
+impl ActionError for SimplicitySighashError {
+	fn subsystem(&self) -> Subsystem {
+		Subsystem::Simplicity
+	}
+
+	fn failure(&self) -> (Failure, Value) {
+		match self {
+			Self::InputIndexOutOfRange {
+				index,
+				n_inputs,
+			} => Failure::out_of_range(*index as usize, *n_inputs),
+			Self::ControlBlockNotFound {
+				cmr,
+			} => (Failure::NotFound, json!({ "cmr": cmr })),
+			Self::ControlBlockRequired => Failure::missing("control_block", None),
+			Self::WitnessUtxoMissing {
+				input,
+			} => Failure::missing("witness_utxo", Some(*input)),
+			Self::InputUtxosRequired => Failure::missing("input_utxos", None),
+			Self::SignatureWithoutPublicKey => Failure::missing("public_key", None),
+			Self::InputUtxoCountMismatch {
+				expected,
+				actual,
+			} => (Failure::Mismatch, json!({ "expected": expected, "actual": actual })),
+			Self::PublicKeyMismatch {
+				derived,
+				provided,
+			} => (Failure::Mismatch, json!({ "derived": derived, "provided": provided })),
+			Self::PsetExtraction(_)
+			| Self::TransactionHexParsing(_)
+			| Self::TransactionDecoding(_)
+			| Self::InputIndexParsing(_)
+			| Self::CmrParsing(_)
+			| Self::ControlBlockHexParsing(_)
+			| Self::ControlBlockDecoding(_)
+			| Self::GenesisHashParsing(_)
+			| Self::SecretKeyParsing(_)
+			| Self::PublicKeyParsing(_)
+			| Self::SignatureParsing(_)
+			| Self::InputUtxoParsing(_) => Failure::Invalid.only(),
+		}
+	}
+}
+
+impl ActionError for PsetError {
+	fn subsystem(&self) -> Subsystem {
+		Subsystem::Pset
+	}
+
+	fn failure(&self) -> (Failure, Value) {
+		match self {
+			Self::InputIndexOutOfRange {
+				index,
+				total,
+			} => Failure::out_of_range(*index, *total),
+			Self::MissingSimplicityLeaf {
+				cmr,
+			} => (Failure::NotFound, json!({ "cmr": cmr })),
+			Self::MissingWitnessUtxo(input) => Failure::missing("witness_utxo", Some(*input)),
+			Self::GenesisHashParse(_) | Self::PsetExtract(_) => Failure::Invalid.only(),
+		}
+	}
+}
+
+impl ActionError for PsetAirgapError {
+	fn subsystem(&self) -> Subsystem {
+		Subsystem::Pset
+	}
+
+	fn failure(&self) -> (Failure, Value) {
+		match self {
+			Self::Signatures(e) => e.failure(),
+			Self::TooManyParts {
+				parts,
+			} => (Failure::Unsupported, json!({ "parts": parts })),
+			Self::BbqrEncoding(_) | Self::BbqrFileType(_) => Failure::Unsupported.only(),
+			Self::BbqrMissingParts {
+				missing,
+			} => (Failure::Missing, json!({ "missing_field": "bbqr_parts", "parts": missing })),
+			Self::PartSizeTooSmall(_) | Self::BbqrParse(_) | Self::SignaturesUtf8 => {
+				Failure::Invalid.only()
+			}
+		}
+	}
+}
+
+impl ActionError for PsetBlindError {
+	fn subsystem(&self) -> Subsystem {
+		Subsystem::Pset
+	}
+
+	fn failure(&self) -> (Failure, Value) {
+		match self {
+			Self::OutputIndexOutOfRange {
+				index,
+				total,
+			} => (Failure::OutOfRange, json!({ "output": index, "n_outputs": total })),
+			Self::BlinderIndexOutOfRange {
+				index,
+				total,
+			} => Failure::out_of_range(*index, *total),
+			Self::PsetDecode(_)
+			| Self::OutputIndexParse(_)
+			| Self::BlinderIndexParse(_)
+			| Self::BlindingKeyParse(_) => Failure::Invalid.only(),
+		}
+	}
+}
+
+impl ActionError for PsetCreateError {
+	fn subsystem(&self) -> Subsystem {
+		Subsystem::Pset
+	}
+
+	fn failure(&self) -> (Failure, Value) {
+		match self {
+			Self::SharedError(e) => e.failure(),
+			Self::ConfidentialAddressNotSupported => Failure::Unsupported.only(),
+			Self::InputsJsonParse(_)
+			| Self::OutputsJsonParse(_)
+			| Self::AmountParse(_)
+			| Self::AddressParse(_)
+			| Self::OpReturnHexParse(_) => Failure::Invalid.only(),
+		}
+	}
+}
+
+impl ActionError for PsetExtractError {
+	fn subsystem(&self) -> Subsystem {
+		Subsystem::Pset
+	}
+
+	fn failure(&self) -> (Failure, Value) {
+		match self {
+			Self::SharedError(e) => e.failure(),
+			Self::PsetDecode(_) | Self::TransactionExtract(_) => Failure::Invalid.only(),
+		}
+	}
+}
+
+impl ActionError for PsetFinalizeError {
+	fn subsystem(&self) -> Subsystem {
+		Subsystem::Pset
+	}
+
+	fn failure(&self) -> (Failure, Value) {
+		match self {
+			Self::SharedError(e) => e.failure(),
+			Self::ProgramPrune(_) => Failure::Execution.only(),
+			Self::OutputsNotBlinded {
+				outputs,
+			} => (Failure::Missing, json!({ "missing_field": "blinding", "outputs": outputs })),
+			Self::PsetDecode(_)
+			| Self::InputIndexParse(_)
+			| Self::ProgramParse(_)
+			| Self::NoRedeemNode => Failure::Invalid.only(),
+		}
+	}
+}
+
+impl ActionError for PsetRunError {
+	fn subsystem(&self) -> Subsystem {
+		Subsystem::Pset
+	}
+
+	fn failure(&self) -> (Failure, Value) {
+		match self {
+			Self::SharedError(e) => e.failure(),
+			Self::BitMachineConstruction(_) | Self::Cancelled => Failure::Execution.only(),
+			Self::PsetDecode(_)
+			| Self::InputIndexParse(_)
+			| Self::ProgramParse(_)
+			| Self::NoRedeemNode => Failure::Invalid.only(),
+		}
+	}
+}
+
+impl ActionError for PsetSignaturesError {
+	fn subsystem(&self) -> Subsystem {
+		Subsystem::Pset
+	}
+
+	fn failure(&self) -> (Failure, Value) {
+		match self {
+			Self::SharedError(e) => e.failure(),
+			Self::InputIndexOutOfRange {
+				index,
+				total,
+			} => Failure::out_of_range(*index, *total),
+			Self::NoSimplicityLeaf {
+				input,
+			} => (Failure::NotFound, json!({ "input": input })),
+			Self::AmbiguousLeaf {
+				input,
+			} => Failure::missing("leaf_hash", Some(*input)),
+			Self::LeafNotFound {
+				input,
+				leaf_hash,
+			} => (Failure::NotFound, json!({ "input": input, "leaf_hash": leaf_hash })),
+			Self::InvalidSignature {
+				input,
+				pubkey,
+			} => (Failure::Mismatch, json!({ "input": input, "pubkey": pubkey })),
+			Self::PsetDecode(_)
+			| Self::SignaturesParse(_)
+			| Self::LeafHashParse(_)
+			| Self::PublicKeyParse(_)
+			| Self::SignatureParse(_) => Failure::Invalid.only(),
+		}
+	}
+}
+
+impl ActionError for PsetUpdateInputError {
+	fn subsystem(&self) -> Subsystem {
+		Subsystem::Pset
+	}
+
+	fn failure(&self) -> (Failure, Value) {
+		match self {
+			Self::SharedError(e) => e.failure(),
+			Self::InputIndexOutOfRange {
+				index,
+				total,
+			} => Failure::out_of_range(*index, *total),
+			Self::MissingInternalKey => Failure::missing("internal_key", None),
+			Self::NotTaprootOutput => Failure::Unsupported.only(),
+			Self::OutputKeyMismatch {
+				output_key,
+				script_pubkey,
+			} => (
+				Failure::Mismatch,
+				json!({ "output_key": output_key, "script_pubkey": script_pubkey }),
+			),
+			Self::PsetDecode(_)
+			| Self::InputIndexParse(_)
+			| Self::CmrParse(_)
+			| Self::InternalKeyParse(_)
+			| Self::StateParse(_)
+			| Self::ElementsUtxoParse(_) => Failure::Invalid.only(),
+		}
+	}
+}
@@ -1,12 +1,81 @@
-use elements::bitcoin::secp256k1::{self, rand};
+use elements::bitcoin::secp256k1::{self, rand, Scalar, SECP256K1};
+
+#[derive(Debug, thiserror::Error)]
+pub enum KeypairError {
+	#[error("invalid public key {key}: {error}")]
+	PublicKeyParse {
+		key: String,
+		error: secp256k1::Error,
+	},
+
+	#[error("invalid tweak: {0}")]
+	TweakHex(hex::FromHexError),
+
+	#[error("invalid tweak: expected 32 bytes, got {0}")]
+	TweakSize(usize),
+
+	#[error("tweak is not less than the curve order")]
+	TweakOutOfRange,
+
+	#[error("the tweaked key is the point at infinity")]
+	TweakedInfinity,
+
+	#[error("no public keys given to combine")]
+	NoPublicKeys,
+
+	#[error("the public keys sum to the point at infinity")]
+	SumInfinity,
+}
 
 #[derive(serde::Serialize)]
+#[cfg_attr(feature = "daemon", derive(schemars::JsonSchema))]
 pub struct KeypairInfo {
+	#[cfg_attr(feature = "daemon", schemars(with = "String"))]
 	pub secret: secp256k1::SecretKey,
+	#[cfg_attr(feature = "daemon", schemars(with = "String"))]
 	pub x_only: secp256k1::XOnlyPublicKey,
+	#[cfg_attr(feature = "daemon", schemars(with = "u8"))]
 	pub parity: secp256k1::Parity,
 }
 
+/// A public key, with its x-only form.
+#[derive(serde::Serialize)]
+#[cfg_attr(feature = "daemon", derive(schemars::JsonSchema))]
+pub struct PublicKeyInfo {
+	#[cfg_attr(feature = "daemon", schemars(with = "String"))]
+	pub public_key: secp256k1::PublicKey,
+	#[cfg_attr(feature = "daemon", schemars(with = "String"))]
+	pub x_only: secp256k1::XOnlyPublicKey,
+	#[cfg_attr(feature = "daemon", schemars(with = "u8"))]
+	pub parity: secp256k1::Parity,
+}
+
+impl From<secp256k1::PublicKey> for PublicKeyInfo {
+	fn from(public_key: secp256k1::PublicKey) -> Self {
+		let (x_only, parity) = public_key.x_only_public_key();
+		PublicKeyInfo {
+			public_key,
+			x_only,
+			parity,
+		}
+	}
+}
+
+/// Parse a public key in hex, taking an x-only key to have an even y-coordinate
+/// as BIP 340 does.
+fn parse_public_key(key: &str) -> Result<secp256k1::PublicKey, KeypairError> {
+	let parse_err = |error| KeypairError::PublicKeyParse {
+		key: key.to_owned(),
+		error,
+	};
+	if key.len() == 64 {
+		let x_only: secp256k1::XOnlyPublicKey = key.parse().map_err(parse_err)?;
+		Ok(x_only.public_key(secp256k1::Parity::Even))
+	} else {
+		key.parse().map_err(parse_err)
+	}
+}
+
 /// Generate a random keypair.
 pub fn keypair_generate() -> KeypairInfo {
 	let (secret, public) = secp256k1::generate_keypair(&mut rand::thread_rng());
@@ -18,3 +87,34 @@ pub fn keypair_generate() -> KeypairInfo {
 		parity,
 	}
 }
+
+/// Add `tweak` times the generator to a public key. Tweaking an x-only key
+/// gives the taproot output key for an internal key and its tap tweak.
+pub fn keypair_tweak_add(key: &str, tweak: &str) -> Result<PublicKeyInfo, KeypairError> {
+	let public_key = parse_public_key(key)?;
+	let tweak = hex::decode(tweak).map_err(KeypairError::TweakHex)?;
+	let tweak: [u8; 32] =
+		tweak.as_slice().try_into().map_err(|_| KeypairError::TweakSize(tweak.len()))?;
+	let tweak = Scalar::from_be_bytes(tweak).map_err(|_| KeypairError::TweakOutOfRange)?;
+
+	let tweaked =
+		public_key.add_exp_tweak(SECP256K1, &tweak).map_err(|_| KeypairError::TweakedInfinity)?;
+	Ok(tweaked.into())
+}
+
+/// Negate a public key. The negation of an x-only key has the same x-only
+/// form, with an odd y-coordinate.
+pub fn keypair_negate(key: &str) -> Result<PublicKeyInfo, KeypairError> {
+	Ok(parse_public_key(key)?.negate(SECP256K1).into())
+}
+
+/// Add public keys together.
+pub fn keypair_combine(keys: &[&str]) -> Result<PublicKeyInfo, KeypairError> {
+	let keys = keys.iter().map(|key| parse_public_key(key)).collect::<Result<Vec<_>, _>>()?;
+	if keys.is_empty() {
+		return Err(KeypairError::NoPublicKeys);
+	}
+	let keys: Vec<_> = keys.iter().collect();
+	let sum = secp256k1::PublicKey::combine_keys(&keys).map_err(|_| KeypairError::SumInfinity)?;
+	Ok(sum.into())
+}
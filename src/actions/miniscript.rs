@@ -0,0 +1,248 @@
+use std::str::FromStr;
+
+use elements::taproot::TapLeafHash;
+use elements_miniscript as ems;
+use ems::descriptor::{ConversionError, DefiniteDescriptorKey, DescriptorPublicKey};
+use ems::policy::compiler::CompilerError;
+use ems::policy::{Concrete, Liftable};
+use ems::{Descriptor, ExtParams, Miniscript, Segwitv0, Tap, ToPublicKey};
+use serde::{Deserialize, Serialize};
+
+use crate::tx::{InputScriptInfo, OutputScript, OutputScriptInfo};
+use crate::{GetInfo, Network};
+
+#[derive(Debug, thiserror::Error)]
+pub enum MiniscriptError {
+	#[error("invalid policy: {0}")]
+	PolicyParse(ems::Error),
+
+	#[error("cannot compile policy: {0}")]
+	Compile(CompilerError),
+
+	#[error("invalid script hex: {0}")]
+	ScriptHex(hex::FromHexError),
+
+	#[error("invalid miniscript: {0}")]
+	MiniscriptParse(ems::Error),
+
+	#[error("cannot lift to a policy: {0}")]
+	Lift(ems::Error),
+
+	#[error(
+		"cannot lift to a policy: {0}; give the script as miniscript, with the keys of any pkh"
+	)]
+	LiftHex(ems::Error),
+
+	#[error("invalid descriptor: {0}")]
+	DescriptorParse(ems::Error),
+
+	#[error("the descriptor has a wildcard, so a derivation index is needed")]
+	MissingIndex,
+
+	#[error("cannot derive the descriptor at index {index}: {error}")]
+	Derivation {
+		index: u32,
+		error: ConversionError,
+	},
+
+	#[error("unknown script context {0}, expected segwitv0 or tap")]
+	UnknownContext(String),
+}
+
+/// The script context a miniscript is used in: a segwit v0 witness script or
+/// a taproot leaf.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[cfg_attr(feature = "daemon", derive(schemars::JsonSchema))]
+#[serde(rename_all = "lowercase")]
+pub enum MiniscriptContext {
+	#[default]
+	Segwitv0,
+	Tap,
+}
+
+impl FromStr for MiniscriptContext {
+	type Err = MiniscriptError;
+
+	fn from_str(s: &str) -> Result<Self, Self::Err> {
+		match s {
+			"segwitv0" => Ok(MiniscriptContext::Segwitv0),
+			"tap" => Ok(MiniscriptContext::Tap),
+			_ => Err(MiniscriptError::UnknownContext(s.to_owned())),
+		}
+	}
+}
+
+#[derive(Serialize)]
+#[cfg_attr(feature = "daemon", derive(schemars::JsonSchema))]
+pub struct MiniscriptInfo {
+	pub context: MiniscriptContext,
+	pub miniscript: String,
+	pub script: InputScriptInfo,
+	pub script_size: usize,
+	/// The size of the largest witness which satisfies the script, if it can
+	/// be satisfied at all.
+	pub max_satisfaction_size: Option<usize>,
+	/// Whether the script is sane: non-malleable, needing a signature on each
+	/// spending path and within consensus and standardness limits.
+	pub sane: bool,
+	/// The semantic policy, normalized, unless the script hashes keys which
+	/// are not known.
+	#[serde(skip_serializing_if = "Option::is_none")]
+	pub policy: Option<String>,
+}
+
+#[derive(Serialize)]
+#[cfg_attr(feature = "daemon", derive(schemars::JsonSchema))]
+pub struct TapLeafInfo {
+	pub depth: usize,
+	#[cfg_attr(feature = "daemon", schemars(with = "String"))]
+	pub leaf_hash: TapLeafHash,
+	#[serde(flatten)]
+	pub miniscript: MiniscriptInfo,
+}
+
+#[derive(Serialize)]
+#[cfg_attr(feature = "daemon", derive(schemars::JsonSchema))]
+pub struct DescriptorInfo {
+	/// The descriptor, with its checksum.
+	pub descriptor: String,
+	#[serde(rename = "type")]
+	pub type_: String,
+	pub sane: bool,
+	pub has_wildcard: bool,
+	#[serde(skip_serializing_if = "Option::is_none")]
+	pub index: Option<u32>,
+	pub script_pub_key: OutputScriptInfo,
+	/// The largest weight a satisfying witness and script sig can add to a
+	/// transaction, if it can be satisfied at all.
+	pub max_weight_to_satisfy: Option<usize>,
+	#[serde(skip_serializing_if = "Option::is_none")]
+	pub policy: Option<String>,
+	/// The script leaves of a taproot descriptor, in order.
+	#[serde(skip_serializing_if = "Vec::is_empty")]
+	pub leaves: Vec<TapLeafInfo>,
+}
+
+fn miniscript_info<Pk, Ctx>(ms: &Miniscript<Pk, Ctx>, context: MiniscriptContext) -> MiniscriptInfo
+where
+	Pk: ToPublicKey,
+	Ctx: ems::ScriptContext,
+{
+	let script = ms.encode();
+	MiniscriptInfo {
+		context,
+		miniscript: ms.to_string(),
+		script: InputScriptInfo {
+			hex: Some(script.to_bytes().into()),
+			asm: Some(script.asm()),
+		},
+		script_size: ms.script_size(),
+		max_satisfaction_size: ms.max_satisfaction_size().ok(),
+		sane: ms.sanity_check().is_ok(),
+		policy: ms.lift().ok().map(|policy| policy.normalized().to_string()),
+	}
+}
+
+fn compile<Ctx: ems::ScriptContext>(
+	policy: &Concrete<DefiniteDescriptorKey>,
+	context: MiniscriptContext,
+) -> Result<MiniscriptInfo, MiniscriptError> {
+	let ms = policy.compile::<Ctx>().map_err(MiniscriptError::Compile)?;
+	Ok(miniscript_info(&ms, context))
+}
+
+/// Compile a policy, such as `or(99@pk(K1),1@and(pk(K2),older(144)))`, to the
+/// cheapest miniscript to satisfy in the given context.
+pub fn miniscript_compile(
+	policy: &str,
+	context: MiniscriptContext,
+) -> Result<MiniscriptInfo, MiniscriptError> {
+	let policy = policy.parse().map_err(MiniscriptError::PolicyParse)?;
+	match context {
+		MiniscriptContext::Segwitv0 => compile::<Segwitv0>(&policy, context),
+		MiniscriptContext::Tap => compile::<Tap>(&policy, context),
+	}
+}
+
+fn lift<Ctx>(script: &str, context: MiniscriptContext) -> Result<MiniscriptInfo, MiniscriptError>
+where
+	Ctx: ems::ScriptContext,
+	Ctx::Key: ToPublicKey,
+{
+	let ext = ExtParams::allow_all();
+	let info = if script.chars().all(|c| c.is_ascii_hexdigit()) {
+		let script = hex::decode(script).map_err(MiniscriptError::ScriptHex)?.into();
+		let ms = Miniscript::<Ctx::Key, Ctx>::parse_with_ext(&script, &ext)
+			.map_err(MiniscriptError::MiniscriptParse)?;
+		ms.lift().map_err(MiniscriptError::LiftHex)?;
+		miniscript_info(&ms, context)
+	} else {
+		let ms = Miniscript::<DefiniteDescriptorKey, Ctx>::from_str_ext(script, &ext)
+			.map_err(MiniscriptError::MiniscriptParse)?;
+		ms.lift().map_err(MiniscriptError::Lift)?;
+		miniscript_info(&ms, context)
+	};
+	Ok(info)
+}
+
+/// Lift a script, given in hex or as miniscript, back to its semantic policy.
+///
+/// A script decoded from hex has only the hashes of the keys in any `pkh`
+/// fragments, so cannot be lifted. Give it as miniscript instead, with the
+/// keys.
+pub fn miniscript_lift(
+	script: &str,
+	context: MiniscriptContext,
+) -> Result<MiniscriptInfo, MiniscriptError> {
+	match context {
+		MiniscriptContext::Segwitv0 => lift::<Segwitv0>(script, context),
+		MiniscriptContext::Tap => lift::<Tap>(script, context),
+	}
+}
+
+/// Parse an output descriptor, such as `elwsh(...)` or `eltr(...)`, and
+/// derive its script pubkey at `index`, which is needed if it has a wildcard.
+pub fn miniscript_descriptor(
+	descriptor: &str,
+	index: Option<u32>,
+	network: Network,
+) -> Result<DescriptorInfo, MiniscriptError> {
+	let descriptor = Descriptor::<DescriptorPublicKey>::from_str(descriptor)
+		.map_err(MiniscriptError::DescriptorParse)?;
+	let has_wildcard = descriptor.has_wildcard();
+	let derived = match index {
+		None if has_wildcard => return Err(MiniscriptError::MissingIndex),
+		index => {
+			let index = index.unwrap_or(0);
+			descriptor.at_derivation_index(index).map_err(|error| MiniscriptError::Derivation {
+				index,
+				error,
+			})?
+		}
+	};
+
+	let mut leaves = vec![];
+	if let Descriptor::Tr(ref tr) = derived {
+		for (depth, leaf) in tr.iter_scripts() {
+			if let Some(ms) = leaf.as_miniscript() {
+				leaves.push(TapLeafInfo {
+					depth,
+					leaf_hash: TapLeafHash::from_script(&leaf.encode(), leaf.version()),
+					miniscript: miniscript_info(ms, MiniscriptContext::Tap),
+				});
+			}
+		}
+	}
+
+	Ok(DescriptorInfo {
+		descriptor: descriptor.to_string(),
+		type_: format!("{:?}", descriptor.desc_type()).to_lowercase(),
+		sane: descriptor.sanity_check().is_ok(),
+		has_wildcard,
+		index,
+		script_pub_key: OutputScript(&derived.script_pubkey()).get_info(network),
+		max_weight_to_satisfy: derived.max_weight_to_satisfy().ok(),
+		policy: descriptor.lift().ok().map(|policy| policy.normalized().to_string()),
+		leaves,
+	})
+}
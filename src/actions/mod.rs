@@ -1,5 +1,9 @@
 pub mod address;
 pub mod block;
+pub mod error;
 pub mod keypair;
+#[cfg(feature = "miniscript")]
+pub mod miniscript;
+pub mod psbt;
 pub mod simplicity;
 pub mod tx;
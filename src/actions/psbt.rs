@@ -0,0 +1,30 @@
+use elements::bitcoin::psbt::{self, Psbt};
+use simplicity::base64::prelude::{Engine as _, BASE64_STANDARD};
+
+use crate::Network;
+
+#[derive(Debug, thiserror::Error)]
+pub enum PsbtError {
+	#[error("invalid PSBT hex: {0}")]
+	Hex(hex::FromHexError),
+
+	#[error("invalid PSBT base64: {0}")]
+	Base64(simplicity::base64::DecodeError),
+
+	#[error("invalid PSBT: {0}")]
+	Deserialize(psbt::Error),
+}
+
+/// Decode a Bitcoin PSBT, given in base64 or hex.
+pub fn psbt_decode(psbt: &str, network: Network) -> Result<hal::psbt::PsbtInfo, PsbtError> {
+	let psbt = psbt.trim();
+	// A hex PSBT starts with the hex of its magic bytes "psbt\xff".
+	let bytes = if psbt.starts_with("70736274ff") {
+		hex::decode(psbt).map_err(PsbtError::Hex)?
+	} else {
+		BASE64_STANDARD.decode(psbt).map_err(PsbtError::Base64)?
+	};
+	let psbt = Psbt::deserialize(&bytes).map_err(PsbtError::Deserialize)?;
+
+	Ok(hal::GetInfo::get_info(&psbt, network.parent_network()))
+}
@@ -0,0 +1,112 @@
+//! Benchmarking programs on the Bit Machine
+//!
+//! The cost which consensus charges for a program is a static bound, computed
+//! from the program alone. Running the program many times against a real
+//! environment shows how its actual running time relates to that cost, so
+//! that program authors can compare implementations.
+
+use std::time::{Duration, Instant};
+
+use serde::Serialize;
+
+use crate::hal_simplicity::{Program, ProgramParseError};
+use crate::simplicity::bit_machine::{BitMachine, ExecutionError, LimitError};
+use crate::simplicity::{jet, Cmr};
+
+use super::pset::{execution_environment, PsetError};
+
+#[derive(Debug, thiserror::Error)]
+pub enum SimplicityBenchError {
+	#[error(transparent)]
+	SharedError(#[from] PsetError),
+
+	#[error("invalid PSET: {0}")]
+	PsetDecode(elements::pset::ParseError),
+
+	#[error("invalid input index: {0}")]
+	InputIndexParse(std::num::ParseIntError),
+
+	#[error("invalid program: {0}")]
+	ProgramParse(ProgramParseError),
+
+	#[error("program does not have a redeem node")]
+	NoRedeemNode,
+
+	#[error("the number of iterations must be at least 1")]
+	NoIterations,
+
+	#[error("failed to construct bit machine: {0}")]
+	BitMachineConstruction(LimitError),
+
+	#[error("program failed: {0}")]
+	Execution(ExecutionError),
+}
+
+#[derive(Serialize)]
+#[cfg_attr(feature = "daemon", derive(schemars::JsonSchema))]
+pub struct BenchInfo {
+	#[cfg_attr(feature = "daemon", schemars(with = "String"))]
+	pub cmr: Cmr,
+	pub iterations: u32,
+	/// The cost of the program, in milliweight units.
+	pub cost: u64,
+	/// The time taken by all iterations, in nanoseconds.
+	pub total_ns: u64,
+	pub mean_ns: u64,
+	pub min_ns: u64,
+	pub max_ns: u64,
+	/// The cost executed per second of the mean iteration.
+	pub cost_per_second: f64,
+}
+
+fn nanos(duration: Duration) -> u64 {
+	duration.as_nanos().try_into().unwrap_or(u64::MAX)
+}
+
+/// Run a Simplicity program `iterations` times in the context of a PSET input,
+/// timing each run.
+pub fn simplicity_bench(
+	pset_b64: &str,
+	input_idx: &str,
+	program: &str,
+	witness: &str,
+	genesis_hash: Option<&str>,
+	iterations: u32,
+) -> Result<BenchInfo, SimplicityBenchError> {
+	let pset: elements::pset::PartiallySignedTransaction =
+		pset_b64.parse().map_err(SimplicityBenchError::PsetDecode)?;
+	let input_idx: usize = input_idx.parse().map_err(SimplicityBenchError::InputIndexParse)?;
+	let program = Program::<jet::Elements>::from_str(program, Some(witness))
+		.map_err(SimplicityBenchError::ProgramParse)?;
+	if iterations == 0 {
+		return Err(SimplicityBenchError::NoIterations);
+	}
+
+	let (tx_env, _, _) = execution_environment(&pset, input_idx, program.cmr(), genesis_hash)?;
+	let redeem_node = program.redeem_node().ok_or(SimplicityBenchError::NoRedeemNode)?;
+
+	let mut times = Vec::with_capacity(iterations as usize);
+	for _ in 0..iterations {
+		// Setting up the machine is part of running the program, so it is timed too.
+		let start = Instant::now();
+		let mut mac = BitMachine::for_program(redeem_node)
+			.map_err(SimplicityBenchError::BitMachineConstruction)?;
+		mac.exec(redeem_node, &tx_env).map_err(SimplicityBenchError::Execution)?;
+		times.push(start.elapsed());
+	}
+
+	// `Cost` only exposes its milliweight through `Display`.
+	let cost: u64 = redeem_node.bounds().cost.to_string().parse().expect("cost is an integer");
+	let total: Duration = times.iter().sum();
+	let mean = total / iterations;
+	Ok(BenchInfo {
+		cmr: program.cmr(),
+		iterations,
+		cost,
+		total_ns: nanos(total),
+		mean_ns: nanos(mean),
+		min_ns: nanos(*times.iter().min().expect("at least one iteration")),
+		max_ns: nanos(*times.iter().max().expect("at least one iteration")),
+		cost_per_second: cost as f64 / mean.as_secs_f64(),
+	})
+}
@@ -0,0 +1,194 @@
+//! Regression fixtures for other Simplicity tools
+//!
+//! Each seed determines a key, a program which checks a signature by that key
+//! on the spending transaction, and a PSET spending an output locked by the
+//! program. A fixture holds all of these along with the CMR, sighash and
+//! witness which hal-simplicity computes for them, so that tools such as
+//! simplicity-unchained and the web IDE can check that they agree.
+
+use std::collections::HashMap;
+
+use elements::hashes::{sha256, Hash as _, HashEngine as _};
+use elements::{BlockHash, Txid};
+use serde::Serialize;
+
+use crate::hal_simplicity::{elements_address, unspendable_internal_key};
+use crate::simplicity::bitcoin::secp256k1::{
+	schnorr, Keypair, Message, SecretKey, XOnlyPublicKey, SECP256K1,
+};
+use crate::simplicity::human_encoding::Forest;
+use crate::simplicity::{jet, types, Cmr, Value};
+use crate::Network;
+
+use super::pset::{
+	execution_environment, pset_create, pset_extract, pset_finalize, pset_update_input,
+};
+
+/// The version of the fixture format, to be bumped whenever a fixture for a
+/// given seed changes.
+pub const FIXTURES_VERSION: u32 = 1;
+
+/// The network of the fixtures' addresses and transactions.
+const NETWORK: Network = Network::LiquidTestnet;
+/// The value of the spent output, in satoshi.
+const VALUE: u64 = 100_000;
+/// The fee of the spending transaction, in satoshi.
+const FEE: u64 = 1_000;
+
+/// One program, spent in one transaction, with the values expected of it.
+#[derive(Serialize)]
+#[cfg_attr(feature = "daemon", derive(schemars::JsonSchema))]
+pub struct Fixture {
+	pub seed: String,
+	/// The key whose signature the program checks (hex).
+	pub secret_key: String,
+	#[cfg_attr(feature = "daemon", schemars(with = "String"))]
+	pub public_key: XOnlyPublicKey,
+	/// The program in the human-readable encoding.
+	pub source: String,
+	/// The pruned program (base64).
+	pub program: String,
+	/// The witness of the pruned program (hex).
+	pub witness: String,
+	#[cfg_attr(feature = "daemon", schemars(with = "String"))]
+	pub cmr: Cmr,
+	pub address: String,
+	#[cfg_attr(feature = "daemon", schemars(with = "String"))]
+	pub genesis_hash: BlockHash,
+	/// The PSET spending an output paid to `address`, before it is signed.
+	pub pset: String,
+	pub input_index: usize,
+	#[cfg_attr(feature = "daemon", schemars(with = "String"))]
+	pub sighash: sha256::Hash,
+	#[cfg_attr(feature = "daemon", schemars(with = "String"))]
+	pub signature: schnorr::Signature,
+	/// `pset` with the program and witness attached to the input.
+	pub finalized_pset: String,
+	/// The transaction extracted from `finalized_pset` (hex).
+	pub transaction: String,
+}
+
+#[derive(Serialize)]
+#[cfg_attr(feature = "daemon", derive(schemars::JsonSchema))]
+pub struct Fixtures {
+	pub version: u32,
+	pub network: &'static str,
+	pub fixtures: Vec<Fixture>,
+}
+
+/// A BIP-0340 tagged hash of `seed`, so that each value drawn from a seed is
+/// independent of the others.
+fn tagged_hash(tag: &str, seed: &str) -> sha256::Hash {
+	let tag = sha256::Hash::hash(tag.as_bytes());
+	let mut eng = sha256::Hash::engine();
+	eng.input(tag.as_byte_array());
+	eng.input(tag.as_byte_array());
+	eng.input(seed.as_bytes());
+	sha256::Hash::from_engine(eng)
+}
+
+/// The program checking a signature by `public_key` on the whole transaction.
+fn p2pk_source(public_key: &XOnlyPublicKey) -> String {
+	format!(
+		"sig := witness : 1 -> 2^512\n\
+		pk := const 0x{} : 1 -> 2^256\n\
+		msg := jet_sig_all_hash : 1 -> 2^256\n\
+		pk_msg := pair pk msg : 1 -> 2^512\n\
+		args := pair pk_msg sig : 1 -> (2^512 * 2^512)\n\
+		verify := jet_bip_0340_verify : (2^512 * 2^512) -> 1\n\
+		main := comp args verify : 1 -> 1\n",
+		public_key,
+	)
+}
+
+fn gen_fixture(seed: &str) -> Fixture {
+	let genesis_hash = NETWORK.genesis_hash().expect("Liquid testnet has a genesis hash");
+	let genesis_hex = genesis_hash.to_string();
+	let asset = NETWORK.policy_asset().expect("Liquid testnet has a policy asset");
+
+	let secret_key =
+		SecretKey::from_slice(tagged_hash("hal-simplicity/fixture/key", seed).as_ref())
+			.expect("a hash is a valid secret key");
+	let keypair = Keypair::from_secret_key(SECP256K1, &secret_key);
+	let (public_key, _) = keypair.x_only_public_key();
+
+	let source = p2pk_source(&public_key);
+	let forest = Forest::<jet::Elements>::parse(&source).expect("fixture program parses");
+	let cmr = types::Context::with_context(|ctx| {
+		let node = forest.to_witness_node(&ctx, &HashMap::new()).expect("program has a main");
+		node.finalize_types().expect("fixture program type-checks").cmr()
+	});
+	let address = elements_address(cmr, None, NETWORK.address_params());
+
+	// Spend an output of a made-up transaction, paying it back to the same
+	// address less the fee.
+	let txid =
+		Txid::from_byte_array(tagged_hash("hal-simplicity/fixture/txid", seed).to_byte_array());
+	let btc = |sat| elements::bitcoin::Amount::from_sat(sat).to_btc();
+	let inputs = serde_json::json!([{ "txid": txid, "vout": 0 }]);
+	let outputs = serde_json::json!([
+		{ "address": address.to_string(), "asset": asset, "amount": btc(VALUE - FEE) },
+		{ "address": "fee", "asset": asset, "amount": btc(FEE) },
+	]);
+	let pset =
+		pset_create(&inputs.to_string(), &outputs.to_string()).expect("fixture PSET is valid").pset;
+	let utxo = format!("{:x}:{}:{}", address.script_pubkey(), asset, btc(VALUE));
+	let pset = pset_update_input(
+		&pset,
+		"0",
+		&utxo,
+		Some(&unspendable_internal_key().to_string()),
+		Some(&cmr.to_string()),
+		None,
+	)
+	.expect("fixture input is valid")
+	.pset;
+
+	let (tx_env, _, _) =
+		execution_environment(&pset.parse().expect("PSET round-trips"), 0, cmr, Some(&genesis_hex))
+			.expect("fixture PSET has an execution environment");
+	let sighash = tx_env.c_tx_env().sighash_all();
+	let signature = SECP256K1
+		.sign_schnorr_no_aux_rand(&Message::from_digest(sighash.to_byte_array()), &keypair);
+
+	let witness = HashMap::from([("sig".into(), Value::u512(signature.serialize()))]);
+	let redeem = types::Context::with_context(|ctx| {
+		let node = forest.to_witness_node(&ctx, &witness).expect("program has a main");
+		node.finalize_pruned(&tx_env).expect("signature satisfies the program")
+	});
+	let program = redeem.display().program().to_string();
+	let witness = redeem.display().witness().to_string();
+
+	let finalized_pset = pset_finalize(&pset, "0", &program, &witness, Some(&genesis_hex))
+		.expect("fixture program finalizes")
+		.pset;
+	let transaction = pset_extract(&finalized_pset).expect("finalized PSET is complete");
+
+	Fixture {
+		seed: seed.to_owned(),
+		secret_key: secret_key.display_secret().to_string(),
+		public_key,
+		source,
+		program,
+		witness,
+		cmr,
+		address: address.to_string(),
+		genesis_hash,
+		pset,
+		input_index: 0,
+		sighash,
+		signature,
+		finalized_pset,
+		transaction,
+	}
+}
+
+/// Generate one fixture for each seed. The same seed always gives the same
+/// fixture, for a given [`FIXTURES_VERSION`].
+pub fn simplicity_gen_fixtures(seeds: &[&str]) -> Fixtures {
+	Fixtures {
+		version: FIXTURES_VERSION,
+		network: NETWORK.as_str(),
+		fixtures: seeds.iter().map(|seed| gen_fixture(seed)).collect(),
+	}
+}
@@ -0,0 +1,225 @@
+//! Hiding chosen branches of a redeem-time program
+//!
+//! Finalizing a PSET prunes a program by running it, hiding every `case`
+//! branch which the run did not take. Here the branches to keep are chosen by
+//! hand instead, by the indices of their `case` nodes in the program's
+//! source map, and nothing is run. A branch which is hidden but needed at
+//! redemption makes the spend fail.
+
+use std::collections::HashMap;
+use std::marker::PhantomData;
+use std::sync::Arc;
+
+use serde::Serialize;
+
+use crate::hal_simplicity::{Program, ProgramParseError};
+use crate::simplicity::dag::{InternalSharing, PostOrderIterItem};
+use crate::simplicity::node::{
+	Construct, ConstructData, ConstructNode, Constructible as _, Converter, Hide, Inner, Redeem,
+	RedeemData, RedeemNode,
+};
+use crate::simplicity::{jet, types, Cmr, Value};
+
+use super::{simplicity_source_map, SimplicityInfoError};
+
+#[derive(Debug, thiserror::Error)]
+pub enum SimplicityHideError {
+	#[error("invalid program: {0}")]
+	ProgramParse(ProgramParseError),
+
+	#[error(transparent)]
+	SourceMap(#[from] SimplicityInfoError),
+
+	#[error("invalid branch {0}: expected <case node index>:left or <case node index>:right")]
+	BranchParse(String),
+
+	#[error("node {index} is not a case node of the program")]
+	NotACase {
+		index: usize,
+	},
+
+	#[error("both branches of case node {index} were chosen")]
+	ConflictingBranches {
+		index: usize,
+	},
+}
+
+#[derive(Serialize)]
+#[cfg_attr(feature = "daemon", derive(schemars::JsonSchema))]
+pub struct HiddenProgram {
+	#[cfg_attr(feature = "daemon", schemars(with = "String"))]
+	pub cmr: Cmr,
+	pub program_base64: String,
+	pub witness_hex: String,
+	/// The number of `case` nodes one of whose branches was hidden.
+	pub hidden_branches: usize,
+}
+
+/// Hides the branches not chosen, keyed by the CMRs of their `case` nodes.
+struct Pruner<'brand, 'k, J> {
+	inference_context: types::Context<'brand>,
+	/// For each case node to prune, whether its right branch is kept.
+	keep_right: &'k HashMap<Cmr, bool>,
+	hidden: usize,
+	phantom: PhantomData<J>,
+}
+
+impl<'brand, J: jet::Jet> Converter<Redeem<J>, Construct<'brand, J>> for Pruner<'brand, '_, J> {
+	type Error = std::convert::Infallible;
+
+	fn convert_witness(
+		&mut self,
+		_: &PostOrderIterItem<&RedeemNode<J>>,
+		witness: &Value,
+	) -> Result<Option<Value>, Self::Error> {
+		// The witness is pruned to its new type once that type is known.
+		Ok(Some(witness.shallow_clone()))
+	}
+
+	fn convert_disconnect(
+		&mut self,
+		_: &PostOrderIterItem<&RedeemNode<J>>,
+		right: Option<&Arc<ConstructNode<'brand, J>>>,
+		_: &Arc<RedeemNode<J>>,
+	) -> Result<Option<Arc<ConstructNode<'brand, J>>>, Self::Error> {
+		Ok(right.map(Arc::clone))
+	}
+
+	fn prune_case(
+		&mut self,
+		data: &PostOrderIterItem<&RedeemNode<J>>,
+		_left: &Arc<ConstructNode<'brand, J>>,
+		_right: &Arc<ConstructNode<'brand, J>>,
+	) -> Result<Hide, Self::Error> {
+		Ok(match self.keep_right.get(&data.node.cmr()) {
+			Some(true) => {
+				self.hidden += 1;
+				Hide::Left
+			}
+			Some(false) => {
+				self.hidden += 1;
+				Hide::Right
+			}
+			None => Hide::Neither,
+		})
+	}
+
+	fn convert_data(
+		&mut self,
+		_: &PostOrderIterItem<&RedeemNode<J>>,
+		inner: Inner<
+			&Arc<ConstructNode<'brand, J>>,
+			J,
+			&Option<Arc<ConstructNode<'brand, J>>>,
+			&Option<Value>,
+		>,
+	) -> Result<ConstructData<'brand, J>, Self::Error> {
+		let inner = inner.map(|node| node.cached_data()).map_witness(Option::<Value>::clone);
+		Ok(ConstructData::from_inner(&self.inference_context, inner)
+			.expect("pruned types check if unpruned types do"))
+	}
+}
+
+/// Finalizes the types of the pruned program, pruning its witnesses to them.
+struct Finalizer<J>(PhantomData<J>);
+
+impl<'brand, J: jet::Jet> Converter<Construct<'brand, J>, Redeem<J>> for Finalizer<J> {
+	type Error = std::convert::Infallible;
+
+	fn convert_witness(
+		&mut self,
+		data: &PostOrderIterItem<&ConstructNode<J>>,
+		witness: &Option<Value>,
+	) -> Result<Value, Self::Error> {
+		let target = data.node.arrow().target.finalize().expect("pruned types are final");
+		Ok(witness
+			.as_ref()
+			.expect("witnesses come from the redeem program")
+			.prune(&target)
+			.expect("pruned types shrink unpruned types"))
+	}
+
+	fn convert_disconnect(
+		&mut self,
+		_: &PostOrderIterItem<&ConstructNode<J>>,
+		right: Option<&Arc<RedeemNode<J>>>,
+		_: &Option<Arc<ConstructNode<J>>>,
+	) -> Result<Arc<RedeemNode<J>>, Self::Error> {
+		Ok(right.map(Arc::clone).expect("disconnects come from the redeem program"))
+	}
+
+	fn convert_data(
+		&mut self,
+		data: &PostOrderIterItem<&ConstructNode<J>>,
+		inner: Inner<&Arc<RedeemNode<J>>, J, &Arc<RedeemNode<J>>, &Value>,
+	) -> Result<Arc<RedeemData<J>>, Self::Error> {
+		let arrow = data.node.arrow().finalize().expect("pruned types are final");
+		let inner = inner
+			.map(|node| node.cached_data())
+			.map_disconnect(|node| node.cached_data())
+			.map_witness(Value::shallow_clone);
+		Ok(Arc::new(RedeemData::new(arrow, inner)))
+	}
+}
+
+/// Hide the branches of a redeem-time program which are not chosen by
+/// `branches`, each of the form `<case node index>:left` or `:right`, giving the
+/// index of the `case` node in the program's source map.
+pub fn simplicity_hide_branches(
+	program: &str,
+	witness: &str,
+	branches: &[&str],
+) -> Result<HiddenProgram, SimplicityHideError> {
+	let source_map = simplicity_source_map(program)?;
+	let parsed = Program::<jet::Elements>::from_str(program, Some(witness))
+		.map_err(SimplicityHideError::ProgramParse)?;
+	let redeem = parsed.redeem_node().expect("a witness was given");
+
+	let mut keep_right = HashMap::new();
+	for branch in branches {
+		let parse_err = || SimplicityHideError::BranchParse(branch.to_string());
+		let (index, side) = branch.split_once(':').ok_or_else(parse_err)?;
+		let index: usize = index.parse().map_err(|_| parse_err())?;
+		let right = match side {
+			"left" => false,
+			"right" => true,
+			_ => return Err(parse_err()),
+		};
+		let node = source_map.nodes.get(index).filter(|node| node.node == "case").ok_or(
+			SimplicityHideError::NotACase {
+				index,
+			},
+		)?;
+		if keep_right.insert(node.cmr, right).is_some_and(|other| other != right) {
+			return Err(SimplicityHideError::ConflictingBranches {
+				index,
+			});
+		}
+	}
+
+	let (pruned, hidden) = types::Context::with_context(|inference_context| {
+		let mut pruner = Pruner {
+			inference_context,
+			keep_right: &keep_right,
+			hidden: 0,
+			phantom: PhantomData,
+		};
+		let construct = redeem
+			.convert::<InternalSharing, _, _>(&mut pruner)
+			.expect("hiding branches is infallible");
+		let pruned = construct
+			.convert::<InternalSharing, _, _>(&mut Finalizer(PhantomData))
+			.expect("finalizing is infallible");
+		(pruned, pruner.hidden)
+	});
+
+	let disp = pruned.display();
+	let program_base64 = disp.program().to_string();
+	let witness_hex = disp.witness().to_string();
+	Ok(HiddenProgram {
+		cmr: pruned.cmr(),
+		program_base64,
+		witness_hex,
+		hidden_branches: hidden,
+	})
+}
@@ -1,38 +1,70 @@
-use crate::hal_simplicity::{elements_address, Program};
+use crate::hal_simplicity::{elements_address_with_leaf_version, script_ver, Program};
 use crate::simplicity::hex::parse::FromHex as _;
 use crate::simplicity::{jet, Amr, Cmr, Ihr};
+use elements::taproot::{LeafVersion, TapLeafHash};
 use serde::Serialize;
 
+use super::{program_source_map, unconstrained_witnesses, SourceMap};
+
 #[derive(Debug, thiserror::Error)]
 pub enum SimplicityInfoError {
 	#[error("invalid program: {0}")]
-	ProgramParse(simplicity::ParseError),
+	ProgramParse(crate::hal_simplicity::ProgramParseError),
 
 	#[error("invalid state: {0}")]
 	StateParse(elements::hashes::hex::HexToArrayError),
+
+	#[error("invalid program encoding at bit {bit}: {error}")]
+	SourceMap {
+		bit: usize,
+		error: crate::simplicity::decode::Error,
+	},
 }
 
 #[derive(Serialize)]
+#[cfg_attr(feature = "daemon", derive(schemars::JsonSchema))]
 pub struct RedeemInfo {
 	pub redeem_base64: String,
 	pub witness_hex: String,
+	#[cfg_attr(feature = "daemon", schemars(with = "String"))]
 	pub amr: Amr,
+	#[cfg_attr(feature = "daemon", schemars(with = "String"))]
 	pub ihr: Ihr,
 }
 
+/// The tapleaf of a program, for adding it to a taptree by other means.
 #[derive(Serialize)]
+#[cfg_attr(feature = "daemon", derive(schemars::JsonSchema))]
+pub struct TapLeafInfo {
+	pub script_hex: String,
+	pub leaf_version: u8,
+	#[cfg_attr(feature = "daemon", schemars(with = "String"))]
+	pub leaf_hash: TapLeafHash,
+}
+
+#[derive(Serialize)]
+#[cfg_attr(feature = "daemon", derive(schemars::JsonSchema))]
 pub struct ProgramInfo {
 	pub jets: &'static str,
 	pub commit_base64: String,
 	pub commit_decode: String,
 	pub type_arrow: String,
+	#[cfg_attr(feature = "daemon", schemars(with = "String"))]
 	pub cmr: Cmr,
+	pub tapleaf: TapLeafInfo,
 	pub liquid_address_unconf: String,
 	pub liquid_testnet_address_unconf: String,
 	pub is_redeem: bool,
 	#[serde(flatten)]
 	#[serde(skip_serializing_if = "Option::is_none")]
 	pub redeem_info: Option<RedeemInfo>,
+	/// The bits each node of the program is encoded in, if asked for.
+	#[serde(skip_serializing_if = "Option::is_none")]
+	pub source_map: Option<SourceMap>,
+	/// Problems found with the program, such as witnesses which a third
+	/// party could change.
+	#[serde(skip_serializing_if = "Vec::is_empty")]
+	pub warnings: Vec<String>,
 }
 
 /// Parse and analyze a Simplicity program.
@@ -40,6 +72,17 @@ pub fn simplicity_info(
 	program: &str,
 	witness: Option<&str>,
 	state: Option<&str>,
+) -> Result<ProgramInfo, SimplicityInfoError> {
+	simplicity_info_with_leaf_version(program, witness, state, simplicity::leaf_version())
+}
+
+/// Parse and analyze a Simplicity program, giving its tapleaf and addresses
+/// under `leaf_version` rather than Simplicity's leaf version.
+pub fn simplicity_info_with_leaf_version(
+	program: &str,
+	witness: Option<&str>,
+	state: Option<&str>,
+	leaf_version: LeafVersion,
 ) -> Result<ProgramInfo, SimplicityInfoError> {
 	// In the future we should attempt to parse as a Bitcoin program if parsing as
 	// Elements fails. May be tricky/annoying in Rust since Program<Elements> is a
@@ -59,6 +102,23 @@ pub fn simplicity_info(
 		}
 	});
 
+	let warnings = unconstrained_witnesses(&program_source_map(&program.commit_prog().to_vec_without_witness())?)
+		.into_iter()
+		.map(|index| {
+			format!(
+				"witness node {} is not checked by any assertion, so a third party may be able to change its value",
+				index
+			)
+		})
+		.collect();
+
+	let (script, _) = script_ver(program.cmr());
+	let tapleaf = TapLeafInfo {
+		script_hex: hex::encode(script.as_bytes()),
+		leaf_version: leaf_version.as_u8(),
+		leaf_hash: TapLeafHash::from_script(&script, leaf_version),
+	};
+
 	let state =
 		state.map(<[u8; 32]>::from_hex).transpose().map_err(SimplicityInfoError::StateParse)?;
 
@@ -69,19 +129,24 @@ pub fn simplicity_info(
 		commit_decode: program.commit_prog().display_expr().to_string(),
 		type_arrow: program.commit_prog().arrow().to_string(),
 		cmr: program.cmr(),
-		liquid_address_unconf: elements_address(
+		tapleaf,
+		liquid_address_unconf: elements_address_with_leaf_version(
 			program.cmr(),
 			state,
 			&elements::AddressParams::LIQUID,
+			leaf_version,
 		)
 		.to_string(),
-		liquid_testnet_address_unconf: elements_address(
+		liquid_testnet_address_unconf: elements_address_with_leaf_version(
 			program.cmr(),
 			state,
 			&elements::AddressParams::LIQUID_TESTNET,
+			leaf_version,
 		)
 		.to_string(),
 		is_redeem: redeem_info.is_some(),
 		redeem_info,
+		source_map: None,
+		warnings,
 	})
 }
@@ -0,0 +1,112 @@
+//! Witness nodes which no assertion checks
+//!
+//! The witness of a program is not committed to by the signatures of the
+//! transaction spending it, so any witness value which the program does not
+//! check can be changed by a third party without invalidating the
+//! transaction. This is found by following the values of the witness nodes
+//! through the nodes of the program: a witness counts as checked once its
+//! value may reach a `case` or an assertion jet (one with no output). This
+//! is a heuristic, which does not see whether the check actually pins the
+//! value down.
+
+use std::collections::BTreeSet;
+use std::str::FromStr as _;
+
+use crate::simplicity::jet::{self, Jet as _};
+
+use super::SourceMap;
+
+/// How the value of a node depends on witnesses and on its input.
+#[derive(Clone, Default)]
+struct Flow {
+	/// The witness nodes whose values may reach the output of the node.
+	witnesses: BTreeSet<usize>,
+	/// Whether the input of the node may reach its output.
+	passes_input: bool,
+	/// Whether the node checks its input.
+	checks_input: bool,
+}
+
+/// Whether the named jet fails on some of its inputs, as it has no output.
+fn is_assertion_jet(name: Option<&str>) -> bool {
+	name.and_then(|name| jet::Elements::from_str(name).ok())
+		.is_some_and(|jet| jet.target_ty().0 == b"1")
+}
+
+/// The indices of the witness nodes of the program whose values no assertion
+/// checks.
+pub fn unconstrained_witnesses(map: &SourceMap) -> Vec<usize> {
+	let mut flows: Vec<Flow> = Vec::with_capacity(map.nodes.len());
+	let mut checked = BTreeSet::new();
+	for node in &map.nodes {
+		let child = |i: usize| &flows[node.children[i]];
+		let flow = match node.node {
+			"iden" => Flow {
+				passes_input: true,
+				..Flow::default()
+			},
+			"witness" => Flow {
+				witnesses: BTreeSet::from([node.index]),
+				..Flow::default()
+			},
+			"jet" => Flow {
+				witnesses: BTreeSet::new(),
+				passes_input: true,
+				checks_input: is_assertion_jet(node.detail.as_deref()),
+			},
+			"injl" | "injr" | "take" | "drop" => child(0).clone(),
+			"pair" => {
+				let (left, right) = (child(0), child(1));
+				Flow {
+					witnesses: left.witnesses.union(&right.witnesses).copied().collect(),
+					passes_input: left.passes_input || right.passes_input,
+					checks_input: left.checks_input || right.checks_input,
+				}
+			}
+			"case" => {
+				let (left, right) = (child(0), child(1));
+				Flow {
+					witnesses: left.witnesses.union(&right.witnesses).copied().collect(),
+					passes_input: true,
+					checks_input: true,
+				}
+			}
+			"comp" => {
+				let (first, second) = (child(0), child(1));
+				if second.checks_input {
+					checked.extend(first.witnesses.iter().copied());
+				}
+				let mut witnesses = second.witnesses.clone();
+				if second.passes_input {
+					witnesses.extend(first.witnesses.iter().copied());
+				}
+				Flow {
+					witnesses,
+					passes_input: first.passes_input && second.passes_input,
+					checks_input: first.checks_input || (first.passes_input && second.checks_input),
+				}
+			}
+			"disconnect" => {
+				let first = child(0);
+				let mut flow = first.clone();
+				if let Some(&second) = node.children.get(1) {
+					let second = &flows[second];
+					if second.checks_input {
+						checked.extend(first.witnesses.iter().copied());
+					}
+					flow.witnesses.extend(second.witnesses.iter().copied());
+				}
+				flow
+			}
+			// unit, word, fail and hidden nodes ignore their input.
+			_ => Flow::default(),
+		};
+		flows.push(flow);
+	}
+
+	map.nodes
+		.iter()
+		.filter(|node| node.node == "witness" && !checked.contains(&node.index))
+		.map(|node| node.index)
+		.collect()
+}
@@ -1,13 +1,27 @@
+pub mod bench;
+pub mod fixtures;
+pub mod hide;
 pub mod info;
+pub mod malleability;
+pub mod prune;
 pub mod pset;
 pub mod sighash;
+pub mod source_map;
+pub mod webide;
 
+pub use bench::*;
+pub use fixtures::*;
+pub use hide::*;
 pub use info::*;
+pub use malleability::*;
+pub use prune::*;
 pub use sighash::*;
+pub use source_map::*;
+pub use webide::*;
 
 use crate::simplicity::bitcoin::{Amount, Denomination};
-use crate::simplicity::elements::confidential;
 use crate::simplicity::elements::hex::FromHex as _;
+use crate::simplicity::elements::{confidential, encode};
 use crate::simplicity::jet::elements::ElementsUtxo;
 
 #[derive(Debug, thiserror::Error)]
@@ -75,3 +89,19 @@ pub fn parse_elements_utxo(s: &str) -> Result<ElementsUtxo, ParseElementsUtxoErr
 		value,
 	})
 }
+
+/// Format an output as `<scriptPubKey>:<asset>:<value>`, as accepted by
+/// [`parse_elements_utxo`].
+pub fn format_elements_utxo(txout: &elements::TxOut) -> String {
+	let asset = match txout.asset {
+		confidential::Asset::Explicit(asset_id) => asset_id.to_string(),
+		asset => encode::serialize_hex(&asset),
+	};
+	let value = match txout.value {
+		confidential::Value::Explicit(sat) => {
+			Amount::from_sat(sat).to_string_in(Denomination::Bitcoin)
+		}
+		value => encode::serialize_hex(&value),
+	};
+	format!("{}:{}:{}", hex::encode(txout.script_pubkey.as_bytes()), asset, value)
+}
@@ -0,0 +1,81 @@
+//! Checking that a pruned program comes from an unpruned one
+//!
+//! A pruned program replaces the branches of `case` nodes which are not taken
+//! by hidden nodes giving only their CMRs. Someone handed a pruned program
+//! by a third party can check it against the program they committed to: the
+//! two must have the same structure, except that each hidden node of the
+//! pruned program must stand for a real subtree of the unpruned one (or for
+//! the same hidden node, as commitment-time programs may have them too).
+
+use std::collections::HashSet;
+
+use serde::Serialize;
+
+use crate::simplicity::Cmr;
+
+use super::{simplicity_source_map, SimplicityInfoError, SourceMap};
+
+#[derive(Serialize)]
+#[cfg_attr(feature = "daemon", derive(schemars::JsonSchema))]
+pub struct PruneCheck {
+	#[cfg_attr(feature = "daemon", schemars(with = "String"))]
+	pub cmr: Cmr,
+	#[cfg_attr(feature = "daemon", schemars(with = "String"))]
+	pub pruned_cmr: Cmr,
+	/// The number of hidden nodes of the pruned program.
+	pub hidden_nodes: usize,
+	pub valid: bool,
+	/// The first difference found between the programs, if any.
+	#[serde(skip_serializing_if = "Option::is_none")]
+	pub mismatch: Option<String>,
+}
+
+/// Compare the nodes of the programs from their roots, returning the first
+/// difference.
+fn compare(unpruned: &SourceMap, pruned: &SourceMap) -> Option<String> {
+	let mut stack = vec![(unpruned.nodes.len() - 1, pruned.nodes.len() - 1)];
+	let mut seen = HashSet::new();
+	while let Some((u, p)) = stack.pop() {
+		if !seen.insert((u, p)) {
+			continue;
+		}
+		let (u, p) = (&unpruned.nodes[u], &pruned.nodes[p]);
+		if p.node == "hidden" {
+			if u.cmr != p.cmr {
+				return Some(format!(
+					"pruned node {} hides CMR {}, but unpruned node {} has CMR {}",
+					p.index, p.cmr, u.index, u.cmr,
+				));
+			}
+			continue;
+		}
+		if u.node != p.node || u.detail != p.detail || u.children.len() != p.children.len() {
+			return Some(format!(
+				"pruned node {} ({}) does not match unpruned node {} ({})",
+				p.index, p.node, u.index, u.node,
+			));
+		}
+		stack.extend(u.children.iter().copied().zip(p.children.iter().copied()));
+	}
+	None
+}
+
+/// Check that `pruned` is a pruning of `unpruned`, both hex or base64-encoded.
+pub fn simplicity_check_prune(
+	unpruned: &str,
+	pruned: &str,
+) -> Result<PruneCheck, SimplicityInfoError> {
+	let unpruned = simplicity_source_map(unpruned)?;
+	let pruned = simplicity_source_map(pruned)?;
+
+	let mismatch = compare(&unpruned, &pruned);
+	// The encoding of a program starts with its number of nodes, which is at least 1.
+	let root_cmr = |map: &SourceMap| map.nodes.last().expect("a program has a node").cmr;
+	Ok(PruneCheck {
+		cmr: root_cmr(&unpruned),
+		pruned_cmr: root_cmr(&pruned),
+		hidden_nodes: pruned.nodes.iter().filter(|node| node.node == "hidden").count(),
+		valid: mismatch.is_none(),
+		mismatch,
+	})
+}
@@ -0,0 +1,220 @@
+// Copyright 2025 Andrew Poelstra
+// SPDX-License-Identifier: CC0-1.0
+
+//! Signing the Simplicity inputs of a PSET on an air-gapped device
+//!
+//! The sighashes are exported as compact JSON, which may be split into BBQr
+//! parts to be shown as a series of QR codes. The signatures come back the
+//! same way, as JSON or as BBQr parts in any order.
+
+use elements::hashes::sha256;
+use elements::taproot::TapLeafHash;
+use serde::Serialize;
+
+use super::{pset_add_signatures, pset_sighashes, PsetSignaturesError, SignerHint, UpdatedPset};
+
+/// The most parts a BBQr series can have, as its part count is two base-36 digits.
+const BBQR_MAX_PARTS: usize = 36 * 36 - 1;
+/// The length of the header of a BBQr part.
+const BBQR_HEADER_LEN: usize = 8;
+/// The alphabet of RFC 4648 base32, which BBQr uses without padding.
+const BASE32_ALPHABET: &[u8; 32] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZ234567";
+
+#[derive(Debug, thiserror::Error)]
+pub enum PsetAirgapError {
+	#[error(transparent)]
+	Signatures(#[from] PsetSignaturesError),
+
+	#[error("QR part size {0} is too small; parts must have at least 16 characters")]
+	PartSizeTooSmall(usize),
+
+	#[error("the export needs {parts} QR parts, more than the {max} of BBQr", max = BBQR_MAX_PARTS)]
+	TooManyParts {
+		parts: usize,
+	},
+
+	#[error("invalid BBQr part: {0}")]
+	BbqrParse(String),
+
+	#[error("unsupported BBQr encoding {0}; give parts encoded in hex (H) or base32 (2)")]
+	BbqrEncoding(char),
+
+	#[error("BBQr parts hold file type {0}, but signatures must be JSON (J)")]
+	BbqrFileType(char),
+
+	#[error("BBQr parts {missing:?} are missing")]
+	BbqrMissingParts {
+		missing: Vec<usize>,
+	},
+
+	#[error("signatures are not valid UTF-8")]
+	SignaturesUtf8,
+}
+
+/// The digest to sign for one Simplicity leaf of one input, as given to an
+/// offline signer.
+#[derive(Serialize)]
+struct ExportedSighash {
+	input: usize,
+	leaf_hash: TapLeafHash,
+	sighash: sha256::Hash,
+	#[serde(skip_serializing_if = "Vec::is_empty")]
+	signers: Vec<SignerHint>,
+}
+
+#[derive(Serialize)]
+#[cfg_attr(feature = "daemon", derive(schemars::JsonSchema))]
+pub struct SighashExport {
+	/// The sighashes as compact JSON.
+	pub export: String,
+	/// The export split into BBQr parts, if a part size was given.
+	#[serde(skip_serializing_if = "Vec::is_empty")]
+	pub bbqr: Vec<String>,
+}
+
+fn base36_digits(n: usize) -> String {
+	let digit = |d: usize| char::from_digit(d as u32, 36).expect("below 36").to_ascii_uppercase();
+	[digit(n / 36), digit(n % 36)].iter().collect()
+}
+
+fn base32_encode(data: &[u8]) -> String {
+	let mut encoded = String::new();
+	for chunk in data.chunks(5) {
+		let mut buf = [0u8; 5];
+		buf[..chunk.len()].copy_from_slice(chunk);
+		let bits = buf.iter().fold(0u64, |acc, &b| (acc << 8) | u64::from(b));
+		let n_chars = (chunk.len() * 8).div_ceil(5);
+		for i in 0..n_chars {
+			encoded.push(BASE32_ALPHABET[(bits >> (35 - 5 * i)) as usize & 31] as char);
+		}
+	}
+	encoded
+}
+
+fn base32_decode(encoded: &str) -> Option<Vec<u8>> {
+	let mut data = vec![];
+	let (mut bits, mut n_bits) = (0u32, 0);
+	for c in encoded.bytes() {
+		let value = BASE32_ALPHABET.iter().position(|&a| a == c)?;
+		bits = (bits << 5) | value as u32;
+		n_bits += 5;
+		if n_bits >= 8 {
+			n_bits -= 8;
+			data.push((bits >> n_bits) as u8);
+		}
+	}
+	Some(data)
+}
+
+/// Split JSON into base32 BBQr parts of at most `part_size` characters.
+fn bbqr_encode(json: &str, part_size: usize) -> Result<Vec<String>, PsetAirgapError> {
+	if part_size < 2 * BBQR_HEADER_LEN {
+		return Err(PsetAirgapError::PartSizeTooSmall(part_size));
+	}
+	let encoded = base32_encode(json.as_bytes());
+	// All parts but the last must hold a whole number of 5-byte groups.
+	let capacity = (part_size - BBQR_HEADER_LEN) / 8 * 8;
+	// Spread the data evenly over as few parts as fit it.
+	let parts = encoded.len().div_ceil(capacity);
+	let chunk_len = encoded.len().div_ceil(parts).div_ceil(8) * 8;
+	let chunks: Vec<_> = encoded.as_bytes().chunks(chunk_len).collect();
+	if chunks.len() > BBQR_MAX_PARTS {
+		return Err(PsetAirgapError::TooManyParts {
+			parts: chunks.len(),
+		});
+	}
+	Ok(chunks
+		.iter()
+		.enumerate()
+		.map(|(i, chunk)| {
+			let chunk = std::str::from_utf8(chunk).expect("base32 is ASCII");
+			format!("B$2J{}{}{}", base36_digits(chunks.len()), base36_digits(i), chunk)
+		})
+		.collect())
+}
+
+/// Join BBQr parts, given in any order, into the JSON they hold.
+fn bbqr_decode(parts: &[&str]) -> Result<String, PsetAirgapError> {
+	let mut encoding = None;
+	let mut chunks: Vec<Option<&str>> = vec![];
+	for part in parts {
+		let parse_err = || PsetAirgapError::BbqrParse(part.to_string());
+		let header = part
+			.get(..BBQR_HEADER_LEN)
+			.filter(|h| h.is_ascii() && h.starts_with("B$"))
+			.ok_or_else(parse_err)?;
+		let mut chars = header[2..4].chars();
+		let (part_encoding, file_type) = (chars.next().unwrap(), chars.next().unwrap());
+		let total = usize::from_str_radix(&header[4..6], 36).map_err(|_| parse_err())?;
+		let index = usize::from_str_radix(&header[6..8], 36).map_err(|_| parse_err())?;
+		if file_type != 'J' {
+			return Err(PsetAirgapError::BbqrFileType(file_type));
+		}
+		if *encoding.get_or_insert(part_encoding) != part_encoding || index >= total {
+			return Err(parse_err());
+		}
+		if chunks.is_empty() {
+			chunks.resize(total, None);
+		} else if chunks.len() != total {
+			return Err(parse_err());
+		}
+		chunks[index] = Some(&part[BBQR_HEADER_LEN..]);
+	}
+
+	let missing: Vec<_> = (0..chunks.len()).filter(|&i| chunks[i].is_none()).collect();
+	if !missing.is_empty() {
+		return Err(PsetAirgapError::BbqrMissingParts {
+			missing,
+		});
+	}
+	let encoded: String = chunks.into_iter().flatten().collect();
+	let parse_err = || PsetAirgapError::BbqrParse(encoded.clone());
+	let data = match encoding {
+		Some('2') => base32_decode(&encoded).ok_or_else(parse_err)?,
+		Some('H') => hex::decode(&encoded).map_err(|_| parse_err())?,
+		Some(encoding) => return Err(PsetAirgapError::BbqrEncoding(encoding)),
+		None => vec![],
+	};
+	String::from_utf8(data).map_err(|_| PsetAirgapError::SignaturesUtf8)
+}
+
+/// Export the digest to sign for every Simplicity leaf of every input of a
+/// PSET as compact JSON, split into BBQr parts of at most `part_size`
+/// characters if it is given.
+pub fn pset_export_sighashes(
+	pset_b64: &str,
+	genesis_hash: Option<&str>,
+	part_size: Option<usize>,
+) -> Result<SighashExport, PsetAirgapError> {
+	let sighashes: Vec<_> = pset_sighashes(pset_b64, genesis_hash)?
+		.sighashes
+		.into_iter()
+		.map(|sighash| ExportedSighash {
+			input: sighash.input,
+			leaf_hash: sighash.leaf_hash,
+			sighash: sighash.sighash,
+			signers: sighash.signers,
+		})
+		.collect();
+	let export = serde_json::to_string(&sighashes).expect("sighashes serialize");
+	let bbqr = part_size.map(|size| bbqr_encode(&export, size)).transpose()?.unwrap_or_default();
+	Ok(SighashExport {
+		export,
+		bbqr,
+	})
+}
+
+/// Check signatures made offline and add them to a PSET. The signatures are
+/// given either as a JSON list, as for [`pset_add_signatures`], or as the
+/// BBQr parts of one, in any order.
+pub fn pset_import_signatures(
+	pset_b64: &str,
+	signatures: &[&str],
+	genesis_hash: Option<&str>,
+) -> Result<UpdatedPset, PsetAirgapError> {
+	let json = match signatures {
+		[json] if !json.starts_with("B$") => json.to_string(),
+		parts => bbqr_decode(parts)?,
+	};
+	Ok(pset_add_signatures(pset_b64, &json, genesis_hash)?)
+}
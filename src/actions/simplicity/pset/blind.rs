@@ -0,0 +1,191 @@
+// Copyright 2025 Andrew Poelstra
+// SPDX-License-Identifier: CC0-1.0
+
+//! The blinder role of a PSET shared between parties
+//!
+//! When several parties build a confidential transaction together, each
+//! output which is to be blinded is marked with its blinding key and the
+//! index of the input whose owner blinds it. That party fills in the
+//! commitments and range and surjection proofs, along with proofs that the
+//! commitments open to the output's explicit amount and asset, so that the
+//! other parties can check what they are signing for.
+
+use elements::pset::PartiallySignedTransaction;
+use elements::secp256k1_zkp::SECP256K1;
+use elements::{BlindAssetProofs as _, BlindValueProofs as _};
+use serde::Serialize;
+
+use super::UpdatedPset;
+
+#[derive(Debug, thiserror::Error)]
+pub enum PsetBlindError {
+	#[error("invalid PSET: {0}")]
+	PsetDecode(elements::pset::ParseError),
+
+	#[error("invalid output index: {0}")]
+	OutputIndexParse(std::num::ParseIntError),
+
+	#[error("output index {index} out-of-range for PSET with {total} outputs")]
+	OutputIndexOutOfRange {
+		index: usize,
+		total: usize,
+	},
+
+	#[error("invalid blinder index: {0}")]
+	BlinderIndexParse(std::num::ParseIntError),
+
+	#[error("blinder index {index} out-of-range for PSET with {total} inputs")]
+	BlinderIndexOutOfRange {
+		index: usize,
+		total: usize,
+	},
+
+	#[error("invalid blinding key: {0}")]
+	BlindingKeyParse(elements::bitcoin::key::ParsePublicKeyError),
+}
+
+/// How far an output has been blinded.
+#[derive(Serialize, Clone, Copy, PartialEq, Eq, Debug)]
+#[cfg_attr(feature = "daemon", derive(schemars::JsonSchema))]
+#[serde(rename_all = "snake_case")]
+pub enum BlindingState {
+	/// The output is not marked for blinding.
+	Explicit,
+	/// The output is marked for blinding, but nothing has been filled in.
+	Unblinded,
+	/// Some but not all of the blinding data has been filled in.
+	PartiallyBlinded,
+	Blinded,
+}
+
+#[derive(Serialize)]
+#[cfg_attr(feature = "daemon", derive(schemars::JsonSchema))]
+pub struct OutputBlinding {
+	pub index: usize,
+	pub state: BlindingState,
+	/// The input whose owner is to blind the output.
+	#[serde(skip_serializing_if = "Option::is_none")]
+	pub blinder_index: Option<u32>,
+	/// Whether the proof that the value commitment opens to the explicit
+	/// amount verifies, if such a proof was given.
+	#[serde(skip_serializing_if = "Option::is_none")]
+	pub value_proof_valid: Option<bool>,
+	/// Whether the proof that the asset commitment opens to the explicit
+	/// asset verifies, if such a proof was given.
+	#[serde(skip_serializing_if = "Option::is_none")]
+	pub asset_proof_valid: Option<bool>,
+}
+
+#[derive(Serialize)]
+#[cfg_attr(feature = "daemon", derive(schemars::JsonSchema))]
+pub struct BlindingStatus {
+	pub outputs: Vec<OutputBlinding>,
+	/// Whether every output marked for blinding is blinded, with no invalid proofs.
+	pub complete: bool,
+}
+
+/// The state of an output's blinding, checking any proofs given for it.
+fn output_blinding(index: usize, output: &elements::pset::Output) -> OutputBlinding {
+	let state = if !output.is_marked_for_blinding() {
+		BlindingState::Explicit
+	} else if output.is_fully_blinded() {
+		BlindingState::Blinded
+	} else if output.is_partially_blinded() {
+		BlindingState::PartiallyBlinded
+	} else {
+		BlindingState::Unblinded
+	};
+
+	let value_proof_valid = output.blind_value_proof.as_ref().map(|proof| {
+		match (output.amount, output.asset_comm, output.amount_comm) {
+			(Some(amount), Some(asset_comm), Some(amount_comm)) => {
+				proof.blind_value_proof_verify(SECP256K1, amount, asset_comm, amount_comm)
+			}
+			_ => false,
+		}
+	});
+	let asset_proof_valid =
+		output.blind_asset_proof.as_ref().map(|proof| match (output.asset, output.asset_comm) {
+			(Some(asset), Some(asset_comm)) => {
+				proof.blind_asset_proof_verify(SECP256K1, asset, asset_comm)
+			}
+			_ => false,
+		});
+
+	OutputBlinding {
+		index,
+		state,
+		blinder_index: output.blinder_index,
+		value_proof_valid,
+		asset_proof_valid,
+	}
+}
+
+/// The indices of the outputs of `pset` which are marked for blinding but
+/// not yet fully blinded.
+pub(super) fn unblinded_outputs(pset: &PartiallySignedTransaction) -> Vec<usize> {
+	pset.outputs()
+		.iter()
+		.enumerate()
+		.filter(|(_, output)| output.is_marked_for_blinding() && !output.is_fully_blinded())
+		.map(|(index, _)| index)
+		.collect()
+}
+
+/// Mark an output of a PSET for blinding to `blinding_key`, by the owner of
+/// the input at `blinder_index`
+pub fn pset_mark_blinding(
+	pset_b64: &str,
+	output_idx: &str,
+	blinding_key: &str,
+	blinder_idx: &str,
+) -> Result<UpdatedPset, PsetBlindError> {
+	let mut pset: PartiallySignedTransaction =
+		pset_b64.parse().map_err(PsetBlindError::PsetDecode)?;
+	let output_idx: usize = output_idx.parse().map_err(PsetBlindError::OutputIndexParse)?;
+	let blinder_idx: u32 = blinder_idx.parse().map_err(PsetBlindError::BlinderIndexParse)?;
+	let blinding_key = blinding_key.parse().map_err(PsetBlindError::BlindingKeyParse)?;
+
+	let n_inputs = pset.n_inputs();
+	if blinder_idx as usize >= n_inputs {
+		return Err(PsetBlindError::BlinderIndexOutOfRange {
+			index: blinder_idx as usize,
+			total: n_inputs,
+		});
+	}
+	let n_outputs = pset.n_outputs();
+	let output =
+		pset.outputs_mut().get_mut(output_idx).ok_or(PsetBlindError::OutputIndexOutOfRange {
+			index: output_idx,
+			total: n_outputs,
+		})?;
+	output.blinding_key = Some(blinding_key);
+	output.blinder_index = Some(blinder_idx);
+
+	Ok(UpdatedPset {
+		pset: pset.to_string(),
+		updated_values: vec!["blinding_key", "blinder_index"],
+	})
+}
+
+/// Report how far each output of a PSET has been blinded, verifying the
+/// proofs that blinded outputs open to their explicit amounts and assets
+pub fn pset_check_blinding(pset_b64: &str) -> Result<BlindingStatus, PsetBlindError> {
+	let pset: PartiallySignedTransaction = pset_b64.parse().map_err(PsetBlindError::PsetDecode)?;
+
+	let outputs: Vec<_> = pset
+		.outputs()
+		.iter()
+		.enumerate()
+		.map(|(index, output)| output_blinding(index, output))
+		.collect();
+	let complete = outputs.iter().all(|output| {
+		matches!(output.state, BlindingState::Explicit | BlindingState::Blinded)
+			&& output.value_proof_valid != Some(false)
+			&& output.asset_proof_valid != Some(false)
+	});
+	Ok(BlindingStatus {
+		outputs,
+		complete,
+	})
+}
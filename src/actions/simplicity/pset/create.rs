@@ -97,8 +97,13 @@ impl OutputSpec {
 	}
 }
 
-/// Create an empty PSET
-pub fn pset_create(inputs_json: &str, outputs_json: &str) -> Result<UpdatedPset, PsetCreateError> {
+/// Create an unsigned transaction spending the given input outpoints to the
+/// given outputs. An output to the address `fee` is a fee output, and one to
+/// `data:<hex>` is an OP_RETURN output carrying the data.
+pub fn unsigned_transaction(
+	inputs_json: &str,
+	outputs_json: &str,
+) -> Result<Transaction, PsetCreateError> {
 	// Parse inputs JSON
 	let input_specs: Vec<InputSpec> =
 		serde_json::from_str(inputs_json).map_err(PsetCreateError::InputsJsonParse)?;
@@ -158,13 +163,17 @@ pub fn pset_create(inputs_json: &str, outputs_json: &str) -> Result<UpdatedPset,
 		});
 	}
 
-	// Create the transaction
-	let tx = Transaction {
+	Ok(Transaction {
 		version: 2,
 		lock_time: elements::LockTime::ZERO,
 		input: inputs,
 		output: outputs,
-	};
+	})
+}
+
+/// Create an empty PSET
+pub fn pset_create(inputs_json: &str, outputs_json: &str) -> Result<UpdatedPset, PsetCreateError> {
+	let tx = unsigned_transaction(inputs_json, outputs_json)?;
 
 	// Create PSET from transaction
 	let pset = PartiallySignedTransaction::from_tx(tx);
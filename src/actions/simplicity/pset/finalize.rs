@@ -1,10 +1,12 @@
 // Copyright 2025 Andrew Poelstra
 // SPDX-License-Identifier: CC0-1.0
 
+use elements::taproot::LeafVersion;
+
 use crate::hal_simplicity::Program;
 use crate::simplicity::jet;
 
-use super::{execution_environment, PsetError, UpdatedPset};
+use super::{execution_environment_with_leaf_version, unblinded_outputs, PsetError, UpdatedPset};
 
 #[derive(Debug, thiserror::Error)]
 pub enum PsetFinalizeError {
@@ -18,13 +20,18 @@ pub enum PsetFinalizeError {
 	InputIndexParse(std::num::ParseIntError),
 
 	#[error("invalid program: {0}")]
-	ProgramParse(simplicity::ParseError),
+	ProgramParse(crate::hal_simplicity::ProgramParseError),
 
 	#[error("program does not have a redeem node")]
 	NoRedeemNode,
 
 	#[error("failed to prune program: {0}")]
 	ProgramPrune(simplicity::bit_machine::ExecutionError),
+
+	#[error("outputs {outputs:?} are marked for blinding but not yet blinded")]
+	OutputsNotBlinded {
+		outputs: Vec<usize>,
+	},
 }
 
 /// Attach a Simplicity program and witness to a PSET input
@@ -34,6 +41,39 @@ pub fn pset_finalize(
 	program: &str,
 	witness: &str,
 	genesis_hash: Option<&str>,
+) -> Result<UpdatedPset, PsetFinalizeError> {
+	pset_finalize_with_leaf_version(
+		pset_b64,
+		input_idx,
+		program,
+		witness,
+		genesis_hash,
+		simplicity::leaf_version(),
+	)
+}
+
+/// Attach a Simplicity program and witness to a PSET input, whose leaf is
+/// under `leaf_version` rather than Simplicity's leaf version
+pub fn pset_finalize_with_leaf_version(
+	pset_b64: &str,
+	input_idx: &str,
+	program: &str,
+	witness: &str,
+	genesis_hash: Option<&str>,
+	leaf_version: LeafVersion,
+) -> Result<UpdatedPset, PsetFinalizeError> {
+	let program = Program::<jet::Elements>::from_str(program, Some(witness))
+		.map_err(PsetFinalizeError::ProgramParse)?;
+	pset_finalize_program(pset_b64, input_idx, &program, genesis_hash, leaf_version)
+}
+
+/// Attach an already-decoded Simplicity program and witness to a PSET input
+pub fn pset_finalize_program(
+	pset_b64: &str,
+	input_idx: &str,
+	program: &Program<jet::Elements>,
+	genesis_hash: Option<&str>,
+	leaf_version: LeafVersion,
 ) -> Result<UpdatedPset, PsetFinalizeError> {
 	// 1. Parse everything.
 	let mut pset: elements::pset::PartiallySignedTransaction =
@@ -41,12 +81,22 @@ pub fn pset_finalize(
 	let input_idx: u32 = input_idx.parse().map_err(PsetFinalizeError::InputIndexParse)?;
 	let input_idx_usize = input_idx as usize; // 32->usize cast ok on almost all systems
 
-	let program = Program::<jet::Elements>::from_str(program, Some(witness))
-		.map_err(PsetFinalizeError::ProgramParse)?;
+	// Blinding changes the outputs, and so the transaction the program signs for.
+	let unblinded = unblinded_outputs(&pset);
+	if !unblinded.is_empty() {
+		return Err(PsetFinalizeError::OutputsNotBlinded {
+			outputs: unblinded,
+		});
+	}
 
 	// 2. Extract transaction environment.
-	let (tx_env, control_block, tap_leaf) =
-		execution_environment(&pset, input_idx_usize, program.cmr(), genesis_hash)?;
+	let (tx_env, control_block, tap_leaf) = execution_environment_with_leaf_version(
+		&pset,
+		input_idx_usize,
+		program.cmr(),
+		genesis_hash,
+		leaf_version,
+	)?;
 	let cb_serialized = control_block.serialize();
 
 	// 3. Prune program.
@@ -1,23 +1,30 @@
 // Copyright 2025 Andrew Poelstra
 // SPDX-License-Identifier: CC0-1.0
 
+mod airgap;
+mod blind;
 mod create;
 mod extract;
 mod finalize;
 mod run;
+mod run_diff;
+mod signatures;
 mod update_input;
 
+pub use airgap::*;
+pub use blind::*;
 pub use create::*;
 pub use extract::*;
 pub use finalize::*;
 pub use run::*;
+pub use run_diff::*;
+pub use signatures::*;
 pub use update_input::*;
 
 use std::sync::Arc;
 
-use elements::hashes::Hash as _;
 use elements::pset::PartiallySignedTransaction;
-use elements::taproot::ControlBlock;
+use elements::taproot::{ControlBlock, LeafVersion};
 use elements::Script;
 use serde::Serialize;
 
@@ -48,6 +55,7 @@ pub enum PsetError {
 }
 
 #[derive(Serialize)]
+#[cfg_attr(feature = "daemon", derive(schemars::JsonSchema))]
 pub struct UpdatedPset {
 	pub pset: String,
 	pub updated_values: Vec<&'static str>,
@@ -59,6 +67,24 @@ pub fn execution_environment(
 	input_idx: usize,
 	cmr: Cmr,
 	genesis_hash: Option<&str>,
+) -> Result<(ElementsEnv<Arc<elements::Transaction>>, ControlBlock, Script), PsetError> {
+	execution_environment_with_leaf_version(
+		pset,
+		input_idx,
+		cmr,
+		genesis_hash,
+		simplicity::leaf_version(),
+	)
+}
+
+/// Like [`execution_environment`], but looking for the program's leaf under
+/// `leaf_version` rather than Simplicity's leaf version.
+pub fn execution_environment_with_leaf_version(
+	pset: &PartiallySignedTransaction,
+	input_idx: usize,
+	cmr: Cmr,
+	genesis_hash: Option<&str>,
+	leaf_version: LeafVersion,
 ) -> Result<(ElementsEnv<Arc<elements::Transaction>>, ControlBlock, Script), PsetError> {
 	let n_inputs = pset.n_inputs();
 	let input = pset.inputs().get(input_idx).ok_or(PsetError::InputIndexOutOfRange {
@@ -66,22 +92,19 @@ pub fn execution_environment(
 		total: n_inputs,
 	})?;
 
-	// Default to Liquid Testnet genesis block
+	// Default to the Liquid testnet genesis block.
 	let genesis_hash = match genesis_hash {
 		Some(s) => s.parse().map_err(PsetError::GenesisHashParse)?,
-		None => elements::BlockHash::from_byte_array([
-			// copied out of simplicity-webide source
-			0xc1, 0xb1, 0x6a, 0xe2, 0x4f, 0x24, 0x23, 0xae, 0xa2, 0xea, 0x34, 0x55, 0x22, 0x92,
-			0x79, 0x3b, 0x5b, 0x5e, 0x82, 0x99, 0x9a, 0x1e, 0xed, 0x81, 0xd5, 0x6a, 0xee, 0x52,
-			0x8e, 0xda, 0x71, 0xa7,
-		]),
+		None => {
+			crate::Network::LiquidTestnet.genesis_hash().expect("Liquid testnet has a genesis hash")
+		}
 	};
 
 	// Unlike in the 'update-input' case we don't insist on any particular form of
 	// the Taptree. We just look for the CMR in the list.
 	let mut control_block_leaf = None;
 	for (cb, script_ver) in &input.tap_scripts {
-		if script_ver.1 == simplicity::leaf_version() && &script_ver.0[..] == cmr.as_ref() {
+		if script_ver.1 == leaf_version && &script_ver.0[..] == cmr.as_ref() {
 			control_block_leaf = Some((cb.clone(), script_ver.0.clone()));
 		}
 	}
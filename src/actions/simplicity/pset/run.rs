@@ -1,6 +1,10 @@
 // Copyright 2025 Andrew Poelstra
 // SPDX-License-Identifier: CC0-1.0
 
+use std::collections::{BTreeMap, VecDeque};
+use std::ops::ControlFlow;
+use std::panic::{self, AssertUnwindSafe};
+
 use serde::Serialize;
 
 use crate::hal_simplicity::Program;
@@ -22,16 +26,20 @@ pub enum PsetRunError {
 	InputIndexParse(std::num::ParseIntError),
 
 	#[error("invalid program: {0}")]
-	ProgramParse(simplicity::ParseError),
+	ProgramParse(crate::hal_simplicity::ProgramParseError),
 
 	#[error("program does not have a redeem node")]
 	NoRedeemNode,
 
 	#[error("failed to construct bit machine: {0}")]
 	BitMachineConstruction(simplicity::bit_machine::LimitError),
+
+	#[error("the run was cancelled")]
+	Cancelled,
 }
 
-#[derive(Serialize)]
+#[derive(Serialize, PartialEq)]
+#[cfg_attr(feature = "daemon", derive(schemars::JsonSchema))]
 pub struct JetCall {
 	pub jet: String,
 	pub source_ty: String,
@@ -44,56 +52,151 @@ pub struct JetCall {
 }
 
 #[derive(Serialize)]
+#[cfg_attr(feature = "daemon", derive(schemars::JsonSchema))]
 pub struct RunResponse {
 	pub success: bool,
 	pub jets: Vec<JetCall>,
+	/// Set if calls were left out of `jets` to bound the memory used.
+	#[serde(skip_serializing_if = "Option::is_none")]
+	pub truncated: Option<TruncatedTrace>,
+}
+
+/// What is kept of the calls which did not fit in a bounded trace.
+#[derive(Serialize)]
+#[cfg_attr(feature = "daemon", derive(schemars::JsonSchema))]
+pub struct TruncatedTrace {
+	/// The number of calls left out between `jets` and `last_jets`.
+	pub omitted: usize,
+	/// The last calls of the run.
+	pub last_jets: Vec<JetCall>,
+	/// The number of calls of each jet over the whole run.
+	pub counts: BTreeMap<String, usize>,
+}
+
+/// How many of the first and last jet calls of a run are kept, unless the
+/// caller asks to keep more or all of them.
+pub const DEFAULT_MAX_JETS: usize = 1000;
+
+/// Collects the jet calls of a run, keeping at most the first and last
+/// `max_jets` of them if a limit is given.
+struct JetTracker<F> {
+	max_jets: Option<usize>,
+	first: Vec<JetCall>,
+	last: VecDeque<JetCall>,
+	total: usize,
+	counts: BTreeMap<String, usize>,
+	on_jet: F,
+}
+
+impl<F> JetTracker<F> {
+	fn new(max_jets: Option<usize>, on_jet: F) -> Self {
+		Self {
+			max_jets,
+			first: vec![],
+			last: VecDeque::new(),
+			total: 0,
+			counts: BTreeMap::new(),
+			on_jet,
+		}
+	}
+
+	fn record(&mut self, call: JetCall) {
+		self.total += 1;
+		let Some(max_jets) = self.max_jets else {
+			self.first.push(call);
+			return;
+		};
+
+		*self.counts.entry(call.jet.clone()).or_default() += 1;
+		if self.first.len() < max_jets {
+			self.first.push(call);
+		} else if max_jets > 0 {
+			if self.last.len() == max_jets {
+				self.last.pop_front();
+			}
+			self.last.push_back(call);
+		}
+	}
+
+	/// The kept calls, and what is known of the others.
+	fn finish(self) -> (Vec<JetCall>, Option<TruncatedTrace>) {
+		let mut jets = self.first;
+		let omitted = self.total - jets.len() - self.last.len();
+		if omitted == 0 {
+			jets.extend(self.last);
+			return (jets, None);
+		}
+		let truncated = TruncatedTrace {
+			omitted,
+			last_jets: self.last.into(),
+			counts: self.counts,
+		};
+		(jets, Some(truncated))
+	}
 }
 
-struct JetTracker(Vec<JetCall>);
+/// The call of a jet node, from what the bit machine gives its tracker.
+pub(super) fn jet_call<J: jet::Jet>(
+	node: &simplicity::RedeemNode<J>,
+	mut input: FrameIter,
+	output: NodeOutput,
+) -> Option<JetCall> {
+	let node::Inner::Jet(jet) = node.inner() else {
+		return None;
+	};
+	let input_value = Value::from_padded_bits(&mut input, &node.arrow().source)
+		.expect("valid value from bit machine");
+
+	let (success, output_value) = match output {
+		NodeOutput::NonTerminal => unreachable!(),
+		NodeOutput::JetFailed => (false, Value::unit()),
+		NodeOutput::Success(mut iter) => (
+			true,
+			Value::from_padded_bits(&mut iter, &node.arrow().target)
+				.expect("valid value from bit machine"),
+		),
+	};
+
+	let jet_name = jet.to_string();
+	let equality_check = if jet_name.strip_prefix("eq_").is_some() {
+		let (left, right) = input_value.as_product().expect("equality jets take a pair of values");
+		Some((left.to_value().to_string(), right.to_value().to_string()))
+	} else {
+		None
+	};
 
-impl<J: jet::Jet> ExecTracker<J> for JetTracker {
+	Some(JetCall {
+		jet: jet_name,
+		source_ty: jet.source_ty().to_final().to_string(),
+		target_ty: jet.target_ty().to_final().to_string(),
+		success,
+		input_value: input_value.to_string(),
+		output_value: output_value.to_string(),
+		equality_check,
+	})
+}
+
+/// Unwound with to stop the bit machine, which has no other way to stop early.
+struct Cancelled;
+
+impl<J: jet::Jet, F: FnMut(&JetCall) -> ControlFlow<()>> ExecTracker<J> for JetTracker<F> {
 	fn visit_node(
 		&mut self,
 		node: &simplicity::RedeemNode<J>,
-		mut input: FrameIter,
+		input: FrameIter,
 		output: NodeOutput,
 	) {
-		if let node::Inner::Jet(jet) = node.inner() {
-			let input_value = Value::from_padded_bits(&mut input, &node.arrow().source)
-				.expect("valid value from bit machine");
-
-			let (success, output_value) = match output {
-				NodeOutput::NonTerminal => unreachable!(),
-				NodeOutput::JetFailed => (false, Value::unit()),
-				NodeOutput::Success(mut iter) => (
-					true,
-					Value::from_padded_bits(&mut iter, &node.arrow().target)
-						.expect("valid value from bit machine"),
-				),
-			};
-
-			let jet_name = jet.to_string();
-			let equality_check = if jet_name.strip_prefix("eq_").is_some() {
-				let (left, right) = input_value.as_product().unwrap();
-				Some((left.to_value().to_string(), right.to_value().to_string()))
-			} else {
-				None
-			};
-
-			self.0.push(JetCall {
-				jet: jet_name,
-				source_ty: jet.source_ty().to_final().to_string(),
-				target_ty: jet.target_ty().to_final().to_string(),
-				success,
-				input_value: input_value.to_string(),
-				output_value: output_value.to_string(),
-				equality_check,
-			});
+		if let Some(call) = jet_call(node, input, output) {
+			if (self.on_jet)(&call).is_break() {
+				panic::resume_unwind(Box::new(Cancelled));
+			}
+			self.record(call);
 		}
 	}
 }
 
-/// Run a Simplicity program in the context of a PSET input
+/// Run a Simplicity program in the context of a PSET input, keeping at most
+/// the first and last [`DEFAULT_MAX_JETS`] jet calls.
 pub fn pset_run(
 	pset_b64: &str,
 	input_idx: &str,
@@ -101,15 +204,80 @@ pub fn pset_run(
 	witness: &str,
 	genesis_hash: Option<&str>,
 ) -> Result<RunResponse, PsetRunError> {
+	let max_jets = Some(DEFAULT_MAX_JETS);
+	pset_run_with_callback(pset_b64, input_idx, program, witness, genesis_hash, max_jets, |_| {
+		ControlFlow::Continue(())
+	})
+}
+
+/// Run a Simplicity program in the context of a PSET input, calling `on_jet`
+/// for each jet as soon as it has executed. The run stops, failing with
+/// [`PsetRunError::Cancelled`], as soon as `on_jet` breaks.
+///
+/// If `max_jets` is given, only the first and last `max_jets` calls are kept in
+/// the response, along with the number of calls of each jet. Without it every
+/// call is kept, which for a large program can take a great deal of memory;
+/// [`DEFAULT_MAX_JETS`] is the usual bound.
+pub fn pset_run_with_callback(
+	pset_b64: &str,
+	input_idx: &str,
+	program: &str,
+	witness: &str,
+	genesis_hash: Option<&str>,
+	max_jets: Option<usize>,
+	on_jet: impl FnMut(&JetCall) -> ControlFlow<()>,
+) -> Result<RunResponse, PsetRunError> {
+	let program = Program::<jet::Elements>::from_str(program, Some(witness))
+		.map_err(PsetRunError::ProgramParse)?;
+	pset_run_program(pset_b64, input_idx, &program, genesis_hash, max_jets, on_jet)
+}
+
+/// Run an already-decoded Simplicity program in the context of a PSET input,
+/// calling `on_jet` for each jet as soon as it has executed.
+///
+/// `max_jets` bounds the calls kept in the response, as for
+/// [`pset_run_with_callback`].
+pub fn pset_run_program(
+	pset_b64: &str,
+	input_idx: &str,
+	program: &Program<jet::Elements>,
+	genesis_hash: Option<&str>,
+	max_jets: Option<usize>,
+	on_jet: impl FnMut(&JetCall) -> ControlFlow<()>,
+) -> Result<RunResponse, PsetRunError> {
+	let mut tracker = JetTracker::new(max_jets, on_jet);
+	let run = AssertUnwindSafe(|| {
+		run_with_tracker(pset_b64, input_idx, program, genesis_hash, &mut tracker)
+	});
+	let success = match panic::catch_unwind(run) {
+		Ok(success) => success?,
+		Err(payload) if payload.is::<Cancelled>() => return Err(PsetRunError::Cancelled),
+		Err(payload) => panic::resume_unwind(payload),
+	};
+	let (jets, truncated) = tracker.finish();
+	Ok(RunResponse {
+		success,
+		jets,
+		truncated,
+	})
+}
+
+/// Run an already-decoded Simplicity program in the context of a PSET input,
+/// passing each node it executes to `tracker`, and return whether it
+/// succeeded.
+pub(super) fn run_with_tracker(
+	pset_b64: &str,
+	input_idx: &str,
+	program: &Program<jet::Elements>,
+	genesis_hash: Option<&str>,
+	tracker: &mut impl ExecTracker<jet::Elements>,
+) -> Result<bool, PsetRunError> {
 	// 1. Parse everything.
 	let pset: elements::pset::PartiallySignedTransaction =
 		pset_b64.parse().map_err(PsetRunError::PsetDecode)?;
 	let input_idx: u32 = input_idx.parse().map_err(PsetRunError::InputIndexParse)?;
 	let input_idx_usize = input_idx as usize; // 32->usize cast ok on almost all systems
 
-	let program = Program::<jet::Elements>::from_str(program, Some(witness))
-		.map_err(PsetRunError::ProgramParse)?;
-
 	// 2. Extract transaction environment.
 	let (tx_env, _control_block, _tap_leaf) =
 		execution_environment(&pset, input_idx_usize, program.cmr(), genesis_hash)?;
@@ -119,11 +287,6 @@ pub fn pset_run(
 
 	let mut mac =
 		BitMachine::for_program(redeem_node).map_err(PsetRunError::BitMachineConstruction)?;
-	let mut tracker = JetTracker(vec![]);
 	// Eat success/failure. FIXME should probably report this to the user.
-	let success = mac.exec_with_tracker(redeem_node, &tx_env, &mut tracker).is_ok();
-	Ok(RunResponse {
-		success,
-		jets: tracker.0,
-	})
+	Ok(mac.exec_with_tracker(redeem_node, &tx_env, tracker).is_ok())
 }
@@ -0,0 +1,119 @@
+// Copyright 2025 Andrew Poelstra
+// SPDX-License-Identifier: CC0-1.0
+
+use serde::Serialize;
+
+use crate::hal_simplicity::Program;
+use crate::simplicity::bit_machine::{ExecTracker, FrameIter, NodeOutput};
+use crate::simplicity::{jet, node, Cmr};
+
+use super::{jet_call, run_with_tracker, JetCall, PsetRunError};
+
+/// A step of a run which may differ between two witnesses.
+#[derive(Serialize, PartialEq)]
+#[cfg_attr(feature = "daemon", derive(schemars::JsonSchema))]
+#[serde(tag = "step", rename_all = "snake_case")]
+pub enum RunStep {
+	/// A jet was called.
+	Jet(JetCall),
+	/// A `case` node took one of its branches.
+	Branch {
+		#[cfg_attr(feature = "daemon", schemars(with = "String"))]
+		cmr: Cmr,
+		right: bool,
+	},
+}
+
+/// Where two runs of a program first differ.
+#[derive(Serialize)]
+#[cfg_attr(feature = "daemon", derive(schemars::JsonSchema))]
+pub struct RunDivergence {
+	/// The number of steps the runs have in common.
+	pub step: usize,
+	/// The next step of the first run, unless it has ended.
+	pub first: Option<RunStep>,
+	/// The next step of the second run, unless it has ended.
+	pub second: Option<RunStep>,
+}
+
+#[derive(Serialize)]
+#[cfg_attr(feature = "daemon", derive(schemars::JsonSchema))]
+pub struct RunDiffResponse {
+	pub first_success: bool,
+	pub second_success: bool,
+	/// The number of steps of each run.
+	pub first_steps: usize,
+	pub second_steps: usize,
+	#[serde(skip_serializing_if = "Option::is_none")]
+	pub divergence: Option<RunDivergence>,
+}
+
+/// Collects the jet calls and branches of a run.
+#[derive(Default)]
+struct StepTracker {
+	steps: Vec<RunStep>,
+}
+
+impl<J: jet::Jet> ExecTracker<J> for StepTracker {
+	fn visit_node(
+		&mut self,
+		node: &simplicity::RedeemNode<J>,
+		mut input: FrameIter,
+		output: NodeOutput,
+	) {
+		match node.inner() {
+			node::Inner::Jet(..) => {
+				self.steps.extend(jet_call(node, input, output).map(RunStep::Jet));
+			}
+			node::Inner::Case(..) | node::Inner::AssertL(..) | node::Inner::AssertR(..) => {
+				if let Some(right) = input.next() {
+					self.steps.push(RunStep::Branch {
+						cmr: node.cmr(),
+						right,
+					});
+				}
+			}
+			_ => {}
+		}
+	}
+}
+
+/// Run a Simplicity program with two witnesses in the context of the same PSET
+/// input, and find the first jet call or branch where the runs differ.
+pub fn pset_run_diff(
+	pset_b64: &str,
+	input_idx: &str,
+	program: &str,
+	witness: &str,
+	other_witness: &str,
+	genesis_hash: Option<&str>,
+) -> Result<RunDiffResponse, PsetRunError> {
+	let run = |witness| {
+		let program = Program::<jet::Elements>::from_str(program, Some(witness))
+			.map_err(PsetRunError::ProgramParse)?;
+		let mut tracker = StepTracker::default();
+		let success = run_with_tracker(pset_b64, input_idx, &program, genesis_hash, &mut tracker)?;
+		Ok::<_, PsetRunError>((success, tracker.steps))
+	};
+	let (first_success, first) = run(witness)?;
+	let (second_success, second) = run(other_witness)?;
+
+	let (first_steps, second_steps) = (first.len(), second.len());
+	let step = first.iter().zip(&second).take_while(|(a, b)| a == b).count();
+	let divergence = if step == first_steps && step == second_steps {
+		None
+	} else {
+		Some(RunDivergence {
+			step,
+			first: first.into_iter().nth(step),
+			second: second.into_iter().nth(step),
+		})
+	};
+	Ok(RunDiffResponse {
+		first_success,
+		second_success,
+		first_steps,
+		second_steps,
+		divergence,
+	})
+}
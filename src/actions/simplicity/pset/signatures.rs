@@ -0,0 +1,257 @@
+// Copyright 2025 Andrew Poelstra
+// SPDX-License-Identifier: CC0-1.0
+
+use elements::bitcoin::secp256k1;
+use elements::hashes::{sha256, Hash as _};
+use elements::pset::PartiallySignedTransaction;
+use elements::schnorr::{SchnorrSig, XOnlyPublicKey};
+use elements::taproot::TapLeafHash;
+use elements::SchnorrSighashType;
+use serde::{Deserialize, Serialize};
+
+use crate::simplicity::bitcoin::secp256k1::{schnorr, Message, SECP256K1};
+use crate::simplicity::Cmr;
+
+use super::{execution_environment, PsetError, UpdatedPset};
+
+#[derive(Debug, thiserror::Error)]
+pub enum PsetSignaturesError {
+	#[error(transparent)]
+	SharedError(#[from] PsetError),
+
+	#[error("invalid PSET: {0}")]
+	PsetDecode(elements::pset::ParseError),
+
+	#[error("invalid signatures: {0}")]
+	SignaturesParse(serde_json::Error),
+
+	#[error("input index {index} out-of-range for PSET with {total} inputs")]
+	InputIndexOutOfRange {
+		index: usize,
+		total: usize,
+	},
+
+	#[error("input {input} has no Simplicity leaf")]
+	NoSimplicityLeaf {
+		input: usize,
+	},
+
+	#[error("input {input} has several Simplicity leaves; the signature must give a leaf_hash")]
+	AmbiguousLeaf {
+		input: usize,
+	},
+
+	#[error("input {input} has no Simplicity leaf with hash {leaf_hash}")]
+	LeafNotFound {
+		input: usize,
+		leaf_hash: String,
+	},
+
+	#[error("invalid leaf hash: {0}")]
+	LeafHashParse(elements::hashes::hex::HexToArrayError),
+
+	#[error("invalid public key: {0}")]
+	PublicKeyParse(secp256k1::Error),
+
+	#[error("invalid signature: {0}")]
+	SignatureParse(secp256k1::Error),
+
+	#[error("signature for input {input} is not valid for public key {pubkey}")]
+	InvalidSignature {
+		input: usize,
+		pubkey: String,
+	},
+}
+
+/// A key which may sign for a Simplicity leaf, as given by the PSET's key origins.
+#[derive(Serialize)]
+#[cfg_attr(feature = "daemon", derive(schemars::JsonSchema))]
+pub struct SignerHint {
+	#[cfg_attr(feature = "daemon", schemars(with = "String"))]
+	pub pubkey: XOnlyPublicKey,
+	/// The fingerprint of the master key, as HWI's `--fingerprint` takes it.
+	pub fingerprint: String,
+	pub path: String,
+}
+
+/// The digest to sign for one Simplicity leaf of one input.
+#[derive(Serialize)]
+#[cfg_attr(feature = "daemon", derive(schemars::JsonSchema))]
+pub struct InputSighash {
+	pub input: usize,
+	#[cfg_attr(feature = "daemon", schemars(with = "String"))]
+	pub cmr: Cmr,
+	#[cfg_attr(feature = "daemon", schemars(with = "String"))]
+	pub leaf_hash: TapLeafHash,
+	#[cfg_attr(feature = "daemon", schemars(with = "String"))]
+	pub sighash: sha256::Hash,
+	pub signers: Vec<SignerHint>,
+}
+
+#[derive(Serialize)]
+#[cfg_attr(feature = "daemon", derive(schemars::JsonSchema))]
+pub struct PsetSighashes {
+	pub sighashes: Vec<InputSighash>,
+}
+
+/// A signature returned by a signer.
+#[derive(Deserialize)]
+pub struct ReturnedSignature {
+	pub input: usize,
+	pub pubkey: String,
+	pub signature: String,
+	/// Only needed if the input has several Simplicity leaves.
+	#[serde(default)]
+	pub leaf_hash: Option<String>,
+}
+
+/// The Simplicity leaves of a PSET input, as CMR and leaf hash.
+fn simplicity_leaves(
+	pset: &PartiallySignedTransaction,
+	input_idx: usize,
+) -> impl Iterator<Item = (Cmr, TapLeafHash)> + '_ {
+	pset.inputs()[input_idx].tap_scripts.values().filter_map(|(script, version)| {
+		if *version != simplicity::leaf_version() {
+			return None;
+		}
+		let cmr = <[u8; 32]>::try_from(&script[..]).ok().map(Cmr::from_byte_array)?;
+		Some((cmr, TapLeafHash::from_script(script, *version)))
+	})
+}
+
+/// The Simplicity `sighash_all` of an input spent through the leaf with the given CMR.
+fn sighash(
+	pset: &PartiallySignedTransaction,
+	input_idx: usize,
+	cmr: Cmr,
+	genesis_hash: Option<&str>,
+) -> Result<sha256::Hash, PsetError> {
+	let (tx_env, _, _) = execution_environment(pset, input_idx, cmr, genesis_hash)?;
+	Ok(tx_env.c_tx_env().sighash_all())
+}
+
+/// List the digest to sign for every Simplicity leaf of every input of a PSET,
+/// with the keys that the PSET says may sign for it.
+pub fn pset_sighashes(
+	pset_b64: &str,
+	genesis_hash: Option<&str>,
+) -> Result<PsetSighashes, PsetSignaturesError> {
+	let pset: PartiallySignedTransaction =
+		pset_b64.parse().map_err(PsetSignaturesError::PsetDecode)?;
+
+	let mut sighashes = vec![];
+	for (input_idx, input) in pset.inputs().iter().enumerate() {
+		for (cmr, leaf_hash) in simplicity_leaves(&pset, input_idx) {
+			let signers = input
+				.tap_key_origins
+				.iter()
+				.filter(|(_, (leaves, _))| leaves.contains(&leaf_hash))
+				.map(|(pubkey, (_, (fingerprint, path)))| SignerHint {
+					pubkey: *pubkey,
+					fingerprint: fingerprint.to_string(),
+					path: if path.is_master() {
+						"m".to_owned()
+					} else {
+						format!("m/{}", path)
+					},
+				})
+				.collect();
+			sighashes.push(InputSighash {
+				input: input_idx,
+				cmr,
+				leaf_hash,
+				sighash: sighash(&pset, input_idx, cmr, genesis_hash)?,
+				signers,
+			});
+		}
+	}
+
+	Ok(PsetSighashes {
+		sighashes,
+	})
+}
+
+/// Check signatures returned by signers against the inputs' sighashes and add
+/// them to the PSET.
+pub fn pset_add_signatures(
+	pset_b64: &str,
+	signatures: &str,
+	genesis_hash: Option<&str>,
+) -> Result<UpdatedPset, PsetSignaturesError> {
+	let mut pset: PartiallySignedTransaction =
+		pset_b64.parse().map_err(PsetSignaturesError::PsetDecode)?;
+	let signatures: Vec<ReturnedSignature> =
+		serde_json::from_str(signatures).map_err(PsetSignaturesError::SignaturesParse)?;
+
+	for returned in &signatures {
+		let input_idx = returned.input;
+		let n_inputs = pset.n_inputs();
+		if input_idx >= n_inputs {
+			return Err(PsetSignaturesError::InputIndexOutOfRange {
+				index: input_idx,
+				total: n_inputs,
+			});
+		}
+
+		let leaf_hash = returned
+			.leaf_hash
+			.as_deref()
+			.map(str::parse::<TapLeafHash>)
+			.transpose()
+			.map_err(PsetSignaturesError::LeafHashParse)?;
+		let leaves: Vec<_> = simplicity_leaves(&pset, input_idx)
+			.filter(|(_, hash)| leaf_hash.map_or(true, |leaf_hash| *hash == leaf_hash))
+			.collect();
+		let (cmr, leaf_hash) = match (&leaves[..], leaf_hash) {
+			([leaf], _) => *leaf,
+			([_, _, ..], _) => {
+				return Err(PsetSignaturesError::AmbiguousLeaf {
+					input: input_idx,
+				})
+			}
+			([], Some(leaf_hash)) => {
+				return Err(PsetSignaturesError::LeafNotFound {
+					input: input_idx,
+					leaf_hash: leaf_hash.to_string(),
+				})
+			}
+			([], None) => {
+				return Err(PsetSignaturesError::NoSimplicityLeaf {
+					input: input_idx,
+				})
+			}
+		};
+
+		let pubkey: XOnlyPublicKey =
+			returned.pubkey.parse().map_err(PsetSignaturesError::PublicKeyParse)?;
+		let signature: schnorr::Signature =
+			returned.signature.parse().map_err(PsetSignaturesError::SignatureParse)?;
+		let sighash = sighash(&pset, input_idx, cmr, genesis_hash)?;
+		let message = Message::from_digest(sighash.to_byte_array());
+		if SECP256K1.verify_schnorr(&signature, &message, &pubkey).is_err() {
+			return Err(PsetSignaturesError::InvalidSignature {
+				input: input_idx,
+				pubkey: pubkey.to_string(),
+			});
+		}
+
+		// Simplicity programs sign the whole transaction, so there is no sighash type.
+		pset.inputs_mut()[input_idx].tap_script_sigs.insert(
+			(pubkey, leaf_hash),
+			SchnorrSig {
+				sig: signature,
+				hash_ty: SchnorrSighashType::Default,
+			},
+		);
+	}
+
+	let updated_values = if signatures.is_empty() {
+		vec![]
+	} else {
+		vec!["tap_script_sigs"]
+	};
+	Ok(UpdatedPset {
+		pset: pset.to_string(),
+		updated_values,
+	})
+}
@@ -6,9 +6,10 @@ use std::collections::BTreeMap;
 
 use elements::bitcoin::secp256k1;
 use elements::schnorr::XOnlyPublicKey;
+use elements::taproot::LeafVersion;
 use simplicity::hex::parse::FromHex as _;
 
-use crate::hal_simplicity::taproot_spend_info;
+use crate::hal_simplicity::taproot_spend_info_with_leaf_version;
 
 use super::{PsetError, UpdatedPset};
 
@@ -64,6 +65,28 @@ pub fn pset_update_input(
 	internal_key: Option<&str>,
 	cmr: Option<&str>,
 	state: Option<&str>,
+) -> Result<UpdatedPset, PsetUpdateInputError> {
+	pset_update_input_with_leaf_version(
+		pset_b64,
+		input_idx,
+		input_utxo,
+		internal_key,
+		cmr,
+		state,
+		simplicity::leaf_version(),
+	)
+}
+
+/// Attach UTXO data to a PSET input, putting the program's leaf under
+/// `leaf_version` rather than Simplicity's leaf version
+pub fn pset_update_input_with_leaf_version(
+	pset_b64: &str,
+	input_idx: &str,
+	input_utxo: &str,
+	internal_key: Option<&str>,
+	cmr: Option<&str>,
+	state: Option<&str>,
+	leaf_version: LeafVersion,
 ) -> Result<UpdatedPset, PsetUpdateInputError> {
 	let mut pset: elements::pset::PartiallySignedTransaction =
 		pset_b64.parse().map_err(PsetUpdateInputError::PsetDecode)?;
@@ -94,7 +117,7 @@ pub fn pset_update_input(
 	}
 
 	// FIXME state is meaningless without CMR; should we warn here
-	// FIXME also should we warn if you don't provide a CMR? seems like if you're calling `simplicity pset update-input`
+	// FIXME also should we warn if you don't provide a CMR? seems like if you're calling `simplicity pset attach-utxo`
 	//   you probably have a simplicity program right? maybe we should even provide a --no-cmr flag
 	let state =
 		state.map(<[u8; 32]>::from_hex).transpose().map_err(PsetUpdateInputError::StateParse)?;
@@ -109,7 +132,8 @@ pub fn pset_update_input(
 			// Guess that the given program is the only Tapleaf. This is the case for addresses
 			// generated from the web IDE, and from `hal-simplicity simplicity info`, and for
 			// most "test" scenarios. We need to design an API to handle more general cases.
-			let spend_info = taproot_spend_info(internal_key, state, cmr);
+			let spend_info =
+				taproot_spend_info_with_leaf_version(internal_key, state, cmr, leaf_version);
 			if spend_info.output_key().as_inner().serialize() != input_utxo.script_pubkey[2..] {
 				// If our guess was wrong, at least error out..
 				return Err(PsetUpdateInputError::OutputKeyMismatch {
@@ -118,9 +142,10 @@ pub fn pset_update_input(
 				});
 			}
 
-			// FIXME these unwraps and clones should be fixed by a new rust-bitcoin taproot API
-			let script_ver = spend_info.as_script_map().keys().next().unwrap();
-			let cb = spend_info.control_block(script_ver).unwrap();
+			// FIXME these clones should be fixed by a new rust-bitcoin taproot API
+			let script_ver =
+				spend_info.as_script_map().keys().next().expect("the program is a tapleaf");
+			let cb = spend_info.control_block(script_ver).expect("the leaf is in the tree");
 			input.tap_merkle_root = spend_info.merkle_root();
 			input.tap_scripts = BTreeMap::new();
 			input.tap_scripts.insert(cb, script_ver.clone());
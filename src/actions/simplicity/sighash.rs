@@ -1,5 +1,5 @@
 use crate::simplicity::bitcoin::secp256k1::{
-	schnorr, Keypair, Message, Secp256k1, SecretKey, XOnlyPublicKey,
+	schnorr, Keypair, Message, SecretKey, XOnlyPublicKey, SECP256K1,
 };
 use crate::simplicity::elements;
 use crate::simplicity::elements::hashes::sha256;
@@ -11,6 +11,8 @@ use crate::simplicity::Cmr;
 use elements::bitcoin::secp256k1;
 use elements::hashes::Hash as _;
 use elements::pset::PartiallySignedTransaction;
+use elements::sighash::{Prevouts, SighashCache};
+use elements::SchnorrSighashType;
 use serde::Serialize;
 
 use crate::simplicity::elements::taproot::ControlBlock;
@@ -95,27 +97,19 @@ pub enum SimplicitySighashError {
 }
 
 #[derive(Serialize)]
+#[cfg_attr(feature = "daemon", derive(schemars::JsonSchema))]
 pub struct SighashInfo {
+	#[cfg_attr(feature = "daemon", schemars(with = "String"))]
 	pub sighash: sha256::Hash,
+	#[cfg_attr(feature = "daemon", schemars(with = "Option<String>"))]
 	pub signature: Option<schnorr::Signature>,
 	pub valid_signature: Option<bool>,
 }
 
-/// Compute signature hash for a Simplicity program.
-#[allow(clippy::too_many_arguments)]
-pub fn simplicity_sighash(
+/// Decode a transaction given either as a PSET or in hex.
+fn parse_tx(
 	tx_hex: &str,
-	input_idx: &str,
-	cmr: &str,
-	control_block: Option<&str>,
-	genesis_hash: Option<&str>,
-	secret_key: Option<&str>,
-	public_key: Option<&str>,
-	signature: Option<&str>,
-	input_utxos: Option<&[&str]>,
-) -> Result<SighashInfo, SimplicitySighashError> {
-	let secp = Secp256k1::new();
-
+) -> Result<(Option<PartiallySignedTransaction>, elements::Transaction), SimplicitySighashError> {
 	// Attempt to decode transaction as PSET first. If it succeeds, we can extract
 	// a lot of information from it. If not, we assume the transaction is hex and
 	// will give the user an error corresponding to this.
@@ -133,6 +127,76 @@ pub fn simplicity_sighash(
 				.map_err(SimplicitySighashError::TransactionDecoding)?
 		}
 	};
+	Ok((pset, tx))
+}
+
+/// The outputs spent by a transaction, given explicitly or else taken from its PSET.
+fn spent_utxos(
+	pset: Option<&PartiallySignedTransaction>,
+	tx: &elements::Transaction,
+	input_utxos: Option<&[&str]>,
+) -> Result<Vec<ElementsUtxo>, SimplicitySighashError> {
+	let input_utxos = if let Some(input_utxos) = input_utxos {
+		input_utxos
+			.iter()
+			.map(|utxo_str| {
+				crate::actions::simplicity::parse_elements_utxo(utxo_str)
+					.map_err(SimplicitySighashError::InputUtxoParsing)
+			})
+			.collect::<Result<Vec<_>, SimplicitySighashError>>()?
+	} else if let Some(pset) = pset {
+		pset.inputs()
+			.iter()
+			.enumerate()
+			.map(|(n, input)| match input.witness_utxo {
+				Some(ref utxo) => Ok(ElementsUtxo {
+					script_pubkey: utxo.script_pubkey.clone(),
+					asset: utxo.asset,
+					value: utxo.value,
+				}),
+				None => Err(SimplicitySighashError::WitnessUtxoMissing {
+					input: n,
+				}),
+			})
+			.collect::<Result<Vec<_>, SimplicitySighashError>>()?
+	} else {
+		return Err(SimplicitySighashError::InputUtxosRequired);
+	};
+	if input_utxos.len() != tx.input.len() {
+		return Err(SimplicitySighashError::InputUtxoCountMismatch {
+			expected: tx.input.len(),
+			actual: input_utxos.len(),
+		});
+	}
+	Ok(input_utxos)
+}
+
+fn parse_genesis_hash(
+	genesis_hash: Option<&str>,
+) -> Result<elements::BlockHash, SimplicitySighashError> {
+	// Default to the Liquid testnet genesis block.
+	match genesis_hash {
+		Some(s) => s.parse().map_err(SimplicitySighashError::GenesisHashParsing),
+		None => Ok(crate::Network::LiquidTestnet
+			.genesis_hash()
+			.expect("Liquid testnet has a genesis hash")),
+	}
+}
+
+/// Compute signature hash for a Simplicity program.
+#[allow(clippy::too_many_arguments)]
+pub fn simplicity_sighash(
+	tx_hex: &str,
+	input_idx: &str,
+	cmr: &str,
+	control_block: Option<&str>,
+	genesis_hash: Option<&str>,
+	secret_key: Option<&str>,
+	public_key: Option<&str>,
+	signature: Option<&str>,
+	input_utxos: Option<&[&str]>,
+) -> Result<SighashInfo, SimplicitySighashError> {
+	let (pset, tx) = parse_tx(tx_hex)?;
 	let input_idx: u32 = input_idx.parse().map_err(SimplicitySighashError::InputIndexParsing)?;
 	let cmr: Cmr = cmr.parse().map_err(SimplicitySighashError::CmrParsing)?;
 
@@ -170,49 +234,9 @@ pub fn simplicity_sighash(
 		return Err(SimplicitySighashError::ControlBlockRequired);
 	};
 
-	let input_utxos = if let Some(input_utxos) = input_utxos {
-		input_utxos
-			.iter()
-			.map(|utxo_str| {
-				crate::actions::simplicity::parse_elements_utxo(utxo_str)
-					.map_err(SimplicitySighashError::InputUtxoParsing)
-			})
-			.collect::<Result<Vec<_>, SimplicitySighashError>>()?
-	} else if let Some(ref pset) = pset {
-		pset.inputs()
-			.iter()
-			.enumerate()
-			.map(|(n, input)| match input.witness_utxo {
-				Some(ref utxo) => Ok(ElementsUtxo {
-					script_pubkey: utxo.script_pubkey.clone(),
-					asset: utxo.asset,
-					value: utxo.value,
-				}),
-				None => Err(SimplicitySighashError::WitnessUtxoMissing {
-					input: n,
-				}),
-			})
-			.collect::<Result<Vec<_>, SimplicitySighashError>>()?
-	} else {
-		return Err(SimplicitySighashError::InputUtxosRequired);
-	};
-	if input_utxos.len() != tx.input.len() {
-		return Err(SimplicitySighashError::InputUtxoCountMismatch {
-			expected: tx.input.len(),
-			actual: input_utxos.len(),
-		});
-	}
+	let input_utxos = spent_utxos(pset.as_ref(), &tx, input_utxos)?;
 
-	// Default to Bitcoin blockhash.
-	let genesis_hash = match genesis_hash {
-		Some(s) => s.parse().map_err(SimplicitySighashError::GenesisHashParsing)?,
-		None => elements::BlockHash::from_byte_array([
-			// copied out of simplicity-webide source
-			0xc1, 0xb1, 0x6a, 0xe2, 0x4f, 0x24, 0x23, 0xae, 0xa2, 0xea, 0x34, 0x55, 0x22, 0x92,
-			0x79, 0x3b, 0x5b, 0x5e, 0x82, 0x99, 0x9a, 0x1e, 0xed, 0x81, 0xd5, 0x6a, 0xee, 0x52,
-			0x8e, 0xda, 0x71, 0xa7,
-		]),
-	};
+	let genesis_hash = parse_genesis_hash(genesis_hash)?;
 
 	let tx_env = ElementsEnv::new(
 		&tx,
@@ -247,7 +271,7 @@ pub fn simplicity_sighash(
 		signature: match secret_key {
 			Some(sk) => {
 				let sk: SecretKey = sk.parse().map_err(SimplicitySighashError::SecretKeyParsing)?;
-				let keypair = Keypair::from_secret_key(&secp, &sk);
+				let keypair = Keypair::from_secret_key(SECP256K1, &sk);
 
 				if let Some(ref pk) = pk {
 					if pk != &keypair.x_only_public_key().0 {
@@ -258,13 +282,149 @@ pub fn simplicity_sighash(
 					}
 				}
 
-				Some(secp.sign_schnorr(&sighash_msg, &keypair))
+				Some(SECP256K1.sign_schnorr(&sighash_msg, &keypair))
 			}
 			None => None,
 		},
 		valid_signature: match (pk, sig) {
-			(Some(pk), Some(sig)) => Some(secp.verify_schnorr(&sig, &sighash_msg, &pk).is_ok()),
+			(Some(pk), Some(sig)) => {
+				Some(SECP256K1.verify_schnorr(&sig, &sighash_msg, &pk).is_ok())
+			}
 			_ => None,
 		},
 	})
 }
+
+/// The Simplicity `sighash_all` of one leaf of an input.
+#[derive(Serialize)]
+#[cfg_attr(feature = "daemon", derive(schemars::JsonSchema))]
+pub struct LeafSighash {
+	#[cfg_attr(feature = "daemon", schemars(with = "String"))]
+	pub cmr: Cmr,
+	#[cfg_attr(feature = "daemon", schemars(with = "String"))]
+	pub sighash: sha256::Hash,
+}
+
+#[derive(Serialize)]
+#[cfg_attr(feature = "daemon", derive(schemars::JsonSchema))]
+pub struct InputSighashes {
+	pub input: usize,
+	/// The BIP 341 sighash of a key spend with `SIGHASH_DEFAULT`, if the input
+	/// spends a taproot output.
+	#[serde(skip_serializing_if = "Option::is_none")]
+	#[cfg_attr(feature = "daemon", schemars(with = "Option<String>"))]
+	pub key_path: Option<sha256::Hash>,
+	#[serde(skip_serializing_if = "Vec::is_empty")]
+	pub simplicity: Vec<LeafSighash>,
+}
+
+#[derive(Serialize)]
+#[cfg_attr(feature = "daemon", derive(schemars::JsonSchema))]
+pub struct TxSighashes {
+	pub inputs: Vec<InputSighashes>,
+}
+
+/// The Simplicity leaves an input may be spent through, with their control
+/// blocks and any annex. A PSET lists them in its taproot scripts; a raw
+/// transaction gives the leaf already in the input's witness.
+fn simplicity_leaves(
+	pset: Option<&PartiallySignedTransaction>,
+	tx: &elements::Transaction,
+	input_idx: usize,
+) -> Vec<(Cmr, ControlBlock, Option<Vec<u8>>)> {
+	let leaf_cmr = |script: &[u8]| <[u8; 32]>::try_from(script).ok().map(Cmr::from_byte_array);
+	if let Some(pset) = pset {
+		return pset.inputs()[input_idx]
+			.tap_scripts
+			.iter()
+			.filter(|(_, (_, version))| *version == simplicity::leaf_version())
+			.filter_map(|(cb, (script, _))| Some((leaf_cmr(script.as_bytes())?, cb.clone(), None)))
+			.collect();
+	}
+
+	let mut stack = tx.input[input_idx].witness.script_witness.clone();
+	let annex = match stack.last() {
+		Some(last) if stack.len() >= 2 && last.first() == Some(&0x50) => stack.pop(),
+		_ => None,
+	};
+	let (Some(cb), Some(script)) = (stack.pop(), stack.pop()) else {
+		return vec![];
+	};
+	match (ControlBlock::from_slice(&cb), leaf_cmr(&script)) {
+		(Ok(cb), Some(cmr)) if cb.leaf_version == simplicity::leaf_version() => {
+			vec![(cmr, cb, annex)]
+		}
+		_ => vec![],
+	}
+}
+
+/// Compute the sighash of every input of a transaction at once: the Simplicity
+/// `sighash_all` of each Simplicity leaf it may be spent through, and the key
+/// spend sighash of each input spending a taproot output.
+///
+/// The spent outputs are taken from the PSET unless `input_utxos` are given, as
+/// for [`simplicity_sighash`].
+pub fn simplicity_sighashes(
+	tx_hex: &str,
+	genesis_hash: Option<&str>,
+	input_utxos: Option<&[&str]>,
+) -> Result<TxSighashes, SimplicitySighashError> {
+	let (pset, tx) = parse_tx(tx_hex)?;
+	let input_utxos = spent_utxos(pset.as_ref(), &tx, input_utxos)?;
+	let genesis_hash = parse_genesis_hash(genesis_hash)?;
+
+	let prevouts: Vec<_> = input_utxos
+		.iter()
+		.map(|utxo| elements::TxOut {
+			asset: utxo.asset,
+			value: utxo.value,
+			nonce: elements::confidential::Nonce::Null,
+			script_pubkey: utxo.script_pubkey.clone(),
+			witness: elements::TxOutWitness::default(),
+		})
+		.collect();
+	let mut cache = SighashCache::new(&tx);
+
+	let inputs = (0..tx.input.len())
+		.map(|input_idx| {
+			let key_path = input_utxos[input_idx].script_pubkey.is_v1_p2tr().then(|| {
+				let sighash = cache
+					.taproot_key_spend_signature_hash(
+						input_idx,
+						&Prevouts::All(&prevouts),
+						SchnorrSighashType::Default,
+						genesis_hash,
+					)
+					.expect("one prevout per input");
+				sha256::Hash::from_byte_array(sighash.to_byte_array())
+			});
+			let simplicity = simplicity_leaves(pset.as_ref(), &tx, input_idx)
+				.into_iter()
+				.map(|(cmr, control_block, annex)| {
+					let tx_env = ElementsEnv::new(
+						&tx,
+						input_utxos.clone(),
+						input_idx as u32, // cast fine, input indices are always small
+						cmr,
+						control_block,
+						annex,
+						genesis_hash,
+					);
+					LeafSighash {
+						cmr,
+						sighash: tx_env.c_tx_env().sighash_all(),
+					}
+				})
+				.collect();
+			InputSighashes {
+				input: input_idx,
+				key_path,
+				simplicity,
+			}
+		})
+		.collect();
+
+	Ok(TxSighashes {
+		inputs,
+	})
+}
@@ -0,0 +1,166 @@
+//! Bit offsets of the nodes of an encoded program
+//!
+//! A program is encoded as its number of nodes, followed by each node in
+//! turn, with children referred to by how many nodes back they are. The
+//! encoding is scanned node by node here, independently of the decoder which
+//! type-checks it, so that the map can be compared against another
+//! implementation's reading of the same bits.
+
+use serde::Serialize;
+
+use crate::simplicity::decode::Error as DecodeError;
+use crate::simplicity::jet::{self, Jet as _};
+use crate::simplicity::{u2, BitIter, Cmr, Word};
+
+use super::SimplicityInfoError;
+
+/// One node of an encoded program.
+#[derive(Serialize)]
+#[cfg_attr(feature = "daemon", derive(schemars::JsonSchema))]
+pub struct SourceMapNode {
+	/// The index of the node, by which later nodes refer to it.
+	pub index: usize,
+	/// The combinator, such as `comp`, `jet` or `hidden`.
+	pub node: &'static str,
+	/// The indices of the node's children.
+	#[serde(skip_serializing_if = "Vec::is_empty")]
+	pub children: Vec<usize>,
+	/// The jet, word, hidden CMR or fail entropy of the node.
+	#[serde(skip_serializing_if = "Option::is_none")]
+	pub detail: Option<String>,
+	/// The offset of the node's first bit in the encoding.
+	pub start: usize,
+	/// The offset of the bit after the node.
+	pub end: usize,
+	/// The CMR of the node, or for a hidden node the CMR it stands for.
+	#[serde(skip)]
+	pub cmr: Cmr,
+}
+
+#[derive(Serialize)]
+#[cfg_attr(feature = "daemon", derive(schemars::JsonSchema))]
+pub struct SourceMap {
+	/// The bits of the node count which starts the encoding.
+	pub header_bits: usize,
+	pub nodes: Vec<SourceMapNode>,
+	/// The number of bits of the encoding, not counting the padding of its
+	/// last byte.
+	pub bits: usize,
+}
+
+/// Read the index of a child of the node at `index`.
+fn read_child<I: Iterator<Item = u8>>(
+	bits: &mut BitIter<I>,
+	index: usize,
+) -> Result<usize, DecodeError> {
+	let offset = bits.read_natural(Some(index))?;
+	Ok(index - offset)
+}
+
+/// A node as its combinator, children, detail and CMR.
+type ReadNode = (&'static str, Vec<usize>, Option<String>, Cmr);
+
+/// Read the node following `nodes`.
+fn read_node<I: Iterator<Item = u8>>(
+	bits: &mut BitIter<I>,
+	nodes: &[SourceMapNode],
+) -> Result<ReadNode, DecodeError> {
+	let index = nodes.len();
+	if bits.read_bit()? {
+		if bits.read_bit()? {
+			let jet = jet::Elements::decode(bits)?;
+			return Ok(("jet", vec![], Some(jet.to_string()), Cmr::jet(jet)));
+		}
+		let n: u32 = bits.read_natural(Some(32))?;
+		let word = Word::from_bits(bits, n - 1)?;
+		return Ok(("word", vec![], Some(word.to_string()), Cmr::const_word(&word)));
+	}
+
+	let node = match bits.read_u2()? {
+		u2::_0 => {
+			let (name, cmr): (_, fn(Cmr, Cmr) -> Cmr) = match bits.read_u2()? {
+				u2::_0 => ("comp", Cmr::comp),
+				u2::_1 => ("case", Cmr::case),
+				u2::_2 => ("pair", Cmr::pair),
+				u2::_3 => ("disconnect", |left, _| Cmr::disconnect(left)),
+			};
+			let left = read_child(bits, index)?;
+			let right = read_child(bits, index)?;
+			(name, vec![left, right], None, cmr(nodes[left].cmr, nodes[right].cmr))
+		}
+		u2::_1 => {
+			let (name, cmr): (_, fn(Cmr) -> Cmr) = match bits.read_u2()? {
+				u2::_0 => ("injl", Cmr::injl),
+				u2::_1 => ("injr", Cmr::injr),
+				u2::_2 => ("take", Cmr::take),
+				u2::_3 => ("drop", Cmr::drop),
+			};
+			let child = read_child(bits, index)?;
+			(name, vec![child], None, cmr(nodes[child].cmr))
+		}
+		u2::_2 => match bits.read_u2()? {
+			u2::_0 => ("iden", vec![], None, Cmr::iden()),
+			u2::_1 => ("unit", vec![], None, Cmr::unit()),
+			u2::_2 => {
+				let entropy = bits.read_fail_entropy()?;
+				("fail", vec![], Some(entropy.to_string()), Cmr::fail(entropy))
+			}
+			u2::_3 => {
+				let child = read_child(bits, index)?;
+				("disconnect", vec![child], None, Cmr::disconnect(nodes[child].cmr))
+			}
+		},
+		u2::_3 => {
+			if bits.read_bit()? {
+				("witness", vec![], None, Cmr::witness())
+			} else {
+				let cmr = bits.read_cmr()?;
+				("hidden", vec![], Some(cmr.to_string()), cmr)
+			}
+		}
+	};
+	Ok(node)
+}
+
+/// Map each node of an encoded program to the bits it is encoded in.
+pub fn program_source_map(prog_bytes: &[u8]) -> Result<SourceMap, SimplicityInfoError> {
+	let mut bits = BitIter::from(prog_bytes);
+	let at = |bits: &BitIter<_>| {
+		let bit = bits.n_total_read();
+		move |error| SimplicityInfoError::SourceMap {
+			bit,
+			error,
+		}
+	};
+
+	let len: usize = bits.read_natural(None).map_err(|e| at(&bits)(e.into()))?;
+	let header_bits = bits.n_total_read();
+	let mut nodes = Vec::with_capacity(len.min(10_000));
+	for index in 0..len {
+		let start = bits.n_total_read();
+		let (node, children, detail, cmr) = read_node(&mut bits, &nodes).map_err(at(&bits))?;
+		nodes.push(SourceMapNode {
+			index,
+			node,
+			children,
+			detail,
+			start,
+			end: bits.n_total_read(),
+			cmr,
+		});
+	}
+
+	Ok(SourceMap {
+		header_bits,
+		nodes,
+		bits: bits.n_total_read(),
+	})
+}
+
+/// Map each node of a hex or base64-encoded program to the bits it is encoded in.
+pub fn simplicity_source_map(program: &str) -> Result<SourceMap, SimplicityInfoError> {
+	let prog_bytes = crate::hex_or_base64(program).map_err(|e| {
+		SimplicityInfoError::ProgramParse(crate::simplicity::ParseError::Base64(e).into())
+	})?;
+	program_source_map(&prog_bytes)
+}
@@ -0,0 +1,168 @@
+//! The bundle exchanged with simplicity-webide
+//!
+//! The web IDE runs a program as the spender of a single output, paying a
+//! recipient and a fee. Its bundle holds the compiled program, its witness
+//! and the parameters of that transaction. The SimplicityHL source, if any,
+//! is carried along unchanged, since hal-simplicity cannot compile it.
+
+use elements::{AssetId, BlockHash, Txid};
+use serde::{Deserialize, Serialize};
+
+use super::{simplicity_info, ProgramInfo, SimplicityInfoError};
+use crate::Network;
+
+#[derive(Debug, thiserror::Error)]
+pub enum WebIdeError {
+	#[error("invalid web IDE bundle: {0}")]
+	BundleParse(serde_json::Error),
+
+	#[error(transparent)]
+	Program(#[from] SimplicityInfoError),
+
+	#[error("the fee of {fee} sat exceeds the value of {value} sat")]
+	FeeExceedsValue {
+		fee: u64,
+		value: u64,
+	},
+}
+
+/// The parameters of the transaction which the web IDE runs a program in.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[cfg_attr(feature = "daemon", derive(schemars::JsonSchema))]
+pub struct WebIdeEnvironment {
+	/// The transaction whose output the program spends.
+	#[serde(default, skip_serializing_if = "Option::is_none")]
+	#[cfg_attr(feature = "daemon", schemars(with = "Option<String>"))]
+	pub txid: Option<Txid>,
+	#[serde(default, skip_serializing_if = "Option::is_none")]
+	pub vout: Option<u32>,
+	/// The value of the spent output, in satoshi.
+	#[serde(default, skip_serializing_if = "Option::is_none")]
+	pub value: Option<u64>,
+	/// The asset of the spent output; the network's policy asset if not given.
+	#[serde(default, skip_serializing_if = "Option::is_none")]
+	#[cfg_attr(feature = "daemon", schemars(with = "Option<String>"))]
+	pub asset: Option<AssetId>,
+	/// The address paid the value less the fee.
+	#[serde(default, skip_serializing_if = "Option::is_none")]
+	pub recipient_address: Option<String>,
+	/// The fee, in satoshi.
+	#[serde(default, skip_serializing_if = "Option::is_none")]
+	pub fee: Option<u64>,
+	#[serde(default, skip_serializing_if = "Option::is_none")]
+	pub lock_time: Option<u32>,
+	#[serde(default, skip_serializing_if = "Option::is_none")]
+	pub sequence: Option<u32>,
+	#[serde(default, skip_serializing_if = "Option::is_none")]
+	#[cfg_attr(feature = "daemon", schemars(with = "Option<String>"))]
+	pub genesis_hash: Option<BlockHash>,
+}
+
+/// A program, its witness and the environment to run it in, as read and
+/// written by the web IDE.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[cfg_attr(feature = "daemon", derive(schemars::JsonSchema))]
+pub struct WebIdeBundle {
+	/// The compiled program (base64).
+	pub program: String,
+	/// The witness of the program (hex).
+	#[serde(default, skip_serializing_if = "Option::is_none")]
+	pub witness: Option<String>,
+	/// The SimplicityHL source of the program.
+	#[serde(default, skip_serializing_if = "Option::is_none")]
+	pub source: Option<String>,
+	#[serde(default)]
+	pub environment: WebIdeEnvironment,
+}
+
+/// The arguments of `simplicity pset create` for a bundle's transaction.
+#[derive(Serialize)]
+#[cfg_attr(feature = "daemon", derive(schemars::JsonSchema))]
+pub struct PsetCreateArgs {
+	pub inputs: String,
+	pub outputs: String,
+}
+
+#[derive(Serialize)]
+#[cfg_attr(feature = "daemon", derive(schemars::JsonSchema))]
+pub struct WebIdeImport {
+	#[serde(flatten)]
+	pub info: ProgramInfo,
+	#[serde(skip_serializing_if = "Option::is_none")]
+	pub source: Option<String>,
+	pub environment: WebIdeEnvironment,
+	/// Set if the environment has all the parameters of the transaction.
+	#[serde(skip_serializing_if = "Option::is_none")]
+	pub pset_create: Option<PsetCreateArgs>,
+}
+
+/// Read a web IDE bundle, decoding its program and turning its environment
+/// into the arguments of `simplicity pset create`.
+pub fn simplicity_import_webide(
+	bundle: &str,
+	network: Network,
+) -> Result<WebIdeImport, WebIdeError> {
+	let bundle: WebIdeBundle = serde_json::from_str(bundle).map_err(WebIdeError::BundleParse)?;
+	let info = simplicity_info(&bundle.program, bundle.witness.as_deref(), None)?;
+	let pset_create = pset_create_args(&bundle.environment, network)?;
+
+	Ok(WebIdeImport {
+		info,
+		source: bundle.source,
+		environment: bundle.environment,
+		pset_create,
+	})
+}
+
+fn pset_create_args(
+	env: &WebIdeEnvironment,
+	network: Network,
+) -> Result<Option<PsetCreateArgs>, WebIdeError> {
+	let (Some(txid), Some(vout), Some(value), Some(recipient), Some(fee), Some(asset)) = (
+		env.txid,
+		env.vout,
+		env.value,
+		env.recipient_address.as_ref(),
+		env.fee,
+		env.asset.or(network.policy_asset()),
+	) else {
+		return Ok(None);
+	};
+	let amount = value.checked_sub(fee).ok_or(WebIdeError::FeeExceedsValue {
+		fee,
+		value,
+	})?;
+
+	let btc = |sat| elements::bitcoin::Amount::from_sat(sat).to_btc();
+	let inputs = serde_json::json!([{
+		"txid": txid,
+		"vout": vout,
+		"sequence": env.sequence.unwrap_or(0xffff_ffff),
+	}]);
+	let outputs = serde_json::json!([
+		{ "address": recipient, "asset": asset, "amount": btc(amount) },
+		{ "address": "fee", "asset": asset, "amount": btc(fee) },
+	]);
+	Ok(Some(PsetCreateArgs {
+		inputs: inputs.to_string(),
+		outputs: outputs.to_string(),
+	}))
+}
+
+/// Write a program, its witness and the environment to run it in as a web IDE
+/// bundle. The program and witness are checked, and written in the encodings
+/// which the web IDE expects.
+pub fn simplicity_export_webide(
+	program: &str,
+	witness: Option<&str>,
+	source: Option<&str>,
+	environment: WebIdeEnvironment,
+) -> Result<WebIdeBundle, WebIdeError> {
+	let info = simplicity_info(program, witness, None)?;
+	Ok(WebIdeBundle {
+		program: info.commit_base64,
+		witness: info.redeem_info.map(|redeem| redeem.witness_hex),
+		source: source.map(str::to_owned),
+		environment,
+	})
+}
@@ -1,22 +1,36 @@
-use std::convert::TryInto;
+use std::collections::HashMap;
+use std::sync::Arc;
 
 use elements::bitcoin::{self, secp256k1};
 use elements::encode::{deserialize, serialize};
-use elements::hashes::Hash;
+use elements::hashes::{sha256, Hash};
+use elements::pset::PartiallySignedTransaction;
+use elements::schnorr::{SchnorrSig, TweakedPublicKey};
 use elements::secp256k1_zkp::{
-	Generator, PedersenCommitment, PublicKey, RangeProof, SurjectionProof, Tweak,
+	Generator, Message, PedersenCommitment, PublicKey, RangeProof, SurjectionProof, Tweak,
+	XOnlyPublicKey, SECP256K1,
 };
+use elements::sighash::{Prevouts, SighashCache};
+use elements::taproot::ControlBlock;
 use elements::{
-	confidential, AssetIssuance, OutPoint, Script, Transaction, TxIn, TxInWitness, TxOut,
-	TxOutWitness,
+	confidential, AssetId, AssetIssuance, ContractHash, EcdsaSighashType, OutPoint, Script,
+	Transaction, TxIn, TxInWitness, TxOut, TxOutWitness,
 };
 
+use crate::actions::simplicity::pset::{unsigned_transaction, PsetCreateError};
+use crate::actions::simplicity::{parse_elements_utxo, ParseElementsUtxoError};
+use crate::hal_simplicity::Program;
+use crate::simplicity::bit_machine::BitMachine;
+use crate::simplicity::jet::elements::{ElementsEnv, ElementsUtxo};
+use crate::simplicity::{jet, Cmr};
+
 use crate::confidential::{
 	ConfidentialAssetInfo, ConfidentialNonceInfo, ConfidentialType, ConfidentialValueInfo,
 };
 use crate::tx::{
-	AssetIssuanceInfo, InputInfo, InputScriptInfo, InputWitnessInfo, OutputInfo, OutputScriptInfo,
-	OutputWitnessInfo, PeginDataInfo, PegoutDataInfo, TransactionInfo,
+	AssetIdInfo, AssetIssuanceInfo, InputInfo, InputScriptInfo, InputVerifyInfo, InputWitnessInfo,
+	OutputInfo, OutputNonceInfo, OutputScriptInfo, OutputSecretsInfo, OutputWitnessInfo,
+	PeginDataInfo, PegoutDataInfo, SpendType, TransactionInfo, TxVerifyInfo,
 };
 use crate::Network;
 
@@ -63,8 +77,8 @@ pub enum TxError {
 	#[error("invalid asset_blinding_nonce: {0}")]
 	AssetBlindingNonce(elements::secp256k1_zkp::Error),
 
-	#[error("decoding script assembly is not yet supported")]
-	AsmNotSupported,
+	#[error("invalid script assembly: {0}")]
+	AsmParse(String),
 
 	#[error("no scriptSig info provided")]
 	NoScriptSig,
@@ -84,9 +98,6 @@ pub enum TxError {
 	#[error("invalid rangeproof: {0}")]
 	RangeProof(elements::secp256k1_zkp::Error),
 
-	#[error("invalid sequence: {0}")]
-	Sequence(core::num::TryFromIntError),
-
 	#[error("addresses for different networks are used in the output scripts")]
 	MixedNetworks,
 
@@ -101,6 +112,90 @@ pub enum TxError {
 
 	#[error("asset in pegout_data does not correspond to output value")]
 	PegoutAssetMismatch,
+
+	#[error("invalid contract hash: {0}")]
+	ContractHashParse(elements::hashes::hex::HexToArrayError),
+
+	#[error("invalid contract JSON: {0}")]
+	ContractJson(serde_json::Error),
+
+	#[error("give either a contract or its hash, not both")]
+	ContractAndHash,
+
+	#[error("invalid asset entropy: {0}")]
+	AssetEntropyParse(elements::hashes::hex::HexToArrayError),
+
+	#[error("an asset entropy already commits to the issuance prevout and contract; give one or the other")]
+	EntropyAndIssuance,
+
+	#[error("invalid asset ID: {0}")]
+	AssetIdParse(elements::hashes::hex::HexToArrayError),
+
+	#[error("failed to decode mainchain block header hex: {0}")]
+	MainchainHeaderHex(hex::FromHexError),
+
+	#[error("invalid mainchain block header: {0}")]
+	MainchainHeader(bitcoin::consensus::encode::Error),
+
+	#[error("failed extracting transaction from PSET: {0}")]
+	PsetExtract(elements::pset::Error),
+
+	#[error("invalid input UTXO: {0}")]
+	InputUtxoParse(ParseElementsUtxoError),
+
+	#[error("witness UTXO field not populated for input {input}")]
+	WitnessUtxoMissing {
+		input: usize,
+	},
+
+	#[error("with a raw transaction, input-utxos must be provided")]
+	InputUtxosRequired,
+
+	#[error("expected {expected} input UTXOs but got {actual}")]
+	InputUtxoCountMismatch {
+		expected: usize,
+		actual: usize,
+	},
+
+	#[error("invalid genesis hash: {0}")]
+	GenesisHashParse(elements::hashes::hex::HexToArrayError),
+
+	#[error(transparent)]
+	SimpleCreate(#[from] PsetCreateError),
+
+	#[error("invalid secret key: {0}")]
+	SecretKeyParse(secp256k1::Error),
+
+	#[error("invalid public key: {0}")]
+	PublicKeyParse(secp256k1::Error),
+
+	#[error("invalid nonce: {0}")]
+	NonceParse(secp256k1::Error),
+
+	#[error("invalid output index: {0}")]
+	OutputIndexParse(std::num::ParseIntError),
+
+	#[error("output index {index} out-of-range for transaction with {total} outputs")]
+	OutputIndexOutOfRange {
+		index: usize,
+		total: usize,
+	},
+
+	#[error("output {index} is not blinded")]
+	OutputNotBlinded {
+		index: usize,
+	},
+
+	#[error("output {index} has no rangeproof")]
+	RangeproofMissing {
+		index: usize,
+	},
+
+	#[error("failed to rewind the rangeproof, which needs the output's nonce: {0}")]
+	Rewind(elements::secp256k1_zkp::Error),
+
+	#[error("the rangeproof message does not hold an asset and its blinding factor")]
+	RewindMessage,
 }
 
 /// Check both ways to specify the outpoint and return error if conflicting.
@@ -233,11 +328,71 @@ fn create_asset_issuance(info: AssetIssuanceInfo) -> Result<AssetIssuance, TxErr
 	})
 }
 
+/// Parse script assembly, as written by [`Script::asm`] or bitcoin's
+/// `Script::to_asm_string`, where `opcode_name` names each opcode.
+///
+/// Pushes are written as the push opcode followed by the data in hex, so that
+/// the assembly of any well-formed script parses back to the same bytes.
+fn parse_asm(asm: &str, opcode_name: impl Fn(u8) -> String) -> Result<Vec<u8>, TxError> {
+	let opcodes: HashMap<String, u8> =
+		(0..=u8::MAX).map(|code| (opcode_name(code), code)).collect();
+	let mut script = vec![];
+	let mut tokens = asm.split_whitespace().peekable();
+	while let Some(token) = tokens.next() {
+		let code = match token {
+			"OP_0" => 0,
+			_ => *opcodes
+				.get(token)
+				.ok_or_else(|| TxError::AsmParse(format!("unknown opcode {}", token)))?,
+		};
+		script.push(code);
+
+		// The number of bytes giving the length of the data, after the opcode.
+		let length_size = match code {
+			1..=75 => 0,
+			0x4c => 1,
+			0x4d => 2,
+			0x4e => 4,
+			_ => continue,
+		};
+		// An empty OP_PUSHDATA push is written without any data.
+		let data = match tokens.next_if(|t| length_size == 0 || !opcodes.contains_key(*t)) {
+			Some(hex) => hex::decode(hex)
+				.map_err(|e| TxError::AsmParse(format!("invalid data for {}: {}", token, e)))?,
+			None if length_size == 0 => {
+				return Err(TxError::AsmParse(format!("no data for {}", token)));
+			}
+			None => vec![],
+		};
+		if length_size == 0 {
+			if data.len() != code as usize {
+				return Err(TxError::AsmParse(format!(
+					"{} followed by {} bytes of data",
+					token,
+					data.len()
+				)));
+			}
+		} else {
+			let length = (data.len() as u64).to_le_bytes();
+			if length[length_size..].iter().any(|&b| b != 0) {
+				return Err(TxError::AsmParse(format!("too much data for {}", token)));
+			}
+			script.extend_from_slice(&length[..length_size]);
+		}
+		script.extend(data);
+	}
+	Ok(script)
+}
+
+fn parse_elements_asm(asm: &str) -> Result<Script, TxError> {
+	parse_asm(asm, |code| format!("{:?}", elements::opcodes::All::from(code))).map(Script::from)
+}
+
 fn create_script_sig(ss: InputScriptInfo) -> Result<Script, TxError> {
 	if let Some(hex) = ss.hex {
 		Ok(hex.0.into())
-	} else if ss.asm.is_some() {
-		Err(TxError::AsmNotSupported)
+	} else if let Some(asm) = ss.asm {
+		parse_elements_asm(&asm)
 	} else {
 		Err(TxError::NoScriptSig)
 	}
@@ -260,9 +415,9 @@ fn create_pegin_witness(
 		serialize(&pd.value),
 		serialize(&asset),
 		pd.genesis_hash.to_byte_array().to_vec(),
-		serialize(&pd.claim_script.0),
-		serialize(&pd.mainchain_tx_hex.0),
-		serialize(&pd.merkle_proof.0),
+		pd.claim_script.0,
+		pd.mainchain_tx_hex.0,
+		pd.merkle_proof.0,
 	])
 }
 
@@ -321,9 +476,7 @@ fn create_input(input: InputInfo) -> Result<TxIn, TxError> {
 
 	let script_sig = input.script_sig.map(create_script_sig).transpose()?.unwrap_or_default();
 
-	let sequence = elements::Sequence::from_height(
-		input.sequence.unwrap_or_default().try_into().map_err(TxError::Sequence)?,
-	);
+	let sequence = elements::Sequence::from_consensus(input.sequence.unwrap_or_default());
 
 	let asset_issuance = if has_issuance {
 		input.asset_issuance.map(create_asset_issuance).transpose()?.unwrap_or_default()
@@ -350,8 +503,8 @@ fn create_script_pubkey(
 	if let Some(hex) = spk.hex {
 		//TODO(stevenroose) do script sanity check to avoid blackhole?
 		Ok(hex.0.into())
-	} else if spk.asm.is_some() {
-		Err(TxError::AsmNotSupported)
+	} else if let Some(asm) = spk.asm {
+		parse_elements_asm(&asm)
 	} else if let Some(address) = spk.address {
 		// Error if another network had already been used.
 		if let Some(network) = Network::from_params(address.params) {
@@ -371,8 +524,9 @@ fn create_bitcoin_script_pubkey(
 	if let Some(hex) = spk.hex {
 		//TODO(stevenroose) do script sanity check to avoid blackhole?
 		Ok(hex.0.into())
-	} else if spk.asm.is_some() {
-		Err(TxError::AsmNotSupported)
+	} else if let Some(asm) = spk.asm {
+		parse_asm(&asm, |code| format!("{:?}", bitcoin::opcodes::Opcode::from(code)))
+			.map(Into::into)
 	} else if let Some(address) = spk.address {
 		Ok(address.assume_checked().script_pubkey())
 	} else {
@@ -496,12 +650,452 @@ pub fn tx_create(info: TransactionInfo) -> Result<Transaction, TxError> {
 	})
 }
 
+/// Create an unsigned transaction from input outpoints and outputs given as
+/// for `pset create`, rather than from full transaction info.
+pub fn tx_create_simple(inputs_json: &str, outputs_json: &str) -> Result<Transaction, TxError> {
+	Ok(unsigned_transaction(inputs_json, outputs_json)?)
+}
+
 /// Decode a raw transaction and return transaction info.
 pub fn tx_decode(raw_tx_hex: &str, network: Network) -> Result<TransactionInfo, TxError> {
+	tx_decode_with_mainchain_headers(raw_tx_hex, network, &[])
+}
+
+/// Decode a raw transaction, checking the merkle proof of each pegin input
+/// against the given mainchain block headers (hex).
+pub fn tx_decode_with_mainchain_headers(
+	raw_tx_hex: &str,
+	network: Network,
+	mainchain_headers: &[&str],
+) -> Result<TransactionInfo, TxError> {
 	use crate::GetInfo;
 
 	let raw_tx = hex::decode(raw_tx_hex).map_err(TxError::TxHex)?;
 	let tx: Transaction = deserialize(&raw_tx).map_err(TxError::TxDeserialize)?;
+	let headers = mainchain_headers
+		.iter()
+		.map(|header| {
+			let bytes = hex::decode(header).map_err(TxError::MainchainHeaderHex)?;
+			bitcoin::consensus::deserialize::<bitcoin::block::Header>(&bytes)
+				.map_err(TxError::MainchainHeader)
+		})
+		.collect::<Result<Vec<_>, _>>()?;
+
+	let mut info: TransactionInfo = tx.get_info(network);
+	if !headers.is_empty() {
+		let inputs = info.inputs.iter_mut().flatten();
+		for pegin in inputs.filter_map(|input| input.pegin_data.as_mut()) {
+			if let Some(ref mut check) = pegin.merkle_proof_check {
+				check.header_matches =
+					Some(headers.iter().any(|h| h.block_hash() == pegin.referenced_block));
+			}
+		}
+	}
+	Ok(info)
+}
+
+/// Compute the entropy, asset ID and reissuance token ID of an issuance,
+/// either from its prevout and contract (or contract hash), or from its asset
+/// entropy for a reissuance, checking the asset ID against `asset` if given.
+///
+/// The reissuance token ID depends on whether the issued amount is blinded.
+pub fn tx_asset_id(
+	prevout: Option<&str>,
+	contract_hash: Option<&str>,
+	contract: Option<&str>,
+	entropy: Option<&str>,
+	confidential: bool,
+	asset: Option<&str>,
+) -> Result<AssetIdInfo, TxError> {
+	let contract_hash = match (contract_hash, contract) {
+		(Some(_), Some(_)) => return Err(TxError::ContractAndHash),
+		(Some(hash), None) => Some(hash.parse().map_err(TxError::ContractHashParse)?),
+		(None, Some(json)) => {
+			Some(ContractHash::from_json_contract(json).map_err(TxError::ContractJson)?)
+		}
+		(None, None) => None,
+	};
+	let asset = asset.map(str::parse::<AssetId>).transpose().map_err(TxError::AssetIdParse)?;
+
+	let (prevout, entropy) = match (prevout, entropy) {
+		(Some(_), Some(_)) => return Err(TxError::EntropyAndIssuance),
+		(None, Some(_)) if contract_hash.is_some() => return Err(TxError::EntropyAndIssuance),
+		(Some(prevout), None) => {
+			let prevout: OutPoint = prevout.parse().map_err(TxError::PrevoutParse)?;
+			let entropy = AssetId::generate_asset_entropy(
+				prevout,
+				contract_hash.unwrap_or(ContractHash::all_zeros()),
+			);
+			(Some(format!("{}:{}", prevout.txid, prevout.vout)), entropy)
+		}
+		(None, Some(entropy)) => {
+			(None, entropy.parse::<sha256::Midstate>().map_err(TxError::AssetEntropyParse)?)
+		}
+		(None, None) => return Err(TxError::NoPrevout),
+	};
+
+	let asset_id = AssetId::from_entropy(entropy);
+	Ok(AssetIdInfo {
+		contract_hash: prevout.as_ref().map(|_| contract_hash.unwrap_or(ContractHash::all_zeros())),
+		prevout,
+		entropy,
+		asset_id,
+		reissuance_token: AssetId::reissuance_token_from_entropy(entropy, confidential),
+		matches: asset.map(|asset| asset == asset_id),
+	})
+}
+
+/// The outcome of checking one input.
+enum Verdict {
+	Valid,
+	Invalid(String),
+	Unchecked(String),
+}
+
+/// Check a taproot spend, by key or by script.
+fn verify_taproot(
+	tx: &Arc<Transaction>,
+	utxos: &[ElementsUtxo],
+	index: usize,
+	genesis_hash: elements::BlockHash,
+) -> (SpendType, Verdict) {
+	let output_key = match XOnlyPublicKey::from_slice(&utxos[index].script_pubkey[2..]) {
+		Ok(key) => key,
+		Err(e) => {
+			return (SpendType::TaprootKey, Verdict::Invalid(format!("invalid output key: {}", e)))
+		}
+	};
+	let mut stack = tx.input[index].witness.script_witness.clone();
+	let annex = match stack.last() {
+		Some(last) if stack.len() >= 2 && last.first() == Some(&0x50) => stack.pop(),
+		_ => None,
+	};
+
+	if stack.len() < 2 {
+		let sig = match stack.first().map(|sig| SchnorrSig::from_slice(sig)) {
+			Some(Ok(sig)) => sig,
+			Some(Err(e)) => {
+				return (
+					SpendType::TaprootKey,
+					Verdict::Invalid(format!("invalid signature: {}", e)),
+				)
+			}
+			None => return (SpendType::TaprootKey, Verdict::Invalid("empty witness".to_string())),
+		};
+		let prevouts: Vec<_> = utxos
+			.iter()
+			.map(|utxo| TxOut {
+				asset: utxo.asset,
+				value: utxo.value,
+				nonce: confidential::Nonce::Null,
+				script_pubkey: utxo.script_pubkey.clone(),
+				witness: TxOutWitness::default(),
+			})
+			.collect();
+		let sighash = SighashCache::new(&**tx).taproot_key_spend_signature_hash(
+			index,
+			&Prevouts::All(&prevouts),
+			sig.hash_ty,
+			genesis_hash,
+		);
+		let verdict = match sighash {
+			Ok(sighash) => {
+				let msg = Message::from_digest(sighash.to_byte_array());
+				match SECP256K1.verify_schnorr(&sig.sig, &msg, &output_key) {
+					Ok(()) => Verdict::Valid,
+					Err(_) => Verdict::Invalid("signature does not verify".to_string()),
+				}
+			}
+			Err(e) => Verdict::Invalid(format!("failed to compute sighash: {}", e)),
+		};
+		return (SpendType::TaprootKey, verdict);
+	}
+
+	let control_block = match ControlBlock::from_slice(&stack.pop().expect("two elements")) {
+		Ok(control_block) => control_block,
+		Err(e) => {
+			return (
+				SpendType::Tapscript,
+				Verdict::Invalid(format!("invalid control block: {}", e)),
+			)
+		}
+	};
+	let script = Script::from(stack.pop().expect("two elements"));
+	let spend_type = if control_block.leaf_version == simplicity::leaf_version() {
+		SpendType::Simplicity
+	} else {
+		SpendType::Tapscript
+	};
+	let tweaked = TweakedPublicKey::new(output_key);
+	if !control_block.verify_taproot_commitment(SECP256K1, &tweaked, &script) {
+		let reason = "control block does not commit to the leaf".to_string();
+		return (spend_type, Verdict::Invalid(reason));
+	}
+	if spend_type == SpendType::Tapscript {
+		let reason = "only Simplicity leaves can be executed".to_string();
+		return (spend_type, Verdict::Unchecked(reason));
+	}
+
+	let verdict = match verify_simplicity(
+		tx,
+		utxos,
+		index,
+		&stack,
+		&script,
+		control_block,
+		annex,
+		genesis_hash,
+	) {
+		Ok(()) => Verdict::Valid,
+		Err(reason) => Verdict::Invalid(reason),
+	};
+	(spend_type, verdict)
+}
+
+/// Run the Simplicity program of a script path spend, given the witness stack
+/// left below the leaf and control block.
+#[allow(clippy::too_many_arguments)]
+fn verify_simplicity(
+	tx: &Arc<Transaction>,
+	utxos: &[ElementsUtxo],
+	index: usize,
+	stack: &[Vec<u8>],
+	script: &Script,
+	control_block: ControlBlock,
+	annex: Option<Vec<u8>>,
+	genesis_hash: elements::BlockHash,
+) -> Result<(), String> {
+	let [witness, program] = stack else {
+		return Err(format!(
+			"expected a witness and a program, but got {} stack items",
+			stack.len()
+		));
+	};
+	let cmr = <[u8; 32]>::try_from(script.as_bytes())
+		.map(Cmr::from_byte_array)
+		.map_err(|_| "leaf is not a CMR".to_string())?;
+	let program = Program::<jet::Elements>::from_bytes(program, Some(witness))
+		.map_err(|e| format!("invalid program: {}", e))?;
+	if program.cmr() != cmr {
+		return Err(format!("program has CMR {}, but the leaf commits to {}", program.cmr(), cmr));
+	}
+	let redeem_node = program.redeem_node().expect("a witness was given");
+	if !redeem_node.bounds().cost.is_budget_valid(&tx.input[index].witness.script_witness) {
+		return Err("program cost exceeds the budget of its witness".to_string());
+	}
+
+	let tx_env = ElementsEnv::new(
+		Arc::clone(tx),
+		utxos.to_vec(),
+		index as u32, // cast fine, input indices are always small
+		cmr,
+		control_block,
+		annex,
+		genesis_hash,
+	);
+	let mut mac = BitMachine::for_program(redeem_node)
+		.map_err(|e| format!("failed to construct bit machine: {}", e))?;
+	mac.exec(redeem_node, &tx_env).map_err(|e| format!("program failed: {}", e))?;
+	Ok(())
+}
+
+/// Check a P2WPKH spend.
+fn verify_p2wpkh(tx: &Transaction, utxos: &[ElementsUtxo], index: usize) -> Verdict {
+	let [sig, pubkey] = &tx.input[index].witness.script_witness[..] else {
+		return Verdict::Invalid("expected a signature and a public key".to_string());
+	};
+	let pubkey = match bitcoin::PublicKey::from_slice(pubkey) {
+		Ok(pubkey) => pubkey,
+		Err(e) => return Verdict::Invalid(format!("invalid public key: {}", e)),
+	};
+	let pubkey_hash = elements::PubkeyHash::hash(&pubkey.to_bytes());
+	if utxos[index].script_pubkey[2..] != pubkey_hash[..] {
+		return Verdict::Invalid("public key does not match the output".to_string());
+	}
+	let Some((&hash_ty, sig)) = sig.split_last() else {
+		return Verdict::Invalid("empty signature".to_string());
+	};
+	let sig = match secp256k1::ecdsa::Signature::from_der(sig) {
+		Ok(sig) => sig,
+		Err(e) => return Verdict::Invalid(format!("invalid signature: {}", e)),
+	};
+	let sighash = SighashCache::new(tx).segwitv0_sighash(
+		index,
+		&Script::new_p2pkh(&pubkey_hash),
+		utxos[index].value,
+		EcdsaSighashType::from_u32(hash_ty.into()),
+	);
+	let msg = Message::from_digest(sighash.to_byte_array());
+	match SECP256K1.verify_ecdsa(&msg, &sig, &pubkey.inner) {
+		Ok(()) => Verdict::Valid,
+		Err(_) => Verdict::Invalid("signature does not verify".to_string()),
+	}
+}
+
+/// Check the witness of every input of a signed transaction, given as hex or
+/// as a finalized PSET, against the outputs it spends.
+///
+/// Simplicity programs are executed, and taproot key spends and P2WPKH
+/// signatures are verified. Other spends, including tapscripts which are not
+/// Simplicity, are reported as not checked. The spent outputs are taken from
+/// the PSET unless `input_utxos` are given, in the form accepted by
+/// [`parse_elements_utxo`].
+pub fn tx_verify(
+	tx: &str,
+	input_utxos: Option<&[&str]>,
+	genesis_hash: Option<&str>,
+) -> Result<TxVerifyInfo, TxError> {
+	let pset = tx.parse::<PartiallySignedTransaction>().ok();
+	let tx = match pset {
+		Some(ref pset) => pset.extract_tx().map_err(TxError::PsetExtract)?,
+		None => {
+			let raw_tx = hex::decode(tx).map_err(TxError::TxHex)?;
+			deserialize(&raw_tx).map_err(TxError::TxDeserialize)?
+		}
+	};
+
+	let utxos = match (input_utxos, pset) {
+		(Some(input_utxos), _) => input_utxos
+			.iter()
+			.map(|utxo| parse_elements_utxo(utxo).map_err(TxError::InputUtxoParse))
+			.collect::<Result<Vec<_>, _>>()?,
+		(None, Some(pset)) => pset
+			.inputs()
+			.iter()
+			.enumerate()
+			.map(|(n, input)| match input.witness_utxo {
+				Some(ref utxo) => Ok(ElementsUtxo {
+					script_pubkey: utxo.script_pubkey.clone(),
+					asset: utxo.asset,
+					value: utxo.value,
+				}),
+				None => Err(TxError::WitnessUtxoMissing {
+					input: n,
+				}),
+			})
+			.collect::<Result<Vec<_>, _>>()?,
+		(None, None) => return Err(TxError::InputUtxosRequired),
+	};
+	if utxos.len() != tx.input.len() {
+		return Err(TxError::InputUtxoCountMismatch {
+			expected: tx.input.len(),
+			actual: utxos.len(),
+		});
+	}
+
+	// Default to the Liquid testnet genesis block.
+	let genesis_hash = match genesis_hash {
+		Some(s) => s.parse().map_err(TxError::GenesisHashParse)?,
+		None => {
+			crate::Network::LiquidTestnet.genesis_hash().expect("Liquid testnet has a genesis hash")
+		}
+	};
+
+	let tx = Arc::new(tx);
+	let inputs: Vec<_> = (0..tx.input.len())
+		.map(|index| {
+			let script_pubkey = &utxos[index].script_pubkey;
+			let (spend_type, verdict) = if tx.input[index].is_pegin {
+				let reason = "pegins are checked by tx decode".to_string();
+				(SpendType::Pegin, Verdict::Unchecked(reason))
+			} else if script_pubkey.is_v1_p2tr() {
+				verify_taproot(&tx, &utxos, index, genesis_hash)
+			} else if script_pubkey.is_v0_p2wpkh() {
+				(SpendType::P2wpkh, verify_p2wpkh(&tx, &utxos, index))
+			} else {
+				let reason = "only taproot and P2WPKH spends can be checked".to_string();
+				(SpendType::Other, Verdict::Unchecked(reason))
+			};
+			let (valid, reason) = match verdict {
+				Verdict::Valid => (Some(true), None),
+				Verdict::Invalid(reason) => (Some(false), Some(reason)),
+				Verdict::Unchecked(reason) => (None, Some(reason)),
+			};
+			InputVerifyInfo {
+				index,
+				spend_type,
+				valid,
+				reason,
+			}
+		})
+		.collect();
 
-	Ok(tx.get_info(network))
+	Ok(TxVerifyInfo {
+		txid: tx.txid(),
+		valid: inputs.iter().all(|input| input.valid == Some(true)),
+		inputs,
+	})
+}
+
+/// Compute the nonce of a blinded output from the ECDH of a secret key and a
+/// public key: either the receiver's blinding key and the output's nonce
+/// commitment, or the sender's ephemeral key and the receiver's blinding
+/// public key.
+pub fn tx_output_nonce(secret_key: &str, public_key: &str) -> Result<OutputNonceInfo, TxError> {
+	let secret_key: secp256k1::SecretKey = secret_key.parse().map_err(TxError::SecretKeyParse)?;
+	let public_key: PublicKey = public_key.parse().map_err(TxError::PublicKeyParse)?;
+	let nonce = confidential::Nonce::Confidential(public_key)
+		.shared_secret(&secret_key)
+		.expect("confidential nonces have a shared secret");
+	Ok(OutputNonceInfo {
+		nonce,
+	})
+}
+
+/// Rewind the rangeproof of a blinded output with its nonce, as computed by
+/// [`tx_output_nonce`], giving the output's amount, asset and blinding factors.
+pub fn tx_rewind_output(
+	raw_tx: &str,
+	output_idx: &str,
+	nonce: &str,
+) -> Result<OutputSecretsInfo, TxError> {
+	let tx_bytes = hex::decode(raw_tx).map_err(TxError::TxHex)?;
+	let tx: Transaction = deserialize(&tx_bytes).map_err(TxError::TxDeserialize)?;
+	let index: usize = output_idx.parse().map_err(TxError::OutputIndexParse)?;
+	let nonce: secp256k1::SecretKey = nonce.parse().map_err(TxError::NonceParse)?;
+
+	let output = tx.output.get(index).ok_or(TxError::OutputIndexOutOfRange {
+		index,
+		total: tx.output.len(),
+	})?;
+	let (commitment, generator) = match (output.value, output.asset) {
+		(confidential::Value::Confidential(comm), confidential::Asset::Confidential(gen)) => {
+			(comm, gen)
+		}
+		_ => {
+			return Err(TxError::OutputNotBlinded {
+				index,
+			})
+		}
+	};
+	let rangeproof = output.witness.rangeproof.as_ref().ok_or(TxError::RangeproofMissing {
+		index,
+	})?;
+
+	let (opening, _) = rangeproof
+		.rewind(SECP256K1, commitment, nonce, output.script_pubkey.as_bytes(), generator)
+		.map_err(TxError::Rewind)?;
+	// The message holds the asset and its blinding factor, as Elements writes it.
+	let message = opening.message.get(..64).ok_or(TxError::RewindMessage)?;
+	let asset = AssetId::from_slice(&message[..32]).map_err(|_| TxError::RewindMessage)?;
+	let asset_blinding_factor = confidential::AssetBlindingFactor::from_slice(&message[32..])
+		.map_err(|_| TxError::RewindMessage)?;
+	let value_blinding_factor =
+		confidential::ValueBlindingFactor::from_slice(opening.blinding_factor.as_ref())
+			.expect("32-byte tweak");
+
+	let asset_comm = confidential::Asset::new_confidential(SECP256K1, asset, asset_blinding_factor);
+	let value_comm = confidential::Value::new_confidential(
+		SECP256K1,
+		opening.value,
+		generator,
+		value_blinding_factor,
+	);
+	Ok(OutputSecretsInfo {
+		value: opening.value,
+		asset,
+		value_blinding_factor,
+		asset_blinding_factor,
+		commitments_match: asset_comm == output.asset && value_comm == output.value,
+	})
 }
@@ -1,44 +1,85 @@
+use elements::bitcoin::Amount;
 use elements::bitcoin::{secp256k1, PublicKey};
-use elements::{Address, PubkeyHash, Script, ScriptHash, WPubkeyHash, WScriptHash};
+use elements::schnorr::XOnlyPublicKey;
+use elements::taproot::TapNodeHash;
+use elements::{Address, AssetId, PubkeyHash, Script, ScriptHash, WPubkeyHash, WScriptHash};
 use serde::{Deserialize, Serialize};
 
+use crate::simplicity::Cmr;
 use crate::Network;
 
 #[derive(Clone, PartialEq, Eq, Debug, Deserialize, Serialize)]
+#[cfg_attr(feature = "daemon", derive(schemars::JsonSchema))]
 pub struct AddressInfo {
 	pub network: Network,
 	#[serde(rename = "type")]
 	pub type_: Option<String>,
+	#[cfg_attr(feature = "daemon", schemars(with = "crate::tx::OutputScriptInfo"))]
 	pub script_pub_key: ::hal::tx::OutputScriptInfo,
 	#[serde(skip_serializing_if = "Option::is_none")]
 	pub witness_program_version: Option<usize>,
 	#[serde(skip_serializing_if = "Option::is_none")]
+	#[cfg_attr(feature = "daemon", schemars(with = "Option<String>"))]
 	pub pubkey_hash: Option<PubkeyHash>,
 	#[serde(skip_serializing_if = "Option::is_none")]
+	#[cfg_attr(feature = "daemon", schemars(with = "Option<String>"))]
 	pub script_hash: Option<ScriptHash>,
 	#[serde(skip_serializing_if = "Option::is_none")]
+	#[cfg_attr(feature = "daemon", schemars(with = "Option<String>"))]
 	pub witness_pubkey_hash: Option<WPubkeyHash>,
 	#[serde(skip_serializing_if = "Option::is_none")]
+	#[cfg_attr(feature = "daemon", schemars(with = "Option<String>"))]
 	pub witness_script_hash: Option<WScriptHash>,
 	#[serde(skip_serializing_if = "Option::is_none")]
+	#[cfg_attr(feature = "daemon", schemars(with = "Option<String>"))]
 	pub blinding_pubkey: Option<secp256k1::PublicKey>,
 	#[serde(skip_serializing_if = "Option::is_none")]
+	#[cfg_attr(feature = "daemon", schemars(with = "Option<String>"))]
 	pub unconfidential: Option<Address>,
+	/// Set when the address was checked against a Simplicity program.
+	#[serde(default, skip_serializing_if = "Option::is_none")]
+	pub simplicity: Option<SimplicityCommitment>,
+}
+
+/// The taproot output committing to a Simplicity program, as reconstructed
+/// from the program's CMR, internal key and state.
+#[derive(Clone, PartialEq, Eq, Debug, Deserialize, Serialize)]
+#[cfg_attr(feature = "daemon", derive(schemars::JsonSchema))]
+pub struct SimplicityCommitment {
+	#[cfg_attr(feature = "daemon", schemars(with = "String"))]
+	pub cmr: Cmr,
+	#[cfg_attr(feature = "daemon", schemars(with = "String"))]
+	pub internal_key: XOnlyPublicKey,
+	#[serde(skip_serializing_if = "Option::is_none")]
+	pub state: Option<String>,
+	#[cfg_attr(feature = "daemon", schemars(with = "String"))]
+	pub merkle_root: TapNodeHash,
+	#[cfg_attr(feature = "daemon", schemars(with = "String"))]
+	pub output_key: XOnlyPublicKey,
+	/// Whether the address pays to `output_key`.
+	pub matches: bool,
 }
 
 #[derive(Clone, PartialEq, Eq, Debug, Default, Deserialize, Serialize)]
+#[cfg_attr(feature = "daemon", derive(schemars::JsonSchema))]
 pub struct Addresses {
 	#[serde(skip_serializing_if = "Option::is_none")]
+	#[cfg_attr(feature = "daemon", schemars(with = "Option<String>"))]
 	pub p2pkh: Option<Address>,
 	#[serde(skip_serializing_if = "Option::is_none")]
+	#[cfg_attr(feature = "daemon", schemars(with = "Option<String>"))]
 	pub p2wpkh: Option<Address>,
 	#[serde(skip_serializing_if = "Option::is_none")]
+	#[cfg_attr(feature = "daemon", schemars(with = "Option<String>"))]
 	pub p2shwpkh: Option<Address>,
 	#[serde(skip_serializing_if = "Option::is_none")]
+	#[cfg_attr(feature = "daemon", schemars(with = "Option<String>"))]
 	pub p2sh: Option<Address>,
 	#[serde(skip_serializing_if = "Option::is_none")]
+	#[cfg_attr(feature = "daemon", schemars(with = "Option<String>"))]
 	pub p2wsh: Option<Address>,
 	#[serde(skip_serializing_if = "Option::is_none")]
+	#[cfg_attr(feature = "daemon", schemars(with = "Option<String>"))]
 	pub p2shwsh: Option<Address>,
 }
 
@@ -71,3 +112,43 @@ impl Addresses {
 		}
 	}
 }
+
+/// A BIP-0021 style payment URI, such as `liquidnetwork:<address>?amount=0.1&assetid=<asset>`.
+/// A P2WPKH address found by grinding keys, with the key which spends it.
+#[derive(Clone, PartialEq, Eq, Debug, Deserialize, Serialize)]
+#[cfg_attr(feature = "daemon", derive(schemars::JsonSchema))]
+pub struct VanityAddress {
+	#[cfg_attr(feature = "daemon", schemars(with = "String"))]
+	pub address: Address,
+	#[cfg_attr(feature = "daemon", schemars(with = "String"))]
+	pub secret: secp256k1::SecretKey,
+	#[cfg_attr(feature = "daemon", schemars(with = "String"))]
+	pub pubkey: PublicKey,
+	/// The number of keys tried before the address was found.
+	pub attempts: u64,
+}
+
+#[derive(Clone, PartialEq, Eq, Debug, Deserialize, Serialize)]
+#[cfg_attr(feature = "daemon", derive(schemars::JsonSchema))]
+pub struct PaymentUri {
+	pub uri: String,
+	pub network: Network,
+	#[cfg_attr(feature = "daemon", schemars(with = "String"))]
+	pub address: Address,
+	/// The amount to pay, in units of the asset with its decimal point 8 places
+	/// from the right.
+	#[serde(
+		default,
+		skip_serializing_if = "Option::is_none",
+		with = "elements::bitcoin::amount::serde::as_btc::opt"
+	)]
+	#[cfg_attr(feature = "daemon", schemars(with = "Option<f64>"))]
+	pub amount: Option<Amount>,
+	#[serde(skip_serializing_if = "Option::is_none")]
+	#[cfg_attr(feature = "daemon", schemars(with = "Option<String>"))]
+	pub assetid: Option<AssetId>,
+	#[serde(skip_serializing_if = "Option::is_none")]
+	pub label: Option<String>,
+	#[serde(skip_serializing_if = "Option::is_none")]
+	pub message: Option<String>,
+}
@@ -0,0 +1,483 @@
+//! Typed library API
+//!
+//! [`Api`] has one method per stateless operation of the daemon, taking the
+//! same request types, so that a Rust application can link the crate rather
+//! than run the CLI or a daemon. The methods call [`crate::actions`] directly
+//! and fail with the actions' own errors, each of which implements
+//! [`ActionError`](crate::actions::error::ActionError). No feature is needed.
+//!
+//! ```no_run
+//! use hal_simplicity::api::{Api, TxDecodeRequest};
+//! use hal_simplicity::Network;
+//!
+//! let api = Api::new(Network::LiquidTestnet);
+//! let tx = api.tx_decode(&TxDecodeRequest {
+//!     raw_tx: "0200...".to_owned(),
+//!     network: None,
+//!     mainchain_headers: None,
+//! })?;
+//! # Ok::<(), hal_simplicity::actions::tx::TxError>(())
+//! ```
+//!
+//! Sessions, watched addresses, jobs and chain backends are kept by a daemon,
+//! so the methods using them are only on its client. The PSET requests here
+//! therefore take the PSET itself, and `pset_update_input` the UTXO spent.
+
+use serde::{Deserialize, Serialize};
+
+use crate::actions;
+use crate::actions::address::AddressError;
+use crate::actions::block::BlockError;
+use crate::actions::simplicity::pset::{
+	PsetCreateError, PsetExtractError, PsetFinalizeError, PsetRunError, PsetUpdateInputError,
+	RunResponse, UpdatedPset, DEFAULT_MAX_JETS,
+};
+use crate::actions::simplicity::{
+	ProgramInfo, SighashInfo, SimplicityInfoError, SimplicitySighashError,
+};
+use crate::actions::tx::TxError;
+use crate::block::BlockInfo;
+use crate::tx::TransactionInfo;
+use crate::Network;
+
+// Address types
+#[derive(Debug, Serialize, Deserialize)]
+#[cfg_attr(feature = "daemon", derive(schemars::JsonSchema))]
+pub struct AddressCreateRequest {
+	pub network: Option<Network>,
+	pub pubkey: Option<String>,
+	pub script: Option<String>,
+	pub blinder: Option<String>,
+}
+
+pub use crate::address::Addresses as AddressCreateResponse;
+
+#[derive(Debug, Serialize, Deserialize)]
+#[cfg_attr(feature = "daemon", derive(schemars::JsonSchema))]
+pub struct AddressInspectRequest {
+	pub address: String,
+	pub cmr: Option<String>,
+	pub internal_key: Option<String>,
+	pub state: Option<String>,
+}
+
+pub use crate::address::AddressInfo as AddressInspectResponse;
+
+// Block types
+#[derive(Debug, Serialize, Deserialize)]
+#[cfg_attr(feature = "daemon", derive(schemars::JsonSchema))]
+pub struct BlockCreateRequest {
+	pub block_info: BlockInfo,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+#[cfg_attr(feature = "daemon", derive(schemars::JsonSchema))]
+pub struct BlockCreateResponse {
+	pub raw_block: String,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+#[cfg_attr(feature = "daemon", derive(schemars::JsonSchema))]
+pub struct BlockDecodeRequest {
+	pub raw_block: String,
+	pub network: Option<Network>,
+	pub txids: Option<bool>,
+	pub full: Option<bool>,
+}
+
+/// The block, or only its header if neither `txids` nor `full` is set.
+pub use crate::actions::block::BlockDecodeOutput as BlockDecodeResponse;
+
+#[derive(Debug, Serialize, Deserialize)]
+#[cfg_attr(feature = "daemon", derive(schemars::JsonSchema))]
+pub struct BlockStatsRequest {
+	pub raw_block: String,
+}
+
+pub use crate::actions::block::BlockStats as BlockStatsResponse;
+
+#[derive(Debug, Serialize, Deserialize)]
+#[cfg_attr(feature = "daemon", derive(schemars::JsonSchema))]
+pub struct BlockVerifyChainRequest {
+	pub raw_headers: Vec<String>,
+}
+
+pub use crate::actions::block::ChainVerification as BlockVerifyChainResponse;
+
+#[derive(Debug, Serialize, Deserialize)]
+#[cfg_attr(feature = "daemon", derive(schemars::JsonSchema))]
+pub struct BlockVerifySignblockRequest {
+	pub raw_block: String,
+}
+
+pub use crate::actions::block::SignblockVerification as BlockVerifySignblockResponse;
+
+// Transaction types
+#[derive(Debug, Serialize, Deserialize)]
+#[cfg_attr(feature = "daemon", derive(schemars::JsonSchema))]
+pub struct TxCreateRequest {
+	pub tx_info: TransactionInfo,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+#[cfg_attr(feature = "daemon", derive(schemars::JsonSchema))]
+pub struct TxCreateResponse {
+	pub raw_tx: String,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+#[cfg_attr(feature = "daemon", derive(schemars::JsonSchema))]
+pub struct TxDecodeRequest {
+	pub raw_tx: String,
+	pub network: Option<Network>,
+	/// Mainchain block headers to check the merkle proofs of pegin inputs
+	/// against (hex).
+	pub mainchain_headers: Option<Vec<String>>,
+}
+
+pub type TxDecodeResponse = TransactionInfo;
+
+// Simplicity types
+#[derive(Debug, Serialize, Deserialize)]
+#[cfg_attr(feature = "daemon", derive(schemars::JsonSchema))]
+pub struct SimplicityInfoRequest {
+	pub program: String,
+	pub witness: Option<String>,
+	pub state: Option<String>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+#[cfg_attr(feature = "daemon", derive(schemars::JsonSchema))]
+pub struct SimplicitySighashRequest {
+	pub tx: String,
+	pub input_index: u32,
+	pub cmr: String,
+	pub control_block: Option<String>,
+	pub genesis_hash: Option<String>,
+	/// The network whose genesis hash to use, unless `genesis_hash` is given.
+	pub network: Option<Network>,
+	pub secret_key: Option<String>,
+	pub public_key: Option<String>,
+	pub signature: Option<String>,
+	pub input_utxos: Option<Vec<String>>,
+}
+
+// PSET types
+#[derive(Debug, Serialize, Deserialize)]
+#[cfg_attr(feature = "daemon", derive(schemars::JsonSchema))]
+pub struct PsetCreateRequest {
+	pub inputs: String,
+	pub outputs: String,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+#[cfg_attr(feature = "daemon", derive(schemars::JsonSchema))]
+pub struct PsetExtractRequest {
+	pub pset: String,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+#[cfg_attr(feature = "daemon", derive(schemars::JsonSchema))]
+pub struct PsetExtractResponse {
+	pub raw_tx: String,
+}
+
+/// As the daemon's `pset_finalize`, without a session.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct PsetFinalizeRequest {
+	pub pset: String,
+	pub input_index: u32,
+	pub program: String,
+	pub witness: String,
+	pub genesis_hash: Option<String>,
+	/// The network whose genesis hash to use, unless `genesis_hash` is given.
+	pub network: Option<Network>,
+}
+
+/// As the daemon's `pset_run`, without a session.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct PsetRunRequest {
+	pub pset: String,
+	pub input_index: u32,
+	pub program: String,
+	pub witness: String,
+	pub genesis_hash: Option<String>,
+	/// The network whose genesis hash to use, unless `genesis_hash` is given.
+	pub network: Option<Network>,
+	/// Keep only the first and last `max_jets` jet calls in the response,
+	/// along with the number of calls of each jet. Defaults to 1000.
+	pub max_jets: Option<usize>,
+	/// Keep every jet call, however many, instead of bounding them by
+	/// `max_jets`.
+	#[serde(default)]
+	pub all_jets: bool,
+}
+
+/// As the daemon's `pset_update_input`, without a session, and with the UTXO
+/// spent always given, as there is no chain backend to fetch it from.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct PsetUpdateInputRequest {
+	pub pset: String,
+	pub input_index: u32,
+	/// The output being spent, as `<scriptPubKey>:<asset>:<value>`.
+	pub input_utxo: String,
+	pub internal_key: Option<String>,
+	pub cmr: Option<String>,
+	pub state: Option<String>,
+}
+
+/// The daemon's stateless operations, called in-process.
+#[derive(Clone, Debug)]
+pub struct Api {
+	/// Network used by requests which do not specify one.
+	pub network: Network,
+	/// Genesis hash used by requests which give neither one nor a network.
+	pub genesis_hash: Option<String>,
+}
+
+impl Default for Api {
+	fn default() -> Self {
+		Self::new(Network::Liquid)
+	}
+}
+
+impl Api {
+	/// Use `network` for requests which do not specify one.
+	pub fn new(network: Network) -> Self {
+		Self {
+			network,
+			genesis_hash: None,
+		}
+	}
+
+	/// The genesis hash of a request: its own, else its network's, else the
+	/// configured one, else that of our network if it named none.
+	fn genesis_hash_for(
+		&self,
+		requested: Option<&str>,
+		network: Option<Network>,
+	) -> Option<String> {
+		requested
+			.map(str::to_owned)
+			.or_else(|| network.and_then(Network::genesis_hash).map(|h| h.to_string()))
+			.or_else(|| self.genesis_hash.clone())
+			.or_else(|| network.unwrap_or(self.network).genesis_hash().map(|h| h.to_string()))
+	}
+
+	pub fn address_create(
+		&self,
+		req: &AddressCreateRequest,
+	) -> Result<AddressCreateResponse, AddressError> {
+		actions::address::address_create(
+			req.pubkey.as_deref(),
+			req.script.as_deref(),
+			req.blinder.as_deref(),
+			req.network.unwrap_or(self.network),
+		)
+	}
+
+	pub fn address_inspect(
+		&self,
+		req: &AddressInspectRequest,
+	) -> Result<AddressInspectResponse, AddressError> {
+		actions::address::address_inspect_with_program(
+			&req.address,
+			req.cmr.as_deref(),
+			req.internal_key.as_deref(),
+			req.state.as_deref(),
+		)
+	}
+
+	pub fn block_create(
+		&self,
+		req: &BlockCreateRequest,
+	) -> Result<BlockCreateResponse, BlockError> {
+		let block = actions::block::block_create(req.block_info.clone())?;
+		Ok(BlockCreateResponse {
+			raw_block: elements::encode::serialize_hex(&block),
+		})
+	}
+
+	pub fn block_decode(
+		&self,
+		req: &BlockDecodeRequest,
+	) -> Result<BlockDecodeResponse, BlockError> {
+		actions::block::block_decode(
+			&req.raw_block,
+			req.network.unwrap_or(self.network),
+			req.txids.unwrap_or(false),
+			req.full.unwrap_or(false),
+		)
+	}
+
+	pub fn block_stats(&self, req: &BlockStatsRequest) -> Result<BlockStatsResponse, BlockError> {
+		actions::block::block_stats(&req.raw_block)
+	}
+
+	pub fn block_verify_chain(
+		&self,
+		req: &BlockVerifyChainRequest,
+	) -> Result<BlockVerifyChainResponse, BlockError> {
+		let raw_headers: Vec<&str> = req.raw_headers.iter().map(String::as_str).collect();
+		actions::block::block_verify_chain(&raw_headers)
+	}
+
+	pub fn block_verify_signblock(
+		&self,
+		req: &BlockVerifySignblockRequest,
+	) -> Result<BlockVerifySignblockResponse, BlockError> {
+		actions::block::block_verify_signblock(&req.raw_block)
+	}
+
+	pub fn tx_create(&self, req: &TxCreateRequest) -> Result<TxCreateResponse, TxError> {
+		let tx = actions::tx::tx_create(req.tx_info.clone())?;
+		Ok(TxCreateResponse {
+			raw_tx: elements::encode::serialize_hex(&tx),
+		})
+	}
+
+	pub fn tx_decode(&self, req: &TxDecodeRequest) -> Result<TxDecodeResponse, TxError> {
+		let headers: Vec<&str> =
+			req.mainchain_headers.iter().flatten().map(String::as_str).collect();
+		actions::tx::tx_decode_with_mainchain_headers(
+			&req.raw_tx,
+			req.network.unwrap_or(self.network),
+			&headers,
+		)
+	}
+
+	pub fn keypair_generate(&self) -> actions::keypair::KeypairInfo {
+		actions::keypair::keypair_generate()
+	}
+
+	pub fn simplicity_info(
+		&self,
+		req: &SimplicityInfoRequest,
+	) -> Result<ProgramInfo, SimplicityInfoError> {
+		actions::simplicity::simplicity_info(
+			&req.program,
+			req.witness.as_deref(),
+			req.state.as_deref(),
+		)
+	}
+
+	pub fn simplicity_sighash(
+		&self,
+		req: &SimplicitySighashRequest,
+	) -> Result<SighashInfo, SimplicitySighashError> {
+		let input_utxos =
+			req.input_utxos.as_ref().map(|v| v.iter().map(String::as_str).collect::<Vec<_>>());
+		let genesis_hash = self.genesis_hash_for(req.genesis_hash.as_deref(), req.network);
+		actions::simplicity::simplicity_sighash(
+			&req.tx,
+			&req.input_index.to_string(),
+			&req.cmr,
+			req.control_block.as_deref(),
+			genesis_hash.as_deref(),
+			req.secret_key.as_deref(),
+			req.public_key.as_deref(),
+			req.signature.as_deref(),
+			input_utxos.as_deref(),
+		)
+	}
+
+	pub fn pset_create(&self, req: &PsetCreateRequest) -> Result<UpdatedPset, PsetCreateError> {
+		actions::simplicity::pset::pset_create(&req.inputs, &req.outputs)
+	}
+
+	pub fn pset_extract(
+		&self,
+		req: &PsetExtractRequest,
+	) -> Result<PsetExtractResponse, PsetExtractError> {
+		Ok(PsetExtractResponse {
+			raw_tx: actions::simplicity::pset::pset_extract(&req.pset)?,
+		})
+	}
+
+	pub fn pset_finalize(
+		&self,
+		req: &PsetFinalizeRequest,
+	) -> Result<UpdatedPset, PsetFinalizeError> {
+		let genesis_hash = self.genesis_hash_for(req.genesis_hash.as_deref(), req.network);
+		actions::simplicity::pset::pset_finalize(
+			&req.pset,
+			&req.input_index.to_string(),
+			&req.program,
+			&req.witness,
+			genesis_hash.as_deref(),
+		)
+	}
+
+	pub fn pset_run(&self, req: &PsetRunRequest) -> Result<RunResponse, PsetRunError> {
+		let genesis_hash = self.genesis_hash_for(req.genesis_hash.as_deref(), req.network);
+		let max_jets = match req.all_jets {
+			true => None,
+			false => Some(req.max_jets.unwrap_or(DEFAULT_MAX_JETS)),
+		};
+		actions::simplicity::pset::pset_run_with_callback(
+			&req.pset,
+			&req.input_index.to_string(),
+			&req.program,
+			&req.witness,
+			genesis_hash.as_deref(),
+			max_jets,
+			|_| std::ops::ControlFlow::Continue(()),
+		)
+	}
+
+	pub fn pset_update_input(
+		&self,
+		req: &PsetUpdateInputRequest,
+	) -> Result<UpdatedPset, PsetUpdateInputError> {
+		actions::simplicity::pset::pset_update_input(
+			&req.pset,
+			&req.input_index.to_string(),
+			&req.input_utxo,
+			req.internal_key.as_deref(),
+			req.cmr.as_deref(),
+			req.state.as_deref(),
+		)
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use crate::actions::error::{ActionError, Subsystem};
+
+	#[test]
+	fn test_api() {
+		let api = Api::new(Network::LiquidTestnet);
+		let pubkey = "026a04ab98d9e4774ad806e302dddeb63bea16b5cb5f223ee77478e861bb583eb3";
+		let addresses = api
+			.address_create(&AddressCreateRequest {
+				network: None,
+				pubkey: Some(pubkey.to_owned()),
+				script: None,
+				blinder: None,
+			})
+			.unwrap();
+		let address = addresses.p2wpkh.unwrap().to_string();
+		assert!(address.starts_with("tex1"));
+
+		let info = api
+			.address_inspect(&AddressInspectRequest {
+				address,
+				cmr: None,
+				internal_key: None,
+				state: None,
+			})
+			.unwrap();
+		assert_eq!(info.network, Network::LiquidTestnet);
+
+		let error = api
+			.tx_decode(&TxDecodeRequest {
+				raw_tx: "00".to_owned(),
+				network: None,
+				mainchain_headers: None,
+			})
+			.unwrap_err();
+		assert!(matches!(error, TxError::TxDeserialize(_)));
+		assert_eq!(error.subsystem(), Subsystem::Tx);
+	}
+}
@@ -6,19 +6,36 @@ fn main() {
 
 #[cfg(feature = "daemon")]
 fn main() {
-	use hal_simplicity::daemon::HalSimplicityDaemon;
+	use hal_simplicity::daemon::config::{Config, ListenerConfig};
 
-	/// Default address for the TCP listener
-	const DEFAULT_ADDRESS: &str = "127.0.0.1:28579";
-
-	/// Setup logging with the given log level.
-	fn setup_logger(lvl: log::LevelFilter) {
-		fern::Dispatch::new()
-			.format(|out, message, _record| out.finish(format_args!("{}", message)))
+	/// Setup logging with the given log level, to stderr and optionally a file.
+	fn setup_logger(lvl: log::LevelFilter, file: Option<&std::path::Path>) {
+		let mut dispatch = fern::Dispatch::new()
+			.format(|out, message, record| {
+				out.finish(format_args!(
+					"{} {:<5} {}: {}",
+					chrono::Utc::now().to_rfc3339_opts(chrono::SecondsFormat::Millis, true),
+					record.level(),
+					record.target(),
+					message
+				))
+			})
 			.level(lvl)
-			.chain(std::io::stderr())
-			.apply()
-			.expect("error setting up logger");
+			.chain(std::io::stderr());
+		if let Some(file) = file {
+			dispatch = dispatch.chain(fern::log_file(file).unwrap_or_else(|e| {
+				eprintln!("Failed to open log file {}: {}", file.display(), e);
+				std::process::exit(1);
+			}));
+		}
+		dispatch.apply().expect("error setting up logger");
+	}
+
+	/// The data directory used unless another is configured.
+	fn default_data_dir() -> Option<std::path::PathBuf> {
+		std::env::var_os("HOME")
+			.or_else(|| std::env::var_os("USERPROFILE"))
+			.map(|home| std::path::PathBuf::from(home).join(".hal-simplicity"))
 	}
 
 	/// Create the main app object.
@@ -27,12 +44,211 @@ fn main() {
 			.bin_name("hal-simplicity-daemon")
 			.version(clap::crate_version!())
 			.about("hal-simplicity-daemon -- JSON-RPC daemon for Simplicity operations")
+			.arg(
+				clap::Arg::with_name("conf")
+					.short("c")
+					.long("conf")
+					.value_name("PATH")
+					.help("TOML configuration file; command-line options take precedence over it")
+					.takes_value(true),
+			)
 			.arg(
 				clap::Arg::with_name("address")
 					.short("a")
 					.long("address")
 					.value_name("ADDRESS")
-					.help("TCP address to bind to (default: 127.0.0.1:28579)")
+					.help("TCP address to bind to, IPv4 or IPv6 (default: 127.0.0.1:28579; may be given several times)")
+					.takes_value(true)
+					.multiple(true)
+					.number_of_values(1),
+			)
+			.arg(
+				clap::Arg::with_name("unix-socket")
+					.long("unix-socket")
+					.value_name("PATH")
+					.help("Serve on this unix socket; also on TCP only if --address is given (may be given several times)")
+					.takes_value(true)
+					.multiple(true)
+					.number_of_values(1),
+			)
+			.arg(
+				clap::Arg::with_name("websocket-address")
+					.long("websocket-address")
+					.value_name("ADDRESS")
+					.help("Also serve JSON-RPC over WebSocket on this TCP address (may be given several times)")
+					.takes_value(true)
+					.multiple(true)
+					.number_of_values(1),
+			)
+			.arg(
+				clap::Arg::with_name("cookie-file")
+					.long("cookie-file")
+					.value_name("PATH")
+					.help("Write a random password to this file at startup and require it from clients")
+					.takes_value(true),
+			)
+			.arg(
+				clap::Arg::with_name("auth-token")
+					.long("auth-token")
+					.value_name("TOKEN")
+					.help("Require this token from clients (as a bearer token or basic auth password)")
+					.takes_value(true),
+			)
+			.arg(
+				clap::Arg::with_name("tls-cert")
+					.long("tls-cert")
+					.value_name("PATH")
+					.help("Serve over TLS with this PEM certificate chain (requires --tls-key)")
+					.takes_value(true)
+					.requires("tls-key"),
+			)
+			.arg(
+				clap::Arg::with_name("tls-key")
+					.long("tls-key")
+					.value_name("PATH")
+					.help("The PEM private key for --tls-cert")
+					.takes_value(true)
+					.requires("tls-cert"),
+			)
+			.arg(
+				clap::Arg::with_name("tls-self-signed")
+					.long("tls-self-signed")
+					.value_name("PATH")
+					.help("Serve over TLS with a fresh self-signed certificate, written to this file for clients to pin")
+					.takes_value(true)
+					.conflicts_with("tls-cert"),
+			)
+			.arg(
+				clap::Arg::with_name("data-dir")
+					.long("data-dir")
+					.value_name("PATH")
+					.help("Keep state such as PSET sessions and watched addresses here (default: ~/.hal-simplicity)")
+					.takes_value(true),
+			)
+			.arg(
+				clap::Arg::with_name("no-persist")
+					.long("no-persist")
+					.help("Keep all state in memory, losing it on exit")
+					.takes_value(false)
+					.conflicts_with("data-dir"),
+			)
+			.arg(
+				clap::Arg::with_name("record")
+					.long("record")
+					.value_name("PATH")
+					.help("Append every call, with secrets redacted, to this file for hal-simplicity replay")
+					.takes_value(true),
+			)
+			.arg(
+				clap::Arg::with_name("esplora-url")
+					.long("esplora-url")
+					.value_name("URL")
+					.help("Use this Esplora API to look up outputs and broadcast")
+					.takes_value(true),
+			)
+			.arg(
+				clap::Arg::with_name("default-backend")
+					.long("default-backend")
+					.value_name("BACKEND")
+					.help("Backend for requests which do not pick one: esplora or elements")
+					.takes_value(true),
+			)
+			.arg(
+				clap::Arg::with_name("elements-url")
+					.long("elements-url")
+					.value_name("URL")
+					.help("Use the Elements node with this RPC URL to look up outputs and broadcast")
+					.takes_value(true),
+			)
+			.arg(
+				clap::Arg::with_name("elements-cookie")
+					.long("elements-cookie")
+					.value_name("PATH")
+					.help("Authenticate to the Elements node with this cookie file")
+					.takes_value(true),
+			)
+			.arg(
+				clap::Arg::with_name("elements-user")
+					.long("elements-user")
+					.value_name("USER")
+					.help("Authenticate to the Elements node as this rpcuser")
+					.takes_value(true)
+					.requires("elements-password")
+					.conflicts_with("elements-cookie"),
+			)
+			.arg(
+				clap::Arg::with_name("elements-password")
+					.long("elements-password")
+					.value_name("PASSWORD")
+					.help("The rpcpassword for --elements-user")
+					.takes_value(true)
+					.requires("elements-user"),
+			)
+			.arg(
+				clap::Arg::with_name("cors-origin")
+					.long("cors-origin")
+					.value_name("ORIGIN")
+					.help("Allow browsers to call the daemon from this origin, or * for any (may be given several times)")
+					.takes_value(true)
+					.multiple(true)
+					.number_of_values(1),
+			)
+			.arg(
+				clap::Arg::with_name("deny-method")
+					.long("deny-method")
+					.value_name("METHOD")
+					.help("Do not serve this method (may be given several times)")
+					.takes_value(true)
+					.multiple(true)
+					.number_of_values(1),
+			)
+			.arg(
+				clap::Arg::with_name("deny-secrets")
+					.long("deny-secrets")
+					.help("Refuse requests which pass a secret key")
+					.takes_value(false),
+			)
+			.arg(
+				clap::Arg::with_name("max-body-size")
+					.long("max-body-size")
+					.value_name("BYTES")
+					.help("Reject requests larger than this (default: 16 MiB)")
+					.takes_value(true),
+			)
+			.arg(
+				clap::Arg::with_name("rate-limit")
+					.long("rate-limit")
+					.value_name("REQUESTS")
+					.help("Allow each client this many requests per second on average")
+					.takes_value(true),
+			)
+			.arg(
+				clap::Arg::with_name("workers")
+					.long("workers")
+					.value_name("N")
+					.help("Run at most this many programs at once (default: number of CPUs)")
+					.takes_value(true),
+			)
+			.arg(
+				clap::Arg::with_name("log-level")
+					.long("log-level")
+					.value_name("LEVEL")
+					.help("Log level: off, error, warn, info, debug or trace (default: info)")
+					.takes_value(true)
+					.conflicts_with("verbose"),
+			)
+			.arg(
+				clap::Arg::with_name("log-file")
+					.long("log-file")
+					.value_name("PATH")
+					.help("Also append the log to this file")
+					.takes_value(true),
+			)
+			.arg(
+				clap::Arg::with_name("otlp-endpoint")
+					.long("otlp-endpoint")
+					.value_name("URL")
+					.help("Export traces to this OTLP/HTTP collector, e.g. http://localhost:4318/v1/traces (requires the 'otel' feature)")
 					.takes_value(true),
 			)
 			.arg(
@@ -47,19 +263,157 @@ fn main() {
 	let app = init_app();
 	let matches = app.get_matches();
 
-	// Enable logging in verbose mode.
-	match matches.is_present("verbose") {
-		true => setup_logger(log::LevelFilter::Debug),
-		false => setup_logger(log::LevelFilter::Info),
+	let mut config = match matches.value_of("conf") {
+		Some(path) => Config::from_file(path.as_ref()).unwrap_or_else(|e| {
+			eprintln!("Failed to read configuration: {}", e);
+			std::process::exit(1);
+		}),
+		None => Config::default(),
+	};
+
+	// Command-line options override the configuration file. Any further
+	// addresses are served as extra listeners.
+	if let Some(mut addresses) = matches.values_of("address") {
+		config.address = addresses.next().map(str::to_owned);
+		config.listeners.extend(addresses.map(|address| ListenerConfig {
+			address: Some(address.to_owned()),
+			..Default::default()
+		}));
+	}
+	if let Some(mut paths) = matches.values_of("unix-socket") {
+		config.unix_socket = paths.next().map(Into::into);
+		config.listeners.extend(paths.map(|path| ListenerConfig {
+			unix_socket: Some(path.into()),
+			..Default::default()
+		}));
+	}
+	if let Some(mut addresses) = matches.values_of("websocket-address") {
+		config.websocket_address = addresses.next().map(str::to_owned);
+		config.listeners.extend(addresses.map(|address| ListenerConfig {
+			websocket_address: Some(address.to_owned()),
+			..Default::default()
+		}));
+	}
+	if let Some(path) = matches.value_of("cookie-file") {
+		config.auth.cookie_file = Some(path.into());
+	}
+	if let Some(token) = matches.value_of("auth-token") {
+		config.auth.tokens.push(token.to_owned());
+	}
+	if let (Some(cert), Some(key)) = (matches.value_of("tls-cert"), matches.value_of("tls-key")) {
+		config.tls.cert = Some(cert.into());
+		config.tls.key = Some(key.into());
+		config.tls.self_signed = None;
+	}
+	if let Some(cert) = matches.value_of("tls-self-signed") {
+		config.tls.cert = None;
+		config.tls.key = None;
+		config.tls.self_signed = Some(cert.into());
+	}
+
+	if matches.is_present("no-persist") {
+		config.data_dir = None;
+	} else if let Some(path) = matches.value_of("data-dir") {
+		config.data_dir = Some(path.into());
+	} else if config.data_dir.is_none() {
+		config.data_dir = default_data_dir();
+	}
+	if let Some(path) = matches.value_of("record") {
+		config.record = Some(path.into());
+	}
+	if let Some(url) = matches.value_of("esplora-url") {
+		config.backend.esplora = Some(url.to_owned());
+	}
+	if let Some(url) = matches.value_of("elements-url") {
+		config.backend.elements_url = Some(url.to_owned());
+	}
+	if let Some(backend) = matches.value_of("default-backend") {
+		config.backend.default = Some(backend.parse().unwrap_or_else(|e| {
+			eprintln!("{}", e);
+			std::process::exit(1);
+		}));
+	}
+	if let Some(path) = matches.value_of("elements-cookie") {
+		config.backend.elements_cookie = Some(path.into());
+		config.backend.elements_user = None;
+		config.backend.elements_password = None;
+	}
+	if let (Some(user), Some(password)) =
+		(matches.value_of("elements-user"), matches.value_of("elements-password"))
+	{
+		config.backend.elements_cookie = None;
+		config.backend.elements_user = Some(user.to_owned());
+		config.backend.elements_password = Some(password.to_owned());
+	}
+	if let Some(origins) = matches.values_of("cors-origin") {
+		config.cors.allowed_origins.extend(origins.map(str::to_owned));
+	}
+	if let Some(methods) = matches.values_of("deny-method") {
+		config.methods.deny.extend(methods.map(str::to_owned));
+	}
+	if matches.is_present("deny-secrets") {
+		config.methods.deny_secrets = true;
+	}
+	if let Some(size) = matches.value_of("max-body-size") {
+		config.limits.max_body_size = Some(size.parse().unwrap_or_else(|_| {
+			eprintln!("Invalid maximum body size: {}", size);
+			std::process::exit(1);
+		}));
+	}
+	if let Some(rate) = matches.value_of("rate-limit") {
+		config.limits.requests_per_second = Some(rate.parse().unwrap_or_else(|_| {
+			eprintln!("Invalid rate limit: {}", rate);
+			std::process::exit(1);
+		}));
+	}
+	if let Some(workers) = matches.value_of("workers") {
+		config.limits.workers = Some(workers.parse().unwrap_or_else(|_| {
+			eprintln!("Invalid number of workers: {}", workers);
+			std::process::exit(1);
+		}));
+	}
+	if let Some(level) = matches.value_of("log-level") {
+		config.log.level = Some(level.parse().unwrap_or_else(|_| {
+			eprintln!("Invalid log level: {}", level);
+			std::process::exit(1);
+		}));
+	}
+	if let Some(path) = matches.value_of("log-file") {
+		config.log.file = Some(path.into());
+	}
+	if let Some(url) = matches.value_of("otlp-endpoint") {
+		config.telemetry.otlp_endpoint = Some(url.to_owned());
 	}
 
-	// Get the address from command line or use default
-	let address = matches.value_of("address").unwrap_or(DEFAULT_ADDRESS);
+	// Enable logging in verbose mode.
+	let level = match matches.is_present("verbose") {
+		true => log::LevelFilter::Debug,
+		false => config.log.level.unwrap_or(log::LevelFilter::Info),
+	};
+	setup_logger(level, config.log.file.as_deref());
+
+	// Export traces if a collector is given. This is flushed on exit.
+	#[cfg(feature = "otel")]
+	let _telemetry = {
+		use hal_simplicity::daemon::telemetry;
+
+		let endpoint = config.telemetry.otlp_endpoint.as_deref();
+		(endpoint.is_some() || telemetry::endpoint_in_env()).then(|| {
+			telemetry::init(endpoint, "hal-simplicity-daemon").unwrap_or_else(|e| {
+				log::error!("Failed to export traces: {}", e);
+				std::process::exit(1);
+			})
+		})
+	};
+	#[cfg(not(feature = "otel"))]
+	if config.telemetry.otlp_endpoint.is_some() {
+		log::warn!("Not exporting traces, as the daemon was built without the 'otel' feature");
+	}
 
-	log::info!("Starting hal-simplicity-daemon on {}...", address);
+	log::info!("Starting hal-simplicity-daemon...");
 
 	// Create the daemon
-	let daemon = match HalSimplicityDaemon::new(address) {
+	let daemon = match config.into_daemon() {
 		Ok(d) => d,
 		Err(e) => {
 			log::error!("Failed to create daemon: {}", e);
@@ -1,4 +1,5 @@
 use clap;
+use log::info;
 
 use crate::cmd;
 
@@ -6,12 +7,20 @@ pub fn subcommand<'a>() -> clap::App<'a, 'a> {
 	cmd::subcommand_group("address", "work with addresses")
 		.subcommand(cmd_create())
 		.subcommand(cmd_inspect())
+		.subcommand(cmd_uri())
+		.subcommand(cmd_vanity())
 }
 
 pub fn execute<'a>(matches: &clap::ArgMatches<'a>) {
 	match matches.subcommand() {
 		("create", Some(m)) => exec_create(m),
 		("inspect", Some(m)) => exec_inspect(m),
+		("uri", Some(m)) => match m.subcommand() {
+			("encode", Some(m)) => exec_uri_encode(m),
+			("decode", Some(m)) => exec_uri_decode(m),
+			(_, _) => unreachable!("clap prints help"),
+		},
+		("vanity", Some(m)) => exec_vanity(m),
 		(_, _) => unreachable!("clap prints help"),
 	};
 }
@@ -37,21 +46,148 @@ fn exec_create<'a>(matches: &clap::ArgMatches<'a>) {
 		blinder_hex,
 		network,
 	) {
-		Ok(addresses) => cmd::print_output(matches, &addresses),
-		Err(e) => panic!("{}", e),
+		Ok(addresses) => {
+			let artifact = if script_hex.is_some() {
+				"p2wsh"
+			} else {
+				"p2wpkh"
+			};
+			cmd::print_artifact(matches, &addresses, artifact)
+		}
+		Err(e) => cmd::fail(matches, e),
 	}
 }
 
 fn cmd_inspect<'a>() -> clap::App<'a, 'a> {
-	cmd::subcommand("inspect", "inspect addresses")
-		.args(&[cmd::opt_yaml(), cmd::arg("address", "the address").required(true)])
+	cmd::subcommand("inspect", "inspect addresses").args(&[
+		cmd::opt_yaml(),
+		cmd::arg("address", "the address").required(true),
+		cmd::opt(
+			"cmr",
+			"check whether the address commits to the Simplicity program with this CMR (hex)",
+		)
+		.short("c")
+		.takes_value(true)
+		.required(false),
+		cmd::opt(
+			"internal-key",
+			"internal public key of the program (hex) (default: unspendable key)",
+		)
+		.short("p")
+		.takes_value(true)
+		.required(false),
+		cmd::opt("state", "32-byte state commitment alongside the program (hex)")
+			.short("s")
+			.takes_value(true)
+			.required(false),
+	])
 }
 
 fn exec_inspect<'a>(matches: &clap::ArgMatches<'a>) {
-	let address_str = matches.value_of("address").expect("address is required");
+	let address_str = cmd::arg_value(matches, "address").expect("address is required");
 
-	match hal_simplicity::actions::address::address_inspect(address_str) {
+	match hal_simplicity::actions::address::address_inspect_with_program(
+		&address_str,
+		matches.value_of("cmr"),
+		matches.value_of("internal-key"),
+		matches.value_of("state"),
+	) {
 		Ok(info) => cmd::print_output(matches, &info),
-		Err(e) => panic!("{}", e),
+		Err(e) => cmd::fail(matches, e),
+	}
+}
+
+fn cmd_uri<'a>() -> clap::App<'a, 'a> {
+	cmd::subcommand_group("uri", "work with liquidnetwork: payment URIs")
+		.subcommand(cmd_uri_encode())
+		.subcommand(cmd_uri_decode())
+}
+
+fn cmd_uri_encode<'a>() -> clap::App<'a, 'a> {
+	cmd::subcommand("encode", "create a payment URI for an address or Simplicity program")
+		.args(&cmd::opts_networks())
+		.args(&[
+			cmd::opt_yaml(),
+			cmd::arg("address", "the address to pay").required(false),
+			cmd::opt("cmr", "pay the Simplicity program with this CMR instead of an address (hex)")
+				.short("c")
+				.takes_value(true)
+				.required(false),
+			cmd::opt("state", "32-byte state commitment to put alongside the program (hex)")
+				.short("s")
+				.takes_value(true)
+				.required(false),
+			cmd::opt("amount", "the amount to pay, in units of the asset (decimal)")
+				.takes_value(true)
+				.required(false),
+			cmd::opt("asset", "the ID of the asset to pay (hex)").takes_value(true).required(false),
+			cmd::opt("label", "a label for the recipient").takes_value(true).required(false),
+			cmd::opt("message", "a message describing the payment")
+				.takes_value(true)
+				.required(false),
+		])
+}
+
+fn exec_uri_encode<'a>(matches: &clap::ArgMatches<'a>) {
+	let address = cmd::arg_value(matches, "address");
+
+	match hal_simplicity::actions::address::address_uri_encode(
+		address.as_deref(),
+		matches.value_of("cmr"),
+		matches.value_of("state"),
+		matches.value_of("amount"),
+		matches.value_of("asset"),
+		matches.value_of("label"),
+		matches.value_of("message"),
+		cmd::network(matches),
+	) {
+		Ok(uri) => cmd::print_artifact(matches, &uri, "uri"),
+		Err(e) => cmd::fail(matches, e),
+	}
+}
+
+fn cmd_uri_decode<'a>() -> clap::App<'a, 'a> {
+	cmd::subcommand("decode", "parse a payment URI into its address and parameters")
+		.args(&[cmd::opt_yaml(), cmd::arg("uri", "the payment URI").required(true)])
+}
+
+fn exec_uri_decode<'a>(matches: &clap::ArgMatches<'a>) {
+	let uri = cmd::arg_value(matches, "uri").expect("uri is required");
+
+	match hal_simplicity::actions::address::address_uri_decode(&uri) {
+		Ok(uri) => cmd::print_artifact(matches, &uri, "address"),
+		Err(e) => cmd::fail(matches, e),
+	}
+}
+
+fn cmd_vanity<'a>() -> clap::App<'a, 'a> {
+	cmd::subcommand("vanity", "grind keys for a P2WPKH address with a chosen prefix")
+		.args(&cmd::opts_networks())
+		.args(&[
+			cmd::opt_yaml(),
+			cmd::opt("prefix", "the characters to follow the witness version, as in ex1q<prefix>")
+				.takes_value(true)
+				.required(true),
+			cmd::opt("threads", "the number of threads to search on (default: one per core)")
+				.takes_value(true)
+				.required(false),
+		])
+}
+
+fn exec_vanity<'a>(matches: &clap::ArgMatches<'a>) {
+	let network = cmd::network(matches);
+	let prefix = matches.value_of("prefix").expect("prefix is mandatory");
+	let threads = matches
+		.value_of("threads")
+		.map(|n| n.parse().unwrap_or_else(|e| panic!("invalid threads: {}", e)))
+		.unwrap_or_else(|| std::thread::available_parallelism().map(|n| n.get()).unwrap_or(1));
+
+	let expected = 32f64.powi(prefix.len() as i32);
+	let on_progress = |attempts| {
+		info!("tried {} keys; expect to try around {} to find the prefix", attempts, expected)
+	};
+	match hal_simplicity::actions::address::address_vanity(prefix, network, threads, on_progress) {
+		Ok(info) => cmd::print_artifact(matches, &info, "address"),
+		Err(e) => cmd::fail(matches, e),
 	}
 }
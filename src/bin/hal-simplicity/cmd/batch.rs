@@ -0,0 +1,180 @@
+use std::collections::{BTreeSet, HashMap};
+use std::fs;
+use std::io::{self, Read, Write};
+use std::process;
+
+use clap;
+use hal_simplicity::daemon::client::ClientError;
+use serde_json::{json, Value};
+
+use crate::cmd;
+use crate::cmd::daemon;
+
+pub fn subcommand<'a>() -> clap::App<'a, 'a> {
+	cmd::subcommand(
+		"batch",
+		"make the daemon calls in a file in order, printing one JSON result per line",
+	)
+	.args(&daemon::opts_auth())
+	.args(&[
+		daemon::opt_daemon_url(),
+		cmd::arg("file", "file with one call per line, or - for stdin").required(true),
+		cmd::opt("keep-going", "make the remaining calls after one fails").takes_value(false),
+	])
+	.after_help(
+		"Each line is a JSON-RPC call, such as {\"method\": \"tx_decode\", \"params\": {...}}, \
+			or a method followed by its params, such as tx_decode {...}. Blank lines and lines \
+			starting with # are skipped. No call is made if the daemon does not offer every \
+			method called.\n\n\
+			A string param of the form $<n> is replaced by the result of the call on line n, and \
+			one of the form $<n>.<path> by the value at that dot-separated path in the result.\n\n\
+			Each result is printed as {\"line\": n, \"result\": ...} or {\"line\": n, \"error\": \
+			{\"code\": ..., \"message\": ...}}. Unless --keep-going is given, the first failed \
+			call ends the batch. The exit status is 1 if any call failed.",
+	)
+}
+
+pub fn execute<'a>(matches: &clap::ArgMatches<'a>) {
+	cmd::require_online(matches, "the daemon");
+	let path = matches.value_of("file").expect("file is mandatory");
+	let script = match path {
+		"-" => {
+			let mut script = String::new();
+			io::stdin()
+				.read_to_string(&mut script)
+				.unwrap_or_else(|e| panic!("cannot read calls from stdin: {}", e));
+			script
+		}
+		path => fs::read_to_string(path)
+			.unwrap_or_else(|e| panic!("cannot read calls from {}: {}", path, e)),
+	};
+	let client = daemon::profile(matches)
+		.client()
+		.unwrap_or_else(|e| panic!("{}", e))
+		.with_trace(matches.is_present("trace-rpc"));
+	let keep_going = matches.is_present("keep-going");
+
+	// Rather than failing partway through, refuse to start a batch calling a
+	// method which the daemon does not offer, e.g. as it is an older version.
+	if let Ok(info) = client.daemon_info() {
+		let missing: BTreeSet<_> = script
+			.lines()
+			.map(str::trim)
+			.filter(|line| !line.is_empty() && !line.starts_with('#'))
+			.filter_map(|line| parse_call(line).ok())
+			.map(|(method, _)| method)
+			.filter(|method| !info.methods.contains(method))
+			.collect();
+		if !missing.is_empty() {
+			let missing: Vec<_> = missing.into_iter().collect();
+			panic!("daemon version {} does not offer {}", info.version, missing.join(", "));
+		}
+	}
+
+	let mut results = HashMap::new();
+	let mut failed = false;
+	let stdout = io::stdout();
+	for (i, line) in script.lines().enumerate() {
+		let number = i + 1;
+		let line = line.trim();
+		if line.is_empty() || line.starts_with('#') {
+			continue;
+		}
+
+		let outcome = parse_call(line).and_then(|(method, params)| {
+			let params = params.map(|params| substitute(params, &results)).transpose()?;
+			client.call(&method, params).map_err(|e| match e {
+				ClientError::Rpc(e) => serde_json::to_value(e).expect("errors serialize"),
+				e => error(e.to_string()),
+			})
+		});
+		let output = match outcome {
+			Ok(result) => {
+				let output = json!({ "line": number, "result": result });
+				results.insert(number, result);
+				output
+			}
+			Err(error) => {
+				failed = true;
+				json!({ "line": number, "error": error })
+			}
+		};
+
+		let mut stdout = stdout.lock();
+		writeln!(stdout, "{}", output).and_then(|_| stdout.flush()).expect("writing to stdout");
+		if failed && !keep_going {
+			break;
+		}
+	}
+
+	if failed {
+		process::exit(1);
+	}
+}
+
+/// An error object for a call which could not be made.
+fn error(message: String) -> Value {
+	json!({ "code": cmd::OTHER_ERROR_CODE, "message": message })
+}
+
+/// The method and params of a line.
+fn parse_call(line: &str) -> Result<(String, Option<Value>), Value> {
+	if line.starts_with('{') {
+		let mut call: Value =
+			serde_json::from_str(line).map_err(|e| error(format!("invalid call: {}", e)))?;
+		let method = match call.get("method") {
+			Some(Value::String(method)) => method.clone(),
+			_ => return Err(error("invalid call: no method".to_owned())),
+		};
+		return Ok((method, call.get_mut("params").map(Value::take)));
+	}
+
+	let (method, params) = match line.split_once(char::is_whitespace) {
+		Some((method, params)) => (method, Some(params.trim())),
+		None => (line, None),
+	};
+	let params = params
+		.map(|params| {
+			serde_json::from_str(params).map_err(|e| error(format!("invalid params: {}", e)))
+		})
+		.transpose()?;
+	Ok((method.to_owned(), params))
+}
+
+/// Replace `$<n>` and `$<n>.<path>` strings in `params` by the results of
+/// earlier calls.
+fn substitute(params: Value, results: &HashMap<usize, Value>) -> Result<Value, Value> {
+	match params {
+		Value::String(s) => match s.strip_prefix('$') {
+			Some(reference) => {
+				let (number, path) = match reference.split_once('.') {
+					Some((number, path)) => (number, Some(path)),
+					None => (reference, None),
+				};
+				let result = number
+					.parse()
+					.ok()
+					.and_then(|number: usize| results.get(&number))
+					.ok_or_else(|| error(format!("no result for ${}", number)))?;
+				match path {
+					Some(path) => cmd::select_field(result, path).cloned().ok_or_else(|| {
+						error(format!("no field {} in the result of ${}", path, number))
+					}),
+					None => Ok(result.clone()),
+				}
+			}
+			None => Ok(Value::String(s)),
+		},
+		Value::Array(items) => items
+			.into_iter()
+			.map(|item| substitute(item, results))
+			.collect::<Result<_, _>>()
+			.map(Value::Array),
+		Value::Object(map) => map
+			.into_iter()
+			.map(|(key, value)| Ok((key, substitute(value, results)?)))
+			.collect::<Result<_, _>>()
+			.map(Value::Object),
+		other => Ok(other),
+	}
+}
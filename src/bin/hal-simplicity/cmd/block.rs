@@ -3,20 +3,34 @@ use std::io::Write;
 use elements::encode::serialize;
 
 use crate::cmd;
+#[cfg(feature = "daemon")]
+use crate::cmd::daemon;
+use hal_simplicity::actions::block::BlockError;
 use hal_simplicity::block::BlockInfo;
 
 use log::warn;
 
 pub fn subcommand<'a>() -> clap::App<'a, 'a> {
-	cmd::subcommand_group("block", "manipulate blocks")
-		.subcommand(cmd_create())
-		.subcommand(cmd_decode())
+	cmd::subcommand_group("block", "manipulate blocks").subcommands(vec![
+		cmd_create(),
+		cmd_decode(),
+		#[cfg(feature = "daemon")]
+		cmd_fetch(),
+		cmd_stats(),
+		cmd_verify_chain(),
+		cmd_verify_signblock(),
+	])
 }
 
 pub fn execute<'a>(matches: &clap::ArgMatches<'a>) {
 	match matches.subcommand() {
 		("create", Some(m)) => exec_create(m),
 		("decode", Some(m)) => exec_decode(m),
+		#[cfg(feature = "daemon")]
+		("fetch", Some(m)) => exec_fetch(m),
+		("stats", Some(m)) => exec_stats(m),
+		("verify-chain", Some(m)) => exec_verify_chain(m),
+		("verify-signblock", Some(m)) => exec_verify_signblock(m),
 		(_, _) => unreachable!("clap prints help"),
 	};
 }
@@ -32,14 +46,15 @@ fn cmd_create<'a>() -> clap::App<'a, 'a> {
 
 fn exec_create<'a>(matches: &clap::ArgMatches<'a>) {
 	let info = serde_json::from_str::<BlockInfo>(&cmd::arg_or_stdin(matches, "block-info"))
-		.unwrap_or_else(|e| panic!("invalid json JSON input: {}", e));
+		.map_err(BlockError::InvalidJsonInput)
+		.unwrap_or_else(|e| cmd::fail(matches, e));
 
 	if info.txids.is_some() {
 		warn!("Field \"txids\" is ignored.");
 	}
 
-	let block =
-		hal_simplicity::actions::block::block_create(info).unwrap_or_else(|e| panic!("{}", e));
+	let block = hal_simplicity::actions::block::block_create(info)
+		.unwrap_or_else(|e| cmd::fail(matches, e));
 
 	let block_bytes = serialize(&block);
 	if matches.is_present("raw-stdout") {
@@ -52,19 +67,131 @@ fn exec_create<'a>(matches: &clap::ArgMatches<'a>) {
 fn cmd_decode<'a>() -> clap::App<'a, 'a> {
 	cmd::subcommand("decode", "decode a raw block to JSON").args(&cmd::opts_networks()).args(&[
 		cmd::opt_yaml(),
+		cmd::opt_field(),
 		cmd::arg("raw-block", "the raw block in hex").required(false),
 		cmd::opt("txids", "provide transactions IDs instead of full transactions"),
+		cmd::opt("full", "decode the whole block, including every transaction"),
 	])
 }
 
 fn exec_decode<'a>(matches: &clap::ArgMatches<'a>) {
-	let hex_block = cmd::arg_or_stdin(matches, "raw-block");
+	// Blocks may be many megabytes, so they are decoded as they are read.
+	let hex_block = cmd::arg_or_stdin_reader(matches, "raw-block");
 	let network = cmd::network(matches);
-	let txids_only = matches.is_present("txids");
+	let txids = matches.is_present("txids");
+	let full = matches.is_present("full");
+
+	let info = hal_simplicity::actions::block::block_decode_reader(hex_block, network, txids, full)
+		.unwrap_or_else(|e| cmd::fail(matches, e));
+
+	cmd::print_output(matches, &info)
+}
+
+#[cfg(feature = "daemon")]
+fn cmd_fetch<'a>() -> clap::App<'a, 'a> {
+	cmd::subcommand("fetch", "fetch a raw block from the daemon's chain backend")
+		.args(&cmd::opts_networks())
+		.args(&daemon::opts_auth())
+		.args(&[
+			daemon::opt_daemon_url(),
+			daemon::opt_backend(),
+			cmd::opt_yaml(),
+			cmd::opt_field(),
+			cmd::opt("decode", "decode the block to JSON, as block decode does"),
+			cmd::opt("txids", "with --decode, provide transactions IDs").requires("decode"),
+			cmd::opt("full", "with --decode, decode every transaction too").requires("decode"),
+			cmd::arg("block", "the hash of the block, or its height").required(true),
+		])
+}
+
+#[cfg(feature = "daemon")]
+fn exec_fetch<'a>(matches: &clap::ArgMatches<'a>) {
+	use hal_simplicity::daemon::types::BlockFetchRequest;
+
+	let block = matches.value_of("block").expect("block is mandatory");
+	let request = BlockFetchRequest {
+		block: block.parse().unwrap_or_else(|e| panic!("{}", e)),
+		backend: daemon::backend(matches),
+	};
+	let raw_block = daemon::connect(matches)
+		.block_fetch(&request)
+		.unwrap_or_else(|e| daemon::fail_call(matches, e))
+		.raw_block;
+	if !matches.is_present("decode") {
+		return print!("{}", raw_block);
+	}
+
+	let txids = matches.is_present("txids");
+	let full = matches.is_present("full");
+	let info = hal_simplicity::actions::block::block_decode(
+		&raw_block,
+		cmd::network(matches),
+		txids,
+		full,
+	)
+	.unwrap_or_else(|e| cmd::fail(matches, e));
+	cmd::print_output(matches, &info)
+}
+
+fn cmd_stats<'a>() -> clap::App<'a, 'a> {
+	cmd::subcommand("stats", "summarize the Simplicity spends in a raw block").args(&[
+		cmd::opt_yaml(),
+		cmd::opt_field(),
+		cmd::arg("raw-block", "the raw block in hex").required(false),
+	])
+}
+
+fn exec_stats<'a>(matches: &clap::ArgMatches<'a>) {
+	let hex_block = cmd::arg_or_stdin(matches, "raw-block");
+
+	let stats = hal_simplicity::actions::block::block_stats(hex_block.as_ref())
+		.unwrap_or_else(|e| cmd::fail(matches, e));
+
+	cmd::print_output(matches, &stats)
+}
+
+fn cmd_verify_chain<'a>() -> clap::App<'a, 'a> {
+	cmd::subcommand("verify-chain", "check that a sequence of raw block headers forms a chain")
+		.args(&[
+			cmd::opt_yaml(),
+			cmd::opt_field(),
+			cmd::arg("raw-headers", "the raw block headers in hex, in order (default: from stdin)")
+				.multiple(true)
+				.required(false),
+		])
+}
+
+fn exec_verify_chain<'a>(matches: &clap::ArgMatches<'a>) {
+	let stdin;
+	let headers: Vec<&str> = match matches.values_of("raw-headers") {
+		Some(headers) => headers.collect(),
+		None => {
+			stdin = cmd::arg_or_stdin(matches, "raw-headers");
+			stdin.split_whitespace().collect()
+		}
+	};
+
+	let info = hal_simplicity::actions::block::block_verify_chain(&headers)
+		.unwrap_or_else(|e| cmd::fail(matches, e));
+
+	cmd::print_output(matches, &info)
+}
+
+fn cmd_verify_signblock<'a>() -> clap::App<'a, 'a> {
+	cmd::subcommand("verify-signblock", "check the signatures on a raw block or block header").args(
+		&[
+			cmd::opt_yaml(),
+			cmd::opt_field(),
+			cmd::arg("raw-block", "the raw block or block header in hex").required(false),
+		],
+	)
+}
+
+fn exec_verify_signblock<'a>(matches: &clap::ArgMatches<'a>) {
+	let hex_block = cmd::arg_or_stdin(matches, "raw-block");
 
-	let info =
-		hal_simplicity::actions::block::block_decode(hex_block.as_ref(), network, txids_only)
-			.unwrap_or_else(|e| panic!("{}", e));
+	let info = hal_simplicity::actions::block::block_verify_signblock(hex_block.as_ref())
+		.unwrap_or_else(|e| cmd::fail(matches, e));
 
 	cmd::print_output(matches, &info)
 }
@@ -0,0 +1,23 @@
+use std::io;
+
+use clap;
+
+use crate::cmd;
+
+pub fn subcommand<'a>() -> clap::App<'a, 'a> {
+	cmd::subcommand("completions", "print a shell completion script").arg(
+		cmd::arg("shell", "the shell to complete commands in")
+			.possible_values(&["bash", "zsh", "fish"])
+			.required(true),
+	)
+}
+
+pub fn execute<'a>(matches: &clap::ArgMatches<'a>) {
+	let shell = match matches.value_of("shell").expect("shell is required") {
+		"bash" => clap::Shell::Bash,
+		"zsh" => clap::Shell::Zsh,
+		"fish" => clap::Shell::Fish,
+		_ => unreachable!("checked by clap"),
+	};
+	crate::init_app().gen_completions_to("hal-simplicity", shell, &mut io::stdout());
+}
@@ -0,0 +1,380 @@
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::process::{self, Command, Stdio};
+use std::thread;
+use std::time::{Duration, Instant};
+
+use clap;
+use hal_simplicity::daemon::backend::BackendKind;
+use hal_simplicity::daemon::client::{ClientError, HalSimplicity, RetryPolicy};
+use hal_simplicity::daemon::profiles::{ClientConfig, Profile};
+use serde::Serialize;
+
+use crate::cmd;
+
+/// How long to wait for the daemon to start answering requests.
+const START_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// How long to wait for the daemon to stop, on top of the time it gives
+/// open connections to finish.
+const STOP_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// How often to poll the daemon while waiting for it.
+const POLL_INTERVAL: Duration = Duration::from_millis(100);
+
+#[derive(Serialize)]
+struct Status {
+	running: bool,
+	url: String,
+	#[serde(skip_serializing_if = "Option::is_none")]
+	pid: Option<u32>,
+	#[serde(skip_serializing_if = "Option::is_none")]
+	info: Option<serde_json::Value>,
+}
+
+pub fn subcommand<'a>() -> clap::App<'a, 'a> {
+	cmd::subcommand_group("daemon", "start, stop and query hal-simplicity-daemon")
+		.subcommand(cmd_start())
+		.subcommand(cmd_stop())
+		.subcommand(cmd_status())
+}
+
+pub fn execute<'a>(matches: &clap::ArgMatches<'a>) {
+	cmd::require_online(matches, "the daemon");
+	match matches.subcommand() {
+		("start", Some(m)) => exec_start(m),
+		("stop", Some(m)) => exec_stop(m),
+		("status", Some(m)) => exec_status(m),
+		(_, _) => unreachable!("clap prints help"),
+	};
+}
+
+/// Options for connecting to the daemon, read by [`profile`].
+pub(super) fn opts_auth<'a>() -> Vec<clap::Arg<'a, 'a>> {
+	vec![
+		cmd::opt("cookie-file", "authenticate with the daemon's cookie file")
+			.value_name("PATH")
+			.takes_value(true),
+		cmd::opt("auth-token", "authenticate with this token")
+			.value_name("TOKEN")
+			.takes_value(true)
+			.conflicts_with("cookie-file"),
+		cmd::opt(
+			"ca-file",
+			"trust the CA certificates in this PEM file, such as a self-signed daemon's",
+		)
+		.value_name("PATH")
+		.takes_value(true),
+		cmd::opt("profile", "use this profile from ~/.hal-simplicity/config.toml")
+			.value_name("NAME")
+			.takes_value(true),
+		cmd::opt(
+			"trace-rpc",
+			"print requests to the daemon as curl commands, and their responses, to stderr",
+		)
+		.takes_value(false),
+	]
+}
+
+fn opt_pid_file<'a>() -> clap::Arg<'a, 'a> {
+	cmd::opt(
+		"pid-file",
+		"file holding the daemon's process ID (default: ~/.hal-simplicity/daemon.pid)",
+	)
+	.value_name("PATH")
+	.takes_value(true)
+}
+
+fn cmd_start<'a>() -> clap::App<'a, 'a> {
+	cmd::subcommand("start", "start the daemon in the background and wait until it is ready")
+		.args(&opts_auth())
+		.args(&[
+			opt_pid_file(),
+			cmd::opt_yaml(),
+			cmd::opt("address", "TCP address for the daemon to bind to (default: 127.0.0.1:28579)")
+				.value_name("ADDRESS")
+				.takes_value(true),
+			cmd::opt("foreground", "run the daemon in this terminal until it exits")
+				.takes_value(false),
+			cmd::arg("daemon-args", "further options for hal-simplicity-daemon, after --")
+				.multiple(true)
+				.last(true),
+		])
+}
+
+fn cmd_stop<'a>() -> clap::App<'a, 'a> {
+	cmd::subcommand("stop", "ask the daemon to shut down and wait until it has")
+		.args(&opts_auth())
+		.args(&[opt_pid_file(), opt_daemon_url(), cmd::opt_yaml()])
+}
+
+fn cmd_status<'a>() -> clap::App<'a, 'a> {
+	cmd::subcommand("status", "show whether the daemon is running; exits with 1 if not")
+		.args(&opts_auth())
+		.args(&[opt_pid_file(), opt_daemon_url(), cmd::opt_yaml()])
+}
+
+pub(super) fn opt_daemon_url<'a>() -> clap::Arg<'a, 'a> {
+	cmd::opt(
+		"daemon-url",
+		"URL of the daemon, or unix:///<path> for a unix socket \
+		(default: $HAL_SIMPLICITY_URL, the profile's, or http://127.0.0.1:28579)",
+	)
+	.value_name("URL")
+	.takes_value(true)
+}
+
+/// The `--backend` option of commands which use the daemon's chain backend.
+pub(super) fn opt_backend<'a>() -> clap::Arg<'a, 'a> {
+	cmd::opt("backend", "the daemon's backend to use: esplora or elements (default: its default)")
+		.value_name("BACKEND")
+		.possible_values(&["esplora", "elements"])
+		.hide_possible_values(true)
+		.takes_value(true)
+}
+
+pub(super) fn backend<'a>(matches: &clap::ArgMatches<'a>) -> Option<BackendKind> {
+	matches.value_of("backend").map(|b| b.parse().expect("checked by clap"))
+}
+
+/// The profile selected by `--profile`, overridden by the environment and
+/// then by the other options of [`opts_auth`] and [`opt_daemon_url`].
+pub(super) fn profile<'a>(matches: &clap::ArgMatches<'a>) -> Profile {
+	let config = ClientConfig::load().unwrap_or_else(|e| panic!("{}", e));
+	let mut profile =
+		config.profile(matches.value_of("profile")).unwrap_or_else(|e| panic!("{}", e)).with_env();
+	if let Some(url) = matches.value_of("daemon-url") {
+		profile.url = Some(url.to_owned());
+		profile.unix_socket = None;
+	}
+	if let Some(token) = matches.value_of("auth-token") {
+		profile.token = Some(token.to_owned());
+		profile.cookie_file = None;
+	} else if let Some(path) = matches.value_of("cookie-file") {
+		profile.cookie_file = Some(path.into());
+		profile.token = None;
+	}
+	if let Some(path) = matches.value_of("ca-file") {
+		profile.ca_file = Some(path.into());
+	}
+	profile
+}
+
+/// The client for `profile`. It does not retry, since these commands poll
+/// the daemon themselves.
+fn client<'a>(matches: &clap::ArgMatches<'a>, profile: &Profile) -> HalSimplicity {
+	profile
+		.client()
+		.unwrap_or_else(|e| panic!("{}", e))
+		.with_retry(RetryPolicy::none())
+		.with_trace(matches.is_present("trace-rpc"))
+}
+
+/// The client for a command which calls the daemon, such as `tx fetch`,
+/// selected by the options of [`opts_auth`] and [`opt_daemon_url`].
+pub(super) fn connect<'a>(matches: &clap::ArgMatches<'a>) -> HalSimplicity {
+	cmd::require_online(matches, "the daemon");
+	profile(matches)
+		.client()
+		.unwrap_or_else(|e| panic!("{}", e))
+		.with_trace(matches.is_present("trace-rpc"))
+}
+
+/// Fail with the error of a call to the daemon, keeping the code of an error
+/// returned by the method.
+pub(super) fn fail_call<'a>(matches: &clap::ArgMatches<'a>, e: ClientError) -> ! {
+	match e {
+		ClientError::Rpc(e) => {
+			cmd::exit_with_error(matches.is_present("json-errors"), e.code, &e.message, e.data)
+		}
+		e => panic!("{}", e),
+	}
+}
+
+fn pid_file<'a>(matches: &clap::ArgMatches<'a>) -> PathBuf {
+	match matches.value_of("pid-file") {
+		Some(path) => path.into(),
+		None => std::env::var_os("HOME")
+			.or_else(|| std::env::var_os("USERPROFILE"))
+			.map(|home| PathBuf::from(home).join(".hal-simplicity").join("daemon.pid"))
+			.expect("cannot find the home directory; use --pid-file"),
+	}
+}
+
+fn read_pid(path: &Path) -> Option<u32> {
+	fs::read_to_string(path).ok().and_then(|pid| pid.trim().parse().ok())
+}
+
+/// The daemon binary: the one next to this executable, or else the one on the `PATH`.
+fn daemon_binary() -> PathBuf {
+	let name = format!("hal-simplicity-daemon{}", std::env::consts::EXE_SUFFIX);
+	std::env::current_exe()
+		.ok()
+		.map(|exe| exe.with_file_name(&name))
+		.filter(|path| path.is_file())
+		.unwrap_or_else(|| name.into())
+}
+
+/// Ask the daemon for `getinfo`, treating an unreachable daemon as not running.
+fn probe(client: &HalSimplicity) -> Result<Option<serde_json::Value>, ClientError> {
+	match client.call("getinfo", None) {
+		Ok(info) => Ok(Some(info)),
+		Err(ClientError::Unreachable {
+			..
+		}) => Ok(None),
+		Err(e) => Err(e),
+	}
+}
+
+fn getinfo(client: &HalSimplicity) -> Option<serde_json::Value> {
+	probe(client).unwrap_or_else(|e| panic!("{}", e))
+}
+
+fn exec_start<'a>(matches: &clap::ArgMatches<'a>) {
+	let address = matches.value_of("address");
+	let url = format!("http://{}", address.unwrap_or("127.0.0.1:28579"));
+	let profile = Profile {
+		url: Some(url.clone()),
+		unix_socket: None,
+		..profile(matches)
+	};
+	// The daemon writes its cookie file only once it starts.
+	let connect = || match profile.cookie_file {
+		Some(ref path) if !path.exists() => HalSimplicity::new(&url)
+			.with_retry(RetryPolicy::none())
+			.with_trace(matches.is_present("trace-rpc")),
+		_ => client(matches, &profile),
+	};
+	let pid_file = pid_file(matches);
+	// Anything answering, even if refusing us, means the address is taken.
+	if !matches!(probe(&connect()), Ok(None)) {
+		match read_pid(&pid_file) {
+			Some(pid) => panic!("the daemon is already running at {} (pid {})", url, pid),
+			None => panic!("a daemon is already running at {}", url),
+		}
+	}
+
+	let mut command = Command::new(daemon_binary());
+	if let Some(address) = address {
+		command.args(["--address", address]);
+	}
+	if let Some(ref path) = profile.cookie_file {
+		command.arg("--cookie-file").arg(path);
+	}
+	if let Some(ref token) = profile.token {
+		command.args(["--auth-token", token]);
+	}
+	if let Some(args) = matches.values_of("daemon-args") {
+		command.args(args);
+	}
+
+	if matches.is_present("foreground") {
+		let status = command.status().unwrap_or_else(|e| panic!("cannot run the daemon: {}", e));
+		process::exit(status.code().unwrap_or(1));
+	}
+
+	// Keep the daemon's output next to its PID file, since there is no terminal for it.
+	if let Some(dir) = pid_file.parent() {
+		fs::create_dir_all(dir)
+			.unwrap_or_else(|e| panic!("cannot create {}: {}", dir.display(), e));
+	}
+	let log_path = pid_file.with_extension("log");
+	let log = fs::OpenOptions::new()
+		.create(true)
+		.append(true)
+		.open(&log_path)
+		.unwrap_or_else(|e| panic!("cannot open {}: {}", log_path.display(), e));
+	command.stdin(Stdio::null()).stdout(Stdio::null()).stderr(log);
+	// Do not forward the terminal's signals, such as ^C, to the daemon.
+	#[cfg(unix)]
+	std::os::unix::process::CommandExt::process_group(&mut command, 0);
+
+	let mut child = command.spawn().unwrap_or_else(|e| panic!("cannot run the daemon: {}", e));
+	fs::write(&pid_file, format!("{}\n", child.id()))
+		.unwrap_or_else(|e| panic!("cannot write {}: {}", pid_file.display(), e));
+
+	let started = Instant::now();
+	let info = loop {
+		if let Some(status) = child.try_wait().expect("waiting for the daemon") {
+			let _ = fs::remove_file(&pid_file);
+			panic!("the daemon exited with {}; see {}", status, log_path.display());
+		}
+		match probe(&connect()) {
+			Ok(Some(info)) => break info,
+			Ok(None) | Err(ClientError::Unauthorized) => {}
+			Err(e) => panic!("{}", e),
+		}
+		if started.elapsed() > START_TIMEOUT {
+			panic!("the daemon did not answer at {}; see {}", url, log_path.display());
+		}
+		thread::sleep(POLL_INTERVAL);
+	};
+
+	cmd::print_output(
+		matches,
+		&Status {
+			running: true,
+			url,
+			pid: Some(child.id()),
+			info: Some(info),
+		},
+	);
+}
+
+fn exec_stop<'a>(matches: &clap::ArgMatches<'a>) {
+	let profile = profile(matches);
+	let url = profile.url();
+	let client = client(matches, &profile);
+	let pid_file = pid_file(matches);
+	let pid = read_pid(&pid_file);
+
+	match client.call("stop", None) {
+		Ok(_) => {}
+		Err(ClientError::Unreachable {
+			..
+		}) => {
+			// Nothing is running, so any PID file is left over from a crash.
+			let _ = fs::remove_file(&pid_file);
+			panic!("the daemon is not running at {}", url);
+		}
+		Err(e) => panic!("{}", e),
+	}
+
+	let stopped = Instant::now();
+	while getinfo(&client).is_some() {
+		if stopped.elapsed() > hal_simplicity::daemon::SHUTDOWN_TIMEOUT + STOP_TIMEOUT {
+			panic!("the daemon at {} did not stop", url);
+		}
+		thread::sleep(POLL_INTERVAL);
+	}
+	let _ = fs::remove_file(&pid_file);
+
+	cmd::print_output(
+		matches,
+		&Status {
+			running: false,
+			url: url.to_owned(),
+			pid,
+			info: None,
+		},
+	);
+}
+
+fn exec_status<'a>(matches: &clap::ArgMatches<'a>) {
+	let profile = profile(matches);
+	let url = profile.url();
+	let info = getinfo(&client(matches, &profile));
+	let running = info.is_some();
+	cmd::print_output(
+		matches,
+		&Status {
+			running,
+			url: url.to_owned(),
+			pid: read_pid(&pid_file(matches)),
+			info,
+		},
+	);
+	if !running {
+		process::exit(1);
+	}
+}
@@ -4,16 +4,39 @@ use crate::cmd;
 
 pub fn subcommand<'a>() -> clap::App<'a, 'a> {
 	cmd::subcommand_group("keypair", "manipulate private and public keys")
+		.subcommand(cmd_combine())
 		.subcommand(cmd_generate())
+		.subcommand(cmd_negate())
+		.subcommand(cmd_tweak_add())
 }
 
 pub fn execute<'a>(matches: &clap::ArgMatches<'a>) {
 	match matches.subcommand() {
+		("combine", Some(m)) => exec_combine(m),
 		("generate", Some(m)) => exec_generate(m),
+		("negate", Some(m)) => exec_negate(m),
+		("tweak-add", Some(m)) => exec_tweak_add(m),
 		(_, _) => unreachable!("clap prints help"),
 	};
 }
 
+fn cmd_combine<'a>() -> clap::App<'a, 'a> {
+	cmd::subcommand("combine", "add public keys together").args(&[
+		cmd::opt_yaml(),
+		cmd::arg("pubkeys", "the public keys to add, compressed or x-only (hex)")
+			.multiple(true)
+			.required(true),
+	])
+}
+
+fn exec_combine<'a>(matches: &clap::ArgMatches<'a>) {
+	let pubkeys: Vec<_> = matches.values_of("pubkeys").expect("pubkeys mandatory").collect();
+	match hal_simplicity::actions::keypair::keypair_combine(&pubkeys) {
+		Ok(info) => cmd::print_artifact(matches, &info, "public_key"),
+		Err(e) => cmd::fail(matches, e),
+	}
+}
+
 fn cmd_generate<'a>() -> clap::App<'a, 'a> {
 	cmd::subcommand("generate", "generate a random private/public keypair").args(&[cmd::opt_yaml()])
 }
@@ -22,3 +45,35 @@ fn exec_generate<'a>(matches: &clap::ArgMatches<'a>) {
 	let keypair = hal_simplicity::actions::keypair::keypair_generate();
 	cmd::print_output(matches, &keypair);
 }
+
+fn cmd_negate<'a>() -> clap::App<'a, 'a> {
+	cmd::subcommand("negate", "negate a public key").args(&[
+		cmd::opt_yaml(),
+		cmd::arg("pubkey", "the public key, compressed or x-only (hex)").required(false),
+	])
+}
+
+fn exec_negate<'a>(matches: &clap::ArgMatches<'a>) {
+	let pubkey = cmd::arg_or_stdin(matches, "pubkey");
+	match hal_simplicity::actions::keypair::keypair_negate(&pubkey) {
+		Ok(info) => cmd::print_artifact(matches, &info, "public_key"),
+		Err(e) => cmd::fail(matches, e),
+	}
+}
+
+fn cmd_tweak_add<'a>() -> clap::App<'a, 'a> {
+	cmd::subcommand("tweak-add", "add a multiple of the generator to a public key").args(&[
+		cmd::opt_yaml(),
+		cmd::arg("pubkey", "the public key, compressed or x-only (hex)").required(true),
+		cmd::arg("tweak", "the 32-byte tweak to multiply the generator by (hex)").required(true),
+	])
+}
+
+fn exec_tweak_add<'a>(matches: &clap::ArgMatches<'a>) {
+	let pubkey = cmd::arg_value(matches, "pubkey").expect("pubkey mandatory");
+	let tweak = cmd::arg_value(matches, "tweak").expect("tweak mandatory");
+	match hal_simplicity::actions::keypair::keypair_tweak_add(&pubkey, &tweak) {
+		Ok(info) => cmd::print_artifact(matches, &info, "public_key"),
+		Err(e) => cmd::fail(matches, e),
+	}
+}
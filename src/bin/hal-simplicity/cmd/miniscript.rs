@@ -0,0 +1,92 @@
+use clap;
+
+use hal_simplicity::actions::miniscript::MiniscriptContext;
+
+use crate::cmd;
+
+pub fn subcommand<'a>() -> clap::App<'a, 'a> {
+	cmd::subcommand_group("miniscript", "analyze miniscript policies, scripts and descriptors")
+		.subcommand(cmd_compile())
+		.subcommand(cmd_descriptor())
+		.subcommand(cmd_lift())
+}
+
+pub fn execute<'a>(matches: &clap::ArgMatches<'a>) {
+	match matches.subcommand() {
+		("compile", Some(m)) => exec_compile(m),
+		("descriptor", Some(m)) => exec_descriptor(m),
+		("lift", Some(m)) => exec_lift(m),
+		(_, _) => unreachable!("clap prints help"),
+	};
+}
+
+fn opt_context<'a>() -> clap::Arg<'a, 'a> {
+	cmd::opt("context", "the script context: segwitv0 (default) or tap")
+		.value_name("CONTEXT")
+		.possible_values(&["segwitv0", "tap"])
+		.hide_possible_values(true)
+		.takes_value(true)
+		.required(false)
+}
+
+fn context<'a>(matches: &clap::ArgMatches<'a>) -> MiniscriptContext {
+	matches.value_of("context").map(|c| c.parse().expect("checked by clap")).unwrap_or_default()
+}
+
+fn cmd_compile<'a>() -> clap::App<'a, 'a> {
+	cmd::subcommand("compile", "compile a policy to the cheapest miniscript to satisfy").args(&[
+		cmd::opt_yaml(),
+		opt_context(),
+		cmd::arg("policy", "the policy, such as or(99@pk(K1),1@and(pk(K2),older(144)))")
+			.required(false),
+	])
+}
+
+fn exec_compile<'a>(matches: &clap::ArgMatches<'a>) {
+	let policy = cmd::arg_or_stdin(matches, "policy");
+	match hal_simplicity::actions::miniscript::miniscript_compile(&policy, context(matches)) {
+		Ok(info) => cmd::print_artifact(matches, &info, "script.hex"),
+		Err(e) => cmd::fail(matches, e),
+	}
+}
+
+fn cmd_lift<'a>() -> clap::App<'a, 'a> {
+	cmd::subcommand("lift", "lift a script back to its semantic policy").args(&[
+		cmd::opt_yaml(),
+		opt_context(),
+		cmd::arg("script", "the script, in hex or as miniscript").required(false),
+	])
+}
+
+fn exec_lift<'a>(matches: &clap::ArgMatches<'a>) {
+	let script = cmd::arg_or_stdin(matches, "script");
+	match hal_simplicity::actions::miniscript::miniscript_lift(&script, context(matches)) {
+		Ok(info) => cmd::print_artifact(matches, &info, "policy"),
+		Err(e) => cmd::fail(matches, e),
+	}
+}
+
+fn cmd_descriptor<'a>() -> clap::App<'a, 'a> {
+	cmd::subcommand("descriptor", "parse an output descriptor and derive its address")
+		.args(&cmd::opts_networks())
+		.args(&[
+			cmd::opt_yaml(),
+			cmd::opt("index", "the derivation index, needed if the descriptor has a wildcard")
+				.takes_value(true)
+				.required(false),
+			cmd::arg("descriptor", "the descriptor, such as elwsh(...) or eltr(...)")
+				.required(false),
+		])
+}
+
+fn exec_descriptor<'a>(matches: &clap::ArgMatches<'a>) {
+	let descriptor = cmd::arg_or_stdin(matches, "descriptor");
+	let index = matches
+		.value_of("index")
+		.map(|i| i.parse().unwrap_or_else(|e| panic!("invalid index: {}", e)));
+	let network = cmd::network(matches);
+	match hal_simplicity::actions::miniscript::miniscript_descriptor(&descriptor, index, network) {
+		Ok(info) => cmd::print_artifact(matches, &info, "script_pub_key.address"),
+		Err(e) => cmd::fail(matches, e),
+	}
+}
@@ -1,21 +1,62 @@
 pub mod address;
+#[cfg(feature = "daemon")]
+pub mod batch;
 pub mod block;
+pub mod completions;
+#[cfg(feature = "daemon")]
+pub mod daemon;
 pub mod keypair;
+#[cfg(feature = "miniscript")]
+pub mod miniscript;
+mod pretty;
+pub mod psbt;
+#[cfg(feature = "repl")]
+pub mod repl;
+#[cfg(feature = "daemon")]
+pub mod replay;
+#[cfg(feature = "daemon")]
+pub mod schema;
 pub mod simplicity;
 pub mod tx;
 
 use std::borrow::Cow;
+use std::fs;
 use std::io;
-use std::io::Read;
+use std::io::{BufRead as _, IsTerminal as _, Read};
+use std::process;
 
-use hal_simplicity::Network;
+use hal_simplicity::actions::error::ActionError;
+use hal_simplicity::{lwk, Network};
+
+/// Error code of failures other than those of an action, such as unreadable
+/// arguments. Failed actions use the codes of [`hal_simplicity::actions::error`].
+pub const OTHER_ERROR_CODE: i64 = 1;
+
+/// Subcommands which were renamed, as `(parent, old name, new name)`. The old
+/// names still work, as hidden aliases, but print a deprecation warning. New
+/// names must be unique among all subcommands.
+const RENAMED: &[(&str, &str, &str)] = &[("simplicity pset", "update-input", "attach-utxo")];
 
 /// Build a list of all built-in subcommands.
 pub fn subcommands<'a>() -> Vec<clap::App<'a, 'a>> {
 	vec![
 		address::subcommand(),
+		#[cfg(feature = "daemon")]
+		batch::subcommand(),
 		block::subcommand(),
+		completions::subcommand(),
+		#[cfg(feature = "daemon")]
+		daemon::subcommand(),
 		keypair::subcommand(),
+		#[cfg(feature = "miniscript")]
+		miniscript::subcommand(),
+		psbt::subcommand(),
+		#[cfg(feature = "repl")]
+		repl::subcommand(),
+		#[cfg(feature = "daemon")]
+		replay::subcommand(),
+		#[cfg(feature = "daemon")]
+		schema::subcommand(),
 		simplicity::subcommand(),
 		tx::subcommand(),
 	]
@@ -35,7 +76,7 @@ pub fn arg<'a>(name: &'static str, help: &'static str) -> clap::Arg<'a, 'a> {
 /// This is not intended for actual commands, but for subcommands that host a bunch of other
 /// subcommands.
 pub fn subcommand_group<'a>(name: &'static str, about: &'static str) -> clap::App<'a, 'a> {
-	clap::SubCommand::with_name(name)
+	with_old_names(clap::SubCommand::with_name(name))
 		.about(about)
 		.setting(clap::AppSettings::SubcommandRequiredElseHelp)
 		//.setting(clap::AppSettings::AllowExternalSubcommands)
@@ -45,7 +86,39 @@ pub fn subcommand_group<'a>(name: &'static str, about: &'static str) -> clap::Ap
 
 /// Create a new subcommand using the template that sets all the common settings.
 pub fn subcommand<'a>(name: &'static str, about: &'static str) -> clap::App<'a, 'a> {
-	clap::SubCommand::with_name(name).about(about).setting(clap::AppSettings::DisableHelpSubcommand)
+	with_old_names(clap::SubCommand::with_name(name))
+		.about(about)
+		.setting(clap::AppSettings::DisableHelpSubcommand)
+}
+
+/// Accept the old names of a renamed subcommand.
+fn with_old_names<'a>(app: clap::App<'a, 'a>) -> clap::App<'a, 'a> {
+	let name = app.get_name().to_owned();
+	RENAMED.iter().filter(|(_, _, new)| *new == name).fold(app, |app, (_, old, _)| app.alias(*old))
+}
+
+/// Warn if the subcommand was called by an old name.
+pub fn warn_renamed<'a>(matches: &clap::ArgMatches<'a>) {
+	let mut args = std::env::args_os().skip(1);
+	let mut parent = Vec::new();
+	let mut matches = matches;
+	while let (name, Some(sub)) = matches.subcommand() {
+		let path = parent.join(" ");
+		let old = RENAMED
+			.iter()
+			.find(|(renamed, _, new)| *renamed == path && *new == name)
+			.map(|(_, old, _)| *old);
+		// clap reports the new name either way, so look at the argument it
+		// matched: the first naming the subcommand, as later ones are values.
+		let Some(arg) = args.find(|arg| arg == name || old.is_some_and(|old| arg == old)) else {
+			return;
+		};
+		if let Some(old) = old.filter(|old| arg == *old) {
+			log::warn!("warning: `{} {}` is deprecated; use `{} {}`", path, old, path, name);
+		}
+		parent.push(name);
+		matches = sub;
+	}
 }
 
 pub fn opts_networks<'a>() -> Vec<clap::Arg<'a, 'a>> {
@@ -64,14 +137,112 @@ pub fn opts_networks<'a>() -> Vec<clap::Arg<'a, 'a>> {
 	]
 }
 
+pub fn opt_network<'a>() -> clap::Arg<'a, 'a> {
+	clap::Arg::with_name("network")
+		.long("network")
+		.help("network to work on: elementsregtest (default), liquid or liquidtestnet")
+		.value_name("NETWORK")
+		.possible_values(&["elementsregtest", "liquid", "liquidtestnet"])
+		.hide_possible_values(true)
+		.takes_value(true)
+		.required(false)
+		.global(true)
+}
+
+/// The network selected by `--network` or by one of its shorthands,
+/// `--elementsregtest` and `--liquid`.
 pub fn network<'a>(matches: &clap::ArgMatches<'a>) -> Network {
+	let mut selected = vec![];
+	if let Some(network) = matches.value_of("network") {
+		selected.push(network.parse().expect("checked by clap"));
+	}
 	if matches.is_present("elementsregtest") {
-		Network::ElementsRegtest
-	} else if matches.is_present("liquid") {
-		Network::Liquid
-	} else {
-		Network::ElementsRegtest
+		selected.push(Network::ElementsRegtest);
+	}
+	if matches.is_present("liquid") {
+		selected.push(Network::Liquid);
+	}
+	match selected[..] {
+		[] => Network::ElementsRegtest,
+		[network, ref rest @ ..] => {
+			if let Some(other) = rest.iter().find(|other| **other != network) {
+				panic!("conflicting networks: {} and {}", network.as_str(), other.as_str());
+			}
+			network
+		}
+	}
+}
+
+#[cfg(feature = "registry")]
+pub fn opt_asset_registry<'a>() -> clap::Arg<'a, 'a> {
+	opt("asset-registry", "asset registry to look up the ticker, name and precision of assets in")
+		.value_name("URL")
+		.env("HAL_SIMPLICITY_ASSET_REGISTRY")
+		.takes_value(true)
+		.required(false)
+}
+
+/// Add what the registry given with `--asset-registry` knows of the assets of
+/// a decoded transaction. With `--offline` the registry is not asked.
+#[cfg(feature = "registry")]
+pub fn resolve_assets<'a>(
+	matches: &clap::ArgMatches<'a>,
+	mut info: hal_simplicity::tx::TransactionInfo,
+) -> hal_simplicity::tx::TransactionInfo {
+	if let Some(url) = matches.value_of("asset-registry") {
+		if !matches.is_present("offline") {
+			let mut registry = hal_simplicity::registry::AssetRegistry::new(url);
+			info.resolve_assets(|asset| registry.lookup(asset))
+				.unwrap_or_else(|e| fail(matches, e));
+		}
 	}
+	info
+}
+
+pub fn opt_genesis_hash<'a>() -> clap::Arg<'a, 'a> {
+	clap::Arg::with_name("genesis-hash")
+		.long("genesis-hash")
+		.short("g")
+		.help("genesis hash of the blockchain the transaction belongs to (hex) (default: the network's)")
+		.value_name("HASH")
+		.takes_value(true)
+		.required(false)
+}
+
+/// The genesis hash given with `--genesis-hash`, or else that of the network.
+pub fn genesis_hash<'a>(matches: &clap::ArgMatches<'a>) -> String {
+	if let Some(genesis_hash) = matches.value_of("genesis-hash") {
+		return genesis_hash.to_owned();
+	}
+	let network = network(matches);
+	match network.genesis_hash() {
+		Some(genesis_hash) => genesis_hash.to_string(),
+		None => panic!(
+			"the genesis hash of an {} chain is not known; use --genesis-hash or --network",
+			network.as_str()
+		),
+	}
+}
+
+pub fn opt_leaf_version<'a>() -> clap::Arg<'a, 'a> {
+	opt(
+		"leaf-version",
+		"expert: tapleaf version of the program, for custom deployments (hex) (default: be)",
+	)
+	.value_name("VERSION")
+	.takes_value(true)
+	.required(false)
+}
+
+/// The leaf version given with `--leaf-version`, or else Simplicity's.
+pub fn leaf_version<'a>(matches: &clap::ArgMatches<'a>) -> elements::taproot::LeafVersion {
+	let Some(version) = matches.value_of("leaf-version") else {
+		return hal_simplicity::simplicity::leaf_version();
+	};
+	u8::from_str_radix(version.trim_start_matches("0x"), 16)
+		.ok()
+		.and_then(|version| elements::taproot::LeafVersion::from_u8(version).ok())
+		.unwrap_or_else(|| panic!("invalid leaf-version: {}", version))
 }
 
 pub fn opt_yaml<'a>() -> clap::Arg<'a, 'a> {
@@ -83,32 +254,299 @@ pub fn opt_yaml<'a>() -> clap::Arg<'a, 'a> {
 		.required(false)
 }
 
+pub fn opt_format<'a>() -> clap::Arg<'a, 'a> {
+	clap::Arg::with_name("format")
+		.long("format")
+		.help("output format: json (default), yaml, pretty for a terminal, or lwk for LWK wallets")
+		.value_name("FORMAT")
+		.possible_values(&["json", "yaml", "pretty", "lwk"])
+		.hide_possible_values(true)
+		.takes_value(true)
+		.required(false)
+		.global(true)
+}
+
+pub fn opt_plain<'a>() -> clap::Arg<'a, 'a> {
+	clap::Arg::with_name("plain")
+		.long("plain")
+		.help("print pretty output without colors")
+		.takes_value(false)
+		.required(false)
+		.global(true)
+}
+
+/// The format in which to print output.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum Format {
+	Json,
+	Yaml,
+	Pretty,
+	/// The JSON shapes of the Liquid Wallet Kit, for PSETs and addresses.
+	Lwk,
+}
+
+/// The format selected by `--format`, or by its shorthand `--yaml`.
+pub fn format<'a>(matches: &clap::ArgMatches<'a>) -> Format {
+	if matches.is_present("yaml") {
+		return Format::Yaml;
+	}
+	match matches.value_of("format") {
+		None | Some("json") => Format::Json,
+		Some("yaml") => Format::Yaml,
+		Some("pretty") => Format::Pretty,
+		Some("lwk") => Format::Lwk,
+		Some(_) => unreachable!("checked by clap"),
+	}
+}
+
+pub fn opt_raw<'a>() -> clap::Arg<'a, 'a> {
+	clap::Arg::with_name("raw")
+		.long("raw")
+		.help("print only the main result, such as a PSET or transaction, without JSON")
+		.takes_value(false)
+		.required(false)
+		.global(true)
+}
+
+pub fn opt_offline<'a>() -> clap::Arg<'a, 'a> {
+	clap::Arg::with_name("offline")
+		.long("offline")
+		.help("refuse to contact any daemon or backend, only computing locally")
+		.takes_value(false)
+		.required(false)
+		.global(true)
+}
+
+/// Fail under `--offline`, before contacting `what`, such as a daemon.
+pub fn require_online<'a>(matches: &clap::ArgMatches<'a>, what: &str) {
+	if matches.is_present("offline") {
+		panic!("refusing to contact {} with --offline", what);
+	}
+}
+
+pub fn opt_json_errors<'a>() -> clap::Arg<'a, 'a> {
+	clap::Arg::with_name("json-errors")
+		.long("json-errors")
+		.help("print errors to stderr as JSON objects with a stable error code")
+		.takes_value(false)
+		.required(false)
+		.global(true)
+}
+
+pub fn opt_field<'a>() -> clap::Arg<'a, 'a> {
+	clap::Arg::with_name("field")
+		.long("field")
+		.help("print only the value at the given dot-separated path (e.g. outputs.1.script_pub_key.hex)")
+		.takes_value(true)
+		.required(false)
+}
+
+/// Look up a dot-separated path (e.g. `outputs.1.value`) in a JSON value.
+///
+/// Path components index into objects by key and into arrays by decimal index.
+fn select_field<'v>(value: &'v serde_json::Value, path: &str) -> Option<&'v serde_json::Value> {
+	path.split('.').try_fold(value, |value, component| match value {
+		serde_json::Value::Object(map) => map.get(component),
+		serde_json::Value::Array(vec) => component.parse::<usize>().ok().and_then(|i| vec.get(i)),
+		_ => None,
+	})
+}
+
 /// Get the named argument from the CLI arguments or try read from stdin if not provided.
+///
+/// The argument is expanded as by [`arg_value`].
 pub fn arg_or_stdin<'a>(matches: &'a clap::ArgMatches<'a>, arg: &str) -> Cow<'a, str> {
-	if let Some(s) = matches.value_of(arg) {
-		s.into()
-	} else {
-		// Read from stdin.
-		let mut input = Vec::new();
-		let stdin = io::stdin();
-		let mut stdin_lock = stdin.lock();
-		let _ = stdin_lock.read_to_end(&mut input);
-		while stdin_lock.read_to_end(&mut input).unwrap_or(0) > 0 {}
-		if input.is_empty() {
-			panic!("no '{}' argument given", arg);
+	arg_value(matches, arg).unwrap_or_else(|| read_stdin(arg).into())
+}
+
+/// Get a reader of the named argument, expanded as by [`arg_or_stdin`], which
+/// streams a file or stdin rather than reading it into memory first.
+pub fn arg_or_stdin_reader<'a>(
+	matches: &'a clap::ArgMatches<'a>,
+	arg: &str,
+) -> Box<dyn io::BufRead + 'a> {
+	match matches.value_of(arg) {
+		None | Some("-") => {
+			let mut stdin = io::stdin().lock();
+			if stdin.fill_buf().map(|buf| buf.is_empty()).unwrap_or(true) {
+				panic!("no '{}' argument given", arg);
+			}
+			Box::new(stdin)
+		}
+		Some(value) => match value.strip_prefix('@') {
+			Some(path) => Box::new(io::BufReader::new(
+				fs::File::open(path)
+					.unwrap_or_else(|e| panic!("cannot read '{}' from {}: {}", arg, path, e)),
+			)),
+			None => Box::new(value.as_bytes()),
+		},
+	}
+}
+
+/// Get the named positional argument. An argument of the form `@<path>` is
+/// replaced by the contents of the file, and `-` by the contents of stdin.
+pub fn arg_value<'a>(matches: &'a clap::ArgMatches<'a>, arg: &str) -> Option<Cow<'a, str>> {
+	matches.value_of(arg).map(|value| {
+		if value == "-" {
+			read_stdin(arg).into()
+		} else if let Some(path) = value.strip_prefix('@') {
+			fs::read_to_string(path)
+				.unwrap_or_else(|e| panic!("cannot read '{}' from {}: {}", arg, path, e))
+				.trim()
+				.to_owned()
+				.into()
+		} else {
+			value.into()
+		}
+	})
+}
+
+/// Get the named secret, such as a secret key. Besides the secret itself, it
+/// may be given as `prompt`, to read it from the terminal without echoing it,
+/// or as `env:<VAR>`, to read it from an environment variable, so that it is
+/// kept out of shell history and process listings.
+pub fn secret_value<'a>(matches: &'a clap::ArgMatches<'a>, arg: &str) -> Option<Cow<'a, str>> {
+	matches.value_of(arg).map(|value| {
+		if value == "prompt" {
+			rpassword::prompt_password(format!("{}: ", arg))
+				.unwrap_or_else(|e| panic!("cannot read '{}' from the terminal: {}", arg, e))
+				.trim()
+				.to_owned()
+				.into()
+		} else if let Some(var) = value.strip_prefix("env:") {
+			std::env::var(var)
+				.unwrap_or_else(|e| panic!("cannot read '{}' from ${}: {}", arg, var, e))
+				.trim()
+				.to_owned()
+				.into()
+		} else {
+			value.into()
+		}
+	})
+}
+
+/// Read the value of the named argument from stdin.
+fn read_stdin(arg: &str) -> String {
+	let mut input = Vec::new();
+	let stdin = io::stdin();
+	let mut stdin_lock = stdin.lock();
+	let _ = stdin_lock.read_to_end(&mut input);
+	while stdin_lock.read_to_end(&mut input).unwrap_or(0) > 0 {}
+	if input.is_empty() {
+		panic!("no '{}' argument given", arg);
+	}
+	String::from_utf8(input)
+		.unwrap_or_else(|e| panic!("invalid utf8 on stdin for '{}': {}", arg, e))
+		.trim()
+		.to_owned()
+}
+
+/// Print the output of a command whose main result, such as a PSET or a
+/// transaction, is the value at the dot-separated path `artifact`. With
+/// `--raw`, only that value is printed.
+pub fn print_artifact<'a, T: serde::Serialize>(
+	matches: &clap::ArgMatches<'a>,
+	out: &T,
+	artifact: &str,
+) {
+	if let Format::Lwk = format(matches) {
+		let value = serde_json::to_value(out).unwrap();
+		if let Some(artifact) = select_field(&value, artifact) {
+			return print_lwk(artifact);
 		}
-		String::from_utf8(input)
-			.unwrap_or_else(|e| panic!("invalid utf8 on stdin for '{}': {}", arg, e))
-			.trim()
-			.to_owned()
-			.into()
+	}
+	if matches.is_present("raw") {
+		let value = serde_json::to_value(out).unwrap();
+		// An error has no artifact, and is printed in full.
+		if let Some(artifact) = select_field(&value, artifact) {
+			print_raw(artifact);
+			return;
+		}
+	}
+	print_output(matches, out)
+}
+
+/// Print a PSET or an address in the shape which the Liquid Wallet Kit uses.
+fn print_lwk(artifact: &serde_json::Value) {
+	let artifact = artifact.as_str().unwrap_or_default();
+	let shaped = if let Ok(address) = artifact.parse() {
+		serde_json::to_value(lwk::Address {
+			address,
+			index: None,
+		})
+	} else if artifact.parse::<elements::pset::PartiallySignedTransaction>().is_ok() {
+		serde_json::to_value(lwk::Pset {
+			pset: artifact.to_owned(),
+		})
+	} else {
+		panic!("--format lwk is only supported for PSETs and addresses");
+	};
+	serde_json::to_writer_pretty(io::stdout(), &shaped.unwrap()).unwrap();
+}
+
+/// Print a value without JSON quoting, for use in shell pipelines.
+fn print_raw(value: &serde_json::Value) {
+	match value {
+		serde_json::Value::String(s) => print!("{}", s),
+		value => print!("{}", value),
 	}
 }
 
 pub fn print_output<'a, T: serde::Serialize>(matches: &clap::ArgMatches<'a>, out: &T) {
-	if matches.is_present("yaml") {
-		serde_yaml::to_writer(::std::io::stdout(), &out).unwrap();
+	if matches.is_present("raw") {
+		// The output is itself the artifact.
+		if let value @ serde_json::Value::String(_) = serde_json::to_value(out).unwrap() {
+			return print_raw(&value);
+		}
+	}
+	if let Some(path) = matches.value_of("field") {
+		let value = serde_json::to_value(out).unwrap();
+		match select_field(&value, path) {
+			// Strings are printed bare so that they can be used directly in shell pipelines.
+			Some(serde_json::Value::String(s)) => print!("{}", s),
+			Some(field) => print_formatted(matches, field),
+			None => panic!("field '{}' not found in output", path),
+		}
+	} else {
+		print_formatted(matches, out);
+	}
+}
+
+/// Print a value in the format selected by [`format`].
+fn print_formatted<'a, T: serde::Serialize>(matches: &clap::ArgMatches<'a>, out: &T) {
+	match format(matches) {
+		Format::Json => serde_json::to_writer_pretty(io::stdout(), out).unwrap(),
+		Format::Yaml => serde_yaml::to_writer(io::stdout(), out).unwrap(),
+		Format::Pretty => {
+			// Colors only make sense on a terminal, and NO_COLOR turns them off as usual.
+			let style = pretty::Style {
+				color: !matches.is_present("plain")
+					&& std::env::var_os("NO_COLOR").is_none()
+					&& io::stdout().is_terminal(),
+			};
+			print!("{}", pretty::render(&serde_json::to_value(out).unwrap(), style));
+		}
+		Format::Lwk => panic!("--format lwk is only supported for PSETs and addresses"),
+	}
+}
+
+/// Report a failed action and exit with status 1.
+pub fn fail<'a, E: ActionError>(matches: &clap::ArgMatches<'a>, e: E) -> ! {
+	exit_with_error(matches.is_present("json-errors"), e.code(), &e.to_string(), Some(e.data()))
+}
+
+/// Print an error to stderr and exit with status 1. With `json`, the error is
+/// printed as `{"error": {"code": ..., "message": ..., "data": ...}}`, like a
+/// JSON-RPC error.
+pub fn exit_with_error(json: bool, code: i64, message: &str, data: Option<serde_json::Value>) -> ! {
+	if json {
+		let mut error = serde_json::json!({ "code": code, "message": message });
+		if let Some(data) = data {
+			error["data"] = data;
+		}
+		eprintln!("{}", serde_json::json!({ "error": error }));
 	} else {
-		serde_json::to_writer_pretty(::std::io::stdout(), &out).unwrap();
+		eprintln!("Execution failed: {}", message);
 	}
+	process::exit(1);
 }
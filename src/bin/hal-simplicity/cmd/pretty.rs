@@ -0,0 +1,157 @@
+//! Human-oriented rendering of command output, for `--format pretty`
+//!
+//! Objects are printed as aligned `key  value` lines, nested objects are
+//! indented under their key, and arrays of flat objects, such as the inputs
+//! of a PSET, are printed as tables.
+
+use std::fmt::Write as _;
+
+use serde_json::{Map, Value};
+
+const INDENT: &str = "  ";
+
+/// ANSI styles, or none when colors are disabled.
+#[derive(Clone, Copy)]
+pub struct Style {
+	pub color: bool,
+}
+
+impl Style {
+	fn paint(self, code: &str, text: &str) -> String {
+		match self.color {
+			true => format!("\x1b[{}m{}\x1b[0m", code, text),
+			false => text.to_owned(),
+		}
+	}
+
+	fn key(self, key: &str) -> String {
+		self.paint("1", key)
+	}
+
+	fn scalar(self, value: &Value) -> String {
+		match value {
+			Value::String(s) => s.clone(),
+			Value::Number(n) => self.paint("36", &n.to_string()),
+			Value::Bool(b) => self.paint("33", &b.to_string()),
+			Value::Null => self.paint("2", "-"),
+			Value::Array(_) => "[]".to_owned(),
+			Value::Object(_) => "{}".to_owned(),
+		}
+	}
+}
+
+/// Render a value, ending with a newline.
+pub fn render(value: &Value, style: Style) -> String {
+	let mut out = String::new();
+	match value {
+		Value::Object(map) => render_object(map, 0, style, &mut out),
+		Value::Array(items) => render_array(items, 0, style, &mut out),
+		scalar => writeln!(out, "{}", style.scalar(scalar)).unwrap(),
+	}
+	out
+}
+
+/// Whether a value fits on one line: a scalar or an empty array or object.
+fn is_flat(value: &Value) -> bool {
+	match value {
+		Value::Array(items) => items.is_empty(),
+		Value::Object(map) => map.is_empty(),
+		_ => true,
+	}
+}
+
+fn render_object(map: &Map<String, Value>, depth: usize, style: Style, out: &mut String) {
+	let indent = INDENT.repeat(depth);
+	let width = map.iter().filter(|(_, v)| is_flat(v)).map(|(k, _)| k.len()).max().unwrap_or(0);
+	for (key, value) in map {
+		if is_flat(value) {
+			// Pad before styling, since escape codes take no room on screen.
+			let padded = format!("{:<width$}", key, width = width);
+			writeln!(out, "{}{}  {}", indent, style.key(&padded), style.scalar(value)).unwrap();
+		} else {
+			writeln!(out, "{}{}", indent, style.key(key)).unwrap();
+			match value {
+				Value::Object(map) => render_object(map, depth + 1, style, out),
+				Value::Array(items) => render_array(items, depth + 1, style, out),
+				_ => unreachable!("scalars are flat"),
+			}
+		}
+	}
+}
+
+fn render_array(items: &[Value], depth: usize, style: Style, out: &mut String) {
+	if let Some(columns) = table_columns(items) {
+		return render_table(items, &columns, depth, style, out);
+	}
+	let indent = INDENT.repeat(depth);
+	for (i, item) in items.iter().enumerate() {
+		match item {
+			Value::Object(map) if !map.is_empty() => {
+				writeln!(out, "{}{}", indent, style.key(&format!("[{}]", i))).unwrap();
+				render_object(map, depth + 1, style, out);
+			}
+			Value::Array(items) if !items.is_empty() => {
+				writeln!(out, "{}{}", indent, style.key(&format!("[{}]", i))).unwrap();
+				render_array(items, depth + 1, style, out);
+			}
+			scalar => writeln!(out, "{}- {}", indent, style.scalar(scalar)).unwrap(),
+		}
+	}
+}
+
+/// The columns of a table for the array, if its items are objects with the
+/// same keys and only flat values.
+fn table_columns(items: &[Value]) -> Option<Vec<&str>> {
+	let first = items.first()?.as_object()?;
+	let columns: Vec<&str> = first.keys().map(String::as_str).collect();
+	let same_shape = items.iter().all(|item| match item.as_object() {
+		Some(map) => map.keys().eq(first.keys()) && map.values().all(is_flat),
+		None => false,
+	});
+	match same_shape && !columns.is_empty() {
+		true => Some(columns),
+		false => None,
+	}
+}
+
+fn render_table(items: &[Value], columns: &[&str], depth: usize, style: Style, out: &mut String) {
+	let indent = INDENT.repeat(depth);
+	let rows: Vec<Vec<(String, String)>> = items
+		.iter()
+		.map(|item| {
+			columns
+				.iter()
+				.map(|column| {
+					let plain = Style {
+						color: false,
+					};
+					(plain.scalar(&item[column]), style.scalar(&item[column]))
+				})
+				.collect()
+		})
+		.collect();
+	let widths: Vec<usize> = columns
+		.iter()
+		.enumerate()
+		.map(|(i, column)| {
+			rows.iter().map(|row| row[i].0.len()).chain([column.len()]).max().unwrap()
+		})
+		.collect();
+
+	let header: Vec<String> = columns
+		.iter()
+		.zip(&widths)
+		.map(|(column, width)| style.key(&format!("{:<width$}", column, width = width)))
+		.collect();
+	writeln!(out, "{}{}", indent, header.join("  ").trim_end()).unwrap();
+	for row in rows {
+		let cells: Vec<String> = row
+			.iter()
+			.zip(&widths)
+			.map(|((plain, styled), width)| {
+				format!("{}{}", styled, " ".repeat(width - plain.len()))
+			})
+			.collect();
+		writeln!(out, "{}{}", indent, cells.join("  ").trim_end()).unwrap();
+	}
+}
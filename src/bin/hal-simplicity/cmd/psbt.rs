@@ -0,0 +1,33 @@
+use clap;
+
+use crate::cmd;
+
+pub fn subcommand<'a>() -> clap::App<'a, 'a> {
+	cmd::subcommand_group("psbt", "inspect mainchain Bitcoin PSBTs, such as those funding peg-ins")
+		.subcommand(cmd_decode())
+}
+
+pub fn execute<'a>(matches: &clap::ArgMatches<'a>) {
+	match matches.subcommand() {
+		("decode", Some(m)) => exec_decode(m),
+		(_, _) => unreachable!("clap prints help"),
+	};
+}
+
+fn cmd_decode<'a>() -> clap::App<'a, 'a> {
+	cmd::subcommand("decode", "decode a Bitcoin PSBT to JSON").args(&cmd::opts_networks()).args(&[
+		cmd::opt_yaml(),
+		cmd::opt_field(),
+		cmd::arg("psbt", "the PSBT in base64 or hex").required(false),
+	])
+}
+
+fn exec_decode<'a>(matches: &clap::ArgMatches<'a>) {
+	let psbt = cmd::arg_or_stdin(matches, "psbt");
+	let network = cmd::network(matches);
+
+	let info = hal_simplicity::actions::psbt::psbt_decode(psbt.as_ref(), network)
+		.unwrap_or_else(|e| cmd::fail(matches, e));
+
+	cmd::print_output(matches, &info)
+}
@@ -0,0 +1,233 @@
+use std::collections::BTreeMap;
+use std::process::Command;
+
+use clap;
+use rustyline::completion::Completer;
+use rustyline::error::ReadlineError;
+use rustyline::highlight::Highlighter;
+use rustyline::hint::Hinter;
+use rustyline::history::DefaultHistory;
+use rustyline::validate::Validator;
+use rustyline::{Context, Editor, Helper};
+use serde_json::Value;
+
+use crate::cmd;
+
+/// Commands of the REPL itself, as opposed to those of hal-simplicity.
+const BUILTINS: &[(&str, &str)] = &[
+	("help", "show this message"),
+	("vars", "show the remembered values"),
+	("set", "set <name> <value>: remember a value"),
+	("unset", "unset <name>: forget a value"),
+	("exit", "leave the REPL (or press ^D)"),
+];
+
+/// Output fields remembered as variables, as `(variable, field)`.
+const REMEMBERED: &[(&str, &str)] =
+	&[("pset", "pset"), ("program", "commit_base64"), ("witness", "witness_hex"), ("cmr", "cmr")];
+
+pub fn subcommand<'a>() -> clap::App<'a, 'a> {
+	cmd::subcommand(
+		"repl",
+		"run commands interactively, remembering the last PSET, program and transaction",
+	)
+}
+
+pub fn execute<'a>(matches: &clap::ArgMatches<'a>) {
+	let helper = ReplHelper {
+		commands: CommandTree::of(&crate::init_app()),
+		vars: BTreeMap::new(),
+	};
+	let mut editor = Editor::<ReplHelper, DefaultHistory>::new()
+		.unwrap_or_else(|e| panic!("cannot start the REPL: {}", e));
+	editor.set_helper(Some(helper));
+
+	// Commands inherit the network and --offline given to the REPL.
+	let network = matches.value_of("network");
+	let offline = matches.is_present("offline");
+	loop {
+		let line = match editor.readline("hal-simplicity> ") {
+			Ok(line) => line,
+			Err(ReadlineError::Interrupted) => continue,
+			Err(ReadlineError::Eof) => break,
+			Err(e) => panic!("cannot read a command: {}", e),
+		};
+		if line.trim().is_empty() {
+			continue;
+		}
+		let _ = editor.add_history_entry(line.as_str());
+		let words = match shlex::split(&line) {
+			Some(words) => words,
+			None => {
+				eprintln!("unbalanced quotes");
+				continue;
+			}
+		};
+		let vars = &mut editor.helper_mut().expect("helper set above").vars;
+		match words[0].as_str() {
+			"exit" | "quit" => break,
+			"help" => {
+				println!("Run any hal-simplicity command, without the leading hal-simplicity.");
+				println!("$name is replaced by a remembered value; see vars.\n");
+				for (name, help) in BUILTINS {
+					println!("    {:<8}{}", name, help);
+				}
+			}
+			"vars" => {
+				for (name, value) in vars.iter() {
+					println!("{} = {}", name, value);
+				}
+			}
+			"set" if words.len() == 3 => {
+				vars.insert(words[1].clone(), words[2].clone());
+			}
+			"unset" if words.len() == 2 => {
+				vars.remove(&words[1]);
+			}
+			"set" | "unset" => {
+				eprintln!("usage: {}", BUILTINS.iter().find(|b| b.0 == words[0]).unwrap().1)
+			}
+			_ => run(&words, network, offline, vars),
+		}
+	}
+}
+
+/// Run a hal-simplicity command in a separate process, so that its failure
+/// does not end the REPL, and remember the values of its output.
+fn run(
+	words: &[String],
+	network: Option<&str>,
+	offline: bool,
+	vars: &mut BTreeMap<String, String>,
+) {
+	let mut args = Vec::with_capacity(words.len() + 2);
+	for word in words {
+		match word.strip_prefix('$') {
+			Some(name) => match vars.get(name) {
+				Some(value) => args.push(value.clone()),
+				None => {
+					eprintln!("unknown variable: ${}", name);
+					return;
+				}
+			},
+			None => args.push(word.clone()),
+		}
+	}
+	if let Some(network) = network {
+		if !args.iter().any(|arg| arg == "--network") {
+			args.extend(["--network".to_owned(), network.to_owned()]);
+		}
+	}
+	if offline {
+		args.push("--offline".to_owned());
+	}
+
+	let exe =
+		std::env::current_exe().unwrap_or_else(|e| panic!("cannot find hal-simplicity: {}", e));
+	let output = match Command::new(exe).args(&args).output() {
+		Ok(output) => output,
+		Err(e) => {
+			eprintln!("cannot run the command: {}", e);
+			return;
+		}
+	};
+	let stdout = String::from_utf8_lossy(&output.stdout);
+	print!("{}", stdout);
+	if !stdout.is_empty() && !stdout.ends_with('\n') {
+		println!();
+	}
+	eprint!("{}", String::from_utf8_lossy(&output.stderr));
+	if output.status.success() {
+		remember(&args, stdout.trim(), vars);
+	}
+}
+
+/// Remember the values in a command's output.
+fn remember(args: &[String], stdout: &str, vars: &mut BTreeMap<String, String>) {
+	match serde_json::from_str::<Value>(stdout) {
+		Ok(Value::Object(fields)) => {
+			for (name, field) in REMEMBERED {
+				if let Some(Value::String(value)) = fields.get(*field) {
+					vars.insert(name.to_string(), value.clone());
+				}
+			}
+		}
+		// `simplicity pset extract` prints only the transaction.
+		Ok(Value::String(tx)) => {
+			vars.insert("tx".to_owned(), tx);
+		}
+		_ if args.starts_with(&["tx".to_owned(), "create".to_owned()]) => {
+			vars.insert("tx".to_owned(), stdout.to_owned());
+		}
+		_ => {}
+	}
+}
+
+/// The names of the subcommands of a command, and of theirs.
+struct CommandTree(Vec<(String, CommandTree)>);
+
+impl CommandTree {
+	fn of(app: &clap::App) -> Self {
+		// clap 2 has no public accessor for subcommands, so use its parser's.
+		CommandTree(
+			app.p
+				.subcommands
+				.iter()
+				.filter(|sub| sub.get_name() != "repl")
+				.map(|sub| (sub.get_name().to_owned(), CommandTree::of(sub)))
+				.collect(),
+		)
+	}
+}
+
+struct ReplHelper {
+	commands: CommandTree,
+	vars: BTreeMap<String, String>,
+}
+
+impl Completer for ReplHelper {
+	type Candidate = String;
+
+	fn complete(
+		&self,
+		line: &str,
+		pos: usize,
+		_ctx: &Context<'_>,
+	) -> rustyline::Result<(usize, Vec<String>)> {
+		let line = &line[..pos];
+		let start = line.rfind(char::is_whitespace).map_or(0, |i| i + 1);
+		let (previous, word) = line.split_at(start);
+
+		let candidates: Vec<String> = if let Some(name) = word.strip_prefix('$') {
+			self.vars
+				.keys()
+				.filter(|var| var.starts_with(name))
+				.map(|var| format!("${}", var))
+				.collect()
+		} else {
+			// Follow the subcommands typed so far; there are none after arguments.
+			let tree = previous.split_whitespace().try_fold(&self.commands, |tree, previous| {
+				tree.0.iter().find(|(name, _)| name == previous).map(|(_, sub)| sub)
+			});
+			let top = previous.trim().is_empty();
+			let builtins = BUILTINS.iter().map(|b| b.0).filter(|_| top);
+			tree.into_iter()
+				.flat_map(|tree| tree.0.iter().map(|(name, _)| name.as_str()))
+				.chain(builtins)
+				.filter(|name| name.starts_with(word))
+				.map(str::to_owned)
+				.collect()
+		};
+		Ok((start, candidates))
+	}
+}
+
+impl Hinter for ReplHelper {
+	type Hint = String;
+}
+
+impl Highlighter for ReplHelper {}
+
+impl Validator for ReplHelper {}
+
+impl Helper for ReplHelper {}
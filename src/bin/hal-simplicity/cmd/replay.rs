@@ -0,0 +1,79 @@
+use std::io::{self, Write};
+use std::path::Path;
+use std::process;
+
+use clap;
+use hal_simplicity::daemon::client::ClientError;
+use hal_simplicity::daemon::record;
+use serde_json::json;
+
+use crate::cmd;
+use crate::cmd::daemon;
+
+pub fn subcommand<'a>() -> clap::App<'a, 'a> {
+	cmd::subcommand(
+		"replay",
+		"make the calls recorded by a daemon again, reporting those whose outcome has changed",
+	)
+	.args(&daemon::opts_auth())
+	.args(&[
+		daemon::opt_daemon_url(),
+		cmd::arg("file", "file recorded by hal-simplicity-daemon --record").required(true),
+		cmd::opt("skip", "do not replay calls of this method (may be given several times)")
+			.value_name("METHOD")
+			.takes_value(true)
+			.multiple(true)
+			.number_of_values(1),
+	])
+	.after_help(
+		"Only calls which should have the same outcome every time are replayed: those which \
+			change nothing, depend on no sessions or other state of the daemon, and had no \
+			secrets redacted when recorded.\n\n\
+			Each call whose outcome differs is printed as {\"line\": n, \"method\": ..., \
+			\"differences\": [{\"path\": ..., \"recorded\": ..., \"replayed\": ...}]}, where the \
+			path is a JSON pointer into {\"result\": ...} or {\"error\": ...}. A summary is \
+			printed last. The exit status is 1 if any outcome differs.",
+	)
+}
+
+pub fn execute<'a>(matches: &clap::ArgMatches<'a>) {
+	cmd::require_online(matches, "the daemon");
+	let path = matches.value_of("file").expect("file is mandatory");
+	let records = record::read(Path::new(path))
+		.unwrap_or_else(|e| panic!("cannot read recorded calls from {}: {}", path, e));
+	let skip: Vec<_> = matches.values_of("skip").map(Iterator::collect).unwrap_or_default();
+	let client = daemon::profile(matches)
+		.client()
+		.unwrap_or_else(|e| panic!("{}", e))
+		.with_trace(matches.is_present("trace-rpc"));
+
+	let (mut replayed, mut differed, mut skipped) = (0, 0, 0);
+	let stdout = io::stdout();
+	for (line, record) in records {
+		if !record.is_replayable() || skip.contains(&record.method.as_str()) {
+			skipped += 1;
+			continue;
+		}
+
+		let outcome = match client.call(&record.method, record.params.clone()) {
+			Err(ClientError::Rpc(error)) => Err(error),
+			Err(e) => panic!("cannot replay line {}: {}", line, e),
+			Ok(result) => Ok(result),
+		};
+		replayed += 1;
+		let differences = record::differences(&record.outcome(), &record::outcome(&outcome));
+		if differences.is_empty() {
+			continue;
+		}
+		differed += 1;
+		let output = json!({ "line": line, "method": record.method, "differences": differences });
+		let mut stdout = stdout.lock();
+		writeln!(stdout, "{}", output).and_then(|_| stdout.flush()).expect("writing to stdout");
+	}
+
+	let summary = json!({ "replayed": replayed, "differed": differed, "skipped": skipped });
+	writeln!(stdout.lock(), "{}", summary).expect("writing to stdout");
+	if differed > 0 {
+		process::exit(1);
+	}
+}
@@ -0,0 +1,31 @@
+use clap;
+use hal_simplicity::daemon::schema;
+
+use crate::cmd;
+
+pub fn subcommand<'a>() -> clap::App<'a, 'a> {
+	cmd::subcommand("schema", "print the JSON Schemas of the output formats")
+		.args(&[
+			cmd::arg("type", "only print the schema of this type, such as TransactionInfo"),
+			cmd::opt("list", "list the names of the types instead").conflicts_with("type"),
+		])
+		.after_help(
+			"Every type which hal-simplicity prints, or which hal-simplicity-daemon returns, is \
+				described. Without a type, the schemas are printed as one object keyed by type \
+				name. The same schemas are returned by the daemon's schema method.",
+		)
+}
+
+pub fn execute<'a>(matches: &clap::ArgMatches<'a>) {
+	if matches.is_present("list") {
+		cmd::print_output(matches, &schema::output_types().collect::<Vec<_>>());
+	} else if let Some(name) = matches.value_of("type") {
+		let schema = schema::output_schema(name).unwrap_or_else(|| {
+			let types = schema::output_types().collect::<Vec<_>>().join(", ");
+			panic!("unknown type {}, expected one of: {}", name, types)
+		});
+		cmd::print_output(matches, &schema);
+	} else {
+		cmd::print_output(matches, &schema::output_schemas());
+	}
+}
@@ -0,0 +1,51 @@
+use crate::cmd;
+
+pub fn cmd<'a>() -> clap::App<'a, 'a> {
+	cmd::subcommand(
+		"bench",
+		"Run a Simplicity program repeatedly in the context of a PSET input, timing it against its cost",
+	)
+	.args(&cmd::opts_networks())
+	.args(&[
+		cmd::opt_yaml(),
+		cmd::arg("pset", "PSET whose input the program spends (base64)")
+			.takes_value(true)
+			.required(true),
+		cmd::arg("input-index", "the index of the input the program spends (decimal)")
+			.takes_value(true)
+			.required(true),
+		cmd::arg("program", "Simplicity program (base64)").takes_value(true).required(true),
+		cmd::arg("witness", "Simplicity program witness (hex), or a SimplicityHL .wit file")
+			.takes_value(true)
+			.required(true),
+		cmd::opt_genesis_hash(),
+		cmd::opt("iterations", "the number of times to run the program (default: 100)")
+			.short("n")
+			.value_name("N")
+			.takes_value(true),
+	])
+}
+
+pub fn exec<'a>(matches: &clap::ArgMatches<'a>) {
+	let pset_b64 = cmd::arg_value(matches, "pset").expect("pset is mandatory");
+	let input_idx = cmd::arg_value(matches, "input-index").expect("input-index is mandatory");
+	let program = cmd::arg_value(matches, "program").expect("program is mandatory");
+	let witness = cmd::arg_value(matches, "witness").expect("witness is mandatory");
+	let genesis_hash = cmd::genesis_hash(matches);
+	let iterations = matches
+		.value_of("iterations")
+		.map(|n| n.parse().unwrap_or_else(|e| panic!("invalid iterations: {}", e)))
+		.unwrap_or(100);
+
+	match hal_simplicity::actions::simplicity::simplicity_bench(
+		&pset_b64,
+		&input_idx,
+		&program,
+		&witness,
+		Some(&genesis_hash),
+		iterations,
+	) {
+		Ok(info) => cmd::print_output(matches, &info),
+		Err(e) => cmd::fail(matches, e),
+	}
+}
@@ -0,0 +1,27 @@
+use crate::cmd;
+
+pub fn cmd<'a>() -> clap::App<'a, 'a> {
+	cmd::subcommand(
+		"check-prune",
+		"Check that a pruned Simplicity program was derived from an unpruned one",
+	)
+	.args(&[
+		cmd::opt_yaml(),
+		cmd::arg("unpruned", "the unpruned Simplicity program (base64)")
+			.takes_value(true)
+			.required(true),
+		cmd::arg("pruned", "the pruned Simplicity program (base64)")
+			.takes_value(true)
+			.required(true),
+	])
+}
+
+pub fn exec<'a>(matches: &clap::ArgMatches<'a>) {
+	let unpruned = cmd::arg_value(matches, "unpruned").expect("unpruned is mandatory");
+	let pruned = cmd::arg_value(matches, "pruned").expect("pruned is mandatory");
+
+	match hal_simplicity::actions::simplicity::simplicity_check_prune(&unpruned, &pruned) {
+		Ok(info) => cmd::print_output(matches, &info),
+		Err(e) => cmd::fail(matches, e),
+	}
+}
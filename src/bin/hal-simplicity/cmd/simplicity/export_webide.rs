@@ -0,0 +1,86 @@
+use std::str::FromStr;
+
+use hal_simplicity::actions::simplicity::WebIdeEnvironment;
+
+use crate::cmd;
+
+pub fn cmd<'a>() -> clap::App<'a, 'a> {
+	cmd::subcommand(
+		"export-webide",
+		"Bundle a program, witness and transaction parameters for simplicity-webide",
+	)
+	.args(&cmd::opts_networks())
+	.args(&[
+		cmd::opt_yaml(),
+		cmd::arg("program", "a Simplicity program in base64").takes_value(true).required(true),
+		cmd::arg(
+			"witness",
+			"a hex encoding of all the witness data for the program, or a SimplicityHL .wit file",
+		)
+		.takes_value(true)
+		.required(false),
+		cmd::opt("source", "the SimplicityHL source of the program, to include as is")
+			.value_name("SOURCE")
+			.takes_value(true),
+		cmd::opt("txid", "the transaction whose output the program spends (hex)")
+			.value_name("TXID")
+			.takes_value(true),
+		cmd::opt("vout", "the index of the spent output (decimal)")
+			.value_name("VOUT")
+			.takes_value(true),
+		cmd::opt("value", "the value of the spent output (satoshi)")
+			.value_name("SAT")
+			.takes_value(true),
+		cmd::opt("asset", "the asset of the spent output (hex) (default: the network's L-BTC)")
+			.value_name("ASSET")
+			.takes_value(true),
+		cmd::opt("recipient", "the address paid the value less the fee")
+			.value_name("ADDRESS")
+			.takes_value(true),
+		cmd::opt("fee", "the fee (satoshi)").value_name("SAT").takes_value(true),
+		cmd::opt("lock-time", "the lock time of the transaction (decimal)")
+			.value_name("LOCKTIME")
+			.takes_value(true),
+		cmd::opt("sequence", "the sequence number of the input (decimal)")
+			.value_name("SEQUENCE")
+			.takes_value(true),
+		cmd::opt_genesis_hash(),
+	])
+}
+
+/// Parse the value of an option, if given.
+fn parse<'a, T: FromStr>(matches: &clap::ArgMatches<'a>, name: &str) -> Option<T>
+where
+	T::Err: std::fmt::Display,
+{
+	matches
+		.value_of(name)
+		.map(|value| value.parse().unwrap_or_else(|e| panic!("invalid {}: {}", name, e)))
+}
+
+pub fn exec<'a>(matches: &clap::ArgMatches<'a>) {
+	let program = cmd::arg_value(matches, "program").expect("program is mandatory");
+	let witness = cmd::arg_value(matches, "witness");
+	let source = cmd::arg_value(matches, "source");
+	let environment = WebIdeEnvironment {
+		txid: parse(matches, "txid"),
+		vout: parse(matches, "vout"),
+		value: parse(matches, "value"),
+		asset: parse(matches, "asset"),
+		recipient_address: matches.value_of("recipient").map(str::to_owned),
+		fee: parse(matches, "fee"),
+		lock_time: parse(matches, "lock-time"),
+		sequence: parse(matches, "sequence"),
+		genesis_hash: parse(matches, "genesis-hash").or(cmd::network(matches).genesis_hash()),
+	};
+
+	match hal_simplicity::actions::simplicity::simplicity_export_webide(
+		&program,
+		witness.as_deref(),
+		source.as_deref(),
+		environment,
+	) {
+		Ok(bundle) => cmd::print_output(matches, &bundle),
+		Err(e) => cmd::fail(matches, e),
+	}
+}
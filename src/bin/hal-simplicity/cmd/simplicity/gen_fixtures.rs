@@ -0,0 +1,26 @@
+use crate::cmd;
+
+pub fn cmd<'a>() -> clap::App<'a, 'a> {
+	cmd::subcommand(
+		"gen-fixtures",
+		"Generate deterministic program, witness and PSET fixtures for testing other tools",
+	)
+	.args(&[
+		cmd::opt_yaml(),
+		cmd::arg("seed", "a seed to generate a fixture from (any string)")
+			.takes_value(true)
+			.multiple(true)
+			.required(true),
+	])
+	.after_help(
+		"Each seed gives a key, a program checking a signature by that key, and a signed \
+			Liquid testnet PSET spending an output locked by the program. The same seed always \
+			gives the same fixture, unless the version of the fixture format changes.",
+	)
+}
+
+pub fn exec<'a>(matches: &clap::ArgMatches<'a>) {
+	let seeds: Vec<_> = matches.values_of("seed").expect("seed is mandatory").collect();
+	let fixtures = hal_simplicity::actions::simplicity::simplicity_gen_fixtures(&seeds);
+	cmd::print_output(matches, &fixtures)
+}
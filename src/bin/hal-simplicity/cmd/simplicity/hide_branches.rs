@@ -0,0 +1,36 @@
+use crate::cmd;
+
+pub fn cmd<'a>() -> clap::App<'a, 'a> {
+	cmd::subcommand(
+		"hide-branches",
+		"Hide the untaken branches of a Simplicity program by hand, without running it",
+	)
+	.args(&[
+		cmd::opt_yaml(),
+		cmd::arg("program", "Simplicity program (base64)").takes_value(true).required(true),
+		cmd::arg("witness", "Simplicity program witness (hex), or a SimplicityHL .wit file")
+			.takes_value(true)
+			.required(true),
+		cmd::opt(
+			"keep",
+			"the branch taken by a case node, as <index>:left or <index>:right with the node's index in --source-map of simplicity info (repeatable)",
+		)
+		.value_name("BRANCH")
+		.takes_value(true)
+		.multiple(true)
+		.number_of_values(1),
+	])
+}
+
+pub fn exec<'a>(matches: &clap::ArgMatches<'a>) {
+	let program = cmd::arg_value(matches, "program").expect("program is mandatory");
+	let witness = cmd::arg_value(matches, "witness").expect("witness is mandatory");
+	let branches: Vec<&str> = matches.values_of("keep").map(Iterator::collect).unwrap_or_default();
+
+	match hal_simplicity::actions::simplicity::simplicity_hide_branches(
+		&program, &witness, &branches,
+	) {
+		Ok(info) => cmd::print_artifact(matches, &info, "program_base64"),
+		Err(e) => cmd::fail(matches, e),
+	}
+}
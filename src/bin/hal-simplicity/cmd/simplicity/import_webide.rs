@@ -0,0 +1,23 @@
+use crate::cmd;
+
+pub fn cmd<'a>() -> clap::App<'a, 'a> {
+	cmd::subcommand(
+		"import-webide",
+		"Decode a program, witness and transaction parameters exported from simplicity-webide",
+	)
+	.args(&cmd::opts_networks())
+	.args(&[
+		cmd::opt_yaml(),
+		cmd::arg("bundle", "the web IDE's JSON bundle").takes_value(true).required(true),
+	])
+}
+
+pub fn exec<'a>(matches: &clap::ArgMatches<'a>) {
+	let bundle = cmd::arg_value(matches, "bundle").expect("bundle is mandatory");
+	let network = cmd::network(matches);
+
+	match hal_simplicity::actions::simplicity::simplicity_import_webide(&bundle, network) {
+		Ok(import) => cmd::print_output(matches, &import),
+		Err(e) => cmd::fail(matches, e),
+	}
+}
@@ -1,9 +1,9 @@
 // Copyright 2025 Andrew Poelstra
 // SPDX-License-Identifier: CC0-1.0
 
-use crate::cmd;
+use hal_simplicity::Network;
 
-use super::Error;
+use crate::cmd;
 
 pub fn cmd<'a>() -> clap::App<'a, 'a> {
 	cmd::subcommand("info", "Parse a base64-encoded Simplicity program and decode it")
@@ -11,9 +11,12 @@ pub fn cmd<'a>() -> clap::App<'a, 'a> {
 		.args(&[
 			cmd::opt_yaml(),
 			cmd::arg("program", "a Simplicity program in base64").takes_value(true).required(true),
-			cmd::arg("witness", "a hex encoding of all the witness data for the program")
-				.takes_value(true)
-				.required(false),
+			cmd::arg(
+				"witness",
+				"a hex encoding of all the witness data for the program, or a SimplicityHL .wit file",
+			)
+			.takes_value(true)
+			.required(false),
 			cmd::opt(
 				"state",
 				"32-byte state commitment to put alongside the program when generating addresess (hex)",
@@ -21,21 +24,43 @@ pub fn cmd<'a>() -> clap::App<'a, 'a> {
 			.takes_value(true)
 			.short("s")
 			.required(false),
+			cmd::opt_leaf_version(),
+			cmd::opt(
+				"source-map",
+				"map each node of the program to the range of bits it is encoded in",
+			),
 		])
 }
 
 pub fn exec<'a>(matches: &clap::ArgMatches<'a>) {
-	let program = matches.value_of("program").expect("program is mandatory");
-	let witness = matches.value_of("witness");
+	let program = cmd::arg_value(matches, "program").expect("program is mandatory");
+	let witness = cmd::arg_value(matches, "witness");
 	let state = matches.value_of("state");
+	// For LWK, the program's address on the network is the artifact.
+	let artifact = match (cmd::format(matches), cmd::network(matches)) {
+		(cmd::Format::Lwk, Network::Liquid) => "liquid_address_unconf",
+		(cmd::Format::Lwk, Network::LiquidTestnet) => "liquid_testnet_address_unconf",
+		(cmd::Format::Lwk, Network::ElementsRegtest) => {
+			panic!("--format lwk needs the address of the program, which is only given for liquid and liquidtestnet")
+		}
+		_ => "cmr",
+	};
 
-	match hal_simplicity::actions::simplicity::simplicity_info(program, witness, state) {
-		Ok(info) => cmd::print_output(matches, &info),
-		Err(e) => cmd::print_output(
-			matches,
-			&Error {
-				error: format!("{}", e),
-			},
-		),
+	let info = hal_simplicity::actions::simplicity::simplicity_info_with_leaf_version(
+		&program,
+		witness.as_deref(),
+		state,
+		cmd::leaf_version(matches),
+	)
+	.and_then(|mut info| {
+		if matches.is_present("source-map") {
+			info.source_map =
+				Some(hal_simplicity::actions::simplicity::simplicity_source_map(&program)?);
+		}
+		Ok(info)
+	});
+	match info {
+		Ok(info) => cmd::print_artifact(matches, &info, artifact),
+		Err(e) => cmd::fail(matches, e),
 	}
 }
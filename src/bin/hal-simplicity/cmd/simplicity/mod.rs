@@ -1,31 +1,45 @@
 // Copyright 2025 Andrew Poelstra
 // SPDX-License-Identifier: CC0-1.0
 
+mod bench;
+mod check_prune;
+mod export_webide;
+mod gen_fixtures;
+mod hide_branches;
+mod import_webide;
 mod info;
 mod pset;
 mod sighash;
+mod sighashes;
 
 use crate::cmd;
 
-use serde::Serialize;
-
-#[derive(Serialize)]
-struct Error {
-	error: String,
-}
-
 pub fn subcommand<'a>() -> clap::App<'a, 'a> {
 	cmd::subcommand_group("simplicity", "manipulate Simplicity programs")
+		.subcommand(self::bench::cmd())
+		.subcommand(self::check_prune::cmd())
+		.subcommand(self::export_webide::cmd())
+		.subcommand(self::gen_fixtures::cmd())
+		.subcommand(self::hide_branches::cmd())
+		.subcommand(self::import_webide::cmd())
 		.subcommand(self::info::cmd())
 		.subcommand(self::pset::cmd())
 		.subcommand(self::sighash::cmd())
+		.subcommand(self::sighashes::cmd())
 }
 
 pub fn execute<'a>(matches: &clap::ArgMatches<'a>) {
 	match matches.subcommand() {
+		("bench", Some(m)) => self::bench::exec(m),
+		("check-prune", Some(m)) => self::check_prune::exec(m),
+		("export-webide", Some(m)) => self::export_webide::exec(m),
+		("gen-fixtures", Some(m)) => self::gen_fixtures::exec(m),
+		("hide-branches", Some(m)) => self::hide_branches::exec(m),
+		("import-webide", Some(m)) => self::import_webide::exec(m),
 		("info", Some(m)) => self::info::exec(m),
 		("pset", Some(m)) => self::pset::exec(m),
 		("sighash", Some(m)) => self::sighash::exec(m),
+		("sighashes", Some(m)) => self::sighashes::exec(m),
 		(_, _) => unreachable!("clap prints help"),
 	};
 }
@@ -1,11 +1,10 @@
 // Copyright 2025 Andrew Poelstra
 // SPDX-License-Identifier: CC0-1.0
 
-use super::super::Error;
 use crate::cmd;
 
 pub fn cmd<'a>() -> clap::App<'a, 'a> {
-	cmd::subcommand("update-input", "Attach UTXO data to a PSET input")
+	cmd::subcommand("attach-utxo", "Attach UTXO data to a PSET input")
 		.args(&cmd::opts_networks())
 		.args(&[
 			cmd::arg("pset", "PSET to update (base64)").takes_value(true).required(true),
@@ -31,33 +30,30 @@ pub fn cmd<'a>() -> clap::App<'a, 'a> {
 			.takes_value(true)
 			.short("s")
 			.required(false),
+			cmd::opt_leaf_version(),
 			// FIXME add merkle path, needed to compute nontrivial control blocks
 		])
 }
 
 pub fn exec<'a>(matches: &clap::ArgMatches<'a>) {
-	let pset_b64 = matches.value_of("pset").expect("tx mandatory");
-	let input_idx = matches.value_of("input-index").expect("input-idx is mandatory");
+	let pset_b64 = cmd::arg_value(matches, "pset").expect("tx mandatory");
+	let input_idx = cmd::arg_value(matches, "input-index").expect("input-idx is mandatory");
 	let input_utxo = matches.value_of("input-utxo").expect("input-utxois mandatory");
 
 	let internal_key = matches.value_of("internal-key");
 	let cmr = matches.value_of("cmr");
 	let state = matches.value_of("state");
 
-	match hal_simplicity::actions::simplicity::pset::pset_update_input(
-		pset_b64,
-		input_idx,
+	match hal_simplicity::actions::simplicity::pset::pset_update_input_with_leaf_version(
+		&pset_b64,
+		&input_idx,
 		input_utxo,
 		internal_key,
 		cmr,
 		state,
+		cmd::leaf_version(matches),
 	) {
-		Ok(info) => cmd::print_output(matches, &info),
-		Err(e) => cmd::print_output(
-			matches,
-			&Error {
-				error: format!("{}", e),
-			},
-		),
+		Ok(info) => cmd::print_artifact(matches, &info, "pset"),
+		Err(e) => cmd::fail(matches, e),
 	}
 }
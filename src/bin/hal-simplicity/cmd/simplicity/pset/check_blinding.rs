@@ -0,0 +1,25 @@
+// Copyright 2025 Andrew Poelstra
+// SPDX-License-Identifier: CC0-1.0
+
+use crate::cmd;
+
+pub fn cmd<'a>() -> clap::App<'a, 'a> {
+	cmd::subcommand(
+		"check-blinding",
+		"report which PSET outputs are blinded and verify the blinding proofs of counterparties",
+	)
+	.args(&cmd::opts_networks())
+	.args(&[
+		cmd::opt_yaml(),
+		cmd::arg("pset", "PSET to check (base64)").takes_value(true).required(true),
+	])
+}
+
+pub fn exec<'a>(matches: &clap::ArgMatches<'a>) {
+	let pset_b64 = cmd::arg_value(matches, "pset").expect("pset is mandatory");
+
+	match hal_simplicity::actions::simplicity::pset::pset_check_blinding(&pset_b64) {
+		Ok(info) => cmd::print_output(matches, &info),
+		Err(e) => cmd::fail(matches, e),
+	}
+}
@@ -1,7 +1,6 @@
 // Copyright 2025 Andrew Poelstra
 // SPDX-License-Identifier: CC0-1.0
 
-use super::super::Error;
 use crate::cmd;
 
 pub fn cmd<'a>() -> clap::App<'a, 'a> {
@@ -19,16 +18,11 @@ pub fn cmd<'a>() -> clap::App<'a, 'a> {
 }
 
 pub fn exec<'a>(matches: &clap::ArgMatches<'a>) {
-	let inputs_json = matches.value_of("inputs").expect("inputs mandatory");
-	let outputs_json = matches.value_of("outputs").expect("inputs mandatory");
+	let inputs_json = cmd::arg_value(matches, "inputs").expect("inputs mandatory");
+	let outputs_json = cmd::arg_value(matches, "outputs").expect("inputs mandatory");
 
-	match hal_simplicity::actions::simplicity::pset::pset_create(inputs_json, outputs_json) {
-		Ok(info) => cmd::print_output(matches, &info),
-		Err(e) => cmd::print_output(
-			matches,
-			&Error {
-				error: format!("{}", e),
-			},
-		),
+	match hal_simplicity::actions::simplicity::pset::pset_create(&inputs_json, &outputs_json) {
+		Ok(info) => cmd::print_artifact(matches, &info, "pset"),
+		Err(e) => cmd::fail(matches, e),
 	}
 }
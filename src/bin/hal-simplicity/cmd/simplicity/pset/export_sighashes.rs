@@ -0,0 +1,46 @@
+// Copyright 2025 Andrew Poelstra
+// SPDX-License-Identifier: CC0-1.0
+
+use crate::cmd;
+
+pub fn cmd<'a>() -> clap::App<'a, 'a> {
+	cmd::subcommand(
+		"export-sighashes",
+		"Export the digest each Simplicity input of a PSET needs signed, for signing on an air-gapped device",
+	)
+	.args(&cmd::opts_networks())
+	.args(&[
+		cmd::opt_yaml(),
+		cmd::arg("pset", "PSET to sign (base64)").takes_value(true).required(true),
+		cmd::opt(
+			"qr-part-size",
+			"also split the export into BBQr parts of at most this many characters, to show as QR codes",
+		)
+		.value_name("CHARS")
+		.takes_value(true)
+		.required(false),
+		cmd::opt_genesis_hash(),
+	])
+	.after_help(
+		"Prints the input index, leaf hash, sighash and signers of each Simplicity leaf of each input \
+			as compact JSON, as hwi-export does, and with --qr-part-size the same JSON as base32 BBQr \
+			parts. Give the signatures made from it to import-signatures.",
+	)
+}
+
+pub fn exec<'a>(matches: &clap::ArgMatches<'a>) {
+	let pset_b64 = cmd::arg_value(matches, "pset").expect("pset is mandatory");
+	let part_size = matches
+		.value_of("qr-part-size")
+		.map(|n| n.parse().unwrap_or_else(|e| panic!("invalid qr-part-size: {}", e)));
+	let genesis_hash = cmd::genesis_hash(matches);
+
+	match hal_simplicity::actions::simplicity::pset::pset_export_sighashes(
+		&pset_b64,
+		Some(&genesis_hash),
+		part_size,
+	) {
+		Ok(info) => cmd::print_artifact(matches, &info, "export"),
+		Err(e) => cmd::fail(matches, e),
+	}
+}
@@ -1,7 +1,6 @@
 // Copyright 2025 Andrew Poelstra
 // SPDX-License-Identifier: CC0-1.0
 
-use super::super::Error;
 use crate::cmd;
 
 pub fn cmd<'a>() -> clap::App<'a, 'a> {
@@ -11,14 +10,9 @@ pub fn cmd<'a>() -> clap::App<'a, 'a> {
 }
 
 pub fn exec<'a>(matches: &clap::ArgMatches<'a>) {
-	let pset_b64 = matches.value_of("pset").expect("tx mandatory");
-	match hal_simplicity::actions::simplicity::pset::pset_extract(pset_b64) {
+	let pset_b64 = cmd::arg_value(matches, "pset").expect("tx mandatory");
+	match hal_simplicity::actions::simplicity::pset::pset_extract(&pset_b64) {
 		Ok(info) => cmd::print_output(matches, &info),
-		Err(e) => cmd::print_output(
-			matches,
-			&Error {
-				error: format!("{}", e),
-			},
-		),
+		Err(e) => cmd::fail(matches, e),
 	}
 }
@@ -1,7 +1,6 @@
 // Copyright 2025 Andrew Poelstra
 // SPDX-License-Identifier: CC0-1.0
 
-use super::super::Error;
 use crate::cmd;
 
 pub fn cmd<'a>() -> clap::App<'a, 'a> {
@@ -13,38 +12,30 @@ pub fn cmd<'a>() -> clap::App<'a, 'a> {
 				.takes_value(true)
 				.required(true),
 			cmd::arg("program", "Simplicity program (base64)").takes_value(true).required(true),
-			cmd::arg("witness", "Simplicity program witness (hex)")
+			cmd::arg("witness", "Simplicity program witness (hex), or a SimplicityHL .wit file")
 				.takes_value(true)
 				.required(true),
-			cmd::opt(
-				"genesis-hash",
-				"genesis hash of the blockchain the transaction belongs to (hex)",
-			)
-			.short("g")
-			.required(false),
+			cmd::opt_genesis_hash(),
+			cmd::opt_leaf_version(),
 		])
 }
 
 pub fn exec<'a>(matches: &clap::ArgMatches<'a>) {
-	let pset_b64 = matches.value_of("pset").expect("tx mandatory");
-	let input_idx = matches.value_of("input-index").expect("input-idx is mandatory");
-	let program = matches.value_of("program").expect("program is mandatory");
-	let witness = matches.value_of("witness").expect("witness is mandatory");
-	let genesis_hash = matches.value_of("genesis-hash");
+	let pset_b64 = cmd::arg_value(matches, "pset").expect("tx mandatory");
+	let input_idx = cmd::arg_value(matches, "input-index").expect("input-idx is mandatory");
+	let program = cmd::arg_value(matches, "program").expect("program is mandatory");
+	let witness = cmd::arg_value(matches, "witness").expect("witness is mandatory");
+	let genesis_hash = cmd::genesis_hash(matches);
 
-	match hal_simplicity::actions::simplicity::pset::pset_finalize(
-		pset_b64,
-		input_idx,
-		program,
-		witness,
-		genesis_hash,
+	match hal_simplicity::actions::simplicity::pset::pset_finalize_with_leaf_version(
+		&pset_b64,
+		&input_idx,
+		&program,
+		&witness,
+		Some(&genesis_hash),
+		cmd::leaf_version(matches),
 	) {
-		Ok(info) => cmd::print_output(matches, &info),
-		Err(e) => cmd::print_output(
-			matches,
-			&Error {
-				error: format!("{}", e),
-			},
-		),
+		Ok(info) => cmd::print_artifact(matches, &info, "pset"),
+		Err(e) => cmd::fail(matches, e),
 	}
 }
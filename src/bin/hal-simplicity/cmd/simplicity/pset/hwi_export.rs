@@ -0,0 +1,33 @@
+// Copyright 2025 Andrew Poelstra
+// SPDX-License-Identifier: CC0-1.0
+
+use crate::cmd;
+
+pub fn cmd<'a>() -> clap::App<'a, 'a> {
+	cmd::subcommand(
+		"hwi-export",
+		"List the digest each Simplicity input of a PSET needs signed, for signing on a hardware wallet",
+	)
+	.args(&cmd::opts_networks())
+	.args(&[
+		cmd::arg("pset", "PSET to sign (base64)").takes_value(true).required(true),
+		cmd::opt_genesis_hash(),
+	])
+	.after_help(
+		"For each Simplicity leaf of each input, prints the input index, the leaf's CMR and leaf hash, \
+			the sighash to sign, and the signers: the keys whose PSET key origins name the leaf, with the \
+			master key fingerprint and derivation path to pass to HWI. Sign each sighash with BIP-0340 \
+			Schnorr and give the signatures to hwi-import.",
+	)
+}
+
+pub fn exec<'a>(matches: &clap::ArgMatches<'a>) {
+	let pset_b64 = cmd::arg_value(matches, "pset").expect("pset is mandatory");
+	let genesis_hash = cmd::genesis_hash(matches);
+
+	match hal_simplicity::actions::simplicity::pset::pset_sighashes(&pset_b64, Some(&genesis_hash))
+	{
+		Ok(info) => cmd::print_output(matches, &info),
+		Err(e) => cmd::fail(matches, e),
+	}
+}
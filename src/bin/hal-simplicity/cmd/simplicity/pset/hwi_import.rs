@@ -0,0 +1,42 @@
+// Copyright 2025 Andrew Poelstra
+// SPDX-License-Identifier: CC0-1.0
+
+use crate::cmd;
+
+pub fn cmd<'a>() -> clap::App<'a, 'a> {
+	cmd::subcommand(
+		"hwi-import",
+		"Check signatures from a hardware wallet and add them to the Simplicity inputs of a PSET",
+	)
+	.args(&cmd::opts_networks())
+	.args(&[
+		cmd::arg("pset", "PSET to update (base64)").takes_value(true).required(true),
+		cmd::arg(
+			"signatures",
+			"signatures as a JSON list of {\"input\": n, \"pubkey\": <hex>, \"signature\": <hex>}",
+		)
+		.takes_value(true)
+		.required(true),
+		cmd::opt_genesis_hash(),
+	])
+	.after_help(
+		"Each signature is checked against the sighash of its input, as listed by hwi-export, and \
+			stored in the input's tap_script_sigs under its public key and leaf hash. If an input has \
+			several Simplicity leaves, a signature must also give the \"leaf_hash\" it is for.",
+	)
+}
+
+pub fn exec<'a>(matches: &clap::ArgMatches<'a>) {
+	let pset_b64 = cmd::arg_value(matches, "pset").expect("pset is mandatory");
+	let signatures = cmd::arg_value(matches, "signatures").expect("signatures is mandatory");
+	let genesis_hash = cmd::genesis_hash(matches);
+
+	match hal_simplicity::actions::simplicity::pset::pset_add_signatures(
+		&pset_b64,
+		&signatures,
+		Some(&genesis_hash),
+	) {
+		Ok(info) => cmd::print_artifact(matches, &info, "pset"),
+		Err(e) => cmd::fail(matches, e),
+	}
+}
@@ -0,0 +1,40 @@
+// Copyright 2025 Andrew Poelstra
+// SPDX-License-Identifier: CC0-1.0
+
+use crate::cmd;
+
+pub fn cmd<'a>() -> clap::App<'a, 'a> {
+	cmd::subcommand(
+		"import-signatures",
+		"Check signatures from an air-gapped device and add them to the Simplicity inputs of a PSET",
+	)
+	.args(&cmd::opts_networks())
+	.args(&[
+		cmd::opt_yaml(),
+		cmd::arg("pset", "PSET to update (base64)").takes_value(true).required(true),
+		cmd::arg(
+			"signatures",
+			"signatures as a JSON list, as hwi-import takes them, or the BBQr parts of one in any order",
+		)
+		.takes_value(true)
+		.multiple(true)
+		.required(true),
+		cmd::opt_genesis_hash(),
+	])
+}
+
+pub fn exec<'a>(matches: &clap::ArgMatches<'a>) {
+	let pset_b64 = cmd::arg_value(matches, "pset").expect("pset is mandatory");
+	let signatures: Vec<_> =
+		matches.values_of("signatures").expect("signatures is mandatory").collect();
+	let genesis_hash = cmd::genesis_hash(matches);
+
+	match hal_simplicity::actions::simplicity::pset::pset_import_signatures(
+		&pset_b64,
+		&signatures,
+		Some(&genesis_hash),
+	) {
+		Ok(info) => cmd::print_artifact(matches, &info, "pset"),
+		Err(e) => cmd::fail(matches, e),
+	}
+}
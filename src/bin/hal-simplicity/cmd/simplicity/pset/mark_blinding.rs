@@ -0,0 +1,42 @@
+// Copyright 2025 Andrew Poelstra
+// SPDX-License-Identifier: CC0-1.0
+
+use crate::cmd;
+
+pub fn cmd<'a>() -> clap::App<'a, 'a> {
+	cmd::subcommand(
+		"mark-blinding",
+		"mark a PSET output to be blinded by the owner of one of its inputs",
+	)
+	.args(&cmd::opts_networks())
+	.args(&[
+		cmd::opt_yaml(),
+		cmd::arg("pset", "PSET to update (base64)").takes_value(true).required(true),
+		cmd::arg("output-index", "the index of the output to blind (decimal)")
+			.takes_value(true)
+			.required(true),
+		cmd::arg("blinding-key", "the public blinding key of the output's recipient (hex)")
+			.takes_value(true)
+			.required(true),
+		cmd::arg("blinder-index", "the index of the input whose owner blinds the output (decimal)")
+			.takes_value(true)
+			.required(true),
+	])
+}
+
+pub fn exec<'a>(matches: &clap::ArgMatches<'a>) {
+	let pset_b64 = cmd::arg_value(matches, "pset").expect("pset is mandatory");
+	let output_idx = cmd::arg_value(matches, "output-index").expect("output-index is mandatory");
+	let blinding_key = cmd::arg_value(matches, "blinding-key").expect("blinding-key is mandatory");
+	let blinder_idx = cmd::arg_value(matches, "blinder-index").expect("blinder-index is mandatory");
+
+	match hal_simplicity::actions::simplicity::pset::pset_mark_blinding(
+		&pset_b64,
+		&output_idx,
+		&blinding_key,
+		&blinder_idx,
+	) {
+		Ok(info) => cmd::print_artifact(matches, &info, "pset"),
+		Err(e) => cmd::fail(matches, e),
+	}
+}
@@ -1,30 +1,51 @@
 // Copyright 2025 Andrew Poelstra
 // SPDX-License-Identifier: CC0-1.0
 
+mod attach_utxo;
+mod check_blinding;
 mod create;
+mod export_sighashes;
 mod extract;
 mod finalize;
+mod hwi_export;
+mod hwi_import;
+mod import_signatures;
+mod mark_blinding;
 mod run;
-mod update_input;
+mod run_diff;
 
 use crate::cmd;
 
 pub fn cmd<'a>() -> clap::App<'a, 'a> {
 	cmd::subcommand_group("pset", "manipulate PSETs for spending from Simplicity programs")
+		.subcommand(self::attach_utxo::cmd())
+		.subcommand(self::check_blinding::cmd())
 		.subcommand(self::create::cmd())
+		.subcommand(self::export_sighashes::cmd())
 		.subcommand(self::extract::cmd())
 		.subcommand(self::finalize::cmd())
+		.subcommand(self::hwi_export::cmd())
+		.subcommand(self::hwi_import::cmd())
+		.subcommand(self::import_signatures::cmd())
+		.subcommand(self::mark_blinding::cmd())
 		.subcommand(self::run::cmd())
-		.subcommand(self::update_input::cmd())
+		.subcommand(self::run_diff::cmd())
 }
 
 pub fn exec<'a>(matches: &clap::ArgMatches<'a>) {
 	match matches.subcommand() {
+		("attach-utxo", Some(m)) => self::attach_utxo::exec(m),
+		("check-blinding", Some(m)) => self::check_blinding::exec(m),
 		("create", Some(m)) => self::create::exec(m),
+		("export-sighashes", Some(m)) => self::export_sighashes::exec(m),
 		("extract", Some(m)) => self::extract::exec(m),
 		("finalize", Some(m)) => self::finalize::exec(m),
+		("hwi-export", Some(m)) => self::hwi_export::exec(m),
+		("hwi-import", Some(m)) => self::hwi_import::exec(m),
+		("import-signatures", Some(m)) => self::import_signatures::exec(m),
+		("mark-blinding", Some(m)) => self::mark_blinding::exec(m),
 		("run", Some(m)) => self::run::exec(m),
-		("update-input", Some(m)) => self::update_input::exec(m),
+		("run-diff", Some(m)) => self::run_diff::exec(m),
 		(_, _) => unreachable!("clap prints help"),
 	};
 }
@@ -1,7 +1,10 @@
 // Copyright 2025 Andrew Poelstra
 // SPDX-License-Identifier: CC0-1.0
 
-use super::super::Error;
+use std::fs::File;
+use std::io::{self, BufWriter, Write};
+use std::ops::ControlFlow;
+
 use crate::cmd;
 
 pub fn cmd<'a>() -> clap::App<'a, 'a> {
@@ -13,38 +16,71 @@ pub fn cmd<'a>() -> clap::App<'a, 'a> {
 				.takes_value(true)
 				.required(true),
 			cmd::arg("program", "Simplicity program (base64)").takes_value(true).required(true),
-			cmd::arg("witness", "Simplicity program witness (hex)")
+			cmd::arg("witness", "Simplicity program witness (hex), or a SimplicityHL .wit file")
 				.takes_value(true)
 				.required(true),
+			cmd::opt_genesis_hash(),
+			cmd::opt(
+				"max-jets",
+				"keep only the first and last N jet calls in the output, with the number of calls of each jet (default 1000)",
+			)
+			.value_name("N")
+			.takes_value(true),
+			cmd::opt("all-jets", "keep every jet call in the output, however many")
+				.conflicts_with("max-jets"),
 			cmd::opt(
-				"genesis-hash",
-				"genesis hash of the blockchain the transaction belongs to (hex)",
+				"jets-file",
+				"write each jet call to FILE as a line of JSON, instead of into the output",
 			)
-			.short("g")
-			.required(false),
+			.value_name("FILE")
+			.takes_value(true),
 		])
 }
 
 pub fn exec<'a>(matches: &clap::ArgMatches<'a>) {
-	let pset_b64 = matches.value_of("pset").expect("tx mandatory");
-	let input_idx = matches.value_of("input-index").expect("input-idx is mandatory");
-	let program = matches.value_of("program").expect("program is mandatory");
-	let witness = matches.value_of("witness").expect("witness is mandatory");
-	let genesis_hash = matches.value_of("genesis-hash");
+	let pset_b64 = cmd::arg_value(matches, "pset").expect("tx mandatory");
+	let input_idx = cmd::arg_value(matches, "input-index").expect("input-idx is mandatory");
+	let program = cmd::arg_value(matches, "program").expect("program is mandatory");
+	let witness = cmd::arg_value(matches, "witness").expect("witness is mandatory");
+	let genesis_hash = cmd::genesis_hash(matches);
+	let max_jets = matches
+		.value_of("max-jets")
+		.map(|n| n.parse().unwrap_or_else(|e| panic!("invalid max-jets: {}", e)));
+	let mut jets_file = matches.value_of("jets-file").map(|path| {
+		let file = File::create(path)
+			.unwrap_or_else(|e| panic!("cannot create jets file {}: {}", path, e));
+		(path, BufWriter::new(file))
+	});
+	let max_jets = match (matches.is_present("all-jets"), max_jets) {
+		(true, _) => None,
+		(false, Some(n)) => Some(n),
+		// The calls are in the file, so only their counts are kept.
+		(false, None) if jets_file.is_some() => Some(0),
+		(false, None) => Some(hal_simplicity::actions::simplicity::pset::DEFAULT_MAX_JETS),
+	};
 
-	match hal_simplicity::actions::simplicity::pset::pset_run(
-		pset_b64,
-		input_idx,
-		program,
-		witness,
-		genesis_hash,
-	) {
+	let result = hal_simplicity::actions::simplicity::pset::pset_run_with_callback(
+		&pset_b64,
+		&input_idx,
+		&program,
+		&witness,
+		Some(&genesis_hash),
+		max_jets,
+		|jet| {
+			if let Some((path, ref mut file)) = jets_file {
+				serde_json::to_writer(&mut *file, jet)
+					.map_err(io::Error::from)
+					.and_then(|_| writeln!(file))
+					.unwrap_or_else(|e| panic!("cannot write to jets file {}: {}", path, e));
+			}
+			ControlFlow::Continue(())
+		},
+	);
+	if let Some((path, mut file)) = jets_file {
+		file.flush().unwrap_or_else(|e| panic!("cannot write to jets file {}: {}", path, e));
+	}
+	match result {
 		Ok(info) => cmd::print_output(matches, &info),
-		Err(e) => cmd::print_output(
-			matches,
-			&Error {
-				error: format!("{}", e),
-			},
-		),
+		Err(e) => cmd::fail(matches, e),
 	}
 }
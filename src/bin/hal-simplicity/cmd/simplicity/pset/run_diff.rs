@@ -0,0 +1,51 @@
+// Copyright 2025 Andrew Poelstra
+// SPDX-License-Identifier: CC0-1.0
+
+use crate::cmd;
+
+pub fn cmd<'a>() -> clap::App<'a, 'a> {
+	cmd::subcommand(
+		"run-diff",
+		"Run a Simplicity program with two witnesses in the context of a PSET input, finding where the runs differ",
+	)
+	.args(&cmd::opts_networks())
+	.args(&[
+		cmd::opt_yaml(),
+		cmd::arg("pset", "PSET whose input the program spends (base64)")
+			.takes_value(true)
+			.required(true),
+		cmd::arg("input-index", "the index of the input the program spends (decimal)")
+			.takes_value(true)
+			.required(true),
+		cmd::arg("program", "Simplicity program (base64)").takes_value(true).required(true),
+		cmd::arg("witness", "Simplicity program witness (hex), or a SimplicityHL .wit file")
+			.takes_value(true)
+			.required(true),
+		cmd::arg("other-witness", "the witness to compare against, in the same forms")
+			.takes_value(true)
+			.required(true),
+		cmd::opt_genesis_hash(),
+	])
+}
+
+pub fn exec<'a>(matches: &clap::ArgMatches<'a>) {
+	let pset_b64 = cmd::arg_value(matches, "pset").expect("pset is mandatory");
+	let input_idx = cmd::arg_value(matches, "input-index").expect("input-index is mandatory");
+	let program = cmd::arg_value(matches, "program").expect("program is mandatory");
+	let witness = cmd::arg_value(matches, "witness").expect("witness is mandatory");
+	let other_witness =
+		cmd::arg_value(matches, "other-witness").expect("other-witness is mandatory");
+	let genesis_hash = cmd::genesis_hash(matches);
+
+	match hal_simplicity::actions::simplicity::pset::pset_run_diff(
+		&pset_b64,
+		&input_idx,
+		&program,
+		&witness,
+		&other_witness,
+		Some(&genesis_hash),
+	) {
+		Ok(info) => cmd::print_output(matches, &info),
+		Err(e) => cmd::fail(matches, e),
+	}
+}
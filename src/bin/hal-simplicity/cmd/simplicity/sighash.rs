@@ -3,8 +3,6 @@
 
 use crate::cmd;
 
-use super::Error;
-
 pub fn cmd<'a>() -> clap::App<'a, 'a> {
 	cmd::subcommand("sighash", "Compute signature hashes or signatures for use with Simplicity")
 		.args(&cmd::opts_networks())
@@ -18,10 +16,8 @@ pub fn cmd<'a>() -> clap::App<'a, 'a> {
 			cmd::arg("control-block", "Taproot control block of the input program (hex)")
 				.takes_value(true)
 				.required(false),
-			cmd::opt("genesis-hash", "genesis hash of the blockchain the transaction belongs to (hex)")
-				.short("g")
-				.required(false),
-			cmd::opt("secret-key", "secret key to sign the transaction with (hex)")
+			cmd::opt_genesis_hash(),
+			cmd::opt("secret-key", "secret key to sign the transaction with (hex), or prompt to type it in, or env:<VAR> to read it from $VAR")
 				.short("x")
 				.takes_value(true)
 				.required(false),
@@ -42,33 +38,35 @@ pub fn cmd<'a>() -> clap::App<'a, 'a> {
 }
 
 pub fn exec<'a>(matches: &clap::ArgMatches<'a>) {
-	let tx_hex = matches.value_of("tx").expect("tx mandatory");
-	let input_idx = matches.value_of("input-index").expect("input-idx is mandatory");
-	let cmr = matches.value_of("cmr").expect("cmr is mandatory");
-	let control_block = matches.value_of("control-block");
-	let genesis_hash = matches.value_of("genesis-hash");
-	let secret_key = matches.value_of("secret-key");
+	let tx_hex = cmd::arg_value(matches, "tx").expect("tx mandatory");
+	let input_idx = cmd::arg_value(matches, "input-index").expect("input-idx is mandatory");
+	let cmr = cmd::arg_value(matches, "cmr").expect("cmr is mandatory");
+	let control_block = cmd::arg_value(matches, "control-block");
+	let genesis_hash = cmd::genesis_hash(matches);
+	let secret_key = cmd::secret_value(matches, "secret-key");
 	let public_key = matches.value_of("public-key");
 	let signature = matches.value_of("signature");
 	let input_utxos: Option<Vec<_>> = matches.values_of("input-utxo").map(|vals| vals.collect());
 
 	match hal_simplicity::actions::simplicity::simplicity_sighash(
-		tx_hex,
-		input_idx,
-		cmr,
-		control_block,
-		genesis_hash,
-		secret_key,
+		&tx_hex,
+		&input_idx,
+		&cmr,
+		control_block.as_deref(),
+		Some(&genesis_hash),
+		secret_key.as_deref(),
 		public_key,
 		signature,
 		input_utxos.as_deref(),
 	) {
-		Ok(info) => cmd::print_output(matches, &info),
-		Err(e) => cmd::print_output(
-			matches,
-			&Error {
-				error: format!("{}", e),
-			},
-		),
+		Ok(info) => {
+			let artifact = if secret_key.is_some() {
+				"signature"
+			} else {
+				"sighash"
+			};
+			cmd::print_artifact(matches, &info, artifact)
+		}
+		Err(e) => cmd::fail(matches, e),
 	}
 }
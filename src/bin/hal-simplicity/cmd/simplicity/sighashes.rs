@@ -0,0 +1,40 @@
+// Copyright 2025 Andrew Poelstra
+// SPDX-License-Identifier: CC0-1.0
+
+use crate::cmd;
+
+pub fn cmd<'a>() -> clap::App<'a, 'a> {
+	cmd::subcommand("sighashes", "Compute the signature hashes of every input of a transaction")
+		.args(&cmd::opts_networks())
+		.args(&[
+			cmd::opt_yaml(),
+			cmd::arg("tx", "transaction to sign (hex or PSET)").takes_value(true).required(true),
+			cmd::opt_genesis_hash(),
+			cmd::opt("input-utxo", "an input UTXO, as for sighash (default: the PSET's)")
+				.value_name("UTXO")
+				.short("i")
+				.multiple(true)
+				.number_of_values(1)
+				.required(false),
+		])
+		.after_help(
+			"Lists the Simplicity sighash of each Simplicity leaf of each input, taken from the \
+				PSET or from the input's witness, and the key spend sighash of each input spending \
+				a taproot output.",
+		)
+}
+
+pub fn exec<'a>(matches: &clap::ArgMatches<'a>) {
+	let tx_hex = cmd::arg_value(matches, "tx").expect("tx mandatory");
+	let genesis_hash = cmd::genesis_hash(matches);
+	let input_utxos: Option<Vec<_>> = matches.values_of("input-utxo").map(|vals| vals.collect());
+
+	match hal_simplicity::actions::simplicity::simplicity_sighashes(
+		&tx_hex,
+		Some(&genesis_hash),
+		input_utxos.as_deref(),
+	) {
+		Ok(info) => cmd::print_output(matches, &info),
+		Err(e) => cmd::fail(matches, e),
+	}
+}
@@ -4,25 +4,103 @@ use clap;
 use elements::encode::serialize;
 
 use crate::cmd;
+#[cfg(feature = "daemon")]
+use crate::cmd::daemon;
+use hal_simplicity::actions::tx::TxError;
 use hal_simplicity::tx::TransactionInfo;
 
 pub fn subcommand<'a>() -> clap::App<'a, 'a> {
-	cmd::subcommand_group("tx", "manipulate transactions")
-		.subcommand(cmd_create())
-		.subcommand(cmd_decode())
+	cmd::subcommand_group("tx", "manipulate transactions").subcommands(vec![
+		cmd_asset_id(),
+		cmd_create(),
+		cmd_decode(),
+		#[cfg(feature = "daemon")]
+		cmd_fetch(),
+		cmd_output_nonce(),
+		cmd_rewind(),
+		cmd_verify(),
+	])
 }
 
 pub fn execute<'a>(matches: &clap::ArgMatches<'a>) {
 	match matches.subcommand() {
+		("asset-id", Some(m)) => exec_asset_id(m),
 		("create", Some(m)) => exec_create(m),
 		("decode", Some(m)) => exec_decode(m),
+		#[cfg(feature = "daemon")]
+		("fetch", Some(m)) => exec_fetch(m),
+		("output-nonce", Some(m)) => exec_output_nonce(m),
+		("rewind", Some(m)) => exec_rewind(m),
+		("verify", Some(m)) => exec_verify(m),
 		(_, _) => unreachable!("clap prints help"),
 	};
 }
 
+fn cmd_asset_id<'a>() -> clap::App<'a, 'a> {
+	cmd::subcommand("asset-id", "compute the asset and reissuance token IDs of an issuance").args(
+		&[
+			cmd::opt_yaml(),
+			cmd::opt("prevout", "the outpoint spent by the issuing input (<txid>:<vout>)")
+				.short("p")
+				.takes_value(true)
+				.required(false),
+			cmd::opt(
+				"contract-hash",
+				"the hash of the issuance contract (hex) (default: all zeros)",
+			)
+			.short("c")
+			.takes_value(true)
+			.required(false),
+			cmd::opt("contract", "the issuance contract, to hash (JSON)")
+				.takes_value(true)
+				.required(false),
+			cmd::opt(
+				"entropy",
+				"the asset entropy of a reissuance, instead of the prevout and contract (hex)",
+			)
+			.short("e")
+			.takes_value(true)
+			.required(false),
+			cmd::opt(
+				"confidential",
+				"the issued amount is blinded, which changes the reissuance token ID",
+			)
+			.required(false),
+			cmd::opt("asset", "an asset ID to check the computed one against (hex)")
+				.short("a")
+				.takes_value(true)
+				.required(false),
+		],
+	)
+}
+
+fn exec_asset_id<'a>(matches: &clap::ArgMatches<'a>) {
+	match hal_simplicity::actions::tx::tx_asset_id(
+		matches.value_of("prevout"),
+		matches.value_of("contract-hash"),
+		matches.value_of("contract"),
+		matches.value_of("entropy"),
+		matches.is_present("confidential"),
+		matches.value_of("asset"),
+	) {
+		Ok(info) => cmd::print_artifact(matches, &info, "asset_id"),
+		Err(e) => cmd::fail(matches, e),
+	}
+}
+
 fn cmd_create<'a>() -> clap::App<'a, 'a> {
 	cmd::subcommand("create", "create a raw transaction from JSON").args(&[
 		cmd::arg("tx-info", "the transaction info in JSON").required(false),
+		cmd::opt("inputs", "input outpoints, as for `pset create`, instead of tx-info (JSON)")
+			.short("i")
+			.takes_value(true)
+			.requires("outputs")
+			.conflicts_with("tx-info"),
+		cmd::opt("outputs", "outputs, as for `pset create`, instead of tx-info (JSON)")
+			.short("o")
+			.takes_value(true)
+			.requires("inputs")
+			.conflicts_with("tx-info"),
 		cmd::opt("raw-stdout", "output the raw bytes of the result to stdout")
 			.short("r")
 			.required(false),
@@ -30,10 +108,15 @@ fn cmd_create<'a>() -> clap::App<'a, 'a> {
 }
 
 fn exec_create<'a>(matches: &clap::ArgMatches<'a>) {
-	let info = serde_json::from_str::<TransactionInfo>(&cmd::arg_or_stdin(matches, "tx-info"))
-		.unwrap_or_else(|e| panic!("invalid JSON provided: {}", e));
-
-	let tx = hal_simplicity::actions::tx::tx_create(info).unwrap_or_else(|e| panic!("{}", e));
+	let tx = match (matches.value_of("inputs"), matches.value_of("outputs")) {
+		(Some(inputs), Some(outputs)) => {
+			hal_simplicity::actions::tx::tx_create_simple(inputs, outputs)
+		}
+		_ => serde_json::from_str::<TransactionInfo>(&cmd::arg_or_stdin(matches, "tx-info"))
+			.map_err(TxError::JsonParse)
+			.and_then(hal_simplicity::actions::tx::tx_create),
+	}
+	.unwrap_or_else(|e| cmd::fail(matches, e));
 
 	let tx_bytes = serialize(&tx);
 	if matches.is_present("raw-stdout") {
@@ -44,17 +127,154 @@ fn exec_create<'a>(matches: &clap::ArgMatches<'a>) {
 }
 
 fn cmd_decode<'a>() -> clap::App<'a, 'a> {
-	cmd::subcommand("decode", "decode a raw transaction to JSON")
-		.args(&cmd::opts_networks())
-		.args(&[cmd::opt_yaml(), cmd::arg("raw-tx", "the raw transaction in hex").required(false)])
+	cmd::subcommand("decode", "decode a raw transaction to JSON").args(&cmd::opts_networks()).args(
+		&[
+			cmd::opt_yaml(),
+			cmd::opt_field(),
+			#[cfg(feature = "registry")]
+			cmd::opt_asset_registry(),
+			cmd::opt(
+				"header",
+				"the mainchain block header to check the proofs of pegins against (hex)",
+			)
+			.takes_value(true)
+			.required(false),
+			cmd::arg("raw-tx", "the raw transaction in hex").required(false),
+		],
+	)
 }
 
 fn exec_decode<'a>(matches: &clap::ArgMatches<'a>) {
 	let hex_tx = cmd::arg_or_stdin(matches, "raw-tx");
 	let network = cmd::network(matches);
+	let headers: Vec<&str> = matches.value_of("header").into_iter().collect();
+
+	let info = hal_simplicity::actions::tx::tx_decode_with_mainchain_headers(
+		hex_tx.as_ref(),
+		network,
+		&headers,
+	)
+	.unwrap_or_else(|e| cmd::fail(matches, e));
+	#[cfg(feature = "registry")]
+	let info = cmd::resolve_assets(matches, info);
+
+	cmd::print_artifact(matches, &info, "txid")
+}
+
+#[cfg(feature = "daemon")]
+fn cmd_fetch<'a>() -> clap::App<'a, 'a> {
+	cmd::subcommand("fetch", "fetch a raw transaction from the daemon's chain backend")
+		.args(&cmd::opts_networks())
+		.args(&daemon::opts_auth())
+		.args(&[
+			daemon::opt_daemon_url(),
+			daemon::opt_backend(),
+			cmd::opt_yaml(),
+			cmd::opt_field(),
+			cmd::opt("decode", "decode the transaction to JSON, as tx decode does"),
+			cmd::arg("txid", "the ID of the transaction").required(true),
+		])
+}
+
+#[cfg(feature = "daemon")]
+fn exec_fetch<'a>(matches: &clap::ArgMatches<'a>) {
+	use hal_simplicity::daemon::types::TxFetchRequest;
+
+	let txid = matches.value_of("txid").expect("txid is mandatory");
+	let request = TxFetchRequest {
+		txid: txid.parse().unwrap_or_else(|e| panic!("invalid txid {}: {}", txid, e)),
+		backend: daemon::backend(matches),
+	};
+	let raw_tx = daemon::connect(matches)
+		.tx_fetch(&request)
+		.unwrap_or_else(|e| daemon::fail_call(matches, e))
+		.raw_tx;
+	if !matches.is_present("decode") {
+		return print!("{}", raw_tx);
+	}
+
+	let info = hal_simplicity::actions::tx::tx_decode(&raw_tx, cmd::network(matches))
+		.unwrap_or_else(|e| cmd::fail(matches, e));
+	cmd::print_artifact(matches, &info, "txid")
+}
+
+fn cmd_output_nonce<'a>() -> clap::App<'a, 'a> {
+	cmd::subcommand("output-nonce", "compute the nonce of a blinded output by ECDH").args(&[
+		cmd::opt_yaml(),
+		cmd::arg("secret-key", "the blinding key, or the sender's ephemeral key (hex), or prompt to type it in, or env:<VAR> to read it from $VAR")
+			.takes_value(true)
+			.required(true),
+		cmd::arg("public-key", "the output's nonce commitment, or the blinding pubkey (hex)")
+			.takes_value(true)
+			.required(true),
+	])
+}
+
+fn exec_output_nonce<'a>(matches: &clap::ArgMatches<'a>) {
+	let secret_key = cmd::secret_value(matches, "secret-key").expect("secret-key is mandatory");
+	let public_key = cmd::arg_value(matches, "public-key").expect("public-key is mandatory");
+
+	match hal_simplicity::actions::tx::tx_output_nonce(&secret_key, &public_key) {
+		Ok(info) => cmd::print_artifact(matches, &info, "nonce"),
+		Err(e) => cmd::fail(matches, e),
+	}
+}
+
+fn cmd_rewind<'a>() -> clap::App<'a, 'a> {
+	cmd::subcommand("rewind", "rewind the rangeproof of a blinded output with its nonce").args(&[
+		cmd::opt_yaml(),
+		cmd::opt_field(),
+		cmd::arg("raw-tx", "the raw transaction in hex").takes_value(true).required(true),
+		cmd::arg("output-index", "the index of the blinded output (decimal)")
+			.takes_value(true)
+			.required(true),
+		cmd::arg("nonce", "the output's nonce, as given by output-nonce (hex)")
+			.takes_value(true)
+			.required(true),
+	])
+}
+
+fn exec_rewind<'a>(matches: &clap::ArgMatches<'a>) {
+	let raw_tx = cmd::arg_value(matches, "raw-tx").expect("raw-tx is mandatory");
+	let output_idx = cmd::arg_value(matches, "output-index").expect("output-index is mandatory");
+	let nonce = cmd::arg_value(matches, "nonce").expect("nonce is mandatory");
 
-	let info = hal_simplicity::actions::tx::tx_decode(hex_tx.as_ref(), network)
-		.unwrap_or_else(|e| panic!("{}", e));
+	match hal_simplicity::actions::tx::tx_rewind_output(&raw_tx, &output_idx, &nonce) {
+		Ok(info) => cmd::print_output(matches, &info),
+		Err(e) => cmd::fail(matches, e),
+	}
+}
 
-	cmd::print_output(matches, &info)
+fn cmd_verify<'a>() -> clap::App<'a, 'a> {
+	cmd::subcommand("verify", "check the witness of every input of a signed transaction")
+		.args(&cmd::opts_networks())
+		.args(&[
+			cmd::opt_yaml(),
+			cmd::opt_genesis_hash(),
+			cmd::opt(
+				"input-utxo",
+				"a spent output, as for simplicity sighash (default: the PSET's)",
+			)
+			.value_name("UTXO")
+			.short("i")
+			.multiple(true)
+			.number_of_values(1)
+			.required(false),
+			cmd::arg("tx", "the raw transaction in hex, or a finalized PSET").required(false),
+		])
+}
+
+fn exec_verify<'a>(matches: &clap::ArgMatches<'a>) {
+	let tx = cmd::arg_or_stdin(matches, "tx");
+	let genesis_hash = cmd::genesis_hash(matches);
+	let input_utxos: Option<Vec<_>> = matches.values_of("input-utxo").map(|vals| vals.collect());
+
+	match hal_simplicity::actions::tx::tx_verify(
+		tx.as_ref(),
+		input_utxos.as_deref(),
+		Some(&genesis_hash),
+	) {
+		Ok(info) => cmd::print_artifact(matches, &info, "valid"),
+		Err(e) => cmd::fail(matches, e),
+	}
 }
@@ -28,20 +28,44 @@ fn init_app<'a, 'b>() -> clap::App<'a, 'b> {
 		.setting(clap::AppSettings::SubcommandRequiredElseHelp)
 		.setting(clap::AppSettings::AllArgsOverrideSelf)
 		.subcommands(cmd::subcommands())
+		.after_help(
+			"Any positional argument may be given as @<path> to read it from a file, \
+			or as - to read it from stdin.",
+		)
 		.arg(
 			cmd::opt("verbose", "print verbose logging output to stderr")
 				.short("v")
 				.takes_value(false)
 				.global(true),
 		)
+		.arg(cmd::opt_format())
+		.arg(cmd::opt_network())
+		.arg(cmd::opt_offline())
+		.arg(cmd::opt_plain())
+		.arg(cmd::opt_raw())
+		.arg(cmd::opt_json_errors())
 }
 
 /// Try execute built-in command. Return false if no command found.
 fn execute_builtin<'a>(matches: &clap::ArgMatches<'a>) -> bool {
 	match matches.subcommand() {
 		("address", Some(m)) => cmd::address::execute(m),
+		#[cfg(feature = "daemon")]
+		("batch", Some(m)) => cmd::batch::execute(m),
 		("block", Some(m)) => cmd::block::execute(m),
+		("completions", Some(m)) => cmd::completions::execute(m),
+		#[cfg(feature = "daemon")]
+		("daemon", Some(m)) => cmd::daemon::execute(m),
 		("keypair", Some(m)) => cmd::keypair::execute(m),
+		#[cfg(feature = "miniscript")]
+		("miniscript", Some(m)) => cmd::miniscript::execute(m),
+		("psbt", Some(m)) => cmd::psbt::execute(m),
+		#[cfg(feature = "repl")]
+		("repl", Some(m)) => cmd::repl::execute(m),
+		#[cfg(feature = "daemon")]
+		("replay", Some(m)) => cmd::replay::execute(m),
+		#[cfg(feature = "daemon")]
+		("schema", Some(m)) => cmd::schema::execute(m),
 		("simplicity", Some(m)) => cmd::simplicity::execute(m),
 		("tx", Some(m)) => cmd::tx::execute(m),
 		_ => return false,
@@ -50,9 +74,13 @@ fn execute_builtin<'a>(matches: &clap::ArgMatches<'a>) -> bool {
 }
 
 fn main() {
+	let app = init_app();
+	let matches = app.get_matches();
+
 	// Apply a custom panic hook to print a more user-friendly message
 	// in case the execution fails.
-	panic::set_hook(Box::new(|info| {
+	let json_errors = matches.is_present("json-errors");
+	panic::set_hook(Box::new(move |info| {
 		let message = if let Some(m) = info.payload().downcast_ref::<String>() {
 			m
 		} else if let Some(m) = info.payload().downcast_ref::<&str>() {
@@ -60,20 +88,34 @@ fn main() {
 		} else {
 			"No error message provided"
 		};
-		println!("Execution failed: {}", message);
-		process::exit(1);
+		cmd::exit_with_error(json_errors, cmd::OTHER_ERROR_CODE, message, None);
 	}));
 
-	let app = init_app();
-	let matches = app.get_matches();
-
 	// Enable logging in verbose mode.
 	match matches.is_present("verbose") {
 		true => setup_logger(log::LevelFilter::Trace),
 		false => setup_logger(log::LevelFilter::Warn),
 	}
+	cmd::warn_renamed(&matches);
+
+	// Trace calls to the daemon if a collector is given.
+	#[cfg(feature = "otel")]
+	let telemetry = {
+		use hal_simplicity::daemon::telemetry;
+
+		telemetry::endpoint_in_env()
+			.then(|| telemetry::init(None, "hal-simplicity").unwrap_or_else(|e| panic!("{}", e)))
+	};
+	#[cfg(feature = "otel")]
+	let executed = tracing::info_span!("command", name = matches.subcommand().0)
+		.in_scope(|| execute_builtin(&matches));
+	#[cfg(not(feature = "otel"))]
+	let executed = execute_builtin(&matches);
 
-	if execute_builtin(&matches) {
+	if executed {
+		// Flush any traces, which exiting would not.
+		#[cfg(feature = "otel")]
+		drop(telemetry);
 		// success
 		process::exit(0);
 	} else {
@@ -7,6 +7,7 @@ use crate::{GetInfo, HexBytes, Network};
 use crate::tx::TransactionInfo;
 
 #[derive(Clone, Default, PartialEq, Eq, Debug, Deserialize, Serialize)]
+#[cfg_attr(feature = "daemon", derive(schemars::JsonSchema))]
 #[serde(rename_all = "lowercase")]
 pub enum ParamsType {
 	#[default]
@@ -16,20 +17,34 @@ pub enum ParamsType {
 }
 
 #[derive(Clone, PartialEq, Eq, Debug, Default, Deserialize, Serialize)]
+#[cfg_attr(feature = "daemon", derive(schemars::JsonSchema))]
 pub struct ParamsInfo {
 	pub params_type: ParamsType,
 	// both
+	#[cfg_attr(feature = "daemon", schemars(with = "Option<String>"))]
 	pub signblockscript: Option<HexBytes>,
+	#[serde(skip_serializing_if = "Option::is_none")]
+	pub signblockscript_asm: Option<String>,
 	pub signblock_witness_limit: Option<u32>,
+	/// The params root, which is what a compact params commits to.
+	#[serde(skip_serializing_if = "Option::is_none")]
+	#[cfg_attr(feature = "daemon", schemars(with = "Option<String>"))]
+	pub root: Option<sha256::Midstate>,
 	// compact only
 	#[serde(skip_serializing_if = "Option::is_none")]
+	#[cfg_attr(feature = "daemon", schemars(with = "Option<String>"))]
 	pub elided_root: Option<sha256::Midstate>,
 	// full only
 	#[serde(skip_serializing_if = "Option::is_none")]
+	#[cfg_attr(feature = "daemon", schemars(with = "Option<String>"))]
 	pub fedpeg_program: Option<HexBytes>,
 	#[serde(skip_serializing_if = "Option::is_none")]
+	#[cfg_attr(feature = "daemon", schemars(with = "Option<String>"))]
 	pub fedpeg_script: Option<HexBytes>,
 	#[serde(skip_serializing_if = "Option::is_none")]
+	pub fedpeg_script_asm: Option<String>,
+	#[serde(skip_serializing_if = "Option::is_none")]
+	#[cfg_attr(feature = "daemon", schemars(with = "Option<Vec<String>>"))]
 	pub extension_space: Option<Vec<HexBytes>>,
 }
 
@@ -46,10 +61,19 @@ impl GetInfo<ParamsInfo> for dynafed::Params {
 				} => ParamsType::Full,
 			},
 			signblockscript: self.signblockscript().map(|s| s.to_bytes().into()),
+			signblockscript_asm: self.signblockscript().map(|s| s.asm()),
 			signblock_witness_limit: self.signblock_witness_limit(),
+			root: if self.is_null() {
+				None
+			} else {
+				Some(self.calculate_root())
+			},
 			elided_root: self.elided_root().copied(),
 			fedpeg_program: self.fedpeg_program().map(|p| HexBytes::from(p.as_bytes())),
 			fedpeg_script: self.fedpegscript().map(|s| s[..].into()),
+			fedpeg_script_asm: self
+				.fedpegscript()
+				.map(|s| elements::Script::from(s.to_vec()).asm()),
 			extension_space: self
 				.extension_space()
 				.map(|s| s.iter().map(|v| v[..].into()).collect()),
@@ -58,25 +82,39 @@ impl GetInfo<ParamsInfo> for dynafed::Params {
 }
 
 #[derive(Clone, PartialEq, Eq, Debug, Deserialize, Serialize)]
+#[cfg_attr(feature = "daemon", derive(schemars::JsonSchema))]
 pub struct BlockHeaderInfo {
 	#[serde(skip_serializing_if = "Option::is_none")]
+	#[cfg_attr(feature = "daemon", schemars(with = "Option<String>"))]
 	pub block_hash: Option<BlockHash>,
 	pub version: u32,
+	#[cfg_attr(feature = "daemon", schemars(with = "String"))]
 	pub previous_block_hash: BlockHash,
-	pub merkle_root: TxMerkleNode,
+	/// When creating a block this may be omitted, in which case it is computed
+	/// from the block's transactions.
+	#[serde(default)]
+	#[cfg_attr(feature = "daemon", schemars(with = "Option<String>"))]
+	pub merkle_root: Option<TxMerkleNode>,
 	pub time: u32,
 	pub height: u32,
 	pub dynafed: bool,
 	#[serde(skip_serializing_if = "Option::is_none")]
+	#[cfg_attr(feature = "daemon", schemars(with = "Option<String>"))]
 	pub legacy_challenge: Option<HexBytes>,
 	#[serde(skip_serializing_if = "Option::is_none")]
+	#[cfg_attr(feature = "daemon", schemars(with = "Option<String>"))]
 	pub legacy_solution: Option<HexBytes>,
 	#[serde(skip_serializing_if = "Option::is_none")]
 	pub dynafed_current: Option<ParamsInfo>,
 	#[serde(skip_serializing_if = "Option::is_none")]
 	pub dynafed_proposed: Option<ParamsInfo>,
 	#[serde(skip_serializing_if = "Option::is_none")]
+	#[cfg_attr(feature = "daemon", schemars(with = "Option<Vec<String>>"))]
 	pub dynafed_witness: Option<Vec<HexBytes>>,
+	/// Whether this is the first block of a dynafed epoch, which is the only
+	/// block in an epoch whose header carries the full current params.
+	#[serde(skip_serializing_if = "Option::is_none")]
+	pub dynafed_epoch_transition: Option<bool>,
 }
 
 impl GetInfo<BlockHeaderInfo> for BlockHeader {
@@ -85,7 +123,7 @@ impl GetInfo<BlockHeaderInfo> for BlockHeader {
 			block_hash: Some(self.block_hash()),
 			version: self.version,
 			previous_block_hash: self.prev_blockhash,
-			merkle_root: self.merkle_root,
+			merkle_root: Some(self.merkle_root),
 			time: self.time,
 			height: self.height,
 			dynafed: Default::default(),
@@ -94,6 +132,7 @@ impl GetInfo<BlockHeaderInfo> for BlockHeader {
 			dynafed_current: Default::default(),
 			dynafed_proposed: Default::default(),
 			dynafed_witness: Default::default(),
+			dynafed_epoch_transition: Default::default(),
 		};
 		match self.ext {
 			BlockExtData::Proof {
@@ -114,6 +153,7 @@ impl GetInfo<BlockHeaderInfo> for BlockHeader {
 				info.dynafed_proposed = Some(proposed.get_info(network));
 				info.dynafed_witness =
 					Some(signblock_witness.iter().map(|b| b[..].into()).collect());
+				info.dynafed_epoch_transition = Some(current.is_full());
 			}
 		};
 		info
@@ -121,14 +161,17 @@ impl GetInfo<BlockHeaderInfo> for BlockHeader {
 }
 
 #[derive(Clone, PartialEq, Eq, Debug, Deserialize, Serialize)]
+#[cfg_attr(feature = "daemon", derive(schemars::JsonSchema))]
 pub struct BlockInfo {
 	pub header: BlockHeaderInfo,
 	#[serde(skip_serializing_if = "Option::is_none")]
 	pub transactions: Option<Vec<TransactionInfo>>,
 
 	#[serde(skip_serializing_if = "Option::is_none")]
+	#[cfg_attr(feature = "daemon", schemars(with = "Option<Vec<String>>"))]
 	pub txids: Option<Vec<Txid>>,
 	#[serde(skip_serializing_if = "Option::is_none")]
+	#[cfg_attr(feature = "daemon", schemars(with = "Option<Vec<String>>"))]
 	pub raw_transactions: Option<Vec<HexBytes>>,
 }
 
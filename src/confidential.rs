@@ -5,6 +5,7 @@ use serde::{Deserialize, Serialize};
 use crate::{GetInfo, HexBytes, Network};
 
 #[derive(Clone, PartialEq, Eq, Debug, Deserialize, Serialize)]
+#[cfg_attr(feature = "daemon", derive(schemars::JsonSchema))]
 #[serde(rename_all = "lowercase")]
 pub enum ConfidentialType {
 	Null,
@@ -13,12 +14,14 @@ pub enum ConfidentialType {
 }
 
 #[derive(Clone, PartialEq, Eq, Debug, Deserialize, Serialize)]
+#[cfg_attr(feature = "daemon", derive(schemars::JsonSchema))]
 pub struct ConfidentialValueInfo {
 	#[serde(rename = "type")]
 	pub type_: ConfidentialType,
 	#[serde(skip_serializing_if = "Option::is_none")]
 	pub value: Option<u64>,
 	#[serde(skip_serializing_if = "Option::is_none")]
+	#[cfg_attr(feature = "daemon", schemars(with = "Option<String>"))]
 	pub commitment: Option<HexBytes>,
 }
 
@@ -43,6 +46,7 @@ impl GetInfo<ConfidentialValueInfo> for Value {
 }
 
 #[derive(Clone, PartialEq, Eq, Debug, Deserialize, Serialize)]
+#[cfg_attr(feature = "daemon", derive(schemars::JsonSchema))]
 #[serde(rename_all = "snake_case")]
 pub enum ConfidentialAssetLabel {
 	LiquidBitcoin,
@@ -60,15 +64,46 @@ impl ConfidentialAssetLabel {
 }
 
 #[derive(Clone, PartialEq, Eq, Debug, Deserialize, Serialize)]
+#[cfg_attr(feature = "daemon", derive(schemars::JsonSchema))]
 pub struct ConfidentialAssetInfo {
 	#[serde(rename = "type")]
 	pub type_: ConfidentialType,
 	#[serde(skip_serializing_if = "Option::is_none")]
+	#[cfg_attr(feature = "daemon", schemars(with = "Option<String>"))]
 	pub asset: Option<AssetId>,
 	#[serde(skip_serializing_if = "Option::is_none")]
+	#[cfg_attr(feature = "daemon", schemars(with = "Option<String>"))]
 	pub commitment: Option<HexBytes>,
 	#[serde(skip_serializing_if = "Option::is_none")]
 	pub label: Option<ConfidentialAssetLabel>,
+	/// What an asset registry knows of the asset, if one was asked.
+	#[serde(default, skip_serializing_if = "Option::is_none")]
+	pub registry: Option<AssetMetadata>,
+}
+
+impl ConfidentialAssetInfo {
+	/// Look up an explicit asset with `lookup`, keeping what it finds in `registry`.
+	pub fn resolve<E>(
+		&mut self,
+		lookup: &mut impl FnMut(AssetId) -> Result<Option<AssetMetadata>, E>,
+	) -> Result<(), E> {
+		if let Some(asset) = self.asset {
+			self.registry = lookup(asset)?;
+		}
+		Ok(())
+	}
+}
+
+/// The metadata an issuer has registered for an asset.
+#[derive(Clone, PartialEq, Eq, Debug, Deserialize, Serialize)]
+#[cfg_attr(feature = "daemon", derive(schemars::JsonSchema))]
+pub struct AssetMetadata {
+	pub ticker: Option<String>,
+	pub name: Option<String>,
+	/// The number of decimal places amounts are shown with.
+	pub precision: Option<u8>,
+	/// The domain of the issuer, which vouches for the metadata.
+	pub domain: Option<String>,
 }
 
 impl GetInfo<ConfidentialAssetInfo> for Asset {
@@ -91,6 +126,7 @@ impl GetInfo<ConfidentialAssetInfo> for Asset {
 				Asset::Explicit(a) => ConfidentialAssetLabel::from_asset_id(*a),
 				_ => None,
 			},
+			registry: None,
 		}
 	}
 }
@@ -102,17 +138,21 @@ impl GetInfo<ConfidentialAssetInfo> for AssetId {
 			asset: Some(*self),
 			commitment: None,
 			label: ConfidentialAssetLabel::from_asset_id(*self),
+			registry: None,
 		}
 	}
 }
 
 #[derive(Clone, PartialEq, Eq, Debug, Deserialize, Serialize)]
+#[cfg_attr(feature = "daemon", derive(schemars::JsonSchema))]
 pub struct ConfidentialNonceInfo {
 	#[serde(rename = "type")]
 	pub type_: ConfidentialType,
 	#[serde(skip_serializing_if = "Option::is_none")]
+	#[cfg_attr(feature = "daemon", schemars(with = "Option<String>"))]
 	pub nonce: Option<HexBytes>,
 	#[serde(skip_serializing_if = "Option::is_none")]
+	#[cfg_attr(feature = "daemon", schemars(with = "Option<String>"))]
 	pub commitment: Option<HexBytes>,
 }
 
@@ -0,0 +1,313 @@
+//! Non-blocking client for the daemon's JSON-RPC interface
+//!
+//! [`AsyncHalSimplicity`] is [`super::client::HalSimplicity`] for async code:
+//! it makes the same calls, with the same authentication, timeouts and
+//! retries, but returns futures, so it can be used from a tokio runtime
+//! without blocking a worker thread. It only connects over TCP, so cannot
+//! call a daemon at a `unix://` URL.
+
+use std::path::Path;
+use std::sync::atomic::AtomicU64;
+use std::sync::OnceLock;
+use std::time::Duration;
+
+use reqwest::header::AUTHORIZATION;
+use reqwest::StatusCode;
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+use serde_json::Value;
+use tracing::Instrument as _;
+
+use super::client::{
+	batch_results, client_span, cookie_authorization, encode, for_each_method, into_result,
+	request, require, supports, trace_batch_response, trace_request, trace_response, ClientError,
+	RetryPolicy, VersionSkew, DEFAULT_TIMEOUT,
+};
+use super::types::*;
+
+/// A connection to a daemon.
+#[derive(Debug)]
+pub struct AsyncHalSimplicity {
+	url: String,
+	authorization: Option<String>,
+	timeout: Duration,
+	retry: RetryPolicy,
+	trace: bool,
+	client: reqwest::Client,
+	next_id: AtomicU64,
+	info: OnceLock<GetInfoResponse>,
+}
+
+impl AsyncHalSimplicity {
+	/// Call the daemon at `url`, e.g. [`super::client::DEFAULT_URL`].
+	pub fn new(url: &str) -> Self {
+		Self {
+			url: url.trim_end_matches('/').to_owned(),
+			authorization: None,
+			timeout: DEFAULT_TIMEOUT,
+			retry: RetryPolicy::default(),
+			trace: false,
+			client: reqwest::Client::new(),
+			next_id: AtomicU64::new(1),
+			info: OnceLock::new(),
+		}
+	}
+
+	/// Authenticate with a token accepted by the daemon.
+	pub fn with_token(mut self, token: &str) -> Self {
+		self.authorization = Some(format!("Bearer {}", token));
+		self
+	}
+
+	/// Authenticate with the cookie file written by the daemon.
+	pub fn with_cookie_file(mut self, path: &Path) -> std::io::Result<Self> {
+		self.authorization = Some(cookie_authorization(path)?);
+		Ok(self)
+	}
+
+	/// Wait this long for each call, instead of [`DEFAULT_TIMEOUT`].
+	pub fn with_timeout(mut self, timeout: Duration) -> Self {
+		self.timeout = timeout;
+		self
+	}
+
+	/// Retry calls to read-only methods as set by `retry`.
+	pub fn with_retry(mut self, retry: RetryPolicy) -> Self {
+		self.retry = retry;
+		self
+	}
+
+	/// Print each request and its response to stderr, if `trace` is set.
+	pub fn with_trace(mut self, trace: bool) -> Self {
+		self.trace = trace;
+		self
+	}
+
+	/// The URL of the daemon.
+	pub fn url(&self) -> &str {
+		&self.url
+	}
+
+	/// What the daemon reported about itself the first time it was asked.
+	pub async fn daemon_info(&self) -> Result<&GetInfoResponse, ClientError> {
+		if let Some(info) = self.info.get() {
+			return Ok(info);
+		}
+		let info = self.getinfo(&GetInfoRequest {}).await?;
+		Ok(self.info.get_or_init(|| info))
+	}
+
+	/// Whether the daemon supports `capability`.
+	pub async fn supports(&self, capability: Capability) -> Result<bool, ClientError> {
+		Ok(supports(self.daemon_info().await?, capability))
+	}
+
+	/// Fail unless the daemon supports `capability`, e.g. before starting a
+	/// workflow which needs it.
+	pub async fn require(&self, capability: Capability) -> Result<(), ClientError> {
+		require(self.daemon_info().await?, capability)
+	}
+
+	/// Call a method, returning its result.
+	pub async fn call(&self, method: &str, params: Option<Value>) -> Result<Value, ClientError> {
+		self.call_with_timeout(method, params, self.timeout).await
+	}
+
+	/// Make several calls, returning their results in order. They are sent
+	/// as one JSON-RPC batch if the daemon supports it, and one at a time
+	/// otherwise. Batches are not retried.
+	pub async fn call_batch(
+		&self,
+		calls: Vec<(String, Option<Value>)>,
+	) -> Result<Vec<Result<Value, ClientError>>, ClientError> {
+		if !self.supports(Capability::Batch).await? {
+			let mut results = Vec::with_capacity(calls.len());
+			for (method, params) in calls {
+				results.push(self.call(&method, params).await);
+			}
+			return Ok(results);
+		}
+		let requests: Vec<_> = calls
+			.into_iter()
+			.map(|(method, params)| request(&self.next_id, &method, params))
+			.collect();
+		let batch = Value::Array(requests);
+		if self.trace {
+			trace_request(&self.url, self.authorization.as_deref(), &batch);
+		}
+		let responses = self.post(&batch, self.timeout).await;
+		if self.trace {
+			trace_batch_response(&responses);
+		}
+		batch_results(&batch, responses?)
+	}
+
+	/// Call a method, decoding its result as `R`.
+	pub async fn call_typed<P: Serialize, R: DeserializeOwned>(
+		&self,
+		method: &str,
+		params: &P,
+	) -> Result<R, ClientError> {
+		let result = self.call(method, Some(encode(params)?)).await?;
+		match R::deserialize(&result) {
+			Ok(result) => Ok(result),
+			Err(e) => {
+				let version = self.daemon_version().await;
+				Err(ClientError::VersionSkew(VersionSkew::new(method, &result, e, version)))
+			}
+		}
+	}
+
+	/// The version of the daemon, if it answers `getinfo`.
+	async fn daemon_version(&self) -> Option<String> {
+		let info = self.call("getinfo", None).await.ok()?;
+		info["version"].as_str().map(str::to_owned)
+	}
+
+	/// Call a method, waiting up to `timeout` for each attempt.
+	pub async fn call_with_timeout(
+		&self,
+		method: &str,
+		params: Option<Value>,
+		timeout: Duration,
+	) -> Result<Value, ClientError> {
+		let retries = self.retry.retries(method);
+		let mut backoff = self.retry.initial_backoff;
+		let mut attempt = 0;
+		loop {
+			match self.send(method, params.clone(), timeout).await {
+				Err(e) if attempt < retries && e.is_transient() => {
+					log::debug!("Retrying {} in {:?}: {}", method, backoff, e);
+					tokio::time::sleep(backoff).await;
+					backoff = (backoff * 2).min(self.retry.max_backoff);
+					attempt += 1;
+				}
+				result => return result,
+			}
+		}
+	}
+
+	/// Make a single attempt at a call.
+	async fn send(
+		&self,
+		method: &str,
+		params: Option<Value>,
+		timeout: Duration,
+	) -> Result<Value, ClientError> {
+		let request = request(&self.next_id, method, params);
+		if self.trace {
+			trace_request(&self.url, self.authorization.as_deref(), &request);
+		}
+		let response = self.post(&request, timeout).await;
+		if self.trace {
+			trace_response(&response);
+		}
+		into_result(response?)
+	}
+
+	/// Post a request, returning the daemon's response.
+	async fn post<T: DeserializeOwned>(
+		&self,
+		request: &Value,
+		timeout: Duration,
+	) -> Result<T, ClientError> {
+		let span = client_span(&self.url, request);
+		if self.url.starts_with(super::client::UNIX_SCHEME) {
+			return Err(ClientError::Unreachable {
+				url: self.url.clone(),
+				message: "unix sockets are only supported by the blocking client".to_owned(),
+			});
+		}
+		let mut http = self.client.post(&self.url).timeout(timeout).json(request);
+		if let Some(ref authorization) = self.authorization {
+			http = http.header(AUTHORIZATION, authorization);
+		}
+		#[cfg(feature = "otel")]
+		if let Some(traceparent) = span.in_scope(super::telemetry::traceparent) {
+			http = http.header(super::telemetry::TRACEPARENT, traceparent);
+		}
+
+		async {
+			let response = http.send().await.map_err(|e| ClientError::Unreachable {
+				url: self.url.clone(),
+				message: e.to_string(),
+			})?;
+
+			let status = response.status();
+			if status == StatusCode::UNAUTHORIZED {
+				return Err(ClientError::Unauthorized);
+			}
+			if !status.is_success() {
+				let body = response.text().await.unwrap_or_default();
+				return Err(ClientError::Http {
+					status: status.as_u16(),
+					body: body.trim().to_owned(),
+				});
+			}
+
+			response.json().await.map_err(|e| ClientError::Response(e.to_string()))
+		}
+		.instrument(span)
+		.await
+	}
+}
+
+macro_rules! methods {
+	($($name:ident($method:literal, $req:ty) -> $resp:ty;)*) => {
+		impl AsyncHalSimplicity {
+			$(
+				#[doc = concat!("Call `", $method, "`.")]
+				pub async fn $name(&self, params: &$req) -> Result<$resp, ClientError> {
+					self.call_typed($method, params).await
+				}
+			)*
+		}
+	};
+}
+
+for_each_method!(methods);
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use crate::daemon::auth::Auth;
+	use crate::daemon::HalSimplicityDaemon;
+
+	#[test]
+	fn test_async_client() {
+		let mut auth = Auth::default();
+		auth.add_token("secret".to_string());
+		let mut daemon = HalSimplicityDaemon::new("127.0.0.1:28592").unwrap().with_auth(auth);
+		daemon.start().unwrap();
+
+		// The daemon runs its own runtime, so the client gets a separate one.
+		let runtime = tokio::runtime::Runtime::new().unwrap();
+		runtime.block_on(async {
+			let url = "http://127.0.0.1:28592";
+			assert!(matches!(
+				AsyncHalSimplicity::new(url).call("getinfo", None).await,
+				Err(ClientError::Unauthorized)
+			));
+
+			let client = AsyncHalSimplicity::new(url).with_token("secret");
+			let info = client.getinfo(&GetInfoRequest {}).await.unwrap();
+			assert_eq!(info.version, env!("CARGO_PKG_VERSION"));
+			match client.call("nope", None).await {
+				Err(ClientError::Rpc(error)) => assert_eq!(error.code, -32601),
+				other => panic!("unexpected {:?}", other),
+			}
+		});
+		daemon.shutdown();
+
+		let retry = RetryPolicy {
+			max_retries: 1,
+			initial_backoff: Duration::from_millis(50),
+			max_backoff: Duration::from_millis(50),
+		};
+		let unreachable = AsyncHalSimplicity::new("http://127.0.0.1:1").with_retry(retry);
+		let start = std::time::Instant::now();
+		let err = runtime.block_on(unreachable.call("getinfo", None)).unwrap_err();
+		assert!(matches!(err, ClientError::Unreachable { .. }));
+		assert!(start.elapsed() >= Duration::from_millis(50));
+	}
+}
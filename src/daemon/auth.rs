@@ -0,0 +1,146 @@
+//! Authentication of daemon requests
+//!
+//! Like Bitcoin Core, the daemon can write a random password to a cookie file
+//! at startup, as `__cookie__:<password>`, so that anyone who can read the file
+//! can use the daemon. Static tokens can be configured as well. Clients send the
+//! secret either as `Authorization: Bearer <secret>` or, with any user name, as
+//! HTTP basic auth.
+
+use std::fs;
+use std::io::Write as _;
+use std::path::{Path, PathBuf};
+
+use elements::bitcoin::secp256k1::rand::{self, RngCore as _};
+use hyper::header::{HeaderMap, HeaderValue, AUTHORIZATION};
+use simplicity::base64::prelude::{Engine as _, BASE64_STANDARD};
+
+/// The user name written to the cookie file.
+pub const COOKIE_USER: &str = "__cookie__";
+
+/// The secrets which are accepted by the daemon. With no secrets, every
+/// request is accepted.
+#[derive(Clone, Debug, Default)]
+pub struct Auth {
+	secrets: Vec<String>,
+	cookie_file: Option<PathBuf>,
+}
+
+impl Auth {
+	/// Accept the given static token.
+	pub fn add_token(&mut self, token: String) {
+		self.secrets.push(token);
+	}
+
+	/// Generate a random password, accept it, and write it to the cookie file at `path`.
+	pub fn add_cookie_file(&mut self, path: &Path) -> std::io::Result<()> {
+		let mut bytes = [0; 32];
+		rand::thread_rng().fill_bytes(&mut bytes);
+		let password = hex::encode(bytes);
+
+		let mut options = fs::OpenOptions::new();
+		options.write(true).create(true).truncate(true);
+		#[cfg(unix)]
+		std::os::unix::fs::OpenOptionsExt::mode(&mut options, 0o600);
+		let mut file = options.open(path)?;
+		write!(file, "{}:{}", COOKIE_USER, password)?;
+
+		self.secrets.push(password);
+		self.cookie_file = Some(path.to_owned());
+		Ok(())
+	}
+
+	/// Remove the cookie file, if one was written.
+	pub fn remove_cookie_file(&self) {
+		if let Some(ref path) = self.cookie_file {
+			let _ = fs::remove_file(path);
+		}
+	}
+
+	/// Whether any authentication is required.
+	pub fn is_enabled(&self) -> bool {
+		!self.secrets.is_empty()
+	}
+
+	/// Check the `Authorization` header of a request.
+	pub fn check(&self, headers: &HeaderMap<HeaderValue>) -> bool {
+		if !self.is_enabled() {
+			return true;
+		}
+
+		let secret = match headers.get(AUTHORIZATION).and_then(|h| h.to_str().ok()) {
+			Some(header) => match header.split_once(' ') {
+				Some(("Bearer", token)) => token.trim().to_owned(),
+				Some(("Basic", encoded)) => {
+					let decoded = match BASE64_STANDARD.decode(encoded.trim()) {
+						Ok(decoded) => decoded,
+						Err(_) => return false,
+					};
+					match String::from_utf8(decoded) {
+						Ok(credentials) => match credentials.split_once(':') {
+							Some((_user, password)) => password.to_owned(),
+							None => return false,
+						},
+						Err(_) => return false,
+					}
+				}
+				_ => return false,
+			},
+			None => return false,
+		};
+
+		// Check every secret, without short-circuiting, so that timing reveals nothing.
+		self.secrets
+			.iter()
+			.fold(false, |ok, s| constant_time_eq(s.as_bytes(), secret.as_bytes()) | ok)
+	}
+}
+
+/// Compare two byte strings in time which depends only on their lengths.
+fn constant_time_eq(a: &[u8], b: &[u8]) -> bool {
+	a.len() == b.len() && a.iter().zip(b).fold(0, |acc, (x, y)| acc | (x ^ y)) == 0
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	fn headers(auth: &str) -> HeaderMap<HeaderValue> {
+		let mut headers = HeaderMap::new();
+		headers.insert(AUTHORIZATION, HeaderValue::from_str(auth).unwrap());
+		headers
+	}
+
+	#[test]
+	fn test_no_auth() {
+		assert!(Auth::default().check(&HeaderMap::new()));
+	}
+
+	#[test]
+	fn test_token() {
+		let mut auth = Auth::default();
+		auth.add_token("hunter2".to_string());
+		assert!(!auth.check(&HeaderMap::new()));
+		assert!(auth.check(&headers("Bearer hunter2")));
+		assert!(!auth.check(&headers("Bearer hunter3")));
+		// "user:hunter2"
+		assert!(auth.check(&headers("Basic dXNlcjpodW50ZXIy")));
+		// "user:hunter3"
+		assert!(!auth.check(&headers("Basic dXNlcjpodW50ZXIz")));
+	}
+
+	#[test]
+	fn test_cookie_file() {
+		let path =
+			std::env::temp_dir().join(format!("hal-simplicity-test-{}.cookie", std::process::id()));
+		let mut auth = Auth::default();
+		auth.add_cookie_file(&path).unwrap();
+
+		let cookie = fs::read_to_string(&path).unwrap();
+		let encoded = BASE64_STANDARD.encode(&cookie);
+		assert!(cookie.starts_with("__cookie__:"));
+		assert!(auth.check(&headers(&format!("Basic {}", encoded))));
+
+		auth.remove_cookie_file();
+		assert!(!path.exists());
+	}
+}
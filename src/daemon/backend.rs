@@ -0,0 +1,564 @@
+//! Chain backends
+//!
+//! The daemon works without any view of the chain, but some methods need one:
+//! `watch_unspent` looks up outputs, `pset_update_input` can fetch the output
+//! being spent, `tx_fetch` and `block_fetch` fetch raw data, `tx_broadcast`
+//! sends a transaction, and the genesis hash defaults to that of the
+//! backend's chain. These go through a
+//! [`ChainBackend`], which is configured at startup.
+//!
+//! Both an Esplora API and an Elements node may be configured. Requests which
+//! use a backend take an optional `backend` param to pick one of them;
+//! otherwise the default one is used.
+
+use std::fmt;
+use std::io::Read as _;
+use std::path::PathBuf;
+use std::str::FromStr;
+use std::time::Duration;
+
+use elements::hashes::{sha256, Hash};
+use elements::{encode, Block, BlockHash, Script, Transaction, Txid};
+use schemars::JsonSchema;
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use simplicity::base64::prelude::{Engine as _, BASE64_STANDARD};
+
+/// How long to wait for a backend to answer.
+pub const TIMEOUT: Duration = Duration::from_secs(30);
+
+#[derive(Debug, thiserror::Error)]
+pub enum BackendError {
+	#[error("backend request failed: {0}")]
+	Request(String),
+
+	#[error("unexpected backend response: {0}")]
+	Response(String),
+
+	#[error("node returned error {code}: {message}")]
+	Node {
+		code: i64,
+		message: String,
+	},
+
+	#[error("backend does not support {0}")]
+	Unsupported(&'static str),
+}
+
+/// The kinds of backend, for choosing one per request.
+///
+/// They are ordered by preference, for when no default is configured.
+#[derive(
+	Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize, JsonSchema,
+)]
+#[serde(rename_all = "lowercase")]
+pub enum BackendKind {
+	Elements,
+	Esplora,
+}
+
+impl fmt::Display for BackendKind {
+	fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+		match self {
+			Self::Esplora => f.write_str("esplora"),
+			Self::Elements => f.write_str("elements"),
+		}
+	}
+}
+
+impl FromStr for BackendKind {
+	type Err = String;
+
+	fn from_str(s: &str) -> Result<Self, String> {
+		match s {
+			"esplora" => Ok(Self::Esplora),
+			"elements" => Ok(Self::Elements),
+			_ => Err(format!("unknown backend: {} (expected esplora or elements)", s)),
+		}
+	}
+}
+
+/// A block, by its hash or by its height in the backend's best chain.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
+#[serde(untagged)]
+pub enum BlockId {
+	Height(u32),
+	Hash(#[schemars(with = "String")] BlockHash),
+}
+
+impl fmt::Display for BlockId {
+	fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+		match self {
+			Self::Height(height) => fmt::Display::fmt(height, f),
+			Self::Hash(hash) => fmt::Display::fmt(hash, f),
+		}
+	}
+}
+
+impl FromStr for BlockId {
+	type Err = String;
+
+	fn from_str(s: &str) -> Result<Self, String> {
+		if let Ok(height) = s.parse() {
+			return Ok(Self::Height(height));
+		}
+		s.parse()
+			.map(Self::Hash)
+			.map_err(|_| format!("invalid block: {} (expected a block hash or height)", s))
+	}
+}
+
+/// An unspent output.
+#[derive(Clone, Debug, Serialize, Deserialize, JsonSchema)]
+pub struct Utxo {
+	#[schemars(with = "String")]
+	pub txid: Txid,
+	pub vout: u32,
+	/// The value, unless it is confidential.
+	pub value: Option<u64>,
+	/// The asset ID, unless it is confidential.
+	pub asset: Option<String>,
+	/// The height of the block confirming the output, if any.
+	pub height: Option<u32>,
+}
+
+/// A source of chain data.
+pub trait ChainBackend: Send + Sync {
+	/// A description of the backend, for `getinfo`.
+	fn name(&self) -> String;
+
+	/// The unspent outputs with the given scriptPubKey.
+	fn unspent(&self, script_pubkey: &Script) -> Result<Vec<Utxo>, BackendError>;
+
+	/// A transaction by its ID.
+	fn transaction(&self, _txid: Txid) -> Result<Transaction, BackendError> {
+		Err(BackendError::Unsupported("fetching transactions"))
+	}
+
+	/// A block by its hash or height.
+	fn block(&self, _id: BlockId) -> Result<Block, BackendError> {
+		Err(BackendError::Unsupported("fetching blocks"))
+	}
+
+	/// The hash of the genesis block of the backend's chain.
+	fn genesis_hash(&self) -> Result<BlockHash, BackendError> {
+		Err(BackendError::Unsupported("fetching the genesis hash"))
+	}
+
+	/// Send a transaction to the network, returning its ID.
+	fn broadcast(&self, _tx: &Transaction) -> Result<Txid, BackendError> {
+		Err(BackendError::Unsupported("broadcasting transactions"))
+	}
+}
+
+/// How to authenticate to an Elements node.
+#[derive(Clone, Debug)]
+pub enum NodeAuth {
+	None,
+	UserPass(String, String),
+	/// The node's cookie file, which is read again on every call since it
+	/// changes whenever the node restarts.
+	CookieFile(PathBuf),
+}
+
+/// The JSON-RPC interface of an Elements Core node.
+pub struct ElementsRpc {
+	url: String,
+	auth: NodeAuth,
+	agent: ureq::Agent,
+}
+
+impl ElementsRpc {
+	/// Connect to the node at `url`.
+	pub fn new(url: &str, auth: NodeAuth) -> Self {
+		Self {
+			url: url.to_owned(),
+			auth,
+			agent: ureq::AgentBuilder::new().timeout(TIMEOUT).build(),
+		}
+	}
+
+	fn call<T: DeserializeOwned>(&self, method: &str, params: Value) -> Result<T, BackendError> {
+		let mut request = self.agent.post(&self.url);
+		let credentials = match self.auth {
+			NodeAuth::None => None,
+			NodeAuth::UserPass(ref user, ref pass) => Some(format!("{}:{}", user, pass)),
+			NodeAuth::CookieFile(ref path) => Some(
+				std::fs::read_to_string(path)
+					.map_err(|e| BackendError::Request(format!("{}: {}", path.display(), e)))?
+					.trim()
+					.to_owned(),
+			),
+		};
+		if let Some(credentials) = credentials {
+			let encoded = BASE64_STANDARD.encode(credentials);
+			request = request.set("Authorization", &format!("Basic {}", encoded));
+		}
+
+		let body = json!({ "jsonrpc": "1.0", "id": 0, "method": method, "params": params });
+		let response = match request.send_json(body) {
+			Ok(response) => response,
+			// The node reports errors with an HTTP error status and a JSON-RPC error body.
+			Err(ureq::Error::Status(_, response)) => response,
+			Err(e) => return Err(BackendError::Request(e.to_string())),
+		};
+		let response: NodeResponse =
+			response.into_json().map_err(|e| BackendError::Response(e.to_string()))?;
+		match response.error {
+			Some(error) => Err(BackendError::Node {
+				code: error.code,
+				message: error.message,
+			}),
+			None => serde_json::from_value(response.result)
+				.map_err(|e| BackendError::Response(e.to_string())),
+		}
+	}
+}
+
+#[derive(Deserialize)]
+struct NodeResponse {
+	#[serde(default)]
+	result: Value,
+	error: Option<NodeError>,
+}
+
+#[derive(Deserialize)]
+struct NodeError {
+	code: i64,
+	message: String,
+}
+
+/// The result of `scantxoutset`.
+#[derive(Deserialize)]
+struct ScanResult {
+	unspents: Vec<ScannedUtxo>,
+}
+
+#[derive(Deserialize)]
+struct ScannedUtxo {
+	txid: Txid,
+	vout: u32,
+	amount: Option<f64>,
+	asset: Option<String>,
+	height: u32,
+}
+
+impl ChainBackend for ElementsRpc {
+	fn name(&self) -> String {
+		format!("elements {}", self.url)
+	}
+
+	fn unspent(&self, script_pubkey: &Script) -> Result<Vec<Utxo>, BackendError> {
+		let descriptor = format!("raw({})", hex::encode(script_pubkey.as_bytes()));
+		let scan: ScanResult = self.call("scantxoutset", json!(["start", [descriptor]]))?;
+		Ok(scan
+			.unspents
+			.into_iter()
+			.map(|utxo| Utxo {
+				txid: utxo.txid,
+				vout: utxo.vout,
+				value: utxo
+					.amount
+					.and_then(|amount| elements::bitcoin::Amount::from_btc(amount).ok())
+					.map(|amount| amount.to_sat()),
+				asset: utxo.asset,
+				height: Some(utxo.height),
+			})
+			.collect())
+	}
+
+	fn transaction(&self, txid: Txid) -> Result<Transaction, BackendError> {
+		let raw: String = self.call("getrawtransaction", json!([txid]))?;
+		decode_transaction(&raw)
+	}
+
+	fn block(&self, id: BlockId) -> Result<Block, BackendError> {
+		let hash: BlockHash = match id {
+			BlockId::Height(height) => self.call("getblockhash", json!([height]))?,
+			BlockId::Hash(hash) => hash,
+		};
+		// Verbosity 0 gives the block as hex.
+		let raw: String = self.call("getblock", json!([hash, 0]))?;
+		let bytes = hex::decode(raw.trim()).map_err(|e| BackendError::Response(e.to_string()))?;
+		encode::deserialize(&bytes).map_err(|e| BackendError::Response(e.to_string()))
+	}
+
+	fn genesis_hash(&self) -> Result<BlockHash, BackendError> {
+		self.call("getblockhash", json!([0]))
+	}
+
+	fn broadcast(&self, tx: &Transaction) -> Result<Txid, BackendError> {
+		self.call("sendrawtransaction", json!([encode::serialize_hex(tx)]))
+	}
+}
+
+fn decode_transaction(raw: &str) -> Result<Transaction, BackendError> {
+	let bytes = hex::decode(raw.trim()).map_err(|e| BackendError::Response(e.to_string()))?;
+	encode::deserialize(&bytes).map_err(|e| BackendError::Response(e.to_string()))
+}
+
+/// An Esplora HTTP API, such as the one at <https://blockstream.info/liquid/api>.
+pub struct Esplora {
+	url: String,
+	agent: ureq::Agent,
+}
+
+impl Esplora {
+	/// Use the API at `url`.
+	pub fn new(url: &str) -> Self {
+		Self {
+			url: url.trim_end_matches('/').to_owned(),
+			agent: ureq::AgentBuilder::new().timeout(TIMEOUT).build(),
+		}
+	}
+
+	fn get(&self, path: &str) -> Result<ureq::Response, BackendError> {
+		esplora_response(self.agent.get(&format!("{}/{}", self.url, path)).call())
+	}
+
+	fn get_text(&self, path: &str) -> Result<String, BackendError> {
+		self.get(path)?.into_string().map_err(|e| BackendError::Response(e.to_string()))
+	}
+
+	fn block_hash(&self, height: u32) -> Result<BlockHash, BackendError> {
+		let hash = self.get_text(&format!("block-height/{}", height))?;
+		hash.trim().parse().map_err(|e| BackendError::Response(format!("{}: {}", hash, e)))
+	}
+}
+
+/// Esplora reports errors as an HTTP error status with a plain text body, so
+/// pass that body on.
+fn esplora_response(
+	result: Result<ureq::Response, ureq::Error>,
+) -> Result<ureq::Response, BackendError> {
+	match result {
+		Ok(response) => Ok(response),
+		Err(ureq::Error::Status(status, response)) => {
+			let body = response.into_string().unwrap_or_default();
+			Err(BackendError::Request(format!("HTTP {}: {}", status, body.trim())))
+		}
+		Err(e) => Err(BackendError::Request(e.to_string())),
+	}
+}
+
+/// An output as returned by Esplora's `/scripthash/:hash/utxo`.
+#[derive(Deserialize)]
+struct EsploraUtxo {
+	txid: Txid,
+	vout: u32,
+	value: Option<u64>,
+	asset: Option<String>,
+	status: EsploraStatus,
+}
+
+#[derive(Deserialize)]
+struct EsploraStatus {
+	block_height: Option<u32>,
+}
+
+impl ChainBackend for Esplora {
+	fn name(&self) -> String {
+		format!("esplora {}", self.url)
+	}
+
+	fn unspent(&self, script_pubkey: &Script) -> Result<Vec<Utxo>, BackendError> {
+		let script_hash = sha256::Hash::hash(script_pubkey.as_bytes());
+		let path = format!("scripthash/{}/utxo", hex::encode(script_hash.to_byte_array()));
+		let utxos: Vec<EsploraUtxo> =
+			self.get(&path)?.into_json().map_err(|e| BackendError::Response(e.to_string()))?;
+		Ok(utxos
+			.into_iter()
+			.map(|utxo| Utxo {
+				txid: utxo.txid,
+				vout: utxo.vout,
+				value: utxo.value,
+				asset: utxo.asset,
+				height: utxo.status.block_height,
+			})
+			.collect())
+	}
+
+	fn transaction(&self, txid: Txid) -> Result<Transaction, BackendError> {
+		decode_transaction(&self.get_text(&format!("tx/{}/hex", txid))?)
+	}
+
+	fn block(&self, id: BlockId) -> Result<Block, BackendError> {
+		let hash = match id {
+			BlockId::Height(height) => self.block_hash(height)?,
+			BlockId::Hash(hash) => hash,
+		};
+		// Unlike transactions, Esplora gives blocks only in binary.
+		let mut bytes = vec![];
+		self.get(&format!("block/{}/raw", hash))?
+			.into_reader()
+			.read_to_end(&mut bytes)
+			.map_err(|e| BackendError::Response(e.to_string()))?;
+		encode::deserialize(&bytes).map_err(|e| BackendError::Response(e.to_string()))
+	}
+
+	fn genesis_hash(&self) -> Result<BlockHash, BackendError> {
+		self.block_hash(0)
+	}
+
+	fn broadcast(&self, tx: &Transaction) -> Result<Txid, BackendError> {
+		let request = self.agent.post(&format!("{}/tx", self.url));
+		let txid = esplora_response(request.send_string(&encode::serialize_hex(tx)))?
+			.into_string()
+			.map_err(|e| BackendError::Response(e.to_string()))?;
+		txid.trim().parse().map_err(|e| BackendError::Response(format!("{}: {}", txid, e)))
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use crate::daemon::handler::DefaultRpcHandler;
+	use crate::daemon::jsonrpc::RpcHandler;
+	use elements::confidential;
+	use serde_json::json;
+	use std::str::FromStr;
+	use std::sync::Arc;
+
+	/// A backend with a single transaction, paying a Simplicity program.
+	struct Mock(Transaction);
+
+	impl Mock {
+		fn new() -> Self {
+			let cmr = simplicity::Cmr::from_byte_array([1; 32]);
+			let address = crate::hal_simplicity::elements_address(
+				cmr,
+				None,
+				&elements::AddressParams::LIQUID_TESTNET,
+			);
+			Self(Transaction {
+				version: 2,
+				lock_time: elements::LockTime::ZERO,
+				input: vec![],
+				output: vec![elements::TxOut {
+					asset: confidential::Asset::Explicit(
+						elements::AssetId::from_slice(&[2; 32]).unwrap(),
+					),
+					value: confidential::Value::Explicit(100_000),
+					nonce: confidential::Nonce::Null,
+					script_pubkey: address.script_pubkey(),
+					witness: Default::default(),
+				}],
+			})
+		}
+	}
+
+	impl ChainBackend for Mock {
+		fn name(&self) -> String {
+			"mock".to_string()
+		}
+
+		fn unspent(&self, _: &Script) -> Result<Vec<Utxo>, BackendError> {
+			Ok(vec![])
+		}
+
+		fn transaction(&self, txid: Txid) -> Result<Transaction, BackendError> {
+			match txid == self.0.txid() {
+				true => Ok(self.0.clone()),
+				false => Err(BackendError::Node {
+					code: -5,
+					message: "No such mempool or blockchain transaction".to_string(),
+				}),
+			}
+		}
+
+		fn block(&self, id: BlockId) -> Result<Block, BackendError> {
+			let block = Block {
+				header: elements::BlockHeader {
+					version: 0x2000_0000,
+					prev_blockhash: BlockHash::from_byte_array([3; 32]),
+					merkle_root: elements::TxMerkleNode::from_byte_array([0; 32]),
+					time: 0,
+					height: 1,
+					ext: elements::BlockExtData::Proof {
+						challenge: Script::new(),
+						solution: Script::new(),
+					},
+				},
+				txdata: vec![self.0.clone()],
+			};
+			match id == BlockId::Height(1) || id == BlockId::Hash(block.block_hash()) {
+				true => Ok(block),
+				false => Err(BackendError::Node {
+					code: -8,
+					message: "Block height out of range".to_string(),
+				}),
+			}
+		}
+
+		fn genesis_hash(&self) -> Result<BlockHash, BackendError> {
+			Ok(BlockHash::from_byte_array([3; 32]))
+		}
+
+		fn broadcast(&self, tx: &Transaction) -> Result<Txid, BackendError> {
+			Ok(tx.txid())
+		}
+	}
+
+	#[test]
+	fn test_backend_methods() {
+		let mock = Mock::new();
+		let txid = mock.0.txid();
+		let raw_tx = encode::serialize_hex(&mock.0);
+		let mut handler = DefaultRpcHandler::default();
+		let liquid = crate::Network::Liquid.genesis_hash().map(|h| h.to_string());
+		assert_eq!(handler.genesis_hash_for(None, None).unwrap(), liquid);
+		handler.backends.insert(BackendKind::Elements, Arc::new(mock));
+
+		let genesis_hash = BlockHash::from_byte_array([3; 32]).to_string();
+		assert_eq!(handler.genesis_hash_for(None, None).unwrap(), Some(genesis_hash));
+		assert_eq!(handler.genesis_hash_for(Some("00"), None).unwrap().as_deref(), Some("00"));
+
+		// The spent output is fetched when not given.
+		let inputs = json!([{ "txid": txid, "vout": 0 }]).to_string();
+		let created = handler
+			.handle("pset_create", Some(json!({ "inputs": inputs, "outputs": "[]" })))
+			.unwrap();
+		let params = json!({ "pset": created["pset"], "input_index": 0 });
+		let updated = handler.handle("pset_update_input", Some(params)).unwrap();
+		assert_eq!(updated["updated_values"], json!(["witness_utxo"]));
+
+		let inputs = json!([{ "txid": Txid::from_str(&"00".repeat(32)).unwrap(), "vout": 0 }]);
+		let params = json!({ "inputs": inputs.to_string(), "outputs": "[]" });
+		let created = handler.handle("pset_create", Some(params)).unwrap();
+		let params = json!({ "pset": created["pset"], "input_index": 0 });
+		let err = handler.handle("pset_update_input", Some(params)).unwrap_err();
+		assert!(err.message.contains("No such"), "{}", err.message);
+
+		let broadcast = handler.handle("tx_broadcast", Some(json!({ "raw_tx": raw_tx }))).unwrap();
+		assert_eq!(broadcast["txid"], json!(txid));
+		assert!(handler.handle("tx_broadcast", Some(json!({ "raw_tx": "00" }))).is_err());
+
+		let fetched = handler.handle("tx_fetch", Some(json!({ "txid": txid }))).unwrap();
+		assert_eq!(fetched["raw_tx"], raw_tx);
+		let params = json!({ "txid": txid, "backend": "esplora" });
+		let err = handler.handle("tx_fetch", Some(params)).unwrap_err();
+		assert_eq!(err.message, "No esplora backend configured");
+
+		let fetched = handler.handle("block_fetch", Some(json!({ "block": 1 }))).unwrap();
+		let raw_block = fetched["raw_block"].as_str().unwrap();
+		let block: Block = encode::deserialize(&hex::decode(raw_block).unwrap()).unwrap();
+		assert_eq!(block.txdata[0].txid(), txid);
+		let params = json!({ "block": block.block_hash() });
+		let fetched = handler.handle("block_fetch", Some(params)).unwrap();
+		assert_eq!(fetched["raw_block"], raw_block);
+		let err = handler.handle("block_fetch", Some(json!({ "block": 2 }))).unwrap_err();
+		assert!(err.message.contains("out of range"), "{}", err.message);
+	}
+
+	#[test]
+	fn test_block_id() {
+		assert_eq!("144".parse(), Ok(BlockId::Height(144)));
+		let hash = BlockHash::from_byte_array([3; 32]);
+		assert_eq!(hash.to_string().parse(), Ok(BlockId::Hash(hash)));
+		assert!("tip".parse::<BlockId>().is_err());
+		assert_eq!(serde_json::from_value::<BlockId>(json!(144)).unwrap(), BlockId::Height(144));
+		let id = serde_json::from_value::<BlockId>(json!(hash.to_string())).unwrap();
+		assert_eq!(id, BlockId::Hash(hash));
+	}
+}
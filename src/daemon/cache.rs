@@ -0,0 +1,143 @@
+//! Cache of decoded programs
+//!
+//! While developing a program, the same program and witness are typically
+//! sent with many `pset_run` and `pset_finalize` calls. Decoding a large
+//! program is not free, so the most recently used programs are kept, keyed by
+//! a hash of the encoded program and witness.
+
+use std::collections::HashMap;
+use std::sync::Mutex;
+
+use elements::hashes::{sha256, Hash, HashEngine};
+use simplicity::jet::Elements;
+
+use crate::hal_simplicity::{Program, ProgramParseError};
+
+/// Default number of programs to keep.
+pub const DEFAULT_CAPACITY: usize = 64;
+
+/// A bounded cache of decoded programs, evicting the least recently used.
+#[derive(Debug)]
+pub struct ProgramCache {
+	capacity: usize,
+	entries: Mutex<Entries>,
+}
+
+#[derive(Debug, Default)]
+struct Entries {
+	programs: HashMap<sha256::Hash, (CachedProgram, u64)>,
+	/// Incremented on every lookup, to order entries by last use.
+	clock: u64,
+}
+
+struct CachedProgram(Program<Elements>);
+
+impl std::fmt::Debug for CachedProgram {
+	fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+		write!(f, "Program({})", self.0.cmr())
+	}
+}
+
+impl Default for ProgramCache {
+	fn default() -> Self {
+		Self::new(DEFAULT_CAPACITY)
+	}
+}
+
+impl ProgramCache {
+	/// Keep up to `capacity` programs. With a capacity of zero nothing is cached.
+	pub fn new(capacity: usize) -> Self {
+		Self {
+			capacity,
+			entries: Mutex::new(Entries::default()),
+		}
+	}
+
+	/// Decode a program and witness, as [`Program::from_str`], or return the
+	/// cached result of doing so.
+	pub fn get_or_parse(
+		&self,
+		program: &str,
+		witness: &str,
+	) -> Result<Program<Elements>, ProgramParseError> {
+		let key = Self::key(program, witness);
+		{
+			let mut entries = self.entries.lock().expect("poisoned");
+			entries.clock += 1;
+			let now = entries.clock;
+			if let Some((cached, last_used)) = entries.programs.get_mut(&key) {
+				*last_used = now;
+				return Ok(cached.0.clone());
+			}
+		}
+
+		// Decode without holding the lock, so other lookups are not blocked.
+		let parsed = Program::from_str(program, Some(witness))?;
+		if self.capacity > 0 {
+			let mut entries = self.entries.lock().expect("poisoned");
+			if entries.programs.len() >= self.capacity && !entries.programs.contains_key(&key) {
+				let oldest = entries
+					.programs
+					.iter()
+					.min_by_key(|(_, (_, last_used))| *last_used)
+					.map(|(key, _)| *key);
+				if let Some(oldest) = oldest {
+					entries.programs.remove(&oldest);
+				}
+			}
+			let now = entries.clock;
+			entries.programs.insert(key, (CachedProgram(parsed.clone()), now));
+		}
+		Ok(parsed)
+	}
+
+	/// The number of cached programs.
+	pub fn len(&self) -> usize {
+		self.entries.lock().expect("poisoned").programs.len()
+	}
+
+	/// Whether no programs are cached.
+	pub fn is_empty(&self) -> bool {
+		self.len() == 0
+	}
+
+	fn key(program: &str, witness: &str) -> sha256::Hash {
+		let mut engine = sha256::Hash::engine();
+		// Prefix the program with its length so that no two pairs collide.
+		engine.input(&(program.len() as u64).to_le_bytes());
+		engine.input(program.as_bytes());
+		engine.input(witness.as_bytes());
+		sha256::Hash::from_engine(engine)
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	// `jet_version` followed by `unit`, with an empty witness.
+	const PROGRAM: &str = "bf0e304820";
+
+	#[test]
+	fn test_cache() {
+		let cache = ProgramCache::new(1);
+		let first = cache.get_or_parse(PROGRAM, "").unwrap();
+		assert_eq!(cache.len(), 1);
+		let second = cache.get_or_parse(PROGRAM, "").unwrap();
+		assert_eq!(first.cmr(), second.cmr());
+		assert_eq!(cache.len(), 1);
+
+		// Errors are not cached.
+		assert!(cache.get_or_parse("zz", "").is_err());
+		assert_eq!(cache.len(), 1);
+
+		// A different encoding is a different entry, replacing the old one.
+		let base64 = cache.get_or_parse("vw4wSCA=", "").unwrap();
+		assert_eq!(base64.cmr(), first.cmr());
+		assert_eq!(cache.len(), 1);
+
+		let cache = ProgramCache::new(0);
+		assert!(cache.get_or_parse(PROGRAM, "").is_ok());
+		assert!(cache.is_empty());
+	}
+}
@@ -0,0 +1,1080 @@
+//! Client for the daemon's JSON-RPC interface
+//!
+//! [`HalSimplicity`] calls methods over HTTP, authenticating with a token or
+//! the cookie file written by the daemon (see [`super::auth`]). Connections
+//! are kept alive and reused between calls. A daemon served over TLS with a
+//! self-signed certificate is reached by pinning it with
+//! [`HalSimplicity::with_ca_file`]. A client can also be set up from a
+//! profile in the client configuration file (see [`super::profiles`]).
+//!
+//! A daemon listening on a unix socket is called at a `unix://` URL, such as
+//! `unix:///home/user/.hal-simplicity/daemon.sock`.
+//!
+//! Calls to read-only methods which fail for a reason that may pass, such as
+//! a dropped connection or a busy daemon, are retried as set by a
+//! [`RetryPolicy`]. Other methods are never retried, since the daemon may
+//! have acted on the first call.
+//!
+//! Besides [`HalSimplicity::call`], which takes and returns raw JSON, each
+//! method has a typed wrapper taking and returning the types in
+//! [`super::types`].
+//!
+//! With [`HalSimplicity::with_trace`], each request is printed to stderr as
+//! a `curl` command that repeats it, followed by the response, with secrets
+//! redacted.
+//!
+//! What the daemon supports is asked for with `getinfo` once, on first use.
+//! Features which older daemons may lack, such as batches and PSET sessions,
+//! are checked for with [`HalSimplicity::require`] before a workflow starts,
+//! and [`HalSimplicity::call_batch`] falls back to one call at a time.
+//!
+//! A typed result which does not decode is most likely from a daemon of
+//! another version, so the daemon's version is asked for and the result is
+//! checked against the method's schema to name the field at fault, giving a
+//! [`VersionSkew`] error.
+//!
+//! With the `async-client` feature, [`super::async_client`] offers the same
+//! calls as futures.
+
+use std::path::Path;
+use std::str::FromStr;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::OnceLock;
+use std::thread;
+use std::time::Duration;
+
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+use serde_json::{json, Value};
+use simplicity::base64::prelude::{Engine as _, BASE64_STANDARD};
+use thiserror::Error;
+
+use super::handler::RpcMethod;
+use super::jsonrpc::{RpcError, RpcResponse};
+use super::limits::RATE_LIMITED_CODE;
+use super::pool::SERVER_BUSY_CODE;
+use super::schema::describe;
+use super::types::*;
+
+/// The URL of a daemon listening on [`super::config::DEFAULT_ADDRESS`].
+pub const DEFAULT_URL: &str = "http://127.0.0.1:28579";
+
+/// The scheme of URLs naming a unix socket rather than an HTTP server.
+pub const UNIX_SCHEME: &str = "unix://";
+
+/// How long to wait for the daemon to answer, unless set otherwise.
+pub const DEFAULT_TIMEOUT: Duration = Duration::from_secs(30);
+
+/// How to retry calls to read-only methods.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct RetryPolicy {
+	/// How many times to retry a call after the first attempt.
+	pub max_retries: u32,
+	/// How long to wait before the first retry. The wait doubles with every
+	/// further retry.
+	pub initial_backoff: Duration,
+	/// The longest wait between retries.
+	pub max_backoff: Duration,
+}
+
+impl Default for RetryPolicy {
+	fn default() -> Self {
+		Self {
+			max_retries: 3,
+			initial_backoff: Duration::from_millis(200),
+			max_backoff: Duration::from_secs(5),
+		}
+	}
+}
+
+impl RetryPolicy {
+	/// Never retry.
+	pub fn none() -> Self {
+		Self {
+			max_retries: 0,
+			..Default::default()
+		}
+	}
+
+	/// How many times a call to `method` may be retried.
+	pub(super) fn retries(&self, method: &str) -> u32 {
+		match RpcMethod::from_str(method) {
+			Ok(method) if method.is_read_only() => self.max_retries,
+			_ => 0,
+		}
+	}
+}
+
+/// Errors from calling the daemon.
+#[derive(Debug, Error)]
+pub enum ClientError {
+	#[error("Cannot reach the daemon at {url}: {message}")]
+	Unreachable {
+		url: String,
+		message: String,
+	},
+	#[error("The daemon requires authentication")]
+	Unauthorized,
+	#[error("{0}")]
+	Config(String),
+	#[error("Invalid params: {0}")]
+	Params(String),
+	#[error("HTTP {status}: {body}")]
+	Http {
+		status: u16,
+		body: String,
+	},
+	#[error("Invalid response from the daemon: {0}")]
+	Response(String),
+	#[error("{0}")]
+	VersionSkew(VersionSkew),
+	#[error("Daemon version {version} does not support {feature}")]
+	Unsupported {
+		feature: String,
+		version: String,
+	},
+	#[error(transparent)]
+	Rpc(#[from] RpcError),
+}
+
+impl ClientError {
+	/// Whether the same call may succeed if tried again later.
+	pub fn is_transient(&self) -> bool {
+		match self {
+			Self::Unreachable {
+				..
+			} => true,
+			Self::Http {
+				status,
+				..
+			} => *status == 429 || *status == 503,
+			Self::Rpc(error) => error.code == RATE_LIMITED_CODE || error.code == SERVER_BUSY_CODE,
+			Self::Config(_)
+			| Self::Unauthorized
+			| Self::Params(_)
+			| Self::Response(_)
+			| Self::VersionSkew(_)
+			| Self::Unsupported {
+				..
+			} => false,
+		}
+	}
+}
+
+/// A field of a result which does not match what the client expects.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum SkewedField {
+	/// The field is absent, though the client requires it.
+	Missing(String),
+	/// The field has a type or value the client does not know.
+	Unexpected(String),
+}
+
+/// A result which the client cannot decode, most likely because the daemon
+/// runs another version.
+#[derive(Debug)]
+pub struct VersionSkew {
+	pub method: String,
+	/// The version the daemon reports, if it could be asked.
+	pub daemon_version: Option<String>,
+	pub field: Option<SkewedField>,
+	/// The error from decoding the result.
+	pub message: String,
+}
+
+impl VersionSkew {
+	/// Diagnose why `result` of `method` did not decode.
+	pub(crate) fn new(
+		method: &str,
+		result: &Value,
+		error: serde_json::Error,
+		daemon_version: Option<String>,
+	) -> Self {
+		let message = error.to_string();
+		let field = RpcMethod::from_str(method)
+			.ok()
+			.and_then(|method| {
+				let schema = serde_json::to_value(describe(method).result).ok()?;
+				skewed_field(&schema, result, "")
+			})
+			.or_else(|| {
+				let name = message.strip_prefix("missing field `")?.split('`').next()?;
+				Some(SkewedField::Missing(name.to_owned()))
+			});
+		Self {
+			method: method.to_owned(),
+			daemon_version,
+			field,
+			message,
+		}
+	}
+}
+
+impl std::fmt::Display for VersionSkew {
+	fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+		match self.daemon_version {
+			Some(ref version) => write!(f, "daemon version {}", version)?,
+			None => write!(f, "the daemon, of unknown version,")?,
+		}
+		let client_version = env!("CARGO_PKG_VERSION");
+		match self.field {
+			Some(SkewedField::Missing(ref field)) => write!(
+				f,
+				" does not return field `{}` of `{}`, which client version {} needs",
+				field, self.method, client_version,
+			)?,
+			Some(SkewedField::Unexpected(ref field)) => write!(
+				f,
+				" returns field `{}` of `{}` in a form client version {} doesn't understand",
+				field, self.method, client_version,
+			)?,
+			None => write!(
+				f,
+				" returns a result of `{}` client version {} doesn't understand",
+				self.method, client_version,
+			)?,
+		}
+		write!(f, " ({})", self.message)
+	}
+}
+
+/// Find the first field of `value` which `schema` does not allow, under the
+/// path `path`. Only the parts of the schema which say what type a value has,
+/// what values it may take and what fields it needs are checked.
+pub(super) fn skewed_field(schema: &Value, value: &Value, path: &str) -> Option<SkewedField> {
+	let type_matches = |ty: &Value| match ty.as_str() {
+		Some("null") => value.is_null(),
+		Some("boolean") => value.is_boolean(),
+		Some("integer") => value.is_i64() || value.is_u64(),
+		Some("number") => value.is_number(),
+		Some("string") => value.is_string(),
+		Some("array") => value.is_array(),
+		Some("object") => value.is_object(),
+		_ => true,
+	};
+	let type_ok = match schema.get("type") {
+		Some(Value::Array(types)) => types.iter().any(type_matches),
+		Some(ty) => type_matches(ty),
+		None => true,
+	};
+	let enum_ok = match schema.get("enum") {
+		Some(Value::Array(values)) => values.contains(value),
+		_ => true,
+	};
+	if !type_ok || !enum_ok {
+		return Some(SkewedField::Unexpected(path.to_owned()));
+	}
+
+	let join = |name: &str| match path {
+		"" => name.to_owned(),
+		path => format!("{}.{}", path, name),
+	};
+	match value {
+		Value::Object(fields) => {
+			let required = schema["required"].as_array().into_iter().flatten();
+			if let Some(name) =
+				required.filter_map(Value::as_str).find(|n| !fields.contains_key(*n))
+			{
+				return Some(SkewedField::Missing(join(name)));
+			}
+			let properties = schema["properties"].as_object().into_iter().flatten();
+			properties
+				.filter_map(|(name, schema)| Some((name, schema, fields.get(name)?)))
+				.find_map(|(name, schema, value)| skewed_field(schema, value, &join(name)))
+		}
+		Value::Array(items) if schema["items"].is_object() => {
+			items.iter().enumerate().find_map(|(i, item)| {
+				skewed_field(&schema["items"], item, &format!("{}[{}]", path, i))
+			})
+		}
+		_ => None,
+	}
+}
+
+/// A connection to a daemon.
+#[derive(Debug)]
+pub struct HalSimplicity {
+	url: String,
+	authorization: Option<String>,
+	timeout: Duration,
+	retry: RetryPolicy,
+	trace: bool,
+	agent: ureq::Agent,
+	next_id: AtomicU64,
+	info: OnceLock<GetInfoResponse>,
+}
+
+impl HalSimplicity {
+	/// Call the daemon at `url`, e.g. [`DEFAULT_URL`] or, for a unix socket,
+	/// `unix:///path/to/daemon.sock`.
+	pub fn new(url: &str) -> Self {
+		Self {
+			url: url.trim_end_matches('/').to_owned(),
+			authorization: None,
+			timeout: DEFAULT_TIMEOUT,
+			retry: RetryPolicy::default(),
+			trace: false,
+			agent: ureq::AgentBuilder::new().build(),
+			next_id: AtomicU64::new(1),
+			info: OnceLock::new(),
+		}
+	}
+
+	/// Authenticate with a token accepted by the daemon.
+	pub fn with_token(mut self, token: &str) -> Self {
+		self.authorization = Some(format!("Bearer {}", token));
+		self
+	}
+
+	/// Authenticate with the cookie file written by the daemon.
+	pub fn with_cookie_file(mut self, path: &Path) -> std::io::Result<Self> {
+		self.authorization = Some(cookie_authorization(path)?);
+		Ok(self)
+	}
+
+	/// Trust only the CA certificates in the PEM file at `path` for an
+	/// `https` URL, rather than the usual web roots. This pins the
+	/// certificate of a daemon started with `--tls-self-signed`.
+	pub fn with_ca_file(mut self, path: &Path) -> std::io::Result<Self> {
+		self.agent = ureq::AgentBuilder::new().tls_config(super::tls::client_config(path)?).build();
+		Ok(self)
+	}
+
+	/// Wait this long for each call, instead of [`DEFAULT_TIMEOUT`].
+	pub fn with_timeout(mut self, timeout: Duration) -> Self {
+		self.timeout = timeout;
+		self
+	}
+
+	/// Retry calls to read-only methods as set by `retry`.
+	pub fn with_retry(mut self, retry: RetryPolicy) -> Self {
+		self.retry = retry;
+		self
+	}
+
+	/// Print each request and its response to stderr, if `trace` is set.
+	pub fn with_trace(mut self, trace: bool) -> Self {
+		self.trace = trace;
+		self
+	}
+
+	/// The URL of the daemon.
+	pub fn url(&self) -> &str {
+		&self.url
+	}
+
+	/// What the daemon reported about itself the first time it was asked.
+	pub fn daemon_info(&self) -> Result<&GetInfoResponse, ClientError> {
+		if let Some(info) = self.info.get() {
+			return Ok(info);
+		}
+		let info = self.getinfo(&GetInfoRequest {})?;
+		Ok(self.info.get_or_init(|| info))
+	}
+
+	/// Whether the daemon supports `capability`.
+	pub fn supports(&self, capability: Capability) -> Result<bool, ClientError> {
+		Ok(supports(self.daemon_info()?, capability))
+	}
+
+	/// Fail unless the daemon supports `capability`, e.g. before starting a
+	/// workflow which needs it.
+	pub fn require(&self, capability: Capability) -> Result<(), ClientError> {
+		require(self.daemon_info()?, capability)
+	}
+
+	/// Call a method, returning its result.
+	pub fn call(&self, method: &str, params: Option<Value>) -> Result<Value, ClientError> {
+		self.call_with_timeout(method, params, self.timeout)
+	}
+
+	/// Make several calls, returning their results in order. They are sent
+	/// as one JSON-RPC batch if the daemon supports it, and one at a time
+	/// otherwise. Batches are not retried.
+	pub fn call_batch(
+		&self,
+		calls: Vec<(String, Option<Value>)>,
+	) -> Result<Vec<Result<Value, ClientError>>, ClientError> {
+		if !self.supports(Capability::Batch)? {
+			return Ok(calls
+				.into_iter()
+				.map(|(method, params)| self.call(&method, params))
+				.collect());
+		}
+		let requests: Vec<_> = calls
+			.into_iter()
+			.map(|(method, params)| request(&self.next_id, &method, params))
+			.collect();
+		let batch = Value::Array(requests);
+		if self.trace {
+			trace_request(&self.url, self.authorization.as_deref(), &batch);
+		}
+		let responses = self.post(&batch, self.timeout);
+		if self.trace {
+			trace_batch_response(&responses);
+		}
+		batch_results(&batch, responses?)
+	}
+
+	/// Call a method, decoding its result as `R`.
+	pub fn call_typed<P: Serialize, R: DeserializeOwned>(
+		&self,
+		method: &str,
+		params: &P,
+	) -> Result<R, ClientError> {
+		let result = self.call(method, Some(encode(params)?))?;
+		R::deserialize(&result).map_err(|e| {
+			ClientError::VersionSkew(VersionSkew::new(method, &result, e, self.daemon_version()))
+		})
+	}
+
+	/// The version of the daemon, if it answers `getinfo`.
+	fn daemon_version(&self) -> Option<String> {
+		let info = self.call("getinfo", None).ok()?;
+		info["version"].as_str().map(str::to_owned)
+	}
+
+	/// Call a method, waiting up to `timeout` for each attempt.
+	pub fn call_with_timeout(
+		&self,
+		method: &str,
+		params: Option<Value>,
+		timeout: Duration,
+	) -> Result<Value, ClientError> {
+		let retries = self.retry.retries(method);
+		let mut backoff = self.retry.initial_backoff;
+		let mut attempt = 0;
+		loop {
+			match self.send(method, params.clone(), timeout) {
+				Err(e) if attempt < retries && e.is_transient() => {
+					log::debug!("Retrying {} in {:?}: {}", method, backoff, e);
+					thread::sleep(backoff);
+					backoff = (backoff * 2).min(self.retry.max_backoff);
+					attempt += 1;
+				}
+				result => return result,
+			}
+		}
+	}
+
+	/// Make a single attempt at a call.
+	fn send(
+		&self,
+		method: &str,
+		params: Option<Value>,
+		timeout: Duration,
+	) -> Result<Value, ClientError> {
+		let request = request(&self.next_id, method, params);
+		if self.trace {
+			trace_request(&self.url, self.authorization.as_deref(), &request);
+		}
+		let response = self.post(&request, timeout);
+		if self.trace {
+			trace_response(&response);
+		}
+		into_result(response?)
+	}
+
+	/// Post a request, returning the daemon's response.
+	fn post<T: DeserializeOwned>(
+		&self,
+		request: &Value,
+		timeout: Duration,
+	) -> Result<T, ClientError> {
+		let span = client_span(&self.url, request);
+		let _span = span.enter();
+		if let Some(path) = self.url.strip_prefix(UNIX_SCHEME) {
+			return self.post_unix(Path::new(path), request, timeout);
+		}
+		let mut http = self.agent.post(&self.url).timeout(timeout);
+		if let Some(ref authorization) = self.authorization {
+			http = http.set("Authorization", authorization);
+		}
+		#[cfg(feature = "otel")]
+		if let Some(traceparent) = super::telemetry::traceparent() {
+			http = http.set(super::telemetry::TRACEPARENT, &traceparent);
+		}
+		let response = match http.send_json(request) {
+			Ok(response) => response,
+			Err(ureq::Error::Status(401, _)) => return Err(ClientError::Unauthorized),
+			Err(ureq::Error::Status(status, response)) => {
+				let body = response.into_string().unwrap_or_default();
+				return Err(ClientError::Http {
+					status,
+					body: body.trim().to_owned(),
+				});
+			}
+			Err(e) => {
+				return Err(ClientError::Unreachable {
+					url: self.url.clone(),
+					message: e.to_string(),
+				})
+			}
+		};
+
+		response.into_json().map_err(|e| ClientError::Response(e.to_string()))
+	}
+
+	/// Post a request over a unix socket. ureq only connects over TCP, so this
+	/// writes a single HTTP/1.1 request and reads the response until the
+	/// daemon closes the connection.
+	#[cfg(unix)]
+	fn post_unix<T: DeserializeOwned>(
+		&self,
+		path: &Path,
+		request: &Value,
+		timeout: Duration,
+	) -> Result<T, ClientError> {
+		use std::io::{Read as _, Write as _};
+
+		let unreachable = |e: std::io::Error| ClientError::Unreachable {
+			url: self.url.clone(),
+			message: e.to_string(),
+		};
+		let body = serde_json::to_vec(request).expect("requests serialize");
+		let mut head = format!(
+			"POST / HTTP/1.1\r\nHost: localhost\r\nConnection: close\r\n\
+			Content-Type: application/json\r\nContent-Length: {}\r\n",
+			body.len()
+		);
+		if let Some(ref authorization) = self.authorization {
+			head.push_str(&format!("Authorization: {}\r\n", authorization));
+		}
+		#[cfg(feature = "otel")]
+		if let Some(traceparent) = super::telemetry::traceparent() {
+			head.push_str(&format!("{}: {}\r\n", super::telemetry::TRACEPARENT, traceparent));
+		}
+		head.push_str("\r\n");
+
+		let mut stream = std::os::unix::net::UnixStream::connect(path).map_err(unreachable)?;
+		stream.set_read_timeout(Some(timeout)).map_err(unreachable)?;
+		stream.set_write_timeout(Some(timeout)).map_err(unreachable)?;
+		stream.write_all(head.as_bytes()).map_err(unreachable)?;
+		stream.write_all(&body).map_err(unreachable)?;
+		let mut response = vec![];
+		stream.read_to_end(&mut response).map_err(unreachable)?;
+
+		let (status, body) = parse_http_response(&response)
+			.ok_or_else(|| ClientError::Response("Invalid HTTP response".to_owned()))?;
+		match status {
+			200..=299 => {
+				serde_json::from_slice(body).map_err(|e| ClientError::Response(e.to_string()))
+			}
+			401 => Err(ClientError::Unauthorized),
+			status => Err(ClientError::Http {
+				status,
+				body: String::from_utf8_lossy(body).trim().to_owned(),
+			}),
+		}
+	}
+
+	#[cfg(not(unix))]
+	fn post_unix<T: DeserializeOwned>(
+		&self,
+		_path: &Path,
+		_request: &Value,
+		_timeout: Duration,
+	) -> Result<T, ClientError> {
+		Err(ClientError::Unreachable {
+			url: self.url.clone(),
+			message: "unix sockets are not supported on this platform".to_owned(),
+		})
+	}
+}
+
+/// The status and body of a raw HTTP/1.1 response, cut to its
+/// `Content-Length` if it has one.
+#[cfg(unix)]
+fn parse_http_response(response: &[u8]) -> Option<(u16, &[u8])> {
+	let end = response.windows(4).position(|w| w == b"\r\n\r\n")?;
+	let head = std::str::from_utf8(&response[..end]).ok()?;
+	let body = &response[end + 4..];
+	let mut lines = head.split("\r\n");
+	let status = lines.next()?.split(' ').nth(1)?.parse().ok()?;
+	let length = lines
+		.filter_map(|line| line.split_once(':'))
+		.find(|(name, _)| name.eq_ignore_ascii_case("content-length"))
+		.and_then(|(_, value)| value.trim().parse::<usize>().ok());
+	match length {
+		Some(length) => Some((status, body.get(..length)?)),
+		None => Some((status, body)),
+	}
+}
+
+/// The `Authorization` header for the credentials in a cookie file.
+pub(super) fn cookie_authorization(path: &Path) -> std::io::Result<String> {
+	let credentials = std::fs::read_to_string(path)?;
+	Ok(format!("Basic {}", BASE64_STANDARD.encode(credentials.trim())))
+}
+
+/// A span for posting a request or batch of requests to the daemon.
+pub(super) fn client_span(url: &str, request: &Value) -> tracing::Span {
+	let method = request.get("method").and_then(Value::as_str).unwrap_or("batch");
+	tracing::info_span!("rpc.client", method, url)
+}
+
+/// A JSON-RPC request, numbered from `next_id`.
+pub(super) fn request(next_id: &AtomicU64, method: &str, params: Option<Value>) -> Value {
+	let id = next_id.fetch_add(1, Ordering::Relaxed);
+	let mut request = json!({ "jsonrpc": "2.0", "method": method, "id": id });
+	if let Some(params) = params {
+		request["params"] = params;
+	}
+	request
+}
+
+/// The keys of params and results whose values are secrets.
+const SECRET_KEYS: &[&str] = &["secret", "secret_key", "token", "password"];
+
+/// A copy of `value` with the values of secret keys replaced, at any depth.
+pub(super) fn redact(value: &Value) -> Value {
+	match value {
+		Value::Object(map) => Value::Object(
+			map.iter()
+				.map(|(key, value)| match SECRET_KEYS.contains(&key.as_str()) {
+					true => (key.clone(), Value::String("<redacted>".to_owned())),
+					false => (key.clone(), redact(value)),
+				})
+				.collect(),
+		),
+		Value::Array(items) => Value::Array(items.iter().map(redact).collect()),
+		other => other.clone(),
+	}
+}
+
+/// The `curl` command which repeats a request, with its credentials redacted.
+pub(super) fn curl_command(url: &str, authorization: Option<&str>, request: &Value) -> String {
+	let target = match url.strip_prefix(UNIX_SCHEME) {
+		Some(path) => format!("--unix-socket {} http://localhost/", path),
+		None => url.to_owned(),
+	};
+	let mut command = format!("curl -s {} -H 'Content-Type: application/json'", target);
+	if let Some(authorization) = authorization {
+		// Keep the scheme, so that it is clear which credentials to fill in.
+		let scheme = authorization.split(' ').next().unwrap_or_default();
+		command.push_str(&format!(" -H 'Authorization: {} <redacted>'", scheme));
+	}
+	let body = redact(request).to_string().replace('\'', "'\\''");
+	command.push_str(&format!(" -d '{}'", body));
+	command
+}
+
+/// Print a request to stderr, for `with_trace`.
+pub(super) fn trace_request(url: &str, authorization: Option<&str>, request: &Value) {
+	eprintln!("--> {}", curl_command(url, authorization, request));
+}
+
+/// Print a response, or the failure to get one, to stderr, for `with_trace`.
+pub(super) fn trace_response(response: &Result<RpcResponse, ClientError>) {
+	match response {
+		Ok(response) => {
+			let json = serde_json::to_value(response).expect("responses serialize");
+			eprintln!("<-- {}", redact(&json));
+		}
+		Err(e) => eprintln!("<-- {}", e),
+	}
+}
+
+/// Print the responses to a batch, or the failure to get them, to stderr,
+/// for `with_trace`.
+pub(super) fn trace_batch_response(responses: &Result<Vec<RpcResponse>, ClientError>) {
+	match responses {
+		Ok(responses) => {
+			let json = serde_json::to_value(responses).expect("responses serialize");
+			eprintln!("<-- {}", redact(&json));
+		}
+		Err(e) => eprintln!("<-- {}", e),
+	}
+}
+
+/// Whether a daemon supports `capability`.
+pub(super) fn supports(info: &GetInfoResponse, capability: Capability) -> bool {
+	info.capabilities.iter().any(|c| c == capability.as_str())
+}
+
+/// Fail unless a daemon supports `capability`.
+pub(super) fn require(info: &GetInfoResponse, capability: Capability) -> Result<(), ClientError> {
+	match supports(info, capability) {
+		true => Ok(()),
+		false => Err(ClientError::Unsupported {
+			feature: capability.to_string(),
+			version: info.version.clone(),
+		}),
+	}
+}
+
+/// The results of the responses to a batch, in the order of its requests.
+pub(super) fn batch_results(
+	batch: &Value,
+	responses: Vec<RpcResponse>,
+) -> Result<Vec<Result<Value, ClientError>>, ClientError> {
+	let mut responses: Vec<_> = responses.into_iter().map(Some).collect();
+	let requests = batch.as_array().map(Vec::as_slice).unwrap_or_default();
+	requests
+		.iter()
+		.map(|request| {
+			let response = responses
+				.iter_mut()
+				.find(|response| response.as_ref().is_some_and(|r| r.id == request["id"]))
+				.and_then(Option::take)
+				.ok_or_else(|| {
+					ClientError::Response(format!("no response to call {}", request["id"]))
+				})?;
+			Ok(into_result(response))
+		})
+		.collect()
+}
+
+/// The result of a JSON-RPC response.
+pub(super) fn into_result(response: RpcResponse) -> Result<Value, ClientError> {
+	match (response.result, response.error) {
+		(_, Some(error)) => Err(ClientError::Rpc(error)),
+		(Some(result), None) => Ok(result),
+		(None, None) => Err(ClientError::Response("neither result nor error".to_string())),
+	}
+}
+
+pub(super) fn encode<P: Serialize>(params: &P) -> Result<Value, ClientError> {
+	serde_json::to_value(params).map_err(|e| ClientError::Params(e.to_string()))
+}
+
+/// Pass the name, params and result type of every method to `$then`.
+macro_rules! for_each_method {
+	($then:ident) => {
+		$then! {
+			getinfo("getinfo", GetInfoRequest) -> GetInfoResponse;
+			help("help", HelpRequest) -> HelpResponse;
+			rpc_discover("rpc.discover", RpcDiscoverRequest) -> RpcDiscoverResponse;
+			stop("stop", StopRequest) -> StopResponse;
+			address_create("address_create", AddressCreateRequest) -> AddressCreateResponse;
+			address_inspect("address_inspect", AddressInspectRequest) -> AddressInspectResponse;
+			block_create("block_create", BlockCreateRequest) -> BlockCreateResponse;
+			block_decode("block_decode", BlockDecodeRequest) -> BlockDecodeResponse;
+			block_stats("block_stats", BlockStatsRequest) -> BlockStatsResponse;
+			block_verify_chain("block_verify_chain", BlockVerifyChainRequest)
+				-> BlockVerifyChainResponse;
+			block_verify_signblock("block_verify_signblock", BlockVerifySignblockRequest)
+				-> BlockVerifySignblockResponse;
+			block_fetch("block_fetch", BlockFetchRequest) -> BlockFetchResponse;
+			tx_create("tx_create", TxCreateRequest) -> TxCreateResponse;
+			tx_decode("tx_decode", TxDecodeRequest) -> TxDecodeResponse;
+			tx_broadcast("tx_broadcast", TxBroadcastRequest) -> TxBroadcastResponse;
+			tx_fetch("tx_fetch", TxFetchRequest) -> TxFetchResponse;
+			keypair_generate("keypair_generate", KeypairGenerateRequest) -> KeypairGenerateResponse;
+			simplicity_info("simplicity_info", SimplicityInfoRequest) -> SimplicityInfoResponse;
+			simplicity_sighash("simplicity_sighash", SimplicitySighashRequest)
+				-> SimplicitySighashResponse;
+			pset_create("pset_create", PsetCreateRequest) -> PsetCreateResponse;
+			pset_extract("pset_extract", PsetExtractRequest) -> PsetExtractResponse;
+			pset_open("pset_open", PsetOpenRequest) -> PsetOpenResponse;
+			pset_get("pset_get", PsetGetRequest) -> PsetGetResponse;
+			pset_close("pset_close", PsetCloseRequest) -> PsetCloseResponse;
+			pset_history("pset_history", PsetHistoryRequest) -> PsetHistoryResponse;
+			pset_revert("pset_revert", PsetRevertRequest) -> PsetRevertResponse;
+			pset_finalize("pset_finalize", PsetFinalizeRequest) -> PsetFinalizeResponse;
+			pset_run("pset_run", PsetRunRequest) -> PsetRunResponse;
+			pset_update_input("pset_update_input", PsetUpdateInputRequest) -> PsetUpdateInputResponse;
+			watch_add("watch_add", WatchAddRequest) -> WatchAddResponse;
+			watch_remove("watch_remove", WatchRemoveRequest) -> WatchRemoveResponse;
+			watch_list("watch_list", WatchListRequest) -> WatchListResponse;
+			watch_unspent("watch_unspent", WatchUnspentRequest) -> WatchUnspentResponse;
+			job_submit("job_submit", JobSubmitRequest) -> JobSubmitResponse;
+			job_status("job_status", JobStatusRequest) -> JobStatusResponse;
+			job_result("job_result", JobResultRequest) -> JobResultResponse;
+			schema("schema", SchemaRequest) -> SchemaResponse;
+		}
+	};
+}
+#[cfg(feature = "async-client")]
+pub(super) use for_each_method;
+
+macro_rules! methods {
+	($($name:ident($method:literal, $req:ty) -> $resp:ty;)*) => {
+		impl HalSimplicity {
+			$(
+				#[doc = concat!("Call `", $method, "`.")]
+				pub fn $name(&self, params: &$req) -> Result<$resp, ClientError> {
+					self.call_typed($method, params)
+				}
+			)*
+		}
+	};
+}
+
+for_each_method!(methods);
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use crate::daemon::auth::Auth;
+	use crate::daemon::HalSimplicityDaemon;
+
+	#[test]
+	fn test_client() {
+		let mut auth = Auth::default();
+		auth.add_token("secret".to_string());
+		let mut daemon = HalSimplicityDaemon::new("127.0.0.1:28590").unwrap().with_auth(auth);
+		daemon.start().unwrap();
+
+		let url = "http://127.0.0.1:28590";
+		assert!(matches!(
+			HalSimplicity::new(url).call("getinfo", None),
+			Err(ClientError::Unauthorized)
+		));
+
+		let client = HalSimplicity::new(url).with_token("secret");
+		let info = client.call("getinfo", None).unwrap();
+		assert_eq!(info["version"], env!("CARGO_PKG_VERSION"));
+		match client.call("nope", None) {
+			Err(ClientError::Rpc(error)) => assert_eq!(error.code, -32601),
+			other => panic!("unexpected {:?}", other),
+		}
+		daemon.shutdown();
+
+		let unreachable = HalSimplicity::new("http://127.0.0.1:1").call("getinfo", None);
+		assert!(matches!(unreachable, Err(ClientError::Unreachable { .. })));
+	}
+
+	#[test]
+	fn test_ca_file() {
+		let cert =
+			std::env::temp_dir().join(format!("hal-simplicity-client-{}.pem", std::process::id()));
+		let tls = crate::daemon::tls::self_signed(&cert).unwrap();
+		let mut daemon = HalSimplicityDaemon::new("127.0.0.1:28595").unwrap().with_tls(tls);
+		daemon.start().unwrap();
+
+		// The self-signed certificate is only trusted once pinned.
+		let url = "https://127.0.0.1:28595";
+		let unpinned = HalSimplicity::new(url).call("getinfo", None);
+		assert!(matches!(unpinned, Err(ClientError::Unreachable { .. })));
+		let client = HalSimplicity::new(url).with_ca_file(&cert).unwrap();
+		let info = client.call("getinfo", None).unwrap();
+		assert_eq!(info["version"], env!("CARGO_PKG_VERSION"));
+		daemon.shutdown();
+		std::fs::remove_file(&cert).unwrap();
+	}
+
+	#[test]
+	#[cfg(unix)]
+	fn test_unix_socket() {
+		let path =
+			std::env::temp_dir().join(format!("hal-simplicity-client-{}.sock", std::process::id()));
+		let mut daemon = HalSimplicityDaemon::new("127.0.0.1:28596")
+			.unwrap()
+			.with_unix_socket(&path)
+			.without_tcp();
+		daemon.start().unwrap();
+
+		let url = format!("{}{}", UNIX_SCHEME, path.display());
+		let client = HalSimplicity::new(&url);
+		let info = client.call("getinfo", None).unwrap();
+		assert_eq!(info["version"], env!("CARGO_PKG_VERSION"));
+		match client.call("nope", None) {
+			Err(ClientError::Rpc(error)) => assert_eq!(error.code, -32601),
+			other => panic!("unexpected {:?}", other),
+		}
+		daemon.shutdown();
+
+		let unreachable = HalSimplicity::new("unix:///nonexistent.sock").call("getinfo", None);
+		assert!(matches!(unreachable, Err(ClientError::Unreachable { .. })));
+	}
+
+	#[test]
+	fn test_trace() {
+		let request = json!({
+			"jsonrpc": "2.0",
+			"method": "simplicity_sighash",
+			"id": 1,
+			"params": { "secret_key": "abcd", "cmr": "it's" },
+		});
+		assert_eq!(
+			curl_command("http://127.0.0.1:28579", Some("Bearer hunter2"), &request),
+			"curl -s http://127.0.0.1:28579 -H 'Content-Type: application/json' \
+			 -H 'Authorization: Bearer <redacted>' \
+			 -d '{\"jsonrpc\":\"2.0\",\"method\":\"simplicity_sighash\",\"id\":1,\
+			 \"params\":{\"secret_key\":\"<redacted>\",\"cmr\":\"it'\\''s\"}}'",
+		);
+		let request = json!({ "jsonrpc": "2.0", "method": "getinfo", "id": 2 });
+		assert_eq!(
+			curl_command("unix:///tmp/daemon.sock", None, &request),
+			"curl -s --unix-socket /tmp/daemon.sock http://localhost/ \
+			 -H 'Content-Type: application/json' \
+			 -d '{\"jsonrpc\":\"2.0\",\"method\":\"getinfo\",\"id\":2}'",
+		);
+		let response = json!({ "secret": "abcd", "x_only": "ef01" });
+		assert_eq!(redact(&response), json!({ "secret": "<redacted>", "x_only": "ef01" }));
+	}
+
+	#[test]
+	fn test_typed() {
+		let mut daemon = HalSimplicityDaemon::new("127.0.0.1:28591").unwrap();
+		daemon.start().unwrap();
+		let client = HalSimplicity::new("http://127.0.0.1:28591");
+
+		let info = client.getinfo(&GetInfoRequest {}).unwrap();
+		assert_eq!(info.version, env!("CARGO_PKG_VERSION"));
+
+		let pubkey = "026a04ab98d9e4774ad806e302dddeb63bea16b5cb5f223ee77478e861bb583eb3";
+		let addresses = client
+			.address_create(&AddressCreateRequest {
+				network: None,
+				pubkey: Some(pubkey.to_owned()),
+				script: None,
+				blinder: None,
+			})
+			.unwrap();
+		assert!(addresses.p2wpkh.is_some());
+
+		let raw_tx = "0200000001010000000000000000000000000000000000000000000000000000000000000000ffffffff0603a730180101ffffffff03016d521c38ec1ea15734ae22b7c46064412829c0d0579f0a713d1c04ede979026f01000000000000000000266a240a8ce26fdbb51a2d03d4e62fdafd4a06dd7faa0d1c083aa7e27905000000000000000000016d521c38ec1ea15734ae22b7c46064412829c0d0579f0a713d1c04ede979026f010000000000000106001976a914fc26751a5025129a2fd006c6fbfa598ddd67f7e188ac016d521c38ec1ea15734ae22b7c46064412829c0d0579f0a713d1c04ede979026f01000000000000000000266a24aa21a9ede8497768bc893ee587244bf5303ac3cf482bab8e4b3fd22e8b114c2a52525ab30000000000000120000000000000000000000000000000000000000000000000000000000000000000000000000000";
+		let tx = client
+			.tx_decode(&TxDecodeRequest {
+				raw_tx: raw_tx.to_owned(),
+				network: None,
+				mainchain_headers: None,
+			})
+			.unwrap();
+		assert_eq!(tx.outputs.as_ref().map(Vec::len), Some(3));
+
+		let program = "zSQIS29W33fvVt9371bfd+9W33fvVt9371bfd+9W33fvVt93hgGA";
+		let info = client
+			.simplicity_info(&SimplicityInfoRequest {
+				program: program.to_owned(),
+				witness: Some(String::new()),
+				state: None,
+			})
+			.unwrap();
+		assert!(info.is_redeem);
+		assert!(info.redeem_info.is_some());
+
+		let header = json!({
+			"version": 536870912,
+			"previous_block_hash": "0000000000000000000000000000000000000000000000000000000000000000",
+			"time": 1700000000,
+			"height": 1,
+			"dynafed": false,
+			"legacy_challenge": "51",
+			"legacy_solution": "",
+		});
+		let block_info = json!({ "header": header, "raw_transactions": [raw_tx] });
+		let raw_block = client
+			.block_create(&BlockCreateRequest {
+				block_info: serde::Deserialize::deserialize(&block_info).unwrap(),
+			})
+			.unwrap()
+			.raw_block;
+		let block = client
+			.block_decode(&BlockDecodeRequest {
+				raw_block,
+				network: None,
+				txids: None,
+				full: None,
+			})
+			.unwrap();
+		match block {
+			BlockDecodeResponse::Header(header) => assert_eq!(header.height, 1),
+			other => panic!("unexpected {:?}", other),
+		}
+		daemon.shutdown();
+	}
+
+	#[test]
+	fn test_version_skew() {
+		let info = json!({
+			"version": "9.0.0",
+			"methods": [],
+			"network": "liquid",
+			"genesis_hash": null,
+			"uptime": 5,
+		});
+		let error = serde_json::from_value::<GetInfoResponse>(info.clone()).unwrap_err();
+		let skew = VersionSkew::new("getinfo", &info, error, Some("9.0.0".to_owned()));
+		assert_eq!(skew.field, Some(SkewedField::Missing("backends".to_owned())));
+		assert!(skew
+			.to_string()
+			.starts_with("daemon version 9.0.0 does not return field `backends`"));
+
+		let mut info = info;
+		info["backends"] = json!([]);
+		info["network"] = json!("liquidv2");
+		let error = serde_json::from_value::<GetInfoResponse>(info.clone()).unwrap_err();
+		let skew = VersionSkew::new("getinfo", &info, error, None);
+		assert_eq!(skew.field, Some(SkewedField::Unexpected("network".to_owned())));
+		assert!(skew.to_string().starts_with(
+			"the daemon, of unknown version, returns field `network` of `getinfo` in a form"
+		));
+
+		// The daemon's version is asked for when a typed call fails.
+		let mut daemon = HalSimplicityDaemon::new("127.0.0.1:28593").unwrap();
+		daemon.start().unwrap();
+		let client = HalSimplicity::new("http://127.0.0.1:28593");
+		match client.call_typed::<_, TxDecodeResponse>("getinfo", &GetInfoRequest {}) {
+			Err(ClientError::VersionSkew(skew)) => {
+				assert_eq!(skew.daemon_version.as_deref(), Some(env!("CARGO_PKG_VERSION")));
+			}
+			other => panic!("unexpected {:?}", other.map(|_| ())),
+		}
+		daemon.shutdown();
+	}
+
+	#[test]
+	fn test_capabilities() {
+		let mut daemon = HalSimplicityDaemon::new("127.0.0.1:28594").unwrap();
+		daemon.start().unwrap();
+		let url = "http://127.0.0.1:28594";
+		let client = HalSimplicity::new(url);
+
+		let info = client.daemon_info().unwrap();
+		assert_eq!(info.capabilities, ["batch", "sessions", "streaming"]);
+		assert_eq!(info.formats, ["json", "ndjson"]);
+		assert_eq!(info.max_request_size, Some(super::super::limits::DEFAULT_MAX_BODY_SIZE));
+		client.require(Capability::Sessions).unwrap();
+
+		let calls = vec![
+			("getinfo".to_owned(), None),
+			("nope".to_owned(), None),
+			("help".to_owned(), Some(json!({ "method": "stop" }))),
+		];
+		let results = client.call_batch(calls.clone()).unwrap();
+		assert_eq!(results[0].as_ref().unwrap()["version"], env!("CARGO_PKG_VERSION"));
+		assert!(matches!(results[1], Err(ClientError::Rpc(ref e)) if e.code == -32601));
+		assert_eq!(results[2].as_ref().unwrap()[0]["name"], "stop");
+
+		// A daemon without batches, or sessions, as an older one would be.
+		let old = HalSimplicity::new(url);
+		let mut info = client.getinfo(&GetInfoRequest {}).unwrap();
+		info.version = "0.1.0".to_owned();
+		info.capabilities.clear();
+		old.info.set(info).unwrap();
+		let old_results = old.call_batch(calls).unwrap();
+		assert_eq!(old_results[2].as_ref().unwrap(), results[2].as_ref().unwrap());
+		assert_eq!(
+			old.require(Capability::Sessions).unwrap_err().to_string(),
+			"Daemon version 0.1.0 does not support sessions",
+		);
+		daemon.shutdown();
+	}
+
+	#[test]
+	fn test_retry() {
+		let retry = RetryPolicy {
+			max_retries: 2,
+			initial_backoff: Duration::from_millis(50),
+			max_backoff: Duration::from_millis(60),
+		};
+		let client = HalSimplicity::new("http://127.0.0.1:1").with_retry(retry);
+
+		// Read-only methods are retried after 50 and then 60ms.
+		let start = std::time::Instant::now();
+		assert!(client.call("getinfo", None).unwrap_err().is_transient());
+		assert!(start.elapsed() >= Duration::from_millis(110));
+
+		// Others are not.
+		let start = std::time::Instant::now();
+		assert!(client.call("tx_broadcast", None).is_err());
+		assert!(start.elapsed() < Duration::from_millis(50));
+
+		assert!(!ClientError::Unauthorized.is_transient());
+		let busy = RpcError::custom(SERVER_BUSY_CODE, "Server busy".to_string());
+		assert!(ClientError::Rpc(busy).is_transient());
+	}
+}
@@ -0,0 +1,589 @@
+//! Configuration file for the daemon
+//!
+//! Every setting is optional. For example:
+//!
+//! ```toml
+//! address = "127.0.0.1:28579"
+//! websocket_address = "127.0.0.1:28580"
+//! network = "liquidtestnet"
+//! data_dir = "/home/user/.hal-simplicity"
+//! record = "/home/user/.hal-simplicity/calls.ndjson"
+//!
+//! [backend]
+//! esplora = "https://blockstream.info/liquidtestnet/api"
+//! elements_url = "http://127.0.0.1:7041"
+//! elements_cookie = "/home/user/.elements/liquidv1/.cookie"
+//! default = "elements"
+//!
+//! [auth]
+//! cookie_file = "/home/user/.hal-simplicity/.cookie"
+//!
+//! # Also serve on IPv6, and on a unix socket without authentication.
+//! [[listeners]]
+//! address = "[::1]:28579"
+//!
+//! [[listeners]]
+//! unix_socket = "/home/user/.hal-simplicity/daemon.sock"
+//! auth = {}
+//!
+//! [methods]
+//! deny = ["tx_broadcast", "keypair_generate"]
+//! deny_secrets = true
+//!
+//! [cors]
+//! allowed_origins = ["http://localhost:8080"]
+//!
+//! [limits]
+//! max_body_size = 1048576
+//! requests_per_second = 10
+//! workers = 2
+//!
+//! [jobs]
+//! callback_secret = "correct horse battery staple"
+//! callback_allow = ["https://example.com/hooks/"]
+//!
+//! [log]
+//! level = "debug"
+//! file = "/home/user/.hal-simplicity/daemon.log"
+//!
+//! [telemetry]
+//! otlp_endpoint = "http://localhost:4318/v1/traces"
+//! ```
+
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+
+use serde::Deserialize;
+
+use super::auth::Auth;
+use super::backend::{BackendKind, ChainBackend, ElementsRpc, Esplora, NodeAuth};
+use super::cors::Cors;
+use super::handler::{DefaultRpcHandler, RpcMethod};
+use super::jobs::Jobs;
+use super::limits::Limits;
+use super::permissions::Permissions;
+use super::pool::{self, WorkerPool};
+use super::record::Recorder;
+use super::sessions::{self, PsetSessions};
+use super::store::{self, Store};
+use super::watch::WatchList;
+use super::{tls, DaemonError, Endpoint, HalSimplicityDaemon};
+use crate::Network;
+
+/// Default address for the TCP listener
+pub const DEFAULT_ADDRESS: &str = "127.0.0.1:28579";
+
+/// The daemon configuration.
+#[derive(Clone, Debug, Default, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Config {
+	/// TCP address to serve HTTP on. If unset, [`DEFAULT_ADDRESS`] is used
+	/// unless a unix socket is configured.
+	pub address: Option<String>,
+	/// Unix socket to serve HTTP on.
+	pub unix_socket: Option<PathBuf>,
+	/// TCP address to serve WebSocket on.
+	pub websocket_address: Option<String>,
+	/// Further addresses and sockets to serve on.
+	#[serde(default)]
+	pub listeners: Vec<ListenerConfig>,
+	/// Network used by requests which do not specify one.
+	pub network: Option<Network>,
+	/// Genesis hash used by requests which do not specify one.
+	pub genesis_hash: Option<String>,
+	/// Directory to keep state in across restarts. If unset, nothing is
+	/// written to disk.
+	pub data_dir: Option<PathBuf>,
+	/// File to append every call to, for `hal-simplicity replay`.
+	pub record: Option<PathBuf>,
+	#[serde(default)]
+	pub backend: BackendConfig,
+	#[serde(default)]
+	pub auth: AuthConfig,
+	#[serde(default)]
+	pub tls: TlsConfig,
+	#[serde(default)]
+	pub cors: CorsConfig,
+	#[serde(default)]
+	pub methods: MethodsConfig,
+	#[serde(default)]
+	pub limits: LimitsConfig,
+	#[serde(default)]
+	pub jobs: JobsConfig,
+	#[serde(default)]
+	pub log: LogConfig,
+	#[serde(default)]
+	pub telemetry: TelemetryConfig,
+}
+
+#[derive(Clone, Debug, Default, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct BackendConfig {
+	/// Base URL of an Esplora HTTP API to look up outputs with.
+	pub esplora: Option<String>,
+	/// URL of the JSON-RPC interface of an Elements node.
+	pub elements_url: Option<String>,
+	/// The node's cookie file, for authentication.
+	pub elements_cookie: Option<PathBuf>,
+	/// The node's `rpcuser`, instead of a cookie file.
+	pub elements_user: Option<String>,
+	/// The node's `rpcpassword`.
+	pub elements_password: Option<String>,
+	/// The backend used by requests which do not pick one, if both are
+	/// configured. Defaults to the Elements node.
+	pub default: Option<BackendKind>,
+}
+
+impl BackendConfig {
+	/// Create the configured backends.
+	pub fn backends(self) -> Result<BTreeMap<BackendKind, Arc<dyn ChainBackend>>, DaemonError> {
+		let auth = match (self.elements_cookie, self.elements_user, self.elements_password) {
+			(None, None, None) => NodeAuth::None,
+			(Some(cookie), None, None) => NodeAuth::CookieFile(cookie),
+			(None, Some(user), Some(password)) => NodeAuth::UserPass(user, password),
+			_ => {
+				return Err(DaemonError::Config(
+					"Elements node needs either a cookie file, or both a user and password"
+						.to_string(),
+				))
+			}
+		};
+		let mut backends = BTreeMap::<_, Arc<dyn ChainBackend>>::new();
+		if let Some(url) = self.esplora {
+			log::info!("Using Esplora at {}", url);
+			backends.insert(BackendKind::Esplora, Arc::new(Esplora::new(&url)));
+		}
+		if let Some(url) = self.elements_url {
+			log::info!("Using Elements node at {}", url);
+			backends.insert(BackendKind::Elements, Arc::new(ElementsRpc::new(&url, auth)));
+		}
+		if let Some(kind) = self.default {
+			if !backends.contains_key(&kind) {
+				return Err(DaemonError::Config(format!(
+					"The default backend {} is not configured",
+					kind
+				)));
+			}
+		}
+		Ok(backends)
+	}
+}
+
+/// A listener in addition to the top-level `address`, `unix_socket` and
+/// `websocket_address`. Exactly one of its addresses must be set.
+#[derive(Clone, Debug, Default, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct ListenerConfig {
+	/// TCP address to serve HTTP on.
+	pub address: Option<String>,
+	/// Unix socket to serve HTTP on.
+	pub unix_socket: Option<PathBuf>,
+	/// TCP address to serve WebSocket on.
+	pub websocket_address: Option<String>,
+	/// Authentication required on this listener instead of `[auth]`. An
+	/// empty table requires none.
+	pub auth: Option<AuthConfig>,
+}
+
+impl ListenerConfig {
+	/// The endpoint to serve on.
+	pub fn endpoint(&self) -> Result<Endpoint, DaemonError> {
+		match (&self.address, &self.unix_socket, &self.websocket_address) {
+			(Some(address), None, None) => Ok(Endpoint::Http(address.parse()?)),
+			(None, Some(path), None) => Ok(Endpoint::Unix(path.clone())),
+			(None, None, Some(address)) => Ok(Endpoint::WebSocket(address.parse()?)),
+			_ => Err(DaemonError::Config(
+				"A listener needs exactly one of address, unix_socket and websocket_address"
+					.to_string(),
+			)),
+		}
+	}
+}
+
+#[derive(Clone, Debug, Default, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct AuthConfig {
+	/// Write a random password to this file at startup and require it.
+	pub cookie_file: Option<PathBuf>,
+	/// Static tokens to accept.
+	#[serde(default)]
+	pub tokens: Vec<String>,
+}
+
+impl AuthConfig {
+	/// Create the configured authentication, writing the cookie file.
+	pub fn auth(self) -> Result<Auth, DaemonError> {
+		let mut auth = Auth::default();
+		for token in self.tokens {
+			auth.add_token(token);
+		}
+		if let Some(path) = self.cookie_file {
+			auth.add_cookie_file(&path)?;
+			log::info!("Wrote authentication cookie to {}", path.display());
+		}
+		Ok(auth)
+	}
+}
+
+#[derive(Clone, Debug, Default, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct TlsConfig {
+	/// PEM certificate chain.
+	pub cert: Option<PathBuf>,
+	/// PEM private key.
+	pub key: Option<PathBuf>,
+	/// Generate a self-signed certificate and write it to this file.
+	pub self_signed: Option<PathBuf>,
+}
+
+#[derive(Clone, Debug, Default, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct CorsConfig {
+	/// Origins which browsers may call the daemon from, or `"*"` for any.
+	#[serde(default)]
+	pub allowed_origins: Vec<String>,
+	/// HTTP methods allowed from those origins. Defaults to `GET` and `POST`.
+	pub allowed_methods: Option<Vec<String>>,
+}
+
+impl CorsConfig {
+	/// Create the configured CORS policy.
+	pub fn cors(&self) -> Result<Cors, DaemonError> {
+		let mut cors = Cors::default();
+		for origin in &self.allowed_origins {
+			cors = cors.allow_origin(origin.as_str());
+		}
+		if let Some(ref methods) = self.allowed_methods {
+			let methods = methods
+				.iter()
+				.map(|method| {
+					method.to_ascii_uppercase().parse().map_err(|_| {
+						DaemonError::Config(format!("Invalid HTTP method: {}", method))
+					})
+				})
+				.collect::<Result<_, _>>()?;
+			cors = cors.allow_methods(methods);
+		}
+		Ok(cors)
+	}
+}
+
+#[derive(Clone, Debug, Default, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct MethodsConfig {
+	/// Serve only these methods. If unset, every method is served.
+	pub allow: Option<Vec<String>>,
+	/// Never serve these methods.
+	#[serde(default)]
+	pub deny: Vec<String>,
+	/// Refuse requests which pass a secret key.
+	#[serde(default)]
+	pub deny_secrets: bool,
+}
+
+impl MethodsConfig {
+	/// Create the configured permissions.
+	pub fn permissions(&self) -> Result<Permissions, DaemonError> {
+		let parse = |names: &[String]| {
+			names
+				.iter()
+				.map(|name| {
+					name.parse::<RpcMethod>()
+						.map_err(|_| DaemonError::Config(format!("Unknown method: {}", name)))
+				})
+				.collect::<Result<Vec<_>, _>>()
+		};
+
+		let mut permissions = Permissions::default().deny(parse(&self.deny)?);
+		if let Some(ref allow) = self.allow {
+			permissions = permissions.allow_only(parse(allow)?);
+		}
+		if self.deny_secrets {
+			permissions = permissions.deny_secrets();
+		}
+		Ok(permissions)
+	}
+}
+
+#[derive(Clone, Debug, Default, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct LimitsConfig {
+	/// Maximum size in bytes of a request body or WebSocket message. If
+	/// unset, [`super::limits::DEFAULT_MAX_BODY_SIZE`] is used.
+	pub max_body_size: Option<usize>,
+	/// Average number of requests allowed per client and second. If unset,
+	/// requests are not rate limited.
+	pub requests_per_second: Option<f64>,
+	/// Number of requests a client may make at once. Defaults to one
+	/// second's worth of requests.
+	pub burst: Option<u32>,
+	/// Number of programs which may run at once. Defaults to the number of CPUs.
+	pub workers: Option<usize>,
+	/// Number of program runs which may wait for a worker. If unset,
+	/// [`super::pool::DEFAULT_QUEUE`] is used.
+	pub queue: Option<usize>,
+}
+
+#[derive(Clone, Debug, Default, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct JobsConfig {
+	/// Key to sign the results POSTed to job callback URLs with. If unset,
+	/// jobs cannot be given callback URLs.
+	pub callback_secret: Option<String>,
+	/// URL prefixes job callbacks may be sent to, such as
+	/// `https://example.com/hooks/`. Callbacks to any other URL are refused.
+	#[serde(default)]
+	pub callback_allow: Vec<String>,
+}
+
+#[derive(Clone, Debug, Default, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct LogConfig {
+	/// Log level (`error`, `warn`, `info`, `debug` or `trace`).
+	pub level: Option<log::LevelFilter>,
+	/// File to log to, in addition to stderr.
+	pub file: Option<PathBuf>,
+}
+
+#[derive(Clone, Debug, Default, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct TelemetryConfig {
+	/// OTLP/HTTP endpoint to export traces to, if the daemon is built with
+	/// the `otel` feature. The standard `OTEL_EXPORTER_OTLP_ENDPOINT`
+	/// environment variables are used if it is unset.
+	pub otlp_endpoint: Option<String>,
+}
+
+impl Config {
+	/// Read a configuration file.
+	pub fn from_file(path: &Path) -> Result<Self, DaemonError> {
+		let contents = fs::read_to_string(path)?;
+		toml::from_str(&contents)
+			.map_err(|e| DaemonError::Config(format!("{}: {}", path.display(), e)))
+	}
+
+	/// Create the daemon described by this configuration.
+	///
+	/// This writes the cookie file and self-signed certificate, if configured.
+	pub fn into_daemon(self) -> Result<HalSimplicityDaemon, DaemonError> {
+		let endpoints = self
+			.listeners
+			.iter()
+			.map(|listener| listener.endpoint())
+			.collect::<Result<Vec<_>, _>>()?;
+		let serves_http = self.unix_socket.is_some()
+			|| endpoints.iter().any(|e| matches!(e, Endpoint::Http(_) | Endpoint::Unix(_)));
+		let mut daemon = HalSimplicityDaemon::new(match self.address {
+			Some(ref address) => address,
+			None => DEFAULT_ADDRESS,
+		})?;
+		if self.address.is_none() && serves_http {
+			daemon = daemon.without_tcp();
+		}
+		if let Some(path) = self.unix_socket {
+			daemon = daemon.with_unix_socket(path);
+		}
+		if let Some(ref address) = self.websocket_address {
+			daemon = daemon.with_websocket(address)?;
+		}
+		for (listener, endpoint) in self.listeners.into_iter().zip(endpoints) {
+			let auth = listener.auth.map(AuthConfig::auth).transpose()?;
+			daemon = daemon.with_endpoint(endpoint, auth);
+		}
+
+		let mut handler = DefaultRpcHandler::default();
+		if let Some(network) = self.network {
+			handler.network = network;
+		}
+		handler.genesis_hash = self.genesis_hash;
+		handler.permissions = self.methods.permissions()?;
+		if let Some(ref dir) = self.data_dir {
+			fs::create_dir_all(dir)?;
+			let store = Store::open(dir)?;
+			handler.sessions = PsetSessions::persistent(
+				sessions::DEFAULT_MAX_SESSIONS,
+				store.tree(store::SESSIONS)?,
+			)?;
+			handler.watch = WatchList::persistent(store.tree(store::WATCH)?)?;
+			log::info!("Storing state in {}", dir.display());
+		}
+		handler.default_backend = self.backend.default;
+		handler.backends = self.backend.backends()?;
+		if self.limits.workers.is_some() || self.limits.queue.is_some() {
+			let default = WorkerPool::default();
+			handler.workers = Arc::new(WorkerPool::new(
+				self.limits.workers.unwrap_or(default.workers()),
+				self.limits.queue.unwrap_or(pool::DEFAULT_QUEUE),
+			));
+		}
+		if let Some(ref path) = self.record {
+			handler.recorder = Some(Recorder::open(path)?);
+			log::info!("Recording calls to {}", path.display());
+		}
+		if let Some(secret) = self.jobs.callback_secret {
+			handler.jobs = Jobs::default()
+				.with_callback_secret(secret)
+				.with_callback_allow(self.jobs.callback_allow);
+		}
+		daemon = daemon.with_handler(handler);
+
+		daemon = daemon.with_auth(self.auth.auth()?);
+
+		let mut limits = Limits::default();
+		if let Some(max_body_size) = self.limits.max_body_size {
+			limits = limits.with_max_body_size(max_body_size);
+		}
+		if let Some(rate) = self.limits.requests_per_second {
+			if rate.is_nan() || rate <= 0.0 {
+				return Err(DaemonError::Config(
+					"requests_per_second must be positive".to_string(),
+				));
+			}
+			let burst = self.limits.burst.unwrap_or(rate.ceil() as u32);
+			limits = limits.with_rate_limit(rate, burst);
+		}
+		daemon = daemon.with_limits(limits);
+		daemon = daemon.with_cors(self.cors.cors()?);
+
+		match (self.tls.cert, self.tls.key, self.tls.self_signed) {
+			(Some(cert), Some(key), None) => daemon = daemon.with_tls(tls::load(&cert, &key)?),
+			(None, None, Some(cert)) => {
+				daemon = daemon.with_tls(tls::self_signed(&cert)?);
+				log::info!("Wrote self-signed TLS certificate to {}", cert.display());
+			}
+			(None, None, None) => {}
+			_ => return Err(DaemonError::Config(
+				"TLS needs either both a certificate and key, or a self-signed certificate path"
+					.to_string(),
+			)),
+		}
+
+		Ok(daemon)
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn test_parse() {
+		let config: Config = toml::from_str(
+			r#"
+			address = "127.0.0.1:1234"
+			network = "liquidtestnet"
+
+			[backend]
+			esplora = "http://localhost:3000"
+
+			[auth]
+			tokens = ["abc"]
+
+			[methods]
+			deny = ["tx_broadcast"]
+
+			[limits]
+			max_body_size = 1024
+			requests_per_second = 2.5
+
+			[jobs]
+			callback_secret = "hunter2"
+			callback_allow = ["http://localhost:8080/"]
+
+			[log]
+			level = "debug"
+			"#,
+		)
+		.unwrap();
+		assert_eq!(config.address.as_deref(), Some("127.0.0.1:1234"));
+		assert_eq!(config.network, Some(Network::LiquidTestnet));
+		assert_eq!(config.backend.esplora.as_deref(), Some("http://localhost:3000"));
+		assert_eq!(config.auth.tokens, vec!["abc".to_string()]);
+		assert!(!config.methods.permissions().unwrap().allows(RpcMethod::TxBroadcast));
+		assert_eq!(config.limits.max_body_size, Some(1024));
+		assert_eq!(config.limits.requests_per_second, Some(2.5));
+		assert_eq!(config.jobs.callback_secret.as_deref(), Some("hunter2"));
+		assert_eq!(config.jobs.callback_allow, vec!["http://localhost:8080/".to_string()]);
+		assert_eq!(config.log.level, Some(log::LevelFilter::Debug));
+
+		assert!(toml::from_str::<Config>("adress = \"127.0.0.1:1234\"").is_err());
+
+		let config: Config = toml::from_str("[methods]\ndeny = [\"tx_brodcast\"]").unwrap();
+		assert!(matches!(config.methods.permissions(), Err(DaemonError::Config(_))));
+	}
+
+	#[test]
+	fn test_listeners() {
+		let config: Config = toml::from_str(
+			r#"
+			unix_socket = "/tmp/hal-simplicity-test.sock"
+
+			[[listeners]]
+			address = "[::1]:28579"
+			auth = { tokens = ["abc"] }
+
+			[[listeners]]
+			websocket_address = "127.0.0.1:28580"
+			"#,
+		)
+		.unwrap();
+		let daemon = config.into_daemon().unwrap();
+		let endpoints = daemon.endpoints().cloned().collect::<Vec<_>>();
+		assert_eq!(
+			endpoints,
+			vec![
+				Endpoint::Unix("/tmp/hal-simplicity-test.sock".into()),
+				Endpoint::Http("[::1]:28579".parse().unwrap()),
+				Endpoint::WebSocket("127.0.0.1:28580".parse().unwrap()),
+			]
+		);
+
+		// Without any HTTP listener, the default address is used.
+		let daemon = Config::default().into_daemon().unwrap();
+		let endpoints = daemon.endpoints().cloned().collect::<Vec<_>>();
+		assert_eq!(endpoints, vec![Endpoint::Http(DEFAULT_ADDRESS.parse().unwrap())]);
+
+		let config: Config =
+			toml::from_str("[[listeners]]\naddress = \"[::1]:1\"\nunix_socket = \"/tmp/x.sock\"")
+				.unwrap();
+		assert!(matches!(config.into_daemon(), Err(DaemonError::Config(_))));
+	}
+
+	#[test]
+	fn test_tls_needs_key() {
+		let config = Config {
+			tls: TlsConfig {
+				cert: Some("cert.pem".into()),
+				..Default::default()
+			},
+			..Default::default()
+		};
+		assert!(matches!(config.into_daemon(), Err(DaemonError::Config(_))));
+	}
+
+	#[test]
+	fn test_backends() {
+		let config = BackendConfig {
+			esplora: Some("http://localhost:3000".to_string()),
+			elements_url: Some("http://localhost:7041".to_string()),
+			..Default::default()
+		};
+		assert_eq!(config.backends().unwrap().len(), 2);
+
+		let config = BackendConfig {
+			esplora: Some("http://localhost:3000".to_string()),
+			default: Some(BackendKind::Elements),
+			..Default::default()
+		};
+		assert!(matches!(config.backends(), Err(DaemonError::Config(_))));
+
+		let config = BackendConfig {
+			elements_url: Some("http://localhost:7041".to_string()),
+			elements_user: Some("user".to_string()),
+			..Default::default()
+		};
+		assert!(matches!(config.backends(), Err(DaemonError::Config(_))));
+	}
+}
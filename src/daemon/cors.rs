@@ -0,0 +1,125 @@
+//! Cross-origin requests from browsers
+//!
+//! Browsers only let a page call the daemon from another origin, such as the
+//! Simplicity web IDE during development, if the daemon allows that origin.
+//! With no allowed origins, no CORS headers are sent and such calls fail.
+//!
+//! Preflight (`OPTIONS`) requests are answered before authentication, since
+//! browsers send them without credentials.
+
+use hyper::header::{self, HeaderMap, HeaderValue};
+use hyper::Method;
+
+/// How long browsers may cache a preflight response, in seconds.
+const MAX_AGE: &str = "600";
+
+/// The origins and methods allowed for cross-origin requests.
+#[derive(Clone, Debug)]
+pub struct Cors {
+	origins: Vec<String>,
+	methods: Vec<Method>,
+}
+
+impl Default for Cors {
+	fn default() -> Self {
+		Self {
+			origins: vec![],
+			methods: vec![Method::GET, Method::POST],
+		}
+	}
+}
+
+impl Cors {
+	/// Allow requests from `origin`, such as `http://localhost:8080`, or from
+	/// any origin if it is `*`.
+	pub fn allow_origin(mut self, origin: impl Into<String>) -> Self {
+		self.origins.push(origin.into().trim_end_matches('/').to_owned());
+		self
+	}
+
+	/// Allow only the given methods, instead of `GET` and `POST`.
+	pub fn allow_methods(mut self, methods: Vec<Method>) -> Self {
+		self.methods = methods;
+		self
+	}
+
+	/// Whether any cross-origin requests are allowed.
+	pub fn is_enabled(&self) -> bool {
+		!self.origins.is_empty()
+	}
+
+	/// The value of `Access-Control-Allow-Origin` for a request from `origin`.
+	fn allowed_origin(&self, origin: &HeaderValue) -> Option<HeaderValue> {
+		if self.origins.iter().any(|o| o == "*") {
+			Some(HeaderValue::from_static("*"))
+		} else if self.origins.iter().any(|o| o.as_bytes() == origin.as_bytes()) {
+			Some(origin.clone())
+		} else {
+			None
+		}
+	}
+
+	/// Add the CORS headers for a request from `origin` to a response.
+	pub fn apply(&self, origin: Option<&HeaderValue>, headers: &mut HeaderMap) {
+		if !self.is_enabled() {
+			return;
+		}
+		// The response depends on the origin unless every origin is allowed.
+		if !self.origins.iter().any(|o| o == "*") {
+			headers.append(header::VARY, HeaderValue::from_static("Origin"));
+		}
+		if let Some(allowed) = origin.and_then(|origin| self.allowed_origin(origin)) {
+			headers.insert(header::ACCESS_CONTROL_ALLOW_ORIGIN, allowed);
+		}
+	}
+
+	/// Add the headers answering a preflight request from `origin`.
+	pub fn apply_preflight(&self, origin: Option<&HeaderValue>, headers: &mut HeaderMap) {
+		self.apply(origin, headers);
+		if !headers.contains_key(header::ACCESS_CONTROL_ALLOW_ORIGIN) {
+			return;
+		}
+		let methods = self.methods.iter().map(Method::as_str).collect::<Vec<_>>().join(", ");
+		if let Ok(methods) = HeaderValue::from_str(&methods) {
+			headers.insert(header::ACCESS_CONTROL_ALLOW_METHODS, methods);
+		}
+		headers.insert(
+			header::ACCESS_CONTROL_ALLOW_HEADERS,
+			HeaderValue::from_static("Authorization, Content-Type"),
+		);
+		headers.insert(header::ACCESS_CONTROL_MAX_AGE, HeaderValue::from_static(MAX_AGE));
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn test_cors() {
+		let ide = HeaderValue::from_static("http://localhost:8080");
+		let other = HeaderValue::from_static("https://example.com");
+
+		let mut headers = HeaderMap::new();
+		Cors::default().apply_preflight(Some(&ide), &mut headers);
+		assert!(headers.is_empty());
+
+		let cors = Cors::default().allow_origin("http://localhost:8080/");
+		let mut headers = HeaderMap::new();
+		cors.apply(Some(&other), &mut headers);
+		assert!(!headers.contains_key(header::ACCESS_CONTROL_ALLOW_ORIGIN));
+		assert_eq!(headers[header::VARY], "Origin");
+
+		let mut headers = HeaderMap::new();
+		cors.apply_preflight(Some(&ide), &mut headers);
+		assert_eq!(headers[header::ACCESS_CONTROL_ALLOW_ORIGIN], ide);
+		assert_eq!(headers[header::ACCESS_CONTROL_ALLOW_METHODS], "GET, POST");
+
+		let cors = Cors::default().allow_origin("*").allow_methods(vec![Method::POST]);
+		let mut headers = HeaderMap::new();
+		cors.apply_preflight(Some(&other), &mut headers);
+		assert_eq!(headers[header::ACCESS_CONTROL_ALLOW_ORIGIN], "*");
+		assert_eq!(headers[header::ACCESS_CONTROL_ALLOW_METHODS], "POST");
+		assert!(!headers.contains_key(header::VARY));
+	}
+}
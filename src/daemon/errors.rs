@@ -0,0 +1,67 @@
+//! Error codes for failed actions
+//!
+//! Failed actions are reported with the codes of [`crate::actions::error`]
+//! as JSON-RPC error codes, and its data as the error data, such as
+//! `{"subsystem": "pset", "failure": "missing", "missing_field": "witness_utxo", "input": 2}`.
+
+use serde_json::{json, Value};
+
+pub use crate::actions::error::{ActionError, Failure, Subsystem};
+
+use super::backend::BackendError;
+use super::jsonrpc::RpcError;
+
+/// Report a failed action with its code and data.
+pub fn action_error<E: ActionError>(e: E) -> RpcError {
+	RpcError::custom(e.code(), e.to_string()).with_data(e.data())
+}
+
+impl ActionError for BackendError {
+	fn subsystem(&self) -> Subsystem {
+		Subsystem::Backend
+	}
+
+	fn failure(&self) -> (Failure, Value) {
+		match self {
+			Self::Request(_) => Failure::Unavailable.only(),
+			Self::Response(_) => Failure::Invalid.only(),
+			Self::Node {
+				code,
+				..
+			} => (Failure::Rejected, json!({ "node_code": code })),
+			Self::Unsupported(_) => Failure::Unsupported.only(),
+		}
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use crate::actions::simplicity::pset::{PsetError, PsetRunError};
+	use crate::daemon::handler::DefaultRpcHandler;
+	use crate::daemon::jsonrpc::RpcHandler;
+
+	#[test]
+	fn test_action_error() {
+		let err = action_error(PsetRunError::SharedError(PsetError::MissingWitnessUtxo(2)));
+		assert_eq!(err.code, 1401);
+		assert_eq!(
+			err.data,
+			Some(json!({
+				"subsystem": "pset",
+				"failure": "missing",
+				"missing_field": "witness_utxo",
+				"input": 2,
+			}))
+		);
+
+		let handler = DefaultRpcHandler::default();
+		let err = handler.handle("tx_decode", Some(json!({ "raw_tx": "00" }))).unwrap_err();
+		assert_eq!(err.code, Subsystem::Tx.base() + Failure::Invalid as i64);
+		assert_eq!(Failure::of_code(err.code), Some(Failure::Invalid));
+		assert_eq!(Failure::of_code(-32603), None);
+		let err = handler.handle("address_create", Some(json!({}))).unwrap_err();
+		assert_eq!(err.code, 1001);
+		assert_eq!(err.data.unwrap()["missing_field"], "pubkey");
+	}
+}
@@ -1,130 +1,406 @@
+use std::collections::BTreeMap;
+use std::ops::ControlFlow;
 use std::str::FromStr;
+use std::sync::{Arc, OnceLock};
+use std::time::Instant;
+
+use tokio::sync::broadcast;
 
 use super::jsonrpc::{ErrorCode, JsonRpcService, RpcError, RpcHandler};
-use serde_json::Value;
+use serde_json::{json, Value};
 
+use super::backend::{BackendError, BackendKind, ChainBackend};
+use super::cache::ProgramCache;
+use super::errors::action_error;
+use super::jobs::Jobs;
+use super::permissions::Permissions;
+use super::pool::WorkerPool;
+use super::record::Recorder;
+use super::schema;
+use super::sessions::PsetSessions;
 use super::types::*;
+use super::watch::{self, WatchList};
 use crate::actions;
+use crate::api::Api;
 
 use crate::Network;
 
 /// RPC method names
-#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
 pub enum RpcMethod {
+	GetInfo,
+	Help,
+	RpcDiscover,
+	Stop,
 	AddressCreate,
 	AddressInspect,
 	BlockCreate,
 	BlockDecode,
+	BlockStats,
+	BlockVerifyChain,
+	BlockVerifySignblock,
+	BlockFetch,
 	TxCreate,
 	TxDecode,
+	TxBroadcast,
+	TxFetch,
 	KeypairGenerate,
 	SimplicityInfo,
 	SimplicitySighash,
 	PsetCreate,
 	PsetExtract,
+	PsetOpen,
+	PsetGet,
+	PsetClose,
+	PsetHistory,
+	PsetRevert,
 	PsetFinalize,
 	PsetRun,
 	PsetUpdateInput,
+	WatchAdd,
+	WatchRemove,
+	WatchList,
+	WatchUnspent,
+	JobSubmit,
+	JobStatus,
+	JobResult,
+	Schema,
+}
+
+impl RpcMethod {
+	/// Every supported method.
+	pub const ALL: &'static [RpcMethod] = &[
+		Self::GetInfo,
+		Self::Help,
+		Self::RpcDiscover,
+		Self::Stop,
+		Self::AddressCreate,
+		Self::AddressInspect,
+		Self::BlockCreate,
+		Self::BlockDecode,
+		Self::BlockStats,
+		Self::BlockVerifyChain,
+		Self::BlockVerifySignblock,
+		Self::BlockFetch,
+		Self::TxCreate,
+		Self::TxDecode,
+		Self::TxBroadcast,
+		Self::TxFetch,
+		Self::KeypairGenerate,
+		Self::SimplicityInfo,
+		Self::SimplicitySighash,
+		Self::PsetCreate,
+		Self::PsetExtract,
+		Self::PsetOpen,
+		Self::PsetGet,
+		Self::PsetClose,
+		Self::PsetHistory,
+		Self::PsetRevert,
+		Self::PsetFinalize,
+		Self::PsetRun,
+		Self::PsetUpdateInput,
+		Self::WatchAdd,
+		Self::WatchRemove,
+		Self::WatchList,
+		Self::WatchUnspent,
+		Self::JobSubmit,
+		Self::JobStatus,
+		Self::JobResult,
+		Self::Schema,
+	];
+
+	/// The JSON-RPC name of the method.
+	pub fn as_str(self) -> &'static str {
+		match self {
+			Self::GetInfo => "getinfo",
+			Self::Help => "help",
+			Self::RpcDiscover => "rpc.discover",
+			Self::Stop => "stop",
+			Self::AddressCreate => "address_create",
+			Self::AddressInspect => "address_inspect",
+			Self::BlockCreate => "block_create",
+			Self::BlockDecode => "block_decode",
+			Self::BlockStats => "block_stats",
+			Self::BlockVerifyChain => "block_verify_chain",
+			Self::BlockVerifySignblock => "block_verify_signblock",
+			Self::BlockFetch => "block_fetch",
+			Self::TxCreate => "tx_create",
+			Self::TxDecode => "tx_decode",
+			Self::TxBroadcast => "tx_broadcast",
+			Self::TxFetch => "tx_fetch",
+			Self::KeypairGenerate => "keypair_generate",
+			Self::SimplicityInfo => "simplicity_info",
+			Self::SimplicitySighash => "simplicity_sighash",
+			Self::PsetCreate => "pset_create",
+			Self::PsetExtract => "pset_extract",
+			Self::PsetOpen => "pset_open",
+			Self::PsetGet => "pset_get",
+			Self::PsetClose => "pset_close",
+			Self::PsetHistory => "pset_history",
+			Self::PsetRevert => "pset_revert",
+			Self::PsetFinalize => "pset_finalize",
+			Self::PsetRun => "pset_run",
+			Self::PsetUpdateInput => "pset_update_input",
+			Self::WatchAdd => "watch_add",
+			Self::WatchRemove => "watch_remove",
+			Self::WatchList => "watch_list",
+			Self::WatchUnspent => "watch_unspent",
+			Self::JobSubmit => "job_submit",
+			Self::JobStatus => "job_status",
+			Self::JobResult => "job_result",
+			Self::Schema => "schema",
+		}
+	}
+
+	/// Whether the method has no effect besides returning its result, so that
+	/// a failed call can safely be repeated.
+	pub fn is_read_only(self) -> bool {
+		!matches!(
+			self,
+			Self::Stop
+				| Self::TxBroadcast
+				| Self::KeypairGenerate
+				| Self::PsetOpen
+				| Self::PsetClose
+				| Self::PsetRevert
+				| Self::PsetFinalize
+				| Self::PsetUpdateInput
+				| Self::WatchAdd
+				| Self::WatchRemove
+				| Self::JobSubmit
+				| Self::JobResult
+		)
+	}
 }
 
 impl FromStr for RpcMethod {
 	type Err = RpcError;
 
 	fn from_str(s: &str) -> Result<Self, RpcError> {
-		let method = match s {
-			"address_create" => Self::AddressCreate,
-			"address_inspect" => Self::AddressInspect,
-			"block_create" => Self::BlockCreate,
-			"block_decode" => Self::BlockDecode,
-			"tx_create" => Self::TxCreate,
-			"tx_decode" => Self::TxDecode,
-			"keypair_generate" => Self::KeypairGenerate,
-			"simplicity_info" => Self::SimplicityInfo,
-			"simplicity_sighash" => Self::SimplicitySighash,
-			"pset_create" => Self::PsetCreate,
-			"pset_extract" => Self::PsetExtract,
-			"pset_finalize" => Self::PsetFinalize,
-			"pset_run" => Self::PsetRun,
-			"pset_update_input" => Self::PsetUpdateInput,
-			_ => return Err(RpcError::new(ErrorCode::MethodNotFound)),
-		};
-
-		Ok(method)
+		Self::ALL
+			.iter()
+			.copied()
+			.find(|method| method.as_str() == s)
+			.ok_or_else(|| RpcError::new(ErrorCode::MethodNotFound))
 	}
 }
 
 /// Default RPC handler that provides basic methods
-#[derive(Default)]
-pub struct DefaultRpcHandler;
+pub struct DefaultRpcHandler {
+	/// The network used by methods whose request does not specify one.
+	pub network: Network,
+	/// The genesis hash used by methods whose request does not specify one. If
+	/// unset, the genesis hash of the backend's chain is used, or failing that
+	/// the Liquid testnet genesis hash.
+	pub genesis_hash: Option<String>,
+	/// Which methods may be called.
+	pub permissions: Permissions,
+	/// Limits how many programs run at once.
+	pub workers: Arc<WorkerPool>,
+	/// Recently decoded programs.
+	pub programs: ProgramCache,
+	/// PSETs stored between calls.
+	pub sessions: PsetSessions,
+	/// Addresses and programs registered with `watch_add`.
+	pub watch: WatchList,
+	/// Methods running in the background.
+	pub jobs: Jobs,
+	/// Where to look up outputs and send transactions, if anywhere.
+	pub backends: BTreeMap<BackendKind, Arc<dyn ChainBackend>>,
+	/// The backend used by requests which do not pick one. If unset, the
+	/// first configured backend is used.
+	pub default_backend: Option<BackendKind>,
+	/// Where each call is recorded, if anywhere.
+	pub recorder: Option<Recorder>,
+	/// The genesis hash reported by the default backend, once asked for.
+	backend_genesis_hash: OnceLock<String>,
+	/// When the handler was created, for reporting uptime.
+	started: Instant,
+	/// The largest request the daemon serving this handler accepts, if any.
+	pub(super) max_request_size: OnceLock<usize>,
+	/// Channel to shut down the daemon serving this handler, if any.
+	pub(super) shutdown: Option<broadcast::Sender<()>>,
+}
+
+impl Default for DefaultRpcHandler {
+	fn default() -> Self {
+		Self {
+			network: Network::Liquid,
+			genesis_hash: None,
+			permissions: Permissions::default(),
+			workers: Arc::new(WorkerPool::default()),
+			programs: ProgramCache::default(),
+			sessions: PsetSessions::default(),
+			watch: WatchList::new(),
+			jobs: Jobs::default(),
+			backends: BTreeMap::new(),
+			default_backend: None,
+			recorder: None,
+			backend_genesis_hash: OnceLock::new(),
+			started: Instant::now(),
+			max_request_size: OnceLock::new(),
+			shutdown: None,
+		}
+	}
+}
 
 impl RpcHandler for DefaultRpcHandler {
 	fn handle(&self, method: &str, params: Option<Value>) -> Result<Value, RpcError> {
+		let Some(ref recorder) = self.recorder else {
+			return self.handle_unrecorded(method, params);
+		};
+		let outcome = self.handle_unrecorded(method, params.clone());
+		recorder.record(method, params, &outcome);
+		outcome
+	}
+}
+
+impl DefaultRpcHandler {
+	fn handle_unrecorded(&self, method: &str, params: Option<Value>) -> Result<Value, RpcError> {
 		let rpc_method = RpcMethod::from_str(method)?;
+		self.permissions.check(rpc_method, params.as_ref())?;
+		check_network_param(params.as_ref())?;
+
+		self.dispatch(rpc_method, params).map_err(|e| {
+			// Tell the caller what the method expects.
+			if e.code == ErrorCode::InvalidParams.code() && e.data.is_none() {
+				e.with_data(json!({ "params": schema::describe(rpc_method).params }))
+			} else {
+				e
+			}
+		})
+	}
 
+	fn dispatch(&self, rpc_method: RpcMethod, params: Option<Value>) -> Result<Value, RpcError> {
 		match rpc_method {
-			RpcMethod::AddressCreate => {
-				let req: AddressCreateRequest = parse_params(params)?;
-				let result = actions::address::address_create(
-					req.pubkey.as_deref(),
-					req.script.as_deref(),
-					req.blinder.as_deref(),
-					req.network.unwrap_or(Network::Liquid),
+			RpcMethod::GetInfo => serialize_result(GetInfoResponse {
+				version: env!("CARGO_PKG_VERSION").to_string(),
+				methods: self.methods().map(|m| m.as_str().to_string()).collect(),
+				network: self.network,
+				genesis_hash: self.genesis_hash.clone(),
+				backends: self.backends.values().map(|backend| backend.name()).collect(),
+				uptime: self.started.elapsed().as_secs(),
+				capabilities: self.capabilities().map(|c| c.as_str().to_string()).collect(),
+				formats: match self.capabilities().any(|c| c == Capability::Streaming) {
+					true => vec!["json".to_string(), "ndjson".to_string()],
+					false => vec!["json".to_string()],
+				},
+				max_request_size: self.max_request_size.get().copied(),
+			}),
+			RpcMethod::Help => {
+				let req: HelpRequest = parse_params(Some(params.unwrap_or_else(|| json!({}))))?;
+				let methods = match req.method {
+					Some(name) => vec![RpcMethod::from_str(&name).map_err(|_| {
+						RpcError::custom(
+							ErrorCode::InvalidParams.code(),
+							format!("Unknown method: {}", name),
+						)
+					})?],
+					None => self.methods().collect(),
+				};
+
+				serialize_result(
+					methods.into_iter().map(schema::describe).collect::<HelpResponse>(),
 				)
-				.map_err(|e| RpcError::custom(ErrorCode::InternalError.code(), e.to_string()))?;
+			}
+			RpcMethod::RpcDiscover => Ok(schema::openrpc(self.methods())),
+			RpcMethod::Schema => {
+				let req: SchemaRequest = parse_params(Some(params.unwrap_or_else(|| json!({}))))?;
+				let schemas = match req.type_ {
+					Some(name) => {
+						let schema = schema::output_schema(&name).ok_or_else(|| {
+							RpcError::custom(
+								ErrorCode::InvalidParams.code(),
+								format!("Unknown type: {}", name),
+							)
+						})?;
+						BTreeMap::from([(name, schema)])
+					}
+					None => schema::output_schemas(),
+				};
+				serialize_result(schemas)
+			}
+			RpcMethod::Stop => {
+				let shutdown = self.shutdown.as_ref().ok_or_else(|| {
+					RpcError::custom(
+						ErrorCode::InternalError.code(),
+						"Not running in a daemon".to_string(),
+					)
+				})?;
+				log::info!("Shutting down on request...");
+				let _ = shutdown.send(());
 
-				serialize_result(result)
+				serialize_result("hal-simplicity-daemon stopping")
+			}
+			RpcMethod::AddressCreate => {
+				let req: AddressCreateRequest = parse_params(params)?;
+				serialize_result(self.api().address_create(&req).map_err(action_error)?)
 			}
 			RpcMethod::AddressInspect => {
 				let req: AddressInspectRequest = parse_params(params)?;
-				let result = actions::address::address_inspect(&req.address).map_err(|e| {
-					RpcError::custom(ErrorCode::InternalError.code(), e.to_string())
-				})?;
-
-				serialize_result(result)
+				serialize_result(self.api().address_inspect(&req).map_err(action_error)?)
 			}
 			RpcMethod::BlockCreate => {
 				let req: BlockCreateRequest = parse_params(params)?;
-
-				let block = actions::block::block_create(req.block_info).map_err(|e| {
-					RpcError::custom(ErrorCode::InternalError.code(), e.to_string())
-				})?;
-
-				let raw_block = hex::encode(elements::encode::serialize(&block));
-				serialize_result(BlockCreateResponse {
-					raw_block,
-				})
+				serialize_result(self.api().block_create(&req).map_err(action_error)?)
 			}
 			RpcMethod::BlockDecode => {
 				let req: BlockDecodeRequest = parse_params(params)?;
-				let result = actions::block::block_decode(
-					&req.raw_block,
-					req.network.unwrap_or(Network::Liquid),
-					req.txids.unwrap_or(false),
-				)
-				.map_err(|e| RpcError::custom(ErrorCode::InternalError.code(), e.to_string()))?;
+				serialize_result(self.api().block_decode(&req).map_err(action_error)?)
+			}
+			RpcMethod::BlockStats => {
+				let req: BlockStatsRequest = parse_params(params)?;
+				serialize_result(self.api().block_stats(&req).map_err(action_error)?)
+			}
+			RpcMethod::BlockVerifyChain => {
+				let req: BlockVerifyChainRequest = parse_params(params)?;
+				serialize_result(self.api().block_verify_chain(&req).map_err(action_error)?)
+			}
+			RpcMethod::BlockVerifySignblock => {
+				let req: BlockVerifySignblockRequest = parse_params(params)?;
+				serialize_result(self.api().block_verify_signblock(&req).map_err(action_error)?)
+			}
+			RpcMethod::BlockFetch => {
+				let req: BlockFetchRequest = parse_params(params)?;
+				let block = self.backend(req.backend)?.block(req.block).map_err(action_error)?;
 
-				serialize_result(result)
+				serialize_result(BlockFetchResponse {
+					raw_block: elements::encode::serialize_hex(&block),
+				})
 			}
 			RpcMethod::TxCreate => {
 				let req: TxCreateRequest = parse_params(params)?;
-				let tx = actions::tx::tx_create(req.tx_info).map_err(|e| {
-					RpcError::custom(ErrorCode::InternalError.code(), e.to_string())
-				})?;
-
-				let raw_tx = hex::encode(elements::encode::serialize(&tx));
-				serialize_result(TxCreateResponse {
-					raw_tx,
-				})
+				serialize_result(self.api().tx_create(&req).map_err(action_error)?)
 			}
 			RpcMethod::TxDecode => {
 				let req: TxDecodeRequest = parse_params(params)?;
-				let result =
-					actions::tx::tx_decode(&req.raw_tx, req.network.unwrap_or(Network::Liquid))
-						.map_err(|e| {
-							RpcError::custom(ErrorCode::InternalError.code(), e.to_string())
-						})?;
+				serialize_result(self.api().tx_decode(&req).map_err(action_error)?)
+			}
+			RpcMethod::TxBroadcast => {
+				let req: TxBroadcastRequest = parse_params(params)?;
+				let tx: elements::Transaction = hex::decode(&req.raw_tx)
+					.ok()
+					.and_then(|bytes| elements::encode::deserialize(&bytes).ok())
+					.ok_or_else(|| {
+						RpcError::custom(
+							ErrorCode::InvalidParams.code(),
+							"Invalid raw transaction".to_string(),
+						)
+					})?;
+				let txid = self.backend(req.backend)?.broadcast(&tx).map_err(action_error)?;
+				log::info!("Broadcast transaction {}", txid);
 
-				serialize_result(result)
+				serialize_result(TxBroadcastResponse {
+					txid,
+				})
+			}
+			RpcMethod::TxFetch => {
+				let req: TxFetchRequest = parse_params(params)?;
+				let tx = self.backend(req.backend)?.transaction(req.txid).map_err(action_error)?;
+
+				serialize_result(TxFetchResponse {
+					raw_tx: elements::encode::serialize_hex(&tx),
+				})
 			}
 			RpcMethod::KeypairGenerate => {
 				let result = actions::keypair::keypair_generate();
@@ -133,14 +409,7 @@ impl RpcHandler for DefaultRpcHandler {
 			}
 			RpcMethod::SimplicityInfo => {
 				let req: SimplicityInfoRequest = parse_params(params)?;
-				let result = actions::simplicity::simplicity_info(
-					&req.program,
-					req.witness.as_deref(),
-					req.state.as_deref(),
-				)
-				.map_err(|e| RpcError::custom(ErrorCode::InternalError.code(), e.to_string()))?;
-
-				serialize_result(result)
+				serialize_result(self.api().simplicity_info(&req).map_err(action_error)?)
 			}
 			RpcMethod::SimplicitySighash => {
 				let req: SimplicitySighashRequest = parse_params(params)?;
@@ -150,100 +419,401 @@ impl RpcHandler for DefaultRpcHandler {
 					.as_ref()
 					.map(|v| v.iter().map(String::as_str).collect::<Vec<_>>());
 
+				let genesis_hash =
+					self.genesis_hash_for(req.genesis_hash.as_deref(), req.network)?;
 				let result = actions::simplicity::simplicity_sighash(
 					&req.tx,
 					&req.input_index.to_string(),
 					&req.cmr,
 					req.control_block.as_deref(),
-					req.genesis_hash.as_deref(),
+					genesis_hash.as_deref(),
 					req.secret_key.as_deref(),
 					req.public_key.as_deref(),
 					req.signature.as_deref(),
 					input_utxos.as_deref(),
 				)
-				.map_err(|e| RpcError::custom(ErrorCode::InternalError.code(), e.to_string()))?;
+				.map_err(action_error)?;
 				serialize_result(result)
 			}
 			RpcMethod::PsetCreate => {
 				let req: PsetCreateRequest = parse_params(params)?;
-				let result = actions::simplicity::pset::pset_create(&req.inputs, &req.outputs)
-					.map_err(|e| {
-						RpcError::custom(ErrorCode::InternalError.code(), e.to_string())
-					})?;
-
-				serialize_result(result)
+				serialize_result(self.api().pset_create(&req).map_err(action_error)?)
 			}
 			RpcMethod::PsetExtract => {
 				let req: PsetExtractRequest = parse_params(params)?;
-				let raw_tx = actions::simplicity::pset::pset_extract(&req.pset).map_err(|e| {
-					RpcError::custom(ErrorCode::InternalError.code(), e.to_string())
-				})?;
+				serialize_result(self.api().pset_extract(&req).map_err(action_error)?)
+			}
+			RpcMethod::PsetOpen => {
+				let req: PsetOpenRequest = parse_params(params)?;
+				let session = self.sessions.open(req.pset)?;
 
-				serialize_result(PsetExtractResponse {
-					raw_tx,
+				serialize_result(PsetOpenResponse {
+					session,
+				})
+			}
+			RpcMethod::PsetGet => {
+				let req: PsetGetRequest = parse_params(params)?;
+				let pset = self.sessions.get(&req.session)?;
+
+				serialize_result(PsetGetResponse {
+					pset,
+				})
+			}
+			RpcMethod::PsetClose => {
+				let req: PsetCloseRequest = parse_params(params)?;
+				let pset = self.sessions.close(&req.session)?;
+
+				serialize_result(PsetCloseResponse {
+					pset,
+				})
+			}
+			RpcMethod::PsetHistory => {
+				let req: PsetHistoryRequest = parse_params(params)?;
+				let revisions = self.sessions.history(&req.session)?;
+
+				serialize_result(PsetHistoryResponse {
+					revisions,
+				})
+			}
+			RpcMethod::PsetRevert => {
+				let req: PsetRevertRequest = parse_params(params)?;
+				let pset = self.sessions.revert(&req.session, req.revision)?;
+
+				serialize_result(PsetRevertResponse {
+					pset,
 				})
 			}
 			RpcMethod::PsetFinalize => {
 				let req: PsetFinalizeRequest = parse_params(params)?;
-				let result = actions::simplicity::pset::pset_finalize(
-					&req.pset,
-					&req.input_index.to_string(),
-					&req.program,
-					&req.witness,
-					req.genesis_hash.as_deref(),
-				)
-				.map_err(|e| RpcError::custom(ErrorCode::InternalError.code(), e.to_string()))?;
+				let pset = self.sessions.resolve(req.pset, req.session.as_deref())?;
+				let genesis_hash =
+					self.genesis_hash_for(req.genesis_hash.as_deref(), req.network)?;
+				let program =
+					self.programs.get_or_parse(&req.program, &req.witness).map_err(|e| {
+						action_error(actions::simplicity::pset::PsetFinalizeError::ProgramParse(e))
+					})?;
+				let span = program_span("finalize", &req.program, &req.witness, &program);
+				let result = span
+					.in_scope(|| {
+						self.workers.run(|| {
+							actions::simplicity::pset::pset_finalize_program(
+								&pset,
+								&req.input_index.to_string(),
+								&program,
+								genesis_hash.as_deref(),
+								simplicity::leaf_version(),
+							)
+						})
+					})?
+					.map_err(action_error)?;
+
+				if let Some(ref session) = req.session {
+					self.sessions.set(session, result.pset.clone(), rpc_method.as_str())?;
+				}
 
 				serialize_result(result)
 			}
 			RpcMethod::PsetRun => {
-				let req: PsetRunRequest = parse_params(params)?;
-				let result = actions::simplicity::pset::pset_run(
-					&req.pset,
-					&req.input_index.to_string(),
-					&req.program,
-					&req.witness,
-					req.genesis_hash.as_deref(),
-				)
-				.map_err(|e| RpcError::custom(ErrorCode::InternalError.code(), e.to_string()))?;
+				let mut req: PsetRunRequest = parse_params(params)?;
+				let pset = self.sessions.resolve(req.pset.take(), req.session.as_deref())?;
+				let genesis_hash =
+					self.genesis_hash_for(req.genesis_hash.as_deref(), req.network)?;
+				let program =
+					self.programs.get_or_parse(&req.program, &req.witness).map_err(|e| {
+						action_error(actions::simplicity::pset::PsetRunError::ProgramParse(e))
+					})?;
+				let span = program_span("run", &req.program, &req.witness, &program);
+				let result = span
+					.in_scope(|| {
+						self.workers.run(|| {
+							actions::simplicity::pset::pset_run_program(
+								&pset,
+								&req.input_index.to_string(),
+								&program,
+								genesis_hash.as_deref(),
+								req.jet_limit(),
+								|_| ControlFlow::Continue(()),
+							)
+						})
+					})?
+					.map_err(action_error)?;
+				span.record("success", result.success);
 
 				serialize_result(result)
 			}
 			RpcMethod::PsetUpdateInput => {
 				let req: PsetUpdateInputRequest = parse_params(params)?;
+				let pset = self.sessions.resolve(req.pset, req.session.as_deref())?;
+				let input_utxo = match req.input_utxo {
+					Some(input_utxo) => input_utxo,
+					None => self.fetch_input_utxo(&pset, req.input_index, req.backend)?,
+				};
 				let result = actions::simplicity::pset::pset_update_input(
-					&req.pset,
+					&pset,
 					&req.input_index.to_string(),
-					&req.input_utxo,
+					&input_utxo,
 					req.internal_key.as_deref(),
 					req.cmr.as_deref(),
 					req.state.as_deref(),
 				)
-				.map_err(|e| RpcError::custom(ErrorCode::InternalError.code(), e.to_string()))?;
+				.map_err(action_error)?;
+
+				if let Some(ref session) = req.session {
+					self.sessions.set(session, result.pset.clone(), rpc_method.as_str())?;
+				}
 
 				serialize_result(result)
 			}
+			RpcMethod::WatchAdd => {
+				let req: WatchAddRequest = parse_params(params)?;
+				let entry = watch::entry(req, self.network)?;
+				self.watch.add(entry.clone())?;
+
+				serialize_result(entry)
+			}
+			RpcMethod::WatchRemove => {
+				let req: WatchRemoveRequest = parse_params(params)?;
+				let entry = self.watch.remove(&req.script_pubkey)?;
+
+				serialize_result(entry)
+			}
+			RpcMethod::WatchList => serialize_result(self.watch.list()),
+			RpcMethod::WatchUnspent => {
+				let req: WatchUnspentRequest =
+					parse_params(Some(params.unwrap_or_else(|| json!({}))))?;
+				let backend = self.backend(req.backend)?;
+
+				let mut result = WatchUnspentResponse::new();
+				for entry in self.watch.list() {
+					if req.script_pubkey.as_ref().is_some_and(|s| *s != entry.script_pubkey) {
+						continue;
+					}
+					let script_pubkey = elements::Script::from(
+						hex::decode(&entry.script_pubkey).expect("stored scriptPubKeys are hex"),
+					);
+					let utxos = backend.unspent(&script_pubkey).map_err(action_error)?;
+					result.push(WatchUnspent {
+						entry,
+						utxos,
+					});
+				}
+
+				serialize_result(result)
+			}
+			RpcMethod::JobSubmit => {
+				let req: JobSubmitRequest = parse_params(params)?;
+				let method = RpcMethod::from_str(&req.method).map_err(|_| {
+					RpcError::custom(
+						ErrorCode::InvalidParams.code(),
+						format!("Unknown method: {}", req.method),
+					)
+				})?;
+				if matches!(
+					method,
+					RpcMethod::JobSubmit | RpcMethod::JobStatus | RpcMethod::JobResult
+				) {
+					return Err(RpcError::custom(
+						ErrorCode::InvalidParams.code(),
+						format!("{} cannot run as a job", req.method),
+					));
+				}
+				self.permissions.check(method, req.params.as_ref())?;
+				let job = self.jobs.submit(req.method, req.params, req.callback)?;
+
+				serialize_result(JobSubmitResponse {
+					job,
+				})
+			}
+			RpcMethod::JobStatus => {
+				let req: JobStatusRequest = parse_params(params)?;
+
+				serialize_result(self.jobs.status(&req.job)?)
+			}
+			RpcMethod::JobResult => {
+				let req: JobResultRequest = parse_params(params)?;
+
+				self.jobs.result(&req.job)
+			}
 		}
 	}
-}
 
-impl DefaultRpcHandler {
-	fn new() -> Self {
-		Self
+	/// The methods which may be called.
+	fn methods(&self) -> impl Iterator<Item = RpcMethod> + '_ {
+		RpcMethod::ALL.iter().copied().filter(|method| self.permissions.allows(*method))
+	}
+
+	/// The optional features available, given the methods allowed.
+	fn capabilities(&self) -> impl Iterator<Item = Capability> + '_ {
+		Capability::ALL.iter().copied().filter(|capability| match capability {
+			Capability::Batch => true,
+			Capability::Sessions => self.permissions.allows(RpcMethod::PsetOpen),
+			Capability::Streaming => self.permissions.allows(RpcMethod::PsetRun),
+		})
+	}
+
+	/// The requested chain backend, or the default one.
+	fn backend(&self, kind: Option<BackendKind>) -> Result<&Arc<dyn ChainBackend>, RpcError> {
+		let backend = match kind.or(self.default_backend) {
+			Some(kind) => self.backends.get(&kind),
+			None => self.backends.values().next(),
+		};
+		backend.ok_or_else(|| {
+			let message = match kind {
+				Some(kind) => format!("No {} backend configured", kind),
+				None => "No chain backend configured".to_string(),
+			};
+			RpcError::custom(ErrorCode::InternalError.code(), message)
+		})
+	}
+
+	/// The stateless methods, with the handler's network and genesis hash.
+	fn api(&self) -> Api {
+		Api {
+			network: self.network,
+			genesis_hash: self.genesis_hash.clone(),
+		}
+	}
+
+	/// The genesis hash to use for a request: the requested one, that of the
+	/// requested network, the configured one, the backend's, or that of the
+	/// daemon's network if none was requested, in that order. `None` means
+	/// the Liquid testnet default, which is only used on regtest.
+	///
+	/// A requested genesis hash must be that of the requested network, if
+	/// both are given.
+	pub fn genesis_hash_for(
+		&self,
+		requested: Option<&str>,
+		network: Option<Network>,
+	) -> Result<Option<String>, RpcError> {
+		if let Some(network) = network {
+			check_genesis_hash(network, requested)?;
+		}
+		let network_genesis_hash = network.and_then(Network::genesis_hash).map(|h| h.to_string());
+		if let Some(genesis_hash) = requested.map(str::to_owned).or(network_genesis_hash) {
+			return Ok(Some(genesis_hash));
+		}
+		if let Some(ref genesis_hash) = self.genesis_hash {
+			return Ok(Some(genesis_hash.clone()));
+		}
+		if let Ok(backend) = self.backend(None) {
+			if let Some(genesis_hash) = self.backend_genesis_hash.get() {
+				return Ok(Some(genesis_hash.clone()));
+			}
+			match backend.genesis_hash() {
+				Ok(genesis_hash) => {
+					let genesis_hash = genesis_hash.to_string();
+					return Ok(Some(
+						self.backend_genesis_hash.get_or_init(|| genesis_hash).clone(),
+					));
+				}
+				Err(BackendError::Unsupported(_)) => {}
+				Err(e) => return Err(action_error(e)),
+			}
+		}
+		Ok(network.unwrap_or(self.network).genesis_hash().map(|h| h.to_string()))
+	}
+
+	/// Fetch the output spent by a PSET input from the backend, formatted as
+	/// for the `input_utxo` param.
+	fn fetch_input_utxo(
+		&self,
+		pset: &str,
+		input_index: u32,
+		backend: Option<BackendKind>,
+	) -> Result<String, RpcError> {
+		let invalid = |message: String| RpcError::custom(ErrorCode::InvalidParams.code(), message);
+		let pset: elements::pset::PartiallySignedTransaction =
+			pset.parse().map_err(|e| invalid(format!("invalid PSET: {}", e)))?;
+		let input = pset.inputs().get(input_index as usize).ok_or_else(|| {
+			invalid(format!(
+				"input index {} out-of-range for PSET with {} inputs",
+				input_index,
+				pset.n_inputs()
+			))
+		})?;
+
+		let tx = self.backend(backend)?.transaction(input.previous_txid).map_err(action_error)?;
+		let output = tx.output.get(input.previous_output_index as usize).ok_or_else(|| {
+			invalid(format!(
+				"transaction {} has no output {}",
+				input.previous_txid, input.previous_output_index
+			))
+		})?;
+		Ok(actions::simplicity::format_elements_utxo(output))
 	}
 }
 
 /// Parse parameters from JSON value
-fn parse_params<T: serde::de::DeserializeOwned>(params: Option<Value>) -> Result<T, RpcError> {
+pub(super) fn parse_params<T: serde::de::DeserializeOwned>(
+	params: Option<Value>,
+) -> Result<T, RpcError> {
 	let params = params.ok_or_else(|| {
 		RpcError::custom(ErrorCode::InvalidParams.code(), "Missing parameters".to_string())
 	})?;
 
-	serde_json::from_value(params).map_err(|e| {
+	// Decode from a reference, since some types, such as `HexBytes`, only
+	// decode from borrowed strings.
+	T::deserialize(&params).map_err(|e| {
 		RpcError::custom(ErrorCode::InvalidParams.code(), format!("Invalid parameters: {}", e))
 	})
 }
 
+/// Check the `network` param, which any method may be given, along with any
+/// `genesis_hash` param, which must agree with it.
+fn check_network_param(params: Option<&Value>) -> Result<(), RpcError> {
+	let Some(network) = params.and_then(|params| params.get("network")).filter(|n| !n.is_null())
+	else {
+		return Ok(());
+	};
+	let network = network.as_str().and_then(|n| n.parse::<Network>().ok()).ok_or_else(|| {
+		RpcError::custom(ErrorCode::InvalidParams.code(), format!("Invalid network: {}", network))
+	})?;
+	let genesis_hash = params.and_then(|params| params.get("genesis_hash"));
+	check_genesis_hash(network, genesis_hash.and_then(Value::as_str))
+}
+
+/// Fail if `genesis_hash` is given but is not that of `network`. Regtest
+/// chains may have any genesis hash.
+fn check_genesis_hash(network: Network, genesis_hash: Option<&str>) -> Result<(), RpcError> {
+	let (Some(expected), Some(given)) = (network.genesis_hash(), genesis_hash) else {
+		return Ok(());
+	};
+	match given.parse::<elements::BlockHash>() {
+		Ok(given) if given != expected => Err(RpcError::custom(
+			ErrorCode::InvalidParams.code(),
+			format!(
+				"Genesis hash {} is not that of {}, which is {}",
+				given,
+				network.as_str(),
+				expected,
+			),
+		)),
+		// An invalid hash is reported by the method itself.
+		_ => Ok(()),
+	}
+}
+
+/// A span for running `program`, recording the lengths of the base64 program
+/// and hex witness it was given as, and the cost which consensus charges for it.
+pub(super) fn program_span(
+	action: &'static str,
+	program_b64: &str,
+	witness_hex: &str,
+	program: &crate::hal_simplicity::Program<simplicity::jet::Elements>,
+) -> tracing::Span {
+	let cost =
+		program.redeem_node().and_then(|node| node.bounds().cost.to_string().parse::<u64>().ok());
+	tracing::info_span!(
+		"simplicity",
+		action,
+		program_len = program_b64.len(),
+		witness_len = witness_hex.len(),
+		cost,
+		success = tracing::field::Empty,
+	)
+}
+
 /// Serialize result to JSON value
 fn serialize_result<T: serde::Serialize>(result: T) -> Result<Value, RpcError> {
 	serde_json::to_value(result).map_err(|e| {
@@ -256,5 +826,95 @@ fn serialize_result<T: serde::Serialize>(result: T) -> Result<Value, RpcError> {
 
 /// Create a JSONRPC service with the default handler
 pub fn create_service() -> JsonRpcService<DefaultRpcHandler> {
-	JsonRpcService::new(DefaultRpcHandler::new())
+	JsonRpcService::new(DefaultRpcHandler::default())
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn test_stop() {
+		let mut handler = DefaultRpcHandler::default();
+		assert!(handler.handle("stop", None).is_err());
+
+		let (shutdown_tx, mut shutdown_rx) = broadcast::channel(1);
+		handler.shutdown = Some(shutdown_tx);
+		assert_eq!(handler.handle("stop", None).unwrap(), "hal-simplicity-daemon stopping");
+		assert!(shutdown_rx.try_recv().is_ok());
+	}
+	#[test]
+	fn test_default_network_genesis_hash() {
+		let handler = DefaultRpcHandler::default();
+		let cmr = "7fd424f70498ef2fb6dd05ffbb7368dc796e6c47f24404e0b1ff138cfce89a7a";
+		let asset = "144c654344aa716d6f3abcc1ca90e5641e4e2a7f633bc09fe3baf64585819a49";
+		let inputs = json!([{ "txid": "11".repeat(32), "vout": 0 }]).to_string();
+		let outputs = json!([{
+			"address": "tex1p7zpxr4sueukel0mdmxwpua2p52wqdsjglrzeklqfjj9tmk0ywzqs29s49v",
+			"asset": asset,
+			"amount": 0.0009,
+		}])
+		.to_string();
+		let created = handler
+			.handle("pset_create", Some(json!({ "inputs": inputs, "outputs": outputs })))
+			.unwrap();
+		let params = json!({
+			"pset": created["pset"],
+			"input_index": 0,
+			"input_utxo": format!(
+				"5120f08261d61ccf2d9fbf6dd99c1e7541a29c06c248f8c59b7c09948abdd9e47081:{}:0.001",
+				asset
+			),
+			"internal_key": "50929b74c1a04954b78b4b6035e97a5e078a5a0f28ec96d547bfee9ace803ac0",
+			"cmr": cmr,
+		});
+		let updated = handler.handle("pset_update_input", Some(params)).unwrap();
+		let sighash = |network: Option<&str>| {
+			let mut params = json!({ "tx": updated["pset"], "input_index": 0, "cmr": cmr });
+			if let Some(network) = network {
+				params["network"] = json!(network);
+			}
+			handler.handle("simplicity_sighash", Some(params)).unwrap()["sighash"].clone()
+		};
+
+		// A Liquid daemon signs for Liquid when the request names no chain.
+		assert_eq!(sighash(None), sighash(Some("liquid")));
+		assert_ne!(sighash(None), sighash(Some("liquidtestnet")));
+	}
+
+	#[test]
+	fn test_network() {
+		let mut handler = DefaultRpcHandler::default();
+		let liquid = Network::Liquid.genesis_hash().unwrap().to_string();
+		let testnet = Network::LiquidTestnet.genesis_hash().unwrap().to_string();
+
+		// A requested network brings its genesis hash, unless it is regtest.
+		assert_eq!(
+			handler.genesis_hash_for(None, Some(Network::Liquid)).unwrap(),
+			Some(liquid.clone())
+		);
+		assert_eq!(handler.genesis_hash_for(None, Some(Network::ElementsRegtest)).unwrap(), None);
+		// Otherwise the daemon's network does.
+		assert_eq!(handler.genesis_hash_for(None, None).unwrap(), Some(liquid));
+		handler.network = Network::ElementsRegtest;
+		assert_eq!(handler.genesis_hash_for(None, None).unwrap(), None);
+		handler.network = Network::Liquid;
+		handler.genesis_hash = Some("00".to_owned());
+		assert_eq!(
+			handler.genesis_hash_for(None, Some(Network::LiquidTestnet)).unwrap(),
+			Some(testnet.clone())
+		);
+		assert_eq!(handler.genesis_hash_for(None, None).unwrap().as_deref(), Some("00"));
+		assert!(handler.genesis_hash_for(Some(&testnet), Some(Network::Liquid)).is_err());
+
+		// Any method may be given a network, which must agree with its genesis hash.
+		let params = json!({ "raw_block": "00", "network": "liquid", "genesis_hash": testnet });
+		let err = handler.handle("block_stats", Some(params)).unwrap_err();
+		assert_eq!(err.code, ErrorCode::InvalidParams.code());
+		assert!(err.message.starts_with("Genesis hash a771da8e"), "{}", err.message);
+		let err = handler.handle("getinfo", Some(json!({ "network": "bitcoin" }))).unwrap_err();
+		assert_eq!(err.message, "Invalid network: \"bitcoin\"");
+		let params = json!({ "network": "elementsregtest", "genesis_hash": testnet });
+		assert!(handler.handle("getinfo", Some(params)).is_ok());
+	}
 }
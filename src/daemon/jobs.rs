@@ -0,0 +1,402 @@
+//! Methods run in the background
+//!
+//! An expensive call, such as a `pset_run` on a large program, can take longer
+//! than an HTTP client is willing to wait. `job_submit` queues any method call
+//! and returns a job ID at once. `job_status` reports whether the job is still
+//! queued or running, and `job_result` returns its result (or error) once it
+//! is done, forgetting the job.
+//!
+//! Jobs are run by threads started with [`spawn_runners`]. Results which are
+//! never fetched are dropped after [`RESULT_TTL`].
+//!
+//! Rather than polling, a caller can give a callback URL when submitting a
+//! job, if the daemon has a callback secret and the URL starts with one of
+//! the allowed callback prefixes, so callers cannot make the daemon POST to
+//! arbitrary hosts. Once the job finishes, its result is POSTed there as a
+//! [`JobCallback`], with an HMAC-SHA256 of the body under the secret in the
+//! [`SIGNATURE_HEADER`] header. Callbacks are delivered on their own threads,
+//! so a slow callback URL does not hold up the runners. A failed delivery is
+//! logged and not repeated; the result can still be fetched with
+//! `job_result`.
+
+use std::collections::{HashMap, VecDeque};
+use std::sync::{Arc, Condvar, Mutex, Weak};
+use std::time::{Duration, Instant};
+
+use elements::bitcoin::secp256k1::rand::{self, RngCore as _};
+use elements::hashes::{hmac, sha256, Hash as _, HashEngine as _};
+use serde_json::Value;
+
+use super::handler::DefaultRpcHandler;
+use super::jsonrpc::{ErrorCode, JsonRpcService, RpcError};
+use super::pool::SERVER_BUSY_CODE;
+use super::types::{JobCallback, JobState, JobStatusResponse};
+
+/// Default maximum number of jobs which are queued, running or holding a result.
+pub const DEFAULT_MAX_JOBS: usize = 100;
+
+/// How long a result is kept if it is not fetched.
+pub const RESULT_TTL: Duration = Duration::from_secs(60 * 60);
+
+/// How long a runner waits for a job before checking whether the daemon is gone.
+const POLL_INTERVAL: Duration = Duration::from_millis(500);
+
+/// The header holding the signature of a callback, as `sha256=<hex>`.
+pub const SIGNATURE_HEADER: &str = "X-Hal-Simplicity-Signature";
+
+/// How long to wait for a callback URL to accept a result.
+const CALLBACK_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// The submitted jobs.
+#[derive(Debug)]
+pub struct Jobs {
+	max_jobs: usize,
+	/// The key callbacks are signed with. Callbacks are refused without one.
+	callback_secret: Option<String>,
+	/// The URL prefixes callbacks may be sent to. Callbacks are refused if empty.
+	callback_allow: Vec<String>,
+	state: Mutex<State>,
+	submitted: Condvar,
+}
+
+#[derive(Debug, Default)]
+struct State {
+	jobs: HashMap<String, Job>,
+	queue: VecDeque<String>,
+}
+
+#[derive(Debug)]
+struct Job {
+	method: String,
+	params: Option<Value>,
+	callback: Option<String>,
+	submitted: Instant,
+	finished: Option<Instant>,
+	state: JobState,
+	result: Option<Result<Value, RpcError>>,
+}
+
+impl Default for Jobs {
+	fn default() -> Self {
+		Self::new(DEFAULT_MAX_JOBS)
+	}
+}
+
+impl Jobs {
+	/// Keep at most `max_jobs` jobs at once.
+	pub fn new(max_jobs: usize) -> Self {
+		Self {
+			max_jobs,
+			callback_secret: None,
+			callback_allow: vec![],
+			state: Mutex::new(State::default()),
+			submitted: Condvar::new(),
+		}
+	}
+
+	/// Accept callback URLs, signing callbacks with `secret`.
+	pub fn with_callback_secret(mut self, secret: String) -> Self {
+		self.callback_secret = Some(secret);
+		self
+	}
+
+	/// Only accept callback URLs starting with one of `prefixes`, such as
+	/// `https://example.com/hooks/`. A prefix without a path only matches
+	/// whole hosts, so `http://localhost` does not allow
+	/// `http://localhost.example.com`.
+	pub fn with_callback_allow(mut self, prefixes: Vec<String>) -> Self {
+		self.callback_allow = prefixes;
+		self
+	}
+
+	/// Whether callbacks may be sent to `url`.
+	fn callback_allowed(&self, url: &str) -> bool {
+		self.callback_allow.iter().any(|prefix| match url.strip_prefix(prefix.as_str()) {
+			Some(rest) => {
+				prefix.ends_with('/') || rest.is_empty() || rest.starts_with(['/', ':', '?', '#'])
+			}
+			None => false,
+		})
+	}
+
+	/// Queue a method call, returning the job ID. If `callback` is given, the
+	/// result is POSTed to it once the job finishes.
+	pub fn submit(
+		&self,
+		method: String,
+		params: Option<Value>,
+		callback: Option<String>,
+	) -> Result<String, RpcError> {
+		if let Some(ref url) = callback {
+			let invalid =
+				|message: String| RpcError::custom(ErrorCode::InvalidParams.code(), message);
+			if self.callback_secret.is_none() {
+				return Err(invalid(
+					"Callbacks need a callback secret to be configured".to_owned(),
+				));
+			}
+			if !url.starts_with("http://") && !url.starts_with("https://") {
+				return Err(invalid(format!("Invalid callback URL: {}", url)));
+			}
+			if !self.callback_allowed(url) {
+				return Err(invalid(format!("Callback URL not allowed: {}", url)));
+			}
+		}
+
+		let mut state = self.state.lock().expect("poisoned");
+		state.jobs.retain(|_, job| job.finished.map_or(true, |t| t.elapsed() < RESULT_TTL));
+		if state.jobs.len() >= self.max_jobs {
+			return Err(RpcError::custom(
+				SERVER_BUSY_CODE,
+				format!("Too many jobs (at most {})", self.max_jobs),
+			));
+		}
+
+		let mut bytes = [0; 16];
+		rand::thread_rng().fill_bytes(&mut bytes);
+		let id = hex::encode(bytes);
+		state.jobs.insert(
+			id.clone(),
+			Job {
+				method,
+				params,
+				callback,
+				submitted: Instant::now(),
+				finished: None,
+				state: JobState::Queued,
+				result: None,
+			},
+		);
+		state.queue.push_back(id.clone());
+		self.submitted.notify_one();
+		Ok(id)
+	}
+
+	/// The state of a job.
+	pub fn status(&self, id: &str) -> Result<JobStatusResponse, RpcError> {
+		let state = self.state.lock().expect("poisoned");
+		let job = state.jobs.get(id).ok_or_else(|| unknown(id))?;
+		Ok(JobStatusResponse {
+			job: id.to_owned(),
+			method: job.method.clone(),
+			state: job.state,
+			seconds: job
+				.finished
+				.unwrap_or_else(Instant::now)
+				.duration_since(job.submitted)
+				.as_secs(),
+		})
+	}
+
+	/// The result of a finished job, which is then forgotten.
+	pub fn result(&self, id: &str) -> Result<Value, RpcError> {
+		let mut state = self.state.lock().expect("poisoned");
+		let job = state.jobs.get(id).ok_or_else(|| unknown(id))?;
+		if job.result.is_none() {
+			return Err(RpcError::custom(
+				ErrorCode::InvalidParams.code(),
+				format!("Job {} has not finished", id),
+			));
+		}
+		let job = state.jobs.remove(id).expect("found above");
+		job.result.expect("checked above")
+	}
+
+	/// Take the next queued job, waiting up to `timeout` for one.
+	#[allow(clippy::type_complexity)]
+	fn next(&self, timeout: Duration) -> Option<(String, String, Option<Value>, Option<String>)> {
+		let state = self.state.lock().expect("poisoned");
+		let (mut state, _) = self
+			.submitted
+			.wait_timeout_while(state, timeout, |state| state.queue.is_empty())
+			.expect("poisoned");
+		let id = state.queue.pop_front()?;
+		let job = state.jobs.get_mut(&id)?;
+		job.state = JobState::Running;
+		Some((id, job.method.clone(), job.params.take(), job.callback.clone()))
+	}
+
+	/// Record the result of a job.
+	fn finish(&self, id: &str, result: Result<Value, RpcError>) {
+		if let Some(job) = self.state.lock().expect("poisoned").jobs.get_mut(id) {
+			job.state = match result {
+				Ok(_) => JobState::Done,
+				Err(_) => JobState::Failed,
+			};
+			job.finished = Some(Instant::now());
+			job.result = Some(result);
+		}
+	}
+}
+
+/// The signature of a callback body under `secret`, as sent in
+/// [`SIGNATURE_HEADER`].
+pub fn callback_signature(secret: &str, body: &[u8]) -> String {
+	let mut engine = hmac::HmacEngine::<sha256::Hash>::new(secret.as_bytes());
+	engine.input(body);
+	format!("sha256={}", hmac::Hmac::from_engine(engine))
+}
+
+/// POST the result of a job to its callback URL.
+fn deliver(url: &str, secret: &str, callback: &JobCallback) {
+	let body = serde_json::to_vec(callback).expect("callbacks serialize");
+	let response = ureq::post(url)
+		.timeout(CALLBACK_TIMEOUT)
+		.set("Content-Type", "application/json")
+		.set(SIGNATURE_HEADER, &callback_signature(secret, &body))
+		.send_bytes(&body);
+	match response {
+		Ok(_) => log::debug!("Delivered job {} to {}", callback.job, url),
+		Err(e) => log::warn!("Cannot deliver job {} to {}: {}", callback.job, url, e),
+	}
+}
+
+fn unknown(id: &str) -> RpcError {
+	RpcError::custom(ErrorCode::InvalidParams.code(), format!("Unknown job: {}", id))
+}
+
+/// Start `count` threads running the jobs submitted to `service`. They stop
+/// once the service is dropped.
+pub fn spawn_runners(service: Weak<JsonRpcService<DefaultRpcHandler>>, count: usize) {
+	for _ in 0..count {
+		let service = service.clone();
+		std::thread::spawn(move || {
+			while let Some(service) = service.upgrade() {
+				run_next(&service);
+			}
+		});
+	}
+}
+
+/// Run the next job, if one is submitted soon.
+fn run_next(service: &Arc<JsonRpcService<DefaultRpcHandler>>) {
+	let jobs = &service.handler().jobs;
+	if let Some((id, method, params, callback)) = jobs.next(POLL_INTERVAL) {
+		log::debug!("Running job {} ({})", id, method);
+		let result = service.call(&method, params);
+		if let (Some(url), Some(secret)) = (callback, jobs.callback_secret.clone()) {
+			let (state, result, error) = match result {
+				Ok(ref result) => (JobState::Done, Some(result.clone()), None),
+				Err(ref error) => (JobState::Failed, None, Some(error.clone())),
+			};
+			let callback = JobCallback {
+				job: id.clone(),
+				method,
+				state,
+				result,
+				error,
+			};
+			std::thread::spawn(move || deliver(&url, &secret, &callback));
+		}
+		jobs.finish(&id, result);
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use crate::daemon::handler::create_service;
+	use serde_json::json;
+
+	#[test]
+	fn test_jobs() {
+		let service = Arc::new(create_service());
+		spawn_runners(Arc::downgrade(&service), 1);
+		let call = |method: &str, params: Value| service.call(method, Some(params));
+
+		let submitted = call("job_submit", json!({ "method": "getinfo" })).unwrap();
+		let failing = json!({ "method": "tx_decode", "params": { "raw_tx": "00" } });
+		let failed = call("job_submit", failing).unwrap();
+		assert!(call("job_submit", json!({ "method": "job_status" })).is_err());
+		assert!(call("job_submit", json!({ "method": "nope" })).is_err());
+
+		let wait = |job: &Value| loop {
+			let status = call("job_status", json!({ "job": job })).unwrap();
+			if status["state"] != "queued" && status["state"] != "running" {
+				return status;
+			}
+			std::thread::sleep(Duration::from_millis(5));
+		};
+
+		assert_eq!(wait(&submitted["job"])["state"], "done");
+		let info = call("job_result", json!({ "job": submitted["job"] })).unwrap();
+		assert_eq!(info["version"], env!("CARGO_PKG_VERSION"));
+		// Results can only be fetched once.
+		assert!(call("job_result", json!({ "job": submitted["job"] })).is_err());
+
+		assert_eq!(wait(&failed["job"])["state"], "failed");
+		let err = call("job_result", json!({ "job": failed["job"] })).unwrap_err();
+		assert_eq!(err.code, 1200);
+	}
+
+	#[test]
+	fn test_callback() {
+		use std::io::{BufRead as _, BufReader, Read as _, Write as _};
+
+		let mut handler = DefaultRpcHandler::default();
+		let listener = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+		let allowed = format!("http://{}", listener.local_addr().unwrap());
+		let url = format!("{}/done", allowed);
+		handler.jobs = Jobs::default()
+			.with_callback_secret("hunter2".to_owned())
+			.with_callback_allow(vec![allowed.clone()]);
+		let service = Arc::new(JsonRpcService::new(handler));
+		let call = |params: Value| service.call("job_submit", Some(params));
+		let err = call(json!({ "method": "getinfo", "callback": "ftp://x" })).unwrap_err();
+		assert_eq!(err.code, ErrorCode::InvalidParams.code());
+		for url in ["http://x/done".to_owned(), format!("{}0/done", allowed)] {
+			let err = call(json!({ "method": "getinfo", "callback": url })).unwrap_err();
+			assert_eq!(err.code, ErrorCode::InvalidParams.code());
+		}
+		let jobs = Jobs::default();
+		let err = jobs.submit("getinfo".to_owned(), None, Some("http://x".to_owned()));
+		assert_eq!(err.unwrap_err().code, ErrorCode::InvalidParams.code());
+
+		let failing =
+			json!({ "method": "tx_decode", "params": { "raw_tx": "00" }, "callback": url });
+		let job = call(failing).unwrap();
+		spawn_runners(Arc::downgrade(&service), 1);
+
+		let (stream, _) = listener.accept().unwrap();
+		let mut reader = BufReader::new(stream);
+		let (mut signature, mut length) = (String::new(), 0);
+		loop {
+			let mut line = String::new();
+			reader.read_line(&mut line).unwrap();
+			let line = line.trim_end();
+			if line.is_empty() {
+				break;
+			}
+			let (name, value) = line.split_once(": ").unwrap_or((line, ""));
+			if name.eq_ignore_ascii_case(SIGNATURE_HEADER) {
+				signature = value.to_owned();
+			} else if name.eq_ignore_ascii_case("content-length") {
+				length = value.parse().unwrap();
+			}
+		}
+		let mut body = vec![0; length];
+		reader.read_exact(&mut body).unwrap();
+		reader.get_mut().write_all(b"HTTP/1.1 204 No Content\r\n\r\n").unwrap();
+
+		assert_eq!(signature, callback_signature("hunter2", &body));
+		assert_ne!(signature, callback_signature("hunter3", &body));
+		let callback: JobCallback = serde_json::from_slice(&body).unwrap();
+		assert_eq!(callback.job, job["job"]);
+		assert_eq!(callback.method, "tx_decode");
+		assert_eq!(callback.state, JobState::Failed);
+		assert_eq!(callback.error.unwrap().code, 1200);
+		assert!(callback.result.is_none());
+	}
+
+	#[test]
+	fn test_max_jobs() {
+		let jobs = Jobs::new(1);
+		let id = jobs.submit("getinfo".to_string(), None, None).unwrap();
+		assert_eq!(
+			jobs.submit("getinfo".to_string(), None, None).unwrap_err().code,
+			SERVER_BUSY_CODE
+		);
+		assert!(jobs.result(&id).is_err());
+		assert_eq!(jobs.status(&id).unwrap().state, JobState::Queued);
+	}
+}
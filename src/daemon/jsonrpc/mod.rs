@@ -5,6 +5,14 @@
 use serde::{Deserialize, Serialize};
 use serde_json::Value;
 use std::fmt;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::time::Instant;
+
+/// Log target of the per-request log lines.
+pub const LOG_TARGET: &str = "rpc";
+
+/// Params whose values are replaced before params are logged.
+pub const SECRET_PARAMS: &[&str] = &["secret_key"];
 
 /// JSONRPC 2.0 Error codes as defined in the specification
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
@@ -149,42 +157,52 @@ impl RpcResponse {
 #[derive(Debug)]
 pub enum RpcCall {
 	Single(RpcRequest),
-	Batch(Vec<RpcRequest>),
+	/// A batch of requests. Each item which is not a valid request is kept as
+	/// the error response to it, with its `id` if it has one, so that the
+	/// rest of the batch can still run.
+	Batch(Vec<Result<RpcRequest, Box<RpcResponse>>>),
 }
 
 impl RpcCall {
 	/// Parse a JSON string into an RPC call
 	pub fn from_json(json: &str) -> Result<Self, RpcError> {
-		// Try parsing as a single request first
-		if let Ok(request) = serde_json::from_str::<RpcRequest>(json) {
-			request.validate()?;
-			return Ok(RpcCall::Single(request));
-		}
+		let value = serde_json::from_str::<Value>(json)
+			.map_err(|_| RpcError::new(ErrorCode::ParseError))?;
 
-		// Try psrsing as a batch request
-		match serde_json::from_str::<Vec<RpcRequest>>(json) {
-			Ok(requests) => {
-				if requests.is_empty() {
+		match value {
+			Value::Array(items) => {
+				if items.is_empty() {
 					return Err(RpcError::new(ErrorCode::InvalidRequest)
 						.with_data(Value::String("batch request cannot be empty".to_string())));
 				}
 
-				for request in &requests {
-					request.validate()?;
-				}
-
-				Ok(RpcCall::Batch(requests))
+				let requests = items.into_iter().map(|item| {
+					// Take the id first, as it is lost if the item is invalid.
+					let id = item.get("id").cloned().unwrap_or(Value::Null);
+					Self::parse_request(item)
+						.map_err(|error| Box::new(RpcResponse::error(error, id)))
+				});
+				Ok(RpcCall::Batch(requests.collect()))
 			}
-			Err(_) => Err(RpcError::new(ErrorCode::ParseError)),
+			value => Ok(RpcCall::Single(Self::parse_request(value)?)),
 		}
 	}
+
+	/// Parse and validate a single request object
+	fn parse_request(value: Value) -> Result<RpcRequest, RpcError> {
+		let request = serde_json::from_value::<RpcRequest>(value).map_err(|e| {
+			RpcError::new(ErrorCode::InvalidRequest).with_data(Value::String(e.to_string()))
+		})?;
+		request.validate()?;
+		Ok(request)
+	}
 }
 
 /// Represents either a single response or batch responses
 #[derive(Debug, Serialize)]
 #[serde(untagged)]
 pub enum RpcOutput {
-	Single(RpcResponse),
+	Single(Box<RpcResponse>),
 	Batch(Vec<RpcResponse>),
 }
 
@@ -202,13 +220,78 @@ pub trait RpcHandler: Send + Sync {
 /// Main JSONRPC service
 pub struct JsonRpcService<H: RpcHandler> {
 	handler: H,
+	next_request_id: AtomicU64,
 }
 
 impl<H: RpcHandler> JsonRpcService<H> {
 	pub fn new(handler: H) -> Self {
 		Self {
 			handler,
+			next_request_id: AtomicU64::new(1),
+		}
+	}
+
+	/// Accessor for the underlying method handler.
+	pub fn handler(&self) -> &H {
+		&self.handler
+	}
+
+	/// Call a method on the handler, logging the call.
+	///
+	/// Each call is given a request ID and logged at info level with its
+	/// method, duration and outcome. At debug level its params are logged
+	/// too, with any secrets redacted.
+	pub fn call(&self, method: &str, params: Option<Value>) -> Result<Value, RpcError> {
+		let request_id = self.next_request_id.fetch_add(1, Ordering::Relaxed);
+		if log::log_enabled!(target: LOG_TARGET, log::Level::Debug) {
+			let params = params.clone().map(redact).unwrap_or(Value::Null);
+			log::debug!(
+				target: LOG_TARGET,
+				"request={} method={:?} params={}",
+				request_id,
+				method,
+				params
+			);
+		}
+
+		let span = tracing::info_span!(
+			"rpc",
+			method,
+			request_id,
+			params_len = tracing::field::Empty,
+			error_code = tracing::field::Empty,
+		);
+		let _span = span.enter();
+		if !span.is_disabled() {
+			let params_len = params.as_ref().map_or(0, |params| params.to_string().len());
+			span.record("params_len", params_len);
 		}
+
+		let start = Instant::now();
+		let result = self.handler.handle(method, params);
+		let duration_ms = start.elapsed().as_secs_f64() * 1000.0;
+		if let Err(ref e) = result {
+			span.record("error_code", e.code);
+		}
+		match result {
+			Ok(_) => log::info!(
+				target: LOG_TARGET,
+				"request={} method={:?} duration_ms={:.3} outcome=ok",
+				request_id,
+				method,
+				duration_ms
+			),
+			Err(ref e) => log::info!(
+				target: LOG_TARGET,
+				"request={} method={:?} duration_ms={:.3} outcome=error code={} message={:?}",
+				request_id,
+				method,
+				duration_ms,
+				e.code,
+				e.message
+			),
+		}
+		result
 	}
 
 	/// Process a raw JSON string and return a JSON response
@@ -256,13 +339,13 @@ impl<H: RpcHandler> JsonRpcService<H> {
 	fn handle_single(&self, request: RpcRequest) -> Option<RpcResponse> {
 		// Notifications don't get responses
 		if request.is_notification() {
-			let _ = self.handler.handle(&request.method, request.params);
+			let _ = self.call(&request.method, request.params);
 			return None;
 		}
 
 		let id = request.id.clone().unwrap_or(Value::Null);
 
-		let response = match self.handler.handle(&request.method, request.params) {
+		let response = match self.call(&request.method, request.params) {
 			Ok(result) => RpcResponse::success(result, id),
 			Err(error) => RpcResponse::error(error, id),
 		};
@@ -271,8 +354,40 @@ impl<H: RpcHandler> JsonRpcService<H> {
 	}
 
 	/// Handle a batch of RPC requests
-	fn handle_batch(&self, requests: Vec<RpcRequest>) -> Vec<RpcResponse> {
-		requests.into_iter().filter_map(|request| self.handle_single(request)).collect()
+	fn handle_batch(
+		&self,
+		requests: Vec<Result<RpcRequest, Box<RpcResponse>>>,
+	) -> Vec<RpcResponse> {
+		requests
+			.into_iter()
+			.filter_map(|request| match request {
+				Ok(request) => self.handle_single(request),
+				Err(response) => Some(*response),
+			})
+			.collect()
+	}
+}
+
+/// Replace the values of secret params, at any depth, so they can be logged.
+fn redact(value: Value) -> Value {
+	redact_fields(value, SECRET_PARAMS)
+}
+
+/// Replace the values of the given fields, at any depth.
+pub(super) fn redact_fields(value: Value, fields: &[&str]) -> Value {
+	match value {
+		Value::Object(map) => Value::Object(
+			map.into_iter()
+				.map(|(key, value)| match fields.contains(&key.as_str()) {
+					true => (key, Value::String("<redacted>".to_owned())),
+					false => (key, redact_fields(value, fields)),
+				})
+				.collect(),
+		),
+		Value::Array(items) => {
+			Value::Array(items.into_iter().map(|item| redact_fields(item, fields)).collect())
+		}
+		value => value,
 	}
 }
 
@@ -358,6 +473,38 @@ mod tests {
 		assert!(response.contains(r#""code":-32600"#));
 	}
 
+	#[test]
+	fn test_invalid_request_object() {
+		let service = JsonRpcService::new(TestHandler);
+		let response = service.handle_raw(r#"{"foo":"bar"}"#);
+		assert!(response.contains(r#""code":-32600"#));
+
+		let response = service.handle_raw("[]");
+		assert!(response.contains(r#""code":-32600"#));
+	}
+
+	#[test]
+	fn test_batch_with_invalid_items() {
+		let service = JsonRpcService::new(TestHandler);
+		let request = r#"[
+            {"jsonrpc":"2.0","method":"add","params":[1,2],"id":"a"},
+            1,
+            {"jsonrpc":"1.0","method":"add","params":[1,2],"id":"b"},
+            {"jsonrpc":"2.0","method":"add","params":[3,4],"id":"c"}
+        ]"#;
+		let response: Vec<RpcResponse> =
+			serde_json::from_str(&service.handle_raw(request)).unwrap();
+		assert_eq!(response.len(), 4);
+		assert_eq!(response[0].id, Value::from("a"));
+		assert_eq!(response[0].result, Some(Value::from(3)));
+		assert_eq!(response[1].id, Value::Null);
+		assert_eq!(response[1].error.as_ref().unwrap().code, ErrorCode::InvalidRequest.code());
+		assert_eq!(response[2].id, Value::from("b"));
+		assert_eq!(response[2].error.as_ref().unwrap().code, ErrorCode::InvalidRequest.code());
+		assert_eq!(response[3].id, Value::from("c"));
+		assert_eq!(response[3].result, Some(Value::from(7)));
+	}
+
 	#[test]
 	fn test_batch_with_notifications() {
 		let service = JsonRpcService::new(TestHandler);
@@ -370,4 +517,17 @@ mod tests {
 		assert!(response.contains(r#""result":3"#));
 		assert!(response.contains(r#""id":1"#));
 	}
+
+	#[test]
+	fn test_redact() {
+		let params = serde_json::json!({
+			"tx": "0200",
+			"secret_key": "0101",
+			"nested": [{ "secret_key": "0202" }],
+		});
+		let redacted = redact(params);
+		assert_eq!(redacted["tx"], "0200");
+		assert_eq!(redacted["secret_key"], "<redacted>");
+		assert_eq!(redacted["nested"][0]["secret_key"], "<redacted>");
+	}
 }
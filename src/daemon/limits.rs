@@ -0,0 +1,152 @@
+//! Request size and rate limits
+//!
+//! Requests (and WebSocket messages) larger than the maximum body size are
+//! rejected before they are parsed. If a rate limit is set, each client,
+//! identified by its IP address, gets a bucket holding up to `burst` requests
+//! which refills at `requests_per_second`. Clients on the unix socket are not
+//! rate limited, since access to it is controlled by filesystem permissions.
+
+use std::collections::HashMap;
+use std::net::IpAddr;
+use std::sync::Mutex;
+use std::time::Instant;
+
+/// Default maximum request body size, which fits the hex of a full block.
+pub const DEFAULT_MAX_BODY_SIZE: usize = 16 * 1024 * 1024;
+
+/// JSON-RPC error code for WebSocket messages over the rate limit.
+pub const RATE_LIMITED_CODE: i64 = -32000;
+
+/// Number of clients above which those with full buckets are forgotten.
+const MAX_TRACKED_CLIENTS: usize = 10_000;
+
+/// The limits applied to every request.
+#[derive(Debug)]
+pub struct Limits {
+	/// Maximum size in bytes of a request body or WebSocket message.
+	pub max_body_size: usize,
+	rate: Option<RateLimiter>,
+}
+
+impl Default for Limits {
+	fn default() -> Self {
+		Self {
+			max_body_size: DEFAULT_MAX_BODY_SIZE,
+			rate: None,
+		}
+	}
+}
+
+impl Limits {
+	/// Set the maximum size in bytes of a request body or WebSocket message.
+	pub fn with_max_body_size(mut self, max_body_size: usize) -> Self {
+		self.max_body_size = max_body_size;
+		self
+	}
+
+	/// Allow each client `requests_per_second` on average, and up to `burst`
+	/// requests at once.
+	pub fn with_rate_limit(mut self, requests_per_second: f64, burst: u32) -> Self {
+		self.rate = Some(RateLimiter {
+			rate: requests_per_second,
+			burst: f64::from(burst.max(1)),
+			buckets: Mutex::new(HashMap::new()),
+		});
+		self
+	}
+
+	/// Count a request from the given client, returning whether it is within
+	/// the rate limit. Requests without a client address are always allowed.
+	pub fn allow(&self, client: Option<IpAddr>) -> bool {
+		match (&self.rate, client) {
+			(Some(rate), Some(client)) => rate.allow(client, Instant::now()),
+			_ => true,
+		}
+	}
+}
+
+#[derive(Debug)]
+struct RateLimiter {
+	rate: f64,
+	burst: f64,
+	buckets: Mutex<HashMap<IpAddr, Bucket>>,
+}
+
+#[derive(Debug)]
+struct Bucket {
+	tokens: f64,
+	updated: Instant,
+}
+
+impl RateLimiter {
+	fn allow(&self, client: IpAddr, now: Instant) -> bool {
+		let refill = |bucket: &Bucket| {
+			let elapsed = now.saturating_duration_since(bucket.updated).as_secs_f64();
+			(bucket.tokens + elapsed * self.rate).min(self.burst)
+		};
+
+		let mut buckets = self.buckets.lock().expect("poisoned");
+		if buckets.len() >= MAX_TRACKED_CLIENTS && !buckets.contains_key(&client) {
+			buckets.retain(|_, bucket| refill(bucket) < self.burst);
+		}
+
+		let bucket = buckets.entry(client).or_insert(Bucket {
+			tokens: self.burst,
+			updated: now,
+		});
+		bucket.tokens = refill(bucket);
+		bucket.updated = now;
+		if bucket.tokens >= 1.0 {
+			bucket.tokens -= 1.0;
+			true
+		} else {
+			false
+		}
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use std::time::Duration;
+
+	#[test]
+	fn test_rate_limit() {
+		let limits = Limits::default().with_rate_limit(2.0, 3);
+		let rate = limits.rate.as_ref().unwrap();
+		let alice = IpAddr::from([127, 0, 0, 1]);
+		let bob = IpAddr::from([127, 0, 0, 2]);
+		let start = Instant::now();
+
+		// The burst is available at once, then the bucket is empty.
+		for _ in 0..3 {
+			assert!(rate.allow(alice, start));
+		}
+		assert!(!rate.allow(alice, start));
+		// Other clients have their own bucket.
+		assert!(rate.allow(bob, start));
+
+		// At two requests per second, one more is allowed after half a second.
+		let later = start + Duration::from_millis(500);
+		assert!(rate.allow(alice, later));
+		assert!(!rate.allow(alice, later));
+
+		// The bucket never holds more than the burst.
+		let much_later = start + Duration::from_secs(60);
+		for _ in 0..3 {
+			assert!(rate.allow(alice, much_later));
+		}
+		assert!(!rate.allow(alice, much_later));
+	}
+
+	#[test]
+	fn test_no_rate_limit() {
+		let limits = Limits::default();
+		for _ in 0..100 {
+			assert!(limits.allow(Some(IpAddr::from([127, 0, 0, 1]))));
+		}
+		let limits = Limits::default().with_rate_limit(1.0, 1);
+		assert!(limits.allow(None));
+		assert!(limits.allow(None));
+	}
+}
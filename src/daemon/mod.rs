@@ -1,24 +1,65 @@
+#[cfg(feature = "async-client")]
+pub mod async_client;
+pub mod auth;
+pub mod backend;
+pub mod cache;
+pub mod client;
+pub mod config;
+pub mod cors;
+pub mod errors;
 pub mod handler;
+pub mod jobs;
 pub mod types;
 
 pub mod jsonrpc;
+pub mod limits;
+pub mod permissions;
+pub mod pool;
+pub mod profiles;
+pub mod record;
+pub mod rest;
+pub mod schema;
+pub mod sessions;
+pub mod store;
+pub mod stream;
+#[cfg(feature = "otel")]
+pub mod telemetry;
+pub mod tls;
+pub mod watch;
+pub mod websocket;
 
-use std::net::SocketAddr;
+use std::convert::Infallible;
+use std::net::{IpAddr, SocketAddr};
+use std::path::PathBuf;
 use std::sync::Arc;
+use std::time::Duration;
 
-use http_body_util::{BodyExt, Full};
+use http_body_util::combinators::UnsyncBoxBody;
+use http_body_util::{BodyExt, Full, LengthLimitError, Limited};
 use hyper::body::Bytes;
 use hyper::server::conn::http1;
 use hyper::service::service_fn;
 use hyper::{body::Incoming, Method, Request, Response, StatusCode};
 use hyper_util::rt::TokioIo;
+use tokio::io::{AsyncRead, AsyncWrite};
 use tokio::net::TcpListener;
 use tokio::sync::broadcast;
+use tokio::task::JoinSet;
+use tokio_rustls::TlsAcceptor;
 
 use thiserror::Error;
 
+use auth::Auth;
+use cors::Cors;
 use handler::DefaultRpcHandler;
 use jsonrpc::JsonRpcService;
+use limits::Limits;
+
+/// The body of an HTTP response, which may be streamed.
+type Body = UnsyncBoxBody<Bytes, Infallible>;
+
+/// How long to wait for open connections to finish on shutdown.
+pub const SHUTDOWN_TIMEOUT: Duration = Duration::from_secs(10);
 
 /// Errors that can occur in the daemon, usually on startup.
 #[derive(Error, Debug)]
@@ -27,14 +68,56 @@ pub enum DaemonError {
 	Io(#[from] std::io::Error),
 	#[error("Address parse error: {0}")]
 	AddrParse(#[from] std::net::AddrParseError),
+	#[error("TLS error: {0}")]
+	Tls(String),
+	#[error("Configuration error: {0}")]
+	Config(String),
+	#[error("Storage error: {0}")]
+	Store(String),
+	#[error("Telemetry error: {0}")]
+	Telemetry(String),
+}
+
+/// A socket the daemon serves on.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum Endpoint {
+	/// HTTP over TCP, on IPv4 or IPv6.
+	Http(SocketAddr),
+	/// HTTP on a unix socket, so that access can be controlled by filesystem
+	/// permissions. Any existing file at the path is replaced.
+	Unix(PathBuf),
+	/// JSON-RPC over WebSocket, on IPv4 or IPv6.
+	WebSocket(SocketAddr),
+}
+
+impl std::fmt::Display for Endpoint {
+	fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+		match self {
+			Self::Http(address) => write!(f, "{}", address),
+			Self::Unix(path) => write!(f, "unix socket {}", path.display()),
+			Self::WebSocket(address) => write!(f, "WebSocket {}", address),
+		}
+	}
+}
+
+/// An endpoint, with the authentication it requires if it differs from the
+/// rest of the daemon.
+struct Listener {
+	endpoint: Endpoint,
+	auth: Option<Arc<Auth>>,
 }
 
 /// The HAL Simplicity Daemon
 ///
-/// It listens for JSON-RPC requests over HTTP and handles them.
+/// It listens for JSON-RPC requests over HTTP (on any number of TCP addresses
+/// and unix sockets), and optionally over WebSocket, and handles them.
 /// Does not block the current thread when started. Instead, it spawns a new thread.
 pub struct HalSimplicityDaemon {
-	address: SocketAddr,
+	listeners: Vec<Listener>,
+	auth: Arc<Auth>,
+	tls: Option<TlsAcceptor>,
+	limits: Arc<Limits>,
+	cors: Arc<Cors>,
 	shutdown_tx: broadcast::Sender<()>,
 	rpc_service: Arc<JsonRpcService<DefaultRpcHandler>>,
 }
@@ -46,54 +129,189 @@ impl HalSimplicityDaemon {
 		let rpc_service = Arc::new(handler::create_service());
 
 		Ok(Self {
-			address,
+			listeners: vec![Listener {
+				endpoint: Endpoint::Http(address),
+				auth: None,
+			}],
+			auth: Arc::new(Auth::default()),
+			tls: None,
+			limits: Arc::new(Limits::default()),
+			cors: Arc::new(Cors::default()),
 			shutdown_tx,
 			rpc_service,
-		})
+		}
+		.with_handler(DefaultRpcHandler::default()))
 	}
 
-	/// Core event loop that accepts connections and handles them
-	async fn run_event_loop(
-		listener: TcpListener,
-		rpc_service: Arc<JsonRpcService<DefaultRpcHandler>>,
-		mut shutdown_rx: broadcast::Receiver<()>,
-	) -> Result<(), DaemonError> {
-		loop {
-			tokio::select! {
-				Ok((stream, _)) = listener.accept() => {
-					let io = TokioIo::new(stream);
-					let rpc_service_clone = rpc_service.clone();
-					tokio::task::spawn(async move {
-						http1::Builder::new()
-							.serve_connection(io, service_fn(move |req| {
-								handle_request(req, rpc_service_clone.clone())
-							}))
-							.await
-					});
+	/// Also serve HTTP on another TCP address, e.g. `[::1]:28579` for IPv6.
+	pub fn with_address(self, address: &str) -> Result<Self, DaemonError> {
+		Ok(self.with_endpoint(Endpoint::Http(address.parse()?), None))
+	}
+
+	/// Also serve HTTP on a unix socket at the given path, so that access can be
+	/// controlled by filesystem permissions. Any existing file at the path is replaced.
+	pub fn with_unix_socket(self, path: impl Into<PathBuf>) -> Self {
+		self.with_endpoint(Endpoint::Unix(path.into()), None)
+	}
+
+	/// Do not serve HTTP over TCP, e.g. when only a unix socket should be used.
+	pub fn without_tcp(mut self) -> Self {
+		self.listeners.retain(|listener| !matches!(listener.endpoint, Endpoint::Http(_)));
+		self
+	}
+
+	/// Also listen for JSON-RPC over WebSocket on the given address.
+	pub fn with_websocket(self, address: &str) -> Result<Self, DaemonError> {
+		Ok(self.with_endpoint(Endpoint::WebSocket(address.parse()?), None))
+	}
+
+	/// Also serve on `endpoint`. If `auth` is given, it is required there
+	/// instead of the authentication set with [`Self::with_auth`].
+	pub fn with_endpoint(mut self, endpoint: Endpoint, auth: Option<Auth>) -> Self {
+		self.listeners.push(Listener {
+			endpoint,
+			auth: auth.map(Arc::new),
+		});
+		self
+	}
+
+	/// The endpoints the daemon serves on.
+	pub fn endpoints(&self) -> impl Iterator<Item = &Endpoint> {
+		self.listeners.iter().map(|listener| &listener.endpoint)
+	}
+
+	/// Handle requests with the given handler, e.g. to change its defaults.
+	pub fn with_handler(mut self, mut handler: DefaultRpcHandler) -> Self {
+		handler.shutdown = Some(self.shutdown_tx.clone());
+		let workers = handler.workers.workers();
+		self.rpc_service = Arc::new(JsonRpcService::new(handler));
+		jobs::spawn_runners(Arc::downgrade(&self.rpc_service), workers);
+		self
+	}
+
+	/// Require every request to authenticate as configured in `auth`.
+	pub fn with_auth(mut self, auth: Auth) -> Self {
+		self.auth = Arc::new(auth);
+		self
+	}
+
+	/// Serve every listener over TLS with the given configuration (see [`tls`]).
+	pub fn with_tls(mut self, config: Arc<tokio_rustls::rustls::ServerConfig>) -> Self {
+		self.tls = Some(TlsAcceptor::from(config));
+		self
+	}
+
+	/// Limit the size and rate of requests (see [`limits`]).
+	pub fn with_limits(mut self, limits: Limits) -> Self {
+		self.limits = Arc::new(limits);
+		self
+	}
+
+	/// Allow cross-origin requests from browsers as configured in `cors`.
+	pub fn with_cors(mut self, cors: Cors) -> Self {
+		self.cors = Arc::new(cors);
+		self
+	}
+
+	/// Bind all the configured listeners.
+	async fn bind(&self) -> Result<Vec<Bound>, DaemonError> {
+		let mut bound = Vec::with_capacity(self.listeners.len());
+		for listener in &self.listeners {
+			let socket = match listener.endpoint {
+				Endpoint::Http(address) => BoundSocket::Http(TcpListener::bind(&address).await?),
+				Endpoint::WebSocket(address) => {
+					BoundSocket::WebSocket(TcpListener::bind(&address).await?)
+				}
+				#[cfg(unix)]
+				Endpoint::Unix(ref path) => {
+					match std::fs::remove_file(path) {
+						Err(e) if e.kind() != std::io::ErrorKind::NotFound => return Err(e.into()),
+						_ => {}
+					}
+					BoundSocket::Unix(tokio::net::UnixListener::bind(path)?)
 				}
-				_ = shutdown_rx.recv() => {
-					break;
+				#[cfg(not(unix))]
+				Endpoint::Unix(_) => {
+					return Err(DaemonError::Io(std::io::Error::new(
+						std::io::ErrorKind::Unsupported,
+						"unix sockets are not supported on this platform",
+					)))
 				}
+			};
+			log::info!("Serving on {}...", listener.endpoint);
+			bound.push(Bound {
+				socket,
+				auth: listener.auth.clone().unwrap_or_else(|| self.auth.clone()),
+			});
+		}
+		Ok(bound)
+	}
+
+	/// Remove files created at startup.
+	fn cleanup(&self) {
+		self.auth.remove_cookie_file();
+		for listener in &self.listeners {
+			if let Some(ref auth) = listener.auth {
+				auth.remove_cookie_file();
 			}
+			if let Endpoint::Unix(ref path) = listener.endpoint {
+				let _ = std::fs::remove_file(path);
+			}
+		}
+	}
+
+	/// The state shared by every connection.
+	fn shared(&self) -> Shared {
+		// Let `getinfo` report the limit, which is set after the handler.
+		let _ = self.rpc_service.handler().max_request_size.set(self.limits.max_body_size);
+		Shared {
+			rpc_service: self.rpc_service.clone(),
+			auth: self.auth.clone(),
+			tls: self.tls.clone(),
+			limits: self.limits.clone(),
+			cors: self.cors.clone(),
+			shutdown: self.shutdown_tx.clone(),
+		}
+	}
+
+	/// Core event loop that accepts connections on every listener and handles them
+	///
+	/// On shutdown, it stops accepting connections and waits up to
+	/// [`SHUTDOWN_TIMEOUT`] for the open ones to finish their requests.
+	async fn run_event_loop(
+		listeners: Vec<Bound>,
+		shared: Shared,
+		mut shutdown_rx: broadcast::Receiver<()>,
+	) -> Result<(), DaemonError> {
+		let (stop_tx, stop_rx) = tokio::sync::watch::channel(());
+		let mut tasks = JoinSet::new();
+		for listener in listeners {
+			let shared = Shared {
+				auth: listener.auth,
+				..shared.clone()
+			};
+			tasks.spawn(serve_listener(listener.socket, shared, stop_rx.clone()));
 		}
 
+		let _ = shutdown_rx.recv().await;
+		let _ = stop_tx.send(());
+		while tasks.join_next().await.is_some() {}
+
 		Ok(())
 	}
 
 	/// Start the daemon on a new thread.
 	/// Useful when you need just to spawn the daemon and continue doing other things in the main thread.
 	pub fn start(&mut self) -> Result<(), DaemonError> {
-		let address = self.address;
-		let shutdown_tx = self.shutdown_tx.clone();
-		let rpc_service = self.rpc_service.clone();
+		let shared = self.shared();
 
 		let runtime = tokio::runtime::Runtime::new()?;
-		let listener = runtime.block_on(async { TcpListener::bind(&address).await })?;
+		let listeners = runtime.block_on(self.bind())?;
+		let shutdown_rx = self.shutdown_tx.subscribe();
 
 		std::thread::spawn(move || {
 			runtime.block_on(async move {
-				let shutdown_rx = shutdown_tx.subscribe();
-				let _ = Self::run_event_loop(listener, rpc_service, shutdown_rx).await;
+				let _ = Self::run_event_loop(listeners, shared, shutdown_rx).await;
 			});
 		});
 
@@ -105,41 +323,337 @@ impl HalSimplicityDaemon {
 	pub fn listen_blocking(self) -> Result<(), DaemonError> {
 		let runtime = tokio::runtime::Runtime::new()?;
 
-		runtime.block_on(async move {
-			let listener = TcpListener::bind(&self.address).await?;
+		let result = runtime.block_on(async {
+			let listeners = self.bind().await?;
 			let shutdown_rx = self.shutdown_tx.subscribe();
-			Self::run_event_loop(listener, self.rpc_service, shutdown_rx).await
-		})
+			tokio::spawn(shutdown_on_signal(self.shutdown_tx.clone()));
+			Self::run_event_loop(listeners, self.shared(), shutdown_rx).await
+		});
+		self.cleanup();
+		result
 	}
 
 	/// Shutdown the daemon
 	pub fn shutdown(&self) {
 		let _ = self.shutdown_tx.send(());
+		self.cleanup();
+	}
+}
+
+/// A bound listener, with the authentication it requires.
+struct Bound {
+	socket: BoundSocket,
+	auth: Arc<Auth>,
+}
+
+/// A bound socket.
+enum BoundSocket {
+	Http(TcpListener),
+	WebSocket(TcpListener),
+	#[cfg(unix)]
+	Unix(tokio::net::UnixListener),
+}
+
+/// An accepted connection.
+enum Connection {
+	Http(tokio::net::TcpStream, SocketAddr),
+	WebSocket(tokio::net::TcpStream, SocketAddr),
+	#[cfg(unix)]
+	Unix(tokio::net::UnixStream),
+}
+
+impl BoundSocket {
+	async fn accept(&self) -> std::io::Result<Connection> {
+		match self {
+			Self::Http(listener) => {
+				listener.accept().await.map(|(stream, peer)| Connection::Http(stream, peer))
+			}
+			Self::WebSocket(listener) => {
+				listener.accept().await.map(|(stream, peer)| Connection::WebSocket(stream, peer))
+			}
+			#[cfg(unix)]
+			Self::Unix(listener) => listener.accept().await.map(|(stream, _)| Connection::Unix(stream)),
+		}
 	}
 }
 
-/// Handles an incoming HTTP request and produces a response.
+/// The state shared by every connection.
+#[derive(Clone)]
+struct Shared {
+	rpc_service: Arc<JsonRpcService<DefaultRpcHandler>>,
+	auth: Arc<Auth>,
+	tls: Option<TlsAcceptor>,
+	limits: Arc<Limits>,
+	cors: Arc<Cors>,
+	shutdown: broadcast::Sender<()>,
+}
+
+/// Trigger a shutdown on SIGINT or, on unix, SIGTERM.
+async fn shutdown_on_signal(shutdown_tx: broadcast::Sender<()>) {
+	#[cfg(unix)]
+	let terminate = async {
+		match tokio::signal::unix::signal(tokio::signal::unix::SignalKind::terminate()) {
+			Ok(mut signal) => {
+				signal.recv().await;
+			}
+			Err(e) => {
+				log::warn!("Cannot listen for SIGTERM: {}", e);
+				std::future::pending::<()>().await
+			}
+		}
+	};
+	#[cfg(not(unix))]
+	let terminate = std::future::pending::<()>();
+
+	tokio::select! {
+		Ok(()) = tokio::signal::ctrl_c() => log::info!("Received SIGINT, shutting down..."),
+		_ = terminate => log::info!("Received SIGTERM, shutting down..."),
+	}
+	let _ = shutdown_tx.send(());
+}
+
+/// Serve HTTP requests on a single connection until it closes, or until
+/// shutdown once its current request is answered.
+///
+/// `client` is the address of the peer, if it is connected over TCP.
+async fn serve_http<S>(
+	stream: S,
+	client: Option<IpAddr>,
+	shared: Shared,
+	mut shutdown_rx: broadcast::Receiver<()>,
+) where
+	S: AsyncRead + AsyncWrite + Unpin + Send + 'static,
+{
+	let connection = http1::Builder::new().serve_connection(
+		TokioIo::new(stream),
+		service_fn(move |req| handle_request(req, client, shared.clone())),
+	);
+	tokio::pin!(connection);
+	let result = tokio::select! {
+		result = connection.as_mut() => result,
+		_ = shutdown_rx.recv() => {
+			connection.as_mut().graceful_shutdown();
+			connection.await
+		}
+	};
+	if let Err(e) = result {
+		log::debug!("HTTP connection error: {}", e);
+	}
+}
+
+/// Accept connections on one listener and serve them until `stop`, then wait
+/// up to [`SHUTDOWN_TIMEOUT`] for the open ones to finish their requests.
+async fn serve_listener(
+	socket: BoundSocket,
+	shared: Shared,
+	mut stop: tokio::sync::watch::Receiver<()>,
+) {
+	let mut connections = JoinSet::new();
+	loop {
+		tokio::select! {
+			accepted = socket.accept() => match accepted {
+				Ok(connection) => {
+					let shutdown_rx = shared.shutdown.subscribe();
+					connections.spawn(serve_connection(connection, shared.clone(), shutdown_rx));
+				}
+				Err(e) => log::debug!("Failed to accept connection: {}", e),
+			},
+			Some(_) = connections.join_next() => {}
+			_ = stop.changed() => break,
+		}
+	}
+
+	drop(socket);
+	if !connections.is_empty() {
+		log::info!("Waiting for {} connections to close...", connections.len());
+	}
+	let drain = async { while connections.join_next().await.is_some() {} };
+	if tokio::time::timeout(SHUTDOWN_TIMEOUT, drain).await.is_err() {
+		log::warn!("Closing {} connections which did not finish in time", connections.len());
+	}
+}
+
+/// Serve a single connection, over TLS if configured, until it closes.
+async fn serve_connection(
+	connection: Connection,
+	shared: Shared,
+	shutdown_rx: broadcast::Receiver<()>,
+) {
+	match connection {
+		Connection::WebSocket(stream, peer) => {
+			let client = Some(peer.ip());
+			let Shared {
+				rpc_service,
+				auth,
+				tls,
+				limits,
+				..
+			} = shared;
+			match tls {
+				Some(ref acceptor) => match acceptor.accept(stream).await {
+					Ok(stream) => {
+						websocket::serve_connection(
+							stream,
+							client,
+							rpc_service,
+							auth,
+							limits,
+							shutdown_rx,
+						)
+						.await
+					}
+					Err(e) => log::debug!("TLS handshake failed: {}", e),
+				},
+				None => {
+					websocket::serve_connection(
+						stream,
+						client,
+						rpc_service,
+						auth,
+						limits,
+						shutdown_rx,
+					)
+					.await
+				}
+			}
+		}
+		#[cfg(unix)]
+		Connection::Unix(stream) => serve_http(stream, None, shared, shutdown_rx).await,
+		Connection::Http(stream, peer) => {
+			let client = Some(peer.ip());
+			match shared.tls {
+				Some(ref acceptor) => match acceptor.accept(stream).await {
+					Ok(stream) => serve_http(stream, client, shared, shutdown_rx).await,
+					Err(e) => log::debug!("TLS handshake failed: {}", e),
+				},
+				None => serve_http(stream, client, shared, shutdown_rx).await,
+			}
+		}
+	}
+}
+
+/// Handles an incoming HTTP request and produces a response, with CORS
+/// headers if configured.
 async fn handle_request(
 	req: Request<Incoming>,
-	rpc_service: Arc<JsonRpcService<DefaultRpcHandler>>,
-) -> Result<Response<Full<Bytes>>, DaemonError> {
+	client: Option<IpAddr>,
+	shared: Shared,
+) -> Result<Response<Body>, DaemonError> {
+	let origin = req.headers().get(hyper::header::ORIGIN).cloned();
+	let cors = shared.cors.clone();
+	if req.method() == Method::OPTIONS && cors.is_enabled() {
+		let mut response = create_status_response(StatusCode::NO_CONTENT);
+		cors.apply_preflight(origin.as_ref(), response.headers_mut());
+		return Ok(response);
+	}
+
+	let mut response = respond(req, client, shared).await?;
+	cors.apply(origin.as_ref(), response.headers_mut());
+	Ok(response)
+}
+
+/// Produces the response to an HTTP request.
+async fn respond(
+	req: Request<Incoming>,
+	client: Option<IpAddr>,
+	shared: Shared,
+) -> Result<Response<Body>, DaemonError> {
+	let Shared {
+		rpc_service,
+		auth,
+		limits,
+		..
+	} = shared;
+
+	if !limits.allow(client) {
+		let mut response = create_status_response(StatusCode::TOO_MANY_REQUESTS);
+		response
+			.headers_mut()
+			.insert(hyper::header::RETRY_AFTER, hyper::header::HeaderValue::from_static("1"));
+		return Ok(response);
+	}
+
+	if !auth.check(req.headers()) {
+		let mut response = create_status_response(StatusCode::UNAUTHORIZED);
+		response.headers_mut().insert(
+			hyper::header::WWW_AUTHENTICATE,
+			hyper::header::HeaderValue::from_static("Basic realm=\"jsonrpc\""),
+		);
+		return Ok(response);
+	}
+
 	let path = req.uri().path();
 	let method = req.method();
+	let span = tracing::info_span!("http", %method, path);
+	#[cfg(feature = "otel")]
+	if let Some(traceparent) =
+		req.headers().get(telemetry::TRACEPARENT).and_then(|value| value.to_str().ok())
+	{
+		telemetry::continue_trace(&span, traceparent);
+	}
+
+	if path.starts_with(rest::PREFIX) {
+		let stream = rest::is_stream(method, path, req.headers());
+		let method = method.clone();
+		let path = path.to_owned();
+		let query = req.uri().query().map(str::to_owned);
+		let body_str = match read_body_as_string(req, limits.max_body_size).await {
+			Ok(body) => body,
+			Err(status) => return Ok(create_status_response(status)),
+		};
+
+		if stream {
+			return Ok(match span.in_scope(|| rest::handle_stream(rpc_service, &body_str)) {
+				Ok(body) => {
+					let mut response = Response::new(body);
+					response.headers_mut().insert(
+						hyper::header::CONTENT_TYPE,
+						hyper::header::HeaderValue::from_static(stream::NDJSON),
+					);
+					response
+				}
+				Err((status, response_str)) => {
+					let mut response = create_json_response(response_str);
+					*response.status_mut() = status;
+					response
+				}
+			});
+		}
+
+		let (status, response_str) = tokio::task::spawn_blocking(move || {
+			span.in_scope(|| {
+				rest::handle(&rpc_service, &method, &path, query.as_deref(), &body_str)
+			})
+		})
+		.await
+		.unwrap_or((StatusCode::INTERNAL_SERVER_ERROR, String::new()));
+		let mut response = create_json_response(response_str);
+		*response.status_mut() = status;
+		return Ok(response);
+	}
 
 	if method != Method::POST {
-		return Ok(create_status_response(StatusCode::METHOD_NOT_ALLOWED));
+		let mut response = create_status_response(StatusCode::METHOD_NOT_ALLOWED);
+		response
+			.headers_mut()
+			.insert(hyper::header::ALLOW, hyper::header::HeaderValue::from_static("POST"));
+		return Ok(response);
 	}
 
 	if path != "/rpc" && path != "/" {
 		return Ok(create_status_response(StatusCode::NOT_FOUND));
 	}
 
-	let body_str = match read_body_as_string(req).await {
+	let body_str = match read_body_as_string(req, limits.max_body_size).await {
 		Ok(body) => body,
 		Err(status) => return Ok(create_status_response(status)),
 	};
 
-	let response_str = rpc_service.handle_raw(&body_str);
+	// Methods may take a while, so keep them off the async workers.
+	let response_str =
+		tokio::task::spawn_blocking(move || span.in_scope(|| rpc_service.handle_raw(&body_str)))
+			.await
+			.unwrap_or_default();
 
 	if response_str.is_empty() {
 		return Ok(create_status_response(StatusCode::NO_CONTENT));
@@ -149,30 +663,119 @@ async fn handle_request(
 }
 
 /// Creates an HTTP response with the given status code
-fn create_status_response(status: StatusCode) -> Response<Full<Bytes>> {
+fn create_status_response(status: StatusCode) -> Response<Body> {
 	let body = if status == StatusCode::NO_CONTENT {
 		Bytes::new()
 	} else {
 		Bytes::from(status.canonical_reason().unwrap_or("Unknown Error"))
 	};
-	let mut response = Response::new(Full::new(body));
+	let mut response = Response::new(Full::new(body).boxed_unsync());
 	*response.status_mut() = status;
+	if status != StatusCode::NO_CONTENT {
+		response.headers_mut().insert(
+			hyper::header::CONTENT_TYPE,
+			hyper::header::HeaderValue::from_static("text/plain; charset=utf-8"),
+		);
+	}
 	response
 }
 
-/// Reads and validates the request body as a UTF-8 string
-async fn read_body_as_string(req: Request<Incoming>) -> Result<String, StatusCode> {
-	let body_bytes = req.collect().await.map_err(|_| StatusCode::BAD_REQUEST)?.to_bytes();
+/// Reads and validates the request body as a UTF-8 string of at most `limit` bytes
+async fn read_body_as_string(req: Request<Incoming>, limit: usize) -> Result<String, StatusCode> {
+	let body_bytes = Limited::new(req.into_body(), limit)
+		.collect()
+		.await
+		.map_err(|e| match e.is::<LengthLimitError>() {
+			true => StatusCode::PAYLOAD_TOO_LARGE,
+			false => StatusCode::BAD_REQUEST,
+		})?
+		.to_bytes();
 
 	String::from_utf8(body_bytes.to_vec()).map_err(|_| StatusCode::BAD_REQUEST)
 }
 
-/// Creates a successful JSON-RPC response
-fn create_json_response(body: String) -> Response<Full<Bytes>> {
-	let mut response = Response::new(Full::new(Bytes::from(body)));
+/// Creates a JSON response
+fn create_json_response(body: String) -> Response<Body> {
+	let mut response = Response::new(Full::new(Bytes::from(body)).boxed_unsync());
 	response.headers_mut().insert(
 		hyper::header::CONTENT_TYPE,
 		hyper::header::HeaderValue::from_static("application/json"),
 	);
 	response
 }
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use crate::daemon::handler::create_service;
+	use tokio::io::{AsyncReadExt as _, AsyncWriteExt as _};
+
+	/// Send a raw HTTP request to a fresh connection, returning the raw response.
+	async fn request(raw: &str) -> String {
+		let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+		let address = listener.local_addr().unwrap();
+		let (shutdown, _) = broadcast::channel(1);
+		let shared = Shared {
+			rpc_service: Arc::new(create_service()),
+			auth: Arc::new(Auth::default()),
+			tls: None,
+			limits: Arc::new(Limits::default()),
+			cors: Arc::new(Cors::default()),
+			shutdown: shutdown.clone(),
+		};
+		tokio::spawn(async move {
+			let (stream, peer) = listener.accept().await.unwrap();
+			serve_http(stream, Some(peer.ip()), shared, shutdown.subscribe()).await;
+		});
+
+		let mut stream = tokio::net::TcpStream::connect(address).await.unwrap();
+		stream.write_all(raw.as_bytes()).await.unwrap();
+		let mut response = String::new();
+		stream.read_to_string(&mut response).await.unwrap();
+		response.to_lowercase()
+	}
+
+	#[tokio::test]
+	async fn test_method_not_allowed() {
+		let response = request("GET /rpc HTTP/1.1\r\nHost: x\r\nConnection: close\r\n\r\n").await;
+		assert!(response.starts_with("http/1.1 405 "), "{}", response);
+		assert!(response.contains("\r\nallow: post\r\n"), "{}", response);
+		assert!(
+			response.contains("\r\ncontent-type: text/plain; charset=utf-8\r\n"),
+			"{}",
+			response
+		);
+	}
+
+	#[tokio::test]
+	async fn test_content_type() {
+		let body = r#"{"jsonrpc":"2.0","method":"getinfo","id":1}"#;
+		let response = request(&format!(
+			"POST /rpc HTTP/1.1\r\nHost: x\r\nConnection: close\r\nContent-Length: {}\r\n\r\n{}",
+			body.len(),
+			body
+		))
+		.await;
+		assert!(response.starts_with("http/1.1 200 "), "{}", response);
+		assert!(response.contains("\r\ncontent-type: application/json\r\n"), "{}", response);
+
+		// Notifications have no response body, so no content type.
+		let body = r#"{"jsonrpc":"2.0","method":"getinfo"}"#;
+		let response = request(&format!(
+			"POST /rpc HTTP/1.1\r\nHost: x\r\nConnection: close\r\nContent-Length: {}\r\n\r\n{}",
+			body.len(),
+			body
+		))
+		.await;
+		assert!(response.starts_with("http/1.1 204 "), "{}", response);
+		assert!(!response.contains("content-type"), "{}", response);
+
+		let response = request("POST /nope HTTP/1.1\r\nHost: x\r\nConnection: close\r\n\r\n").await;
+		assert!(response.starts_with("http/1.1 404 "), "{}", response);
+		assert!(
+			response.contains("\r\ncontent-type: text/plain; charset=utf-8\r\n"),
+			"{}",
+			response
+		);
+	}
+}
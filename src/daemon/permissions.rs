@@ -0,0 +1,116 @@
+//! Which methods the daemon serves
+//!
+//! By default every method is served. A deployment which should only offer
+//! pure functions, such as a public decoding service, can list the methods it
+//! allows, deny individual methods such as `tx_broadcast`, and refuse any
+//! request which carries a secret key. Refused calls fail with
+//! [`METHOD_DENIED_CODE`].
+
+use std::collections::HashSet;
+
+use serde_json::Value;
+
+use super::handler::RpcMethod;
+use super::jsonrpc::{RpcError, SECRET_PARAMS};
+
+/// JSON-RPC error code for calls refused by the daemon's configuration.
+pub const METHOD_DENIED_CODE: i64 = -32002;
+
+/// The methods which may be called.
+#[derive(Clone, Debug, Default)]
+pub struct Permissions {
+	allow: Option<HashSet<RpcMethod>>,
+	deny: HashSet<RpcMethod>,
+	deny_secrets: bool,
+}
+
+impl Permissions {
+	/// Serve only the given methods, less any denied ones.
+	pub fn allow_only(mut self, methods: impl IntoIterator<Item = RpcMethod>) -> Self {
+		self.allow = Some(methods.into_iter().collect());
+		self
+	}
+
+	/// Never serve the given methods.
+	pub fn deny(mut self, methods: impl IntoIterator<Item = RpcMethod>) -> Self {
+		self.deny.extend(methods);
+		self
+	}
+
+	/// Refuse requests with a secret key among their params.
+	pub fn deny_secrets(mut self) -> Self {
+		self.deny_secrets = true;
+		self
+	}
+
+	/// Whether a method is served at all.
+	pub fn allows(&self, method: RpcMethod) -> bool {
+		self.allow.as_ref().map_or(true, |allow| allow.contains(&method))
+			&& !self.deny.contains(&method)
+	}
+
+	/// Check whether a call may go ahead.
+	pub fn check(&self, method: RpcMethod, params: Option<&Value>) -> Result<(), RpcError> {
+		if !self.allows(method) {
+			return Err(RpcError::custom(
+				METHOD_DENIED_CODE,
+				format!("Method {} is disabled", method.as_str()),
+			));
+		}
+		if self.deny_secrets && params.is_some_and(contains_secret) {
+			return Err(RpcError::custom(
+				METHOD_DENIED_CODE,
+				"Secret keys are not accepted by this daemon".to_string(),
+			));
+		}
+		Ok(())
+	}
+}
+
+/// Whether a secret param is set, at any depth.
+fn contains_secret(value: &Value) -> bool {
+	match value {
+		Value::Object(map) => map.iter().any(|(key, value)| {
+			(SECRET_PARAMS.contains(&key.as_str()) && !value.is_null()) || contains_secret(value)
+		}),
+		Value::Array(items) => items.iter().any(contains_secret),
+		_ => false,
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use crate::daemon::handler::DefaultRpcHandler;
+	use crate::daemon::jsonrpc::RpcHandler;
+	use serde_json::json;
+
+	#[test]
+	fn test_permissions() {
+		let mut handler = DefaultRpcHandler::default();
+		handler.permissions = Permissions::default()
+			.allow_only([
+				RpcMethod::GetInfo,
+				RpcMethod::KeypairGenerate,
+				RpcMethod::SimplicitySighash,
+			])
+			.deny([RpcMethod::KeypairGenerate])
+			.deny_secrets();
+
+		let err = handler.handle("keypair_generate", None).unwrap_err();
+		assert_eq!(err.code, METHOD_DENIED_CODE);
+		let err = handler.handle("tx_decode", Some(json!({ "raw_tx": "00" }))).unwrap_err();
+		assert_eq!(err.code, METHOD_DENIED_CODE);
+
+		let params = json!({ "tx": "00", "input_index": 0, "cmr": "00", "secret_key": "01" });
+		let err = handler.handle("simplicity_sighash", Some(params)).unwrap_err();
+		assert_eq!(err.code, METHOD_DENIED_CODE);
+		let params = json!({ "tx": "00", "input_index": 0, "cmr": "00", "secret_key": null });
+		let err = handler.handle("simplicity_sighash", Some(params)).unwrap_err();
+		assert_ne!(err.code, METHOD_DENIED_CODE);
+
+		// Disabled methods are not advertised.
+		let info = handler.handle("getinfo", None).unwrap();
+		assert_eq!(info["methods"], json!(["getinfo", "simplicity_sighash"]));
+	}
+}
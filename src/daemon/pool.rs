@@ -0,0 +1,132 @@
+//! Bounded concurrency for expensive methods
+//!
+//! Methods which run a Simplicity program (`pset_run` and `pset_finalize`) can
+//! keep a CPU busy for a long time. At most `workers` of them run at once and
+//! at most `queue` more wait for their turn. Any further call fails at once
+//! with [`SERVER_BUSY_CODE`], so that a burst of them cannot starve the cheap
+//! methods.
+
+use std::sync::{Condvar, Mutex};
+
+use super::jsonrpc::RpcError;
+
+/// JSON-RPC error code for calls rejected because the pool is saturated.
+pub const SERVER_BUSY_CODE: i64 = -32001;
+
+/// Default number of calls waiting for a worker.
+pub const DEFAULT_QUEUE: usize = 16;
+
+/// A limit on how many expensive calls run at once.
+#[derive(Debug)]
+pub struct WorkerPool {
+	workers: usize,
+	queue: usize,
+	state: Mutex<State>,
+	turn: Condvar,
+}
+
+#[derive(Debug, Default)]
+struct State {
+	running: usize,
+	waiting: usize,
+}
+
+impl Default for WorkerPool {
+	/// One worker per available CPU, with a queue of [`DEFAULT_QUEUE`].
+	fn default() -> Self {
+		let workers = std::thread::available_parallelism().map(|n| n.get()).unwrap_or(1);
+		Self::new(workers, DEFAULT_QUEUE)
+	}
+}
+
+impl WorkerPool {
+	/// Allow `workers` calls to run at once and `queue` more to wait.
+	pub fn new(workers: usize, queue: usize) -> Self {
+		Self {
+			workers: workers.max(1),
+			queue,
+			state: Mutex::new(State::default()),
+			turn: Condvar::new(),
+		}
+	}
+
+	/// The number of calls which may run at once.
+	pub fn workers(&self) -> usize {
+		self.workers
+	}
+
+	/// Run `f` on the current thread once a worker is free, or fail if too
+	/// many calls are already waiting.
+	pub fn run<T>(&self, f: impl FnOnce() -> T) -> Result<T, RpcError> {
+		{
+			let mut state = self.state.lock().expect("poisoned");
+			if state.running >= self.workers {
+				if state.waiting >= self.queue {
+					return Err(RpcError::custom(SERVER_BUSY_CODE, "Server busy".to_string()));
+				}
+				state.waiting += 1;
+				while state.running >= self.workers {
+					state = self.turn.wait(state).expect("poisoned");
+				}
+				state.waiting -= 1;
+			}
+			state.running += 1;
+		}
+
+		// Free the worker even if `f` panics.
+		struct Release<'a>(&'a WorkerPool);
+		impl Drop for Release<'_> {
+			fn drop(&mut self) {
+				self.0.state.lock().expect("poisoned").running -= 1;
+				self.0.turn.notify_one();
+			}
+		}
+		let _release = Release(self);
+		Ok(f())
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use std::sync::mpsc;
+	use std::sync::Arc;
+	use std::time::Duration;
+
+	#[test]
+	fn test_busy() {
+		let pool = Arc::new(WorkerPool::new(1, 1));
+		let (started_tx, started_rx) = mpsc::channel();
+		let (finish_tx, finish_rx) = mpsc::channel::<()>();
+
+		// Occupy the only worker.
+		let running = {
+			let pool = pool.clone();
+			std::thread::spawn(move || {
+				pool.run(|| {
+					started_tx.send(()).unwrap();
+					finish_rx.recv().unwrap();
+				})
+			})
+		};
+		started_rx.recv().unwrap();
+
+		// The next call waits in the queue...
+		let queued = {
+			let pool = pool.clone();
+			std::thread::spawn(move || pool.run(|| 2))
+		};
+		while pool.state.lock().unwrap().waiting == 0 {
+			std::thread::sleep(Duration::from_millis(1));
+		}
+
+		// ...and the one after that is turned away.
+		let err = pool.run(|| 3).unwrap_err();
+		assert_eq!(err.code, SERVER_BUSY_CODE);
+
+		finish_tx.send(()).unwrap();
+		running.join().unwrap().unwrap();
+		assert_eq!(queued.join().unwrap().unwrap(), 2);
+		assert_eq!(pool.run(|| 4).unwrap(), 4);
+	}
+}
@@ -0,0 +1,200 @@
+//! Configuration file for clients
+//!
+//! Clients such as `hal-simplicity daemon status` find the daemon through a
+//! named profile in `~/.hal-simplicity/config.toml`, for example:
+//!
+//! ```toml
+//! default_profile = "local"
+//!
+//! [profiles.local]
+//! cookie_file = "/home/user/.hal-simplicity/.cookie"
+//!
+//! [profiles.testnet]
+//! url = "https://10.0.0.2:28579"
+//! token = "secret"
+//! ca_file = "/home/user/.hal-simplicity/testnet.pem"
+//!
+//! [profiles.socket]
+//! unix_socket = "/home/user/.hal-simplicity/daemon.sock"
+//! ```
+//!
+//! The [`URL_ENV`] and [`TOKEN_ENV`] environment variables override the
+//! selected profile.
+
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use serde::Deserialize;
+
+use super::client::{ClientError, HalSimplicity, DEFAULT_URL, UNIX_SCHEME};
+
+/// Environment variable holding the URL of the daemon.
+pub const URL_ENV: &str = "HAL_SIMPLICITY_URL";
+
+/// Environment variable holding a token to authenticate with.
+pub const TOKEN_ENV: &str = "HAL_SIMPLICITY_TOKEN";
+
+/// The client configuration.
+#[derive(Clone, Debug, Default, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct ClientConfig {
+	/// Profile used when none is named.
+	pub default_profile: Option<String>,
+	#[serde(default)]
+	pub profiles: BTreeMap<String, Profile>,
+}
+
+/// How to reach and authenticate with one daemon.
+#[derive(Clone, Debug, Default, PartialEq, Eq, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Profile {
+	/// URL of the daemon. Defaults to [`DEFAULT_URL`].
+	pub url: Option<String>,
+	/// Unix socket the daemon listens on, to connect to instead of `url`.
+	pub unix_socket: Option<PathBuf>,
+	/// Token to authenticate with.
+	pub token: Option<String>,
+	/// Cookie file written by the daemon, to authenticate with instead of `token`.
+	pub cookie_file: Option<PathBuf>,
+	/// PEM file of the CA certificates to trust for an `https` URL, such as
+	/// the certificate of a daemon with a self-signed one.
+	pub ca_file: Option<PathBuf>,
+}
+
+/// The default location of the configuration file, `~/.hal-simplicity/config.toml`.
+pub fn default_path() -> Option<PathBuf> {
+	std::env::var_os("HOME")
+		.or_else(|| std::env::var_os("USERPROFILE"))
+		.map(|home| PathBuf::from(home).join(".hal-simplicity").join("config.toml"))
+}
+
+impl ClientConfig {
+	/// Read a configuration file.
+	pub fn from_file(path: &Path) -> Result<Self, ClientError> {
+		let contents = fs::read_to_string(path)
+			.map_err(|e| ClientError::Config(format!("{}: {}", path.display(), e)))?;
+		toml::from_str(&contents)
+			.map_err(|e| ClientError::Config(format!("{}: {}", path.display(), e)))
+	}
+
+	/// Read the file at [`default_path`], if there is one.
+	pub fn load() -> Result<Self, ClientError> {
+		match default_path() {
+			Some(path) if path.exists() => Self::from_file(&path),
+			_ => Ok(Self::default()),
+		}
+	}
+
+	/// The profile called `name`, or else the default profile, if any.
+	pub fn profile(&self, name: Option<&str>) -> Result<Profile, ClientError> {
+		match name.or(self.default_profile.as_deref()) {
+			Some(name) => self
+				.profiles
+				.get(name)
+				.cloned()
+				.ok_or_else(|| ClientError::Config(format!("Unknown profile: {}", name))),
+			None => Ok(Profile::default()),
+		}
+	}
+}
+
+impl Profile {
+	/// Override the profile with [`URL_ENV`] and [`TOKEN_ENV`], where set.
+	pub fn with_env(self) -> Self {
+		self.with_vars(|name| std::env::var(name).ok().filter(|value| !value.is_empty()))
+	}
+
+	fn with_vars(mut self, var: impl Fn(&str) -> Option<String>) -> Self {
+		if let Some(url) = var(URL_ENV) {
+			self.url = Some(url);
+			self.unix_socket = None;
+		}
+		if let Some(token) = var(TOKEN_ENV) {
+			self.token = Some(token);
+			self.cookie_file = None;
+		}
+		self
+	}
+
+	/// The URL of the daemon, which is a `unix://` URL for a unix socket.
+	pub fn url(&self) -> String {
+		match self.unix_socket {
+			Some(ref path) => format!("{}{}", UNIX_SCHEME, path.display()),
+			None => self.url.as_deref().unwrap_or(DEFAULT_URL).to_owned(),
+		}
+	}
+
+	/// A client for the daemon.
+	pub fn client(&self) -> Result<HalSimplicity, ClientError> {
+		let mut client = HalSimplicity::new(&self.url());
+		if let Some(ref path) = self.ca_file {
+			client = client.with_ca_file(path).map_err(|e| {
+				ClientError::Config(format!("Cannot read CA file {}: {}", path.display(), e))
+			})?;
+		}
+		if let Some(ref token) = self.token {
+			Ok(client.with_token(token))
+		} else if let Some(ref path) = self.cookie_file {
+			client.with_cookie_file(path).map_err(|e| {
+				ClientError::Config(format!("Cannot read cookie file {}: {}", path.display(), e))
+			})
+		} else {
+			Ok(client)
+		}
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn test_profiles() {
+		let config: ClientConfig = toml::from_str(
+			r#"
+			default_profile = "local"
+
+			[profiles.local]
+			cookie_file = "/tmp/.cookie"
+
+			[profiles.testnet]
+			url = "http://10.0.0.2:28579"
+			token = "secret"
+
+			[profiles.socket]
+			unix_socket = "/tmp/daemon.sock"
+
+			[profiles.pinned]
+			url = "https://10.0.0.3:28579"
+			ca_file = "/nonexistent/daemon.pem"
+			"#,
+		)
+		.unwrap();
+
+		let local = config.profile(None).unwrap();
+		assert_eq!(local.url(), DEFAULT_URL);
+		assert_eq!(local.cookie_file, Some("/tmp/.cookie".into()));
+		let testnet = config.profile(Some("testnet")).unwrap();
+		assert_eq!(testnet.url(), "http://10.0.0.2:28579");
+		assert!(testnet.client().is_ok());
+		assert!(matches!(config.profile(Some("mainnet")), Err(ClientError::Config(_))));
+		let socket = config.profile(Some("socket")).unwrap();
+		assert_eq!(socket.url(), "unix:///tmp/daemon.sock");
+		let pinned = config.profile(Some("pinned")).unwrap();
+		assert_eq!(pinned.ca_file, Some("/nonexistent/daemon.pem".into()));
+		assert!(matches!(pinned.client(), Err(ClientError::Config(_))));
+		assert_eq!(ClientConfig::default().profile(None).unwrap(), Profile::default());
+
+		// A token in the environment replaces the cookie file.
+		let env = local.with_vars(|name| match name {
+			TOKEN_ENV => Some("other".to_string()),
+			_ => None,
+		});
+		assert_eq!(env.token.as_deref(), Some("other"));
+		assert_eq!(env.cookie_file, None);
+		assert_eq!(env.url(), DEFAULT_URL);
+
+		assert!(toml::from_str::<ClientConfig>("[profiles.x]\nadress = \"\"").is_err());
+	}
+}
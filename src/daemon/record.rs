@@ -0,0 +1,272 @@
+//! Recording calls to replay against another daemon
+//!
+//! A daemon given a recording file appends each call it handles to it as a
+//! line of JSON, a [`Record`] of the method, params and outcome. Secrets are
+//! replaced in both params and results before they are written.
+//!
+//! `hal-simplicity replay` makes the recorded calls again, usually against a
+//! newer daemon, and reports each whose outcome has changed, by the
+//! [`differences`] between the recorded and replayed outcomes. Only calls
+//! which should give the same outcome every time are replayed: see
+//! [`Record::is_replayable`].
+
+use std::fs::{File, OpenOptions};
+use std::io::{self, BufRead as _, BufReader, Write as _};
+use std::path::Path;
+use std::str::FromStr;
+use std::sync::Mutex;
+
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+
+use super::handler::RpcMethod;
+use super::jsonrpc::{redact_fields, RpcError, SECRET_PARAMS};
+
+/// Result fields whose values are replaced before a call is recorded.
+pub const SECRET_RESULTS: &[&str] = &["secret"];
+
+/// Methods which change nothing, but whose outcome depends on the state of
+/// the daemon or the chain rather than only on their params.
+const STATEFUL_METHODS: &[RpcMethod] = &[
+	RpcMethod::GetInfo,
+	RpcMethod::PsetGet,
+	RpcMethod::PsetHistory,
+	RpcMethod::WatchList,
+	RpcMethod::WatchUnspent,
+	RpcMethod::JobStatus,
+];
+
+/// A call, as recorded.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct Record {
+	/// When the call was made, in RFC 3339 format.
+	pub time: String,
+	/// The version of the daemon which handled the call.
+	pub version: String,
+	pub method: String,
+	#[serde(default, skip_serializing_if = "Option::is_none")]
+	pub params: Option<Value>,
+	#[serde(default, skip_serializing_if = "Option::is_none")]
+	pub result: Option<Value>,
+	#[serde(default, skip_serializing_if = "Option::is_none")]
+	pub error: Option<RpcError>,
+	/// Whether any secrets were replaced, so that the params are not those
+	/// of the call.
+	#[serde(default, skip_serializing_if = "std::ops::Not::not")]
+	pub redacted: bool,
+}
+
+impl Record {
+	/// Record a call, replacing any secrets.
+	pub fn new(method: &str, params: Option<Value>, outcome: &Result<Value, RpcError>) -> Self {
+		let redacted_params = params.clone().map(|params| redact_fields(params, SECRET_PARAMS));
+		let (result, error) = match outcome {
+			Ok(result) => (Some(redact_fields(result.clone(), SECRET_RESULTS)), None),
+			Err(error) => (None, Some(error.clone())),
+		};
+		Record {
+			time: chrono::Utc::now().to_rfc3339_opts(chrono::SecondsFormat::Millis, true),
+			version: env!("CARGO_PKG_VERSION").to_string(),
+			method: method.to_owned(),
+			redacted: redacted_params != params || result.as_ref() != outcome.as_ref().ok(),
+			params: redacted_params,
+			result,
+			error,
+		}
+	}
+
+	/// Whether replaying the call should give the same outcome: it must
+	/// change nothing, depend on no state kept by the daemon and have had no
+	/// secrets replaced. Calls of unknown methods are replayed, as another
+	/// daemon may offer them.
+	pub fn is_replayable(&self) -> bool {
+		let uses_session =
+			self.params.as_ref().is_some_and(|params| params.get("session").is_some());
+		let stateless = match RpcMethod::from_str(&self.method) {
+			Ok(method) => method.is_read_only() && !STATEFUL_METHODS.contains(&method),
+			Err(_) => true,
+		};
+		stateless && !uses_session && !self.redacted
+	}
+
+	/// The outcome of the call, as `{"result": ...}` or `{"error": ...}`.
+	pub fn outcome(&self) -> Value {
+		outcome(&match self.error {
+			Some(ref error) => Err(error.clone()),
+			None => Ok(self.result.clone().unwrap_or(Value::Null)),
+		})
+	}
+}
+
+/// An outcome as `{"result": ...}` or `{"error": ...}`, for comparison with
+/// that of a [`Record`].
+pub fn outcome(outcome: &Result<Value, RpcError>) -> Value {
+	match outcome {
+		Ok(result) => json!({ "result": result }),
+		Err(error) => json!({ "error": error }),
+	}
+}
+
+/// Appends calls to a recording file.
+pub struct Recorder {
+	file: Mutex<File>,
+}
+
+impl Recorder {
+	/// Append to the recording file at `path`, creating it if needed.
+	pub fn open(path: &Path) -> io::Result<Self> {
+		let file = OpenOptions::new().create(true).append(true).open(path)?;
+		Ok(Self {
+			file: Mutex::new(file),
+		})
+	}
+
+	/// Record a call. Failing to write is logged rather than failing the call.
+	pub fn record(&self, method: &str, params: Option<Value>, outcome: &Result<Value, RpcError>) {
+		let mut line = serde_json::to_string(&Record::new(method, params, outcome))
+			.expect("records serialize");
+		line.push('\n');
+		// Write each line at once, so that concurrent calls do not interleave.
+		let mut file = self.file.lock().expect("poisoned");
+		if let Err(e) = file.write_all(line.as_bytes()) {
+			log::warn!("Failed to record call to {}: {}", method, e);
+		}
+	}
+}
+
+/// Read the records in a recording file, with their line numbers.
+pub fn read(path: &Path) -> io::Result<Vec<(usize, Record)>> {
+	let mut records = vec![];
+	for (i, line) in BufReader::new(File::open(path)?).lines().enumerate() {
+		let line = line?;
+		if line.trim().is_empty() {
+			continue;
+		}
+		let record = serde_json::from_str(&line).map_err(|e| {
+			io::Error::new(io::ErrorKind::InvalidData, format!("line {}: {}", i + 1, e))
+		})?;
+		records.push((i + 1, record));
+	}
+	Ok(records)
+}
+
+/// A value which differs between a recorded and a replayed outcome.
+#[derive(Clone, Debug, PartialEq, Serialize)]
+pub struct Difference {
+	/// The JSON pointer to the value, e.g. `/result/cmr`.
+	pub path: String,
+	/// The recorded value, if there was one.
+	#[serde(skip_serializing_if = "Option::is_none")]
+	pub recorded: Option<Value>,
+	/// The replayed value, if there was one.
+	#[serde(skip_serializing_if = "Option::is_none")]
+	pub replayed: Option<Value>,
+}
+
+/// The values which differ between a recorded and a replayed outcome, at
+/// the deepest paths where they differ.
+pub fn differences(recorded: &Value, replayed: &Value) -> Vec<Difference> {
+	let mut differences = vec![];
+	diff(String::new(), Some(recorded), Some(replayed), &mut differences);
+	differences
+}
+
+fn diff(
+	path: String,
+	recorded: Option<&Value>,
+	replayed: Option<&Value>,
+	out: &mut Vec<Difference>,
+) {
+	match (recorded, replayed) {
+		(Some(Value::Object(a)), Some(Value::Object(b))) => {
+			for (key, value) in a {
+				diff(pointer(&path, key), Some(value), b.get(key), out);
+			}
+			for (key, value) in b.iter().filter(|(key, _)| !a.contains_key(*key)) {
+				diff(pointer(&path, key), None, Some(value), out);
+			}
+		}
+		(Some(Value::Array(a)), Some(Value::Array(b))) => {
+			for i in 0..a.len().max(b.len()) {
+				diff(pointer(&path, &i.to_string()), a.get(i), b.get(i), out);
+			}
+		}
+		(a, b) if a != b => out.push(Difference {
+			path,
+			recorded: a.cloned(),
+			replayed: b.cloned(),
+		}),
+		_ => {}
+	}
+}
+
+/// Extend a JSON pointer by one key, escaping it as RFC 6901 does.
+fn pointer(path: &str, key: &str) -> String {
+	format!("{}/{}", path, key.replace('~', "~0").replace('/', "~1"))
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use crate::daemon::jsonrpc::ErrorCode;
+
+	#[test]
+	fn test_record() {
+		let outcome = Ok(json!({ "secret": "0101", "x_only": "02" }));
+		let record = Record::new("keypair_generate", None, &outcome);
+		assert_eq!(record.result.as_ref().unwrap()["secret"], "<redacted>");
+		assert!(record.redacted);
+		assert!(!record.is_replayable());
+
+		let params = json!({ "program": "AA==", "witness": "" });
+		let record = Record::new("simplicity_info", Some(params.clone()), &outcome);
+		assert!(record.redacted);
+		let outcome = Err(RpcError::new(ErrorCode::InvalidParams));
+		let record = Record::new("simplicity_info", Some(params), &outcome);
+		assert!(!record.redacted);
+		assert!(record.is_replayable());
+		assert_eq!(record.outcome()["error"]["code"], ErrorCode::InvalidParams.code());
+
+		let line = serde_json::to_string(&record).unwrap();
+		let read: Record = serde_json::from_str(&line).unwrap();
+		assert_eq!(read.outcome(), record.outcome());
+
+		let params = json!({ "session": "alice" });
+		assert!(!Record::new("pset_extract", Some(params), &Ok(Value::Null)).is_replayable());
+		assert!(!Record::new("getinfo", None, &Ok(Value::Null)).is_replayable());
+		assert!(!Record::new("tx_broadcast", None, &Ok(Value::Null)).is_replayable());
+		assert!(Record::new("no_such_method", None, &Ok(Value::Null)).is_replayable());
+	}
+
+	#[test]
+	fn test_differences() {
+		let recorded = json!({ "result": { "cmr": "aa", "jets": [1, 2], "a/b": true } });
+		assert!(differences(&recorded, &recorded).is_empty());
+
+		let replayed = json!({ "result": { "cmr": "bb", "jets": [1], "a/b": true, "new": 0 } });
+		assert_eq!(
+			differences(&recorded, &replayed),
+			vec![
+				Difference {
+					path: "/result/cmr".to_owned(),
+					recorded: Some(json!("aa")),
+					replayed: Some(json!("bb")),
+				},
+				Difference {
+					path: "/result/jets/1".to_owned(),
+					recorded: Some(json!(2)),
+					replayed: None,
+				},
+				Difference {
+					path: "/result/new".to_owned(),
+					recorded: None,
+					replayed: Some(json!(0)),
+				},
+			]
+		);
+
+		let replayed = json!({ "error": { "code": -32601 } });
+		let paths: Vec<_> = differences(&recorded, &replayed).into_iter().map(|d| d.path).collect();
+		assert_eq!(paths, ["/result", "/error"]);
+	}
+}
@@ -0,0 +1,429 @@
+//! REST routes mapped onto the JSON-RPC methods
+//!
+//! Every method `<group>_<action>` is also served at `/v1/<group>/<action>`
+//! (with any further underscores turned into dashes), taking the same params
+//! as a JSON body (`POST`) or as query parameters (`GET`). Query values are
+//! converted to the types of the method's params: a string param takes the
+//! value as it is, an array param takes one item per repetition of its key,
+//! and any other param reads the value as JSON, as in `input_index=2` or
+//! `txids=true`. The result is returned as the response body, and errors as
+//! the JSON-RPC error object with a matching HTTP status. An OpenAPI
+//! description is served at `/v1/openapi.json`.
+//!
+//! The `pset_run` route can also stream its jets as NDJSON (see
+//! [`super::stream`]).
+
+use std::convert::Infallible;
+use std::sync::Arc;
+
+use http_body_util::combinators::UnsyncBoxBody;
+use hyper::body::Bytes;
+use hyper::{Method, StatusCode};
+use schemars::gen::SchemaSettings;
+use serde_json::{json, Map, Value};
+
+use super::errors::Failure;
+use super::handler::{DefaultRpcHandler, RpcMethod};
+use super::jsonrpc::{ErrorCode, JsonRpcService, RpcError, RpcHandler};
+use super::stream::{self, PsetRun};
+use super::{permissions, pool, schema};
+
+/// The prefix under which all REST routes live.
+pub const PREFIX: &str = "/v1/";
+
+/// The REST path of an RPC method.
+pub fn path(method: RpcMethod) -> String {
+	format!("{}{}", PREFIX, method.as_str().replacen('_', "/", 1).replace('_', "-"))
+}
+
+/// Handle a REST request, returning the HTTP status and JSON body.
+pub fn handle<H: RpcHandler>(
+	service: &JsonRpcService<H>,
+	http_method: &Method,
+	uri_path: &str,
+	query: Option<&str>,
+	body: &str,
+) -> (StatusCode, String) {
+	if uri_path == "/v1/openapi.json" {
+		return match *http_method {
+			Method::GET => (StatusCode::OK, openapi().to_string()),
+			_ => error_response(StatusCode::METHOD_NOT_ALLOWED, ErrorCode::InvalidRequest),
+		};
+	}
+
+	let method = match RpcMethod::ALL.iter().copied().find(|m| path(*m) == uri_path) {
+		Some(method) => method,
+		None => return error_response(StatusCode::NOT_FOUND, ErrorCode::MethodNotFound),
+	};
+
+	let params = match *http_method {
+		Method::POST => match body_params(body) {
+			Ok(params) => params,
+			Err(error) => return error,
+		},
+		Method::GET => query.map(|query| query_params(method, query)),
+		_ => return error_response(StatusCode::METHOD_NOT_ALLOWED, ErrorCode::InvalidRequest),
+	};
+
+	match service.call(method.as_str(), params) {
+		Ok(result) => (StatusCode::OK, result.to_string()),
+		Err(error) => rpc_error_response(&error),
+	}
+}
+
+/// Whether a request should be answered by [`handle_stream`] rather than [`handle`].
+pub fn is_stream(http_method: &Method, uri_path: &str, headers: &hyper::HeaderMap) -> bool {
+	http_method == Method::POST
+		&& uri_path == path(RpcMethod::PsetRun)
+		&& stream::accepts_ndjson(headers)
+}
+
+/// Start a `pset_run` which streams its jets, returning the response body, or
+/// the HTTP status and JSON body of the error.
+pub fn handle_stream(
+	service: Arc<JsonRpcService<DefaultRpcHandler>>,
+	body: &str,
+) -> Result<UnsyncBoxBody<Bytes, Infallible>, (StatusCode, String)> {
+	let params = body_params(body)?;
+	let run = PsetRun::new(service.handler(), params).map_err(|e| rpc_error_response(&e))?;
+	Ok(stream::ndjson(service, run))
+}
+
+/// Parse the params in a `POST` body, which may be empty.
+fn body_params(body: &str) -> Result<Option<Value>, (StatusCode, String)> {
+	if body.trim().is_empty() {
+		return Ok(None);
+	}
+	serde_json::from_str(body)
+		.map(Some)
+		.map_err(|_| error_response(StatusCode::BAD_REQUEST, ErrorCode::ParseError))
+}
+
+fn rpc_error_response(error: &RpcError) -> (StatusCode, String) {
+	(status_for(error), serde_json::to_string(error).unwrap_or_default())
+}
+
+fn error_response(status: StatusCode, code: ErrorCode) -> (StatusCode, String) {
+	(status, serde_json::to_string(&RpcError::new(code)).unwrap_or_default())
+}
+
+/// Pick the HTTP status which best matches a JSON-RPC error.
+fn status_for(error: &RpcError) -> StatusCode {
+	if error.code == ErrorCode::MethodNotFound.code() {
+		StatusCode::NOT_FOUND
+	} else if error.code == ErrorCode::InvalidParams.code()
+		|| error.code == ErrorCode::InvalidRequest.code()
+		|| error.code == ErrorCode::ParseError.code()
+	{
+		StatusCode::BAD_REQUEST
+	} else if error.code == permissions::METHOD_DENIED_CODE {
+		StatusCode::FORBIDDEN
+	} else if error.code == pool::SERVER_BUSY_CODE {
+		StatusCode::SERVICE_UNAVAILABLE
+	} else if let Some(failure) = Failure::of_code(error.code) {
+		match failure {
+			Failure::Unavailable | Failure::Rejected => StatusCode::BAD_GATEWAY,
+			_ => StatusCode::UNPROCESSABLE_ENTITY,
+		}
+	} else {
+		StatusCode::INTERNAL_SERVER_ERROR
+	}
+}
+
+/// The JSON types a property schema allows, which are none if it does not
+/// say, such as for a union of types.
+fn schema_types(schema: &Value) -> Vec<&str> {
+	match schema.get("type") {
+		Some(Value::String(ty)) => vec![ty.as_str()],
+		Some(Value::Array(types)) => types.iter().filter_map(Value::as_str).collect(),
+		_ => vec![],
+	}
+}
+
+/// Convert a query value to a param of the given schema: kept as a string if
+/// the param may be one, else read as JSON, falling back to a string so that
+/// parsing the params reports the mismatch.
+fn query_value(schema: &Value, value: String) -> Value {
+	if schema_types(schema).contains(&"string") {
+		return Value::String(value);
+	}
+	serde_json::from_str(&value).unwrap_or(Value::String(value))
+}
+
+/// Turn a query string into the params of `method`, converting each value to
+/// the type of its param (see the module documentation).
+fn query_params(method: RpcMethod, query: &str) -> Value {
+	let params = serde_json::to_value(schema::describe(method).params).unwrap_or_default();
+	let properties = &params["properties"];
+	let mut map = Map::new();
+	for pair in query.split('&').filter(|pair| !pair.is_empty()) {
+		let (key, value) = pair.split_once('=').unwrap_or((pair, ""));
+		let (key, value) = (percent_decode(key), percent_decode(value));
+		let schema = &properties[&key];
+		if schema_types(schema).contains(&"array") {
+			let item = query_value(&schema["items"], value);
+			match map.entry(key).or_insert_with(|| Value::Array(vec![])) {
+				Value::Array(items) => items.push(item),
+				_ => unreachable!("array params are only inserted as arrays"),
+			}
+		} else {
+			let value = query_value(schema, value);
+			map.insert(key, value);
+		}
+	}
+	Value::Object(map)
+}
+
+/// Decode `%XX` escapes. Unlike form encoding, `+` is kept as-is since it is
+/// common in base64-encoded programs.
+fn percent_decode(s: &str) -> String {
+	let bytes = s.as_bytes();
+	let mut out = Vec::with_capacity(bytes.len());
+	let mut i = 0;
+	while i < bytes.len() {
+		match (bytes[i], bytes.get(i + 1..i + 3)) {
+			(b'%', Some(hex)) => {
+				match std::str::from_utf8(hex).ok().and_then(|h| u8::from_str_radix(h, 16).ok()) {
+					Some(b) => {
+						out.push(b);
+						i += 3;
+					}
+					None => {
+						out.push(b'%');
+						i += 1;
+					}
+				}
+			}
+			(b, _) => {
+				out.push(b);
+				i += 1;
+			}
+		}
+	}
+	String::from_utf8_lossy(&out).into_owned()
+}
+
+/// Describe the query parameters of a `GET` route, given its params schema.
+/// Params which are neither strings, numbers, booleans nor arrays of them are
+/// described as JSON, as [`query_params`] reads them.
+fn query_parameters(params: &Value) -> Vec<Value> {
+	let required = params["required"].as_array().cloned().unwrap_or_default();
+	let Some(properties) = params["properties"].as_object() else {
+		return vec![];
+	};
+	properties
+		.iter()
+		.map(|(name, schema)| {
+			let mut parameter = json!({
+				"name": name,
+				"in": "query",
+				"required": required.contains(&json!(name)),
+			});
+			let scalar = |types: Vec<&str>| {
+				!types.is_empty() && types.iter().all(|ty| !matches!(*ty, "object" | "array"))
+			};
+			let items = schema_types(&schema["items"]);
+			if scalar(schema_types(schema)) || (schema_types(schema) == ["array"] && scalar(items))
+			{
+				parameter["schema"] = schema.clone();
+			} else {
+				parameter["content"] = json!({ "application/json": { "schema": schema } });
+			}
+			parameter
+		})
+		.collect()
+}
+
+/// Generate the OpenAPI description of the REST routes.
+pub fn openapi() -> Value {
+	let error = json!({
+		"description": "JSON-RPC error object",
+		"content": { "application/json": { "schema": { "$ref": "#/components/schemas/Error" } } },
+	});
+	let paths = RpcMethod::ALL
+		.iter()
+		.map(|method| {
+			let schema = schema::method_schema(*method, SchemaSettings::openapi3());
+			let params = serde_json::to_value(&schema.params.schema).unwrap_or_default();
+			let responses = json!({
+				"200": {
+					"description": "method result",
+					"content": { "application/json": { "schema": schema.result.schema } },
+				},
+				"400": error,
+				"500": error,
+			});
+			let mut post = json!({
+				"operationId": method.as_str(),
+				"summary": format!("Same as the `{}` JSON-RPC method", method.as_str()),
+				"requestBody": {
+					"required": false,
+					"content": { "application/json": { "schema": params } },
+				},
+				"responses": responses,
+			});
+			if *method == RpcMethod::PsetRun {
+				post["responses"]["200"]["content"][stream::NDJSON] = json!({
+					"schema": { "type": "string", "description": "one JSON object per line" },
+				});
+			}
+			let get = json!({
+				"operationId": format!("{}_get", method.as_str()),
+				"summary": format!("Same as the `{}` JSON-RPC method", method.as_str()),
+				"parameters": query_parameters(&params),
+				"responses": responses,
+			});
+			(path(*method), json!({ "get": get, "post": post }))
+		})
+		.collect::<Map<_, _>>();
+
+	json!({
+		"openapi": "3.0.3",
+		"info": {
+			"title": "hal-simplicity",
+			"version": env!("CARGO_PKG_VERSION"),
+		},
+		"paths": paths,
+		"components": {
+			"schemas": {
+				"Error": {
+					"type": "object",
+					"required": ["code", "message"],
+					"properties": {
+						"code": { "type": "integer" },
+						"message": { "type": "string" },
+						"data": {},
+					},
+				},
+			},
+		},
+	})
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use crate::daemon::handler::create_service;
+
+	#[test]
+	fn test_paths() {
+		assert_eq!(path(RpcMethod::PsetFinalize), "/v1/pset/finalize");
+		assert_eq!(path(RpcMethod::PsetUpdateInput), "/v1/pset/update-input");
+		assert_eq!(path(RpcMethod::BlockVerifySignblock), "/v1/block/verify-signblock");
+	}
+
+	#[test]
+	fn test_get_with_query() {
+		let (status, body) = handle(
+			&create_service(),
+			&Method::GET,
+			"/v1/simplicity/info",
+			Some("program=vw4wSCA%3D"),
+			"",
+		);
+		assert_eq!(status, StatusCode::OK, "{}", body);
+		assert!(body.contains(
+			r#""cmr":"69cf8e9284799b072ddb78eac6d4ffa1c920baef099f17e59a63a8afef702a19""#
+		));
+	}
+
+	#[test]
+	fn test_query_params() {
+		let params = query_params(
+			RpcMethod::PsetRun,
+			"program=vw4wSCA%3D&input_index=2&max_jets=5&all_jets=true&network=liquid",
+		);
+		assert_eq!(params["program"], "vw4wSCA=");
+		assert_eq!(params["input_index"], 2);
+		assert_eq!(params["max_jets"], 5);
+		assert_eq!(params["all_jets"], true);
+		assert_eq!(params["network"], "liquid");
+
+		let params = query_params(
+			RpcMethod::TxDecode,
+			"raw_tx=00&mainchain_headers=aa&mainchain_headers=bb",
+		);
+		assert_eq!(params["raw_tx"], "00");
+		assert_eq!(params["mainchain_headers"], json!(["aa", "bb"]));
+
+		// A value which does not fit is left for the params to reject.
+		let params = query_params(RpcMethod::PsetRevert, "session=s&revision=last");
+		assert_eq!(params["revision"], "last");
+	}
+
+	#[test]
+	fn test_get_with_typed_query() {
+		let service = create_service();
+		let (status, body) =
+			handle(&service, &Method::POST, "/v1/pset/open", None, r#"{"pset": "cHNldP8="}"#);
+		assert_eq!(status, StatusCode::OK, "{}", body);
+		let session: Value = serde_json::from_str(&body).unwrap();
+		let session = session["session"].as_str().unwrap();
+
+		let query = format!("session={}&revision=0", session);
+		let (status, body) = handle(&service, &Method::GET, "/v1/pset/revert", Some(&query), "");
+		assert_eq!(status, StatusCode::OK, "{}", body);
+		assert_eq!(serde_json::from_str::<Value>(&body).unwrap()["pset"], "cHNldP8=");
+
+		let query = format!("session={}&revision=last", session);
+		let (status, _) = handle(&service, &Method::GET, "/v1/pset/revert", Some(&query), "");
+		assert_eq!(status, StatusCode::BAD_REQUEST);
+	}
+
+	#[test]
+	fn test_errors() {
+		let (status, _) = handle(&create_service(), &Method::POST, "/v1/nope", None, "{}");
+		assert_eq!(status, StatusCode::NOT_FOUND);
+
+		let (status, _) = handle(&create_service(), &Method::POST, "/v1/tx/decode", None, "{");
+		assert_eq!(status, StatusCode::BAD_REQUEST);
+
+		let (status, _) = handle(&create_service(), &Method::POST, "/v1/tx/decode", None, "{}");
+		assert_eq!(status, StatusCode::BAD_REQUEST);
+
+		let (status, body) =
+			handle(&create_service(), &Method::POST, "/v1/tx/decode", None, r#"{"raw_tx": "00"}"#);
+		assert_eq!(status, StatusCode::UNPROCESSABLE_ENTITY);
+		assert!(body.contains(r#""code":1200"#));
+	}
+
+	#[test]
+	fn test_getinfo() {
+		let (status, body) = handle(&create_service(), &Method::GET, "/v1/getinfo", None, "");
+		assert_eq!(status, StatusCode::OK);
+		let info: Value = serde_json::from_str(&body).unwrap();
+		assert_eq!(info["version"], json!(env!("CARGO_PKG_VERSION")));
+		assert_eq!(info["network"], json!("liquid"));
+		assert!(info["methods"].as_array().unwrap().contains(&json!("pset_finalize")));
+	}
+
+	#[test]
+	fn test_openapi() {
+		let doc = openapi();
+		assert!(doc["paths"]["/v1/pset/finalize"]["post"].is_object());
+		assert_eq!(doc["paths"].as_object().unwrap().len(), RpcMethod::ALL.len());
+		let body = &doc["paths"]["/v1/pset/finalize"]["post"]["requestBody"];
+		let schema = &body["content"]["application/json"]["schema"];
+		assert_eq!(schema["properties"]["input_index"]["type"], "integer");
+		let responses = &doc["paths"]["/v1/pset/run"]["post"]["responses"];
+		assert!(responses["200"]["content"][stream::NDJSON].is_object());
+
+		let get = &doc["paths"]["/v1/pset/revert"]["get"];
+		assert_eq!(get["operationId"], "pset_revert_get");
+		let parameters = get["parameters"].as_array().unwrap();
+		let revision = parameters.iter().find(|p| p["name"] == "revision").unwrap();
+		assert_eq!(revision["in"], "query");
+		assert_eq!(revision["required"], true);
+		assert_eq!(revision["schema"]["type"], "integer");
+
+		// Params with no query form are given as JSON.
+		let get = &doc["paths"]["/v1/tx/create"]["get"];
+		let tx_info = &get["parameters"][0];
+		assert_eq!(tx_info["name"], "tx_info");
+		assert!(tx_info["content"]["application/json"]["schema"].is_object());
+		let get = &doc["paths"]["/v1/tx/decode"]["get"];
+		let headers = get["parameters"].as_array().unwrap();
+		let headers = headers.iter().find(|p| p["name"] == "mainchain_headers").unwrap();
+		assert_eq!(headers["schema"]["type"], "array");
+	}
+}
@@ -0,0 +1,361 @@
+//! JSON Schemas of the RPC methods and output formats
+//!
+//! The schemas are derived from the request and response types in
+//! [`super::types`], and from the info types of the library, which derive
+//! them with the `daemon` feature. [`output_schemas`] describes every type
+//! which `hal-simplicity` prints or the daemon returns, by name, so that
+//! other tools can validate its output or generate code to read it.
+
+use std::collections::BTreeMap;
+
+use schemars::gen::{SchemaGenerator, SchemaSettings};
+use schemars::schema::RootSchema;
+use schemars::JsonSchema;
+use serde_json::{json, Value};
+
+use super::handler::RpcMethod;
+use super::types::*;
+use crate::actions::block::{
+	BlockDecodeOutput, BlockStats, ChainVerification, SignblockVerification,
+};
+use crate::actions::keypair::{KeypairInfo, PublicKeyInfo};
+#[cfg(feature = "miniscript")]
+use crate::actions::miniscript::{DescriptorInfo, MiniscriptInfo};
+use crate::actions::simplicity::bench::BenchInfo;
+use crate::actions::simplicity::fixtures::Fixtures;
+use crate::actions::simplicity::hide::HiddenProgram;
+use crate::actions::simplicity::info::ProgramInfo;
+use crate::actions::simplicity::prune::PruneCheck;
+use crate::actions::simplicity::pset::{
+	BlindingStatus, PsetSighashes, RunDiffResponse, RunResponse, SighashExport, UpdatedPset,
+};
+use crate::actions::simplicity::sighash::{SighashInfo, TxSighashes};
+use crate::actions::simplicity::webide::{WebIdeBundle, WebIdeImport};
+use crate::address::{AddressInfo, Addresses, PaymentUri, VanityAddress};
+use crate::block::{BlockHeaderInfo, BlockInfo};
+use crate::lwk::{Address as LwkAddress, Pset as LwkPset};
+use crate::tx::{AssetIdInfo, OutputNonceInfo, OutputSecretsInfo, TransactionInfo, TxVerifyInfo};
+
+/// How [`elements::LockTime`] is serialized, to describe it.
+#[derive(JsonSchema)]
+#[allow(dead_code)]
+pub(crate) enum LockTime {
+	Blocks(u32),
+	Seconds(u32),
+}
+
+/// Map the name of each output type to a function describing it.
+macro_rules! output_types {
+	($($(#[$attr:meta])* $ty:ident),* $(,)?) => {
+		const OUTPUT_TYPES: &[(&str, fn(&mut SchemaGenerator) -> RootSchema)] =
+			&[$($(#[$attr])* (stringify!($ty), |gen| gen.root_schema_for::<$ty>()),)*];
+	};
+}
+
+output_types!(
+	// Printed by `hal-simplicity`
+	AddressInfo,
+	Addresses,
+	PaymentUri,
+	VanityAddress,
+	KeypairInfo,
+	PublicKeyInfo,
+	#[cfg(feature = "miniscript")]
+	MiniscriptInfo,
+	#[cfg(feature = "miniscript")]
+	DescriptorInfo,
+	TransactionInfo,
+	TxVerifyInfo,
+	AssetIdInfo,
+	OutputNonceInfo,
+	OutputSecretsInfo,
+	BlockInfo,
+	BlockHeaderInfo,
+	BlockDecodeOutput,
+	BlockStats,
+	ChainVerification,
+	SignblockVerification,
+	ProgramInfo,
+	PruneCheck,
+	HiddenProgram,
+	BenchInfo,
+	Fixtures,
+	SighashInfo,
+	TxSighashes,
+	WebIdeBundle,
+	WebIdeImport,
+	UpdatedPset,
+	BlindingStatus,
+	PsetSighashes,
+	SighashExport,
+	RunResponse,
+	RunDiffResponse,
+	LwkAddress,
+	LwkPset,
+	// Returned by the daemon
+	GetInfoResponse,
+	MethodSchema,
+	BlockCreateResponse,
+	BlockFetchResponse,
+	TxCreateResponse,
+	TxBroadcastResponse,
+	TxFetchResponse,
+	KeypairGenerateResponse,
+	SimplicityInfoResponse,
+	SimplicitySighashResponse,
+	PsetCreateResponse,
+	PsetExtractResponse,
+	PsetOpenResponse,
+	PsetGetResponse,
+	PsetHistoryResponse,
+	PsetFinalizeResponse,
+	PsetRunResponse,
+	PsetUpdateInputResponse,
+	WatchEntry,
+	WatchUnspent,
+	JobSubmitResponse,
+	JobStatusResponse,
+);
+
+/// The names of the types described by [`output_schema`].
+pub fn output_types() -> impl Iterator<Item = &'static str> {
+	OUTPUT_TYPES.iter().map(|(name, _)| *name)
+}
+
+/// Describe the output type called `name`, if there is one.
+///
+/// Nested types are given as definitions, referred to by name.
+pub fn output_schema(name: &str) -> Option<RootSchema> {
+	let mut gen = SchemaSettings::draft07().into_generator();
+	OUTPUT_TYPES.iter().find(|(n, _)| *n == name).map(|(_, schema)| schema(&mut gen))
+}
+
+/// Describe every output type, by name.
+pub fn output_schemas() -> BTreeMap<String, RootSchema> {
+	output_types().map(|name| (name.to_owned(), output_schema(name).expect("known type"))).collect()
+}
+
+/// Describe a method's params and result, using the given schema dialect.
+///
+/// Nested types are inlined so that every schema stands on its own.
+pub fn method_schema(method: RpcMethod, settings: SchemaSettings) -> MethodSchema {
+	fn schemas<P: JsonSchema, R: JsonSchema>(
+		method: RpcMethod,
+		mut gen: SchemaGenerator,
+	) -> MethodSchema {
+		MethodSchema {
+			name: method.as_str().to_string(),
+			params: gen.root_schema_for::<P>(),
+			result: gen.root_schema_for::<R>(),
+		}
+	}
+
+	let gen = settings.with(|s| s.inline_subschemas = true).into_generator();
+	match method {
+		RpcMethod::GetInfo => schemas::<GetInfoRequest, GetInfoResponse>(method, gen),
+		RpcMethod::Help => schemas::<HelpRequest, HelpResponse>(method, gen),
+		RpcMethod::RpcDiscover => schemas::<RpcDiscoverRequest, RpcDiscoverResponse>(method, gen),
+		RpcMethod::Stop => schemas::<StopRequest, StopResponse>(method, gen),
+		RpcMethod::AddressCreate => {
+			schemas::<AddressCreateRequest, AddressCreateResponse>(method, gen)
+		}
+		RpcMethod::AddressInspect => {
+			schemas::<AddressInspectRequest, AddressInspectResponse>(method, gen)
+		}
+		RpcMethod::BlockCreate => schemas::<BlockCreateRequest, BlockCreateResponse>(method, gen),
+		RpcMethod::BlockDecode => schemas::<BlockDecodeRequest, BlockDecodeResponse>(method, gen),
+		RpcMethod::BlockStats => schemas::<BlockStatsRequest, BlockStatsResponse>(method, gen),
+		RpcMethod::BlockVerifyChain => {
+			schemas::<BlockVerifyChainRequest, BlockVerifyChainResponse>(method, gen)
+		}
+		RpcMethod::BlockVerifySignblock => {
+			schemas::<BlockVerifySignblockRequest, BlockVerifySignblockResponse>(method, gen)
+		}
+		RpcMethod::BlockFetch => schemas::<BlockFetchRequest, BlockFetchResponse>(method, gen),
+		RpcMethod::TxCreate => schemas::<TxCreateRequest, TxCreateResponse>(method, gen),
+		RpcMethod::TxDecode => schemas::<TxDecodeRequest, TxDecodeResponse>(method, gen),
+		RpcMethod::TxBroadcast => schemas::<TxBroadcastRequest, TxBroadcastResponse>(method, gen),
+		RpcMethod::TxFetch => schemas::<TxFetchRequest, TxFetchResponse>(method, gen),
+		RpcMethod::KeypairGenerate => {
+			schemas::<KeypairGenerateRequest, KeypairGenerateResponse>(method, gen)
+		}
+		RpcMethod::SimplicityInfo => {
+			schemas::<SimplicityInfoRequest, SimplicityInfoResponse>(method, gen)
+		}
+		RpcMethod::SimplicitySighash => {
+			schemas::<SimplicitySighashRequest, SimplicitySighashResponse>(method, gen)
+		}
+		RpcMethod::PsetCreate => schemas::<PsetCreateRequest, PsetCreateResponse>(method, gen),
+		RpcMethod::PsetExtract => schemas::<PsetExtractRequest, PsetExtractResponse>(method, gen),
+		RpcMethod::PsetOpen => schemas::<PsetOpenRequest, PsetOpenResponse>(method, gen),
+		RpcMethod::PsetGet => schemas::<PsetGetRequest, PsetGetResponse>(method, gen),
+		RpcMethod::PsetClose => schemas::<PsetCloseRequest, PsetCloseResponse>(method, gen),
+		RpcMethod::PsetHistory => schemas::<PsetHistoryRequest, PsetHistoryResponse>(method, gen),
+		RpcMethod::PsetRevert => schemas::<PsetRevertRequest, PsetRevertResponse>(method, gen),
+		RpcMethod::PsetFinalize => {
+			schemas::<PsetFinalizeRequest, PsetFinalizeResponse>(method, gen)
+		}
+		RpcMethod::PsetRun => schemas::<PsetRunRequest, PsetRunResponse>(method, gen),
+		RpcMethod::PsetUpdateInput => {
+			schemas::<PsetUpdateInputRequest, PsetUpdateInputResponse>(method, gen)
+		}
+		RpcMethod::WatchAdd => schemas::<WatchAddRequest, WatchAddResponse>(method, gen),
+		RpcMethod::WatchRemove => schemas::<WatchRemoveRequest, WatchRemoveResponse>(method, gen),
+		RpcMethod::WatchList => schemas::<WatchListRequest, WatchListResponse>(method, gen),
+		RpcMethod::WatchUnspent => {
+			schemas::<WatchUnspentRequest, WatchUnspentResponse>(method, gen)
+		}
+		RpcMethod::JobSubmit => schemas::<JobSubmitRequest, JobSubmitResponse>(method, gen),
+		RpcMethod::JobStatus => schemas::<JobStatusRequest, JobStatusResponse>(method, gen),
+		RpcMethod::JobResult => schemas::<JobResultRequest, JobResultResponse>(method, gen),
+		RpcMethod::Schema => schemas::<SchemaRequest, SchemaResponse>(method, gen),
+	}
+}
+
+/// Describe a method for the `help` method and error messages.
+pub fn describe(method: RpcMethod) -> MethodSchema {
+	method_schema(method, SchemaSettings::draft07())
+}
+
+/// Generate the OpenRPC document describing `methods`, as returned by
+/// `rpc.discover`.
+///
+/// All methods take their params by name, so each property of the params
+/// schema becomes one OpenRPC param.
+pub fn openrpc(methods: impl IntoIterator<Item = RpcMethod>) -> Value {
+	let methods = methods
+		.into_iter()
+		.map(|method| {
+			let schema = describe(method);
+			let params_schema = serde_json::to_value(&schema.params.schema).unwrap_or_default();
+			let required = params_schema["required"].as_array().cloned().unwrap_or_default();
+			let params = params_schema["properties"]
+				.as_object()
+				.map(|properties| {
+					properties
+						.iter()
+						.map(|(name, schema)| {
+							json!({
+								"name": name,
+								"required": required.contains(&json!(name)),
+								"schema": schema,
+							})
+						})
+						.collect::<Vec<_>>()
+				})
+				.unwrap_or_default();
+
+			json!({
+				"name": schema.name,
+				"paramStructure": "by-name",
+				"params": params,
+				"result": {
+					"name": "result",
+					"schema": schema.result.schema,
+				},
+			})
+		})
+		.collect::<Vec<_>>();
+
+	json!({
+		"openrpc": "1.2.6",
+		"info": {
+			"title": "hal-simplicity",
+			"version": env!("CARGO_PKG_VERSION"),
+		},
+		"methods": methods,
+	})
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use crate::daemon::client::skewed_field;
+	use crate::daemon::handler::DefaultRpcHandler;
+	use crate::daemon::jsonrpc::{ErrorCode, RpcHandler};
+
+	#[test]
+	fn test_describe() {
+		let schema = serde_json::to_value(describe(RpcMethod::PsetExtract)).unwrap();
+		assert_eq!(schema["name"], "pset_extract");
+		assert_eq!(schema["params"]["required"], json!(["pset"]));
+		assert_eq!(schema["params"]["properties"]["pset"]["type"], "string");
+		assert_eq!(schema["result"]["properties"]["raw_tx"]["type"], "string");
+
+		// Nested types are inlined rather than referenced.
+		let schema = serde_json::to_value(describe(RpcMethod::AddressCreate)).unwrap();
+		assert!(schema["params"].get("definitions").is_none());
+		let network = &schema["params"]["properties"]["network"];
+		assert!(network["enum"].as_array().unwrap().contains(&json!("liquidtestnet")));
+	}
+
+	#[test]
+	fn test_openrpc() {
+		let doc = openrpc(RpcMethod::ALL.iter().copied());
+		let methods = doc["methods"].as_array().unwrap();
+		assert_eq!(methods.len(), RpcMethod::ALL.len());
+
+		let finalize = methods.iter().find(|m| m["name"] == "pset_finalize").unwrap();
+		let params = finalize["params"].as_array().unwrap();
+		let param = |name: &str| params.iter().find(|p| p["name"] == name).unwrap();
+		assert_eq!(param("input_index")["required"], true);
+		assert_eq!(param("session")["required"], false);
+	}
+
+	#[test]
+	fn test_help() {
+		let handler = DefaultRpcHandler::default();
+		let all = handler.handle("help", None).unwrap();
+		assert_eq!(all.as_array().unwrap().len(), RpcMethod::ALL.len());
+
+		let one = handler.handle("help", Some(json!({ "method": "tx_decode" }))).unwrap();
+		assert_eq!(one[0]["name"], "tx_decode");
+
+		let err = handler.handle("help", Some(json!({ "method": "nope" }))).unwrap_err();
+		assert_eq!(err.code, ErrorCode::InvalidParams.code());
+	}
+
+	#[test]
+	fn test_output_schemas() {
+		let schemas = output_schemas();
+		assert_eq!(schemas.len(), output_types().count());
+		let tx = serde_json::to_value(&schemas["TransactionInfo"]).unwrap();
+		assert_eq!(tx["title"], "TransactionInfo");
+		assert_eq!(tx["properties"]["inputs"]["items"]["$ref"], "#/definitions/InputInfo");
+		assert!(tx["definitions"]["InputInfo"].is_object());
+		assert!(output_schema("Nope").is_none());
+
+		let handler = DefaultRpcHandler::default();
+		let all = handler.handle("schema", None).unwrap();
+		assert_eq!(all.as_object().unwrap().len(), output_types().count());
+		let one = handler.handle("schema", Some(json!({ "type": "BlockInfo" }))).unwrap();
+		assert_eq!(one["BlockInfo"]["title"], "BlockInfo");
+		let err = handler.handle("schema", Some(json!({ "type": "Nope" }))).unwrap_err();
+		assert_eq!(err.code, ErrorCode::InvalidParams.code());
+	}
+
+	#[test]
+	fn test_outputs_match_schemas() {
+		let fixtures = crate::actions::simplicity::fixtures::simplicity_gen_fixtures(&["0"]);
+		let mut gen =
+			SchemaSettings::draft07().with(|s| s.inline_subschemas = true).into_generator();
+		let schema = serde_json::to_value(gen.root_schema_for::<Fixtures>()).unwrap();
+		let value = serde_json::to_value(&fixtures).unwrap();
+		assert_eq!(skewed_field(&schema, &value, ""), None);
+
+		let handler = DefaultRpcHandler::default();
+		let params = json!({ "raw_tx": fixtures.fixtures[0].transaction });
+		let value = handler.handle("tx_decode", Some(params)).unwrap();
+		let schema = serde_json::to_value(describe(RpcMethod::TxDecode).result).unwrap();
+		assert_eq!(schema["properties"]["inputs"]["items"]["type"], "object");
+		assert_eq!(skewed_field(&schema, &value, ""), None);
+	}
+
+	#[test]
+	fn test_invalid_params_include_schema() {
+		let handler = DefaultRpcHandler::default();
+		let err = handler.handle("pset_extract", Some(json!({ "psbt": "" }))).unwrap_err();
+		assert_eq!(err.code, ErrorCode::InvalidParams.code());
+		assert_eq!(err.data.unwrap()["params"]["required"], json!(["pset"]));
+	}
+}
@@ -0,0 +1,312 @@
+//! PSETs stored in the daemon between calls
+//!
+//! `pset_open` stores a PSET under a random session handle. The PSET methods
+//! then accept `session` instead of `pset`; `pset_update_input` and
+//! `pset_finalize` store their result back in the session, so a client does
+//! not need to send the ever-growing PSET on every step. `pset_get` returns
+//! the current PSET and `pset_close` forgets it.
+//!
+//! Every PSET stored in a session is kept as a revision. `pset_history` lists
+//! them and `pset_revert` makes an earlier one current again, by appending a
+//! copy of it, so that a step which attached the wrong data can be undone.
+//!
+//! With a [`super::store::Store`], sessions are written through to disk and
+//! survive a restart.
+
+use std::collections::HashMap;
+use std::sync::Mutex;
+
+use elements::bitcoin::secp256k1::rand::{self, RngCore as _};
+
+use super::jsonrpc::{ErrorCode, RpcError};
+use super::types::PsetRevision;
+use super::DaemonError;
+
+/// Default maximum number of open sessions.
+pub const DEFAULT_MAX_SESSIONS: usize = 1000;
+
+/// The open sessions.
+#[derive(Debug)]
+pub struct PsetSessions {
+	max_sessions: usize,
+	/// The revisions of each session, the current PSET last.
+	psets: Mutex<HashMap<String, Vec<PsetRevision>>>,
+	tree: Option<sled::Tree>,
+}
+
+impl Default for PsetSessions {
+	fn default() -> Self {
+		Self::new(DEFAULT_MAX_SESSIONS)
+	}
+}
+
+impl PsetSessions {
+	/// Allow at most `max_sessions` sessions to be open at once.
+	pub fn new(max_sessions: usize) -> Self {
+		Self {
+			max_sessions,
+			psets: Mutex::new(HashMap::new()),
+			tree: None,
+		}
+	}
+
+	/// Keep sessions in `tree`, starting with those already stored there.
+	pub fn persistent(max_sessions: usize, tree: sled::Tree) -> Result<Self, DaemonError> {
+		let psets = tree
+			.iter()
+			.map(|entry| {
+				let (session, value) = entry.map_err(|e| DaemonError::Store(e.to_string()))?;
+				let session = String::from_utf8_lossy(&session).into_owned();
+				// Sessions stored before revisions were kept hold a bare PSET.
+				let revisions = if value.starts_with(b"[") {
+					serde_json::from_slice(&value)
+						.map_err(|e| DaemonError::Store(format!("session {}: {}", session, e)))?
+				} else {
+					vec![revision(0, "pset_open", String::from_utf8_lossy(&value).into_owned())]
+				};
+				Ok((session, revisions))
+			})
+			.collect::<Result<_, DaemonError>>()?;
+		Ok(Self {
+			max_sessions,
+			psets: Mutex::new(psets),
+			tree: Some(tree),
+		})
+	}
+
+	/// Write a session to disk, or remove it if `revisions` is `None`.
+	fn persist(&self, session: &str, revisions: Option<&[PsetRevision]>) -> Result<(), RpcError> {
+		let tree = match self.tree {
+			Some(ref tree) => tree,
+			None => return Ok(()),
+		};
+		let result = match revisions {
+			Some(revisions) => {
+				let value = serde_json::to_vec(revisions).expect("revisions serialize");
+				tree.insert(session, value).map(|_| ())
+			}
+			None => tree.remove(session).map(|_| ()),
+		};
+		result.map_err(|e| {
+			RpcError::custom(
+				ErrorCode::InternalError.code(),
+				format!("Failed to store session: {}", e),
+			)
+		})
+	}
+
+	/// Store a PSET in a new session, returning its handle.
+	pub fn open(&self, pset: String) -> Result<String, RpcError> {
+		let mut psets = self.psets.lock().expect("poisoned");
+		if psets.len() >= self.max_sessions {
+			return Err(RpcError::custom(
+				ErrorCode::InternalError.code(),
+				format!("Too many open sessions (at most {})", self.max_sessions),
+			));
+		}
+
+		let mut bytes = [0; 16];
+		rand::thread_rng().fill_bytes(&mut bytes);
+		let session = hex::encode(bytes);
+		let revisions = vec![revision(0, "pset_open", pset)];
+		self.persist(&session, Some(&revisions))?;
+		psets.insert(session.clone(), revisions);
+		Ok(session)
+	}
+
+	/// The current PSET of a session.
+	pub fn get(&self, session: &str) -> Result<String, RpcError> {
+		let psets = self.psets.lock().expect("poisoned");
+		let revisions = psets.get(session).ok_or_else(|| unknown(session))?;
+		Ok(revisions.last().expect("sessions have a revision").pset.clone())
+	}
+
+	/// Replace the PSET of a session with a new revision, stored by `method`.
+	pub fn set(&self, session: &str, pset: String, method: &str) -> Result<(), RpcError> {
+		let mut psets = self.psets.lock().expect("poisoned");
+		let revisions = psets.get_mut(session).ok_or_else(|| unknown(session))?;
+		self.append(session, revisions, revision(revisions.len(), method, pset))
+	}
+
+	/// Every revision of the PSET of a session, the current one last.
+	pub fn history(&self, session: &str) -> Result<Vec<PsetRevision>, RpcError> {
+		self.psets.lock().expect("poisoned").get(session).cloned().ok_or_else(|| unknown(session))
+	}
+
+	/// Make an earlier revision of a session current again, returning its PSET.
+	pub fn revert(&self, session: &str, to: usize) -> Result<String, RpcError> {
+		let mut psets = self.psets.lock().expect("poisoned");
+		let revisions = psets.get_mut(session).ok_or_else(|| unknown(session))?;
+		let pset = match revisions.get(to) {
+			Some(old) => old.pset.clone(),
+			None => {
+				return Err(RpcError::custom(
+					ErrorCode::InvalidParams.code(),
+					format!(
+						"Revision {} out of range for session with {} revisions",
+						to,
+						revisions.len()
+					),
+				))
+			}
+		};
+		self.append(session, revisions, revision(revisions.len(), "pset_revert", pset.clone()))?;
+		Ok(pset)
+	}
+
+	/// Append a revision to a session, writing it through to disk first.
+	fn append(
+		&self,
+		session: &str,
+		revisions: &mut Vec<PsetRevision>,
+		new: PsetRevision,
+	) -> Result<(), RpcError> {
+		revisions.push(new);
+		if let Err(e) = self.persist(session, Some(revisions)) {
+			revisions.pop();
+			return Err(e);
+		}
+		Ok(())
+	}
+
+	/// Forget a session, returning its last PSET.
+	pub fn close(&self, session: &str) -> Result<String, RpcError> {
+		let mut psets = self.psets.lock().expect("poisoned");
+		let mut revisions = psets.remove(session).ok_or_else(|| unknown(session))?;
+		self.persist(session, None)?;
+		Ok(revisions.pop().expect("sessions have a revision").pset)
+	}
+
+	/// Pick the PSET of a request, given either inline or as a session.
+	pub fn resolve(&self, pset: Option<String>, session: Option<&str>) -> Result<String, RpcError> {
+		match (pset, session) {
+			(Some(pset), None) => Ok(pset),
+			(None, Some(session)) => self.get(session),
+			_ => Err(RpcError::custom(
+				ErrorCode::InvalidParams.code(),
+				"Exactly one of pset and session must be given".to_string(),
+			)),
+		}
+	}
+
+	/// The number of open sessions.
+	pub fn len(&self) -> usize {
+		self.psets.lock().expect("poisoned").len()
+	}
+
+	/// Whether no sessions are open.
+	pub fn is_empty(&self) -> bool {
+		self.len() == 0
+	}
+}
+
+fn revision(revision: usize, method: &str, pset: String) -> PsetRevision {
+	PsetRevision {
+		revision,
+		method: method.to_string(),
+		pset,
+	}
+}
+
+fn unknown(session: &str) -> RpcError {
+	RpcError::custom(ErrorCode::InvalidParams.code(), format!("Unknown session: {}", session))
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use crate::daemon::handler::DefaultRpcHandler;
+	use crate::daemon::jsonrpc::RpcHandler;
+	use crate::daemon::store::{Store, SESSIONS};
+	use serde_json::json;
+
+	#[test]
+	fn test_sessions() {
+		let sessions = PsetSessions::new(1);
+		let session = sessions.open("first".to_string()).unwrap();
+		assert!(sessions.open("second".to_string()).is_err());
+
+		sessions.set(&session, "updated".to_string(), "pset_update_input").unwrap();
+		assert_eq!(sessions.resolve(None, Some(&session)).unwrap(), "updated");
+		assert_eq!(sessions.resolve(Some("inline".to_string()), None).unwrap(), "inline");
+		assert!(sessions.resolve(Some("inline".to_string()), Some(&session)).is_err());
+		assert!(sessions.resolve(None, None).is_err());
+
+		assert_eq!(sessions.close(&session).unwrap(), "updated");
+		assert!(sessions.get(&session).is_err());
+		assert!(sessions.is_empty());
+	}
+
+	#[test]
+	fn test_persistent_sessions() {
+		let store = Store::temporary().unwrap();
+		let sessions = PsetSessions::persistent(10, store.tree(SESSIONS).unwrap()).unwrap();
+		let kept = sessions.open("first".to_string()).unwrap();
+		let closed = sessions.open("second".to_string()).unwrap();
+		sessions.set(&kept, "updated".to_string(), "pset_update_input").unwrap();
+		sessions.close(&closed).unwrap();
+		drop(sessions);
+
+		// A restarted daemon sees the same sessions.
+		let sessions = PsetSessions::persistent(10, store.tree(SESSIONS).unwrap()).unwrap();
+		assert_eq!(sessions.get(&kept).unwrap(), "updated");
+		assert_eq!(sessions.history(&kept).unwrap().len(), 2);
+		assert!(sessions.get(&closed).is_err());
+		assert_eq!(sessions.len(), 1);
+	}
+
+	#[test]
+	fn test_legacy_sessions() {
+		let store = Store::temporary().unwrap();
+		let tree = store.tree(SESSIONS).unwrap();
+		tree.insert("old", "cHNldP8=".as_bytes()).unwrap();
+
+		let sessions = PsetSessions::persistent(10, tree).unwrap();
+		let history = sessions.history("old").unwrap();
+		assert_eq!(history.len(), 1);
+		assert_eq!(history[0].method, "pset_open");
+		assert_eq!(history[0].pset, "cHNldP8=");
+	}
+
+	#[test]
+	fn test_revisions() {
+		let sessions = PsetSessions::default();
+		let session = sessions.open("first".to_string()).unwrap();
+		sessions.set(&session, "second".to_string(), "pset_update_input").unwrap();
+		sessions.set(&session, "third".to_string(), "pset_finalize").unwrap();
+
+		assert_eq!(sessions.revert(&session, 1).unwrap(), "second");
+		assert_eq!(sessions.get(&session).unwrap(), "second");
+		assert!(sessions.revert(&session, 4).is_err());
+
+		let history = sessions.history(&session).unwrap();
+		let methods: Vec<_> = history.iter().map(|rev| rev.method.as_str()).collect();
+		assert_eq!(methods, ["pset_open", "pset_update_input", "pset_finalize", "pset_revert"]);
+		assert!(history.iter().enumerate().all(|(i, rev)| rev.revision == i));
+		assert_eq!(history[2].pset, "third");
+	}
+
+	#[test]
+	fn test_session_methods() {
+		let handler = DefaultRpcHandler::default();
+		let opened = handler.handle("pset_open", Some(json!({ "pset": "cHNldP8=" }))).unwrap();
+		let session = opened["session"].clone();
+
+		let got = handler.handle("pset_get", Some(json!({ "session": session }))).unwrap();
+		assert_eq!(got["pset"], "cHNldP8=");
+
+		// Methods taking a PSET read it from the session.
+		let params = json!({ "session": session, "input_index": 0, "input_utxo": "" });
+		let err = handler.handle("pset_update_input", Some(params)).unwrap_err();
+		assert!(err.message.contains("PSET"), "{}", err.message);
+
+		let history = handler.handle("pset_history", Some(json!({ "session": session }))).unwrap();
+		assert_eq!(history["revisions"][0]["pset"], "cHNldP8=");
+		let params = json!({ "session": session, "revision": 0 });
+		let reverted = handler.handle("pset_revert", Some(params)).unwrap();
+		assert_eq!(reverted["pset"], "cHNldP8=");
+
+		handler.handle("pset_close", Some(json!({ "session": session }))).unwrap();
+		assert!(handler.handle("pset_get", Some(json!({ "session": session }))).is_err());
+	}
+}
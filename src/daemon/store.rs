@@ -0,0 +1,48 @@
+//! On-disk storage for daemon state
+//!
+//! State which should survive a restart, such as PSET sessions, is kept in a
+//! sled database in the data directory, with one tree per kind of object.
+//! Without a store the daemon keeps everything in memory.
+
+use std::path::Path;
+
+use super::DaemonError;
+
+/// The name of the tree holding PSET sessions.
+pub const SESSIONS: &str = "sessions";
+
+/// The name of the tree holding watched addresses.
+pub const WATCH: &str = "watch";
+
+/// A handle to the database. Clones refer to the same database.
+#[derive(Clone, Debug)]
+pub struct Store {
+	db: sled::Db,
+}
+
+impl Store {
+	/// Open or create the database in `dir`.
+	pub fn open(dir: &Path) -> Result<Self, DaemonError> {
+		let db = sled::open(dir.join("store"))
+			.map_err(|e| DaemonError::Store(format!("{}: {}", dir.display(), e)))?;
+		Ok(Self {
+			db,
+		})
+	}
+
+	/// Create a database which is deleted when dropped.
+	pub fn temporary() -> Result<Self, DaemonError> {
+		let db = sled::Config::new()
+			.temporary(true)
+			.open()
+			.map_err(|e| DaemonError::Store(e.to_string()))?;
+		Ok(Self {
+			db,
+		})
+	}
+
+	/// Open the tree for one kind of object.
+	pub fn tree(&self, name: &str) -> Result<sled::Tree, DaemonError> {
+		self.db.open_tree(name).map_err(|e| DaemonError::Store(e.to_string()))
+	}
+}
@@ -0,0 +1,182 @@
+//! Streaming `pset_run`
+//!
+//! A `pset_run` on a large program can execute a great many jets. Rather than
+//! waiting for all of them in one response, a client can have them streamed
+//! as they execute:
+//!
+//! * over WebSocket, with `pset_run_subscribe` (see [`super::websocket`]);
+//! * over HTTP, by posting to the `pset_run` REST route with
+//!   `Accept: application/x-ndjson`. The response then has one JSON object
+//!   per line: `{"jet": ...}` for each jet, followed by `{"success": ...}`, or
+//!   by `{"error": ...}` if the program could not be run.
+//!
+//! Lines are produced no faster than the client reads them, so a slow client
+//! keeps its worker busy for longer. A client which goes away cancels the run.
+
+use std::convert::Infallible;
+use std::ops::ControlFlow;
+use std::sync::Arc;
+
+use http_body_util::combinators::UnsyncBoxBody;
+use http_body_util::{BodyExt as _, StreamBody};
+use hyper::body::{Bytes, Frame};
+use serde_json::{json, Value};
+use tokio::sync::mpsc;
+
+use super::errors::action_error;
+use super::handler::{parse_params, program_span, DefaultRpcHandler, RpcMethod};
+use super::jsonrpc::{JsonRpcService, RpcError};
+use super::types::PsetRunRequest;
+use crate::actions::simplicity::pset::{pset_run_program, JetCall, PsetRunError, RunResponse};
+
+/// The media type of a streamed response.
+pub const NDJSON: &str = "application/x-ndjson";
+
+/// How many lines may be waiting to be sent before the program is paused.
+const BUFFERED_LINES: usize = 64;
+
+/// Whether the request headers ask for a streamed response.
+pub fn accepts_ndjson(headers: &hyper::HeaderMap) -> bool {
+	headers
+		.get_all(hyper::header::ACCEPT)
+		.iter()
+		.filter_map(|value| value.to_str().ok())
+		.flat_map(|value| value.split(','))
+		.any(|media| media.split(';').next().unwrap_or_default().trim() == NDJSON)
+}
+
+/// A checked `pset_run` request, ready to run.
+pub struct PsetRun {
+	req: PsetRunRequest,
+	pset: String,
+}
+
+impl PsetRun {
+	/// Check that `pset_run` may be called with `params`, and parse them.
+	pub fn new(handler: &DefaultRpcHandler, params: Option<Value>) -> Result<Self, RpcError> {
+		handler.permissions.check(RpcMethod::PsetRun, params.as_ref())?;
+		let mut req: PsetRunRequest = parse_params(params)?;
+		let pset = handler.sessions.resolve(req.pset.take(), req.session.as_deref())?;
+		Ok(Self {
+			req,
+			pset,
+		})
+	}
+
+	/// Run the program, calling `on_jet` as each jet executes, until it breaks.
+	///
+	/// This blocks, and waits for a worker like a `pset_run` call (see
+	/// [`super::pool`]).
+	pub fn run(
+		self,
+		handler: &DefaultRpcHandler,
+		on_jet: impl FnMut(&JetCall) -> ControlFlow<()>,
+	) -> Result<RunResponse, RpcError> {
+		let genesis_hash =
+			handler.genesis_hash_for(self.req.genesis_hash.as_deref(), self.req.network)?;
+		let program = handler
+			.programs
+			.get_or_parse(&self.req.program, &self.req.witness)
+			.map_err(|e| action_error(PsetRunError::ProgramParse(e)))?;
+		let span = program_span("run", &self.req.program, &self.req.witness, &program);
+		let result = span
+			.in_scope(|| {
+				handler.workers.run(|| {
+					pset_run_program(
+						&self.pset,
+						&self.req.input_index.to_string(),
+						&program,
+						genesis_hash.as_deref(),
+						self.req.jet_limit(),
+						on_jet,
+					)
+				})
+			})?
+			.map_err(action_error)?;
+		span.record("success", result.success);
+		Ok(result)
+	}
+}
+
+/// Run `run` in the background, streaming its output as NDJSON.
+pub fn ndjson(
+	service: Arc<JsonRpcService<DefaultRpcHandler>>,
+	run: PsetRun,
+) -> UnsyncBoxBody<Bytes, Infallible> {
+	let (tx, rx) = mpsc::channel(BUFFERED_LINES);
+	let span = tracing::Span::current();
+	tokio::task::spawn_blocking(move || {
+		let _span = span.enter();
+		// If the client goes away, the run is cancelled.
+		let send = |value: Value| match tx.blocking_send(format!("{}\n", value)) {
+			Ok(()) => ControlFlow::Continue(()),
+			Err(_) => ControlFlow::Break(()),
+		};
+		let result = run.run(service.handler(), |jet| send(json!({ "jet": jet })));
+		let _ = send(match result {
+			Ok(result) => json!({ "success": result.success }),
+			Err(error) => json!({ "error": error }),
+		});
+	});
+
+	let lines = futures_util::stream::unfold(rx, |mut rx| async move {
+		let line = rx.recv().await?;
+		Some((Ok(Frame::data(Bytes::from(line))), rx))
+	});
+	StreamBody::new(lines).boxed_unsync()
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use crate::daemon::handler::create_service;
+	use hyper::header::{HeaderValue, ACCEPT};
+
+	#[test]
+	fn test_accepts_ndjson() {
+		let mut headers = hyper::HeaderMap::new();
+		assert!(!accepts_ndjson(&headers));
+		headers.insert(ACCEPT, HeaderValue::from_static("application/json"));
+		assert!(!accepts_ndjson(&headers));
+		headers.insert(ACCEPT, HeaderValue::from_static("text/html, application/x-ndjson;q=0.9"));
+		assert!(accepts_ndjson(&headers));
+	}
+
+	#[tokio::test]
+	async fn test_ndjson() {
+		let service = Arc::new(create_service());
+		assert!(PsetRun::new(service.handler(), Some(json!({}))).is_err());
+
+		let params = json!({ "pset": "", "input_index": 0, "program": "", "witness": "" });
+		let run = PsetRun::new(service.handler(), Some(params)).unwrap();
+		let body = ndjson(service, run).collect().await.unwrap().to_bytes();
+		let body = std::str::from_utf8(&body).unwrap();
+		let lines =
+			body.lines().map(|line| serde_json::from_str(line).unwrap()).collect::<Vec<Value>>();
+		assert_eq!(lines.len(), 1, "{}", body);
+		assert!(lines[0]["error"]["message"].is_string());
+	}
+
+	#[test]
+	fn test_cancel_run() {
+		let service = create_service();
+		let fixtures = crate::actions::simplicity::fixtures::simplicity_gen_fixtures(&["0"]);
+		let fixture = &fixtures.fixtures[0];
+		let params = json!({
+			"pset": fixture.pset,
+			"input_index": 0,
+			"program": fixture.program,
+			"witness": fixture.witness,
+			"network": "liquidtestnet",
+		});
+		let run = PsetRun::new(service.handler(), Some(params)).unwrap();
+		let mut jets = 0;
+		let result = run.run(service.handler(), |_| {
+			jets += 1;
+			ControlFlow::Break(())
+		});
+		assert_eq!(jets, 1);
+		let error = result.err().expect("cancelled");
+		assert_eq!(error.message, "the run was cancelled");
+	}
+}
@@ -0,0 +1,115 @@
+//! Exporting traces over OpenTelemetry
+//!
+//! The daemon records a [`tracing`] span for each HTTP request, each RPC call
+//! and each program it runs or finalizes, the last with the sizes of the
+//! program and witness and the program's cost. [`init`] exports these spans
+//! to an OTLP collector over HTTP. The client records a span for each call
+//! too, and passes its trace on in a W3C `traceparent` header, which the
+//! daemon continues, so that a slow execution can be followed from the
+//! caller down to the Bit Machine.
+
+use std::collections::HashMap;
+
+use opentelemetry::propagation::TextMapPropagator as _;
+use opentelemetry::trace::TracerProvider as _;
+use opentelemetry_otlp::{Protocol, WithExportConfig as _};
+use opentelemetry_sdk::propagation::TraceContextPropagator;
+use opentelemetry_sdk::trace::SdkTracerProvider;
+use opentelemetry_sdk::Resource;
+use tracing_opentelemetry::OpenTelemetrySpanExt as _;
+use tracing_subscriber::layer::SubscriberExt as _;
+
+use super::DaemonError;
+
+/// The header carrying the trace context of a request.
+pub const TRACEPARENT: &str = "traceparent";
+
+/// The environment variables which give the endpoint to export to.
+pub const ENDPOINT_VARS: &[&str] =
+	&["OTEL_EXPORTER_OTLP_TRACES_ENDPOINT", "OTEL_EXPORTER_OTLP_ENDPOINT"];
+
+/// Exports spans while it is alive, flushing them when dropped.
+pub struct Telemetry {
+	provider: SdkTracerProvider,
+}
+
+impl Drop for Telemetry {
+	fn drop(&mut self) {
+		if let Err(e) = self.provider.shutdown() {
+			log::warn!("Failed to flush traces: {}", e);
+		}
+	}
+}
+
+/// Export spans to the OTLP/HTTP collector at `endpoint`, such as
+/// `http://localhost:4318/v1/traces`, as `service_name`. Without an endpoint,
+/// those in [`ENDPOINT_VARS`] are used, as by other OpenTelemetry SDKs.
+///
+/// This must be called outside of any async runtime, and at most once.
+pub fn init(endpoint: Option<&str>, service_name: &'static str) -> Result<Telemetry, DaemonError> {
+	let mut exporter =
+		opentelemetry_otlp::SpanExporter::builder().with_http().with_protocol(Protocol::HttpJson);
+	if let Some(endpoint) = endpoint {
+		exporter = exporter.with_endpoint(endpoint);
+	}
+	let exporter = exporter.build().map_err(|e| DaemonError::Telemetry(e.to_string()))?;
+
+	let provider = SdkTracerProvider::builder()
+		.with_batch_exporter(exporter)
+		.with_resource(Resource::builder().with_service_name(service_name).build())
+		.build();
+	let layer = tracing_opentelemetry::layer().with_tracer(provider.tracer(service_name));
+	tracing::subscriber::set_global_default(tracing_subscriber::registry().with(layer))
+		.map_err(|e| DaemonError::Telemetry(e.to_string()))?;
+
+	Ok(Telemetry {
+		provider,
+	})
+}
+
+/// Whether the environment gives an endpoint to export to.
+pub fn endpoint_in_env() -> bool {
+	ENDPOINT_VARS.iter().any(|var| std::env::var_os(var).is_some())
+}
+
+/// The `traceparent` header continuing the trace of the current span, if it
+/// is being exported.
+pub fn traceparent() -> Option<String> {
+	let context = tracing::Span::current().context();
+	let mut headers = HashMap::new();
+	TraceContextPropagator::new().inject_context(&context, &mut headers);
+	headers.remove(TRACEPARENT)
+}
+
+/// Make `span` part of the trace of a `traceparent` header.
+pub fn continue_trace(span: &tracing::Span, traceparent: &str) {
+	let headers = HashMap::from([(TRACEPARENT.to_owned(), traceparent.to_owned())]);
+	let context = TraceContextPropagator::new().extract(&headers);
+	// This only fails if the span is not being exported.
+	let _ = span.set_parent(context);
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn test_continue_trace() {
+		let provider = SdkTracerProvider::builder().build();
+		let layer = tracing_opentelemetry::layer().with_tracer(provider.tracer("test"));
+		let subscriber = tracing_subscriber::registry().with(layer);
+
+		tracing::subscriber::with_default(subscriber, || {
+			// Outside any span, there is no trace to continue.
+			assert_eq!(traceparent(), None);
+
+			let parent = "00-0af7651916cd43dd8448eb211c80319c-b7ad6b7169203331-01";
+			let span = tracing::info_span!("rpc");
+			continue_trace(&span, parent);
+			let header = span.in_scope(traceparent).expect("span is exported");
+			assert!(header.starts_with("00-0af7651916cd43dd8448eb211c80319c-"));
+			assert!(header.ends_with("-01"));
+			assert_ne!(header, parent);
+		});
+	}
+}
@@ -0,0 +1,91 @@
+//! TLS configuration for the daemon listeners, and for clients which pin
+//! their certificate
+
+use std::fs;
+use std::io::BufReader;
+use std::path::Path;
+use std::sync::Arc;
+
+use tokio_rustls::rustls::crypto::ring::default_provider;
+use tokio_rustls::rustls::pki_types::{CertificateDer, PrivateKeyDer, PrivatePkcs8KeyDer};
+use tokio_rustls::rustls::{ClientConfig, RootCertStore, ServerConfig};
+
+use super::DaemonError;
+
+fn server_config(
+	certs: Vec<CertificateDer<'static>>,
+	key: PrivateKeyDer<'static>,
+) -> Result<Arc<ServerConfig>, DaemonError> {
+	let config = ServerConfig::builder_with_provider(Arc::new(default_provider()))
+		.with_safe_default_protocol_versions()
+		.map_err(|e| DaemonError::Tls(e.to_string()))?
+		.with_no_client_auth()
+		.with_single_cert(certs, key)
+		.map_err(|e| DaemonError::Tls(e.to_string()))?;
+	Ok(Arc::new(config))
+}
+
+/// Load a PEM certificate chain and private key.
+pub fn load(cert_path: &Path, key_path: &Path) -> Result<Arc<ServerConfig>, DaemonError> {
+	let certs = rustls_pemfile::certs(&mut BufReader::new(fs::File::open(cert_path)?))
+		.collect::<Result<Vec<_>, _>>()?;
+	if certs.is_empty() {
+		return Err(DaemonError::Tls(format!("no certificates in {}", cert_path.display())));
+	}
+	let key = rustls_pemfile::private_key(&mut BufReader::new(fs::File::open(key_path)?))?
+		.ok_or_else(|| DaemonError::Tls(format!("no private key in {}", key_path.display())))?;
+	server_config(certs, key)
+}
+
+/// Generate a self-signed certificate for `localhost`, writing it as PEM to
+/// `cert_path` so that clients can pin it.
+pub fn self_signed(cert_path: &Path) -> Result<Arc<ServerConfig>, DaemonError> {
+	let names = vec!["localhost".to_string(), "127.0.0.1".to_string(), "::1".to_string()];
+	let certified =
+		rcgen::generate_simple_self_signed(names).map_err(|e| DaemonError::Tls(e.to_string()))?;
+	fs::write(cert_path, certified.cert.pem())?;
+
+	let key = PrivatePkcs8KeyDer::from(certified.key_pair.serialize_der());
+	server_config(vec![certified.cert.der().clone()], key.into())
+}
+
+/// A client configuration trusting only the CA certificates in the PEM file
+/// at `ca_path`, such as the one written by [`self_signed`].
+pub fn client_config(ca_path: &Path) -> std::io::Result<Arc<ClientConfig>> {
+	let invalid = |message: String| std::io::Error::new(std::io::ErrorKind::InvalidData, message);
+	let mut roots = RootCertStore::empty();
+	for cert in rustls_pemfile::certs(&mut BufReader::new(fs::File::open(ca_path)?)) {
+		roots.add(cert?).map_err(|e| invalid(e.to_string()))?;
+	}
+	if roots.is_empty() {
+		return Err(invalid(format!("no certificates in {}", ca_path.display())));
+	}
+	let config = ClientConfig::builder_with_provider(Arc::new(default_provider()))
+		.with_safe_default_protocol_versions()
+		.map_err(|e| invalid(e.to_string()))?
+		.with_root_certificates(roots)
+		.with_no_client_auth();
+	Ok(Arc::new(config))
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn test_self_signed() {
+		let path =
+			std::env::temp_dir().join(format!("hal-simplicity-test-{}.pem", std::process::id()));
+		self_signed(&path).unwrap();
+
+		let certs = rustls_pemfile::certs(&mut BufReader::new(fs::File::open(&path).unwrap()))
+			.collect::<Result<Vec<_>, _>>()
+			.unwrap();
+		assert_eq!(certs.len(), 1);
+		// The certificate alone is not enough to serve, but can be pinned.
+		assert!(load(&path, &path).is_err());
+		assert!(client_config(&path).is_ok());
+		assert!(client_config(Path::new("/nonexistent")).is_err());
+		fs::remove_file(&path).unwrap();
+	}
+}
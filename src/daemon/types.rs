@@ -1,3 +1,7 @@
+use std::collections::BTreeMap;
+
+use schemars::schema::RootSchema;
+use schemars::JsonSchema;
 use serde::{Deserialize, Deserializer, Serialize, Serializer};
 
 pub use elements::bitcoin::secp256k1;
@@ -5,8 +9,18 @@ pub use elements::hashes::sha256;
 pub use simplicity::bitcoin::secp256k1::schnorr;
 pub use simplicity::{Amr, Cmr, Ihr};
 
-use crate::block::BlockInfo;
-use crate::tx::TransactionInfo;
+pub use crate::api::{
+	AddressCreateRequest, AddressCreateResponse, AddressInspectRequest, AddressInspectResponse,
+	BlockCreateRequest, BlockCreateResponse, BlockDecodeRequest, BlockDecodeResponse,
+	BlockStatsRequest, BlockStatsResponse, BlockVerifyChainRequest, BlockVerifyChainResponse,
+	BlockVerifySignblockRequest, BlockVerifySignblockResponse, PsetCreateRequest,
+	PsetExtractRequest, PsetExtractResponse, SimplicityInfoRequest, SimplicitySighashRequest,
+	TxCreateRequest, TxCreateResponse, TxDecodeRequest, TxDecodeResponse,
+};
+
+use super::backend::{BackendKind, BlockId};
+use super::jsonrpc::RpcError;
+use crate::actions::simplicity::pset::DEFAULT_MAX_JETS;
 use crate::Network;
 
 // Custom serialization for Parity as 0 or 1
@@ -30,202 +44,481 @@ mod parity_serde {
 	}
 }
 
-// Address types
-#[derive(Debug, Serialize, Deserialize)]
-pub struct AddressCreateRequest {
-	pub network: Option<Network>,
-	pub pubkey: Option<String>,
-	pub script: Option<String>,
-	pub blinder: Option<String>,
+// Block types
+#[derive(Debug, Serialize, Deserialize, JsonSchema)]
+pub struct BlockFetchRequest {
+	/// The block's hash, or its height in the backend's best chain.
+	pub block: BlockId,
+	/// The backend to fetch the block from, instead of the default.
+	pub backend: Option<BackendKind>,
 }
 
-pub use crate::address::Addresses as AddressCreateResponse;
+#[derive(Debug, Serialize, Deserialize, JsonSchema)]
+pub struct BlockFetchResponse {
+	pub raw_block: String,
+}
 
-#[derive(Debug, Serialize, Deserialize)]
-pub struct AddressInspectRequest {
-	pub address: String,
+// Transaction types
+#[derive(Debug, Serialize, Deserialize, JsonSchema)]
+pub struct TxBroadcastRequest {
+	pub raw_tx: String,
+	/// The backend to send the transaction with, instead of the default.
+	pub backend: Option<BackendKind>,
 }
 
-pub use crate::address::AddressInfo as AddressInspectResponse;
+#[derive(Debug, Serialize, Deserialize, JsonSchema)]
+pub struct TxBroadcastResponse {
+	#[schemars(with = "String")]
+	pub txid: elements::Txid,
+}
 
-// Block types
-#[derive(Debug, Serialize, Deserialize)]
-pub struct BlockCreateRequest {
-	pub block_info: BlockInfo,
+#[derive(Debug, Serialize, Deserialize, JsonSchema)]
+pub struct TxFetchRequest {
+	#[schemars(with = "String")]
+	pub txid: elements::Txid,
+	/// The backend to fetch the transaction from, instead of the default.
+	pub backend: Option<BackendKind>,
 }
 
-#[derive(Debug, Serialize, Deserialize)]
-pub struct BlockCreateResponse {
-	pub raw_block: String,
+#[derive(Debug, Serialize, Deserialize, JsonSchema)]
+pub struct TxFetchResponse {
+	pub raw_tx: String,
 }
 
-#[derive(Debug, Serialize, Deserialize)]
-pub struct BlockDecodeRequest {
-	pub raw_block: String,
-	pub network: Option<Network>,
-	pub txids: Option<bool>,
+// Daemon types
+#[derive(Debug, Serialize, Deserialize, JsonSchema)]
+pub struct GetInfoRequest {}
+
+#[derive(Debug, Serialize, Deserialize, JsonSchema)]
+pub struct GetInfoResponse {
+	pub version: String,
+	pub methods: Vec<String>,
+	/// Network used by requests which do not specify one.
+	pub network: Network,
+	/// Genesis hash used by requests which do not specify one.
+	pub genesis_hash: Option<String>,
+	/// Configured chain backends.
+	pub backends: Vec<String>,
+	/// Seconds since the daemon started.
+	pub uptime: u64,
+	/// Optional features the daemon supports, as named by [`Capability`].
+	/// They are strings so that clients can read the list of a newer daemon.
+	#[serde(default)]
+	pub capabilities: Vec<String>,
+	/// Formats results can be returned in: `json`, and `ndjson` if results
+	/// can be streamed.
+	#[serde(default)]
+	pub formats: Vec<String>,
+	/// The largest request the daemon accepts, in bytes, if it limits them.
+	#[serde(default)]
+	pub max_request_size: Option<usize>,
 }
 
-pub type BlockDecodeResponse = serde_json::Value;
+/// An optional feature of the daemon, which a client should check for in
+/// [`GetInfoResponse::capabilities`] before relying on it.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Capability {
+	/// Several calls can be sent in one JSON-RPC batch.
+	Batch,
+	/// PSETs can be kept between calls with `pset_open`.
+	Sessions,
+	/// The jets run by `pset_run` can be streamed as they execute.
+	Streaming,
+}
 
-// Transaction types
-#[derive(Debug, Serialize, Deserialize)]
-pub struct TxCreateRequest {
-	pub tx_info: TransactionInfo,
+impl Capability {
+	pub const ALL: &'static [Capability] = &[Self::Batch, Self::Sessions, Self::Streaming];
+
+	pub fn as_str(&self) -> &'static str {
+		match self {
+			Self::Batch => "batch",
+			Self::Sessions => "sessions",
+			Self::Streaming => "streaming",
+		}
+	}
 }
 
-#[derive(Debug, Serialize, Deserialize)]
-pub struct TxCreateResponse {
-	pub raw_tx: String,
+impl std::fmt::Display for Capability {
+	fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+		f.write_str(self.as_str())
+	}
 }
 
-#[derive(Debug, Serialize, Deserialize)]
-pub struct TxDecodeRequest {
-	pub raw_tx: String,
-	pub network: Option<Network>,
+#[derive(Debug, Serialize, Deserialize, JsonSchema)]
+pub struct HelpRequest {
+	/// Only describe this method.
+	pub method: Option<String>,
+}
+
+/// The JSON Schemas of a method's params and result.
+#[derive(Debug, Serialize, Deserialize, JsonSchema)]
+pub struct MethodSchema {
+	pub name: String,
+	#[schemars(with = "serde_json::Value")]
+	pub params: RootSchema,
+	#[schemars(with = "serde_json::Value")]
+	pub result: RootSchema,
+}
+
+pub type HelpResponse = Vec<MethodSchema>;
+
+#[derive(Debug, Serialize, Deserialize, JsonSchema)]
+pub struct SchemaRequest {
+	/// Only describe this type, such as `TransactionInfo`.
+	#[serde(rename = "type")]
+	pub type_: Option<String>,
 }
 
-pub type TxDecodeResponse = serde_json::Value;
+/// The JSON Schema of each output type, by name.
+pub type SchemaResponse = BTreeMap<String, serde_json::Value>;
+
+#[derive(Debug, Serialize, Deserialize, JsonSchema)]
+pub struct RpcDiscoverRequest {}
+
+/// An OpenRPC document.
+pub type RpcDiscoverResponse = serde_json::Value;
+
+#[derive(Debug, Serialize, Deserialize, JsonSchema)]
+pub struct StopRequest {}
+
+pub type StopResponse = String;
 
 // Keypair types
-#[derive(Debug, Serialize, Deserialize)]
+#[derive(Debug, Serialize, Deserialize, JsonSchema)]
 pub struct KeypairGenerateRequest {}
 
-#[derive(Debug, Serialize, Deserialize)]
+#[derive(Debug, Serialize, Deserialize, JsonSchema)]
 pub struct KeypairGenerateResponse {
+	#[schemars(with = "String")]
 	pub secret: secp256k1::SecretKey,
+	#[schemars(with = "String")]
 	pub x_only: secp256k1::XOnlyPublicKey,
 	#[serde(with = "parity_serde")]
+	#[schemars(with = "u8")]
 	pub parity: secp256k1::Parity,
 }
 
 // Simplicity types
-#[derive(Debug, Serialize, Deserialize)]
-pub struct SimplicityInfoRequest {
-	pub program: String,
-	pub witness: Option<String>,
-	pub state: Option<String>,
-	pub network: Option<String>,
-}
-
-#[derive(Debug, Serialize, Deserialize)]
+#[derive(Debug, Serialize, Deserialize, JsonSchema)]
 pub struct SimplicityInfoResponse {
 	pub jets: String,
 	pub commit_base64: String,
 	pub commit_decode: String,
 	pub type_arrow: String,
+	#[schemars(with = "String")]
 	pub cmr: Cmr,
 	pub liquid_address_unconf: String,
 	pub liquid_testnet_address_unconf: String,
 	pub is_redeem: bool,
+	#[serde(flatten)]
 	pub redeem_info: Option<RedeemInfo>,
 }
 
-#[derive(Debug, Serialize, Deserialize)]
+#[derive(Debug, Serialize, Deserialize, JsonSchema)]
 pub struct RedeemInfo {
 	pub redeem_base64: String,
 	pub witness_hex: String,
+	#[schemars(with = "String")]
 	pub amr: Amr,
+	#[schemars(with = "String")]
 	pub ihr: Ihr,
 }
 
-#[derive(Debug, Serialize, Deserialize)]
-pub struct SimplicitySighashRequest {
-	pub tx: String,
-	pub input_index: u32,
-	pub cmr: String,
-	pub control_block: Option<String>,
-	pub genesis_hash: Option<String>,
-	pub secret_key: Option<String>,
-	pub public_key: Option<String>,
-	pub signature: Option<String>,
-	pub input_utxos: Option<Vec<String>>,
-}
-
-#[derive(Debug, Serialize, Deserialize)]
+#[derive(Debug, Serialize, Deserialize, JsonSchema)]
 pub struct SimplicitySighashResponse {
+	#[schemars(with = "String")]
 	pub sighash: sha256::Hash,
+	#[schemars(with = "Option<String>")]
 	pub signature: Option<schnorr::Signature>,
 	pub valid_signature: Option<bool>,
 }
 
 // PSET types
-#[derive(Debug, Serialize, Deserialize)]
-pub struct PsetCreateRequest {
-	pub inputs: String,
-	pub outputs: String,
-	pub network: Option<String>,
-}
-
-#[derive(Debug, Serialize, Deserialize)]
+#[derive(Debug, Serialize, Deserialize, JsonSchema)]
 pub struct PsetCreateResponse {
 	pub pset: String,
 	pub updated_values: Vec<String>,
 }
 
-#[derive(Debug, Serialize, Deserialize)]
-pub struct PsetExtractRequest {
+#[derive(Debug, Serialize, Deserialize, JsonSchema)]
+pub struct PsetOpenRequest {
 	pub pset: String,
 }
 
-#[derive(Debug, Serialize, Deserialize)]
-pub struct PsetExtractResponse {
-	pub raw_tx: String,
+#[derive(Debug, Serialize, Deserialize, JsonSchema)]
+pub struct PsetOpenResponse {
+	pub session: String,
 }
 
-#[derive(Debug, Serialize, Deserialize)]
-pub struct PsetFinalizeRequest {
+#[derive(Debug, Serialize, Deserialize, JsonSchema)]
+pub struct PsetGetRequest {
+	pub session: String,
+}
+
+#[derive(Debug, Serialize, Deserialize, JsonSchema)]
+pub struct PsetGetResponse {
+	pub pset: String,
+}
+
+#[derive(Debug, Serialize, Deserialize, JsonSchema)]
+pub struct PsetCloseRequest {
+	pub session: String,
+}
+
+/// The last PSET of the closed session.
+pub type PsetCloseResponse = PsetGetResponse;
+
+#[derive(Debug, Serialize, Deserialize, JsonSchema)]
+pub struct PsetHistoryRequest {
+	pub session: String,
+}
+
+/// One PSET stored in a session.
+#[derive(Clone, Debug, Serialize, Deserialize, JsonSchema)]
+pub struct PsetRevision {
+	/// The position of the revision in the session's history, from 0.
+	pub revision: usize,
+	/// The method which stored the revision.
+	pub method: String,
 	pub pset: String,
+}
+
+#[derive(Debug, Serialize, Deserialize, JsonSchema)]
+pub struct PsetHistoryResponse {
+	/// Every revision of the session, the current one last.
+	pub revisions: Vec<PsetRevision>,
+}
+
+#[derive(Debug, Serialize, Deserialize, JsonSchema)]
+pub struct PsetRevertRequest {
+	pub session: String,
+	/// The revision to make current again.
+	pub revision: usize,
+}
+
+/// The PSET of the reverted-to revision, which is now current.
+pub type PsetRevertResponse = PsetGetResponse;
+
+#[derive(Debug, Serialize, Deserialize, JsonSchema)]
+pub struct PsetFinalizeRequest {
+	/// The PSET, unless `session` is given.
+	pub pset: Option<String>,
+	/// A session opened by `pset_open`, instead of `pset`.
+	pub session: Option<String>,
 	pub input_index: u32,
 	pub program: String,
 	pub witness: String,
 	pub genesis_hash: Option<String>,
+	/// The network whose genesis hash to use, unless `genesis_hash` is given.
+	pub network: Option<Network>,
 }
 
-#[derive(Debug, Serialize, Deserialize)]
+#[derive(Debug, Serialize, Deserialize, JsonSchema)]
 pub struct PsetFinalizeResponse {
 	pub pset: String,
 	pub updated_values: Vec<String>,
 }
 
-#[derive(Debug, Serialize, Deserialize)]
+#[derive(Debug, Serialize, Deserialize, JsonSchema)]
 pub struct PsetRunRequest {
-	pub pset: String,
+	/// The PSET, unless `session` is given.
+	pub pset: Option<String>,
+	/// A session opened by `pset_open`, instead of `pset`.
+	pub session: Option<String>,
 	pub input_index: u32,
 	pub program: String,
 	pub witness: String,
 	pub genesis_hash: Option<String>,
+	/// The network whose genesis hash to use, unless `genesis_hash` is given.
+	pub network: Option<Network>,
+	/// Keep only the first and last `max_jets` jet calls in the response,
+	/// along with the number of calls of each jet. Defaults to 1000.
+	pub max_jets: Option<usize>,
+	/// Keep every jet call, however many, instead of bounding them by
+	/// `max_jets`.
+	#[serde(default)]
+	pub all_jets: bool,
 }
 
-#[derive(Debug, Serialize, Deserialize)]
+impl PsetRunRequest {
+	/// The bound on the jet calls kept in the response, if any.
+	pub fn jet_limit(&self) -> Option<usize> {
+		match self.all_jets {
+			true => None,
+			false => Some(self.max_jets.unwrap_or(DEFAULT_MAX_JETS)),
+		}
+	}
+}
+
+#[derive(Debug, Serialize, Deserialize, JsonSchema)]
 pub struct PsetRunResponse {
 	pub success: bool,
 	pub jets: Vec<JetCall>,
+	/// Set if calls were left out of `jets` because of `max_jets`.
+	#[serde(skip_serializing_if = "Option::is_none")]
+	pub truncated: Option<TruncatedTrace>,
 }
 
-#[derive(Debug, Serialize, Deserialize)]
+#[derive(Debug, Serialize, Deserialize, JsonSchema)]
+pub struct TruncatedTrace {
+	/// The number of calls left out between `jets` and `last_jets`.
+	pub omitted: usize,
+	pub last_jets: Vec<JetCall>,
+	/// The number of calls of each jet over the whole run.
+	pub counts: BTreeMap<String, usize>,
+}
+
+#[derive(Debug, Serialize, Deserialize, JsonSchema)]
 pub struct JetCall {
 	pub jet: String,
 	pub source_ty: String,
 	pub target_ty: String,
 	pub success: bool,
-	pub input_hex: String,
-	pub output_hex: String,
+	pub input_value: String,
+	pub output_value: String,
+	#[serde(skip_serializing_if = "Option::is_none")]
 	pub equality_check: Option<(String, String)>,
 }
 
-#[derive(Debug, Serialize, Deserialize)]
+#[derive(Debug, Serialize, Deserialize, JsonSchema)]
 pub struct PsetUpdateInputRequest {
-	pub pset: String,
+	/// The PSET, unless `session` is given.
+	pub pset: Option<String>,
+	/// A session opened by `pset_open`, instead of `pset`.
+	pub session: Option<String>,
 	pub input_index: u32,
-	pub input_utxo: String,
+	/// The output being spent, as `<scriptPubKey>:<asset>:<value>`. If unset,
+	/// it is fetched from the chain backend.
+	pub input_utxo: Option<String>,
+	/// The backend to fetch `input_utxo` from, instead of the default.
+	pub backend: Option<BackendKind>,
 	pub internal_key: Option<String>,
 	pub cmr: Option<String>,
 	pub state: Option<String>,
 }
 
-#[derive(Debug, Serialize, Deserialize)]
+#[derive(Debug, Serialize, Deserialize, JsonSchema)]
 pub struct PsetUpdateInputResponse {
 	pub pset: String,
 	pub updated_values: Vec<String>,
 }
+
+// Watch types
+pub use super::backend::Utxo;
+
+/// An address or program registered with `watch_add`.
+#[derive(Clone, Debug, Serialize, Deserialize, JsonSchema)]
+pub struct WatchEntry {
+	/// Hex-encoded scriptPubKey, which identifies the entry.
+	pub script_pubkey: String,
+	pub address: String,
+	pub cmr: Option<String>,
+	pub state: Option<String>,
+	pub label: Option<String>,
+}
+
+#[derive(Debug, Serialize, Deserialize, JsonSchema)]
+pub struct WatchAddRequest {
+	/// The address to watch, unless `cmr` is given.
+	pub address: Option<String>,
+	/// The CMR of a program to watch, instead of `address`.
+	pub cmr: Option<String>,
+	/// The state commitment of the program, if any.
+	pub state: Option<String>,
+	/// The network of the program's address.
+	pub network: Option<Network>,
+	pub label: Option<String>,
+}
+
+pub type WatchAddResponse = WatchEntry;
+
+#[derive(Debug, Serialize, Deserialize, JsonSchema)]
+pub struct WatchRemoveRequest {
+	pub script_pubkey: String,
+}
+
+/// The removed entry.
+pub type WatchRemoveResponse = WatchEntry;
+
+#[derive(Debug, Serialize, Deserialize, JsonSchema)]
+pub struct WatchListRequest {}
+
+pub type WatchListResponse = Vec<WatchEntry>;
+
+#[derive(Debug, Serialize, Deserialize, JsonSchema)]
+pub struct WatchUnspentRequest {
+	/// Only look up this entry.
+	pub script_pubkey: Option<String>,
+	/// The backend to look up outputs with, instead of the default.
+	pub backend: Option<BackendKind>,
+}
+
+#[derive(Debug, Serialize, Deserialize, JsonSchema)]
+pub struct WatchUnspent {
+	#[serde(flatten)]
+	pub entry: WatchEntry,
+	pub utxos: Vec<Utxo>,
+}
+
+pub type WatchUnspentResponse = Vec<WatchUnspent>;
+
+// Job types
+
+#[derive(Debug, Serialize, Deserialize, JsonSchema)]
+pub struct JobSubmitRequest {
+	/// The method to run in the background.
+	pub method: String,
+	/// Its params.
+	pub params: Option<serde_json::Value>,
+	/// A URL to POST the result to once the job finishes, signed with the
+	/// daemon's callback secret. It must start with an allowed callback prefix.
+	pub callback: Option<String>,
+}
+
+#[derive(Debug, Serialize, Deserialize, JsonSchema)]
+pub struct JobSubmitResponse {
+	pub job: String,
+}
+
+#[derive(Debug, Serialize, Deserialize, JsonSchema)]
+pub struct JobStatusRequest {
+	pub job: String,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
+#[serde(rename_all = "lowercase")]
+pub enum JobState {
+	Queued,
+	Running,
+	Done,
+	Failed,
+}
+
+#[derive(Debug, Serialize, Deserialize, JsonSchema)]
+pub struct JobStatusResponse {
+	pub job: String,
+	pub method: String,
+	pub state: JobState,
+	/// Seconds since the job was submitted, or how long it took once finished.
+	pub seconds: u64,
+}
+
+#[derive(Debug, Serialize, Deserialize, JsonSchema)]
+pub struct JobResultRequest {
+	pub job: String,
+}
+
+/// The result of the job's method.
+pub type JobResultResponse = serde_json::Value;
+
+/// The body POSTed to the callback URL of a finished job.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct JobCallback {
+	pub job: String,
+	pub method: String,
+	pub state: JobState,
+	#[serde(skip_serializing_if = "Option::is_none")]
+	pub result: Option<serde_json::Value>,
+	#[serde(skip_serializing_if = "Option::is_none")]
+	pub error: Option<RpcError>,
+}
@@ -0,0 +1,207 @@
+//! Watch-only tracking of Simplicity outputs
+//!
+//! `watch_add` registers an address, or the CMR (and optional state) of a
+//! program, whose address is then computed as for `simplicity_info`. Entries
+//! are keyed by scriptPubKey. `watch_unspent` asks the configured
+//! [`super::backend::ChainBackend`] for the unspent outputs of every entry.
+//!
+//! With a [`super::store::Store`], the list is written through to disk and
+//! survives a restart.
+
+use std::collections::BTreeMap;
+use std::str::FromStr;
+use std::sync::Mutex;
+
+use elements::hex::FromHex;
+
+use super::jsonrpc::{ErrorCode, RpcError};
+use super::types::{WatchAddRequest, WatchEntry};
+use super::DaemonError;
+use crate::Network;
+
+/// The registered entries.
+#[derive(Debug, Default)]
+pub struct WatchList {
+	entries: Mutex<BTreeMap<String, WatchEntry>>,
+	tree: Option<sled::Tree>,
+}
+
+impl WatchList {
+	/// An empty list, kept in memory.
+	pub fn new() -> Self {
+		Self::default()
+	}
+
+	/// Keep the list in `tree`, starting with the entries already stored there.
+	pub fn persistent(tree: sled::Tree) -> Result<Self, DaemonError> {
+		let entries = tree
+			.iter()
+			.map(|entry| {
+				let (script_pubkey, entry) =
+					entry.map_err(|e| DaemonError::Store(e.to_string()))?;
+				let entry = serde_json::from_slice(&entry).map_err(|e| {
+					DaemonError::Store(format!(
+						"watch entry {}: {}",
+						String::from_utf8_lossy(&script_pubkey),
+						e
+					))
+				})?;
+				Ok((String::from_utf8_lossy(&script_pubkey).into_owned(), entry))
+			})
+			.collect::<Result<_, DaemonError>>()?;
+		Ok(Self {
+			entries: Mutex::new(entries),
+			tree: Some(tree),
+		})
+	}
+
+	/// Write an entry to disk, or remove it if `entry` is `None`.
+	fn persist(&self, script_pubkey: &str, entry: Option<&WatchEntry>) -> Result<(), RpcError> {
+		let tree = match self.tree {
+			Some(ref tree) => tree,
+			None => return Ok(()),
+		};
+		let result = match entry {
+			Some(entry) => {
+				let value = serde_json::to_vec(entry).expect("entries serialize");
+				tree.insert(script_pubkey, value).map(|_| ())
+			}
+			None => tree.remove(script_pubkey).map(|_| ()),
+		};
+		result.map_err(|e| {
+			RpcError::custom(
+				ErrorCode::InternalError.code(),
+				format!("Failed to store watch entry: {}", e),
+			)
+		})
+	}
+
+	/// Register an entry, replacing any with the same scriptPubKey.
+	pub fn add(&self, entry: WatchEntry) -> Result<(), RpcError> {
+		let mut entries = self.entries.lock().expect("poisoned");
+		self.persist(&entry.script_pubkey, Some(&entry))?;
+		entries.insert(entry.script_pubkey.clone(), entry);
+		Ok(())
+	}
+
+	/// Forget the entry with the given scriptPubKey, returning it.
+	pub fn remove(&self, script_pubkey: &str) -> Result<WatchEntry, RpcError> {
+		let mut entries = self.entries.lock().expect("poisoned");
+		let entry = entries.remove(script_pubkey).ok_or_else(|| {
+			RpcError::custom(
+				ErrorCode::InvalidParams.code(),
+				format!("Not watching scriptPubKey {}", script_pubkey),
+			)
+		})?;
+		self.persist(script_pubkey, None)?;
+		Ok(entry)
+	}
+
+	/// All entries, ordered by scriptPubKey.
+	pub fn list(&self) -> Vec<WatchEntry> {
+		self.entries.lock().expect("poisoned").values().cloned().collect()
+	}
+}
+
+/// Build the entry for a `watch_add` request.
+pub fn entry(req: WatchAddRequest, default_network: Network) -> Result<WatchEntry, RpcError> {
+	let invalid = |message: String| RpcError::custom(ErrorCode::InvalidParams.code(), message);
+
+	let address = match (&req.address, &req.cmr) {
+		(Some(address), None) => {
+			if req.state.is_some() {
+				return Err(invalid("state requires cmr".to_string()));
+			}
+			elements::Address::from_str(address)
+				.map_err(|e| invalid(format!("invalid address: {}", e)))?
+		}
+		(None, Some(cmr)) => {
+			let cmr = simplicity::Cmr::from_str(cmr)
+				.map_err(|e| invalid(format!("invalid CMR: {}", e)))?;
+			let state = req
+				.state
+				.as_deref()
+				.map(<[u8; 32]>::from_hex)
+				.transpose()
+				.map_err(|e| invalid(format!("invalid state commitment: {}", e)))?;
+			let network = req.network.unwrap_or(default_network);
+			crate::hal_simplicity::elements_address(cmr, state, network.address_params())
+		}
+		_ => return Err(invalid("Exactly one of address and cmr must be given".to_string())),
+	};
+
+	Ok(WatchEntry {
+		script_pubkey: hex::encode(address.script_pubkey().as_bytes()),
+		address: address.to_string(),
+		cmr: req.cmr,
+		state: req.state,
+		label: req.label,
+	})
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use crate::daemon::handler::DefaultRpcHandler;
+	use crate::daemon::jsonrpc::RpcHandler;
+	use crate::daemon::store::{Store, WATCH};
+	use serde_json::json;
+
+	const CMR: &str = "c40a10263f7436b4160acbef1c36fba4be4d95df181a968afeab5eac247adff7";
+
+	fn add(list: &WatchList, label: &str) -> WatchEntry {
+		let req = WatchAddRequest {
+			address: None,
+			cmr: Some(CMR.to_string()),
+			state: None,
+			network: None,
+			label: Some(label.to_string()),
+		};
+		let entry = entry(req, Network::Liquid).unwrap();
+		list.add(entry.clone()).unwrap();
+		entry
+	}
+
+	#[test]
+	fn test_persistent_watch_list() {
+		let store = Store::temporary().unwrap();
+		let list = WatchList::persistent(store.tree(WATCH).unwrap()).unwrap();
+		let entry = add(&list, "first");
+		assert!(entry.address.starts_with("ex1p"), "{}", entry.address);
+		// Adding the same program again replaces the entry.
+		add(&list, "second");
+		drop(list);
+
+		let list = WatchList::persistent(store.tree(WATCH).unwrap()).unwrap();
+		let entries = list.list();
+		assert_eq!(entries.len(), 1);
+		assert_eq!(entries[0].label.as_deref(), Some("second"));
+
+		list.remove(&entry.script_pubkey).unwrap();
+		assert!(list.remove(&entry.script_pubkey).is_err());
+		assert!(WatchList::persistent(store.tree(WATCH).unwrap()).unwrap().list().is_empty());
+	}
+
+	#[test]
+	fn test_watch_methods() {
+		let handler = DefaultRpcHandler::default();
+		let added = handler.handle("watch_add", Some(json!({ "cmr": CMR }))).unwrap();
+
+		// The address is the same whether registered by CMR or by address.
+		let params = json!({ "address": added["address"], "label": "by address" });
+		let again = handler.handle("watch_add", Some(params)).unwrap();
+		assert_eq!(again["script_pubkey"], added["script_pubkey"]);
+		assert_eq!(handler.handle("watch_list", None).unwrap().as_array().unwrap().len(), 1);
+
+		let both = json!({ "address": added["address"], "cmr": CMR });
+		assert!(handler.handle("watch_add", Some(both)).is_err());
+
+		// Without a backend there is no way to look up outputs.
+		let err = handler.handle("watch_unspent", None).unwrap_err();
+		assert!(err.message.contains("backend"), "{}", err.message);
+
+		let params = json!({ "script_pubkey": added["script_pubkey"] });
+		handler.handle("watch_remove", Some(params)).unwrap();
+		assert_eq!(handler.handle("watch_list", None).unwrap(), json!([]));
+	}
+}
@@ -0,0 +1,260 @@
+//! WebSocket transport for the JSON-RPC methods
+//!
+//! Each text message is handled as a JSON-RPC request (or batch), exactly as
+//! over HTTP. In addition, `pset_run_subscribe` takes the params of `pset_run`
+//! and returns a subscription ID. The program is then run in the background:
+//! each jet is sent as a `pset_run_jet` notification as soon as it executes,
+//! followed by a single `pset_run_done` notification carrying the full result
+//! (or an error). Notifications are produced no faster than the client reads
+//! them, and closing the connection cancels the run.
+//!
+//! Each message counts against the client's rate limit, and messages larger
+//! than the maximum body size close the connection (see [`super::limits`]).
+
+use std::net::IpAddr;
+use std::ops::ControlFlow;
+use std::sync::Arc;
+
+use futures_util::{SinkExt as _, StreamExt as _};
+use serde_json::{json, Value};
+use tokio::io::{AsyncRead, AsyncWrite};
+use tokio::sync::{broadcast, mpsc};
+use tokio_tungstenite::tungstenite::handshake::server::{ErrorResponse, Request, Response};
+use tokio_tungstenite::tungstenite::http::StatusCode;
+use tokio_tungstenite::tungstenite::protocol::WebSocketConfig;
+use tokio_tungstenite::tungstenite::Message;
+
+use super::auth::Auth;
+use super::handler::DefaultRpcHandler;
+use super::jsonrpc::{JsonRpcService, RpcError, RpcRequest, RpcResponse};
+use super::limits::{Limits, RATE_LIMITED_CODE};
+use super::stream::PsetRun;
+
+/// The method which starts a streaming `pset_run`.
+pub const PSET_RUN_SUBSCRIBE: &str = "pset_run_subscribe";
+
+/// How many messages may be waiting to be sent before a run is paused.
+const BUFFERED_MESSAGES: usize = 64;
+
+/// Serve JSON-RPC over a single WebSocket connection until it closes.
+///
+/// `client` is the address of the peer, used for rate limiting.
+pub async fn serve_connection<S>(
+	stream: S,
+	client: Option<IpAddr>,
+	rpc_service: Arc<JsonRpcService<DefaultRpcHandler>>,
+	auth: Arc<Auth>,
+	limits: Arc<Limits>,
+	mut shutdown_rx: broadcast::Receiver<()>,
+) where
+	S: AsyncRead + AsyncWrite + Unpin + Send + 'static,
+{
+	#[allow(clippy::result_large_err)] // the error type is fixed by tungstenite
+	let check_auth = |request: &Request, response: Response| {
+		if auth.check(request.headers()) {
+			Ok(response)
+		} else {
+			let mut error = ErrorResponse::new(Some("Unauthorized".to_string()));
+			*error.status_mut() = StatusCode::UNAUTHORIZED;
+			Err(error)
+		}
+	};
+	let config = WebSocketConfig {
+		max_message_size: Some(limits.max_body_size),
+		max_frame_size: Some(limits.max_body_size),
+		..Default::default()
+	};
+	let ws = match tokio_tungstenite::accept_hdr_async_with_config(stream, check_auth, Some(config))
+		.await
+	{
+		Ok(ws) => ws,
+		Err(e) => {
+			log::debug!("WebSocket handshake failed: {}", e);
+			return;
+		}
+	};
+	let (mut sink, mut source) = ws.split();
+
+	// All responses and notifications go through one channel, so that
+	// subscriptions running in the background can write to the socket.
+	let (tx, mut rx) = mpsc::channel::<String>(BUFFERED_MESSAGES);
+	let writer = tokio::spawn(async move {
+		while let Some(text) = rx.recv().await {
+			if sink.send(Message::Text(text)).await.is_err() {
+				break;
+			}
+		}
+		let _ = sink.close().await;
+	});
+
+	let mut next_subscription = 0u64;
+	loop {
+		// On shutdown, stop reading but let running subscriptions finish.
+		let message = tokio::select! {
+			message = source.next() => message,
+			_ = shutdown_rx.recv() => break,
+		};
+		let Some(Ok(message)) = message else {
+			break;
+		};
+		let text = match message {
+			Message::Text(text) => text,
+			Message::Close(_) => break,
+			_ => continue,
+		};
+
+		if !limits.allow(client) {
+			let error = RpcError::custom(RATE_LIMITED_CODE, "Too many requests".to_string());
+			send(&tx, &RpcResponse::error(error, Value::Null)).await;
+			continue;
+		}
+
+		match serde_json::from_str::<RpcRequest>(&text) {
+			Ok(request) if request.method == PSET_RUN_SUBSCRIBE => {
+				subscribe_pset_run(request, &rpc_service, &mut next_subscription, tx.clone()).await;
+			}
+			_ => {
+				let rpc_service = rpc_service.clone();
+				let response = tokio::task::spawn_blocking(move || rpc_service.handle_raw(&text))
+					.await
+					.unwrap_or_default();
+				if !response.is_empty() {
+					let _ = tx.send(response).await;
+				}
+			}
+		}
+	}
+
+	drop(tx);
+	let _ = writer.await;
+}
+
+/// Start a `pset_run` whose jets are streamed as notifications.
+///
+/// The run waits for a worker like a `pset_run` call (see [`super::pool`]),
+/// and stops if a notification cannot be sent because the connection closed.
+async fn subscribe_pset_run(
+	request: RpcRequest,
+	rpc_service: &Arc<JsonRpcService<DefaultRpcHandler>>,
+	next_subscription: &mut u64,
+	tx: mpsc::Sender<String>,
+) {
+	let id = request.id.clone().unwrap_or(Value::Null);
+	let run = match PsetRun::new(rpc_service.handler(), request.params) {
+		Ok(run) => run,
+		Err(error) => {
+			send(&tx, &RpcResponse::error(error, id)).await;
+			return;
+		}
+	};
+	*next_subscription += 1;
+	let subscription = *next_subscription;
+	send(&tx, &RpcResponse::success(json!(subscription), id)).await;
+
+	let rpc_service = rpc_service.clone();
+	tokio::task::spawn_blocking(move || {
+		let result = run.run(rpc_service.handler(), |jet| {
+			notify(&tx, "pset_run_jet", subscription, json!({ "result": jet }))
+		});
+		let params = match result {
+			Ok(result) => json!({ "result": result }),
+			Err(error) => json!({ "error": error }),
+		};
+		let _ = notify(&tx, "pset_run_done", subscription, params);
+	});
+}
+
+/// Send a subscription notification, adding the subscription ID to `params`,
+/// waiting while the client is behind. Breaks if the connection has closed.
+fn notify(
+	tx: &mpsc::Sender<String>,
+	method: &str,
+	subscription: u64,
+	mut params: Value,
+) -> ControlFlow<()> {
+	params["subscription"] = json!(subscription);
+	let notification = RpcRequest::new(method.to_string(), Some(params), None);
+	let text = serde_json::to_string(&notification).expect("notifications serialize");
+	match tx.blocking_send(text) {
+		Ok(()) => ControlFlow::Continue(()),
+		Err(_) => ControlFlow::Break(()),
+	}
+}
+
+async fn send<T: serde::Serialize>(tx: &mpsc::Sender<String>, message: &T) {
+	if let Ok(text) = serde_json::to_string(message) {
+		// The connection may already be closed, in which case there is no one to tell.
+		let _ = tx.send(text).await;
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use crate::daemon::handler::create_service;
+	use crate::daemon::jsonrpc::ErrorCode;
+	use tokio::net::TcpListener;
+
+	async fn next_json<S>(client: &mut S) -> Value
+	where
+		S: futures_util::Stream<Item = Result<Message, tokio_tungstenite::tungstenite::Error>>
+			+ Unpin,
+	{
+		match client.next().await {
+			Some(Ok(Message::Text(text))) => serde_json::from_str(&text).unwrap(),
+			other => panic!("unexpected message {:?}", other),
+		}
+	}
+
+	#[tokio::test]
+	async fn test_websocket() {
+		let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+		let address = listener.local_addr().unwrap();
+		let rpc_service = Arc::new(create_service());
+		tokio::spawn(async move {
+			let (stream, _) = listener.accept().await.unwrap();
+			let limits = Arc::new(Limits::default());
+			let (_shutdown_tx, shutdown_rx) = broadcast::channel(1);
+			let auth = Arc::new(Auth::default());
+			serve_connection(stream, None, rpc_service, auth, limits, shutdown_rx).await;
+		});
+
+		let (mut client, _) =
+			tokio_tungstenite::connect_async(format!("ws://{}", address)).await.unwrap();
+
+		// Ordinary methods behave as over HTTP.
+		client
+			.send(Message::Text(r#"{"jsonrpc":"2.0","method":"nope","id":1}"#.to_string()))
+			.await
+			.unwrap();
+		let response = next_json(&mut client).await;
+		assert_eq!(response["error"]["code"], json!(ErrorCode::MethodNotFound.code()));
+
+		// Bad params are rejected without a subscription.
+		client
+			.send(Message::Text(
+				r#"{"jsonrpc":"2.0","method":"pset_run_subscribe","params":{},"id":2}"#.to_string(),
+			))
+			.await
+			.unwrap();
+		let response = next_json(&mut client).await;
+		assert_eq!(response["id"], json!(2));
+		assert_eq!(response["error"]["code"], json!(ErrorCode::InvalidParams.code()));
+
+		// Errors while running are reported in the final notification.
+		client
+			.send(Message::Text(
+				r#"{"jsonrpc":"2.0","method":"pset_run_subscribe","params":{"pset":"","input_index":0,"program":"","witness":""},"id":3}"#
+					.to_string(),
+			))
+			.await
+			.unwrap();
+		let response = next_json(&mut client).await;
+		assert_eq!(response["id"], json!(3));
+		assert_eq!(response["result"], json!(1));
+		let notification = next_json(&mut client).await;
+		assert_eq!(notification["method"], json!("pset_run_done"));
+		assert_eq!(notification["params"]["subscription"], json!(1));
+		assert!(notification["params"]["error"]["message"].is_string());
+	}
+}
@@ -0,0 +1,272 @@
+//! C ABI for the action layer
+//!
+//! Each function takes its params as a NUL-terminated JSON object, with the
+//! same fields as the daemon method of the same name, and returns a JSON
+//! object, either `{"result": ...}` or `{"error": {"code": ..., "message":
+//! ..., "data": ...}}` with the codes of [`crate::actions::error`]. Failures
+//! which are not those of an action, such as invalid params, have code 1.
+//!
+//! The returned string is owned by the caller and must be released with
+//! [`hal_simplicity_free`]. Build the library with
+//!
+//! ```text
+//! cargo rustc --release --lib --features ffi --crate-type cdylib
+//! ```
+//!
+//! `contrib/hal_simplicity.h` declares these functions for C, and
+//! `contrib/hal_simplicity.py` wraps them for Python.
+
+use std::ffi::{c_char, CStr, CString};
+use std::ops::ControlFlow;
+use std::panic::{self, AssertUnwindSafe};
+
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+
+use crate::actions;
+use crate::actions::error::ActionError;
+
+#[derive(Deserialize)]
+struct SimplicityInfoParams {
+	program: String,
+	witness: Option<String>,
+	state: Option<String>,
+}
+
+#[derive(Deserialize)]
+struct SimplicitySighashParams {
+	tx: String,
+	input_index: u32,
+	cmr: String,
+	control_block: Option<String>,
+	genesis_hash: Option<String>,
+	secret_key: Option<String>,
+	public_key: Option<String>,
+	signature: Option<String>,
+	input_utxos: Option<Vec<String>>,
+}
+
+#[derive(Deserialize)]
+struct PsetFinalizeParams {
+	pset: String,
+	input_index: u32,
+	program: String,
+	witness: String,
+	genesis_hash: Option<String>,
+}
+
+#[derive(Deserialize)]
+struct PsetRunParams {
+	pset: String,
+	input_index: u32,
+	program: String,
+	witness: String,
+	genesis_hash: Option<String>,
+	max_jets: Option<usize>,
+	#[serde(default)]
+	all_jets: bool,
+}
+
+/// An error which is not that of an action.
+fn generic_error(message: String) -> Value {
+	json!({ "error": { "code": 1, "message": message } })
+}
+
+/// Decode `params`, run `action` on them and encode its outcome.
+///
+/// # Safety
+///
+/// `params` must be null or point to a NUL-terminated string.
+unsafe fn call<P, R, E>(
+	params: *const c_char,
+	action: impl FnOnce(P) -> Result<R, E>,
+) -> *mut c_char
+where
+	P: DeserializeOwned,
+	R: Serialize,
+	E: ActionError,
+{
+	let outcome = if params.is_null() {
+		generic_error("params must not be null".to_owned())
+	} else {
+		let params = CStr::from_ptr(params);
+		// Panics must not unwind into the caller.
+		panic::catch_unwind(AssertUnwindSafe(|| {
+			let params = match params.to_str() {
+				Ok(params) => params,
+				Err(e) => return generic_error(format!("params are not UTF-8: {}", e)),
+			};
+			let params = match serde_json::from_str(params) {
+				Ok(params) => params,
+				Err(e) => return generic_error(format!("invalid params: {}", e)),
+			};
+			match action(params) {
+				Ok(result) => json!({ "result": result }),
+				Err(e) => json!({
+					"error": { "code": e.code(), "message": e.to_string(), "data": e.data() }
+				}),
+			}
+		}))
+		.unwrap_or_else(|_| generic_error("internal error".to_owned()))
+	};
+	CString::new(outcome.to_string()).expect("JSON escapes NUL").into_raw()
+}
+
+/// Decode a program, as the `simplicity_info` method.
+///
+/// # Safety
+///
+/// `params` must be null or point to a NUL-terminated string.
+#[no_mangle]
+pub unsafe extern "C" fn hal_simplicity_info(params: *const c_char) -> *mut c_char {
+	call(params, |p: SimplicityInfoParams| {
+		actions::simplicity::simplicity_info(&p.program, p.witness.as_deref(), p.state.as_deref())
+	})
+}
+
+/// Compute a sighash, and optionally sign it or check a signature, as the
+/// `simplicity_sighash` method.
+///
+/// # Safety
+///
+/// `params` must be null or point to a NUL-terminated string.
+#[no_mangle]
+pub unsafe extern "C" fn hal_simplicity_sighash(params: *const c_char) -> *mut c_char {
+	call(params, |p: SimplicitySighashParams| {
+		let input_utxos =
+			p.input_utxos.as_ref().map(|v| v.iter().map(String::as_str).collect::<Vec<_>>());
+		actions::simplicity::simplicity_sighash(
+			&p.tx,
+			&p.input_index.to_string(),
+			&p.cmr,
+			p.control_block.as_deref(),
+			p.genesis_hash.as_deref(),
+			p.secret_key.as_deref(),
+			p.public_key.as_deref(),
+			p.signature.as_deref(),
+			input_utxos.as_deref(),
+		)
+	})
+}
+
+/// Attach a program and witness to a PSET input, as the `pset_finalize` method.
+///
+/// # Safety
+///
+/// `params` must be null or point to a NUL-terminated string.
+#[no_mangle]
+pub unsafe extern "C" fn hal_simplicity_pset_finalize(params: *const c_char) -> *mut c_char {
+	call(params, |p: PsetFinalizeParams| {
+		actions::simplicity::pset::pset_finalize(
+			&p.pset,
+			&p.input_index.to_string(),
+			&p.program,
+			&p.witness,
+			p.genesis_hash.as_deref(),
+		)
+	})
+}
+
+/// Run a program in the context of a PSET input, as the `pset_run` method.
+///
+/// # Safety
+///
+/// `params` must be null or point to a NUL-terminated string.
+#[no_mangle]
+pub unsafe extern "C" fn hal_simplicity_pset_run(params: *const c_char) -> *mut c_char {
+	call(params, |p: PsetRunParams| {
+		actions::simplicity::pset::pset_run_with_callback(
+			&p.pset,
+			&p.input_index.to_string(),
+			&p.program,
+			&p.witness,
+			p.genesis_hash.as_deref(),
+			match p.all_jets {
+				true => None,
+				false => Some(p.max_jets.unwrap_or(actions::simplicity::pset::DEFAULT_MAX_JETS)),
+			},
+			|_| ControlFlow::Continue(()),
+		)
+	})
+}
+
+/// Release a string returned by one of the functions above.
+///
+/// # Safety
+///
+/// `s` must be null or have been returned by one of the functions above, and
+/// not released already.
+#[no_mangle]
+pub unsafe extern "C" fn hal_simplicity_free(s: *mut c_char) {
+	if !s.is_null() {
+		drop(CString::from_raw(s));
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	fn call_json(f: unsafe extern "C" fn(*const c_char) -> *mut c_char, params: &str) -> Value {
+		let params = CString::new(params).unwrap();
+		unsafe {
+			let out = f(params.as_ptr());
+			let value = serde_json::from_slice(CStr::from_ptr(out).to_bytes()).unwrap();
+			hal_simplicity_free(out);
+			value
+		}
+	}
+
+	#[test]
+	fn test_ffi() {
+		let fixtures = actions::simplicity::simplicity_gen_fixtures(&["0"]);
+		let fixture = &fixtures.fixtures[0];
+
+		let info = call_json(
+			hal_simplicity_info,
+			&json!({ "program": fixture.program, "witness": fixture.witness }).to_string(),
+		);
+		assert_eq!(info["result"]["cmr"], fixture.cmr.to_string());
+
+		let run = call_json(
+			hal_simplicity_pset_run,
+			&json!({
+				"pset": fixture.pset,
+				"input_index": 0,
+				"program": fixture.program,
+				"witness": fixture.witness,
+				"genesis_hash": fixture.genesis_hash.to_string(),
+			})
+			.to_string(),
+		);
+		assert_eq!(run["result"]["success"], true);
+
+		let finalized = call_json(
+			hal_simplicity_pset_finalize,
+			&json!({
+				"pset": fixture.pset,
+				"input_index": 0,
+				"program": fixture.program,
+				"witness": fixture.witness,
+				"genesis_hash": fixture.genesis_hash.to_string(),
+			})
+			.to_string(),
+		);
+		assert_eq!(finalized["result"]["pset"], fixture.finalized_pset);
+
+		let error = call_json(
+			hal_simplicity_sighash,
+			&json!({ "tx": "zz", "input_index": 0, "cmr": fixture.cmr }).to_string(),
+		);
+		assert_eq!(error["error"]["data"]["subsystem"], "simplicity");
+
+		let error = call_json(hal_simplicity_info, "{");
+		assert_eq!(error["error"]["code"], 1);
+		unsafe {
+			let out = hal_simplicity_info(std::ptr::null());
+			assert!(CStr::from_ptr(out).to_str().unwrap().contains("null"));
+			hal_simplicity_free(out);
+		}
+	}
+}
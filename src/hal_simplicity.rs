@@ -3,22 +3,34 @@
 
 use std::sync::Arc;
 
-use elements::taproot::{TaprootBuilder, TaprootSpendInfo};
+use elements::taproot::{LeafVersion, TaprootBuilder, TaprootSpendInfo};
 use simplicity::bitcoin::secp256k1;
 use simplicity::jet::Jet;
 use simplicity::{BitIter, CommitNode, DecodeError, ParseError, RedeemNode};
 
+use crate::simplicityhl::{self, WitnessFileError};
+
+#[derive(Debug, thiserror::Error)]
+pub enum ProgramParseError {
+	#[error(transparent)]
+	Parse(#[from] ParseError),
+
+	#[error("invalid witness file: {0}")]
+	WitnessFile(#[from] WitnessFileError),
+}
+
 /// A representation of a hex or base64-encoded Simplicity program, as seen by
 /// hal-simplicity.
+///
+/// Cloning is cheap, since the decoded nodes are reference-counted.
+#[derive(Clone)]
 pub struct Program<J: Jet> {
 	/// A commitment-time program. This should have no hidden branches (though the
 	/// rust-simplicity encoding allows this) and no witness data.
 	///
-	/// When parsing a redeem-time program, we first parse it as a commitment-time
-	/// program (which will always succeed, despite the potentially hidden branches)
-	/// because this lets the tool provide information like CMRs or addresses even
-	/// if there is no witness data available or if the program is improperly
-	/// pruned.
+	/// When parsing a redeem-time program, this is recovered from the redeem-time
+	/// program by forgetting its witness data, rather than by decoding the program
+	/// a second time, which is slow for large programs.
 	commit_prog: Arc<CommitNode<J>>,
 	/// A redemption-time program. This should be pruned (though an unpruned or
 	/// improperly-pruned program can still be parsed) and have witness data.
@@ -34,34 +46,56 @@ impl<J: Jet> Program<J> {
 	///
 	/// The canonical representation of witnesses is hex, but old versions of simc
 	/// (e.g. every released version, and master, as of 2025-10-25) output base64.
-	pub fn from_str(prog_b64: &str, wit_hex: Option<&str>) -> Result<Self, ParseError> {
+	/// The witness may also be given as a SimplicityHL `.wit` file; see
+	/// [`crate::simplicityhl`].
+	pub fn from_str(prog_b64: &str, wit_hex: Option<&str>) -> Result<Self, ProgramParseError> {
 		let prog_bytes = crate::hex_or_base64(prog_b64).map_err(ParseError::Base64)?;
-		let iter = BitIter::new(prog_bytes.iter().copied());
-		let commit_prog = CommitNode::decode(iter).map_err(ParseError::Decode)?;
+		let prog_iter = BitIter::new(prog_bytes.into_iter());
 
-		let redeem_prog = wit_hex
-			.map(|wit_hex| {
+		match wit_hex {
+			Some(wit_file) if simplicityhl::is_witness_file(wit_file) => {
+				let commit_prog = CommitNode::decode(prog_iter).map_err(ParseError::Decode)?;
+				let redeem_prog = simplicityhl::attach_witness_file(&commit_prog, wit_file)?;
+				Ok(Self {
+					commit_prog,
+					redeem_prog: Some(redeem_prog),
+				})
+			}
+			Some(wit_hex) => {
 				let wit_bytes = crate::hex_or_base64(wit_hex).map_err(ParseError::Base64)?;
-				let prog_iter = BitIter::new(prog_bytes.into_iter());
 				let wit_iter = BitIter::new(wit_bytes.into_iter());
-				RedeemNode::decode(prog_iter, wit_iter).map_err(ParseError::Decode)
-			})
-			.transpose()?;
-
-		Ok(Self {
-			commit_prog,
-			redeem_prog,
-		})
+				let redeem_prog =
+					RedeemNode::decode(prog_iter, wit_iter).map_err(ParseError::Decode)?;
+				Ok(Self::from_redeem_prog(redeem_prog))
+			}
+			None => Ok(Self {
+				commit_prog: CommitNode::decode(prog_iter).map_err(ParseError::Decode)?,
+				redeem_prog: None,
+			}),
+		}
 	}
 
 	/// Constructs a program from raw bytes.
 	pub fn from_bytes(prog_bytes: &[u8], wit_bytes: Option<&[u8]>) -> Result<Self, DecodeError> {
 		let prog_iter = BitIter::from(prog_bytes);
-		let wit_iter = wit_bytes.map(BitIter::from);
-		Ok(Self {
-			commit_prog: CommitNode::decode(prog_iter.clone())?,
-			redeem_prog: wit_iter.map(|iter| RedeemNode::decode(prog_iter, iter)).transpose()?,
-		})
+		match wit_bytes {
+			Some(wit_bytes) => {
+				let redeem_prog = RedeemNode::decode(prog_iter, BitIter::from(wit_bytes))?;
+				Ok(Self::from_redeem_prog(redeem_prog))
+			}
+			None => Ok(Self {
+				commit_prog: CommitNode::decode(prog_iter)?,
+				redeem_prog: None,
+			}),
+		}
+	}
+
+	/// Constructs a program from a decoded redeem-time program.
+	fn from_redeem_prog(redeem_prog: Arc<RedeemNode<J>>) -> Self {
+		Self {
+			commit_prog: redeem_prog.unfinalize().expect("types of a redeem node are final"),
+			redeem_prog: Some(redeem_prog),
+		}
 	}
 
 	/// The CMR of the program.
@@ -103,7 +137,8 @@ pub fn unspendable_internal_key() -> secp256k1::XOnlyPublicKey {
 	.expect("key should be valid")
 }
 
-fn script_ver(cmr: simplicity::Cmr) -> (elements::Script, elements::taproot::LeafVersion) {
+/// The tapleaf script and leaf version committing to a Simplicity CMR.
+pub fn script_ver(cmr: simplicity::Cmr) -> (elements::Script, elements::taproot::LeafVersion) {
 	let script = elements::script::Script::from(cmr.as_ref().to_vec());
 	(script, simplicity::leaf_version())
 }
@@ -114,9 +149,20 @@ pub fn taproot_spend_info(
 	internal_key: secp256k1::XOnlyPublicKey,
 	state: Option<[u8; 32]>,
 	cmr: simplicity::Cmr,
+) -> TaprootSpendInfo {
+	taproot_spend_info_with_leaf_version(internal_key, state, cmr, simplicity::leaf_version())
+}
+
+/// Like [`taproot_spend_info`], but with the leaf under `version` rather than
+/// Simplicity's leaf version, for experimenting with other deployments.
+pub fn taproot_spend_info_with_leaf_version(
+	internal_key: secp256k1::XOnlyPublicKey,
+	state: Option<[u8; 32]>,
+	cmr: simplicity::Cmr,
+	version: LeafVersion,
 ) -> TaprootSpendInfo {
 	let builder = TaprootBuilder::new();
-	let (script, version) = script_ver(cmr);
+	let (script, _) = script_ver(cmr);
 	let builder = if let Some(state) = state {
 		use elements::hashes::{sha256, Hash as _, HashEngine as _};
 		let tag = sha256::Hash::hash(b"TapData");
@@ -146,7 +192,18 @@ pub fn elements_address(
 	state: Option<[u8; 32]>,
 	params: &'static elements::AddressParams,
 ) -> elements::Address {
-	let info = taproot_spend_info(unspendable_internal_key(), state, cmr);
+	elements_address_with_leaf_version(cmr, state, params, simplicity::leaf_version())
+}
+
+/// Like [`elements_address`], but with the leaf under `version`.
+pub fn elements_address_with_leaf_version(
+	cmr: simplicity::Cmr,
+	state: Option<[u8; 32]>,
+	params: &'static elements::AddressParams,
+	version: LeafVersion,
+) -> elements::Address {
+	let info =
+		taproot_spend_info_with_leaf_version(unspendable_internal_key(), state, cmr, version);
 	let blinder = None;
 	elements::Address::p2tr(
 		secp256k1::SECP256K1,
@@ -198,4 +255,20 @@ mod tests {
 		assert_eq!(prog.amr(), None);
 		assert_eq!(prog.ihr(), None);
 	}
+
+	#[test]
+	fn commit_prog_from_redeem_prog() {
+		// The commitment-time program recovered from the redeem-time program is
+		// the one decoded directly.
+		let b64 = "zSQIS29W33fvVt9371bfd+9W33fvVt9371bfd+9W33fvVt93hgGA";
+		let with_witness = Program::<simplicity::jet::Core>::from_str(b64, Some("")).unwrap();
+		let without_witness = Program::<simplicity::jet::Core>::from_str(b64, None).unwrap();
+
+		assert_eq!(with_witness.commit_prog().cmr(), without_witness.commit_prog().cmr());
+		assert_eq!(
+			with_witness.commit_prog().to_string(),
+			without_witness.commit_prog().to_string()
+		);
+		assert_eq!(with_witness.commit_prog().arrow(), without_witness.commit_prog().arrow());
+	}
 }
@@ -1,25 +1,35 @@
 pub extern crate simplicity;
 
 pub mod actions;
+pub mod api;
 
 pub mod address;
 pub mod block;
 pub mod hal_simplicity;
+pub mod lwk;
+pub mod simplicityhl;
 pub mod tx;
 
 pub mod confidential;
+#[cfg(feature = "ffi")]
+pub mod ffi;
 
 pub use elements::bitcoin;
 pub use hal::HexBytes;
 
 #[cfg(feature = "daemon")]
 pub mod daemon;
+#[cfg(feature = "registry")]
+pub mod registry;
+
+use std::io;
 
 use elements::AddressParams;
 use serde::{Deserialize, Serialize};
 
 /// Known Elements networks.
 #[derive(Clone, Copy, PartialEq, Eq, Debug, Deserialize, Serialize)]
+#[cfg_attr(feature = "daemon", derive(schemars::JsonSchema))]
 #[serde(rename_all = "lowercase")]
 pub enum Network {
 	ElementsRegtest,
@@ -47,8 +57,76 @@ impl Network {
 			Network::LiquidTestnet => &AddressParams::LIQUID_TESTNET,
 		}
 	}
+
+	/// The hash of the network's genesis block, or `None` for regtest, whose
+	/// genesis block depends on how the chain was set up.
+	pub fn genesis_hash(self) -> Option<elements::BlockHash> {
+		let hash = match self {
+			Network::ElementsRegtest => return None,
+			Network::Liquid => "1466275836220db2944ca059a3a10ef6fd2ea684b0688d2c379296888a206003",
+			Network::LiquidTestnet => {
+				"a771da8e52ee6ad581ed1e9a99825e5b3b7992225534eaa2ae23244fe26ab1c1"
+			}
+		};
+		Some(hash.parse().expect("valid genesis hash"))
+	}
+
+	/// The ID of the network's policy asset, L-BTC, or `None` for regtest,
+	/// whose policy asset depends on how the chain was set up.
+	pub fn policy_asset(self) -> Option<elements::AssetId> {
+		let asset = match self {
+			Network::ElementsRegtest => return None,
+			Network::Liquid => "6f0279e9ed041c3d710a9f57d0c02928416460c4b722ae3457a11eec381c526d",
+			Network::LiquidTestnet => {
+				"144c654344aa716d6f3abcc1ca90e5641e4e2a7f633bc09fe3baf64585819a49"
+			}
+		};
+		Some(asset.parse().expect("valid asset ID"))
+	}
+
+	/// The Bitcoin network the chain is pegged to.
+	pub fn parent_network(self) -> bitcoin::Network {
+		match self {
+			Network::ElementsRegtest => bitcoin::Network::Regtest,
+			Network::Liquid => bitcoin::Network::Bitcoin,
+			Network::LiquidTestnet => bitcoin::Network::Testnet,
+		}
+	}
+
+	/// The scheme of payment URIs for the network's addresses. Regtest has
+	/// none of its own, so shares that of Liquid.
+	pub fn uri_scheme(self) -> &'static str {
+		match self {
+			Network::ElementsRegtest | Network::Liquid => "liquidnetwork",
+			Network::LiquidTestnet => "liquidtestnet",
+		}
+	}
+
+	pub fn as_str(self) -> &'static str {
+		match self {
+			Network::ElementsRegtest => "elementsregtest",
+			Network::Liquid => "liquid",
+			Network::LiquidTestnet => "liquidtestnet",
+		}
+	}
+}
+
+impl std::str::FromStr for Network {
+	type Err = UnknownNetworkError;
+
+	fn from_str(s: &str) -> Result<Self, Self::Err> {
+		[Network::ElementsRegtest, Network::Liquid, Network::LiquidTestnet]
+			.into_iter()
+			.find(|network| network.as_str() == s)
+			.ok_or_else(|| UnknownNetworkError(s.to_owned()))
+	}
 }
 
+/// A network name which is not one of [`Network`]'s.
+#[derive(Debug, Clone, PartialEq, Eq, thiserror::Error)]
+#[error("unknown network: {0}")]
+pub struct UnknownNetworkError(pub String);
+
 /// Get JSON-able objects that describe the type.
 pub trait GetInfo<T: ::serde::Serialize> {
 	/// Get a description of this object given the network of interest.
@@ -70,3 +148,80 @@ pub fn hex_or_base64(s: &str) -> Result<Vec<u8>, simplicity::base64::DecodeError
 		simplicity::base64::prelude::BASE64_STANDARD.decode(s)
 	}
 }
+
+/// A reader which decodes the hex read from an underlying reader, so that a
+/// large value such as a block can be deserialized as it is read, without
+/// holding both its hex and its bytes in memory. Whitespace is skipped.
+///
+/// Invalid hex is reported as an [`io::ErrorKind::InvalidData`] error wrapping
+/// a [`hex::FromHexError`].
+pub struct HexReader<R> {
+	inner: R,
+	/// The number of characters read so far, for the positions of errors.
+	position: usize,
+}
+
+impl<R: io::BufRead> HexReader<R> {
+	pub fn new(inner: R) -> Self {
+		HexReader {
+			inner,
+			position: 0,
+		}
+	}
+
+	/// Skip whitespace, returning the next character without consuming it.
+	fn peek(&mut self) -> io::Result<Option<u8>> {
+		loop {
+			let c = match self.inner.fill_buf()?.first() {
+				Some(&c) => c,
+				None => return Ok(None),
+			};
+			if !c.is_ascii_whitespace() {
+				return Ok(Some(c));
+			}
+			self.inner.consume(1);
+			self.position += 1;
+		}
+	}
+
+	/// Whether all of the hex has been read.
+	pub fn at_end(&mut self) -> io::Result<bool> {
+		Ok(self.peek()?.is_none())
+	}
+
+	fn next_digit(&mut self) -> io::Result<Option<u8>> {
+		let c = match self.peek()? {
+			Some(c) => c,
+			None => return Ok(None),
+		};
+		let index = self.position;
+		self.inner.consume(1);
+		self.position += 1;
+		match (c as char).to_digit(16) {
+			Some(digit) => Ok(Some(digit as u8)),
+			None => Err(io::Error::new(
+				io::ErrorKind::InvalidData,
+				hex::FromHexError::InvalidHexCharacter {
+					c: c as char,
+					index,
+				},
+			)),
+		}
+	}
+}
+
+impl<R: io::BufRead> io::Read for HexReader<R> {
+	fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+		for (n, byte) in buf.iter_mut().enumerate() {
+			let high = match self.next_digit()? {
+				Some(digit) => digit,
+				None => return Ok(n),
+			};
+			let low = self.next_digit()?.ok_or_else(|| {
+				io::Error::new(io::ErrorKind::InvalidData, hex::FromHexError::OddLength)
+			})?;
+			*byte = high << 4 | low;
+		}
+		Ok(buf.len())
+	}
+}
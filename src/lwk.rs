@@ -0,0 +1,27 @@
+//! JSON shapes of the Liquid Wallet Kit
+//!
+//! LWK's JSON-RPC server and CLI pass a PSET as `{"pset": "<base64>"}` and an
+//! address as `{"address": "...", "index": n}`. Writing artifacts in these
+//! shapes lets LWK-based wallets take them as they are. Addresses of
+//! Simplicity programs are not derived from a wallet, so they have no index.
+
+use serde::{Deserialize, Serialize};
+
+/// A PSET, as LWK passes it.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[cfg_attr(feature = "daemon", derive(schemars::JsonSchema))]
+pub struct Pset {
+	/// The PSET (base64).
+	pub pset: String,
+}
+
+/// An address, as LWK passes it.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[cfg_attr(feature = "daemon", derive(schemars::JsonSchema))]
+pub struct Address {
+	#[cfg_attr(feature = "daemon", schemars(with = "String"))]
+	pub address: elements::Address,
+	/// The index of the address in its wallet, if it is a wallet's.
+	#[serde(default, skip_serializing_if = "Option::is_none")]
+	pub index: Option<u32>,
+}
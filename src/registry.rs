@@ -0,0 +1,102 @@
+//! Liquid asset registries
+//!
+//! An asset registry, such as the one at <https://assets.blockstream.info>,
+//! serves the ticker, name and precision which issuers have registered for
+//! their assets, at `<url>/<asset ID>`.
+
+use std::collections::HashMap;
+use std::time::Duration;
+
+use elements::AssetId;
+use serde::Deserialize;
+use serde_json::Value;
+
+use crate::actions::error::{ActionError, Failure, Subsystem};
+use crate::confidential::AssetMetadata;
+
+/// How long to wait for the registry to answer.
+pub const TIMEOUT: Duration = Duration::from_secs(30);
+
+#[derive(Debug, thiserror::Error)]
+pub enum RegistryError {
+	#[error("asset registry request failed: {0}")]
+	Request(String),
+
+	#[error("unexpected asset registry response: {0}")]
+	Response(String),
+}
+
+impl ActionError for RegistryError {
+	fn subsystem(&self) -> Subsystem {
+		Subsystem::Backend
+	}
+
+	fn failure(&self) -> (Failure, Value) {
+		match self {
+			Self::Request(_) => Failure::Unavailable.only(),
+			Self::Response(_) => Failure::Invalid.only(),
+		}
+	}
+}
+
+/// An entry of the registry, of which only the metadata is kept.
+#[derive(Deserialize)]
+struct RegistryEntry {
+	ticker: Option<String>,
+	name: Option<String>,
+	precision: Option<u8>,
+	entity: Option<RegistryEntity>,
+}
+
+#[derive(Deserialize)]
+struct RegistryEntity {
+	domain: Option<String>,
+}
+
+/// An asset registry, which looks each asset up at most once.
+pub struct AssetRegistry {
+	url: String,
+	agent: ureq::Agent,
+	cache: HashMap<AssetId, Option<AssetMetadata>>,
+}
+
+impl AssetRegistry {
+	/// Use the registry at `url`.
+	pub fn new(url: &str) -> Self {
+		Self {
+			url: url.trim_end_matches('/').to_owned(),
+			agent: ureq::AgentBuilder::new().timeout(TIMEOUT).build(),
+			cache: HashMap::new(),
+		}
+	}
+
+	/// The metadata registered for `asset`, or `None` if it is not registered.
+	pub fn lookup(&mut self, asset: AssetId) -> Result<Option<AssetMetadata>, RegistryError> {
+		if let Some(metadata) = self.cache.get(&asset) {
+			return Ok(metadata.clone());
+		}
+
+		let response = match self.agent.get(&format!("{}/{}", self.url, asset)).call() {
+			Ok(response) => response,
+			Err(ureq::Error::Status(404, _)) => {
+				self.cache.insert(asset, None);
+				return Ok(None);
+			}
+			Err(ureq::Error::Status(status, response)) => {
+				let body = response.into_string().unwrap_or_default();
+				return Err(RegistryError::Request(format!("HTTP {}: {}", status, body.trim())));
+			}
+			Err(e) => return Err(RegistryError::Request(e.to_string())),
+		};
+		let entry: RegistryEntry =
+			response.into_json().map_err(|e| RegistryError::Response(e.to_string()))?;
+		let metadata = AssetMetadata {
+			ticker: entry.ticker,
+			name: entry.name,
+			precision: entry.precision,
+			domain: entry.entity.and_then(|entity| entity.domain),
+		};
+		self.cache.insert(asset, Some(metadata.clone()));
+		Ok(Some(metadata))
+	}
+}
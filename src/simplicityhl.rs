@@ -0,0 +1,176 @@
+//! SimplicityHL witness files
+//!
+//! The SimplicityHL toolchain writes the witness of a program as a `.wit`
+//! JSON file, which maps each witness name to its value and type:
+//!
+//! ```json
+//! { "SIG": { "value": "0x1234...", "type": "Signature" } }
+//! ```
+//!
+//! A compiled program does not keep the names of its witness nodes, so the
+//! values are attached to the witness nodes in the order that the file lists
+//! them, and each is checked against the type of its node.
+
+use std::sync::Arc;
+
+use serde::Deserialize;
+use serde_json::Map;
+use simplicity::dag::{DagLike as _, NoSharing};
+use simplicity::jet::Jet;
+use simplicity::node::{Inner, SimpleFinalizer};
+use simplicity::types::Final;
+use simplicity::{BitIter, CommitNode, FinalizeError, RedeemNode, Value};
+
+#[derive(Debug, thiserror::Error)]
+pub enum WitnessFileError {
+	#[error("invalid JSON: {0}")]
+	Json(serde_json::Error),
+
+	#[error("the program has {program} witness nodes, but the file gives {file} values")]
+	WitnessCount {
+		program: usize,
+		file: usize,
+	},
+
+	#[error("witness {name} has type {ty}, but only integer, byte string and boolean witnesses are supported")]
+	UnsupportedType {
+		name: String,
+		ty: String,
+	},
+
+	#[error("witness {name} has {bits} bits, but the program expects {expected}")]
+	WidthMismatch {
+		name: String,
+		bits: usize,
+		expected: usize,
+	},
+
+	#[error("invalid value for witness {name}: {value}")]
+	InvalidValue {
+		name: String,
+		value: String,
+	},
+
+	#[error("cannot attach the witness: {0}")]
+	Finalize(FinalizeError),
+}
+
+/// A witness value as written by the SimplicityHL toolchain.
+#[derive(Deserialize)]
+struct WitnessEntry {
+	value: serde_json::Value,
+	#[serde(rename = "type")]
+	ty: Option<String>,
+}
+
+/// Whether `witness` looks like a witness file rather than an encoded witness.
+pub fn is_witness_file(witness: &str) -> bool {
+	witness.trim_start().starts_with('{')
+}
+
+/// The bits of a value written in the SimplicityHL syntax for integers,
+/// byte strings (`0x...`), bit strings (`0b...`) and booleans, most
+/// significant first, with `width` bits.
+fn value_bits(value: &serde_json::Value, width: usize) -> Option<Vec<bool>> {
+	let s = match value {
+		serde_json::Value::String(s) => s.as_str(),
+		serde_json::Value::Bool(b) => return (width == 1).then(|| vec![*b]),
+		serde_json::Value::Number(n) => return int_bits(n.as_u64()?.into(), width),
+		_ => return None,
+	};
+
+	if let Some(hex) = s.strip_prefix("0x") {
+		let mut bits = Vec::with_capacity(hex.len() * 4);
+		for c in hex.chars() {
+			let digit = c.to_digit(16)?;
+			bits.extend((0..4).rev().map(|i| digit & (1 << i) != 0));
+		}
+		Some(bits)
+	} else if let Some(bin) = s.strip_prefix("0b") {
+		bin.chars()
+			.map(|c| match c {
+				'0' => Some(false),
+				'1' => Some(true),
+				_ => None,
+			})
+			.collect()
+	} else if width == 1 && (s == "true" || s == "false") {
+		Some(vec![s == "true"])
+	} else {
+		int_bits(s.parse().ok()?, width)
+	}
+}
+
+/// The `width` low bits of `n`, or `None` if it does not fit.
+fn int_bits(n: u128, width: usize) -> Option<Vec<bool>> {
+	if width < 128 && n >> width != 0 {
+		return None;
+	}
+	Some((0..width).rev().map(|i| i < 128 && n & (1 << i) != 0).collect())
+}
+
+/// The value of a witness node of word type `ty`.
+fn word_value(name: &str, entry: &WitnessEntry, ty: &Final) -> Result<Value, WitnessFileError> {
+	if ty.as_word().is_none() {
+		return Err(WitnessFileError::UnsupportedType {
+			name: name.to_owned(),
+			ty: entry.ty.clone().unwrap_or_else(|| ty.to_string()),
+		});
+	}
+	let width = ty.bit_width();
+	let bits = value_bits(&entry.value, width).ok_or_else(|| WitnessFileError::InvalidValue {
+		name: name.to_owned(),
+		value: entry.value.to_string(),
+	})?;
+	if bits.len() != width {
+		return Err(WitnessFileError::WidthMismatch {
+			name: name.to_owned(),
+			bits: bits.len(),
+			expected: width,
+		});
+	}
+
+	let mut bytes = vec![0u8; width.div_ceil(8)];
+	for (i, bit) in bits.into_iter().enumerate() {
+		if bit {
+			bytes[i / 8] |= 0x80 >> (i % 8);
+		}
+	}
+	Ok(Value::from_padded_bits(&mut BitIter::new(bytes.into_iter()), ty)
+		.expect("there are enough bits for the type"))
+}
+
+/// Attach the values of a SimplicityHL witness file to a program.
+pub fn attach_witness_file<J: Jet>(
+	program: &CommitNode<J>,
+	witness_file: &str,
+) -> Result<Arc<RedeemNode<J>>, WitnessFileError> {
+	let entries: Map<String, serde_json::Value> =
+		serde_json::from_str(witness_file).map_err(WitnessFileError::Json)?;
+
+	// Witness nodes are finalized in post-order, without sharing.
+	let types: Vec<Arc<Final>> = program
+		.post_order_iter::<NoSharing>()
+		.filter(|item| matches!(item.node.inner(), Inner::Witness(_)))
+		.map(|item| Arc::clone(&item.node.arrow().target))
+		.collect();
+	if types.len() != entries.len() {
+		return Err(WitnessFileError::WitnessCount {
+			program: types.len(),
+			file: entries.len(),
+		});
+	}
+
+	let values = entries
+		.into_iter()
+		.zip(&types)
+		.map(|((name, entry), ty)| {
+			let entry: WitnessEntry =
+				serde_json::from_value(entry).map_err(WitnessFileError::Json)?;
+			word_value(&name, &entry, ty)
+		})
+		.collect::<Result<Vec<_>, _>>()?;
+	program
+		.finalize(&mut SimpleFinalizer::new(values.into_iter()))
+		.map_err(WitnessFileError::Finalize)
+}
@@ -1,21 +1,28 @@
+use elements::confidential::{AssetBlindingFactor, ValueBlindingFactor};
 use elements::encode::serialize;
-use elements::secp256k1_zkp::{RangeProof, SurjectionProof};
+use elements::hashes::sha256;
+use elements::secp256k1_zkp::{RangeProof, SecretKey, SurjectionProof};
 use elements::{
-	bitcoin, confidential, Address, AssetIssuance, PeginData, PegoutData, Script, Transaction,
-	TxIn, TxInWitness, TxOut, TxOutWitness, Txid, Wtxid,
+	bitcoin, confidential, Address, AssetId, AssetIssuance, ContractHash, LockTime, PeginData,
+	PegoutData, Script, Sequence, Transaction, TxIn, TxInWitness, TxOut, TxOutWitness, Txid, Wtxid,
 };
 
 use serde::{Deserialize, Serialize};
 
 use crate::{GetInfo, HexBytes, Network};
 
-use crate::confidential::{ConfidentialAssetInfo, ConfidentialNonceInfo, ConfidentialValueInfo};
+use crate::confidential::{
+	AssetMetadata, ConfidentialAssetInfo, ConfidentialNonceInfo, ConfidentialValueInfo,
+};
 
 const BTCNET: elements::bitcoin::Network = elements::bitcoin::Network::Bitcoin;
 
 #[derive(Clone, PartialEq, Eq, Debug, Deserialize, Serialize)]
+#[cfg_attr(feature = "daemon", derive(schemars::JsonSchema))]
 pub struct AssetIssuanceInfo {
+	#[cfg_attr(feature = "daemon", schemars(with = "Option<String>"))]
 	pub asset_blinding_nonce: Option<HexBytes>,
+	#[cfg_attr(feature = "daemon", schemars(with = "Option<String>"))]
 	pub asset_entropy: Option<HexBytes>,
 	pub amount: Option<ConfidentialValueInfo>,
 	pub inflation_keys: Option<ConfidentialValueInfo>,
@@ -32,17 +39,135 @@ impl GetInfo<AssetIssuanceInfo> for AssetIssuance {
 	}
 }
 
+/// The asset and reissuance token of an issuance, from the entropy it
+/// derives them from.
+#[derive(Clone, PartialEq, Eq, Debug, Deserialize, Serialize)]
+#[cfg_attr(feature = "daemon", derive(schemars::JsonSchema))]
+pub struct AssetIdInfo {
+	#[serde(skip_serializing_if = "Option::is_none")]
+	pub prevout: Option<String>,
+	#[serde(skip_serializing_if = "Option::is_none")]
+	#[cfg_attr(feature = "daemon", schemars(with = "Option<String>"))]
+	pub contract_hash: Option<ContractHash>,
+	/// The asset entropy, in the byte order Elements Core displays it in.
+	#[cfg_attr(feature = "daemon", schemars(with = "String"))]
+	pub entropy: sha256::Midstate,
+	#[cfg_attr(feature = "daemon", schemars(with = "String"))]
+	pub asset_id: AssetId,
+	#[cfg_attr(feature = "daemon", schemars(with = "String"))]
+	pub reissuance_token: AssetId,
+	/// Whether `asset_id` is the asset ID given to check against.
+	#[serde(skip_serializing_if = "Option::is_none")]
+	pub matches: Option<bool>,
+}
+
+/// How an input spends its previous output, as far as `tx verify` can tell.
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Deserialize, Serialize)]
+#[cfg_attr(feature = "daemon", derive(schemars::JsonSchema))]
+#[serde(rename_all = "snake_case")]
+pub enum SpendType {
+	/// A taproot script path spend of a Simplicity leaf.
+	Simplicity,
+	/// A taproot script path spend of a leaf which is not Simplicity.
+	Tapscript,
+	TaprootKey,
+	P2wpkh,
+	Pegin,
+	/// Any other spend, which cannot be checked.
+	Other,
+}
+
+/// The result of checking the witness of one input.
+#[derive(Clone, PartialEq, Eq, Debug, Deserialize, Serialize)]
+#[cfg_attr(feature = "daemon", derive(schemars::JsonSchema))]
+pub struct InputVerifyInfo {
+	pub index: usize,
+	pub spend_type: SpendType,
+	/// Whether the input is validly spent, or `None` if it could not be checked.
+	pub valid: Option<bool>,
+	/// Why the input is not validly spent, or could not be checked.
+	#[serde(skip_serializing_if = "Option::is_none")]
+	pub reason: Option<String>,
+}
+
+#[derive(Clone, PartialEq, Eq, Debug, Deserialize, Serialize)]
+#[cfg_attr(feature = "daemon", derive(schemars::JsonSchema))]
+pub struct TxVerifyInfo {
+	#[cfg_attr(feature = "daemon", schemars(with = "String"))]
+	pub txid: Txid,
+	pub inputs: Vec<InputVerifyInfo>,
+	/// Whether every input was checked and is validly spent.
+	pub valid: bool,
+}
+
+/// The nonce which opens the rangeproof of a blinded output.
+#[derive(Clone, PartialEq, Eq, Debug, Deserialize, Serialize)]
+#[cfg_attr(feature = "daemon", derive(schemars::JsonSchema))]
+pub struct OutputNonceInfo {
+	#[cfg_attr(feature = "daemon", schemars(with = "String"))]
+	pub nonce: SecretKey,
+}
+
+/// The amount and asset of a blinded output, with the factors which blind them.
+#[derive(Clone, PartialEq, Eq, Debug, Deserialize, Serialize)]
+#[cfg_attr(feature = "daemon", derive(schemars::JsonSchema))]
+pub struct OutputSecretsInfo {
+	pub value: u64,
+	#[cfg_attr(feature = "daemon", schemars(with = "String"))]
+	pub asset: AssetId,
+	#[cfg_attr(feature = "daemon", schemars(with = "String"))]
+	pub value_blinding_factor: ValueBlindingFactor,
+	#[cfg_attr(feature = "daemon", schemars(with = "String"))]
+	pub asset_blinding_factor: AssetBlindingFactor,
+	/// Whether the output's commitments open to these secrets.
+	pub commitments_match: bool,
+}
+
 #[derive(Clone, PartialEq, Eq, Debug, Deserialize, Serialize)]
+#[cfg_attr(feature = "daemon", derive(schemars::JsonSchema))]
 pub struct PeginDataInfo {
 	pub outpoint: String,
 	pub value: u64,
 	pub asset: ConfidentialAssetInfo,
+	#[cfg_attr(feature = "daemon", schemars(with = "String"))]
 	pub genesis_hash: bitcoin::BlockHash,
+	#[cfg_attr(feature = "daemon", schemars(with = "String"))]
 	pub claim_script: HexBytes,
+	#[cfg_attr(feature = "daemon", schemars(with = "String"))]
 	pub mainchain_tx_hex: HexBytes,
+	#[cfg_attr(feature = "daemon", schemars(with = "Option<serde_json::Value>"))]
 	pub mainchain_tx: Option<hal::tx::TransactionInfo>,
+	#[cfg_attr(feature = "daemon", schemars(with = "String"))]
 	pub merkle_proof: HexBytes,
+	#[cfg_attr(feature = "daemon", schemars(with = "String"))]
 	pub referenced_block: bitcoin::BlockHash,
+	#[serde(default, skip_serializing_if = "Option::is_none")]
+	pub merkle_proof_check: Option<PeginProofInfo>,
+}
+
+/// The result of checking the merkle proof of a pegin.
+#[derive(Clone, PartialEq, Eq, Debug, Deserialize, Serialize)]
+#[cfg_attr(feature = "daemon", derive(schemars::JsonSchema))]
+pub struct PeginProofInfo {
+	/// Whether the proof commits to the mainchain transaction under the merkle
+	/// root of the block header it contains.
+	pub valid: bool,
+	/// Whether the block header in the proof is one of the mainchain headers
+	/// given to check against, if any were given.
+	#[serde(skip_serializing_if = "Option::is_none")]
+	pub header_matches: Option<bool>,
+}
+
+/// Whether the merkle proof of a pegin commits to its mainchain transaction.
+fn pegin_proof_valid(pegin: &PeginData) -> bool {
+	let (Ok(tx), Ok(proof)) = (pegin.parse_tx(), pegin.parse_merkle_proof()) else {
+		return false;
+	};
+	let txid = tx.compute_txid();
+	let (mut matches, mut indexes) = (vec![], vec![]);
+	proof.extract_matches(&mut matches, &mut indexes).is_ok()
+		&& pegin.outpoint.txid == txid
+		&& matches.contains(&txid)
 }
 
 impl<'tx> GetInfo<PeginDataInfo> for PeginData<'tx> {
@@ -62,17 +187,26 @@ impl<'tx> GetInfo<PeginDataInfo> for PeginData<'tx> {
 			},
 			merkle_proof: self.merkle_proof.into(),
 			referenced_block: self.referenced_block,
+			merkle_proof_check: Some(PeginProofInfo {
+				valid: pegin_proof_valid(self),
+				header_matches: None,
+			}),
 		}
 	}
 }
 
 #[derive(Clone, PartialEq, Eq, Debug, Deserialize, Serialize)]
+#[cfg_attr(feature = "daemon", derive(schemars::JsonSchema))]
 pub struct InputWitnessInfo {
+	#[cfg_attr(feature = "daemon", schemars(with = "Option<String>"))]
 	pub amount_rangeproof: Option<HexBytes>,
+	#[cfg_attr(feature = "daemon", schemars(with = "Option<String>"))]
 	pub inflation_keys_rangeproof: Option<HexBytes>,
 	#[serde(skip_serializing_if = "Option::is_none")]
+	#[cfg_attr(feature = "daemon", schemars(with = "Option<Vec<String>>"))]
 	pub script_witness: Option<Vec<HexBytes>>,
 	#[serde(skip_serializing_if = "Option::is_none")]
+	#[cfg_attr(feature = "daemon", schemars(with = "Option<Vec<String>>"))]
 	pub pegin_witness: Option<Vec<HexBytes>>,
 }
 
@@ -102,7 +236,9 @@ impl GetInfo<InputWitnessInfo> for TxInWitness {
 }
 
 #[derive(Clone, PartialEq, Eq, Debug, Deserialize, Serialize)]
+#[cfg_attr(feature = "daemon", derive(schemars::JsonSchema))]
 pub struct InputScriptInfo {
+	#[cfg_attr(feature = "daemon", schemars(with = "Option<String>"))]
 	pub hex: Option<HexBytes>,
 	pub asm: Option<String>,
 }
@@ -118,13 +254,54 @@ impl<'a> GetInfo<InputScriptInfo> for InputScript<'a> {
 	}
 }
 
+/// Interpretation of an input's nSequence field.
+///
+/// Relative locktimes are per BIP68, which is only enforced for transactions
+/// with version 2 or higher.
 #[derive(Clone, PartialEq, Eq, Debug, Deserialize, Serialize)]
+#[cfg_attr(feature = "daemon", derive(schemars::JsonSchema))]
+pub struct SequenceInfo {
+	/// Whether the BIP68 disable flag is unset, i.e. the field encodes a relative locktime.
+	pub relative_locktime_enabled: bool,
+	#[serde(skip_serializing_if = "Option::is_none")]
+	pub relative_locktime_blocks: Option<u16>,
+	#[serde(skip_serializing_if = "Option::is_none")]
+	pub relative_locktime_seconds: Option<u32>,
+	/// Whether this sequence number allows the transaction locktime to be enforced.
+	pub enables_absolute_locktime: bool,
+}
+
+impl GetInfo<SequenceInfo> for Sequence {
+	fn get_info(&self, _network: Network) -> SequenceInfo {
+		let value = (self.to_consensus_u32() & 0xffff) as u16;
+		SequenceInfo {
+			relative_locktime_enabled: self.is_relative_lock_time(),
+			relative_locktime_blocks: if self.is_height_locked() {
+				Some(value)
+			} else {
+				None
+			},
+			relative_locktime_seconds: if self.is_time_locked() {
+				Some(u32::from(value) * 512)
+			} else {
+				None
+			},
+			enables_absolute_locktime: self.enables_absolute_lock_time(),
+		}
+	}
+}
+
+#[derive(Clone, PartialEq, Eq, Debug, Deserialize, Serialize)]
+#[cfg_attr(feature = "daemon", derive(schemars::JsonSchema))]
 pub struct InputInfo {
 	pub prevout: Option<String>,
+	#[cfg_attr(feature = "daemon", schemars(with = "Option<String>"))]
 	pub txid: Option<Txid>,
 	pub vout: Option<u32>,
 	pub script_sig: Option<InputScriptInfo>,
 	pub sequence: Option<u32>,
+	#[serde(skip_serializing_if = "Option::is_none")]
+	pub sequence_info: Option<SequenceInfo>,
 
 	pub is_pegin: Option<bool>,
 	pub has_issuance: Option<bool>,
@@ -145,6 +322,7 @@ impl GetInfo<InputInfo> for TxIn {
 			txid: Some(self.previous_output.txid),
 			vout: Some(self.previous_output.vout),
 			sequence: Some(self.sequence.to_consensus_u32()),
+			sequence_info: Some(self.sequence.get_info(network)),
 			script_sig: Some(GetInfo::get_info(&InputScript(&self.script_sig), network)),
 
 			is_pegin: Some(self.is_pegin),
@@ -165,11 +343,15 @@ impl GetInfo<InputInfo> for TxIn {
 }
 
 #[derive(Clone, PartialEq, Eq, Debug, Deserialize, Serialize)]
+#[cfg_attr(feature = "daemon", derive(schemars::JsonSchema))]
 pub struct PegoutDataInfo {
 	pub value: u64,
 	pub asset: ConfidentialAssetInfo,
+	#[cfg_attr(feature = "daemon", schemars(with = "String"))]
 	pub genesis_hash: bitcoin::BlockHash,
+	#[cfg_attr(feature = "daemon", schemars(with = "OutputScriptInfo"))]
 	pub script_pub_key: hal::tx::OutputScriptInfo,
+	#[cfg_attr(feature = "daemon", schemars(with = "Vec<String>"))]
 	pub extra_data: Vec<HexBytes>,
 }
 
@@ -189,8 +371,11 @@ impl<'tx> GetInfo<PegoutDataInfo> for PegoutData<'tx> {
 }
 
 #[derive(Clone, PartialEq, Eq, Debug, Deserialize, Serialize)]
+#[cfg_attr(feature = "daemon", derive(schemars::JsonSchema))]
 pub struct OutputWitnessInfo {
+	#[cfg_attr(feature = "daemon", schemars(with = "Option<String>"))]
 	pub surjection_proof: Option<HexBytes>,
+	#[cfg_attr(feature = "daemon", schemars(with = "Option<String>"))]
 	pub rangeproof: Option<HexBytes>,
 }
 
@@ -207,12 +392,15 @@ impl GetInfo<OutputWitnessInfo> for TxOutWitness {
 }
 
 #[derive(Clone, PartialEq, Eq, Debug, Deserialize, Serialize)]
+#[cfg_attr(feature = "daemon", derive(schemars::JsonSchema))]
 pub struct OutputScriptInfo {
+	#[cfg_attr(feature = "daemon", schemars(with = "Option<String>"))]
 	pub hex: Option<HexBytes>,
 	pub asm: Option<String>,
 	#[serde(skip_serializing_if = "Option::is_none", rename = "type")]
 	pub type_: Option<String>,
 	#[serde(skip_serializing_if = "Option::is_none")]
+	#[cfg_attr(feature = "daemon", schemars(with = "Option<String>"))]
 	pub address: Option<Address>,
 }
 
@@ -247,6 +435,7 @@ impl<'a> GetInfo<OutputScriptInfo> for OutputScript<'a> {
 }
 
 #[derive(Clone, PartialEq, Eq, Debug, Deserialize, Serialize)]
+#[cfg_attr(feature = "daemon", derive(schemars::JsonSchema))]
 pub struct OutputInfo {
 	pub script_pub_key: Option<OutputScriptInfo>,
 
@@ -284,15 +473,55 @@ impl GetInfo<OutputInfo> for TxOut {
 }
 
 #[derive(Clone, PartialEq, Eq, Debug, Deserialize, Serialize)]
+#[cfg_attr(feature = "daemon", derive(schemars::JsonSchema))]
+#[serde(rename_all = "lowercase")]
+pub enum LockTimeType {
+	Height,
+	Timestamp,
+}
+
+/// Interpretation of a transaction's nLockTime field.
+#[derive(Clone, PartialEq, Eq, Debug, Deserialize, Serialize)]
+#[cfg_attr(feature = "daemon", derive(schemars::JsonSchema))]
+pub struct LockTimeInfo {
+	#[serde(rename = "type")]
+	pub type_: LockTimeType,
+	pub value: u32,
+	/// Whether any input has a non-final sequence number, without which the
+	/// locktime is not enforced.
+	pub enforced: bool,
+}
+
+impl GetInfo<LockTimeInfo> for Transaction {
+	fn get_info(&self, _network: Network) -> LockTimeInfo {
+		LockTimeInfo {
+			type_: match self.lock_time {
+				LockTime::Blocks(..) => LockTimeType::Height,
+				LockTime::Seconds(..) => LockTimeType::Timestamp,
+			},
+			value: self.lock_time.to_consensus_u32(),
+			enforced: self.input.iter().any(|i| i.sequence.enables_absolute_lock_time()),
+		}
+	}
+}
+
+#[derive(Clone, PartialEq, Eq, Debug, Deserialize, Serialize)]
+#[cfg_attr(feature = "daemon", derive(schemars::JsonSchema))]
 pub struct TransactionInfo {
+	#[cfg_attr(feature = "daemon", schemars(with = "Option<String>"))]
 	pub txid: Option<Txid>,
+	#[cfg_attr(feature = "daemon", schemars(with = "Option<String>"))]
 	pub wtxid: Option<Wtxid>,
+	#[cfg_attr(feature = "daemon", schemars(with = "Option<String>"))]
 	pub hash: Option<Wtxid>,
 	pub size: Option<usize>,
 	pub weight: Option<usize>,
 	pub vsize: Option<usize>,
 	pub version: Option<u32>,
+	#[cfg_attr(feature = "daemon", schemars(with = "Option<crate::daemon::schema::LockTime>"))]
 	pub locktime: Option<elements::LockTime>,
+	#[serde(skip_serializing_if = "Option::is_none")]
+	pub locktime_info: Option<LockTimeInfo>,
 	pub inputs: Option<Vec<InputInfo>>,
 	pub outputs: Option<Vec<OutputInfo>>,
 }
@@ -305,6 +534,7 @@ impl GetInfo<TransactionInfo> for Transaction {
 			hash: Some(self.wtxid()),
 			version: Some(self.version),
 			locktime: Some(self.lock_time),
+			locktime_info: Some(GetInfo::<LockTimeInfo>::get_info(self, network)),
 			size: Some(serialize(self).len()),
 			weight: Some(self.weight()),
 			vsize: Some(self.weight() / 4),
@@ -313,3 +543,27 @@ impl GetInfo<TransactionInfo> for Transaction {
 		}
 	}
 }
+
+impl TransactionInfo {
+	/// Look up every explicit asset of the transaction's outputs and peg-ins
+	/// with `lookup`, such as in an asset registry.
+	pub fn resolve_assets<E>(
+		&mut self,
+		mut lookup: impl FnMut(AssetId) -> Result<Option<AssetMetadata>, E>,
+	) -> Result<(), E> {
+		for input in self.inputs.iter_mut().flatten() {
+			if let Some(ref mut pegin) = input.pegin_data {
+				pegin.asset.resolve(&mut lookup)?;
+			}
+		}
+		for output in self.outputs.iter_mut().flatten() {
+			if let Some(ref mut asset) = output.asset {
+				asset.resolve(&mut lookup)?;
+			}
+			if let Some(ref mut pegout) = output.pegout_data {
+				pegout.asset.resolve(&mut lookup)?;
+			}
+		}
+		Ok(())
+	}
+}
@@ -81,30 +81,63 @@ fn assert_cmd(args: &[&str], expected_stdout: impl AsRef<str>, expected_stderr:
 
 #[test]
 fn cli_help() {
-	let expected_help = "\
+	// The commands which talk to the daemon are only built with the daemon.
+	let (batch, daemon, replay, schema) = match cfg!(feature = "daemon") {
+		true => (
+			"    batch          make the daemon calls in a file in order, printing one JSON result per line\n",
+			"    daemon         start, stop and query hal-simplicity-daemon\n",
+			"    replay         make the calls recorded by a daemon again, reporting those whose outcome has changed\n",
+			"    schema         print the JSON Schemas of the output formats\n",
+		),
+		false => ("", "", "", ""),
+	};
+	let miniscript = match cfg!(feature = "miniscript") {
+		true => "    miniscript     analyze miniscript policies, scripts and descriptors\n",
+		false => "",
+	};
+	let repl = match cfg!(feature = "repl") {
+		true => "    repl           run commands interactively, remembering the last PSET, program and transaction\n",
+		false => "",
+	};
+	let expected_help = format!(
+		"\
 hal-simplicity 0.2.0
 hal-simplicity -- a Simplicity-enabled fork of hal
 
 USAGE:
-    hal-simplicity [FLAGS] <SUBCOMMAND>
+    hal-simplicity [FLAGS] [OPTIONS] <SUBCOMMAND>
 
 FLAGS:
-    -h, --help       Prints help information
-    -V, --version    Prints version information
-    -v, --verbose    print verbose logging output to stderr
+    -h, --help           Prints help information
+        --json-errors    print errors to stderr as JSON objects with a stable error code
+        --offline        refuse to contact any daemon or backend, only computing locally
+        --plain          print pretty output without colors
+        --raw            print only the main result, such as a PSET or transaction, without JSON
+    -V, --version        Prints version information
+    -v, --verbose        print verbose logging output to stderr
+
+OPTIONS:
+        --format <FORMAT>      output format: json (default), yaml, pretty for a terminal, or lwk for LWK wallets
+        --network <NETWORK>    network to work on: elementsregtest (default), liquid or liquidtestnet
 
 SUBCOMMANDS:
-    address       work with addresses
-    block         manipulate blocks
-    help          Prints this message or the help of the given subcommand(s)
-    keypair       manipulate private and public keys
-    simplicity    manipulate Simplicity programs
-    tx            manipulate transactions
-";
-	assert_cmd(&[], "", expected_help); // note on stdout, not stderr
-	assert_cmd(&["help"], expected_help, "");
-	assert_cmd(&["--help"], expected_help, "");
-	assert_cmd(&["-h"], expected_help, "");
+    address        work with addresses
+{}    block          manipulate blocks
+    completions    print a shell completion script
+{}    help           Prints this message or the help of the given subcommand(s)
+    keypair        manipulate private and public keys
+{}    psbt           inspect mainchain Bitcoin PSBTs, such as those funding peg-ins
+{}{}{}    simplicity     manipulate Simplicity programs
+    tx             manipulate transactions
+
+Any positional argument may be given as @<path> to read it from a file, or as - to read it from stdin.
+",
+		batch, daemon, miniscript, repl, replay, schema
+	);
+	assert_cmd(&[], "", &expected_help); // note on stdout, not stderr
+	assert_cmd(&["help"], &expected_help, "");
+	assert_cmd(&["--help"], &expected_help, "");
+	assert_cmd(&["-h"], &expected_help, "");
 }
 
 #[test]
@@ -116,7 +149,7 @@ fn cli_bad_flag() {
 error: Found argument '-?' which wasn't expected, or isn't valid in this context
 
 USAGE:
-    hal-simplicity [FLAGS] <SUBCOMMAND>
+    hal-simplicity [FLAGS] [OPTIONS] <SUBCOMMAND>
 
 For more information try --help
 ",
@@ -130,15 +163,25 @@ hal-simplicity-address 0.2.0
 work with addresses
 
 USAGE:
-    hal-simplicity address [FLAGS] <SUBCOMMAND>
+    hal-simplicity address [FLAGS] [OPTIONS] <SUBCOMMAND>
 
 FLAGS:
-    -h, --help       Prints help information
-    -v, --verbose    print verbose logging output to stderr
+    -h, --help           Prints help information
+        --json-errors    print errors to stderr as JSON objects with a stable error code
+        --offline        refuse to contact any daemon or backend, only computing locally
+        --plain          print pretty output without colors
+        --raw            print only the main result, such as a PSET or transaction, without JSON
+    -v, --verbose        print verbose logging output to stderr
+
+OPTIONS:
+        --format <FORMAT>      output format: json (default), yaml, pretty for a terminal, or lwk for LWK wallets
+        --network <NETWORK>    network to work on: elementsregtest (default), liquid or liquidtestnet
 
 SUBCOMMANDS:
     create     create addresses
     inspect    inspect addresses
+    uri        work with liquidnetwork: payment URIs
+    vanity     grind keys for a P2WPKH address with a chosen prefix
 ";
 	assert_cmd(&["address"], "", expected_help);
 	assert_cmd(&["address", "-h"], expected_help, "");
@@ -158,12 +201,18 @@ USAGE:
 FLAGS:
     -r, --elementsregtest    run in elementsregtest mode
     -h, --help               Prints help information
+        --json-errors        print errors to stderr as JSON objects with a stable error code
         --liquid             run in liquid mode
+        --offline            refuse to contact any daemon or backend, only computing locally
+        --plain              print pretty output without colors
+        --raw                print only the main result, such as a PSET or transaction, without JSON
     -v, --verbose            print verbose logging output to stderr
     -y, --yaml               print output in YAML instead of JSON
 
 OPTIONS:
         --blinder <blinder>    a blinding pubkey in hex
+        --format <FORMAT>      output format: json (default), yaml, pretty for a terminal, or lwk for LWK wallets
+        --network <NETWORK>    network to work on: elementsregtest (default), liquid or liquidtestnet
         --pubkey <pubkey>      a public key in hex
         --script <script>      a script in hex
 ";
@@ -172,8 +221,8 @@ OPTIONS:
 	// length-prefixed, which is a little surprising and should be documented
 	assert_cmd(
 		&["address", "create"],
-		"Execution failed: can't create addresses without a pubkey\n",
 		"",
+		"Execution failed: can't create addresses without a pubkey\n",
 	);
 	assert_cmd(&["address", "create", "-h"], expected_help, "");
 	assert_cmd(&["address", "create", "--help"], expected_help, "");
@@ -191,11 +240,10 @@ USAGE:
 For more information try --help
 ",
 	);
-	// FIXME stdout instead of stderr
 	assert_cmd(
 		&["address", "create", "--pubkey", ""],
-		"Execution failed: invalid pubkey: pubkey string should be 66 or 130 digits long, got: 0\n",
 		"",
+		"Execution failed: invalid pubkey: pubkey string should be 66 or 130 digits long, got: 0\n",
 	);
 	// x-only keys not supported
 	assert_cmd(
@@ -205,8 +253,8 @@ For more information try --help
 			"--pubkey",
 			"abababababababababababababababababababababababababababababababab",
 		],
-		"Execution failed: invalid pubkey: pubkey string should be 66 or 130 digits long, got: 64\n",
 		"",
+		"Execution failed: invalid pubkey: pubkey string should be 66 or 130 digits long, got: 64\n",
 	);
 	assert_cmd(
 		&[
@@ -215,8 +263,8 @@ For more information try --help
 			"--pubkey",
 			"020000000000000000000000000000000000000000000000000000000000000000",
 		],
-		"Execution failed: invalid pubkey: string error\n",
 		"",
+		"Execution failed: invalid pubkey: string error\n",
 	);
 	// uncompressed keys ok (though FIXME we should not produce p2wpkh or p2shwpkh addresses which are unspendable!!)
 	assert_cmd(
@@ -231,8 +279,8 @@ For more information try --help
 	// hybrid keys are not
 	assert_cmd(
 		&["address", "create", "--pubkey", "0700000000000000000000003b78ce563f89a0ed9414f5aa28ad0d96d6795f9c633f3979bf72ae8202983dc989aec7f2ff2ed91bdd69ce02fc0700ca100e59ddf3"],
-		"Execution failed: invalid pubkey: string error\n",
 		"",
+		"Execution failed: invalid pubkey: string error\n",
 	);
 	// compressed keys are ok, and the output is NOT the same as for uncompressed keys
 	assert_cmd(
@@ -258,19 +306,19 @@ For more information try --help
 			"--blinder",
 			"0200000000000000000000003b78ce563f89a0ed9414f5aa28ad0d96d6795f9c63",
 		],
-		"Execution failed: can't create addresses without a pubkey\n",
 		"",
+		"Execution failed: can't create addresses without a pubkey\n",
 	);
 	// Invalid blinders all get the same generic message, and we don't even check for a pubkey
 	assert_cmd(
 		&["address", "create", "--blinder", ""],
-		"Execution failed: invalid blinder: malformed public key\n",
 		"",
+		"Execution failed: invalid blinder: malformed public key\n",
 	);
 	assert_cmd(
 		&["address", "create", "--blinder", "02abcd"],
-		"Execution failed: invalid blinder: malformed public key\n",
 		"",
+		"Execution failed: invalid blinder: malformed public key\n",
 	);
 	assert_cmd(
 		&[
@@ -279,8 +327,8 @@ For more information try --help
 			"--blinder",
 			"abababababababababababababababababababababababababababababababab",
 		],
-		"Execution failed: invalid blinder: malformed public key\n",
 		"",
+		"Execution failed: invalid blinder: malformed public key\n",
 	);
 	assert_cmd(
 		&[
@@ -289,8 +337,8 @@ For more information try --help
 			"--blinder",
 			"020000000000000000000000000000000000000000000000000000000000000000",
 		],
-		"Execution failed: invalid blinder: malformed public key\n",
 		"",
+		"Execution failed: invalid blinder: malformed public key\n",
 	);
 	// good pubkey, blinder
 	let good_key_output = r#"{
@@ -591,12 +639,24 @@ hal-simplicity-address-inspect 0.2.0
 inspect addresses
 
 USAGE:
-    hal-simplicity address inspect [FLAGS] <address>
+    hal-simplicity address inspect [FLAGS] [OPTIONS] <address>
 
 FLAGS:
-    -h, --help       Prints help information
-    -v, --verbose    print verbose logging output to stderr
-    -y, --yaml       print output in YAML instead of JSON
+    -h, --help           Prints help information
+        --json-errors    print errors to stderr as JSON objects with a stable error code
+        --offline        refuse to contact any daemon or backend, only computing locally
+        --plain          print pretty output without colors
+        --raw            print only the main result, such as a PSET or transaction, without JSON
+    -v, --verbose        print verbose logging output to stderr
+    -y, --yaml           print output in YAML instead of JSON
+
+OPTIONS:
+    -c, --cmr <cmr>                      check whether the address commits to the Simplicity program with this CMR (hex)
+        --format <FORMAT>                output format: json (default), yaml, pretty for a terminal, or lwk for LWK
+                                         wallets
+    -p, --internal-key <internal-key>    internal public key of the program (hex) (default: unspendable key)
+        --network <NETWORK>              network to work on: elementsregtest (default), liquid or liquidtestnet
+    -s, --state <state>                  32-byte state commitment alongside the program (hex)
 
 ARGS:
     <address>    the address
@@ -611,7 +671,7 @@ ARGS:
     <address>
 
 USAGE:
-    hal-simplicity address inspect [FLAGS] <address>
+    hal-simplicity address inspect [FLAGS] [OPTIONS] <address>
 
 For more information try --help
 ",
@@ -620,23 +680,22 @@ For more information try --help
 	assert_cmd(&["address", "inspect", "--help"], expected_help, "");
 	assert_cmd(&["address", "inspect", "--help", "xyz"], expected_help, "");
 
-	// FIXME stdout instead of stderr
 	assert_cmd(
 		&["address", "inspect", ""],
-		"Execution failed: invalid address format: base58 error: too short\n",
 		"",
+		"Execution failed: invalid address format: base58 error: too short\n",
 	);
 	// FIXME this error is absolutely terrible
 	assert_cmd(
 		&["address", "inspect", "bc1q7z3dshje7e4tftag5c3w7e85pr00r6cq34khh8"],
-		"Execution failed: invalid address format: base58 error: decode\n",
 		"",
+		"Execution failed: invalid address format: base58 error: decode\n",
 	);
 	// FIXME this one is possibly even worse
 	assert_cmd(
 		&["address", "inspect", "1Au8w4fejHaJBbrZCMrfg6v2hwJNr3go1N"],
-		"Execution failed: invalid address format: was unable to parse the address: 1Au8w4fejHaJBbrZCMrfg6v2hwJNr3go1N\n",
 		"",
+		"Execution failed: invalid address format: was unable to parse the address: 1Au8w4fejHaJBbrZCMrfg6v2hwJNr3go1N\n",
 	);
 	// liquid addresses ok
 	assert_cmd(
@@ -742,7 +801,7 @@ pubkey_hash: 6c95622b280be97792ec1b3505700f9e674cf509"#,
 error: Found argument '' which wasn't expected, or isn't valid in this context
 
 USAGE:
-    hal-simplicity address inspect [FLAGS] <address>
+    hal-simplicity address inspect [FLAGS] [OPTIONS] <address>
 
 For more information try --help
 ",
@@ -750,26 +809,229 @@ For more information try --help
 }
 
 #[test]
-fn cli_block() {
+fn cli_address_inspect_simplicity() {
+	let address = "tex1pyn9ll8g6djft9smgjmythqlhmuexq6lpfhsurujjgdxj58d2uc9qm93ecp";
+	let cmr = "40579de3090ff3e92326b12bf5808c961683c641c25d7325d608b5ec4b6cdd7f";
+	assert_cmd(
+		&["address", "inspect", address, "--cmr", cmr],
+		r#"{
+  "network": "liquidtestnet",
+  "type": "unknown-witness-program-version",
+  "script_pub_key": {
+    "hex": "512024cbff9d1a6c92b2c36896c8bb83f7df32606be14de1c1f252434d2a1daae60a",
+    "asm": "OP_PUSHNUM_1 OP_PUSHBYTES_32 24cbff9d1a6c92b2c36896c8bb83f7df32606be14de1c1f252434d2a1daae60a"
+  },
+  "witness_program_version": 1,
+  "simplicity": {
+    "cmr": "40579de3090ff3e92326b12bf5808c961683c641c25d7325d608b5ec4b6cdd7f",
+    "internal_key": "50929b74c1a04954b78b4b6035e97a5e078a5a0f28ec96d547bfee9ace803ac0",
+    "merkle_root": "a1642c0655ad712f68a4e49c8ca4e485e252167e24901c0b03bc30fa41bb196a",
+    "output_key": "24cbff9d1a6c92b2c36896c8bb83f7df32606be14de1c1f252434d2a1daae60a",
+    "matches": true
+  }
+}"#,
+		"",
+	);
+	// A state changes the merkle root, so the address no longer matches
+	assert_cmd(
+		&[
+			"address",
+			"inspect",
+			address,
+			"--cmr",
+			cmr,
+			"--state",
+			"0000000000000000000000000000000000000000000000000000000000000001",
+		],
+		r#"{
+  "network": "liquidtestnet",
+  "type": "unknown-witness-program-version",
+  "script_pub_key": {
+    "hex": "512024cbff9d1a6c92b2c36896c8bb83f7df32606be14de1c1f252434d2a1daae60a",
+    "asm": "OP_PUSHNUM_1 OP_PUSHBYTES_32 24cbff9d1a6c92b2c36896c8bb83f7df32606be14de1c1f252434d2a1daae60a"
+  },
+  "witness_program_version": 1,
+  "simplicity": {
+    "cmr": "40579de3090ff3e92326b12bf5808c961683c641c25d7325d608b5ec4b6cdd7f",
+    "internal_key": "50929b74c1a04954b78b4b6035e97a5e078a5a0f28ec96d547bfee9ace803ac0",
+    "state": "0000000000000000000000000000000000000000000000000000000000000001",
+    "merkle_root": "c713e0d1c7c3aac57473f10b5b62dc5d708ccc23952c21a163fd83babb476c0c",
+    "output_key": "6590e18b3d2ce395bbbdd2616bd5c39b384d585edf894a8953ed55ee177457ec",
+    "matches": false
+  }
+}"#,
+		"",
+	);
+	assert_cmd(
+		&["address", "inspect", address, "--state", "00"],
+		"",
+		"Execution failed: an internal key or state must come with the CMR of the program they belong to\n",
+	);
+}
+
+#[test]
+fn cli_address_uri() {
+	let cmr = "7fd424f70498ef2fb6dd05ffbb7368dc796e6c47f24404e0b1ff138cfce89a7a";
+	let asset = "6f0279e9ed041c3d710a9f57d0c02928416460c4b722ae3457a11eec381c526d";
+	let address = "ex1p7zpxr4sueukel0mdmxwpua2p52wqdsjglrzeklqfjj9tmk0ywzqstuzj5r";
+	let uri =
+		format!("liquidnetwork:{}?amount=0.001&assetid={}&label=Alice%20%26%20Bob", address, asset);
+	let expected = serde_json::json!({
+		"uri": uri,
+		"network": "liquid",
+		"address": address,
+		"amount": 0.001,
+		"assetid": asset,
+		"label": "Alice & Bob",
+	});
+
+	// A Simplicity program is paid at its address.
+	let encoded = assert_deserialize_cmd(
+		&[
+			"address",
+			"uri",
+			"encode",
+			"--liquid",
+			"-c",
+			cmr,
+			"--amount",
+			"0.00100000",
+			"--asset",
+			asset,
+			"--label",
+			"Alice & Bob",
+		],
+		|s| serde_json::from_slice::<serde_json::Value>(s),
+	);
+	assert_eq!(encoded, expected);
+	let decoded = assert_deserialize_cmd(&["address", "uri", "decode", &uri], |s| {
+		serde_json::from_slice::<serde_json::Value>(s)
+	});
+	assert_eq!(decoded, expected);
+	assert_cmd(
+		&["address", "uri", "encode", "--raw", address],
+		format!("liquidnetwork:{}", address),
+		"",
+	);
+
+	assert_cmd(
+		&["address", "uri", "encode", "--amount", "1", address],
+		"",
+		"Execution failed: an amount must come with the asset ID it is an amount of\n",
+	);
+	assert_cmd(
+		&["address", "uri", "decode", &format!("liquidtestnet:{}", address)],
+		"",
+		format!(
+			"Execution failed: a liquidtestnet: URI cannot pay the liquid address {}\n",
+			address
+		),
+	);
+	assert_cmd(
+		&["address", "uri", "decode", &format!("{}&req-expires=1", uri)],
+		"",
+		"Execution failed: unsupported required payment URI parameter req-expires\n",
+	);
+}
+#[test]
+fn cli_address_vanity() {
 	let expected_help = "\
+hal-simplicity-address-vanity 0.2.0
+grind keys for a P2WPKH address with a chosen prefix
+
+USAGE:
+    hal-simplicity address vanity [FLAGS] [OPTIONS] --prefix <prefix>
+
+FLAGS:
+    -r, --elementsregtest    run in elementsregtest mode
+    -h, --help               Prints help information
+        --json-errors        print errors to stderr as JSON objects with a stable error code
+        --liquid             run in liquid mode
+        --offline            refuse to contact any daemon or backend, only computing locally
+        --plain              print pretty output without colors
+        --raw                print only the main result, such as a PSET or transaction, without JSON
+    -v, --verbose            print verbose logging output to stderr
+    -y, --yaml               print output in YAML instead of JSON
+
+OPTIONS:
+        --format <FORMAT>      output format: json (default), yaml, pretty for a terminal, or lwk for LWK wallets
+        --network <NETWORK>    network to work on: elementsregtest (default), liquid or liquidtestnet
+        --prefix <prefix>      the characters to follow the witness version, as in ex1q<prefix>
+        --threads <threads>    the number of threads to search on (default: one per core)
+";
+	assert_cmd(&["address", "vanity", "-h"], expected_help, "");
+	assert_cmd(&["address", "vanity", "--help"], expected_help, "");
+
+	let vanity = assert_deserialize_cmd(
+		&["address", "vanity", "--liquid", "--prefix", "Q", "--threads", "2"],
+		|s| serde_json::from_slice::<serde_json::Value>(s),
+	);
+	let address = vanity["address"].as_str().unwrap();
+	assert!(address.starts_with("ex1qq"), "{}", address);
+	assert!(vanity["attempts"].as_u64().unwrap() >= 1);
+	// The key spends the address.
+	let pubkey = vanity["pubkey"].as_str().unwrap();
+	assert_cmd(&["address", "create", "--liquid", "--raw", "--pubkey", pubkey], address, "");
+
+	assert_cmd(
+		&["address", "vanity", "--prefix", "demo"],
+		"",
+		"Execution failed: vanity prefix demo is not bech32: it may only use the characters \
+		qpzry9x8gf2tvdw0s3jn54khce6mua7l\n",
+	);
+	// Longer than the data part of any P2WPKH address, so it would never be found.
+	let prefix = "q".repeat(39);
+	assert_cmd(
+		&["address", "vanity", "--prefix", &prefix],
+		"",
+		format!(
+			"Execution failed: vanity prefix {} is too long: a P2WPKH address has only 38 \
+			characters after its witness version\n",
+			prefix
+		),
+	);
+}
+
+#[test]
+fn cli_block() {
+	// fetch goes through the daemon, so is only built with it.
+	let fetch = match cfg!(feature = "daemon") {
+		true => "    fetch               fetch a raw block from the daemon's chain backend\n",
+		false => "",
+	};
+	let expected_help = format!(
+		"\
 hal-simplicity-block 0.2.0
 manipulate blocks
 
 USAGE:
-    hal-simplicity block [FLAGS] <SUBCOMMAND>
+    hal-simplicity block [FLAGS] [OPTIONS] <SUBCOMMAND>
 
 FLAGS:
-    -h, --help       Prints help information
-    -v, --verbose    print verbose logging output to stderr
+    -h, --help           Prints help information
+        --json-errors    print errors to stderr as JSON objects with a stable error code
+        --offline        refuse to contact any daemon or backend, only computing locally
+        --plain          print pretty output without colors
+        --raw            print only the main result, such as a PSET or transaction, without JSON
+    -v, --verbose        print verbose logging output to stderr
+
+OPTIONS:
+        --format <FORMAT>      output format: json (default), yaml, pretty for a terminal, or lwk for LWK wallets
+        --network <NETWORK>    network to work on: elementsregtest (default), liquid or liquidtestnet
 
 SUBCOMMANDS:
-    create    create a raw block from JSON
-    decode    decode a raw block to JSON
-";
-	assert_cmd(&["block"], "", expected_help);
-	assert_cmd(&["block", "-h"], expected_help, "");
-	assert_cmd(&["block", "--help"], expected_help, "");
-	assert_cmd(&["block", "--help", "xyz"], expected_help, "");
+    create              create a raw block from JSON
+    decode              decode a raw block to JSON
+{}    stats               summarize the Simplicity spends in a raw block
+    verify-chain        check that a sequence of raw block headers forms a chain
+    verify-signblock    check the signatures on a raw block or block header
+",
+		fetch
+	);
+	assert_cmd(&["block"], "", &expected_help);
+	assert_cmd(&["block", "-h"], &expected_help, "");
+	assert_cmd(&["block", "--help"], &expected_help, "");
+	assert_cmd(&["block", "--help", "xyz"], &expected_help, "");
 }
 
 #[test]
@@ -779,18 +1041,25 @@ hal-simplicity-block-create 0.2.0
 create a raw block from JSON
 
 USAGE:
-    hal-simplicity block create [FLAGS] [block-info]
+    hal-simplicity block create [FLAGS] [OPTIONS] [block-info]
 
 FLAGS:
-    -h, --help          Prints help information
-    -r, --raw-stdout    output the raw bytes of the result to stdout
-    -v, --verbose       print verbose logging output to stderr
+    -h, --help           Prints help information
+        --json-errors    print errors to stderr as JSON objects with a stable error code
+        --offline        refuse to contact any daemon or backend, only computing locally
+        --plain          print pretty output without colors
+        --raw            print only the main result, such as a PSET or transaction, without JSON
+    -r, --raw-stdout     output the raw bytes of the result to stdout
+    -v, --verbose        print verbose logging output to stderr
+
+OPTIONS:
+        --format <FORMAT>      output format: json (default), yaml, pretty for a terminal, or lwk for LWK wallets
+        --network <NETWORK>    network to work on: elementsregtest (default), liquid or liquidtestnet
 
 ARGS:
     <block-info>    the block info in JSON
 ";
-	// FIXME stdout not stderr
-	assert_cmd(&["block", "create"], "Execution failed: no 'block-info' argument given\n", "");
+	assert_cmd(&["block", "create"], "", "Execution failed: no 'block-info' argument given\n");
 	assert_cmd(&["block", "create", "-h"], expected_help, "");
 	assert_cmd(&["block", "create", "--help"], expected_help, "");
 	assert_cmd(&["block", "create", "--help", "xyz"], expected_help, "");
@@ -798,13 +1067,13 @@ ARGS:
 	// TODO this was as far as I got trying to find a valid input
 	assert_cmd(
 		&["block", "create", ""],
-		"Execution failed: invalid json JSON input: EOF while parsing a value at line 1 column 0\n",
 		"",
+		"Execution failed: invalid json JSON input: EOF while parsing a value at line 1 column 0\n",
 	);
 	assert_cmd(
 		&["block", "create", "{}"],
-		"Execution failed: invalid json JSON input: missing field `header` at line 1 column 2\n",
 		"",
+		"Execution failed: invalid json JSON input: missing field `header` at line 1 column 2\n",
 	);
 	assert_cmd(
 		&[
@@ -821,13 +1090,13 @@ ARGS:
 			}
 		 }"#,
 		],
-		"Execution failed: challenge missing in proof params\n",
 		"",
+		"Execution failed: challenge missing in proof params\n",
 	);
 	assert_cmd(
 		&["block", "create", "{}"],
-		"Execution failed: invalid json JSON input: missing field `header` at line 1 column 2\n",
 		"",
+		"Execution failed: invalid json JSON input: missing field `header` at line 1 column 2\n",
 	);
 	// FIXME this error is awful; the actual field it wants is called `dynafed_current`
 	assert_cmd(
@@ -845,15 +1114,14 @@ ARGS:
 			}
 		 }"#,
 		],
-		"Execution failed: current missing in dynafed params\n",
 		"",
+		"Execution failed: current missing in dynafed params\n",
 	);
 
 	let header_json = r#"{
 		"header": {
 		    "version": 1,
-		    "previous_block_hash": "046cf11845388f39eeb83b73dee09c25e9db08a19b3ab2612c80c5f20d605084",
-		    "merkle_root": "046cf11845388f39eeb83b73dee09c25e9db08a19b3ab2612c80c5f20d605084",
+		    "previous_block_hash": "046cf11845388f39eeb83b73dee09c25e9db08a19b3ab2612c80c5f20d605084",%MERKLE_ROOT%
 		    "dynafed": true,
 		    "time": 100,
 			"height": 10,
@@ -877,29 +1145,31 @@ ARGS:
 	//  the `transactions` array which takes a poorly specified json array and the `raw_transactions` array
 	//  which takes hex. Also you are not allowed to provide both. Also you can provide an empty array, which
 	//  will satisfy the "no transactions provided" error.
-	//
-	// Also, as always, these errors show up on stdout instead of stderr..
 	assert_cmd(
-		&["block", "create", &header_json.replace("%TRANSACTIONS%", "")],
-		"Execution failed: no transactions provided.\n",
+		&[
+			"block",
+			"create",
+			&header_json.replace("%MERKLE_ROOT%", "").replace("%TRANSACTIONS%", ""),
+		],
 		"",
+		"Execution failed: no transactions provided.\n",
 	);
 	assert_cmd(
 		&[
 			"block",
 			"create",
-			&header_json.replace("%TRANSACTIONS%", ", \"transactions\": []"),
+			&header_json.replace("%MERKLE_ROOT%", "").replace("%TRANSACTIONS%", ", \"transactions\": []"),
 		],
-		"010000808450600df2c5802c61b23a9ba108dbe9259ce0de733bb8ee398f384518f16c048450600df2c5802c61b23a9ba108dbe9259ce0de733bb8ee398f384518f16c04640000000a00000001220020e51211e91d9cf4aec3bdc370a0303acde5d24baedb12235fdd2786885069d91c880500007e755ded4e96bdcc0f5db0f6d21a46e3c91ab474f1a8c95a04ad3452e8600fff000000",
+		"010000808450600df2c5802c61b23a9ba108dbe9259ce0de733bb8ee398f384518f16c040000000000000000000000000000000000000000000000000000000000000000640000000a00000001220020e51211e91d9cf4aec3bdc370a0303acde5d24baedb12235fdd2786885069d91c880500007e755ded4e96bdcc0f5db0f6d21a46e3c91ab474f1a8c95a04ad3452e8600fff000000",
 		"",
 	);
 	assert_cmd(
 		&[
 			"block",
 			"create",
-			&header_json.replace("%TRANSACTIONS%", ", \"raw_transactions\": []"),
+			&header_json.replace("%MERKLE_ROOT%", "").replace("%TRANSACTIONS%", ", \"raw_transactions\": []"),
 		],
-		"010000808450600df2c5802c61b23a9ba108dbe9259ce0de733bb8ee398f384518f16c048450600df2c5802c61b23a9ba108dbe9259ce0de733bb8ee398f384518f16c04640000000a00000001220020e51211e91d9cf4aec3bdc370a0303acde5d24baedb12235fdd2786885069d91c880500007e755ded4e96bdcc0f5db0f6d21a46e3c91ab474f1a8c95a04ad3452e8600fff000000",
+		"010000808450600df2c5802c61b23a9ba108dbe9259ce0de733bb8ee398f384518f16c040000000000000000000000000000000000000000000000000000000000000000640000000a00000001220020e51211e91d9cf4aec3bdc370a0303acde5d24baedb12235fdd2786885069d91c880500007e755ded4e96bdcc0f5db0f6d21a46e3c91ab474f1a8c95a04ad3452e8600fff000000",
 		"",
 	);
 	assert_cmd(
@@ -907,11 +1177,57 @@ ARGS:
 			"block",
 			"create",
 			&header_json
+				.replace("%MERKLE_ROOT%", "")
 				.replace("%TRANSACTIONS%", ", \"transactions\": [], \"raw_transactions\": []"),
 		],
+		"",
 		"Execution failed: can't provide transactions both in JSON and raw.\n",
+	);
+
+	// If a merkle root is given it must match the transactions; otherwise it is computed.
+	let raw_transactions = format!(", \"raw_transactions\": [\"{}\"]", SIMPLICITY_SPEND_TX);
+	let expected_block = format!(
+		"{}{}",
+		"010000808450600df2c5802c61b23a9ba108dbe9259ce0de733bb8ee398f384518f16c04ae160b02ae68e515df019075a9a99d729f090f874f208baaa3b29695b72fe5ad640000000a00000001220020e51211e91d9cf4aec3bdc370a0303acde5d24baedb12235fdd2786885069d91c880500007e755ded4e96bdcc0f5db0f6d21a46e3c91ab474f1a8c95a04ad3452e8600fff000001",
+		SIMPLICITY_SPEND_TX,
+	);
+	assert_cmd(
+		&[
+			"block",
+			"create",
+			&header_json.replace("%MERKLE_ROOT%", "").replace("%TRANSACTIONS%", &raw_transactions),
+		],
+		&expected_block,
+		"",
+	);
+	assert_cmd(
+		&[
+			"block",
+			"create",
+			&header_json
+				.replace(
+					"%MERKLE_ROOT%",
+					"\"merkle_root\": \"ade52fb79596b2a3aa8b204f870f099f729da9a9759001df15e568ae020b16ae\",",
+				)
+				.replace("%TRANSACTIONS%", &raw_transactions),
+		],
+		&expected_block,
 		"",
 	);
+	assert_cmd(
+		&[
+			"block",
+			"create",
+			&header_json
+				.replace(
+					"%MERKLE_ROOT%",
+					"\"merkle_root\": \"046cf11845388f39eeb83b73dee09c25e9db08a19b3ab2612c80c5f20d605084\",",
+				)
+				.replace("%TRANSACTIONS%", &raw_transactions),
+		],
+		"",
+		"Execution failed: merkle root 046cf11845388f39eeb83b73dee09c25e9db08a19b3ab2612c80c5f20d605084 does not match the transactions, which have merkle root ade52fb79596b2a3aa8b204f870f099f729da9a9759001df15e568ae020b16ae\n",
+	);
 
 	// To test -r we can't use `assert_cmd` since it assumes that stdout
 	// is valid utf-8, which a raw block will not be.
@@ -919,11 +1235,13 @@ ARGS:
 		"block",
 		"create",
 		"-r",
-		&header_json.replace("%TRANSACTIONS%", ", \"raw_transactions\": []"),
+		&header_json
+			.replace("%MERKLE_ROOT%", "")
+			.replace("%TRANSACTIONS%", ", \"raw_transactions\": []"),
 	];
 	let output = self_command().args(args.iter()).output().unwrap();
 	assert_eq!(output.stdout.as_hex().to_string(),
-		"010000808450600df2c5802c61b23a9ba108dbe9259ce0de733bb8ee398f384518f16c048450600df2c5802c61b23a9ba108dbe9259ce0de733bb8ee398f384518f16c04640000000a00000001220020e51211e91d9cf4aec3bdc370a0303acde5d24baedb12235fdd2786885069d91c880500007e755ded4e96bdcc0f5db0f6d21a46e3c91ab474f1a8c95a04ad3452e8600fff000000"
+		"010000808450600df2c5802c61b23a9ba108dbe9259ce0de733bb8ee398f384518f16c040000000000000000000000000000000000000000000000000000000000000000640000000a00000001220020e51211e91d9cf4aec3bdc370a0303acde5d24baedb12235fdd2786885069d91c880500007e755ded4e96bdcc0f5db0f6d21a46e3c91ab474f1a8c95a04ad3452e8600fff000000"
 			);
 	assert_eq!(output.stderr, Vec::<u8>::new());
 }
@@ -935,43 +1253,52 @@ hal-simplicity-block-decode 0.2.0
 decode a raw block to JSON
 
 USAGE:
-    hal-simplicity block decode [FLAGS] [raw-block]
+    hal-simplicity block decode [FLAGS] [OPTIONS] [raw-block]
 
 FLAGS:
     -r, --elementsregtest    run in elementsregtest mode
+        --full               decode the whole block, including every transaction
     -h, --help               Prints help information
+        --json-errors        print errors to stderr as JSON objects with a stable error code
         --liquid             run in liquid mode
+        --offline            refuse to contact any daemon or backend, only computing locally
+        --plain              print pretty output without colors
+        --raw                print only the main result, such as a PSET or transaction, without JSON
         --txids              provide transactions IDs instead of full transactions
     -v, --verbose            print verbose logging output to stderr
     -y, --yaml               print output in YAML instead of JSON
 
+OPTIONS:
+        --field <field>        print only the value at the given dot-separated path (e.g. outputs.1.script_pub_key.hex)
+        --format <FORMAT>      output format: json (default), yaml, pretty for a terminal, or lwk for LWK wallets
+        --network <NETWORK>    network to work on: elementsregtest (default), liquid or liquidtestnet
+
 ARGS:
     <raw-block>    the raw block in hex
 ";
-	// FIXME stdout not stderr
-	assert_cmd(&["block", "decode"], "Execution failed: no 'raw-block' argument given\n", "");
+	assert_cmd(&["block", "decode"], "", "Execution failed: no 'raw-block' argument given\n");
 	assert_cmd(&["block", "decode", "-h"], expected_help, "");
 	assert_cmd(&["block", "decode", "--help"], expected_help, "");
 	assert_cmd(&["block", "decode", "--help", "xyz"], expected_help, "");
 
-	// FIXME this error message is awful, and it's on stdout
+	// FIXME this error message is awful
 	assert_cmd(
 		&["block", "decode", ""],
-		"Execution failed: invalid block format: I/O error: failed to fill whole buffer\n",
 		"",
+		"Execution failed: invalid block format: I/O error: failed to fill whole buffer\n",
 	);
 	// This is a hex-encoded block header, not a full block
 	assert_cmd(&["block", "decode", BLOCK_HEADER_1585319], HEADER_DECODE_1585319, "");
 	// This is the same hex-encoded block header, with --txids. FIXME this is awful.
 	assert_cmd(
 		&["block", "decode", "--txids", BLOCK_HEADER_1585319],
-		"Execution failed: invalid block format: I/O error: failed to fill whole buffer\n",
 		"",
+		"Execution failed: invalid block format: I/O error: failed to fill whole buffer\n",
 	);
 	// Here is the header plus some arbitrary junk
 	assert_cmd(&["block", "decode", &(BLOCK_HEADER_1585319.to_owned() + "0000")],
-		"Execution failed: invalid block format: parse failed: data not consumed entirely when explicitly deserializing\n",
-"");
+		"",
+"Execution failed: invalid block format: parse failed: data not consumed entirely when explicitly deserializing\n");
 	// Here is the whole block.
 	assert_cmd(&["block", "decode", FULL_BLOCK_1585319], HEADER_DECODE_1585319, "");
 	assert_cmd(&["block", "decode", "--liquid", FULL_BLOCK_1585319], HEADER_DECODE_1585319, "");
@@ -981,10 +1308,13 @@ ARGS:
 		"",
 	);
 	assert_cmd(&["block", "decode", "-r", FULL_BLOCK_1585319], HEADER_DECODE_1585319, "");
-	// FIXME you can pass -r and --liquid at the same time, but these are incompatible. (Though they appear
-	//  to do nothing so maybe this is fine..)
 	assert_cmd(
 		&["block", "decode", "-r", "--liquid", FULL_BLOCK_1585319],
+		"",
+		"Execution failed: conflicting networks: elementsregtest and liquid\n",
+	);
+	assert_cmd(
+		&["block", "decode", "--network", "liquid", "--liquid", FULL_BLOCK_1585319],
 		HEADER_DECODE_1585319,
 		"",
 	);
@@ -1003,85 +1333,693 @@ ARGS:
 		),
 		"",
 	);
+	// --field selects a single value out of the output
+	assert_cmd(&["block", "decode", "--field", "height", FULL_BLOCK_1585319], "1585319", "");
+	assert_cmd(
+		&["block", "decode", "--txids", "--field", "txids.1", FULL_BLOCK_1585319],
+		"ae9d4031fbbb21950837012fe1dbbf53501cca0cf0796e7b53bc7a38c91c463c",
+		"",
+	);
+	assert_cmd(
+		&["block", "decode", "--field", "dynafed_current.nonexistent", FULL_BLOCK_1585319],
+		"",
+		"Execution failed: field 'dynafed_current.nonexistent' not found in output\n",
+	);
+	// --full decodes every transaction in the block
+	assert_cmd(
+		&["block", "decode", "--full", "--field", "transactions.1.txid", FULL_BLOCK_1585319],
+		"ae9d4031fbbb21950837012fe1dbbf53501cca0cf0796e7b53bc7a38c91c463c",
+		"",
+	);
+	assert_cmd(
+		&[
+			"block",
+			"decode",
+			"--full",
+			"--field",
+			"transactions.0.outputs.1.value.value",
+			FULL_BLOCK_1585319,
+		],
+		"262",
+		"",
+	);
+	assert_cmd(
+		&["block", "decode", "--full", "--field", "txids", FULL_BLOCK_1585319],
+		"",
+		"Execution failed: field 'txids' not found in output\n",
+	);
+	assert_cmd(
+		&["block", "decode", "--full", "--txids", "--field", "txids.0", FULL_BLOCK_1585319],
+		"9523d75b48b3411a3f4ebd31b6005898deebbe748875aa6ee084b94aa8422ba6",
+		"",
+	);
 }
 
 #[test]
-fn cli_keypair() {
-	let expected_help = "\
-hal-simplicity-keypair 0.2.0
-manipulate private and public keys
-
-USAGE:
-    hal-simplicity keypair [FLAGS] <SUBCOMMAND>
-
-FLAGS:
-    -h, --help       Prints help information
-    -v, --verbose    print verbose logging output to stderr
-
-SUBCOMMANDS:
-    generate    generate a random private/public keypair
-";
-	assert_cmd(&["keypair"], "", expected_help);
-	// -h does NOT mean --help. It is just ignored entirely.
-	//assert_cmd(&["keypair", "-h"], expected_help, "");
-	assert_cmd(&["keypair", "--help"], expected_help, "");
-	assert_cmd(&["keypair", "--help", "xyz"], expected_help, "");
+fn cli_block_decode_full_params() {
+	// A block proposing full dynafed params, with fedpeg script
+	// OP_1 OP_CHECKSIGFROMSTACK OP_CSV OP_DROP.
+	let block = "010000808450600df2c5802c61b23a9ba108dbe9259ce0de733bb8ee398f384518f16c040000000000000000000000000000000000000000000000000000000000000000640000000a00000001220020e51211e91d9cf4aec3bdc370a0303acde5d24baedb12235fdd2786885069d91c880500007e755ded4e96bdcc0f5db0f6d21a46e3c91ab474f1a8c95a04ad3452e8600fff02220020e51211e91d9cf4aec3bdc370a0303acde5d24baedb12235fdd2786885069d91c88050000160014879d7ec6f6b3c1ef2d4cde5a2d0e5c25e2d0e4b00451c1b275000000";
+	assert_cmd(&["block", "decode", "--field", "dynafed_proposed.params_type", block], "full", "");
+	assert_cmd(
+		&["block", "decode", "--field", "dynafed_proposed.fedpeg_script", block],
+		"51c1b275",
+		"",
+	);
+	// Elements opcodes are named, as in every other script asm.
+	assert_cmd(
+		&["block", "decode", "--field", "dynafed_proposed.fedpeg_script_asm", block],
+		"OP_PUSHNUM_1 OP_CHECKSIGFROMSTACK OP_CSV OP_DROP",
+		"",
+	);
 }
 
 #[test]
-fn cli_keypair_generate() {
-	let expected_help = "\
-hal-simplicity-keypair-generate 0.2.0
-generate a random private/public keypair
-
-USAGE:
-    hal-simplicity keypair generate [FLAGS]
+fn cli_block_decode_stream() {
+	use std::io::Write as _;
+	use std::process::Stdio;
 
-FLAGS:
-    -h, --help       Prints help information
-    -v, --verbose    print verbose logging output to stderr
-    -y, --yaml       print output in YAML instead of JSON
-";
-	assert_cmd(&["keypair", "generate", "-h"], expected_help, "");
-	assert_cmd(&["keypair", "generate", "--help"], expected_help, "");
-	assert_cmd(&["keypair", "generate", "--help", "xyz"], expected_help, "");
+	let args = ["block", "decode", "--full", "--txids"];
+	let expected = assert_deserialize_cmd(&[&args[..], &[FULL_BLOCK_1585319]].concat(), |s| {
+		serde_json::from_slice::<serde_json::Value>(s)
+	});
 
-	// New block to avoid warnings about `struct`s being defined not at the beginning of block
-	{
-		use elements::bitcoin::secp256k1;
+	// Files and stdin are decoded as they are read, skipping line breaks.
+	let wrapped = FULL_BLOCK_1585319
+		.as_bytes()
+		.chunks(64)
+		.map(|line| format!("{}\n", std::str::from_utf8(line).unwrap()))
+		.collect::<String>();
+	let path =
+		std::env::temp_dir().join(format!("hal-simplicity-cli-{}.block", std::process::id()));
+	std::fs::write(&path, &wrapped).unwrap();
+	let arg = format!("@{}", path.display());
+	let from_file = assert_deserialize_cmd(&[&args[..], &[&arg]].concat(), |s| {
+		serde_json::from_slice::<serde_json::Value>(s)
+	});
+	std::fs::remove_file(&path).unwrap();
+	assert_eq!(from_file, expected);
 
-		#[allow(dead_code)]
-		#[derive(serde::Deserialize)]
-		struct Object {
-			secret: secp256k1::SecretKey,
-			x_only: secp256k1::XOnlyPublicKey,
-			parity: usize, // secp256k1::Parity does not seem to round-trip through serde_json
-		}
+	let mut child =
+		self_command().args(args).stdin(Stdio::piped()).stdout(Stdio::piped()).spawn().unwrap();
+	child.stdin.take().unwrap().write_all(wrapped.as_bytes()).unwrap();
+	let output = child.wait_with_output().unwrap();
+	let from_stdin: serde_json::Value = serde_json::from_slice(&output.stdout).unwrap();
+	assert_eq!(from_stdin, expected);
 
-		// Closure needed for borrowck reasons
-		assert_deserialize_cmd(&["keypair", "generate"], |s| serde_json::from_slice::<Object>(s));
-		assert_deserialize_cmd(&["keypair", "generate"], serde_yaml::from_slice::<Object>);
-	}
+	assert_cmd(
+		&["block", "decode", &(BLOCK_HEADER_1585319.to_owned() + "0x")],
+		"",
+		format!(
+			"Execution failed: could not decode raw block hex: Invalid character 'x' at position {}\n",
+			BLOCK_HEADER_1585319.len() + 1
+		),
+	);
 }
 
 #[test]
-fn cli_simplicity() {
+fn cli_block_stats() {
 	let expected_help = "\
-hal-simplicity-simplicity 0.2.0
-manipulate Simplicity programs
+hal-simplicity-block-stats 0.2.0
+summarize the Simplicity spends in a raw block
 
 USAGE:
-    hal-simplicity simplicity [FLAGS] <SUBCOMMAND>
+    hal-simplicity block stats [FLAGS] [OPTIONS] [raw-block]
 
 FLAGS:
-    -h, --help       Prints help information
-    -v, --verbose    print verbose logging output to stderr
+    -h, --help           Prints help information
+        --json-errors    print errors to stderr as JSON objects with a stable error code
+        --offline        refuse to contact any daemon or backend, only computing locally
+        --plain          print pretty output without colors
+        --raw            print only the main result, such as a PSET or transaction, without JSON
+    -v, --verbose        print verbose logging output to stderr
+    -y, --yaml           print output in YAML instead of JSON
 
-SUBCOMMANDS:
-    info       Parse a base64-encoded Simplicity program and decode it
-    pset       manipulate PSETs for spending from Simplicity programs
-    sighash    Compute signature hashes or signatures for use with Simplicity
-";
+OPTIONS:
+        --field <field>        print only the value at the given dot-separated path (e.g. outputs.1.script_pub_key.hex)
+        --format <FORMAT>      output format: json (default), yaml, pretty for a terminal, or lwk for LWK wallets
+        --network <NETWORK>    network to work on: elementsregtest (default), liquid or liquidtestnet
+
+ARGS:
+    <raw-block>    the raw block in hex
+";
+	assert_cmd(&["block", "stats"], "", "Execution failed: no 'raw-block' argument given\n");
+	assert_cmd(&["block", "stats", "-h"], expected_help, "");
+	assert_cmd(&["block", "stats", "--help"], expected_help, "");
+
+	assert_cmd(
+		&["block", "stats", FULL_BLOCK_1585319],
+		r#"{
+  "block_hash": "5f37039a5ae15d9239bb2e137643a51d3a525d6e850b5e8974b4323c9e13a39b",
+  "transactions": 2,
+  "simplicity_transactions": 0,
+  "simplicity_spends": 0,
+  "undecodable_spends": 0,
+  "total_program_bytes": 0,
+  "total_witness_bytes": 0,
+  "max_program_bytes": 0,
+  "max_witness_bytes": 0,
+  "jets": {},
+  "cmrs": []
+}"#,
+		"",
+	);
+
+	// The 1585319 header with a single transaction spending a Simplicity
+	// program which just calls the `version` jet.
+	let block = format!("{}01{}", BLOCK_HEADER_1585319, SIMPLICITY_SPEND_TX);
+	assert_cmd(
+		&["block", "stats", &block],
+		r#"{
+  "block_hash": "5f37039a5ae15d9239bb2e137643a51d3a525d6e850b5e8974b4323c9e13a39b",
+  "transactions": 1,
+  "simplicity_transactions": 1,
+  "simplicity_spends": 1,
+  "undecodable_spends": 0,
+  "total_program_bytes": 5,
+  "total_witness_bytes": 0,
+  "max_program_bytes": 5,
+  "max_witness_bytes": 0,
+  "jets": {
+    "version": 1
+  },
+  "cmrs": [
+    "69cf8e9284799b072ddb78eac6d4ffa1c920baef099f17e59a63a8afef702a19"
+  ]
+}"#,
+		"",
+	);
+	assert_cmd(&["block", "stats", "--field", "jets.version", &block], "1", "");
+}
+
+#[test]
+fn cli_block_verify_chain() {
+	let expected_help = "\
+hal-simplicity-block-verify-chain 0.2.0
+check that a sequence of raw block headers forms a chain
+
+USAGE:
+    hal-simplicity block verify-chain [FLAGS] [OPTIONS] [raw-headers]...
+
+FLAGS:
+    -h, --help           Prints help information
+        --json-errors    print errors to stderr as JSON objects with a stable error code
+        --offline        refuse to contact any daemon or backend, only computing locally
+        --plain          print pretty output without colors
+        --raw            print only the main result, such as a PSET or transaction, without JSON
+    -v, --verbose        print verbose logging output to stderr
+    -y, --yaml           print output in YAML instead of JSON
+
+OPTIONS:
+        --field <field>        print only the value at the given dot-separated path (e.g. outputs.1.script_pub_key.hex)
+        --format <FORMAT>      output format: json (default), yaml, pretty for a terminal, or lwk for LWK wallets
+        --network <NETWORK>    network to work on: elementsregtest (default), liquid or liquidtestnet
+
+ARGS:
+    <raw-headers>...    the raw block headers in hex, in order (default: from stdin)
+";
+	assert_cmd(&["block", "verify-chain", "-h"], expected_help, "");
+	assert_cmd(&["block", "verify-chain", "--help"], expected_help, "");
+	assert_cmd(
+		&["block", "verify-chain", "00"],
+		"",
+		"Execution failed: invalid block header 0: I/O error: failed to fill whole buffer\n",
+	);
+
+	let header = BLOCK_HEADER_1585319;
+	let hash = "5f37039a5ae15d9239bb2e137643a51d3a525d6e850b5e8974b4323c9e13a39b";
+	assert_cmd(&["block", "verify-chain", "--field", "valid", header], "true", "");
+	assert_cmd(&["block", "verify-chain", "--field", "tip.block_hash", header], hash, "");
+
+	// A header can't follow itself.
+	let args = ["block", "verify-chain", "--field", "first_invalid.reason", header, header];
+	assert_cmd(
+		&args,
+		"previous block hash 3365afcddd47de8f1e53f7407115f47ae20c91a818a64016de5b4ea3e0096417 \
+		is not the hash 5f37039a5ae15d9239bb2e137643a51d3a525d6e850b5e8974b4323c9e13a39b of the \
+		header before it",
+		"",
+	);
+
+	// Linking the header to itself leaves it at the same height.
+	let prev = "9ba3139e3c32b474895e0b856e5d523a1da54376132ebb39925de15a9a03375f";
+	let linked = format!("{}{}{}", &header[..8], prev, &header[72..]);
+	let args = ["block", "verify-chain", "--field", "first_invalid.reason", header, &linked];
+	assert_cmd(&args, "height 1585319 does not follow height 1585319 of the header before it", "");
+
+	// One higher, it follows, but the federation's signatures are for the original.
+	let next = format!("{}{}{}a8301800{}", &header[..8], prev, &header[72..144], &header[152..]);
+	let args = ["block", "verify-chain", "--field", "first_invalid", header, &next];
+	let invalid = assert_deserialize_cmd(&args, |s| serde_json::from_slice::<serde_json::Value>(s));
+	assert_eq!(invalid["index"], 1);
+	assert_eq!(invalid["height"], 1585320);
+	assert_eq!(invalid["reason"], "signblock has 0 valid signatures of 11 needed");
+	assert_cmd(&["block", "verify-chain", "--field", "tip.index", header, &next], "0", "");
+}
+
+#[test]
+fn cli_block_verify_signblock() {
+	let expected_help = "\
+hal-simplicity-block-verify-signblock 0.2.0
+check the signatures on a raw block or block header
+
+USAGE:
+    hal-simplicity block verify-signblock [FLAGS] [OPTIONS] [raw-block]
+
+FLAGS:
+    -h, --help           Prints help information
+        --json-errors    print errors to stderr as JSON objects with a stable error code
+        --offline        refuse to contact any daemon or backend, only computing locally
+        --plain          print pretty output without colors
+        --raw            print only the main result, such as a PSET or transaction, without JSON
+    -v, --verbose        print verbose logging output to stderr
+    -y, --yaml           print output in YAML instead of JSON
+
+OPTIONS:
+        --field <field>        print only the value at the given dot-separated path (e.g. outputs.1.script_pub_key.hex)
+        --format <FORMAT>      output format: json (default), yaml, pretty for a terminal, or lwk for LWK wallets
+        --network <NETWORK>    network to work on: elementsregtest (default), liquid or liquidtestnet
+
+ARGS:
+    <raw-block>    the raw block or block header in hex
+";
+	assert_cmd(
+		&["block", "verify-signblock"],
+		"",
+		"Execution failed: no 'raw-block' argument given\n",
+	);
+	assert_cmd(&["block", "verify-signblock", "-h"], expected_help, "");
+	assert_cmd(&["block", "verify-signblock", "--help"], expected_help, "");
+
+	for block in [BLOCK_HEADER_1585319, FULL_BLOCK_1585319] {
+		assert_cmd(&["block", "verify-signblock", "--field", "valid_signatures", block], "11", "");
+		assert_cmd(&["block", "verify-signblock", "--field", "valid", block], "true", "");
+	}
+	assert_cmd(
+		&["block", "verify-signblock", "--field", "witness_size", BLOCK_HEADER_1585319],
+		"1318",
+		"",
+	);
+
+	// Corrupt the first signature.
+	let corrupted = BLOCK_HEADER_1585319.replacen("c44868", "c44869", 1);
+	assert_cmd(&["block", "verify-signblock", "--field", "valid_signatures", &corrupted], "10", "");
+	assert_cmd(&["block", "verify-signblock", "--field", "valid", &corrupted], "false", "");
+}
+
+#[test]
+fn cli_keypair() {
+	let expected_help = "\
+hal-simplicity-keypair 0.2.0
+manipulate private and public keys
+
+USAGE:
+    hal-simplicity keypair [FLAGS] [OPTIONS] <SUBCOMMAND>
+
+FLAGS:
+    -h, --help           Prints help information
+        --json-errors    print errors to stderr as JSON objects with a stable error code
+        --offline        refuse to contact any daemon or backend, only computing locally
+        --plain          print pretty output without colors
+        --raw            print only the main result, such as a PSET or transaction, without JSON
+    -v, --verbose        print verbose logging output to stderr
+
+OPTIONS:
+        --format <FORMAT>      output format: json (default), yaml, pretty for a terminal, or lwk for LWK wallets
+        --network <NETWORK>    network to work on: elementsregtest (default), liquid or liquidtestnet
+
+SUBCOMMANDS:
+    combine      add public keys together
+    generate     generate a random private/public keypair
+    negate       negate a public key
+    tweak-add    add a multiple of the generator to a public key
+";
+	assert_cmd(&["keypair"], "", expected_help);
+	// -h does NOT mean --help. It is just ignored entirely.
+	//assert_cmd(&["keypair", "-h"], expected_help, "");
+	assert_cmd(&["keypair", "--help"], expected_help, "");
+	assert_cmd(&["keypair", "--help", "xyz"], expected_help, "");
+}
+
+#[test]
+fn cli_keypair_generate() {
+	let expected_help = "\
+hal-simplicity-keypair-generate 0.2.0
+generate a random private/public keypair
+
+USAGE:
+    hal-simplicity keypair generate [FLAGS] [OPTIONS]
+
+FLAGS:
+    -h, --help           Prints help information
+        --json-errors    print errors to stderr as JSON objects with a stable error code
+        --offline        refuse to contact any daemon or backend, only computing locally
+        --plain          print pretty output without colors
+        --raw            print only the main result, such as a PSET or transaction, without JSON
+    -v, --verbose        print verbose logging output to stderr
+    -y, --yaml           print output in YAML instead of JSON
+
+OPTIONS:
+        --format <FORMAT>      output format: json (default), yaml, pretty for a terminal, or lwk for LWK wallets
+        --network <NETWORK>    network to work on: elementsregtest (default), liquid or liquidtestnet
+";
+	assert_cmd(&["keypair", "generate", "-h"], expected_help, "");
+	assert_cmd(&["keypair", "generate", "--help"], expected_help, "");
+	assert_cmd(&["keypair", "generate", "--help", "xyz"], expected_help, "");
+
+	// New block to avoid warnings about `struct`s being defined not at the beginning of block
+	{
+		use elements::bitcoin::secp256k1;
+
+		#[allow(dead_code)]
+		#[derive(serde::Deserialize)]
+		struct Object {
+			secret: secp256k1::SecretKey,
+			x_only: secp256k1::XOnlyPublicKey,
+			parity: usize, // secp256k1::Parity does not seem to round-trip through serde_json
+		}
+
+		// Closure needed for borrowck reasons
+		assert_deserialize_cmd(&["keypair", "generate"], |s| serde_json::from_slice::<Object>(s));
+		assert_deserialize_cmd(&["keypair", "generate"], serde_yaml::from_slice::<Object>);
+	}
+}
+
+#[test]
+fn cli_keypair_tweak_add() {
+	let expected_help = "\
+hal-simplicity-keypair-tweak-add 0.2.0
+add a multiple of the generator to a public key
+
+USAGE:
+    hal-simplicity keypair tweak-add [FLAGS] [OPTIONS] <pubkey> <tweak>
+
+FLAGS:
+    -h, --help           Prints help information
+        --json-errors    print errors to stderr as JSON objects with a stable error code
+        --offline        refuse to contact any daemon or backend, only computing locally
+        --plain          print pretty output without colors
+        --raw            print only the main result, such as a PSET or transaction, without JSON
+    -v, --verbose        print verbose logging output to stderr
+    -y, --yaml           print output in YAML instead of JSON
+
+OPTIONS:
+        --format <FORMAT>      output format: json (default), yaml, pretty for a terminal, or lwk for LWK wallets
+        --network <NETWORK>    network to work on: elementsregtest (default), liquid or liquidtestnet
+
+ARGS:
+    <pubkey>    the public key, compressed or x-only (hex)
+    <tweak>     the 32-byte tweak to multiply the generator by (hex)
+";
+	assert_cmd(&["keypair", "tweak-add", "-h"], expected_help, "");
+	assert_cmd(&["keypair", "tweak-add", "--help"], expected_help, "");
+
+	// The taproot output key of the first BIP 341 wallet test vector.
+	let internal = "d6889cb081036e0faefa3a35157ad71086b123b2b144b649798b494c300a961d";
+	let tweak = "b86e7be8f39bab32a6f2c0443abbc210f0edac0e2c53d501b36b64437d9c6c70";
+	assert_cmd(
+		&["keypair", "tweak-add", "--raw", internal, tweak],
+		"0353a1f6e454df1aa2776a2814a721372d6258050de330b3c6d10ee8f4e0dda343",
+		"",
+	);
+
+	let generator = "79be667ef9dcbbac55a06295ce870b07029bfcdb2dce28d959f2815b16f81798";
+	let one = "0000000000000000000000000000000000000000000000000000000000000001";
+	assert_cmd(
+		&["keypair", "tweak-add", "--raw", &format!("02{}", generator), one],
+		"02c6047f9441ed7d6d3045406e95c07cd85c778e4b8cef3ca7abac09b95c709ee5",
+		"",
+	);
+	assert_cmd(
+		&["keypair", "tweak-add", generator, "00"],
+		"",
+		"Execution failed: invalid tweak: expected 32 bytes, got 1\n",
+	);
+	assert_cmd(
+		&["keypair", "tweak-add", generator, &"ff".repeat(32)],
+		"",
+		"Execution failed: tweak is not less than the curve order\n",
+	);
+	// Adding the order minus one to the generator gives the point at infinity.
+	let minus_one = "fffffffffffffffffffffffffffffffebaaedce6af48a03bbfd25e8cd0364140";
+	assert_cmd(
+		&["keypair", "tweak-add", generator, minus_one],
+		"",
+		"Execution failed: the tweaked key is the point at infinity\n",
+	);
+}
+
+#[test]
+fn cli_keypair_negate() {
+	let expected_help = "\
+hal-simplicity-keypair-negate 0.2.0
+negate a public key
+
+USAGE:
+    hal-simplicity keypair negate [FLAGS] [OPTIONS] [pubkey]
+
+FLAGS:
+    -h, --help           Prints help information
+        --json-errors    print errors to stderr as JSON objects with a stable error code
+        --offline        refuse to contact any daemon or backend, only computing locally
+        --plain          print pretty output without colors
+        --raw            print only the main result, such as a PSET or transaction, without JSON
+    -v, --verbose        print verbose logging output to stderr
+    -y, --yaml           print output in YAML instead of JSON
+
+OPTIONS:
+        --format <FORMAT>      output format: json (default), yaml, pretty for a terminal, or lwk for LWK wallets
+        --network <NETWORK>    network to work on: elementsregtest (default), liquid or liquidtestnet
+
+ARGS:
+    <pubkey>    the public key, compressed or x-only (hex)
+";
+	assert_cmd(&["keypair", "negate", "-h"], expected_help, "");
+	assert_cmd(&["keypair", "negate", "--help"], expected_help, "");
+
+	let generator = "79be667ef9dcbbac55a06295ce870b07029bfcdb2dce28d959f2815b16f81798";
+	assert_cmd(
+		&["keypair", "negate", "--raw", generator],
+		"0379be667ef9dcbbac55a06295ce870b07029bfcdb2dce28d959f2815b16f81798",
+		"",
+	);
+	assert_cmd(
+		&["keypair", "negate", "--raw", &format!("03{}", generator)],
+		"0279be667ef9dcbbac55a06295ce870b07029bfcdb2dce28d959f2815b16f81798",
+		"",
+	);
+	assert_cmd(
+		&["keypair", "negate", "0479be"],
+		"",
+		"Execution failed: invalid public key 0479be: malformed public key\n",
+	);
+}
+
+#[test]
+fn cli_keypair_combine() {
+	let expected_help = "\
+hal-simplicity-keypair-combine 0.2.0
+add public keys together
+
+USAGE:
+    hal-simplicity keypair combine [FLAGS] [OPTIONS] <pubkeys>...
+
+FLAGS:
+    -h, --help           Prints help information
+        --json-errors    print errors to stderr as JSON objects with a stable error code
+        --offline        refuse to contact any daemon or backend, only computing locally
+        --plain          print pretty output without colors
+        --raw            print only the main result, such as a PSET or transaction, without JSON
+    -v, --verbose        print verbose logging output to stderr
+    -y, --yaml           print output in YAML instead of JSON
+
+OPTIONS:
+        --format <FORMAT>      output format: json (default), yaml, pretty for a terminal, or lwk for LWK wallets
+        --network <NETWORK>    network to work on: elementsregtest (default), liquid or liquidtestnet
+
+ARGS:
+    <pubkeys>...    the public keys to add, compressed or x-only (hex)
+";
+	assert_cmd(&["keypair", "combine", "-h"], expected_help, "");
+	assert_cmd(&["keypair", "combine", "--help"], expected_help, "");
+
+	let generator = "79be667ef9dcbbac55a06295ce870b07029bfcdb2dce28d959f2815b16f81798";
+	assert_cmd(
+		&["keypair", "combine", "--raw", generator, &format!("02{}", generator)],
+		"02c6047f9441ed7d6d3045406e95c07cd85c778e4b8cef3ca7abac09b95c709ee5",
+		"",
+	);
+	assert_cmd(
+		&["keypair", "combine", "--raw", generator],
+		"0279be667ef9dcbbac55a06295ce870b07029bfcdb2dce28d959f2815b16f81798",
+		"",
+	);
+	assert_cmd(
+		&["keypair", "combine", generator, &format!("03{}", generator)],
+		"",
+		"Execution failed: the public keys sum to the point at infinity\n",
+	);
+}
+
+#[test]
+#[cfg(feature = "miniscript")]
+fn cli_miniscript() {
+	let key1 = "03a34b99f22c790c4e36b2b3c2c35a36db06226e41c692fc82b8b56ac1c540c5bd";
+	let key2 = "02a34b99f22c790c4e36b2b3c2c35a36db06226e41c692fc82b8b56ac1c540c5bd";
+	let policy = format!("or(99@pk({}),1@and(pk({}),older(144)))", key1, key2);
+	let compiled = assert_deserialize_cmd(&["miniscript", "compile", &policy], |s| {
+		serde_json::from_slice::<serde_json::Value>(s)
+	});
+	let miniscript = format!("or_d(pk({}),and_v(v:pkh({}),older(144)))", key1, key2);
+	assert_eq!(compiled["context"], "segwitv0");
+	assert_eq!(compiled["miniscript"], miniscript);
+	assert_eq!(compiled["script_size"], 67);
+	assert_eq!(compiled["max_satisfaction_size"], 108);
+	assert_eq!(compiled["sane"], true);
+	let lifted = format!("or(pk({}),and(pk({}),older(144)))", key1, key2);
+	assert_eq!(compiled["policy"], lifted);
+
+	// Lifting the miniscript gives the policy without its probabilities, but the
+	// script alone has only the hash of the second key.
+	assert_cmd(&["miniscript", "lift", "--raw", &miniscript], &lifted, "");
+	let script = compiled["script"]["hex"].as_str().unwrap();
+	assert_cmd(
+		&["miniscript", "lift", script],
+		"",
+		"Execution failed: cannot lift to a policy: Cannot lift raw descriptors; give the script as miniscript, with the keys of any pkh\n",
+	);
+	assert_cmd(
+		&[
+			"miniscript",
+			"lift",
+			"--raw",
+			"2103a34b99f22c790c4e36b2b3c2c35a36db06226e41c692fc82b8b56ac1c540c5bdac",
+		],
+		format!("pk({})", key1),
+		"",
+	);
+
+	// In a taproot leaf, keys are x-only.
+	let leaf =
+		assert_deserialize_cmd(&["miniscript", "compile", "--context", "tap", &policy], |s| {
+			serde_json::from_slice::<serde_json::Value>(s)
+		});
+	assert_eq!(leaf["context"], "tap");
+	assert_eq!(leaf["script_size"], 66);
+
+	let descriptor = format!("elwsh({})", miniscript);
+	let info =
+		assert_deserialize_cmd(&["miniscript", "descriptor", "--liquid", &descriptor], |s| {
+			serde_json::from_slice::<serde_json::Value>(s)
+		});
+	assert_eq!(info["type"], "wsh");
+	assert_eq!(info["max_weight_to_satisfy"], 176);
+	assert_eq!(
+		info["script_pub_key"]["address"],
+		"ex1q93adg96wj3fxhmqw4ytwshgtxrgdxkg9r5e9v4868cm8r57ja0hqs3g4r3"
+	);
+
+	let x_only = &key1[2..];
+	let descriptor =
+		format!("eltr({},{{pk({}),and_v(v:pk({}),older(10))}})", x_only, x_only, x_only);
+	let info = assert_deserialize_cmd(&["miniscript", "descriptor", &descriptor], |s| {
+		serde_json::from_slice::<serde_json::Value>(s)
+	});
+	assert_eq!(info["type"], "tr");
+	assert_eq!(info["leaves"].as_array().unwrap().len(), 2);
+	assert_eq!(info["leaves"][1]["depth"], 1);
+	assert_eq!(info["leaves"][1]["miniscript"], format!("and_v(v:pk({}),older(10))", x_only));
+
+	let xpub = "xpub661MyMwAqRbcFtXgS5sYJABqqG9YLmC4Q1Rdap9gSE8NqtwybGhePY2gZ29ESFjqJoCu1Rupje8YtGqsefD265TMg7usUDFdp6W1EGMcet8";
+	let descriptor = format!("elwsh(pk({}/0/*))", xpub);
+	assert_cmd(
+		&["miniscript", "descriptor", &descriptor],
+		"",
+		"Execution failed: the descriptor has a wildcard, so a derivation index is needed\n",
+	);
+	assert_cmd(
+		&["miniscript", "descriptor", "--raw", "--index", "3", &descriptor],
+		"ert1qm48y7t976jxatzvtn4fm7cphdeuaddhtdl08g6cufnkxgg4v3yzq029em0",
+		"",
+	);
+}
+
+#[test]
+fn cli_psbt_decode() {
+	// The unsigned PSBT of the BIP-0174 test vectors.
+	let psbt = concat!(
+		"cHNidP8BAHUCAAAAASaBcTce3/KF6Tet7qSze3gADAVmy7OtZGQXE8pCFxv2AAAAAAD+////AtPf9QUAAAAA",
+		"GXapFNDFmQPFusKGh2DpD9UhpGZap2UgiKwA4fUFAAAAABepFDVF5uM7gyxHBQ8k0+65PJwDlIvHh7MuEwAA",
+		"AQD9pQEBAAAAAAECiaPHHqtNIOA3G7ukzGmPopXJRjr6Ljl/hTPMti+VZ+UBAAAAFxYAFL4Y0VKpsBIDna89",
+		"p95PUzSe7LmF/////4b4qkOnHf8USIk6UwpyN+9rRgi7st0tAXHmOuxqSJC0AQAAABcWABT+Pp7xp0XpdNkC",
+		"xDVZQ6vLNL1TU/////8CAMLrCwAAAAAZdqkUhc/xCX/Z4Ai7NK9wnGIZeziXikiIrHL++E4sAAAAF6kUM5cl",
+		"uiHv1irHU6m80GfWx6ajnQWHAkcwRAIgJxK+IuAnDzlPVoMR3HyppolwuAJf3TskAinwf4pfOiQCIAGLONfc",
+		"0xTnNMkna9b7QPZzMlvEuqFEyADS8vAtsnZcASED0uFWdJQbrUqZY3LLh+GFbTZSYG2YVi/jnF6efkE/IQUC",
+		"SDBFAiEA0SuFLYXc2WHS9fSrZgZU327tzHlMDDPOXMMJ/7X85Y0CIGczio4OFyXBl/saiK9Z9R5E5CVbIBZ8",
+		"hoQDHAXR8lkqASECI7cr7vCWXRC+B3jv7NYfysb3mk6haTkzgHNEZPhPKrMAAAAAAAAA",
+	);
+	let decoded = assert_deserialize_cmd(&["psbt", "decode", "--liquid", psbt], |s| {
+		serde_json::from_slice::<serde_json::Value>(s)
+	});
+	let tx = &decoded["unsigned_tx"];
+	assert_eq!(tx["txid"], "af2cac1e0e33d896d9d0751d66fcb2fa54b737c7a13199281fb57e4f497bb652");
+	assert_eq!(tx["locktime"], 1257139);
+	// Liquid is pegged to Bitcoin mainnet, so addresses are mainnet ones.
+	assert_eq!(tx["outputs"][0]["value"], 99999699);
+	assert_eq!(tx["outputs"][0]["script_pub_key"]["address"], "1L2tGENeoh4mSoiUZrSbs1J3jazSdJH9QS");
+	assert_eq!(decoded["inputs"].as_array().unwrap().len(), 1);
+	assert!(decoded["inputs"][0]["non_witness_utxo"].is_object());
+
+	// Hex is decoded the same.
+	let hex = hex::encode(
+		simplicity::base64::Engine::decode(&simplicity::base64::prelude::BASE64_STANDARD, psbt)
+			.unwrap(),
+	);
+	let from_hex = assert_deserialize_cmd(&["psbt", "decode", "--liquid", &hex], |s| {
+		serde_json::from_slice::<serde_json::Value>(s)
+	});
+	assert_eq!(from_hex, decoded);
+
+	assert_cmd(
+		&["psbt", "decode", "cHNidP8="],
+		"",
+		"Execution failed: invalid PSBT: bitcoin consensus encoding error\n",
+	);
+}
+
+#[test]
+fn cli_simplicity() {
+	let expected_help = "\
+hal-simplicity-simplicity 0.2.0
+manipulate Simplicity programs
+
+USAGE:
+    hal-simplicity simplicity [FLAGS] [OPTIONS] <SUBCOMMAND>
+
+FLAGS:
+    -h, --help           Prints help information
+        --json-errors    print errors to stderr as JSON objects with a stable error code
+        --offline        refuse to contact any daemon or backend, only computing locally
+        --plain          print pretty output without colors
+        --raw            print only the main result, such as a PSET or transaction, without JSON
+    -v, --verbose        print verbose logging output to stderr
+
+OPTIONS:
+        --format <FORMAT>      output format: json (default), yaml, pretty for a terminal, or lwk for LWK wallets
+        --network <NETWORK>    network to work on: elementsregtest (default), liquid or liquidtestnet
+
+SUBCOMMANDS:
+    bench            Run a Simplicity program repeatedly in the context of a PSET input, timing it against its cost
+    check-prune      Check that a pruned Simplicity program was derived from an unpruned one
+    export-webide    Bundle a program, witness and transaction parameters for simplicity-webide
+    gen-fixtures     Generate deterministic program, witness and PSET fixtures for testing other tools
+    hide-branches    Hide the untaken branches of a Simplicity program by hand, without running it
+    import-webide    Decode a program, witness and transaction parameters exported from simplicity-webide
+    info             Parse a base64-encoded Simplicity program and decode it
+    pset             manipulate PSETs for spending from Simplicity programs
+    sighash          Compute signature hashes or signatures for use with Simplicity
+    sighashes        Compute the signature hashes of every input of a transaction
+";
 	assert_cmd(&["simplicity"], "", expected_help);
 	assert_cmd(&["simplicity", "-h"], expected_help, "");
 	assert_cmd(&["simplicity", "--help"], expected_help, "");
@@ -1100,16 +2038,25 @@ USAGE:
 FLAGS:
     -r, --elementsregtest    run in elementsregtest mode
     -h, --help               Prints help information
+        --json-errors        print errors to stderr as JSON objects with a stable error code
         --liquid             run in liquid mode
+        --offline            refuse to contact any daemon or backend, only computing locally
+        --plain              print pretty output without colors
+        --raw                print only the main result, such as a PSET or transaction, without JSON
+        --source-map         map each node of the program to the range of bits it is encoded in
     -v, --verbose            print verbose logging output to stderr
     -y, --yaml               print output in YAML instead of JSON
 
 OPTIONS:
-    -s, --state <state>    32-byte state commitment to put alongside the program when generating addresess (hex)
+        --format <FORMAT>           output format: json (default), yaml, pretty for a terminal, or lwk for LWK wallets
+        --leaf-version <VERSION>    expert: tapleaf version of the program, for custom deployments (hex) (default: be)
+        --network <NETWORK>         network to work on: elementsregtest (default), liquid or liquidtestnet
+    -s, --state <state>             32-byte state commitment to put alongside the program when generating addresess
+                                    (hex)
 
 ARGS:
     <program>    a Simplicity program in base64
-    <witness>    a hex encoding of all the witness data for the program
+    <witness>    a hex encoding of all the witness data for the program, or a SimplicityHL .wit file
 ";
 	// For the transaction/block create / decode functions we can take input by
 	// stdin as an undocumented JSON blob. FIXME we probably want to do this
@@ -1134,27 +2081,893 @@ For more information try --help
 	assert_cmd(&["simplicity", "info", "--help", "xyz"], expected_help, "");
 }
 
+#[test]
+fn cli_simplicity_witness_file() {
+	let program = concat!(
+		"5PugUJtN8SDh0yBFHxQXLEZHZkba+NDW2oDoTJhsxeBz+A7U3PAhAoQYckgSasjmcVRCRXQmYAIq4WDF",
+		"4UsJ7AwqF1hL9cVIyFlhwCtu/AEMAxCa0kIMPwAUCxarkc113LwehOp6MgcZy/xtyV5RlPnsqZbVWnst",
+		"doxRHioxDhgGJAoSQbcKNWJzAu99qFH3Wh9HF0gSGitpeJMKWMyu4jCUAb0bbp/LsAGGAYgagOEgcRkC",
+		"hJBuKjcVnCoWLNug5n4KrWbIJljsDH8qWizDjD9hqJKs0No6Ez/56tZohz3GDAMQtbBzBEX+oDjSJpgM",
+		"Lm9+S+nolYSNH9l/IQDbQwBMtOrd78UGAYhcB4Fw5vE6GEjgGe+I3i56PBVh0YKLO+DykN75/uv1TalC",
+		"SUcsDAMSBQkg/II43IYUOKBZtjDm7yVnAtI8+S8yl59PzZ/zkJz3syU4qvsOOiPsQAebHRMMAxA3hcIH",
+		"5MggHFoHJYDk4A==",
+	);
+	let first = "9bef8d556d80e43ae7e0becb3a7e6838b95defe45896ed6075bb9035d06c9964";
+	let second = concat!(
+		"e09e91b2ee81dd61d97ec6e83bfdb03c28f79e0e7038a98964ea5c29cde0b231",
+		"9878a86dc9e5a0d00269215a43754755a6e173246ad7d330eb82d27e779fd90a",
+	);
+	let witness_file = |first: &str, second: &str| {
+		serde_json::json!({
+			"HASH": { "value": format!("0x{}", first), "type": "u256" },
+			"SIG": { "value": format!("0x{}", second), "type": "Signature" },
+		})
+		.to_string()
+	};
+
+	// The witness file gives the same witness as its encoding.
+	let info = assert_deserialize_cmd(
+		&["simplicity", "info", program, &witness_file(first, second)],
+		|s| serde_json::from_slice::<serde_json::Value>(s),
+	);
+	assert_eq!(info["witness_hex"], format!("{}{}", first, second));
+	assert_eq!(info["amr"], "463d72225db55f4bb9811a9d4d921854ef8893fa92b6358f6d47719ecfb1ced5");
+
+	assert_cmd(
+		&["simplicity", "info", program, &witness_file(second, first)],
+		"",
+		"Execution failed: invalid program: invalid witness file: witness HASH has 512 bits, but the program expects 256\n",
+	);
+	assert_cmd(
+		&["simplicity", "info", program, r#"{"HASH": {"value": "0x00", "type": "u8"}}"#],
+		"",
+		"Execution failed: invalid program: invalid witness file: the program has 2 witness nodes, but the file gives 1 values\n",
+	);
+}
+
+#[test]
+fn cli_simplicity_info_source_map() {
+	let program = concat!(
+		"5PugUJtN8SDh0yBFHxQXLEZHZkba+NDW2oDoTJhsxeBz+A7U3PAhAoQYckgSasjmcVRCRXQmYAIq4WDF",
+		"4UsJ7AwqF1hL9cVIyFlhwCtu/AEMAxCa0kIMPwAUCxarkc113LwehOp6MgcZy/xtyV5RlPnsqZbVWnst",
+		"doxRHioxDhgGJAoSQbcKNWJzAu99qFH3Wh9HF0gSGitpeJMKWMyu4jCUAb0bbp/LsAGGAYgagOEgcRkC",
+		"hJBuKjcVnCoWLNug5n4KrWbIJljsDH8qWizDjD9hqJKs0No6Ez/56tZohz3GDAMQtbBzBEX+oDjSJpgM",
+		"Lm9+S+nolYSNH9l/IQDbQwBMtOrd78UGAYhcB4Fw5vE6GEjgGe+I3i56PBVh0YKLO+DykN75/uv1TalC",
+		"SUcsDAMSBQkg/II43IYUOKBZtjDm7yVnAtI8+S8yl59PzZ/zkJz3syU4qvsOOiPsQAebHRMMAxA3hcIH",
+		"5MggHFoHJYDk4A==",
+	);
+
+	let info = assert_deserialize_cmd(&["simplicity", "info", "--source-map", program], |s| {
+		serde_json::from_slice::<serde_json::Value>(s)
+	});
+	let map = &info["source_map"];
+	let nodes = map["nodes"].as_array().unwrap();
+	assert!(!nodes.is_empty());
+	// The nodes follow each other, and the encoding ends with the last of them.
+	let mut offset = map["header_bits"].as_u64().unwrap();
+	for (index, node) in nodes.iter().enumerate() {
+		assert_eq!(node["index"], index);
+		assert_eq!(node["start"], offset);
+		offset = node["end"].as_u64().unwrap();
+		for child in node["children"].as_array().into_iter().flatten() {
+			assert!(child.as_u64().unwrap() < index as u64);
+		}
+	}
+	assert_eq!(map["bits"], offset);
+	assert_eq!(nodes.iter().filter(|node| node["node"] == "witness").count(), 2);
+
+	// The map is only given when asked for.
+	let info = assert_deserialize_cmd(&["simplicity", "info", program], |s| {
+		serde_json::from_slice::<serde_json::Value>(s)
+	});
+	assert!(info.get("source_map").is_none());
+}
+
+#[test]
+fn cli_simplicity_info_unconstrained_witness() {
+	// Both witnesses of this program, a hash and a signature, are checked.
+	let program = concat!(
+		"5PugUJtN8SDh0yBFHxQXLEZHZkba+NDW2oDoTJhsxeBz+A7U3PAhAoQYckgSasjmcVRCRXQmYAIq4WDF",
+		"4UsJ7AwqF1hL9cVIyFlhwCtu/AEMAxCa0kIMPwAUCxarkc113LwehOp6MgcZy/xtyV5RlPnsqZbVWnst",
+		"doxRHioxDhgGJAoSQbcKNWJzAu99qFH3Wh9HF0gSGitpeJMKWMyu4jCUAb0bbp/LsAGGAYgagOEgcRkC",
+		"hJBuKjcVnCoWLNug5n4KrWbIJljsDH8qWizDjD9hqJKs0No6Ez/56tZohz3GDAMQtbBzBEX+oDjSJpgM",
+		"Lm9+S+nolYSNH9l/IQDbQwBMtOrd78UGAYhcB4Fw5vE6GEjgGe+I3i56PBVh0YKLO+DykN75/uv1TalC",
+		"SUcsDAMSBQkg/II43IYUOKBZtjDm7yVnAtI8+S8yl59PzZ/zkJz3syU4qvsOOiPsQAebHRMMAxA3hcIH",
+		"5MggHFoHJYDk4A==",
+	);
+	let info = assert_deserialize_cmd(&["simplicity", "info", program], |s| {
+		serde_json::from_slice::<serde_json::Value>(s)
+	});
+	assert!(info.get("warnings").is_none());
+
+	// comp witness (comp (jet eq_32) (jet verify)) checks its witness ...
+	let info = assert_deserialize_cmd(&["simplicity", "info", "xfNuOAQDAA=="], |s| {
+		serde_json::from_slice::<serde_json::Value>(s)
+	});
+	assert!(info.get("warnings").is_none());
+
+	// ... but comp witness (comp (jet add_32) unit) only computes with it.
+	let program = "xfS4pBAMAA==";
+	let info = assert_deserialize_cmd(&["simplicity", "info", program], |s| {
+		serde_json::from_slice::<serde_json::Value>(s)
+	});
+	assert_eq!(
+		info["warnings"][0],
+		"witness node 0 is not checked by any assertion, so a third party may be able to change its value"
+	);
+}
+
+#[test]
+fn cli_simplicity_info_tapleaf() {
+	let info = assert_deserialize_cmd(&["simplicity", "info", "xfNuOAQDAA=="], |s| {
+		serde_json::from_slice::<serde_json::Value>(s)
+	});
+	let cmr = info["cmr"].as_str().unwrap();
+	assert_eq!(info["tapleaf"]["script_hex"], cmr);
+	assert_eq!(info["tapleaf"]["leaf_version"], 0xbe);
+
+	let script = elements::Script::from(hex::decode(cmr).unwrap());
+	let version = elements::taproot::LeafVersion::from_u8(0xbe).unwrap();
+	let leaf_hash = elements::taproot::TapLeafHash::from_script(&script, version);
+	assert_eq!(info["tapleaf"]["leaf_hash"], leaf_hash.to_string());
+
+	// Another leaf version gives another leaf, and so other addresses.
+	let other = assert_deserialize_cmd(
+		&["simplicity", "info", "xfNuOAQDAA==", "--leaf-version", "c4"],
+		|s| serde_json::from_slice::<serde_json::Value>(s),
+	);
+	assert_eq!(other["tapleaf"]["leaf_version"], 0xc4);
+	assert_eq!(other["tapleaf"]["script_hex"], cmr);
+	assert_ne!(other["tapleaf"]["leaf_hash"], info["tapleaf"]["leaf_hash"]);
+	assert_ne!(other["liquid_address_unconf"], info["liquid_address_unconf"]);
+
+	// Leaf versions are even, and 0x50 marks an annex.
+	assert_cmd(
+		&["simplicity", "info", "xfNuOAQDAA==", "--leaf-version", "c5"],
+		"",
+		"Execution failed: invalid leaf-version: c5\n",
+	);
+}
+
+#[test]
+fn cli_simplicity_check_prune() {
+	// comp (pair (injl unit) unit) (case unit (comp iden unit)), and the same
+	// with its right branch hidden, correctly and with the wrong CMR.
+	let unpruned = "0JIEiUAQNAxA";
+	let pruned = "zSQJEtlCA6Y/96n58xADeZgSwMvMFv98VS8okM+5B3mFfUJ84YBg";
+	let wrong = "zSQJEtiBQgTH7obWgsFZfeOG33SXybK74wNS0V/Va9WEj1v+4YBg";
+
+	let check = assert_deserialize_cmd(&["simplicity", "check-prune", unpruned, pruned], |s| {
+		serde_json::from_slice::<serde_json::Value>(s)
+	});
+	assert_eq!(check["valid"], true);
+	assert_eq!(check["hidden_nodes"], 1);
+	assert_eq!(check["cmr"], check["pruned_cmr"]);
+
+	let check = assert_deserialize_cmd(&["simplicity", "check-prune", unpruned, wrong], |s| {
+		serde_json::from_slice::<serde_json::Value>(s)
+	});
+	assert_eq!(check["valid"], false);
+	assert_ne!(check["cmr"], check["pruned_cmr"]);
+	assert!(check["mismatch"].as_str().unwrap().starts_with("pruned node 4 hides CMR"));
+
+	// A hidden node of the unpruned program is not a pruning of anything.
+	let check = assert_deserialize_cmd(&["simplicity", "check-prune", pruned, unpruned], |s| {
+		serde_json::from_slice::<serde_json::Value>(s)
+	});
+	assert_eq!(check["valid"], false);
+}
+
+#[test]
+fn cli_simplicity_hide_branches() {
+	// comp (pair (injl unit) unit) (case unit (comp iden unit)), whose case
+	// node is node 6.
+	let program = "0JIEiUAQNAxA";
+	let hide = |keep: &[&str]| {
+		let mut args = vec!["simplicity", "hide-branches", program, ""];
+		for branch in keep {
+			args.extend(["--keep", branch]);
+		}
+		assert_deserialize_cmd(&args, |s| serde_json::from_slice::<serde_json::Value>(s))
+	};
+
+	let hidden = hide(&["6:left"]);
+	assert_eq!(hidden["hidden_branches"], 1);
+	assert_eq!(hidden["program_base64"], "zSQJEtlCA6Y/96n58xADeZgSwMvMFv98VS8okM+5B3mFfUJ84YBg");
+	let check = assert_deserialize_cmd(
+		&["simplicity", "check-prune", program, hidden["program_base64"].as_str().unwrap()],
+		|s| serde_json::from_slice::<serde_json::Value>(s),
+	);
+	assert_eq!(check["valid"], true);
+
+	// Without any branch chosen, nothing is hidden.
+	let unchanged = hide(&[]);
+	assert_eq!(unchanged["hidden_branches"], 0);
+	assert_eq!(unchanged["program_base64"], program);
+
+	assert_cmd(
+		&["simplicity", "hide-branches", program, "", "--keep", "5:left"],
+		"",
+		"Execution failed: node 5 is not a case node of the program\n",
+	);
+	assert_cmd(
+		&["simplicity", "hide-branches", program, "", "--keep", "6:left", "--keep", "6:right"],
+		"",
+		"Execution failed: both branches of case node 6 were chosen\n",
+	);
+}
+
+#[test]
+fn cli_simplicity_webide() {
+	let program = concat!(
+		"5PugUJtN8SDh0yBFHxQXLEZHZkba+NDW2oDoTJhsxeBz+A7U3PAhAoQYckgSasjmcVRCRXQmYAIq4WDF",
+		"4UsJ7AwqF1hL9cVIyFlhwCtu/AEMAxCa0kIMPwAUCxarkc113LwehOp6MgcZy/xtyV5RlPnsqZbVWnst",
+		"doxRHioxDhgGJAoSQbcKNWJzAu99qFH3Wh9HF0gSGitpeJMKWMyu4jCUAb0bbp/LsAGGAYgagOEgcRkC",
+		"hJBuKjcVnCoWLNug5n4KrWbIJljsDH8qWizDjD9hqJKs0No6Ez/56tZohz3GDAMQtbBzBEX+oDjSJpgM",
+		"Lm9+S+nolYSNH9l/IQDbQwBMtOrd78UGAYhcB4Fw5vE6GEjgGe+I3i56PBVh0YKLO+DykN75/uv1TalC",
+		"SUcsDAMSBQkg/II43IYUOKBZtjDm7yVnAtI8+S8yl59PzZ/zkJz3syU4qvsOOiPsQAebHRMMAxA3hcIH",
+		"5MggHFoHJYDk4A==",
+	);
+	let witness = concat!(
+		"9bef8d556d80e43ae7e0becb3a7e6838b95defe45896ed6075bb9035d06c9964e09e91b2ee81dd61d97ec6e83bfdb03c",
+		"28f79e0e7038a98964ea5c29cde0b2319878a86dc9e5a0d00269215a43754755a6e173246ad7d330eb82d27e779fd90a",
+	);
+	let txid = "6ccab2797962fc13fafed9705bbf8e908327b215acf772b49b222f2f090e3eb3";
+	let recipient = "tex1pyuvwaqedernfdc7c6qf7r67en3szas6s0sdegzq3jxduhj4mhlestul9m7";
+	let bundle = assert_deserialize_cmd(
+		&[
+			"--network",
+			"liquidtestnet",
+			"simplicity",
+			"export-webide",
+			program,
+			witness,
+			"--txid",
+			txid,
+			"--vout",
+			"0",
+			"--value",
+			"100000",
+			"--fee",
+			"300",
+			"--recipient",
+			recipient,
+		],
+		|s| serde_json::from_slice::<serde_json::Value>(s),
+	);
+	assert_eq!(bundle["program"], program);
+	assert_eq!(bundle["witness"], witness);
+	assert_eq!(
+		bundle["environment"]["genesis_hash"],
+		"a771da8e52ee6ad581ed1e9a99825e5b3b7992225534eaa2ae23244fe26ab1c1"
+	);
+
+	let bundle = bundle.to_string();
+	let import = assert_deserialize_cmd(
+		&["--network", "liquidtestnet", "simplicity", "import-webide", &bundle],
+		|s| serde_json::from_slice::<serde_json::Value>(s),
+	);
+	assert_eq!(import["cmr"], "7fd424f70498ef2fb6dd05ffbb7368dc796e6c47f24404e0b1ff138cfce89a7a");
+	assert_eq!(import["witness_hex"], witness);
+	let inputs = import["pset_create"]["inputs"].as_str().unwrap();
+	let outputs = import["pset_create"]["outputs"].as_str().unwrap();
+	let outputs_json: serde_json::Value = serde_json::from_str(outputs).unwrap();
+	assert_eq!(outputs_json[0]["amount"], 0.000997);
+	assert_eq!(outputs_json[1]["address"], "fee");
+	assert_eq!(
+		outputs_json[1]["asset"],
+		"144c654344aa716d6f3abcc1ca90e5641e4e2a7f633bc09fe3baf64585819a49"
+	);
+	assert_deserialize_cmd(&["simplicity", "pset", "create", inputs, outputs], |s| {
+		serde_json::from_slice::<serde_json::Value>(s)
+	});
+
+	// Without a known asset, there is no transaction to create.
+	let import = assert_deserialize_cmd(&["simplicity", "import-webide", &bundle], |s| {
+		serde_json::from_slice::<serde_json::Value>(s)
+	});
+	assert!(import.get("pset_create").is_none());
+}
+
+#[test]
+fn cli_simplicity_pset_hwi() {
+	let cmr = "7fd424f70498ef2fb6dd05ffbb7368dc796e6c47f24404e0b1ff138cfce89a7a";
+	let asset = "144c654344aa716d6f3abcc1ca90e5641e4e2a7f633bc09fe3baf64585819a49";
+	let spk = "5120f08261d61ccf2d9fbf6dd99c1e7541a29c06c248f8c59b7c09948abdd9e47081";
+	let internal_key = "50929b74c1a04954b78b4b6035e97a5e078a5a0f28ec96d547bfee9ace803ac0";
+	let inputs = format!(r#"[{{"txid":"{}","vout":0}}]"#, "11".repeat(32));
+	let outputs = format!(
+		r#"[{{"address":"tex1p7zpxr4sueukel0mdmxwpua2p52wqdsjglrzeklqfjj9tmk0ywzqs29s49v","asset":"{}","amount":0.0009}}]"#,
+		asset
+	);
+	let run = |args: &[&str]| {
+		let output =
+			self_command().args(["--network", "liquidtestnet"]).args(args).output().unwrap();
+		assert!(output.status.success(), "{}", String::from_utf8_lossy(&output.stderr));
+		String::from_utf8(output.stdout).unwrap()
+	};
+	let pset = run(&["simplicity", "pset", "create", "--raw", &inputs, &outputs]);
+	let utxo = format!("{}:{}:0.001", spk, asset);
+	let pset = run(&[
+		"simplicity",
+		"pset",
+		"attach-utxo",
+		"--raw",
+		&pset,
+		"0",
+		"-i",
+		&utxo,
+		"-p",
+		internal_key,
+		"-c",
+		cmr,
+	]);
+
+	let export: serde_json::Value =
+		serde_json::from_str(&run(&["simplicity", "pset", "hwi-export", &pset])).unwrap();
+	let sighash = "cce1c08fac1245345e6fb3e89e4fcbc339fbf3910647297982079df3374cd0c6";
+	assert_eq!(
+		export,
+		serde_json::json!({ "sighashes": [{
+			"input": 0,
+			"cmr": cmr,
+			"leaf_hash": "6da346a8b4512054fecd71742d7218a555b35af33889d12b6d07363c95b658dd",
+			"sighash": sighash,
+			"signers": [],
+		}] }),
+	);
+
+	// Sign as an external signer would, then import the signature.
+	let secret_key = "0000000000000000000000000000000000000000000000000000000000000001";
+	let pubkey = "79be667ef9dcbbac55a06295ce870b07029bfcdb2dce28d959f2815b16f81798";
+	let signed: serde_json::Value =
+		serde_json::from_str(&run(&["simplicity", "sighash", &pset, "0", cmr, "-x", secret_key]))
+			.unwrap();
+	assert_eq!(signed["sighash"], sighash);
+	let signatures = serde_json::json!([{
+		"input": 0,
+		"pubkey": pubkey,
+		"signature": signed["signature"],
+	}])
+	.to_string();
+	let imported: serde_json::Value =
+		serde_json::from_str(&run(&["simplicity", "pset", "hwi-import", &pset, &signatures]))
+			.unwrap();
+	assert_eq!(imported["updated_values"], serde_json::json!(["tap_script_sigs"]));
+	assert_ne!(imported["pset"], pset);
+
+	// A signature over another chain's sighash is refused.
+	assert_cmd(
+		&["--network", "liquid", "simplicity", "pset", "hwi-import", &pset, &signatures],
+		"",
+		format!("Execution failed: signature for input 0 is not valid for public key {}\n", pubkey),
+	);
+}
+
+#[test]
+fn cli_simplicity_sighashes() {
+	let fixtures = assert_deserialize_cmd(&["simplicity", "gen-fixtures", "0"], |s| {
+		serde_json::from_slice::<serde_json::Value>(s)
+	});
+	let fixture = &fixtures["fixtures"][0];
+	let sighashes = |args: &[&str]| {
+		let mut all = vec!["--network", "liquidtestnet", "simplicity", "sighashes"];
+		all.extend(args);
+		assert_deserialize_cmd(&all, |s| serde_json::from_slice::<serde_json::Value>(s))
+	};
+
+	let expected = serde_json::json!({
+		"inputs": [{
+			"input": 0,
+			"key_path": "a36e2e1b2f63d34c7736a7dec31791c82cb6819be467c41a4b07bed1f046aca8",
+			"simplicity": [{ "cmr": fixture["cmr"], "sighash": fixture["sighash"] }],
+		}],
+	});
+	assert_eq!(sighashes(&[fixture["pset"].as_str().unwrap()]), expected);
+	// A raw transaction gives the leaf in its witness, but not the outputs it spends.
+	let tx = fixture["transaction"].as_str().unwrap();
+	let utxo = "5120f906d95c4bad87927b1f3d5d397cd5606344079006622878dc8e2dd22b109bd6:144c654344aa716d6f3abcc1ca90e5641e4e2a7f633bc09fe3baf64585819a49:0.001";
+	assert_eq!(sighashes(&["-i", utxo, tx]), expected);
+	assert_cmd(
+		&["--network", "liquidtestnet", "simplicity", "sighashes", tx],
+		"",
+		"Execution failed: with a raw transaction, input-utxos must be provided\n",
+	);
+	// Only taproot outputs have a key spend sighash.
+	let p2sh = "a914774b87be1ef871d82a01edbb89a70bf4bb59310387:144c654344aa716d6f3abcc1ca90e5641e4e2a7f633bc09fe3baf64585819a49:0.001";
+	assert!(sighashes(&["-i", p2sh, tx])["inputs"][0].get("key_path").is_none());
+}
+
+#[test]
+fn cli_simplicity_pset_airgap() {
+	let cmr = "7fd424f70498ef2fb6dd05ffbb7368dc796e6c47f24404e0b1ff138cfce89a7a";
+	let asset = "144c654344aa716d6f3abcc1ca90e5641e4e2a7f633bc09fe3baf64585819a49";
+	let spk = "5120f08261d61ccf2d9fbf6dd99c1e7541a29c06c248f8c59b7c09948abdd9e47081";
+	let internal_key = "50929b74c1a04954b78b4b6035e97a5e078a5a0f28ec96d547bfee9ace803ac0";
+	let inputs = format!(r#"[{{"txid":"{}","vout":0}}]"#, "11".repeat(32));
+	let outputs = format!(
+		r#"[{{"address":"tex1p7zpxr4sueukel0mdmxwpua2p52wqdsjglrzeklqfjj9tmk0ywzqs29s49v","asset":"{}","amount":0.0009}}]"#,
+		asset
+	);
+	let run = |args: &[&str]| {
+		let output =
+			self_command().args(["--network", "liquidtestnet"]).args(args).output().unwrap();
+		assert!(output.status.success(), "{}", String::from_utf8_lossy(&output.stderr));
+		String::from_utf8(output.stdout).unwrap()
+	};
+	let pset = run(&["simplicity", "pset", "create", "--raw", &inputs, &outputs]);
+	let utxo = format!("{}:{}:0.001", spk, asset);
+	let pset = run(&[
+		"simplicity",
+		"pset",
+		"attach-utxo",
+		"--raw",
+		&pset,
+		"0",
+		"-i",
+		&utxo,
+		"-p",
+		internal_key,
+		"-c",
+		cmr,
+	]);
+
+	let sighash = "cce1c08fac1245345e6fb3e89e4fcbc339fbf3910647297982079df3374cd0c6";
+	let export = run(&["simplicity", "pset", "export-sighashes", "--raw", &pset]);
+	assert_eq!(
+		export,
+		format!(
+			r#"[{{"input":0,"leaf_hash":"6da346a8b4512054fecd71742d7218a555b35af33889d12b6d07363c95b658dd","sighash":"{}"}}]"#,
+			sighash
+		),
+	);
+	let export: serde_json::Value = serde_json::from_str(&run(&[
+		"simplicity",
+		"pset",
+		"export-sighashes",
+		&pset,
+		"--qr-part-size",
+		"60",
+	]))
+	.unwrap();
+	let parts = export["bbqr"].as_array().unwrap();
+	assert_eq!(parts.len(), 6);
+	for (i, part) in parts.iter().enumerate() {
+		let part = part.as_str().unwrap();
+		assert!(part.len() <= 60);
+		assert!(part.starts_with(&format!("B$2J060{}", i)));
+	}
+	assert_cmd(
+		&[
+			"--network",
+			"liquidtestnet",
+			"simplicity",
+			"pset",
+			"export-sighashes",
+			&pset,
+			"--qr-part-size",
+			"10",
+		],
+		"",
+		"Execution failed: QR part size 10 is too small; parts must have at least 16 characters\n",
+	);
+
+	// Sign offline, and bring the signatures back as hex BBQr parts in any order.
+	let secret_key = "0000000000000000000000000000000000000000000000000000000000000001";
+	let pubkey = "79be667ef9dcbbac55a06295ce870b07029bfcdb2dce28d959f2815b16f81798";
+	let signed: serde_json::Value =
+		serde_json::from_str(&run(&["simplicity", "sighash", &pset, "0", cmr, "-x", secret_key]))
+			.unwrap();
+	let signatures = serde_json::json!([{
+		"input": 0,
+		"pubkey": pubkey,
+		"signature": signed["signature"],
+	}])
+	.to_string();
+	let encoded = hex::encode(&signatures).to_uppercase();
+	let (first, second) = encoded.split_at(encoded.len() / 2);
+	let (first, second) = (format!("B$HJ0200{}", first), format!("B$HJ0201{}", second));
+	let imported: serde_json::Value = serde_json::from_str(&run(&[
+		"simplicity",
+		"pset",
+		"import-signatures",
+		&pset,
+		&second,
+		&first,
+	]))
+	.unwrap();
+	assert_eq!(imported["updated_values"], serde_json::json!(["tap_script_sigs"]));
+	let from_json: serde_json::Value = serde_json::from_str(&run(&[
+		"simplicity",
+		"pset",
+		"import-signatures",
+		&pset,
+		&signatures,
+	]))
+	.unwrap();
+	assert_eq!(imported, from_json);
+
+	assert_cmd(
+		&["--network", "liquidtestnet", "simplicity", "pset", "import-signatures", &pset, &second],
+		"",
+		"Execution failed: BBQr parts [0] are missing\n",
+	);
+}
+
+#[test]
+fn cli_simplicity_gen_fixtures() {
+	let gen = || {
+		assert_deserialize_cmd(&["simplicity", "gen-fixtures", "0", "1"], |s| {
+			serde_json::from_slice::<serde_json::Value>(s)
+		})
+	};
+	let fixtures = gen();
+	assert_eq!(fixtures, gen());
+	assert_eq!(fixtures["version"], 1);
+	assert_eq!(fixtures["network"], "liquidtestnet");
+	assert_eq!(fixtures["fixtures"].as_array().unwrap().len(), 2);
+
+	let fixture = &fixtures["fixtures"][0];
+	let cmr = "2f4a1f69c5be41c397c7d92a6537fb9ce3c6245ceff32b5c0225973cda6461bb";
+	let sighash = "5704618b194a12b7ea2e31bdef6f50e294a24db7fce8b09349df6f34a71656bd";
+	assert_eq!(fixture["seed"], "0");
+	assert_eq!(
+		fixture["public_key"],
+		"246b9aebf3600fe7a4baf2c751c69a481053dfe5807ec29b63eca60e9f8c986f"
+	);
+	assert_eq!(fixture["cmr"], cmr);
+	assert_eq!(
+		fixture["address"],
+		"tex1plyrdjhzt4krey7cl84wnjlx4vp35gpusqe3zs7xu3ckay2csn0tqqk8gst"
+	);
+	assert_eq!(fixture["sighash"], sighash);
+	assert_eq!(fixture["witness"], fixture["signature"]);
+	assert_ne!(fixture["cmr"], fixtures["fixtures"][1]["cmr"]);
+
+	// The other commands agree with the fixture.
+	let program = fixture["program"].as_str().unwrap();
+	let witness = fixture["witness"].as_str().unwrap();
+	let info = assert_deserialize_cmd(&["simplicity", "info", program, witness], |s| {
+		serde_json::from_slice::<serde_json::Value>(s)
+	});
+	assert_eq!(info["cmr"], cmr);
+	assert_eq!(info["liquid_testnet_address_unconf"], fixture["address"]);
+	let pset = fixture["pset"].as_str().unwrap();
+	let checked = assert_deserialize_cmd(
+		&[
+			"--network",
+			"liquidtestnet",
+			"simplicity",
+			"sighash",
+			pset,
+			"0",
+			cmr,
+			"-p",
+			fixture["public_key"].as_str().unwrap(),
+			"-s",
+			fixture["signature"].as_str().unwrap(),
+		],
+		|s| serde_json::from_slice::<serde_json::Value>(s),
+	);
+	assert_eq!(checked["sighash"], sighash);
+	assert_eq!(checked["valid_signature"], true);
+	assert_cmd(
+		&["simplicity", "pset", "extract", fixture["finalized_pset"].as_str().unwrap()],
+		fixture["transaction"].to_string(),
+		"",
+	);
+}
+
+#[test]
+fn cli_simplicity_bench() {
+	let fixtures = assert_deserialize_cmd(&["simplicity", "gen-fixtures", "0"], |s| {
+		serde_json::from_slice::<serde_json::Value>(s)
+	});
+	let fixture = &fixtures["fixtures"][0];
+	let args = |iterations| {
+		vec![
+			"--network",
+			"liquidtestnet",
+			"simplicity",
+			"bench",
+			fixture["pset"].as_str().unwrap(),
+			"0",
+			fixture["program"].as_str().unwrap(),
+			fixture["witness"].as_str().unwrap(),
+			"-n",
+			iterations,
+		]
+	};
+
+	let info =
+		assert_deserialize_cmd(&args("3"), |s| serde_json::from_slice::<serde_json::Value>(s));
+	assert_eq!(info["cmr"], fixture["cmr"]);
+	assert_eq!(info["iterations"], 3);
+	assert!(info["cost"].as_u64().unwrap() > 0);
+	let mean = info["mean_ns"].as_u64().unwrap();
+	assert!(info["min_ns"].as_u64().unwrap() <= mean);
+	assert!(mean <= info["max_ns"].as_u64().unwrap());
+
+	assert_cmd(&args("0"), "", "Execution failed: the number of iterations must be at least 1\n");
+}
+
+#[test]
+fn cli_simplicity_pset_run_max_jets() {
+	let fixtures = assert_deserialize_cmd(&["simplicity", "gen-fixtures", "0"], |s| {
+		serde_json::from_slice::<serde_json::Value>(s)
+	});
+	let fixture = &fixtures["fixtures"][0];
+	let pset = fixture["pset"].as_str().unwrap();
+	let program = fixture["program"].as_str().unwrap();
+	let witness = fixture["witness"].as_str().unwrap();
+	let run = |extra: &[&str]| {
+		let mut args = vec!["--network", "liquidtestnet", "simplicity", "pset", "run"];
+		args.extend([pset, "0", program, witness]);
+		args.extend(extra);
+		assert_deserialize_cmd(&args, |s| serde_json::from_slice::<serde_json::Value>(s))
+	};
+
+	let full = run(&[]);
+	assert_eq!(full["success"], true);
+	assert_eq!(full["jets"].as_array().unwrap().len(), 2);
+	assert!(full.get("truncated").is_none());
+	assert_eq!(run(&["--max-jets", "2"]), full);
+	assert_eq!(run(&["--all-jets"]), full);
+
+	let bounded = run(&["--max-jets", "0"]);
+	assert_eq!(bounded["jets"], serde_json::json!([]));
+	assert_eq!(
+		bounded["truncated"],
+		serde_json::json!({
+			"omitted": 2,
+			"last_jets": [],
+			"counts": { "bip_0340_verify": 1, "sig_all_hash": 1 },
+		}),
+	);
+
+	// With a jets file, the calls go to the file rather than the output.
+	let path = std::env::temp_dir().join(format!("hal-simplicity-cli-{}.jets", std::process::id()));
+	let streamed = run(&["--jets-file", path.to_str().unwrap()]);
+	let lines = std::fs::read_to_string(&path).unwrap();
+	std::fs::remove_file(&path).unwrap();
+	assert_eq!(streamed, bounded);
+	let jets: Vec<serde_json::Value> =
+		lines.lines().map(|line| serde_json::from_str(line).unwrap()).collect();
+	assert_eq!(serde_json::Value::from(jets), full["jets"]);
+}
+
+#[test]
+fn cli_simplicity_pset_run_diff() {
+	let fixtures = assert_deserialize_cmd(&["simplicity", "gen-fixtures", "0"], |s| {
+		serde_json::from_slice::<serde_json::Value>(s)
+	});
+	let fixture = &fixtures["fixtures"][0];
+	let pset = fixture["pset"].as_str().unwrap();
+	let program = fixture["program"].as_str().unwrap();
+	let witness = fixture["witness"].as_str().unwrap();
+	// The witness is a signature, which fails to verify once a bit is flipped.
+	let mut bad_witness = hex::decode(witness).unwrap();
+	*bad_witness.last_mut().unwrap() ^= 1;
+	let bad_witness = hex::encode(bad_witness);
+	let run_diff = |other: &str| {
+		assert_deserialize_cmd(
+			&[
+				"--network",
+				"liquidtestnet",
+				"simplicity",
+				"pset",
+				"run-diff",
+				pset,
+				"0",
+				program,
+				witness,
+				other,
+			],
+			|s| serde_json::from_slice::<serde_json::Value>(s),
+		)
+	};
+
+	let same = run_diff(witness);
+	assert_eq!(same["first_success"], true);
+	assert_eq!(same["second_success"], true);
+	assert!(same.get("divergence").is_none());
+
+	let diff = run_diff(&bad_witness);
+	assert_eq!(diff["second_success"], false);
+	let divergence = &diff["divergence"];
+	assert_eq!(divergence["step"], 1);
+	assert_eq!(divergence["first"]["jet"], "bip_0340_verify");
+	assert_eq!(divergence["first"]["success"], true);
+	assert_eq!(divergence["second"]["jet"], "bip_0340_verify");
+	assert_eq!(divergence["second"]["success"], false);
+}
+
+#[test]
+fn cli_simplicity_pset_blinding() {
+	let fixtures = assert_deserialize_cmd(&["simplicity", "gen-fixtures", "0"], |s| {
+		serde_json::from_slice::<serde_json::Value>(s)
+	});
+	let fixture = &fixtures["fixtures"][0];
+	let pset = fixture["pset"].as_str().unwrap();
+	let program = fixture["program"].as_str().unwrap();
+	let witness = fixture["witness"].as_str().unwrap();
+	let blinding_key = "0290ff4e5caabef9fccfc8c1d8ba19fabe708e602e87f9df7f5695bc4bc1c9dda9";
+	let check_blinding = |pset: &str| {
+		assert_deserialize_cmd(&["simplicity", "pset", "check-blinding", pset], |s| {
+			serde_json::from_slice::<serde_json::Value>(s)
+		})
+	};
+
+	let status = check_blinding(pset);
+	assert_eq!(status["complete"], true);
+	assert_eq!(status["outputs"][0]["state"], "explicit");
+
+	let marked = assert_deserialize_cmd(
+		&["simplicity", "pset", "mark-blinding", pset, "0", blinding_key, "0"],
+		|s| serde_json::from_slice::<serde_json::Value>(s),
+	);
+	let marked = marked["pset"].as_str().unwrap();
+	let status = check_blinding(marked);
+	assert_eq!(status["complete"], false);
+	assert_eq!(status["outputs"][0]["state"], "unblinded");
+	assert_eq!(status["outputs"][0]["blinder_index"], 0);
+	assert_eq!(status["outputs"][1]["state"], "explicit");
+
+	assert_cmd(
+		&["simplicity", "pset", "mark-blinding", pset, "2", blinding_key, "0"],
+		"",
+		"Execution failed: output index 2 out-of-range for PSET with 2 outputs\n",
+	);
+	assert_cmd(
+		&["simplicity", "pset", "mark-blinding", pset, "0", blinding_key, "1"],
+		"",
+		"Execution failed: blinder index 1 out-of-range for PSET with 1 inputs\n",
+	);
+	// The program would sign for outputs which are yet to change.
+	assert_cmd(
+		&[
+			"--network",
+			"liquidtestnet",
+			"simplicity",
+			"pset",
+			"finalize",
+			marked,
+			"0",
+			program,
+			witness,
+		],
+		"",
+		"Execution failed: outputs [0] are marked for blinding but not yet blinded\n",
+	);
+}
+
 #[test]
 fn cli_tx() {
-	let expected_help = "\
+	// fetch goes through the daemon, so is only built with it.
+	let fetch = match cfg!(feature = "daemon") {
+		true => "    fetch           fetch a raw transaction from the daemon's chain backend\n",
+		false => "",
+	};
+	let expected_help = format!(
+		"\
 hal-simplicity-tx 0.2.0
 manipulate transactions
 
 USAGE:
-    hal-simplicity tx [FLAGS] <SUBCOMMAND>
+    hal-simplicity tx [FLAGS] [OPTIONS] <SUBCOMMAND>
 
 FLAGS:
-    -h, --help       Prints help information
-    -v, --verbose    print verbose logging output to stderr
+    -h, --help           Prints help information
+        --json-errors    print errors to stderr as JSON objects with a stable error code
+        --offline        refuse to contact any daemon or backend, only computing locally
+        --plain          print pretty output without colors
+        --raw            print only the main result, such as a PSET or transaction, without JSON
+    -v, --verbose        print verbose logging output to stderr
+
+OPTIONS:
+        --format <FORMAT>      output format: json (default), yaml, pretty for a terminal, or lwk for LWK wallets
+        --network <NETWORK>    network to work on: elementsregtest (default), liquid or liquidtestnet
 
 SUBCOMMANDS:
-    create    create a raw transaction from JSON
-    decode    decode a raw transaction to JSON
-";
-	assert_cmd(&["tx"], "", expected_help);
-	assert_cmd(&["tx", "-h"], expected_help, "");
-	assert_cmd(&["tx", "--help"], expected_help, "");
-	assert_cmd(&["tx", "--help", "xyz"], expected_help, "");
+    asset-id        compute the asset and reissuance token IDs of an issuance
+    create          create a raw transaction from JSON
+    decode          decode a raw transaction to JSON
+{}    output-nonce    compute the nonce of a blinded output by ECDH
+    rewind          rewind the rangeproof of a blinded output with its nonce
+    verify          check the witness of every input of a signed transaction
+",
+		fetch
+	);
+	assert_cmd(&["tx"], "", &expected_help);
+	assert_cmd(&["tx", "-h"], &expected_help, "");
+	assert_cmd(&["tx", "--help"], &expected_help, "");
+	assert_cmd(&["tx", "--help", "xyz"], &expected_help, "");
+}
+
+#[test]
+fn cli_tx_asset_id() {
+	// Test vectors from Elements Core
+	assert_cmd(
+		&[
+			"tx",
+			"asset-id",
+			"-p",
+			"05a047c98e82a848dee94efcf32462b065198bebf2404d201ba2e06db30b28f4:0",
+		],
+		r#"{
+  "prevout": "05a047c98e82a848dee94efcf32462b065198bebf2404d201ba2e06db30b28f4:0",
+  "contract_hash": "0000000000000000000000000000000000000000000000000000000000000000",
+  "entropy": "746f447f691323502cad2ef646f932613d37a83aeaa2133185b316648df4b70a",
+  "asset_id": "dcd60818d863b5c026c40b2bc3ba6fdaf5018bcc8606c18adf7db4da0bcd8533",
+  "reissuance_token": "c1adb114f4f87d33bf9ce90dd4f9ca523dd414d6cd010a7917903e2009689530"
+}"#,
+		"",
+	);
+	assert_cmd(
+		&[
+			"tx",
+			"asset-id",
+			"-p",
+			"c76664aa4be760056dcc39b59637eeea8f3c3c3b2aeefb9f23a7b99945a2931e:1",
+			"--confidential",
+			"--raw",
+		],
+		"2ec6c1a06e895b06fffb8dc36084255f890467fb906565b0c048d4c807b4a129",
+		"",
+	);
+	assert_cmd(
+		&[
+			"tx",
+			"asset-id",
+			"-p",
+			"ee45365ddb62e8822182fbdd132fb156b4991e0b7411cff4aab576fd964f2edb:0",
+			"-c",
+			"e06e6d4933e76afd7b9cc6a013e0855aa60bbe6d2fca1c27ec6951ff5f1a20c9",
+			"-a",
+			"8eebf6109bca0331fe559f0cbd1ef846a2bbb6812f3ae3d8b0b610170cc21a4e",
+		],
+		r#"{
+  "prevout": "ee45365ddb62e8822182fbdd132fb156b4991e0b7411cff4aab576fd964f2edb:0",
+  "contract_hash": "e06e6d4933e76afd7b9cc6a013e0855aa60bbe6d2fca1c27ec6951ff5f1a20c9",
+  "entropy": "1922da340705eef526640b49d28b08928630d1ad52db0f945f3c389267e292c9",
+  "asset_id": "8eebf6109bca0331fe559f0cbd1ef846a2bbb6812f3ae3d8b0b610170cc21a4e",
+  "reissuance_token": "eb02cbc591c9ede071625c129f0a1fab386202cb27a894a45be0d564e961d6bc",
+  "matches": true
+}"#,
+		"",
+	);
+	// A reissuance gives only its entropy
+	assert_cmd(
+		&[
+			"tx",
+			"asset-id",
+			"-e",
+			"bc67a13736341d8ad19e558433483a38cae48a44a5a8b5598ca0b01b5f9f9f41",
+			"--confidential",
+			"-a",
+			"dcd60818d863b5c026c40b2bc3ba6fdaf5018bcc8606c18adf7db4da0bcd8533",
+		],
+		r#"{
+  "entropy": "bc67a13736341d8ad19e558433483a38cae48a44a5a8b5598ca0b01b5f9f9f41",
+  "asset_id": "2ec6c1a06e895b06fffb8dc36084255f890467fb906565b0c048d4c807b4a129",
+  "reissuance_token": "d09d205ff7c626ca98c91fed24787ff747fec62194ed1b7e6ef6cc775a1a1fdc",
+  "matches": false
+}"#,
+		"",
+	);
+	assert_cmd(
+		&[
+			"tx",
+			"asset-id",
+			"-e",
+			"bc67a13736341d8ad19e558433483a38cae48a44a5a8b5598ca0b01b5f9f9f41",
+			"-c",
+			"e06e6d4933e76afd7b9cc6a013e0855aa60bbe6d2fca1c27ec6951ff5f1a20c9",
+		],
+		"",
+		"Execution failed: an asset entropy already commits to the issuance prevout and contract; give one or the other\n",
+	);
+	assert_cmd(&["tx", "asset-id"], "", "Execution failed: no previous output provided\n");
 }
 
 #[test]
@@ -1164,33 +2977,43 @@ hal-simplicity-tx-create 0.2.0
 create a raw transaction from JSON
 
 USAGE:
-    hal-simplicity tx create [FLAGS] [tx-info]
+    hal-simplicity tx create [FLAGS] [OPTIONS] [tx-info]
 
 FLAGS:
-    -h, --help          Prints help information
-    -r, --raw-stdout    output the raw bytes of the result to stdout
-    -v, --verbose       print verbose logging output to stderr
+    -h, --help           Prints help information
+        --json-errors    print errors to stderr as JSON objects with a stable error code
+        --offline        refuse to contact any daemon or backend, only computing locally
+        --plain          print pretty output without colors
+        --raw            print only the main result, such as a PSET or transaction, without JSON
+    -r, --raw-stdout     output the raw bytes of the result to stdout
+    -v, --verbose        print verbose logging output to stderr
+
+OPTIONS:
+        --format <FORMAT>      output format: json (default), yaml, pretty for a terminal, or lwk for LWK wallets
+    -i, --inputs <inputs>      input outpoints, as for `pset create`, instead of tx-info (JSON)
+        --network <NETWORK>    network to work on: elementsregtest (default), liquid or liquidtestnet
+    -o, --outputs <outputs>    outputs, as for `pset create`, instead of tx-info (JSON)
 
 ARGS:
     <tx-info>    the transaction info in JSON
 ";
-	assert_cmd(&["tx", "create"], "Execution failed: no 'tx-info' argument given\n", "");
+	assert_cmd(&["tx", "create"], "", "Execution failed: no 'tx-info' argument given\n");
 	assert_cmd(&["tx", "create", "-h"], expected_help, "");
 	assert_cmd(&["tx", "create", "--help"], expected_help, "");
 	assert_cmd(&["tx", "create", "--help", "xyz"], expected_help, "");
 
 	assert_cmd(
 		&["tx", "create", ""],
-		"Execution failed: invalid JSON provided: EOF while parsing a value at line 1 column 0\n",
 		"",
+		"Execution failed: invalid JSON provided: EOF while parsing a value at line 1 column 0\n",
 	);
-	assert_cmd(&["tx", "create", "{ }"], "Execution failed: field \"version\" is required.\n", "");
+	assert_cmd(&["tx", "create", "{ }"], "", "Execution failed: field \"version\" is required.\n");
 	// FIXME I have no idea what is wrong here. But putting a test in to track fixing
 	//  whatever is causing this nonsense error.
 	assert_cmd(
 		&["tx", "create", "{ \"version\": 10, \"locktime\": 10 }"],
-		"Execution failed: invalid JSON provided: expected value at line 1 column 30\n",
 		"",
+		"Execution failed: invalid JSON provided: expected value at line 1 column 30\n",
 	);
 	// FIXME: lol, replace this locktime format with something sane
 	assert_cmd(
@@ -1216,6 +3039,141 @@ ARGS:
 	let output = self_command().args(args.iter()).output().unwrap();
 	assert_eq!(output.stdout.as_hex().to_string(), "0a0000000000000a000000",);
 	assert_eq!(output.stderr, Vec::<u8>::new());
+
+	// The simple syntax of `pset create`, with fee and OP_RETURN outputs.
+	let asset = "144c654344aa716d6f3abcc1ca90e5641e4e2a7f633bc09fe3baf64585819a49";
+	let inputs = format!("[{{ \"txid\": \"{asset}\", \"vout\": 1 }}]");
+	let outputs = format!(
+		"[{{ \"address\": \"fee\", \"asset\": \"{asset}\", \"amount\": 0.0001 }}, \
+		{{ \"address\": \"data:deadbeef\", \"asset\": \"{asset}\", \"amount\": 0 }}]"
+	);
+	assert_cmd(
+		&["tx", "create", "-i", &inputs, "-o", &outputs],
+		"020000000001499a818545f6bae39fc03b637f2a4e1e64e590cac1bc3a6f6d71aa4443654c140100000000ffffffff0201499a818545f6bae39fc03b637f2a4e1e64e590cac1bc3a6f6d71aa4443654c14010000000000002710000001499a818545f6bae39fc03b637f2a4e1e64e590cac1bc3a6f6d71aa4443654c1401000000000000000000066a04deadbeef00000000",
+		"",
+	);
+	assert_cmd(
+		&["tx", "create", "-i", "[]", "-o", "[{ \"address\": \"data:xy\", \"asset\": \"144c654344aa716d6f3abcc1ca90e5641e4e2a7f633bc09fe3baf64585819a49\", \"amount\": 0 }]"],
+		"",
+		"Execution failed: invalid OP_RETURN hex data: Invalid character 'x' at position 0\n",
+	);
+}
+
+#[test]
+fn cli_tx_output_nonce() {
+	let expected_help = "\
+hal-simplicity-tx-output-nonce 0.2.0
+compute the nonce of a blinded output by ECDH
+
+USAGE:
+    hal-simplicity tx output-nonce [FLAGS] [OPTIONS] <secret-key> <public-key>
+
+FLAGS:
+    -h, --help           Prints help information
+        --json-errors    print errors to stderr as JSON objects with a stable error code
+        --offline        refuse to contact any daemon or backend, only computing locally
+        --plain          print pretty output without colors
+        --raw            print only the main result, such as a PSET or transaction, without JSON
+    -v, --verbose        print verbose logging output to stderr
+    -y, --yaml           print output in YAML instead of JSON
+
+OPTIONS:
+        --format <FORMAT>      output format: json (default), yaml, pretty for a terminal, or lwk for LWK wallets
+        --network <NETWORK>    network to work on: elementsregtest (default), liquid or liquidtestnet
+
+ARGS:
+    <secret-key>    the blinding key, or the sender's ephemeral key (hex), or prompt to type it in, or env:<VAR> to
+                    read it from $VAR
+    <public-key>    the output's nonce commitment, or the blinding pubkey (hex)
+";
+	assert_cmd(&["tx", "output-nonce", "-h"], expected_help, "");
+	assert_cmd(&["tx", "output-nonce", "--help"], expected_help, "");
+
+	let blinding_key = "1111111111111111111111111111111111111111111111111111111111111111";
+	let nonce = "034216644d171cce7f4b9f58053f657a9c43313de4d55f40806b9a5e085e7390";
+	// The receiver's view, from the nonce commitment of the output.
+	let commitment = "02b434fa30aff1e889585718178c5f4c9ffb5e39135bd780e95a585efd96a43157";
+	assert_cmd(&["tx", "output-nonce", "--raw", blinding_key, commitment], nonce, "");
+	// The sender's view, from the ephemeral key used to blind it.
+	let ephemeral_key = "df29354429ac8863e0936626412ce51726eecd577b5630ad244ca0e3c49a7117";
+	let blinding_pubkey = "034f355bdcb7cc0af728ef3cceb9615d90684bb5b2ca5f859ab0f0b704075871aa";
+	assert_cmd(&["tx", "output-nonce", "--raw", ephemeral_key, blinding_pubkey], nonce, "");
+	// The key can be kept out of the command line.
+	let output = self_command()
+		.args(["tx", "output-nonce", "--raw", "env:HAL_SIMPLICITY_TEST_KEY", commitment])
+		.env("HAL_SIMPLICITY_TEST_KEY", blinding_key)
+		.output()
+		.unwrap();
+	assert!(output.status.success(), "{}", String::from_utf8_lossy(&output.stderr));
+	assert_eq!(String::from_utf8(output.stdout).unwrap().trim(), nonce);
+
+	assert_cmd(
+		&["tx", "output-nonce", blinding_key, "02"],
+		"",
+		"Execution failed: invalid public key: malformed public key\n",
+	);
+}
+
+#[test]
+fn cli_tx_rewind() {
+	let expected_help = "\
+hal-simplicity-tx-rewind 0.2.0
+rewind the rangeproof of a blinded output with its nonce
+
+USAGE:
+    hal-simplicity tx rewind [FLAGS] [OPTIONS] <raw-tx> <output-index> <nonce>
+
+FLAGS:
+    -h, --help           Prints help information
+        --json-errors    print errors to stderr as JSON objects with a stable error code
+        --offline        refuse to contact any daemon or backend, only computing locally
+        --plain          print pretty output without colors
+        --raw            print only the main result, such as a PSET or transaction, without JSON
+    -v, --verbose        print verbose logging output to stderr
+    -y, --yaml           print output in YAML instead of JSON
+
+OPTIONS:
+        --field <field>        print only the value at the given dot-separated path (e.g. outputs.1.script_pub_key.hex)
+        --format <FORMAT>      output format: json (default), yaml, pretty for a terminal, or lwk for LWK wallets
+        --network <NETWORK>    network to work on: elementsregtest (default), liquid or liquidtestnet
+
+ARGS:
+    <raw-tx>          the raw transaction in hex
+    <output-index>    the index of the blinded output (decimal)
+    <nonce>           the output's nonce, as given by output-nonce (hex)
+";
+	assert_cmd(&["tx", "rewind", "-h"], expected_help, "");
+	assert_cmd(&["tx", "rewind", "--help"], expected_help, "");
+
+	let nonce = "034216644d171cce7f4b9f58053f657a9c43313de4d55f40806b9a5e085e7390";
+	assert_cmd(
+		&["tx", "rewind", BLINDED_TX, "0", nonce],
+		r#"{
+  "value": 99000,
+  "asset": "144c654344aa716d6f3abcc1ca90e5641e4e2a7f633bc09fe3baf64585819a49",
+  "value_blinding_factor": "a3419009af21d5f4177054f29ac517ae85951d558e064d9156f933cdba78272c",
+  "asset_blinding_factor": "ad786f0d1c00fd775a6fb885f89ac6a97fcb299795e042bbaffe8ecaffdc40dd",
+  "commitments_match": true
+}"#,
+		"",
+	);
+	assert_cmd(
+		&["tx", "rewind", BLINDED_TX, "1", nonce],
+		"",
+		"Execution failed: output 1 is not blinded\n",
+	);
+	assert_cmd(
+		&["tx", "rewind", BLINDED_TX, "2", nonce],
+		"",
+		"Execution failed: output index 2 out-of-range for transaction with 2 outputs\n",
+	);
+	let wrong_nonce = "1111111111111111111111111111111111111111111111111111111111111111";
+	assert_cmd(
+		&["tx", "rewind", BLINDED_TX, "0", wrong_nonce],
+		"",
+		"Execution failed: failed to rewind the rangeproof, which needs the output's nonce: \
+		failed to verify range proof\n",
+	);
 }
 
 #[test]
@@ -1225,30 +3183,43 @@ hal-simplicity-tx-decode 0.2.0
 decode a raw transaction to JSON
 
 USAGE:
-    hal-simplicity tx decode [FLAGS] [raw-tx]
+    hal-simplicity tx decode [FLAGS] [OPTIONS] [raw-tx]
 
 FLAGS:
     -r, --elementsregtest    run in elementsregtest mode
     -h, --help               Prints help information
+        --json-errors        print errors to stderr as JSON objects with a stable error code
         --liquid             run in liquid mode
+        --offline            refuse to contact any daemon or backend, only computing locally
+        --plain              print pretty output without colors
+        --raw                print only the main result, such as a PSET or transaction, without JSON
     -v, --verbose            print verbose logging output to stderr
     -y, --yaml               print output in YAML instead of JSON
 
+OPTIONS:
+        --field <field>        print only the value at the given dot-separated path (e.g. outputs.1.script_pub_key.hex)
+        --format <FORMAT>      output format: json (default), yaml, pretty for a terminal, or lwk for LWK wallets
+        --header <header>      the mainchain block header to check the proofs of pegins against (hex)
+        --network <NETWORK>    network to work on: elementsregtest (default), liquid or liquidtestnet
+
 ARGS:
     <raw-tx>    the raw transaction in hex
 ";
-	assert_cmd(&["tx", "decode"], "Execution failed: no 'raw-tx' argument given\n", "");
-	assert_cmd(&["tx", "decode", "-h"], expected_help, "");
-	assert_cmd(&["tx", "decode", "--help"], expected_help, "");
-	assert_cmd(&["tx", "decode", "--help", "xyz"], expected_help, "");
+	assert_cmd(&["tx", "decode"], "", "Execution failed: no 'raw-tx' argument given\n");
+	// The registry feature adds --asset-registry, which cli_asset_registry checks.
+	if !cfg!(feature = "registry") {
+		assert_cmd(&["tx", "decode", "-h"], expected_help, "");
+		assert_cmd(&["tx", "decode", "--help"], expected_help, "");
+		assert_cmd(&["tx", "decode", "--help", "xyz"], expected_help, "");
+	}
 
 	assert_cmd(
 		&["tx", "decode", ""],
-		"Execution failed: invalid tx format: I/O error: failed to fill whole buffer\n",
 		"",
+		"Execution failed: invalid tx format: I/O error: failed to fill whole buffer\n",
 	);
 	// A bitcoin transaction
-	assert_cmd(&["tx", "decode", "02000000000101cd5d8addc8ed0d91d9338a1e524a87185b8bb3c1760e0a19c4ad576b217fd7ca0100000000fdffffff02f50100000000000016001468647ece9c25ab162c72dbedfe7de63db1913e39e50d00000000000016001413aac2fc1cef3dacc656bfe8fe342a03a5feac6302473044022059e6f5ccc1d89bf31a3847a464cce1fcf0e56e43633787d03ebb2ebc1899e28c02207f3f05a16a87f07fe82bfa35c509e7d969243c6215080a6775877bef113c9e7b012103b303769299ca63c9076fc8f91d6e27152a81fc884f9fe95f47fd2a262c987256b7c50d00"], "Execution failed: invalid tx format: non-minimal varint\n", "");
+	assert_cmd(&["tx", "decode", "02000000000101cd5d8addc8ed0d91d9338a1e524a87185b8bb3c1760e0a19c4ad576b217fd7ca0100000000fdffffff02f50100000000000016001468647ece9c25ab162c72dbedfe7de63db1913e39e50d00000000000016001413aac2fc1cef3dacc656bfe8fe342a03a5feac6302473044022059e6f5ccc1d89bf31a3847a464cce1fcf0e56e43633787d03ebb2ebc1899e28c02207f3f05a16a87f07fe82bfa35c509e7d969243c6215080a6775877bef113c9e7b012103b303769299ca63c9076fc8f91d6e27152a81fc884f9fe95f47fd2a262c987256b7c50d00"], "", "Execution failed: invalid tx format: non-minimal varint\n");
 	// A Liquid transaction
 	let tx_decode = r#"{
   "txid": "9523d75b48b3411a3f4ebd31b6005898deebbe748875aa6ee084b94aa8422ba6",
@@ -1261,6 +3232,11 @@ ARGS:
   "locktime": {
     "Blocks": 0
   },
+  "locktime_info": {
+    "type": "height",
+    "value": 0,
+    "enforced": false
+  },
   "inputs": [
     {
       "prevout": "0000000000000000000000000000000000000000000000000000000000000000:4294967295",
@@ -1271,6 +3247,10 @@ ARGS:
         "asm": "OP_PUSHBYTES_3 a73018 OP_PUSHBYTES_1 01"
       },
       "sequence": 4294967295,
+      "sequence_info": {
+        "relative_locktime_enabled": false,
+        "enables_absolute_locktime": false
+      },
       "is_pegin": false,
       "has_issuance": false,
       "witness": {
@@ -1371,10 +3351,9 @@ ARGS:
 	assert_cmd(&["tx", "decode", "--liquid", "0200000001010000000000000000000000000000000000000000000000000000000000000000ffffffff0603a730180101ffffffff03016d521c38ec1ea15734ae22b7c46064412829c0d0579f0a713d1c04ede979026f01000000000000000000266a240a8ce26fdbb51a2d03d4e62fdafd4a06dd7faa0d1c083aa7e27905000000000000000000016d521c38ec1ea15734ae22b7c46064412829c0d0579f0a713d1c04ede979026f010000000000000106001976a914fc26751a5025129a2fd006c6fbfa598ddd67f7e188ac016d521c38ec1ea15734ae22b7c46064412829c0d0579f0a713d1c04ede979026f01000000000000000000266a24aa21a9ede8497768bc893ee587244bf5303ac3cf482bab8e4b3fd22e8b114c2a52525ab30000000000000120000000000000000000000000000000000000000000000000000000000000000000000000000000"],
 		tx_decode.replace("2dxQzjvrkmRGSa5gwgaQn1oLtRo5pXS94oJ", "QLFdUboUPJnUzvsXKu83hUtrQ1DuxyggRg"),
 		"");
-	// FIXME both -r and --liquid are allowed, and it seems that -r wins. Should error out instead.
 	assert_cmd(&["tx", "decode", "-r", "--liquid", "0200000001010000000000000000000000000000000000000000000000000000000000000000ffffffff0603a730180101ffffffff03016d521c38ec1ea15734ae22b7c46064412829c0d0579f0a713d1c04ede979026f01000000000000000000266a240a8ce26fdbb51a2d03d4e62fdafd4a06dd7faa0d1c083aa7e27905000000000000000000016d521c38ec1ea15734ae22b7c46064412829c0d0579f0a713d1c04ede979026f010000000000000106001976a914fc26751a5025129a2fd006c6fbfa598ddd67f7e188ac016d521c38ec1ea15734ae22b7c46064412829c0d0579f0a713d1c04ede979026f01000000000000000000266a24aa21a9ede8497768bc893ee587244bf5303ac3cf482bab8e4b3fd22e8b114c2a52525ab30000000000000120000000000000000000000000000000000000000000000000000000000000000000000000000000"],
-		tx_decode,
-		"");
+		"",
+		"Execution failed: conflicting networks: elementsregtest and liquid\n");
 	// -v works but seems to do nothing
 	assert_cmd(&["tx", "decode", "-y", "0200000001010000000000000000000000000000000000000000000000000000000000000000ffffffff0603a730180101ffffffff03016d521c38ec1ea15734ae22b7c46064412829c0d0579f0a713d1c04ede979026f01000000000000000000266a240a8ce26fdbb51a2d03d4e62fdafd4a06dd7faa0d1c083aa7e27905000000000000000000016d521c38ec1ea15734ae22b7c46064412829c0d0579f0a713d1c04ede979026f010000000000000106001976a914fc26751a5025129a2fd006c6fbfa598ddd67f7e188ac016d521c38ec1ea15734ae22b7c46064412829c0d0579f0a713d1c04ede979026f01000000000000000000266a24aa21a9ede8497768bc893ee587244bf5303ac3cf482bab8e4b3fd22e8b114c2a52525ab30000000000000120000000000000000000000000000000000000000000000000000000000000000000000000000000"],
 		r#"---
@@ -1387,6 +3366,10 @@ vsize: 301
 version: 2
 locktime:
   Blocks: 0
+locktime_info:
+  type: height
+  value: 0
+  enforced: false
 inputs:
   - prevout: "0000000000000000000000000000000000000000000000000000000000000000:4294967295"
     txid: "0000000000000000000000000000000000000000000000000000000000000000"
@@ -1395,6 +3378,9 @@ inputs:
       hex: 03a730180101
       asm: OP_PUSHBYTES_3 a73018 OP_PUSHBYTES_1 01
     sequence: 4294967295
+    sequence_info:
+      relative_locktime_enabled: false
+      enables_absolute_locktime: false
     is_pegin: false
     has_issuance: false
     witness:
@@ -1456,6 +3442,911 @@ outputs:
       rangeproof: ~
     is_fee: false"#,
 		"");
+	// --field selects a single value; strings are printed bare, anything else as JSON
+	assert_cmd(&["tx", "decode", "--field", "outputs.1.script_pub_key.hex", "0200000001010000000000000000000000000000000000000000000000000000000000000000ffffffff0603a730180101ffffffff03016d521c38ec1ea15734ae22b7c46064412829c0d0579f0a713d1c04ede979026f01000000000000000000266a240a8ce26fdbb51a2d03d4e62fdafd4a06dd7faa0d1c083aa7e27905000000000000000000016d521c38ec1ea15734ae22b7c46064412829c0d0579f0a713d1c04ede979026f010000000000000106001976a914fc26751a5025129a2fd006c6fbfa598ddd67f7e188ac016d521c38ec1ea15734ae22b7c46064412829c0d0579f0a713d1c04ede979026f01000000000000000000266a24aa21a9ede8497768bc893ee587244bf5303ac3cf482bab8e4b3fd22e8b114c2a52525ab30000000000000120000000000000000000000000000000000000000000000000000000000000000000000000000000"],
+		"76a914fc26751a5025129a2fd006c6fbfa598ddd67f7e188ac",
+		"");
+	assert_cmd(&["tx", "decode", "--field", "outputs.1.value", "0200000001010000000000000000000000000000000000000000000000000000000000000000ffffffff0603a730180101ffffffff03016d521c38ec1ea15734ae22b7c46064412829c0d0579f0a713d1c04ede979026f01000000000000000000266a240a8ce26fdbb51a2d03d4e62fdafd4a06dd7faa0d1c083aa7e27905000000000000000000016d521c38ec1ea15734ae22b7c46064412829c0d0579f0a713d1c04ede979026f010000000000000106001976a914fc26751a5025129a2fd006c6fbfa598ddd67f7e188ac016d521c38ec1ea15734ae22b7c46064412829c0d0579f0a713d1c04ede979026f01000000000000000000266a24aa21a9ede8497768bc893ee587244bf5303ac3cf482bab8e4b3fd22e8b114c2a52525ab30000000000000120000000000000000000000000000000000000000000000000000000000000000000000000000000"],
+		r#"{
+  "type": "explicit",
+  "value": 262
+}"#,
+		"");
+	assert_cmd(&["tx", "decode", "--field", "outputs.3", "0200000001010000000000000000000000000000000000000000000000000000000000000000ffffffff0603a730180101ffffffff03016d521c38ec1ea15734ae22b7c46064412829c0d0579f0a713d1c04ede979026f01000000000000000000266a240a8ce26fdbb51a2d03d4e62fdafd4a06dd7faa0d1c083aa7e27905000000000000000000016d521c38ec1ea15734ae22b7c46064412829c0d0579f0a713d1c04ede979026f010000000000000106001976a914fc26751a5025129a2fd006c6fbfa598ddd67f7e188ac016d521c38ec1ea15734ae22b7c46064412829c0d0579f0a713d1c04ede979026f01000000000000000000266a24aa21a9ede8497768bc893ee587244bf5303ac3cf482bab8e4b3fd22e8b114c2a52525ab30000000000000120000000000000000000000000000000000000000000000000000000000000000000000000000000"],
+		"",
+		"Execution failed: field 'outputs.3' not found in output\n");
+}
+
+#[test]
+fn cli_tx_decode_pegin() {
+	// The block header at the start of the merkle proof.
+	let header = concat!(
+		"00000020a060086af92ac34dbbc8bd89bbbe03ef7e0016930f7fdc806ff15d163b5fda5e32105949",
+		"c748222d3e1c5b6e0a4d47f8de45b25d63f145c4056682a7b15cc3da56a2815bffff7f2000000000",
+	);
+	let field = "inputs.0.pegin_data.merkle_proof_check";
+	assert_cmd(&["tx", "decode", "--field", field, PEGIN_TX], "{\n  \"valid\": true\n}", "");
+	assert_cmd(
+		&["tx", "decode", "--field", field, "--header", header, PEGIN_TX],
+		"{\n  \"valid\": true,\n  \"header_matches\": true\n}",
+		"",
+	);
+	// Another header
+	assert_cmd(
+		&["tx", "decode", "--field", field, "--header", &header.replace("a060", "b060"), PEGIN_TX],
+		"{\n  \"valid\": true,\n  \"header_matches\": false\n}",
+		"",
+	);
+	// A proof which does not commit to the mainchain transaction
+	assert_cmd(
+		&["tx", "decode", "--field", field, &PEGIN_TX.replace("946c969d", "956c969d")],
+		"{\n  \"valid\": false\n}",
+		"",
+	);
+	assert_cmd(
+		&["tx", "decode", "--header", "00", PEGIN_TX],
+		"",
+		"Execution failed: invalid mainchain block header: IO error\n",
+	);
+}
+
+#[test]
+fn cli_tx_create_round_trip() {
+	let decode = |tx: &str| {
+		assert_deserialize_cmd(&["tx", "decode", tx], |s| {
+			serde_json::from_slice::<serde_json::Value>(s)
+		})
+	};
+	for tx in [SIMPLICITY_SPEND_TX, PEGIN_TX, BLINDED_TX] {
+		let info = decode(tx);
+		assert_cmd(&["tx", "create", "--raw", &info.to_string()], tx, "");
+
+		// Scripts given only as assembly, and the pegin witness only as pegin data
+		let mut info = info;
+		for input in info["inputs"].as_array_mut().unwrap() {
+			input["script_sig"].as_object_mut().unwrap().remove("hex");
+			if let Some(witness) = input["witness"].as_object_mut() {
+				witness.remove("pegin_witness");
+			}
+		}
+		for output in info["outputs"].as_array_mut().unwrap() {
+			let asm = output["script_pub_key"]["asm"].take();
+			output["script_pub_key"] = serde_json::json!({ "asm": asm });
+		}
+		assert_cmd(&["tx", "create", "--raw", &info.to_string()], tx, "");
+	}
+
+	let create = |asm: &str| {
+		let info = serde_json::json!({
+			"version": 2,
+			"locktime": { "Blocks": 0 },
+			"inputs": [],
+			"outputs": [{
+				"script_pub_key": { "asm": asm },
+				"asset": { "type": "null" },
+				"value": { "type": "null" },
+			}],
+		});
+		let output = self_command().args(["tx", "create", "--raw", &info.to_string()]).output();
+		let output = output.unwrap();
+		(String::from_utf8(output.stdout).unwrap(), String::from_utf8(output.stderr).unwrap())
+	};
+	// Every kind of push, including an empty OP_PUSHDATA1
+	let asm = format!(
+		"OP_0 OP_PUSHBYTES_2 abcd OP_PUSHDATA1 OP_PUSHDATA2 {} OP_CHECKSIG",
+		"ab".repeat(80)
+	);
+	let (tx, _) = create(&asm);
+	let script = assert_deserialize_cmd(
+		&["tx", "decode", "--field", "outputs.0.script_pub_key", &tx],
+		|s| serde_json::from_slice::<serde_json::Value>(s),
+	);
+	assert_eq!(script["asm"], asm);
+	assert_eq!(script["hex"], format!("0002abcd4c004d5000{}ac", "ab".repeat(80)));
+
+	let error = |asm: &str| create(asm).1;
+	assert_eq!(
+		error("OP_DUP OP_FOO"),
+		"Execution failed: invalid script assembly: unknown opcode OP_FOO\n",
+	);
+	assert_eq!(
+		error("OP_PUSHBYTES_2 ab"),
+		"Execution failed: invalid script assembly: OP_PUSHBYTES_2 followed by 1 bytes of data\n",
+	);
+	assert_eq!(
+		error("OP_PUSHBYTES_1"),
+		"Execution failed: invalid script assembly: no data for OP_PUSHBYTES_1\n",
+	);
+	assert_eq!(
+		error("OP_PUSHBYTES_1 xy"),
+		"Execution failed: invalid script assembly: invalid data for OP_PUSHBYTES_1: \
+			Invalid character 'x' at position 0\n",
+	);
+}
+
+#[test]
+fn cli_block_create_round_trip() {
+	let info = assert_deserialize_cmd(&["block", "decode", "--full", FULL_BLOCK_1585319], |s| {
+		serde_json::from_slice::<serde_json::Value>(s)
+	});
+	assert_cmd(&["block", "create", "--raw", &info.to_string()], FULL_BLOCK_1585319, "");
+}
+
+#[test]
+fn cli_tx_verify() {
+	let fixtures = assert_deserialize_cmd(&["simplicity", "gen-fixtures", "0"], |s| {
+		serde_json::from_slice::<serde_json::Value>(s)
+	});
+	let fixture = &fixtures["fixtures"][0];
+	let finalized = fixture["finalized_pset"].as_str().unwrap();
+	let tx = fixture["transaction"].as_str().unwrap();
+	let verify = |args: &[&str]| {
+		let mut all = vec!["--network", "liquidtestnet", "tx", "verify"];
+		all.extend(args);
+		assert_deserialize_cmd(&all, |s| serde_json::from_slice::<serde_json::Value>(s))
+	};
+
+	// The Simplicity program of a finalized PSET runs against its witness UTXOs.
+	let verified = verify(&[finalized]);
+	assert_eq!(verified["valid"], true);
+	assert_eq!(verified["inputs"][0]["spend_type"], "simplicity");
+	assert_eq!(
+		verified["txid"],
+		"ee997a6c0b3f14be54994886f279d81fedd968d608eeb27e27dc19f86d02c5f1"
+	);
+
+	// A raw transaction needs its spent outputs.
+	let utxo = "5120f906d95c4bad87927b1f3d5d397cd5606344079006622878dc8e2dd22b109bd6:144c654344aa716d6f3abcc1ca90e5641e4e2a7f633bc09fe3baf64585819a49:0.001";
+	assert_eq!(verify(&["-i", utxo, tx])["valid"], true);
+	assert_cmd(
+		&["--network", "liquidtestnet", "tx", "verify", tx],
+		"",
+		"Execution failed: with a raw transaction, input-utxos must be provided\n",
+	);
+	// The signature commits to the amount spent.
+	let wrong_amount = verify(&["-i", &utxo.replace("0.001", "0.002"), tx]);
+	assert_eq!(wrong_amount["valid"], false);
+	assert!(wrong_amount["inputs"][0]["reason"].as_str().unwrap().starts_with("program failed"));
+	// Any other output is not checked.
+	let p2sh = "a914774b87be1ef871d82a01edbb89a70bf4bb59310387:144c654344aa716d6f3abcc1ca90e5641e4e2a7f633bc09fe3baf64585819a49:0.001";
+	let unchecked = verify(&["-i", p2sh, tx]);
+	assert_eq!(unchecked["valid"], false);
+	assert_eq!(unchecked["inputs"][0]["spend_type"], "other");
+	assert_eq!(unchecked["inputs"][0]["valid"], serde_json::Value::Null);
+
+	// A taproot key spend and a P2WPKH spend
+	let key_spends = concat!(
+		"02000000010200000000000000000000000000000000000000000000000000000000000000000000",
+		"000000ffffffff000000000000000000000000000000000000000000000000000000000000000001",
+		"00000000ffffffff0101499a818545f6bae39fc03b637f2a4e1e64e590cac1bc3a6f6d71aa444365",
+		"4c140100000000000186a0000000000000000001400d2eaa8366dad80ded13834e91efe4d341a152",
+		"78884ea44f6a899b5a69130abaed92b55433170fec9e3314e22f9716914d519b35f55434849fff4b",
+		"3c6fb25dd500000002483045022100e8f400c0c53ba8970dfe18155fbd96e39aac94619323bce5e0",
+		"27b946e60f758202201588f4cae9bbb9c6be0c66cd353f6a5ae29a979751269557d983152770d462",
+		"4d012102989c0b76cb563971fdc9bef31ec06c3560f3249d6ee9e5d83c57625596e05f6f000000",
+	);
+	let taproot = "5120787eca5032bfbf47c0a1a6ce1b44b7185c1f860d3e1804a8eede7a96422a963a:144c654344aa716d6f3abcc1ca90e5641e4e2a7f633bc09fe3baf64585819a49:0.001";
+	let p2wpkh = "0014a3c6b1ee4a49d9f2af3b3802974744fba924164a:144c654344aa716d6f3abcc1ca90e5641e4e2a7f633bc09fe3baf64585819a49:0.001";
+	assert_eq!(
+		verify(&["-i", taproot, "-i", p2wpkh, key_spends])["inputs"],
+		serde_json::json!([
+			{ "index": 0, "spend_type": "taproot_key", "valid": true },
+			{ "index": 1, "spend_type": "p2wpkh", "valid": true },
+		]),
+	);
+	// Signatures commit to the genesis hash.
+	assert_cmd(
+		&["--network", "liquid", "tx", "verify", "--raw", "-i", taproot, "-i", p2wpkh, key_spends],
+		"false",
+		"",
+	);
+}
+
+#[test]
+fn cli_tx_decode_timelocks() {
+	// version 2, one input with nSequence 10 (relative locktime of 10 blocks), locktime 1700000000
+	let tx = "020000000001010000000000000000000000000000000000000000000000000000000000000000000000000a0000000000f15365";
+	assert_cmd(
+		&["tx", "decode", "--field", "inputs.0.sequence_info", tx],
+		r#"{
+  "relative_locktime_enabled": true,
+  "relative_locktime_blocks": 10,
+  "enables_absolute_locktime": true
+}"#,
+		"",
+	);
+	assert_cmd(
+		&["tx", "decode", "--field", "locktime_info", tx],
+		r#"{
+  "type": "timestamp",
+  "value": 1700000000,
+  "enforced": true
+}"#,
+		"",
+	);
+	// same transaction with nSequence 0x00400003 (relative locktime of 3*512 seconds)
+	let tx = tx.replace("0a000000", "03004000");
+	assert_cmd(
+		&["tx", "decode", "--field", "inputs.0.sequence_info", &tx],
+		r#"{
+  "relative_locktime_enabled": true,
+  "relative_locktime_seconds": 1536,
+  "enables_absolute_locktime": true
+}"#,
+		"",
+	);
+	// nSequence 0x003f0090: bits 16 to 21 are not part of the lock and are ignored
+	let tx = tx.replace("03004000", "90003f00");
+	assert_cmd(
+		&["tx", "decode", "--field", "inputs.0.sequence_info", &tx],
+		r#"{
+  "relative_locktime_enabled": true,
+  "relative_locktime_blocks": 144,
+  "enables_absolute_locktime": true
+}"#,
+		"",
+	);
+	// nSequence 0x0040ffff: the longest time lock, 65535*512 seconds
+	let tx = tx.replace("90003f00", "ffff4000");
+	assert_cmd(
+		&["tx", "decode", "--field", "inputs.0.sequence_info", &tx],
+		r#"{
+  "relative_locktime_enabled": true,
+  "relative_locktime_seconds": 33553920,
+  "enables_absolute_locktime": true
+}"#,
+		"",
+	);
+	// nSequence 0x8000000a: the disable flag turns off the 10 block lock
+	let tx = tx.replace("ffff4000", "0a000080");
+	assert_cmd(
+		&["tx", "decode", "--field", "inputs.0.sequence_info", &tx],
+		r#"{
+  "relative_locktime_enabled": false,
+  "enables_absolute_locktime": true
+}"#,
+		"",
+	);
+}
+
+#[test]
+fn cli_arg_expansion() {
+	use std::io::Write as _;
+	use std::process::Stdio;
+
+	let expected = assert_deserialize_cmd(&["tx", "decode", SIMPLICITY_SPEND_TX], |s| {
+		serde_json::from_slice::<serde_json::Value>(s)
+	});
+
+	let path = std::env::temp_dir().join(format!("hal-simplicity-cli-{}.hex", std::process::id()));
+	std::fs::write(&path, format!("{}\n", SIMPLICITY_SPEND_TX)).unwrap();
+	let arg = format!("@{}", path.display());
+	let from_file = assert_deserialize_cmd(&["tx", "decode", &arg], |s| {
+		serde_json::from_slice::<serde_json::Value>(s)
+	});
+	std::fs::remove_file(&path).unwrap();
+	assert_eq!(from_file, expected);
+	assert_cmd(
+		&["tx", "decode", &arg],
+		"",
+		format!(
+			"Execution failed: cannot read 'raw-tx' from {}: No such file or directory (os error 2)\n",
+			path.display()
+		),
+	);
+
+	let mut child = self_command()
+		.args(["tx", "decode", "-"])
+		.stdin(Stdio::piped())
+		.stdout(Stdio::piped())
+		.spawn()
+		.unwrap();
+	child.stdin.take().unwrap().write_all(SIMPLICITY_SPEND_TX.as_bytes()).unwrap();
+	let output = child.wait_with_output().unwrap();
+	let from_stdin: serde_json::Value = serde_json::from_slice(&output.stdout).unwrap();
+	assert_eq!(from_stdin, expected);
+}
+
+#[test]
+fn cli_raw() {
+	let info = assert_deserialize_cmd(&["tx", "decode", SIMPLICITY_SPEND_TX], |s| {
+		serde_json::from_slice::<serde_json::Value>(s)
+	});
+	let txid = info["txid"].as_str().unwrap();
+	assert_cmd(&["tx", "decode", "--raw", SIMPLICITY_SPEND_TX], txid, "");
+
+	let pubkey = "026a04ab98d9e4774ad806e302dddeb63bea16b5cb5f223ee77478e861bb583eb3";
+	assert_cmd(
+		&["address", "create", "--raw", "--pubkey", pubkey],
+		"ert1qzyck8uy0xkrcj2emdhmagr6e3wqrwvuwfjrjez",
+		"",
+	);
+	// Commands without a main result ignore the flag.
+	let header = assert_deserialize_cmd(&["block", "decode", "--raw", BLOCK_HEADER_1585319], |s| {
+		serde_json::from_slice::<serde_json::Value>(s)
+	});
+	assert!(header.is_object());
+}
+
+#[test]
+fn cli_network() {
+	let pubkey = "026a04ab98d9e4774ad806e302dddeb63bea16b5cb5f223ee77478e861bb583eb3";
+	let create = |args: &[&str]| {
+		let args = [&["address", "create", "--pubkey", pubkey], args].concat();
+		assert_deserialize_cmd(&args, |s| serde_json::from_slice::<serde_json::Value>(s))
+	};
+	assert_eq!(create(&["--network", "liquid"]), create(&["--liquid"]));
+	assert_eq!(create(&["--network", "elementsregtest"]), create(&[]));
+	assert!(create(&["--network", "liquidtestnet"])["p2wpkh"]
+		.as_str()
+		.unwrap()
+		.starts_with("tex1"));
+
+	// The genesis hash of a regtest chain must be given explicitly.
+	assert_cmd(
+		&["simplicity", "sighash", SIMPLICITY_SPEND_TX, "0", "00"],
+		"",
+		"Execution failed: the genesis hash of an elementsregtest chain is not known; use --genesis-hash or --network\n",
+	);
+}
+
+#[test]
+fn cli_secret_value() {
+	let cmr = "abababababababababababababababababababababababababababababababab";
+	let control_block = "be50929b74c1a04954b78b4b6035e97a5e078a5a0f28ec96d547bfee9ace803ac0";
+	let utxo = format!(
+		"5120{}:6f0279e9ed041c3d710a9f57d0c02928416460c4b722ae3457a11eec381c526d:0.001",
+		cmr
+	);
+	let secret_key = "0000000000000000000000000000000000000000000000000000000000000001";
+	let sighash = |secret_key: &str, var: &str| {
+		let output = self_command()
+			.args(["simplicity", "sighash", SIMPLICITY_SPEND_TX, "0", cmr, control_block])
+			.args(["--network", "liquid", "-i", &utxo, "-x", secret_key])
+			.env("HAL_SIMPLICITY_TEST_KEY", var)
+			.output()
+			.unwrap();
+		assert!(output.status.success(), "{}", String::from_utf8_lossy(&output.stderr));
+		serde_json::from_slice::<serde_json::Value>(&output.stdout).unwrap()
+	};
+	// Signatures use fresh randomness, so compare only the sighash.
+	let direct = sighash(secret_key, "");
+	let from_env = sighash("env:HAL_SIMPLICITY_TEST_KEY", secret_key);
+	assert_eq!(from_env["sighash"], direct["sighash"]);
+	assert!(from_env["signature"].is_string());
+
+	assert_cmd(
+		&["simplicity", "sighash", SIMPLICITY_SPEND_TX, "0", cmr, "--liquid", "-x", "env:HAL_SIMPLICITY_UNSET"],
+		"",
+		"Execution failed: cannot read 'secret-key' from $HAL_SIMPLICITY_UNSET: environment variable not found\n",
+	);
+}
+
+#[test]
+fn cli_renamed() {
+	let inputs = format!(r#"[{{"txid":"{}","vout":0}}]"#, "11".repeat(32));
+	let output = self_command()
+		.args(["simplicity", "pset", "create", "--raw", &inputs, "[]"])
+		.output()
+		.unwrap();
+	let pset = String::from_utf8(output.stdout).unwrap();
+	let utxo = format!("5120{}:{}:0.001", "ab".repeat(32), "6f".repeat(32));
+	let attach = |name: &str| {
+		self_command().args(["simplicity", "pset", name, &pset, "0", "-i", &utxo]).output().unwrap()
+	};
+
+	// The old name still works, with a warning.
+	let new = attach("attach-utxo");
+	let old = attach("update-input");
+	assert!(new.status.success() && old.status.success());
+	assert_eq!(old.stdout, new.stdout);
+	assert_eq!(new.stderr, b"");
+	assert_eq!(
+		String::from_utf8(old.stderr).unwrap(),
+		"warning: `simplicity pset update-input` is deprecated; use `simplicity pset attach-utxo`\n",
+	);
+
+	// The old name given as a value, rather than as the subcommand, is not a use of it.
+	let output = self_command()
+		.args(["simplicity", "pset", "attach-utxo", &pset, "update-input", "-i", &utxo])
+		.output()
+		.unwrap();
+	assert!(!output.status.success());
+	let stderr = String::from_utf8(output.stderr).unwrap();
+	assert!(stderr.starts_with("Execution failed: "), "{}", stderr);
+	assert!(!stderr.contains("deprecated"), "{}", stderr);
+}
+
+#[test]
+fn cli_completions() {
+	for shell in ["bash", "zsh", "fish"] {
+		let output = self_command().args(["completions", shell]).output().unwrap();
+		assert!(output.status.success());
+		let script = String::from_utf8(output.stdout).unwrap();
+		assert!(script.contains("attach-utxo"), "{} completions lack nested subcommands", shell);
+	}
+	let output = self_command().args(["completions", "tcsh"]).output().unwrap();
+	assert!(!output.status.success());
+}
+
+#[test]
+#[cfg(feature = "repl")]
+fn cli_repl() {
+	use std::io::Write as _;
+	use std::process::Stdio;
+
+	let mut child = self_command()
+		.arg("repl")
+		.stdin(Stdio::piped())
+		.stdout(Stdio::piped())
+		.stderr(Stdio::piped())
+		.spawn()
+		.unwrap();
+	let inputs = format!(r#"'[{{"txid":"{}","vout":0}}]'"#, "11".repeat(32));
+	let commands = [
+		format!("simplicity pset create {} '[]'", inputs),
+		"simplicity pset extract $pset".to_owned(),
+		"tx decode $unknown".to_owned(),
+		"vars".to_owned(),
+	];
+	child.stdin.take().unwrap().write_all(commands.join("\n").as_bytes()).unwrap();
+	let output = child.wait_with_output().unwrap();
+	assert!(output.status.success());
+
+	// The PSET is remembered, then the transaction extracted from it.
+	let tx = "02000000000111111111111111111111111111111111111111111111111111111111111111110000000000ffffffff0000000000";
+	let stdout = String::from_utf8(output.stdout).unwrap();
+	assert!(stdout.contains(&format!("tx = {}\n", tx)));
+	assert!(stdout.contains("pset = cHNldP8"));
+	assert_eq!(String::from_utf8(output.stderr).unwrap(), "unknown variable: $unknown\n");
+}
+
+#[test]
+#[cfg(feature = "daemon")]
+fn cli_batch() {
+	use std::io::Write as _;
+	use std::process::Stdio;
+
+	let address = "127.0.0.1:28612";
+	let mut daemon = Command::new(env!("CARGO_BIN_EXE_hal-simplicity-daemon"))
+		.args(["--address", address, "--no-persist"])
+		.stderr(Stdio::null())
+		.spawn()
+		.unwrap();
+	while std::net::TcpStream::connect(address).is_err() {
+		std::thread::sleep(std::time::Duration::from_millis(50));
+	}
+
+	let pubkey = "026a04ab98d9e4774ad806e302dddeb63bea16b5cb5f223ee77478e861bb583eb3";
+	let calls = [
+		"# Results of earlier calls may be used by later ones.".to_owned(),
+		format!(r#"{{"method": "address_create", "params": {{"pubkey": "{}"}}}}"#, pubkey),
+		"".to_owned(),
+		r#"address_inspect {"address": "$2.p2wpkh"}"#.to_owned(),
+		r#"address_inspect {"address": "$3.p2wpkh"}"#.to_owned(),
+		"getinfo".to_owned(),
+	];
+	let batch = |calls: &[String], args: &[&str]| {
+		let mut child = self_command()
+			.args(["batch", "--daemon-url", &format!("http://{}", address), "-"])
+			.args(args)
+			.stdin(Stdio::piped())
+			.stdout(Stdio::piped())
+			.stderr(Stdio::piped())
+			.spawn()
+			.unwrap();
+		child.stdin.take().unwrap().write_all(calls.join("\n").as_bytes()).unwrap();
+		let output = child.wait_with_output().unwrap();
+		let results = String::from_utf8(output.stdout)
+			.unwrap()
+			.lines()
+			.map(|line| serde_json::from_str::<serde_json::Value>(line).unwrap())
+			.collect::<Vec<_>>();
+		(output.status.success(), results, String::from_utf8(output.stderr).unwrap())
+	};
+
+	let (success, results, _) = batch(&calls, &[]);
+	let (_, all_results, _) = batch(&calls, &["--keep-going"]);
+	// No call is made if any method is not offered.
+	let unknown = ["getinfo".to_owned(), "tx_frobnicate {}".to_owned()];
+	let (unknown_success, unknown_results, unknown_stderr) = batch(&unknown, &["--keep-going"]);
+	daemon.kill().unwrap();
+	daemon.wait().unwrap();
+	assert!(!success);
+	assert_eq!(results.len(), 3);
+	assert_eq!(results[0]["line"], 2);
+	assert_eq!(results[1]["line"], 4);
+	assert_eq!(results[1]["result"]["type"], "p2wpkh");
+	assert_eq!(results[2]["line"], 5);
+	assert_eq!(results[2]["error"]["message"], "no result for $3");
+	assert_eq!(all_results.len(), 4);
+	assert_eq!(all_results[3]["result"]["version"], env!("CARGO_PKG_VERSION"));
+	assert!(!unknown_success);
+	assert!(unknown_results.is_empty());
+	assert_eq!(
+		unknown_stderr,
+		format!(
+			"Execution failed: daemon version {} does not offer tx_frobnicate\n",
+			env!("CARGO_PKG_VERSION")
+		),
+	);
+}
+
+#[test]
+#[cfg(feature = "daemon")]
+fn cli_replay() {
+	use std::io::Write as _;
+	use std::process::Stdio;
+
+	let address = "127.0.0.1:28613";
+	let path =
+		std::env::temp_dir().join(format!("hal-simplicity-cli-{}.calls", std::process::id()));
+	let _ = std::fs::remove_file(&path);
+	let mut daemon = Command::new(env!("CARGO_BIN_EXE_hal-simplicity-daemon"))
+		.args(["--address", address, "--no-persist", "--record", path.to_str().unwrap()])
+		.stderr(Stdio::null())
+		.spawn()
+		.unwrap();
+	while std::net::TcpStream::connect(address).is_err() {
+		std::thread::sleep(std::time::Duration::from_millis(50));
+	}
+	let url = format!("http://{}", address);
+
+	let pubkey = "026a04ab98d9e4774ad806e302dddeb63bea16b5cb5f223ee77478e861bb583eb3";
+	let calls = [
+		format!(r#"address_create {{"pubkey": "{}"}}"#, pubkey),
+		r#"address_inspect {"address": "not an address"}"#.to_owned(),
+		"getinfo".to_owned(),
+		"keypair_generate".to_owned(),
+	];
+	let mut child = self_command()
+		.args(["batch", "--keep-going", "--daemon-url", &url, "-"])
+		.stdin(Stdio::piped())
+		.stdout(Stdio::null())
+		.spawn()
+		.unwrap();
+	child.stdin.take().unwrap().write_all(calls.join("\n").as_bytes()).unwrap();
+	child.wait().unwrap();
+
+	let recorded = std::fs::read_to_string(&path).unwrap();
+
+	// Replay a copy, as the daemon records the replayed calls too.
+	let copy = path.with_extension("replayed");
+	let replay = |recording: &str, args: &[&str]| {
+		std::fs::write(&copy, recording).unwrap();
+		let output = self_command()
+			.args(["replay", "--daemon-url", &url, copy.to_str().unwrap()])
+			.args(args)
+			.output()
+			.unwrap();
+		let lines = String::from_utf8(output.stdout)
+			.unwrap()
+			.lines()
+			.map(|line| serde_json::from_str::<serde_json::Value>(line).unwrap())
+			.collect::<Vec<_>>();
+		(output.status.success(), lines)
+	};
+	let (success, lines) = replay(&recorded, &[]);
+	// Change the recorded outcome of the first call, as an older daemon might have.
+	let changed = recorded.replacen(r#""p2wpkh":"ex1"#, r#""p2wpkh":"lq1"#, 1);
+	let (changed_success, changed_lines) = replay(&changed, &[]);
+	let (skip_success, skip_lines) = replay(&changed, &["--skip", "address_create"]);
+	daemon.kill().unwrap();
+	daemon.wait().unwrap();
+	let _ = std::fs::remove_file(&path);
+	let _ = std::fs::remove_file(&copy);
+
+	// The batch's getinfo call is recorded too, and the secret key is not.
+	assert_eq!(recorded.lines().count(), 5);
+	assert!(recorded.lines().last().unwrap().contains(r#""secret":"<redacted>""#));
+	assert!(success);
+	assert_eq!(lines, [serde_json::json!({ "replayed": 2, "differed": 0, "skipped": 3 })]);
+	assert!(!changed_success);
+	assert_eq!(changed_lines.len(), 2);
+	assert_eq!(changed_lines[0]["line"], 2);
+	assert_eq!(changed_lines[0]["method"], "address_create");
+	assert_eq!(changed_lines[0]["differences"][0]["path"], "/result/p2wpkh");
+	assert_eq!(changed_lines[1]["differed"], 1);
+	assert!(skip_success);
+	assert_eq!(skip_lines[0]["skipped"], 4);
+}
+
+#[test]
+#[cfg(feature = "daemon")]
+fn cli_fetch() {
+	use std::io::{BufRead as _, Write as _};
+	use std::process::Stdio;
+
+	// A stand-in for an Esplora API, with one block and one transaction.
+	let esplora = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+	let esplora_url = format!("http://{}", esplora.local_addr().unwrap());
+	let block_hash = "5f37039a5ae15d9239bb2e137643a51d3a525d6e850b5e8974b4323c9e13a39b";
+	std::thread::spawn(move || {
+		for stream in esplora.incoming() {
+			let mut stream = stream.unwrap();
+			let mut request = String::new();
+			let mut reader = std::io::BufReader::new(&stream);
+			reader.read_line(&mut request).unwrap();
+			while reader.read_line(&mut String::new()).unwrap() > 2 {}
+			let path = request.split(' ').nth(1).unwrap().to_owned();
+			let (status, body) = match path.as_str() {
+				"/block-height/1585319" => ("200 OK", block_hash.as_bytes().to_vec()),
+				p if p == format!("/block/{}/raw", block_hash) => {
+					("200 OK", hex::decode(FULL_BLOCK_1585319).unwrap())
+				}
+				p if p.starts_with("/tx/") => ("200 OK", SIMPLICITY_SPEND_TX.as_bytes().to_vec()),
+				_ => ("404 Not Found", b"Block not found".to_vec()),
+			};
+			let head = format!(
+				"HTTP/1.1 {}\r\nContent-Length: {}\r\nConnection: close\r\n\r\n",
+				status,
+				body.len()
+			);
+			let _ = stream.write_all(head.as_bytes()).and_then(|_| stream.write_all(&body));
+		}
+	});
+
+	let address = "127.0.0.1:28614";
+	let mut daemon = Command::new(env!("CARGO_BIN_EXE_hal-simplicity-daemon"))
+		.args(["--address", address, "--no-persist", "--esplora-url", &esplora_url])
+		.stderr(Stdio::null())
+		.spawn()
+		.unwrap();
+	while std::net::TcpStream::connect(address).is_err() {
+		std::thread::sleep(std::time::Duration::from_millis(50));
+	}
+	let url = format!("http://{}", address);
+	let fetch = |args: &[&str]| {
+		let output = self_command().args(args).args(["--daemon-url", &url]).output().unwrap();
+		(String::from_utf8(output.stdout).unwrap(), String::from_utf8(output.stderr).unwrap())
+	};
+
+	let txid = "9523d75b48b3411a3f4ebd31b6005898deebbe748875aa6ee084b94aa8422ba6";
+	let raw_tx = fetch(&["tx", "fetch", txid]);
+	let decoded_tx = fetch(&["tx", "fetch", "--decode", "--field", "version", txid]);
+	let raw_block = fetch(&["block", "fetch", "1585319"]);
+	let block_txids = fetch(&["block", "fetch", "--decode", "--txids", block_hash]);
+	let missing = fetch(&["block", "fetch", "--json-errors", "1"]);
+	let not_decoded = fetch(&["block", "fetch", "--txids", "1585319"]);
+	daemon.kill().unwrap();
+	daemon.wait().unwrap();
+
+	assert_eq!(raw_tx, (SIMPLICITY_SPEND_TX.to_owned(), String::new()));
+	assert_eq!(decoded_tx, ("2".to_owned(), String::new()));
+	assert_eq!(raw_block, (FULL_BLOCK_1585319.to_owned(), String::new()));
+	let decoded: serde_json::Value = serde_json::from_str(&block_txids.0).unwrap();
+	assert_eq!(decoded["header"]["height"], 1585319);
+	assert_eq!(decoded["txids"][0], txid);
+	let error: serde_json::Value = serde_json::from_str(&missing.1).unwrap();
+	assert_eq!(error["error"]["message"], "backend request failed: HTTP 404: Block not found");
+	assert!(not_decoded.1.contains("--decode"), "{}", not_decoded.1);
+}
+
+#[test]
+#[cfg(feature = "daemon")]
+fn cli_schema() {
+	let json = |args: &[&str]| {
+		assert_deserialize_cmd(args, |s| serde_json::from_slice::<serde_json::Value>(s))
+	};
+	let types = json(&["schema", "--list"]);
+	let types = types.as_array().unwrap();
+	assert!(types.contains(&"TransactionInfo".into()));
+	assert!(types.contains(&"PsetRunResponse".into()));
+
+	let all = json(&["schema"]);
+	assert_eq!(all.as_object().unwrap().len(), types.len());
+
+	let keypair = json(&["schema", "KeypairInfo"]);
+	assert_eq!(keypair["title"], "KeypairInfo");
+	assert_eq!(keypair["required"], serde_json::json!(["parity", "secret", "x_only"]));
+	assert_eq!(keypair["properties"]["secret"]["type"], "string");
+	assert_eq!(all["KeypairInfo"], keypair);
+
+	let output = self_command().args(["schema", "Nope"]).output().unwrap();
+	let stderr = String::from_utf8(output.stderr).unwrap();
+	assert!(
+		stderr.starts_with("Execution failed: unknown type Nope, expected one of: AddressInfo,")
+	);
+}
+#[test]
+#[cfg(feature = "daemon")]
+fn cli_offline() {
+	assert_cmd(
+		&["--offline", "daemon", "status"],
+		"",
+		"Execution failed: refusing to contact the daemon with --offline\n",
+	);
+	assert_cmd(
+		&["batch", "--offline", "-"],
+		"",
+		"Execution failed: refusing to contact the daemon with --offline\n",
+	);
+	// Local computations are unaffected.
+	let online = assert_deserialize_cmd(&["tx", "decode", SIMPLICITY_SPEND_TX], |s| {
+		serde_json::from_slice::<serde_json::Value>(s)
+	});
+	let offline =
+		assert_deserialize_cmd(&["tx", "decode", "--offline", SIMPLICITY_SPEND_TX], |s| {
+			serde_json::from_slice::<serde_json::Value>(s)
+		});
+	assert_eq!(online, offline);
+}
+
+#[test]
+#[cfg(feature = "registry")]
+fn cli_asset_registry() {
+	use std::io::{BufRead as _, BufReader, Write as _};
+	use std::net::TcpListener;
+
+	let lbtc = "6f0279e9ed041c3d710a9f57d0c02928416460c4b722ae3457a11eec381c526d";
+	let unregistered = "2222222222222222222222222222222222222222222222222222222222222222";
+	let tx = concat!(
+		"0200000000011111111111111111111111111111111111111111111111111111111111111111000000",
+		"0000ffffffff02016d521c38ec1ea15734ae22b7c46064412829c0d0579f0a713d1c04ede979026f01",
+		"0000000000015f9000225120f08261d61ccf2d9fbf6dd99c1e7541a29c06c248f8c59b7c09948abdd9",
+		"e470810122222222222222222222222222222222222222222222222222222222222222220100000000",
+		"05f5e10000225120f08261d61ccf2d9fbf6dd99c1e7541a29c06c248f8c59b7c09948abdd9e4708100",
+		"000000",
+	);
+
+	// A registry which knows only L-BTC, and answers once for each asset.
+	let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+	let url = format!("http://{}", listener.local_addr().unwrap());
+	let server = std::thread::spawn(move || {
+		let mut paths = vec![];
+		for stream in listener.incoming().take(2) {
+			let mut stream = stream.unwrap();
+			let mut request_line = String::new();
+			BufReader::new(&stream).read_line(&mut request_line).unwrap();
+			let path = request_line.split(' ').nth(1).unwrap().to_owned();
+			let (status, body) = if path == format!("/{}", lbtc) {
+				let entry = serde_json::json!({
+					"asset_id": lbtc,
+					"ticker": "L-BTC",
+					"name": "Liquid Bitcoin",
+					"precision": 8,
+					"entity": { "domain": "blockstream.com" },
+				});
+				("200 OK", entry.to_string())
+			} else {
+				("404 Not Found", "Not Found".to_owned())
+			};
+			write!(
+				stream,
+				"HTTP/1.1 {}\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+				status,
+				body.len(),
+				body
+			)
+			.unwrap();
+			paths.push(path);
+		}
+		paths
+	});
+
+	let decoded = assert_deserialize_cmd(&["tx", "decode", "--asset-registry", &url, tx], |s| {
+		serde_json::from_slice::<serde_json::Value>(s)
+	});
+	assert_eq!(
+		decoded["outputs"][0]["asset"]["registry"],
+		serde_json::json!({
+			"ticker": "L-BTC",
+			"name": "Liquid Bitcoin",
+			"precision": 8,
+			"domain": "blockstream.com",
+		}),
+	);
+	assert_eq!(decoded["outputs"][1]["asset"]["asset"], unregistered);
+	assert!(decoded["outputs"][1]["asset"].get("registry").is_none());
+	assert_eq!(server.join().unwrap(), [format!("/{}", lbtc), format!("/{}", unregistered)]);
+
+	// Without a registry, or offline, nothing is looked up.
+	let plain = assert_deserialize_cmd(&["tx", "decode", tx], |s| {
+		serde_json::from_slice::<serde_json::Value>(s)
+	});
+	assert!(plain["outputs"][0]["asset"].get("registry").is_none());
+	let offline =
+		assert_deserialize_cmd(&["tx", "decode", "--offline", "--asset-registry", &url, tx], |s| {
+			serde_json::from_slice::<serde_json::Value>(s)
+		});
+	assert_eq!(offline, plain);
+
+	let help = self_command().args(["tx", "decode", "-h"]).output().unwrap();
+	let help = String::from_utf8(help.stdout).unwrap();
+	assert!(help.contains("--asset-registry <URL>"));
+	assert!(help.contains("HAL_SIMPLICITY_ASSET_REGISTRY"));
+}
+
+#[test]
+fn cli_format() {
+	let yaml = assert_deserialize_cmd(&["tx", "decode", "-y", SIMPLICITY_SPEND_TX], |s| {
+		serde_yaml::from_slice::<serde_json::Value>(s)
+	});
+	let format_yaml =
+		assert_deserialize_cmd(&["tx", "decode", "--format", "yaml", SIMPLICITY_SPEND_TX], |s| {
+			serde_yaml::from_slice::<serde_json::Value>(s)
+		});
+	assert_eq!(yaml, format_yaml);
+
+	// Output to a pipe is never colored.
+	let output = self_command()
+		.args(["tx", "decode", "--format", "pretty", SIMPLICITY_SPEND_TX])
+		.output()
+		.unwrap();
+	let pretty = String::from_utf8(output.stdout).unwrap();
+	assert!(pretty.starts_with(
+		"txid     ade52fb79596b2a3aa8b204f870f099f729da9a9759001df15e568ae020b16ae\n"
+	));
+	assert!(pretty.contains("\ninputs\n  [0]\n    prevout       1111"));
+	assert!(!pretty.contains('\x1b'));
+	assert_cmd(
+		&["tx", "decode", "--format", "pretty", "--field", "vsize", SIMPLICITY_SPEND_TX],
+		"71\n",
+		"",
+	);
+
+	// LWK takes PSETs and addresses as objects of their own.
+	let pubkey = "026a04ab98d9e4774ad806e302dddeb63bea16b5cb5f223ee77478e861bb583eb3";
+	assert_cmd(
+		&["address", "create", "--format", "lwk", "--pubkey", pubkey],
+		"{\n  \"address\": \"ert1qzyck8uy0xkrcj2emdhmagr6e3wqrwvuwfjrjez\"\n}",
+		"",
+	);
+	let inputs = format!(r#"[{{"txid":"{}","vout":0}}]"#, "11".repeat(32));
+	let pset = assert_deserialize_cmd(
+		&["simplicity", "pset", "create", "--format", "lwk", &inputs, "[]"],
+		|s| serde_json::from_slice::<serde_json::Value>(s),
+	);
+	assert!(pset["pset"].as_str().unwrap().starts_with("cHNldP8"));
+	assert_eq!(pset.as_object().unwrap().len(), 1);
+	assert_cmd(
+		&["tx", "decode", "--format", "lwk", SIMPLICITY_SPEND_TX],
+		"",
+		"Execution failed: --format lwk is only supported for PSETs and addresses\n",
+	);
+}
+
+#[test]
+fn cli_json_errors() {
+	// Failures exit with status 1 and are reported on stderr.
+	let output = self_command().args(["simplicity", "pset", "extract", "zz"]).output().unwrap();
+	assert_eq!(output.status.code(), Some(1));
+	assert!(output.stdout.is_empty());
+	assert_eq!(
+		String::from_utf8(output.stderr).unwrap(),
+		"Execution failed: invalid PSET: Base64 error\n"
+	);
+
+	let output = self_command()
+		.args(["--json-errors", "simplicity", "pset", "extract", "zz"])
+		.output()
+		.unwrap();
+	assert_eq!(output.status.code(), Some(1));
+	let error: serde_json::Value = serde_json::from_slice(&output.stderr).unwrap();
+	assert_eq!(
+		error,
+		serde_json::json!({
+			"error": {
+				"code": 1400,
+				"message": "invalid PSET: Base64 error",
+				"data": { "subsystem": "pset", "failure": "invalid" },
+			}
+		})
+	);
+
+	// Invalid JSON input is an action error too.
+	let output = self_command().args(["tx", "create", "--json-errors", "{"]).output().unwrap();
+	assert_eq!(output.status.code(), Some(1));
+	let error: serde_json::Value = serde_json::from_slice(&output.stderr).unwrap();
+	assert_eq!(error["error"]["code"], 1200);
+	assert_eq!(error["error"]["data"]["subsystem"], "tx");
+
+	// Failures other than those of an action have a generic code.
+	let output = self_command().args(["tx", "decode", "--json-errors"]).output().unwrap();
+	assert_eq!(output.status.code(), Some(1));
+	let error: serde_json::Value = serde_json::from_slice(&output.stderr).unwrap();
+	assert_eq!(error["error"]["code"], 1);
+	assert!(error["error"].get("data").is_none());
 }
 
 // Stick some big constants down here
@@ -1510,7 +4401,9 @@ static HEADER_DECODE_1585319: &str = r#"{
   "dynafed_current": {
     "params_type": "compact",
     "signblockscript": "0020e51211e91d9cf4aec3bdc370a0303acde5d24baedb12235fdd2786885069d91c",
+    "signblockscript_asm": "OP_0 OP_PUSHBYTES_32 e51211e91d9cf4aec3bdc370a0303acde5d24baedb12235fdd2786885069d91c",
     "signblock_witness_limit": 1416,
+    "root": "fbcf7fa8fc7c056f0f0b135091031a2a0b9b653436e92c9c61f187b71a5d25e1",
     "elided_root": "ff0f60e85234ad045ac9a8f174b41ac9e3461ad2f6b05d0fccbd964eed5d757e"
   },
   "dynafed_proposed": {
@@ -1532,7 +4425,8 @@ static HEADER_DECODE_1585319: &str = r#"{
     "30440220212d552bc35aac010dd546467cf0d15fe3f2b3349ba6e554d10cadd2b37d975802201ede6c1f518056dd843bf7338f6b3d31f4811d9590db3a4c2679311ea6f9bf1a01",
     "3045022100fb4aee60b6157f7942e720e893e39676c6bd97e5bca37e1248ce6133a6b2b65302200de5611208eb3c12f713b2eee904f7d70a19f74491bbe4fcf11210d7c1c46b9c01",
     "5b21026a2a106ec32c8a1e8052e5d02a7b0a150423dbd9b116fc48d46630ff6e6a05b92102791646a8b49c2740352b4495c118d876347bf47d0551c01c4332fdc2df526f1a2102888bda53a424466b0451627df22090143bbf7c060e9eacb1e38426f6b07f2ae12102aee8967150dee220f613de3b239320355a498808084a93eaf39a34dcd62024852102d46e9259d0a0bb2bcbc461a3e68f34adca27b8d08fbe985853992b4b104e27412102e9944e35e5750ab621e098145b8e6cf373c273b7c04747d1aa020be0af40ccd62102f9a9d4b10a6d6c56d8c955c547330c589bb45e774551d46d415e51cd9ad5116321033b421566c124dfde4db9defe4084b7aa4e7f36744758d92806b8f72c2e943309210353dcc6b4cf6ad28aceb7f7b2db92a4bf07ac42d357adf756f3eca790664314b621037f55980af0455e4fb55aad9b85a55068bb6dc4740ea87276dc693f4598db45fa210384001daa88dabd23db878dbb1ce5b4c2a5fa72c3113e3514bf602325d0c37b8e21039056d089f2fe72dbc0a14780b4635b0dc8a1b40b7a59106325dd1bc45cc70493210397ab8ea7b0bf85bc7fc56bb27bf85e75502e94e76a6781c409f3f2ec3d1122192103b00e3b5b77884bf3cae204c4b4eac003601da75f96982ffcb3dcb29c5ee419b92103c1f3c0874cfe34b8131af34699589aacec4093399739ae352e8a46f80a6f68375fae"
-  ]
+  ],
+  "dynafed_epoch_transition": false
 }"#;
 
 static FULL_BLOCK_1585319: &str = concat!(
@@ -1813,3 +4707,150 @@ static FULL_BLOCK_1585319: &str = concat!(
 	"5d988f5792c74202e8c4dad8d8b46423b3cbd0943cbafeaeeaf4cdc7b1ceaad213d56d49d5e14580",
 	"98a340b9ba0000",
 );
+
+static SIMPLICITY_SPEND_TX: &str = concat!(
+	"02000000010111111111111111111111111111111111111111111111111111111111111111110000",
+	"000000ffffffff00000000000000040005bf0e3048202069cf8e9284799b072ddb78eac6d4ffa1c9",
+	"20baef099f17e59a63a8afef702a1921be50929b74c1a04954b78b4b6035e97a5e078a5a0f28ec96",
+	"d547bfee9ace803ac000",
+);
+
+/// A pegin transaction from the Liquid integration tests.
+static PEGIN_TX: &str = concat!(
+	"0200000001013fe9fcf1d5eae66a152efa45ad32baa5eed3cf11ab5e04edde650313b58ed8c90000",
+	"004000ffffffff0201f80bb0038f482243202f0b2dcf88d9b4e7f930a48a3fcdc003af76b1f9d60e",
+	"63010000000005f5c88c001976a914d7cc0ea6d5e53af78c7802101519cc100692668e88ac01f80b",
+	"b0038f482243202f0b2dcf88d9b4e7f930a48a3fcdc003af76b1f9d60e6301000000000000187400",
+	"0000000000000002473044022048cf10f12a31cb0ec36ba3a6f79fad7e0dea3f1aa790a5aed02f8e",
+	"8455c8cb1502201a2624089ce70c893dfd07a156ba91223edd5680cbd93d3336285ceefcb3dc1401",
+	"210205914becd15ac5d2f72ad0aa42e84349c825a544d8c16e78ecc21534ef561fd4060800e1f505",
+	"0000000020f80bb0038f482243202f0b2dcf88d9b4e7f930a48a3fcdc003af76b1f9d60e63200622",
+	"6e46111a0b59caaf126043eb5bbf28c34f3a5e332a1fc7b2b73cf188910f1600141ab7f5995cf0df",
+	"cb90cbb02b63397e5326eae6febe020000000113244fa59fcb407124038ff9121ed546f6dc217571",
+	"cb366a50d3193f2c80298c0000000049483045022100d1e212715d2dcbc1c66d76f43d9f326f54ff",
+	"339b565c68f046ed74040730433b02201d9ccbad57566100a06b4be47a4c777cbd7c99e0a08e17f7",
+	"bf10458117426cd801feffffff0200e1f5050000000017a914774b87be1ef871d82a01edbb89a70b",
+	"f4bb59310387a88c8b44000000001976a914b14b7395623921dbbce438f4fc1fc8f1a495affa88ac",
+	"f4010000b700000020a060086af92ac34dbbc8bd89bbbe03ef7e0016930f7fdc806ff15d163b5fda",
+	"5e32105949c748222d3e1c5b6e0a4d47f8de45b25d63f145c4056682a7b15cc3da56a2815bffff7f",
+	"20000000000300000003946c969d81a3b0ca473ab54c11fa665234d6ce1ad09e87a1dbc56eb6de40",
+	"02b83fe9fcf1d5eae66a152efa45ad32baa5eed3cf11ab5e04edde650313b58ed8c9fccdc0d07eaf",
+	"48f928fecfc07707b95769704d25f855529711ed6450cc9b3c95010b00000000",
+);
+
+/// A transaction with one output blinded to the blinding key 1111...11.
+static BLINDED_TX: &str = concat!(
+	"020000000101499a818545f6bae39fc03b637f2a4e1e64e590cac1bc3a6f6d71aa4443654c140000",
+	"000000ffffffff020a1207de9043c5dcdd7cf8045c6925de088831866bbf14ee4823c7b6469a987b",
+	"e308b585286155e1e8263f4d543ac171a74bc9b793b12f3a884e744695f6bce1a8a402b434fa30af",
+	"f1e889585718178c5f4c9ffb5e39135bd780e95a585efd96a43157225120f906d95c4bad87927b1f",
+	"3d5d397cd5606344079006622878dc8e2dd22b109bd601499a818545f6bae39fc03b637f2a4e1e64",
+	"e590cac1bc3a6f6d71aa4443654c140100000000000003e80000000000000000000043010001e7ec",
+	"31cee872e3a3cb55de648f56d7a6eb81b10ee0c804723fc9052c31076a33b58b5700126a7586745f",
+	"51584369a9dd61ff0a571efa0929500fcc919dcfa753fd4e1060330000000000000001b6be7601f7",
+	"80cb38a6ad29b38f5e98d431c87a7dd239072cab2d2f3a4d52a474334c1664f561af690f61f2813c",
+	"d4ec9fdf6e1c08389aea69d8a7d4ff845bf762baeca1405b3155935a29364fbe0b5dad8c92f68b84",
+	"6ab03c0677ddc64e8b988e29f19d49dce310e5b539707266c67535b1276d735041dc243d642430f5",
+	"fa76868ff828bcf407b26b0fa6589a2d6e76ddb357fac41aac48d6d9ae296fc68cb07393b2cf1614",
+	"37ffff09df3a462ad288c6c158cc7619157001a0fcd4bea14064bee67a3af293c18ba43d5b5e9434",
+	"19ed6b52fbe157df29127477de5f92b102400275619ec35839fbf27d653f200c376fe1d4c7b3536d",
+	"e2afa0d6d415368f11dd4c9a2899c3fde0d727b6030ade990eab5c61e1de25a30ba83b4eb8cc6aa4",
+	"d7ff35fa656d2b2dac99f8c1eb92cc3fe79a2c6e60d79118dd388de86c6df4ff58c4a4b59f67ec62",
+	"4101f84b86c2ff1f39ab63d9278308fd100e97963e704b7ed69ae394c49b496a666abf713942b0c8",
+	"52fd9ff617ea834c420f90aabefe241942559b8f2afd0c9bc54fa5c9f5f1b8f44dfb1208165f37d6",
+	"0d53abae16277f1556f95ecaa6fcee165f9246e00847443de81b3cb39bea4070cbdbc496d61c4df8",
+	"3cf35afd224e2901d6a3578e12bdbb412223fc5f35928292825de5445c14eea322f5254ab5b4fa6a",
+	"a6fd415a0955cbbe1d56c5b535f9fd52a26088404a66af09b088c5b098009dc4088c92af67fb042b",
+	"37108ea2c945bd94680972d5d5038fb2e8a24c00aa4dd8021b28ad548a31c6a67662ea454a47a83f",
+	"7f8ce907c6c248b2986bf01241e9094f1b9ac92b5c84af18be2d59d9a261f3fafd120ddde663cccf",
+	"4b5d07801857affabd4b17f89c4afbbbd4f19cd134e1b1e5b01d9c07d07efb4a377e9c50e76cb7be",
+	"84451a55c70418eb67282f6fa00e1c30e9206a1700cd798cff5a7286ba4646cbb23cb606837d2a07",
+	"d606b5b23bbe80ad1c985407a382b5393437f0108da5cdee84d41c692497d8993d58dbbb6dce3637",
+	"080e288c282d713844ca17698f2434cb0ae67612894741b761f8f766b5aebde416951c37ee2b6ebd",
+	"7bf6c12f4f92aa2bf4d0bc4e235f62e85be7fed5263ef0e1713e865ed12460afdb2705825084cea5",
+	"5ef8c3bc6fff7c199c946b2ea2e33dd5366fa73717ef52ea119e7df1de63294419029340e3dfe2f1",
+	"c1b1514e20fdc264af9c3919f70816ddec6cbf71f42013b64c6ebbb76bf9333496df0168ceac9bf5",
+	"10a7c3564d230702cc7064e87beeaa05922cb82cc9cd6705c707c1e1468ce097b4fb02f80e8248b9",
+	"06aad04cc5bea3d272de9eb198b49de576b2edff5c050065a3fa255c16b9acfaa4921e0a7bfed0c4",
+	"2f3090d9337fbef31185e52264b23ee2e7bba9f0c91a031014d4b371b6edf8333f9403b98eb15ef3",
+	"928327f99fe81122ce5b454fbcbd587bb23f26ff66d422016df1d40a49fc16b0c9968b1d97f18852",
+	"c6c38ff3505dfc641295cf38fec8fcc1f7ee6404339d93a5425bc8da66b131eaa5e2fa8119ce52a4",
+	"00c238abceb3763cafb7be99d2992a94036f192165daafb549301d5bbb5b48aee663ba6ef0bde803",
+	"d4aefb5669296d0c289ee5f35773db13c7fc596e1dda65bdaf6e61afabea5b4403e0224256fdcfcb",
+	"2222a0cfc9adce839fc51f05ee2be5c5b2099350a1bd21bf87c7489554b8fb95e9690d088ca12a85",
+	"76346621fdcac9b78175c24e818d119fcb95589cce45acf494a16d65aa8171bccb58f3cdc33f71fc",
+	"6014772a24ebda5284f74862a72ee85a0882bacec8140d0eed7b1921356ad85096ba6d3f02c4fe3d",
+	"b0d4794cf20e63978ae995cf0feeabddde0805b0f38d8f23545a6ecb376a03a09494555e38670e7a",
+	"d352d49d81ce738b67526f7e35770bbbf89f7f99cff7f90c0d71dce5b4dfee0e662d89ce19c57d94",
+	"7c51b27bfa71d8341751ad36f41d714352e944df1df35660e8e98fabf57c94cd5730aee9101483e1",
+	"de43cb41344643d4ad0f54f607f7e7296bdab64aa34861c6d83781eb01e343b42694f738f66159fa",
+	"6c0bca05239234514c05747c4e7f8075c9a877d8023f6ae9c7fc920092c44f65791d47dcc74d2af6",
+	"e39cb313493c60569376c4cd4b390485142b33fd44293fa91d97660a9cb5b15020424c67b092a4c2",
+	"7e0bc9bed3fa75672227051076a3563b4275e109e7f1dc3c0772abf690dea03ff65824b3f5ff4393",
+	"3e99565f0031f601e5400187dc9d3d4adb25f4dd79b8c6547c7fd6047e6b7e3887fbd7a41fa19503",
+	"34fb041cccc7bbd9970a734390c93e6ac1b5853e4d900025d1041b778c3d230eae2151725a9e2b7d",
+	"c134e3d933fea1e6658af6e8a180ce6c555451e48053a461e44f0b71af445f358ae5cc544ffa1ae7",
+	"f77e4e0b542327df20c9f94fd79c23ee38729db3870329c22e3856783bb7bf847fae2ae5a016f877",
+	"9854d499181c1b9a2f73c65cc72529eb2d6ba77f1388057b50bbe6b0fa4647289671d13af6504b82",
+	"b0ece1dedd059df43ee19fa2ae908d1d9f8cf2f78b1500ef1ec3c713f5aa5560f1f0d611bfb2d2d9",
+	"83be00cd4984c2d4a26dc0482e90d1d72ca0c58e46686e6a6a9c88c105cd6fef566c16b31c059fe8",
+	"221da55ba38d2e46f2cadf9882e1e9cd90990fc85b02f60c5c2880f9bca04562124de023e1381c8f",
+	"bbe5a50a2b9d74a17bc6bd8e44289e046d6d8fe84cbcc84e82678fe0940d1f9448e1babca058a8e5",
+	"a0d30adae3d00963343048fddb1d5922e61c32d84d25a77613e330eebca553682e49669181fc7929",
+	"da055e1bb2fe84c90402254b096ea49e120b9b0998db0490cfbfbc3cd70ac9e35c461ee922ed835d",
+	"8033272e178fdfad355490fad8165fcc42e66096da57e7e4af6f690404db5ceb0458d6139f289cc0",
+	"7a784a9d1c4fc433184b8f137df8c78ee4c76cb642541792913be2e1491cecdc9bb290dde9f232d5",
+	"4d1f3bfb2abcd5d04690a4fa6c49726d88a71cd634853e33b4d43d17564c32c1302ea4ea10046cc5",
+	"4e2a27727a30e64cb1696e77496e533a0db0b6d82583748ef5487d518bb3ea66e43d53aa2b06f131",
+	"570f26ae3fd75e6ba71d7aa39d988a43e856919662549d0376fba117106b20aa8c10cd3af185ae74",
+	"4d0e472f5813fe1ec8d976fb237ffc8adf0445efd4690b446e7a05c49bcad7142f33587479f66573",
+	"9a73a4d714e19308506a31b957f68084e551b17b6c579625b92b1e390f89f50f9dca20414f8f69ab",
+	"ca4803a7df8945533fec1b2f9eaff6cdde40491ec5b0e299f4346659bf07ba46f109a41cfe4aabb0",
+	"cdcd516cde95eadb689499e7e8bd3629b5f31ad4c6af6675ae8f63faf674e7cdf2a47e8d4fec9a86",
+	"925ab741aab8c04a5c6c865febea548e046eae9a37f0bf101caac0efc795ce4536830d0a5cb249e1",
+	"4e611075c58f8ecafaea177c6ecbd65e30a7b89247cdc98c92070909ffa86c599239667d1ad1f492",
+	"a3e8bffbb5462dcee272a6a6c44ee91cc63cded06a6a2b755f6ed5dac86c2c9648215c3300468f6a",
+	"11597fb801e081a53e8251db58b3a664633fc6fc2478fe2bbfe79a9468757b8b234fca0625b62755",
+	"08dcad4ea1de7673699750a0bf0f587d3ba5aee763ad907148bcee18fded6e6f445ef407e88c2a38",
+	"28e2210928c8e58b9f7d4995cf0fa2dd577e3be576ad4843d4271d8083621900b5a06c02bfa17134",
+	"8558ec12d03e22569cea96c833ee776938f3a43693d96b673832b3d3f46d733a6bbc6ce0f1ce3a48",
+	"ba0f59ff318b261cd89af9823f97183f7aced377ee9f06ffce6c601d2c7f4a6a49c57c9cf7204c1e",
+	"940e9d095858c946822785c84b31048d83e80981ad4cdcea4b208e6b6ba59986cd2208566667ae41",
+	"5668d4e8e05e656ca6ffcb3fbaa00a89cf856d0be1f826d164a32c6d4e17db7a190fb9ffee2426f0",
+	"faf235d10c81b10421e0898a2bb432c33c4dbf594c5762565a87b3224ce81c0642897cf750aa6380",
+	"17b5723950cecf8d249e2a5f58c688129d36d2be5736c3f0871b0ab2e8acd6b040f419bdb20e74d0",
+	"bbb13fffde5d8cb38adf210083189f2481afe0692c0421e5364c05d7a45121128907f4eb8b62f6e7",
+	"7ccd76f905e25d4025335d621c42a3a845858ded1fb81a6b6b20ec49ec3f00b4177f9bbff496dd07",
+	"6ec0711025bd6778e140698ca592353d473593ec93645343134102a9254c6f668b80a0a23304f1e7",
+	"f4dd898da4aa35e4030dac9831b9d431b0941bd23f97696f4dd0e6772d27d918603dbe35d74fad37",
+	"71edf5ee3d64702641adc87a27cb24106ece92ecedcb947faffa0a8e016ddb709bd67f3f622023be",
+	"7c640047cdd5ab8458f4a3ac958f29ab7f358439d9141dc5c53bf5e16ee529ff4352789639c3c3a0",
+	"14bfff580b19dd82af3e43ea8c1974cc63c1afb2fb1195970558a38836376163d3ae5d09db8d044d",
+	"681e8201d7db09c0482827c48b9ea46dd539474657cb66f5dc412a1412b5fd692f5fbfa19f8296fc",
+	"55948f454cb2806653342e7872ef68ab754d09f8db9d3414fa9f1b2062b5f0f5aa599a90cf65b33c",
+	"83fd97ffb464394c2d40ddf921bf836b1db81e46d12b3ca62d3c3b727e5cbc73de04a04496b05421",
+	"0b01e27eef74f353c3f767db38cd959a23de89e38fb802748f6fe18d28a329f0d976c62b8e4829f3",
+	"1ad938181ec0394b8af991f89d53e23f3baeeb0257afc27dd626015ebf34c9d9ed33812535fa41f0",
+	"1bba5899d737984dc9b0e8e2aae148c4371f78873fad64a424b928305e50b26805092c04c4bfda88",
+	"db5e91a5e62de92052b5378bafe72c66dd5afc6c57933379fbf31b1d0f80536869f5736605e5d9cf",
+	"a1cca9171b404420d87409bc1281e91ccc0d0e6d72ac11173e724216149a90bb26ecc86647e60e29",
+	"c656860f79924e90c72410a6c714c8ab75bca72a3d328c57d064fa71709addaf6a03b62d879287da",
+	"1180b2aa6f566074d92c54276c5eb3efae7cb093da7763f11dcca0a428b07158a6955528915f780d",
+	"6f31e06bd3b99e393d7b834671e888d2d0bca0beee311292dbdf88d9c54866feacbbe847bf4ac069",
+	"bf6025c57544156ed51e207ae468536d60d005417705846ef7674b347484d2ba0d9b3f723ccf3c3a",
+	"13afd4bb3bff145dbeae8496df34526c6dbb5cd3b48367b2493037bcc764cf9889bbd7af33efc50d",
+	"c86de72a8f83482bec52470cf423df146c9dc3ba34d70f853ebd5d8d8ff6944a011611081ed67d14",
+	"c4b0946d9d849cbe9b0251f49dbb6885cfc768d0c30de4a83eb4fbc594af907d516f79369fc59a62",
+	"4c927bfd0a8214c2cb42d0ed5853e6ccbf65a5066dcae6ff8970a292437f83e709c51f7c345e8f69",
+	"223cdf9db880592d6f2bdcb5e0db71f1f0f7413db1e68c502c068318e0a131f47a7bd17e2aadae57",
+	"4fb19f43d93b6ae52b709326b0d74ee14a0053e8d0ea7cf0900c8a5ea8c265471757cc4a1e0851a1",
+	"5041f9c8d4a23adbba0c7640aa79c8cab28562d2d76461b4227c928d74c415e12d4a53ea1371a919",
+	"0449c77c0c9e02c4f3042b3fe27874cd6054ac36fb7902ff0d632d7017d087f302a6e5e6106ebda2",
+	"840113f85a6e808212a55dc803a83f3835beccad3df0d5ff54d4cfc9b46527c546b2e14ea16de2c8",
+	"5840bcdbede3ee7073e4d733a238ccfc1f25497baa9c4b124467e16c083e4ac25ee19cd00ffac394",
+	"49298653916f1acaa3d2c7370622fb3f6fe0139ab774523d7f6fd7f2d71527a918fcfd465b273da6",
+	"9d0467d206465deafef4aad1d116c7b5f9f6f5980be718d03b987e248abec90b3c8f8455f2267a9e",
+	"4b00432c67448bf88670631a7768a5b59df87c9d05a37ab27687450d58590dfd0789fec60362e4eb",
+	"ca778da45fb57eed9de336695ae817b04304b4cc0a0bb64ce43b181567d8ee5a0f3c73f32c87c700",
+	"00",
+);